@@ -0,0 +1,454 @@
+//! # SCIM v2 derive
+//!
+//! Derive macros for the `scim_v2` crate. Enable the `derive` feature of `scim_v2` to use them; they are
+//! re-exported from the crate root.
+//!
+//! ## `#[derive(ScimExtension)]`
+//!
+//! Implements `scim_v2::models::extension::ScimExtension` for a struct describing a custom schema extension.
+//! The extension's schema URI is given with `#[scim(urn = "...")]`. Attribute names are derived from the
+//! field names in camelCase, honouring `#[serde(rename = "...")]` on individual fields. Since a derive cannot
+//! change how serde names the fields, the struct must carry `#[serde(rename_all = "camelCase")]` (or rename
+//! each field whose name is not already camelCase); otherwise the derive fails to compile rather than report
+//! names that do not match the JSON. Fields marked `#[serde(skip)]` are not attributes; `#[serde(flatten)]`
+//! is rejected, as the names of the flattened fields are not known to the derive.
+//!
+//! ```ignore
+//! use scim_v2::ScimExtension;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, ScimExtension)]
+//! #[serde(rename_all = "camelCase")]
+//! #[scim(urn = "urn:example:params:scim:schemas:extension:license:2.0:User")]
+//! struct LicenseExtension {
+//!     license_key: Option<String>,
+//!     seat_count: Option<i64>,
+//! }
+//! ```
+//!
+//! ## `#[derive(ScimSchema)]` and `#[derive(SchemaAttribute)]`
+//!
+//! `ScimSchema` implements `scim_v2::models::schema_builder::ScimSchema`, producing an RFC 7643 `Schema`
+//! document from a struct. The schema is described with `#[scim(urn = "...", name = "...", description =
+//! "...")]`; only `urn` is required, `name` defaults to the struct name. Every field becomes an attribute named
+//! as for `ScimExtension` (with the same serde requirements), typed through the `SchemaAttribute`
+//! implementation of the field's type. Fields take `#[scim(mutability = "...", returned = "...", uniqueness =
+//! "...", description = "...", case_exact)]`, or `#[scim(skip)]` to be left out.
+//!
+//! `SchemaAttribute` implements `scim_v2::models::schema_builder::SchemaAttribute` for a struct used as a
+//! complex attribute; its fields become sub-attributes, following the same rules.
+//!
+//! ```ignore
+//! use scim_v2::{SchemaAttribute, ScimSchema};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, SchemaAttribute)]
+//! #[serde(rename_all = "camelCase")]
+//! struct Owner {
+//!     display_name: Option<String>,
+//! }
+//!
+//! #[derive(Serialize, Deserialize, ScimSchema)]
+//! #[serde(rename_all = "camelCase")]
+//! #[scim(urn = "urn:example:params:scim:schemas:core:1.0:Device", name = "Device")]
+//! struct Device {
+//!     #[scim(mutability = "immutable", uniqueness = "server")]
+//!     serial_number: String,
+//!     owner: Option<Owner>,
+//! }
+//! ```
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::{Data, DeriveInput, Field, Fields, LitStr, Token, parse_macro_input};
+
+/// Derives `ScimExtension` for a struct with named fields.
+#[proc_macro_derive(ScimExtension, attributes(scim))]
+pub fn derive_scim_extension(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_scim_extension(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Derives `ScimSchema` for a struct with named fields.
+#[proc_macro_derive(ScimSchema, attributes(scim))]
+pub fn derive_scim_schema(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_scim_schema(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Derives `SchemaAttribute` for a struct with named fields, describing it as a complex attribute.
+#[proc_macro_derive(SchemaAttribute, attributes(scim))]
+pub fn derive_schema_attribute(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_schema_attribute(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand_scim_extension(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let urn = container_attrs(input, "ScimExtension")?.urn;
+    let attribute_names = serialized_fields(input, "ScimExtension")?
+        .into_iter()
+        .map(|(field, serde)| attribute_name(field, serde))
+        .collect::<Vec<_>>();
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::scim_v2::models::extension::ScimExtension for #ident #ty_generics #where_clause {
+            const URN: &'static str = #urn;
+
+            fn attribute_names() -> &'static [&'static str] {
+                &[#(#attribute_names),*]
+            }
+        }
+    })
+}
+
+fn expand_scim_schema(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let container = container_attrs(input, "ScimSchema")?;
+    let urn = container.urn;
+    let name = container
+        .name
+        .unwrap_or_else(|| LitStr::new(&input.ident.to_string(), input.ident.span()));
+    let description = container
+        .description
+        .unwrap_or_else(|| LitStr::new("", input.ident.span()));
+    let attributes = field_attributes(serialized_fields(input, "ScimSchema")?)?;
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::scim_v2::models::schema_builder::ScimSchema for #ident #ty_generics #where_clause {
+            fn scim_schema() -> ::scim_v2::models::scim_schema::Schema {
+                ::scim_v2::models::schema_builder::SchemaBuilder::new(#urn, #name)
+                    .description(#description)
+                    #(.attribute(#attributes))*
+                    .build()
+            }
+        }
+    })
+}
+
+fn expand_schema_attribute(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let attributes = field_attributes(serialized_fields(input, "SchemaAttribute")?)?;
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::scim_v2::models::schema_builder::SchemaAttribute for #ident #ty_generics #where_clause {
+            fn attribute(name: &str) -> ::scim_v2::models::scim_schema::Attributes {
+                ::scim_v2::models::scim_schema::Attributes::new(name, "complex")
+                    .with_required(true)
+                    #(.with_sub_attribute(#attributes))*
+            }
+        }
+    })
+}
+
+fn named_fields<'a>(
+    input: &'a DeriveInput,
+    derive: &str,
+) -> syn::Result<&'a Punctuated<Field, Token![,]>> {
+    match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => Ok(&fields.named),
+            _ => Err(syn::Error::new_spanned(
+                &input.ident,
+                format!(
+                    "{} can only be derived for structs with named fields",
+                    derive
+                ),
+            )),
+        },
+        _ => Err(syn::Error::new_spanned(
+            &input.ident,
+            format!("{} can only be derived for structs", derive),
+        )),
+    }
+}
+
+/// Returns the fields that serde serializes, with their serde attributes, checking that serde names them
+/// the way the derive does.
+fn serialized_fields<'a>(
+    input: &'a DeriveInput,
+    derive: &str,
+) -> syn::Result<Vec<(&'a Field, SerdeField)>> {
+    let rename_all = serde_rename_all(&input.attrs)?;
+    let mut fields = Vec::new();
+    for field in named_fields(input, derive)? {
+        let serde = serde_field(&field.attrs)?;
+        if serde.skip {
+            continue;
+        }
+        let ident = field.ident.as_ref().expect("named field");
+        if serde.flatten {
+            return Err(syn::Error::new_spanned(
+                ident,
+                format!(
+                    "{} does not support #[serde(flatten)]; declare the flattened attributes as fields",
+                    derive
+                ),
+            ));
+        }
+        if serde.rename.is_none() {
+            match &rename_all {
+                Some(rule) if rule.value() != "camelCase" => {
+                    return Err(syn::Error::new_spanned(
+                        rule,
+                        format!(
+                            "{} names attributes in camelCase; use #[serde(rename_all = \"camelCase\")]",
+                            derive
+                        ),
+                    ));
+                }
+                None => {
+                    let name = ident.to_string();
+                    let name = name.strip_prefix("r#").unwrap_or(&name);
+                    if camel_case(name) != name {
+                        return Err(syn::Error::new_spanned(
+                            ident,
+                            format!(
+                                "field `{}` is serialized as `{}` but {} names it `{}`; add #[serde(rename_all = \"camelCase\")] to the struct",
+                                name,
+                                name,
+                                derive,
+                                camel_case(name)
+                            ),
+                        ));
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+        fields.push((field, serde));
+    }
+    Ok(fields)
+}
+
+fn attribute_name(field: &Field, serde: SerdeField) -> String {
+    let ident = field.ident.as_ref().expect("named field");
+    serde
+        .rename
+        .unwrap_or_else(|| camel_case(&ident.to_string()))
+}
+
+/// Builds an `Attributes` expression for every field that is not marked `#[scim(skip)]`.
+fn field_attributes(
+    fields: Vec<(&Field, SerdeField)>,
+) -> syn::Result<Vec<proc_macro2::TokenStream>> {
+    let mut attributes = Vec::new();
+    for (field, serde) in fields {
+        let mut skip = false;
+        let mut setters = Vec::new();
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("scim")) {
+            attr.parse_nested_meta(|meta| {
+                let setter = ["mutability", "returned", "uniqueness", "description"]
+                    .into_iter()
+                    .find(|key| meta.path.is_ident(key));
+                if meta.path.is_ident("skip") {
+                    skip = true;
+                } else if meta.path.is_ident("case_exact") {
+                    setters.push(quote!(.with_case_exact(true)));
+                } else if let Some(key) = setter {
+                    let value = meta.value()?.parse::<LitStr>()?;
+                    let method = syn::Ident::new(&format!("with_{}", key), value.span());
+                    setters.push(quote!(.#method(#value)));
+                } else {
+                    return Err(meta.error(
+                        "unsupported scim attribute, expected `skip`, `case_exact`, `mutability`, `returned`, `uniqueness` or `description`",
+                    ));
+                }
+                Ok(())
+            })?;
+        }
+        if skip {
+            continue;
+        }
+        let ty = &field.ty;
+        let name = attribute_name(field, serde);
+        attributes.push(quote! {
+            <#ty as ::scim_v2::models::schema_builder::SchemaAttribute>::attribute(#name) #(#setters)*
+        });
+    }
+    Ok(attributes)
+}
+
+struct ContainerAttrs {
+    urn: LitStr,
+    name: Option<LitStr>,
+    description: Option<LitStr>,
+}
+
+fn container_attrs(input: &DeriveInput, derive: &str) -> syn::Result<ContainerAttrs> {
+    let mut urn = None;
+    let mut name = None;
+    let mut description = None;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("scim")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("urn") {
+                urn = Some(meta.value()?.parse::<LitStr>()?);
+            } else if meta.path.is_ident("name") {
+                name = Some(meta.value()?.parse::<LitStr>()?);
+            } else if meta.path.is_ident("description") {
+                description = Some(meta.value()?.parse::<LitStr>()?);
+            } else {
+                return Err(meta
+                    .error("unsupported scim attribute, expected `urn`, `name` or `description`"));
+            }
+            Ok(())
+        })?;
+    }
+    let urn = urn.ok_or_else(|| {
+        syn::Error::new_spanned(
+            &input.ident,
+            format!("missing #[scim(urn = \"...\")] attribute on {}", derive),
+        )
+    })?;
+    Ok(ContainerAttrs {
+        urn,
+        name,
+        description,
+    })
+}
+
+/// The serde attributes of a field that affect its attribute name.
+#[derive(Default)]
+struct SerdeField {
+    rename: Option<String>,
+    skip: bool,
+    flatten: bool,
+}
+
+fn serde_field(attrs: &[syn::Attribute]) -> syn::Result<SerdeField> {
+    let mut field = SerdeField::default();
+    for attr in attrs.iter().filter(|a| a.path().is_ident("serde")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                field.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("skip") {
+                field.skip = true;
+            } else if meta.path.is_ident("flatten") {
+                field.flatten = true;
+            } else {
+                skip_serde_meta(&meta)?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(field)
+}
+
+fn serde_rename_all(attrs: &[syn::Attribute]) -> syn::Result<Option<LitStr>> {
+    let mut rename_all = None;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("serde")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename_all") && meta.input.peek(syn::Token![=]) {
+                rename_all = Some(meta.value()?.parse::<LitStr>()?);
+            } else {
+                skip_serde_meta(&meta)?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(rename_all)
+}
+
+// Consumes the value of a serde attribute the derives do not interpret.
+fn skip_serde_meta(meta: &syn::meta::ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(syn::Token![=]) {
+        meta.value()?.parse::<syn::Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        meta.parse_nested_meta(|nested| {
+            if nested.input.peek(syn::Token![=]) {
+                nested.value()?.parse::<syn::Expr>()?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(())
+}
+
+fn camel_case(field: &str) -> String {
+    let field = field.strip_prefix("r#").unwrap_or(field);
+    let mut name = String::with_capacity(field.len());
+    let mut upper = false;
+    for c in field.chars() {
+        if c == '_' {
+            upper = !name.is_empty();
+        } else if upper {
+            name.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            name.push(c);
+        }
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn camel_case_converts_snake_case_fields() {
+        assert_eq!(camel_case("license_key"), "licenseKey");
+        assert_eq!(camel_case("seats"), "seats");
+        assert_eq!(camel_case("r#type"), "type");
+        assert_eq!(camel_case("x509_certificates"), "x509Certificates");
+    }
+
+    #[test]
+    fn serialized_fields_follow_serde_attributes() {
+        let input: DeriveInput = syn::parse_quote! {
+            #[serde(rename_all = "camelCase")]
+            struct License {
+                license_key: String,
+                #[serde(rename = "seatCount")]
+                seats: i64,
+                #[serde(skip)]
+                cached: bool,
+            }
+        };
+        let names: Vec<String> = serialized_fields(&input, "ScimExtension")
+            .unwrap()
+            .into_iter()
+            .map(|(field, serde)| attribute_name(field, serde))
+            .collect();
+        assert_eq!(names, ["licenseKey", "seatCount"]);
+    }
+
+    #[test]
+    fn serialized_fields_reject_names_serde_would_not_produce() {
+        let missing_rename_all: DeriveInput = syn::parse_quote! {
+            struct License {
+                license_key: String,
+            }
+        };
+        assert!(serialized_fields(&missing_rename_all, "ScimExtension").is_err());
+
+        let flatten: DeriveInput = syn::parse_quote! {
+            #[serde(rename_all = "camelCase")]
+            struct License {
+                #[serde(flatten)]
+                extra: Extra,
+            }
+        };
+        assert!(serialized_fields(&flatten, "ScimExtension").is_err());
+
+        let single_words: DeriveInput = syn::parse_quote! {
+            struct License {
+                seats: i64,
+            }
+        };
+        assert!(serialized_fields(&single_words, "ScimExtension").is_ok());
+    }
+}
@@ -0,0 +1,31 @@
+//! Prints Rust struct definitions for a SCIM `Schema` document.
+//!
+//! Usage: `scim-codegen [schema.json]`. The schema is read from standard input if no file is given.
+
+use std::io::Read;
+use std::process::ExitCode;
+
+use scim_v2::models::scim_schema::Schema;
+use scim_v2::utils::codegen::generate_rust;
+
+fn main() -> ExitCode {
+    let mut json = String::new();
+    let read = match std::env::args().nth(1) {
+        Some(path) => std::fs::read_to_string(path).map(|content| json = content),
+        None => std::io::stdin().read_to_string(&mut json).map(|_| ()),
+    };
+    if let Err(e) = read {
+        eprintln!("Error reading schema: {}", e);
+        return ExitCode::FAILURE;
+    }
+    match Schema::deserialize(&json) {
+        Ok(schema) => {
+            print!("{}", generate_rust(&schema));
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("Error parsing schema: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
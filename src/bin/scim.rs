@@ -0,0 +1,18 @@
+//! A command line tool for SCIM payloads and service providers.
+//!
+//! Usage: `scim <command> [options]`; run `scim help` for the commands.
+
+use std::process::ExitCode;
+
+use scim_v2::cli::run;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args, &mut std::io::stdin(), &mut std::io::stdout()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,507 @@
+//! The `scim` command line tool, for debugging identity provider integrations.
+//!
+//! The offline commands validate, format, convert and diff payloads; the others send one request to a
+//! service provider, built with [`Requests`], and print the response. The binary in `src/bin/scim.rs` only
+//! calls [`run`].
+
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+
+use serde_json::{Value, json};
+
+use crate::client::request::{Requests, ScimRequest, into_result};
+use crate::compat::scim11::{
+    downgrade_list_response, downgrade_resource, upgrade_list_response, upgrade_resource,
+};
+use crate::mapping::vcard::to_vcard;
+use crate::models::others::{ListQuery, SearchRequest};
+use crate::models::schema_registry::SchemaRegistry;
+use crate::models::schema_uri::PATCH_OP_URN;
+use crate::models::user::User;
+use crate::utils::diff::diff_resources;
+use crate::utils::error::SCIMError;
+
+/// The help text of the `scim` binary.
+pub const USAGE: &str = "\
+Usage: scim <command> [options]
+
+Payloads are read from FILE, or from standard input if FILE is missing or `-`.
+
+Offline commands:
+  validate [--type TYPE] FILE...      Validate resources against the built-in schemas
+  fmt [--compact] [FILE]              Pretty-print (or compact) a JSON payload
+  convert --to FORMAT [--type TYPE] [FILE]
+                                      Convert a resource or list response to scim2 (from SCIM 1.1),
+                                      scim11 (from SCIM 2.0) or vcard (from a User)
+  diff BEFORE AFTER                   Print the PatchOp that turns BEFORE into AFTER
+
+Server commands (--url URL or SCIM_URL, --token TOKEN or SCIM_TOKEN):
+  get ENDPOINT ID
+  create ENDPOINT [FILE]
+  replace ENDPOINT ID [FILE]
+  patch ENDPOINT ID [FILE]
+  delete ENDPOINT ID
+  search ENDPOINT [--filter FILTER] [--attributes ATTRS] [--excluded-attributes ATTRS]
+         [--sort-by ATTR] [--sort-order ORDER] [--start-index N] [--count N] [--cursor CURSOR] [--post]
+  discover PATH                       e.g. ServiceProviderConfig, Schemas or ResourceTypes
+";
+
+const VALUE_OPTIONS: [&str; 12] = [
+    "--to",
+    "--type",
+    "--url",
+    "--token",
+    "--filter",
+    "--attributes",
+    "--excluded-attributes",
+    "--sort-by",
+    "--sort-order",
+    "--start-index",
+    "--count",
+    "--cursor",
+];
+const FLAGS: [&str; 2] = ["--compact", "--post"];
+
+/// Runs the command given by `args` (without the program name), reading payloads from `input` when no file
+/// is given and writing results to `output`.
+///
+/// # Errors
+///
+/// * `SCIMError::OtherError` - If the arguments are invalid or a file cannot be read.
+/// * `SCIMError::DeserializationError` - If a payload is not JSON.
+/// * `SCIMError::InvalidFieldValue` / `SCIMError::MissingRequiredField` - If `validate` finds an invalid
+///   resource.
+/// * The error the service provider reported, or `SCIMError::RequestError` if it could not be reached.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::cli::run;
+///
+/// let args = ["fmt".to_string(), "--compact".to_string()];
+/// let mut output = Vec::new();
+/// run(&args, &mut r#"{ "userName": "bjensen" }"#.as_bytes(), &mut output).unwrap();
+/// assert_eq!(String::from_utf8(output).unwrap(), "{\"userName\":\"bjensen\"}\n");
+/// ```
+pub fn run(args: &[String], input: &mut dyn Read, output: &mut dyn Write) -> Result<(), SCIMError> {
+    let args = Args::parse(args)?;
+    let Some((command, operands)) = args.positional.split_first() else {
+        return Err(usage_error("missing command"));
+    };
+    let operands: Vec<&str> = operands.iter().map(String::as_str).collect();
+    match (command.as_str(), operands.as_slice()) {
+        ("help", []) => write_text(output, USAGE),
+        ("validate", files) => validate(&args, files, input, output),
+        ("fmt", [] | [_]) => {
+            let value = read_json(operands.first().copied(), input)?;
+            if args.flag("--compact") {
+                write_text(output, &format!("{}\n", value))
+            } else {
+                write_json(output, &value)
+            }
+        }
+        ("convert", [] | [_]) => convert(&args, operands.first().copied(), input, output),
+        ("diff", [before, after]) => {
+            let before = read_json(Some(before), input)?;
+            let after = read_json(Some(after), input)?;
+            let operations = diff_resources(
+                &before,
+                &after,
+                |name| name.starts_with("urn:"),
+                |path| !matches!(path, "id" | "meta" | "schemas"),
+            );
+            write_json(
+                output,
+                &json!({"schemas": [PATCH_OP_URN], "Operations": operations}),
+            )
+        }
+        ("get", [endpoint, id]) => send(&args, output, |r| Ok(r.get(endpoint, id))),
+        ("create", [endpoint] | [endpoint, _]) => {
+            let resource = read_json(operands.get(1).copied(), input)?;
+            send(&args, output, |r| Ok(r.create(endpoint, &resource)))
+        }
+        ("replace", [endpoint, id] | [endpoint, id, _]) => {
+            let resource = read_json(operands.get(2).copied(), input)?;
+            send(&args, output, |r| Ok(r.replace(endpoint, id, &resource)))
+        }
+        ("patch", [endpoint, id] | [endpoint, id, _]) => {
+            let patch = read_json(operands.get(2).copied(), input)?;
+            send(&args, output, |r| Ok(r.patch(endpoint, id, &patch)))
+        }
+        ("delete", [endpoint, id]) => send(&args, output, |r| Ok(r.delete(endpoint, id))),
+        ("search", [endpoint]) => {
+            let query = ListQuery {
+                filter: args.option("--filter").map(str::to_string),
+                start_index: args.number("--start-index")?,
+                count: args.number("--count")?,
+                attributes: args.option("--attributes").map(str::to_string),
+                excluded_attributes: args.option("--excluded-attributes").map(str::to_string),
+                sort_by: args.option("--sort-by").map(str::to_string),
+                sort_order: args.option("--sort-order").map(str::to_string),
+                cursor: args.option("--cursor").map(str::to_string),
+            };
+            if args.flag("--post") {
+                let list = |attributes: &Option<String>| {
+                    attributes
+                        .as_ref()
+                        .map(|a| a.split(',').map(|a| a.trim().to_string()).collect())
+                };
+                let defaults = SearchRequest::default();
+                let search = SearchRequest {
+                    attributes: list(&query.attributes),
+                    excluded_attributes: list(&query.excluded_attributes),
+                    filter: query.filter.unwrap_or_default(),
+                    start_index: query.start_index.unwrap_or(defaults.start_index),
+                    count: query.count.unwrap_or(defaults.count),
+                    cursor: query.cursor,
+                    ..defaults
+                };
+                send(&args, output, |r| r.search(endpoint, &search))
+            } else {
+                send(&args, output, |r| Ok(r.list(endpoint, &query)))
+            }
+        }
+        ("discover", [path]) => send(&args, output, |r| Ok(r.discover(path))),
+        (command, _) => Err(usage_error(&format!(
+            "unknown command or wrong arguments: {}",
+            command
+        ))),
+    }
+}
+
+struct Args {
+    positional: Vec<String>,
+    options: BTreeMap<String, String>,
+}
+
+impl Args {
+    fn parse(args: &[String]) -> Result<Self, SCIMError> {
+        let mut parsed = Args {
+            positional: Vec::new(),
+            options: BTreeMap::new(),
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let (name, inline) = match arg.split_once('=') {
+                Some((name, value)) if name.starts_with("--") => (name, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+            if VALUE_OPTIONS.contains(&name) {
+                let value = match inline {
+                    Some(value) => value,
+                    None => args
+                        .next()
+                        .cloned()
+                        .ok_or_else(|| usage_error(&format!("{} needs a value", name)))?,
+                };
+                parsed.options.insert(name.to_string(), value);
+            } else if FLAGS.contains(&name) {
+                parsed.options.insert(name.to_string(), String::new());
+            } else if name.starts_with("--") {
+                return Err(usage_error(&format!("unknown option {}", name)));
+            } else {
+                parsed.positional.push(arg.clone());
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(String::as_str)
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.options.contains_key(name)
+    }
+
+    fn number(&self, name: &str) -> Result<Option<i64>, SCIMError> {
+        self.option(name)
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| usage_error(&format!("{} must be a number", name)))
+            })
+            .transpose()
+    }
+}
+
+fn validate(
+    args: &Args,
+    files: &[&str],
+    input: &mut dyn Read,
+    output: &mut dyn Write,
+) -> Result<(), SCIMError> {
+    let registry = SchemaRegistry::with_builtins();
+    let files = if files.is_empty() { &["-"][..] } else { files };
+    let mut invalid = 0;
+    for file in files {
+        let result = read_json(Some(file), input).and_then(|resource| {
+            let resource_type = match args.option("--type") {
+                Some(name) => name.to_string(),
+                None => resource_type_of(&registry, &resource)?,
+            };
+            registry.validate_resource(&resource_type, &resource)
+        });
+        match result {
+            Ok(()) => write_text(output, &format!("{}: ok\n", file))?,
+            Err(e) => {
+                invalid += 1;
+                write_text(output, &format!("{}: {}\n", file, e))?;
+            }
+        }
+    }
+    match invalid {
+        0 => Ok(()),
+        _ => Err(SCIMError::OtherError(
+            format!("{} of {} resources are invalid", invalid, files.len()).into(),
+        )),
+    }
+}
+
+// The resource type whose base schema the resource lists.
+fn resource_type_of(registry: &SchemaRegistry, resource: &Value) -> Result<String, SCIMError> {
+    let schemas: Vec<&str> = resource
+        .get("schemas")
+        .and_then(Value::as_array)
+        .ok_or_else(|| SCIMError::MissingRequiredField("schemas".into()))?
+        .iter()
+        .filter_map(Value::as_str)
+        .collect();
+    registry
+        .resource_types()
+        .iter()
+        .find(|rt| schemas.iter().any(|s| s.eq_ignore_ascii_case(&rt.schema)))
+        .map(|rt| rt.name.clone())
+        .ok_or_else(|| {
+            SCIMError::ResourceTypeNotFound(
+                format!("no resource type for schemas {:?}", schemas).into(),
+            )
+        })
+}
+
+fn convert(
+    args: &Args,
+    file: Option<&str>,
+    input: &mut dyn Read,
+    output: &mut dyn Write,
+) -> Result<(), SCIMError> {
+    let mut value = read_json(file, input)?;
+    let is_list = value.get("Resources").is_some();
+    match args.option("--to") {
+        Some("scim2") => {
+            // SCIM 1.1 shares one core schema between Users and Groups.
+            let resource_type = args.option("--type").unwrap_or("User");
+            if is_list {
+                upgrade_list_response(resource_type, &mut value);
+            } else {
+                upgrade_resource(resource_type, &mut value);
+            }
+            write_json(output, &value)
+        }
+        Some("scim11") => {
+            if is_list {
+                downgrade_list_response(&mut value);
+            } else {
+                downgrade_resource(&mut value);
+            }
+            write_json(output, &value)
+        }
+        Some("vcard") => write_text(output, &to_vcard(&User::try_from(value)?)),
+        Some(format) => Err(usage_error(&format!("unknown format {}", format))),
+        None => Err(usage_error("convert needs --to")),
+    }
+}
+
+fn send(
+    args: &Args,
+    output: &mut dyn Write,
+    build: impl FnOnce(&Requests) -> Result<ScimRequest, SCIMError>,
+) -> Result<(), SCIMError> {
+    let url = args
+        .option("--url")
+        .map(str::to_string)
+        .or_else(|| std::env::var("SCIM_URL").ok())
+        .ok_or_else(|| usage_error("the service provider needs --url or SCIM_URL"))?;
+    let mut requests = Requests::new(url);
+    if let Some(token) = args
+        .option("--token")
+        .map(str::to_string)
+        .or_else(|| std::env::var("SCIM_TOKEN").ok())
+    {
+        requests = requests.with_bearer_token(token);
+    }
+    let response = execute(&build(&requests)?)?;
+    if response.is_null() {
+        return Ok(());
+    }
+    write_json(output, &response)
+}
+
+// Sends a request with ureq; error statuses are interpreted by `into_result`, not treated as failures.
+fn execute(request: &ScimRequest) -> Result<Value, SCIMError> {
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .http_status_as_error(false)
+        .build()
+        .into();
+    let mut builder = ureq::http::Request::builder()
+        .method(request.method.as_str())
+        .uri(&request.url);
+    for (name, value) in &request.headers {
+        builder = builder.header(name, value);
+    }
+    let request_error = |e: &dyn std::fmt::Display| SCIMError::RequestError(e.to_string().into());
+    let response = match &request.body {
+        Some(body) => builder
+            .body(body.clone())
+            .map_err(|e| request_error(&e))
+            .and_then(|r| agent.run(r).map_err(|e| request_error(&e))),
+        None => builder
+            .body(())
+            .map_err(|e| request_error(&e))
+            .and_then(|r| agent.run(r).map_err(|e| request_error(&e))),
+    };
+    let mut response = response?;
+    let status = response.status().as_u16();
+    let body = response
+        .body_mut()
+        .read_to_string()
+        .map_err(|e| request_error(&e))?;
+    into_result(status, &body)
+}
+
+fn read_json(file: Option<&str>, input: &mut dyn Read) -> Result<Value, SCIMError> {
+    let mut json = String::new();
+    let read = match file {
+        None | Some("-") => input.read_to_string(&mut json).map(|_| ()),
+        Some(path) => std::fs::read_to_string(path).map(|content| json = content),
+    };
+    read.map_err(|e| SCIMError::OtherError(format!("{}: {}", file.unwrap_or("stdin"), e).into()))?;
+    serde_json::from_str(&json).map_err(SCIMError::DeserializationError)
+}
+
+fn write_json(output: &mut dyn Write, value: &Value) -> Result<(), SCIMError> {
+    let json = serde_json::to_string_pretty(value).map_err(SCIMError::SerializationError)?;
+    write_text(output, &format!("{}\n", json))
+}
+
+fn write_text(output: &mut dyn Write, text: &str) -> Result<(), SCIMError> {
+    output
+        .write_all(text.as_bytes())
+        .map_err(|e| SCIMError::OtherError(e.to_string().into()))
+}
+
+fn usage_error(message: &str) -> SCIMError {
+    SCIMError::OtherError(format!("{}; run `scim help` for usage", message).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufRead;
+    use std::net::TcpListener;
+    use std::thread;
+
+    use super::*;
+
+    fn run_with(args: &[&str], input: &str) -> Result<String, SCIMError> {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        let mut output = Vec::new();
+        run(&args, &mut input.as_bytes(), &mut output)?;
+        Ok(String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn validates_and_diffs_payloads() {
+        let valid =
+            r#"{"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "userName": "bjensen"}"#;
+        assert_eq!(run_with(&["validate"], valid).unwrap(), "-: ok\n");
+        let invalid = r#"{"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"]}"#;
+        assert!(run_with(&["validate", "-"], invalid).is_err());
+        assert!(run_with(&["validate", "--type=Device"], valid).is_err());
+
+        let dir = std::env::temp_dir().join(format!("scim-cli-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let before = dir.join("before.json");
+        let after = dir.join("after.json");
+        std::fs::write(
+            &before,
+            r#"{"id": "1", "userName": "bjensen", "nickName": "Babs"}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            &after,
+            r#"{"id": "2", "userName": "bjensen", "urn:example:ext": {"level": 3}}"#,
+        )
+        .unwrap();
+        let patch: Value = serde_json::from_str(
+            &run_with(
+                &["diff", before.to_str().unwrap(), after.to_str().unwrap()],
+                "",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            patch,
+            json!({
+                "schemas": [PATCH_OP_URN],
+                "Operations": [
+                    {"op": "add", "path": "urn:example:ext:level", "value": 3},
+                    {"op": "remove", "path": "nickName"}
+                ]
+            })
+        );
+
+        let downgraded = run_with(
+            &["convert", "--to", "scim11"],
+            r#"{"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "userName": "bjensen"}"#,
+        )
+        .unwrap();
+        assert!(downgraded.contains("urn:scim:schemas:core:1.0"));
+        assert!(run_with(&["convert", "--to", "xml"], "{}").is_err());
+        assert!(run_with(&["frobnicate"], "").is_err());
+    }
+
+    #[test]
+    fn sends_requests_to_the_service_provider() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/v2", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+            let mut head = Vec::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                head.push(line.trim().to_string());
+            }
+            let body = r#"{"schemas": ["urn:ietf:params:scim:api:messages:2.0:Error"], "detail": "User 42 not found", "status": "404"}"#;
+            write!(
+                &stream,
+                "HTTP/1.1 404 Not Found\r\nContent-Type: application/scim+json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+            head
+        });
+
+        let result = run_with(
+            &["get", "/Users", "42", "--url", &url, "--token", "t0k3n"],
+            "",
+        );
+        let head = server.join().unwrap();
+        assert_eq!(head[0], "GET /v2/Users/42 HTTP/1.1");
+        assert!(
+            head.iter()
+                .any(|h| h.eq_ignore_ascii_case("authorization: Bearer t0k3n"))
+        );
+        assert!(matches!(
+            result,
+            Err(SCIMError::NotFoundError(detail)) if detail == "User 42 not found"
+        ));
+    }
+}
@@ -0,0 +1,220 @@
+//! A client-side cache of responses, revalidated with `ETag`s (RFC 7644 §3.14).
+//!
+//! Reconciliation loops read the same resources over and over, and most of them have not changed since the
+//! last read. A [`ResponseCache`] keeps the resources read by URL together with their version, adds
+//! `If-None-Match` to the next `GET` of the same URL, and answers a `304 Not Modified` response with the
+//! cached copy, so that unchanged resources cost a request but no body. Like [`Requests`], it is independent
+//! of the HTTP library: a transport calls [`ResponseCache::prepare`] before sending a request and
+//! [`ResponseCache::complete`] instead of [`into_result`] with the response.
+//!
+//! [`Requests`]: crate::client::request::Requests
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use serde_json::Value;
+
+use crate::client::request::{Method, ScimRequest, into_result};
+use crate::utils::error::SCIMError;
+
+/// Caches responses by URL and revalidates them with `If-None-Match`.
+///
+/// Responses to `GET` requests are cached if they carry an `ETag` header or a `meta.version`. The
+/// resources returned by `PUT` and `PATCH` requests replace the cached copy of the resource, and any other
+/// request to a URL, such as a `DELETE`, evicts it. At most `max_entries` responses are kept; the oldest
+/// is evicted to make room for a new one.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::client::cache::ResponseCache;
+/// use scim_v2::client::request::Requests;
+///
+/// let requests = Requests::new("https://example.com/v2");
+/// let cache = ResponseCache::new(1000);
+///
+/// let mut request = requests.get("/Users", "2819c223");
+/// cache.prepare(&mut request);
+/// // ... send `request`; the service provider answers 200 with the user and `ETag: W/"3694e05e"` ...
+/// let body = r#"{"id": "2819c223", "userName": "bjensen", "meta": {"version": "W/\"3694e05e\""}}"#;
+/// let user = cache.complete(&request, 200, Some(r#"W/"3694e05e""#), body).unwrap();
+///
+/// let mut request = requests.get("/Users", "2819c223");
+/// cache.prepare(&mut request);
+/// assert!(request.headers.contains(&("If-None-Match".to_string(), r#"W/"3694e05e""#.to_string())));
+/// // ... send `request`; the user has not changed, so the service provider answers 304 ...
+/// assert_eq!(cache.complete(&request, 304, None, "").unwrap(), user);
+/// ```
+#[derive(Debug)]
+pub struct ResponseCache {
+    max_entries: usize,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    entries: HashMap<String, Entry>,
+    // The cached URLs, oldest first.
+    order: VecDeque<String>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    etag: String,
+    value: Value,
+}
+
+impl ResponseCache {
+    /// Creates an empty cache keeping at most `max_entries` responses.
+    pub fn new(max_entries: usize) -> Self {
+        ResponseCache {
+            max_entries,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Adds `If-None-Match` with the cached version to a `GET` request for a cached URL.
+    pub fn prepare(&self, request: &mut ScimRequest) {
+        if request.method != Method::Get {
+            return;
+        }
+        if let Some(entry) = self.lock().entries.get(&request.url) {
+            request
+                .headers
+                .push(("If-None-Match".to_string(), entry.etag.clone()));
+        }
+    }
+
+    /// Interprets the response to `request`, as [`into_result`] does, and updates the cache with it.
+    ///
+    /// `etag` is the value of the response's `ETag` header, if it has one. A `304` response to a `GET`
+    /// request is answered with the cached copy.
+    ///
+    /// # Errors
+    ///
+    /// The errors of [`into_result`]; a `304` response for a URL that is no longer cached is a
+    /// `SCIMError::RequestError`.
+    pub fn complete(
+        &self,
+        request: &ScimRequest,
+        status: u16,
+        etag: Option<&str>,
+        body: &str,
+    ) -> Result<Value, SCIMError> {
+        if request.method == Method::Get && status == 304 {
+            if let Some(entry) = self.lock().entries.get(&request.url) {
+                return Ok(entry.value.clone());
+            }
+        }
+        let result = into_result(status, body);
+        match (request.method, &result) {
+            (Method::Get | Method::Put | Method::Patch, Ok(value)) => {
+                let version = etag.or_else(|| value["meta"]["version"].as_str());
+                match version {
+                    Some(version) => self.store(&request.url, version.to_string(), value.clone()),
+                    None => self.invalidate(&request.url),
+                }
+            }
+            (Method::Post, _) => {}
+            _ => self.invalidate(&request.url),
+        }
+        result
+    }
+
+    /// Evicts the cached response for `url`.
+    pub fn invalidate(&self, url: &str) {
+        let mut state = self.lock();
+        if state.entries.remove(url).is_some() {
+            state.order.retain(|cached| cached != url);
+        }
+    }
+
+    /// Evicts every cached response.
+    pub fn clear(&self) {
+        *self.lock() = State::default();
+    }
+
+    /// Returns the number of cached responses.
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Returns `true` if no responses are cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn store(&self, url: &str, etag: String, value: Value) {
+        if self.max_entries == 0 {
+            return;
+        }
+        let mut state = self.lock();
+        if state
+            .entries
+            .insert(url.to_string(), Entry { etag, value })
+            .is_some()
+        {
+            state.order.retain(|cached| cached != url);
+        }
+        state.order.push_back(url.to_string());
+        while state.order.len() > self.max_entries {
+            if let Some(oldest) = state.order.pop_front() {
+                state.entries.remove(&oldest);
+            }
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::client::request::Requests;
+
+    #[test]
+    fn follows_writes_and_evicts_the_oldest() {
+        let requests = Requests::new("https://example.com/v2");
+        let cache = ResponseCache::new(2);
+        let user = |id: &str, version: &str| {
+            json!({"id": id, "userName": id, "meta": {"version": version}}).to_string()
+        };
+        let get = |id: &str| {
+            let mut request = requests.get("/Users", id);
+            cache.prepare(&mut request);
+            request
+        };
+
+        cache
+            .complete(&get("a"), 200, None, &user("a", "v1"))
+            .unwrap();
+        let replace = requests.replace("/Users", "a", &json!({"userName": "a"}));
+        cache
+            .complete(&replace, 200, Some("v2"), &user("a", "v2"))
+            .unwrap();
+        assert_eq!(get("a").headers.last().unwrap().1, "v2");
+        assert!(matches!(
+            cache.complete(&replace, 412, None, ""),
+            Err(SCIMError::PreconditionFailed(_))
+        ));
+        assert_eq!(get("a").headers.len(), 1);
+
+        for id in ["a", "b", "c"] {
+            cache
+                .complete(&get(id), 200, None, &user(id, "v1"))
+                .unwrap();
+        }
+        assert_eq!(cache.len(), 2);
+        assert!(matches!(
+            cache.complete(&get("a"), 304, None, ""),
+            Err(SCIMError::RequestError(_))
+        ));
+        let delete = requests.delete("/Users", "c");
+        cache.complete(&delete, 204, None, "").unwrap();
+        assert_eq!(cache.len(), 1);
+    }
+}
@@ -0,0 +1,179 @@
+//! A SCIM client for browsers and edge runtimes, built on the JavaScript `fetch` API.
+
+use std::sync::Arc;
+
+use js_sys::{Function, Promise, Reflect};
+use serde_json::Value;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Headers, Request, RequestInit, Response};
+
+use crate::client::cache::ResponseCache;
+use crate::client::request::{Requests, ScimRequest, into_result};
+use crate::models::others::{ListQuery, ListResponse, Resource, SearchRequest};
+use crate::utils::error::SCIMError;
+use crate::utils::pagination::{PageRequest, PageResponse};
+
+/// Sends SCIM requests with the global `fetch` function, so it works in browser windows, web workers,
+/// Cloudflare Workers and other runtimes that provide `fetch` on wasm32-unknown-unknown.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scim_v2::client::fetch::FetchClient;
+/// use scim_v2::client::request::Requests;
+///
+/// async fn user_name(id: &str) -> Option<String> {
+///     let client = FetchClient::new(
+///         Requests::new("https://example.com/scim/v2").with_bearer_token("s3cr3t"),
+///     );
+///     let user = client.get("/Users", id).await.ok()?;
+///     user["userName"].as_str().map(str::to_string)
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct FetchClient {
+    requests: Requests,
+    cache: Option<Arc<ResponseCache>>,
+}
+
+impl FetchClient {
+    /// Creates a client sending the requests `requests` builds.
+    pub fn new(requests: Requests) -> Self {
+        FetchClient {
+            requests,
+            cache: None,
+        }
+    }
+
+    /// Revalidates reads with `cache` instead of downloading unchanged resources again. Clones of the client
+    /// share the cache.
+    pub fn with_cache(mut self, cache: ResponseCache) -> Self {
+        self.cache = Some(Arc::new(cache));
+        self
+    }
+
+    /// Returns the request builder.
+    pub fn requests(&self) -> &Requests {
+        &self.requests
+    }
+
+    /// Reads a resource.
+    pub async fn get(&self, endpoint: &str, id: &str) -> Result<Value, SCIMError> {
+        self.send(&self.requests.get(endpoint, id)).await
+    }
+
+    /// Creates a resource and returns it as the service provider stored it.
+    pub async fn create(&self, endpoint: &str, resource: &Value) -> Result<Value, SCIMError> {
+        self.send(&self.requests.create(endpoint, resource)).await
+    }
+
+    /// Replaces a resource and returns it as the service provider stored it.
+    pub async fn replace(
+        &self,
+        endpoint: &str,
+        id: &str,
+        resource: &Value,
+    ) -> Result<Value, SCIMError> {
+        self.send(&self.requests.replace(endpoint, id, resource))
+            .await
+    }
+
+    /// Applies a `PatchOp` message. Returns the resource if the service provider sent it back, and `null`
+    /// for `204 No Content`.
+    pub async fn patch(&self, endpoint: &str, id: &str, patch: &Value) -> Result<Value, SCIMError> {
+        self.send(&self.requests.patch(endpoint, id, patch)).await
+    }
+
+    /// Deletes a resource.
+    pub async fn delete(&self, endpoint: &str, id: &str) -> Result<(), SCIMError> {
+        self.send(&self.requests.delete(endpoint, id))
+            .await
+            .map(|_| ())
+    }
+
+    /// Lists resources.
+    pub async fn list(&self, endpoint: &str, query: &ListQuery) -> Result<ListResponse, SCIMError> {
+        ListResponse::try_from(self.send(&self.requests.list(endpoint, query)).await?)
+    }
+
+    /// Lists the page of resources `request` names, e.g. to follow
+    /// [`PageResponse::next`](crate::utils::pagination::PageResponse::next) by index or by cursor.
+    pub async fn list_page(
+        &self,
+        endpoint: &str,
+        query: &ListQuery,
+        request: &PageRequest,
+    ) -> Result<PageResponse<Resource>, SCIMError> {
+        let response = self.list(endpoint, &request.apply(query)).await?;
+        Ok(PageResponse::from_list_response(request, response))
+    }
+
+    /// Searches with `POST .search`.
+    pub async fn search(
+        &self,
+        endpoint: &str,
+        search: &SearchRequest,
+    ) -> Result<ListResponse, SCIMError> {
+        ListResponse::try_from(self.send(&self.requests.search(endpoint, search)?).await?)
+    }
+
+    /// Sends a request and interprets the response with [`into_result`], or with
+    /// [`ResponseCache::complete`] if the client has a cache.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::RequestError` if `fetch` fails, e.g. on a network or CORS error, and the error
+    /// the service provider reported otherwise.
+    pub async fn send(&self, request: &ScimRequest) -> Result<Value, SCIMError> {
+        let mut request = request.clone();
+        if let Some(cache) = &self.cache {
+            cache.prepare(&mut request);
+        }
+        let init = RequestInit::new();
+        init.set_method(request.method.as_str());
+        let headers = Headers::new().map_err(js_error)?;
+        for (name, value) in &request.headers {
+            headers.set(name, value).map_err(js_error)?;
+        }
+        init.set_headers(&headers);
+        if let Some(body) = &request.body {
+            init.set_body(&JsValue::from_str(body));
+        }
+        let fetch_request =
+            Request::new_with_str_and_init(&request.url, &init).map_err(js_error)?;
+
+        // `fetch` is looked up on the global object, which is a `Window` only in browser windows.
+        let global = js_sys::global();
+        let fetch: Function = Reflect::get(&global, &JsValue::from_str("fetch"))
+            .map_err(js_error)?
+            .dyn_into()
+            .map_err(js_error)?;
+        let promise: Promise = fetch
+            .call1(&global, &fetch_request)
+            .map_err(js_error)?
+            .dyn_into()
+            .map_err(js_error)?;
+        let response: Response = JsFuture::from(promise)
+            .await
+            .map_err(js_error)?
+            .dyn_into()
+            .map_err(js_error)?;
+        let body = JsFuture::from(response.text().map_err(js_error)?)
+            .await
+            .map_err(js_error)?
+            .as_string()
+            .unwrap_or_default();
+        match &self.cache {
+            Some(cache) => {
+                let etag = response.headers().get("ETag").map_err(js_error)?;
+                cache.complete(&request, response.status(), etag.as_deref(), &body)
+            }
+            None => into_result(response.status(), &body),
+        }
+    }
+}
+
+fn js_error(value: JsValue) -> SCIMError {
+    SCIMError::RequestError(format!("fetch: {:?}", value).into())
+}
@@ -0,0 +1,401 @@
+//! The HTTP requests of the SCIM protocol, independent of any HTTP library.
+//!
+//! [`Requests`] builds the [`ScimRequest`] for each protocol operation and [`into_result`] interprets the
+//! response, so a client only has to move bytes: [`FetchClient`](crate::client::fetch::FetchClient) does it
+//! with the `fetch` API, and other transports can do the same with the HTTP library they already use.
+
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use serde_json::Value;
+
+use crate::models::capabilities::NegotiatedCapabilities;
+use crate::models::errors::ScimHttpError;
+use crate::models::others::{ListQuery, SearchRequest};
+use crate::models::resource::ScimResource;
+use crate::utils::error::SCIMError;
+
+/// The media type of SCIM messages.
+pub const SCIM_MEDIA_TYPE: &str = "application/scim+json";
+
+/// An HTTP method used by SCIM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    Get,
+    Post,
+    Put,
+    Patch,
+    Delete,
+}
+
+impl Method {
+    /// Returns the method name, e.g. `GET`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Method::Get => "GET",
+            Method::Post => "POST",
+            Method::Put => "PUT",
+            Method::Patch => "PATCH",
+            Method::Delete => "DELETE",
+        }
+    }
+}
+
+impl Display for Method {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An HTTP request to a SCIM service provider.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScimRequest {
+    pub method: Method,
+    /// The absolute URL, including the query string.
+    pub url: String,
+    /// Header names and values.
+    pub headers: Vec<(String, String)>,
+    /// The JSON body, if the request has one.
+    pub body: Option<String>,
+}
+
+/// Builds the requests for the protocol operations against one service provider.
+///
+/// Endpoints are given as the resource type's `endpoint`, e.g. `/Users`.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::client::request::{Method, Requests};
+/// use scim_v2::models::others::ListQuery;
+///
+/// let requests = Requests::new("https://example.com/scim/v2/").with_bearer_token("s3cr3t");
+/// let request = requests.list(
+///     "/Users",
+///     &ListQuery {
+///         filter: Some(r#"userName eq "bjensen""#.to_string()),
+///         ..ListQuery::default()
+///     },
+/// );
+/// assert_eq!(request.method, Method::Get);
+/// assert_eq!(
+///     request.url,
+///     "https://example.com/scim/v2/Users?filter=userName%20eq%20%22bjensen%22&startIndex=1&count=100"
+/// );
+/// assert!(request.headers.contains(&("Authorization".to_string(), "Bearer s3cr3t".to_string())));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Requests {
+    base_url: String,
+    bearer_token: Option<String>,
+    capabilities: Option<NegotiatedCapabilities>,
+}
+
+impl Requests {
+    /// Targets the service provider at `base_url`, e.g. `https://example.com/scim/v2`.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Requests {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            bearer_token: None,
+            capabilities: None,
+        }
+    }
+
+    /// Authenticates requests with an OAuth bearer token.
+    pub fn with_bearer_token(mut self, token: impl Into<String>) -> Self {
+        self.bearer_token = Some(token.into());
+        self
+    }
+
+    /// Keeps requests within what was negotiated with the service provider: list requests ask for no more
+    /// resources than it returns in one response.
+    pub fn with_capabilities(mut self, capabilities: NegotiatedCapabilities) -> Self {
+        self.capabilities = Some(capabilities);
+        self
+    }
+
+    /// Returns the capabilities set with [`Requests::with_capabilities`].
+    pub fn capabilities(&self) -> Option<&NegotiatedCapabilities> {
+        self.capabilities.as_ref()
+    }
+
+    /// Returns the base URL, without a trailing slash.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// `GET {endpoint}/{id}`.
+    pub fn get(&self, endpoint: &str, id: &str) -> ScimRequest {
+        self.request(Method::Get, &self.resource_url(endpoint, id), None)
+    }
+
+    /// `POST {endpoint}` with the resource to create.
+    pub fn create(&self, endpoint: &str, resource: &Value) -> ScimRequest {
+        self.request(Method::Post, &self.url(endpoint), Some(resource))
+    }
+
+    /// `PUT {endpoint}/{id}` with the replacement resource.
+    pub fn replace(&self, endpoint: &str, id: &str, resource: &Value) -> ScimRequest {
+        self.request(
+            Method::Put,
+            &self.resource_url(endpoint, id),
+            Some(resource),
+        )
+    }
+
+    /// `PATCH {endpoint}/{id}` with a `PatchOp` message.
+    pub fn patch(&self, endpoint: &str, id: &str, patch: &Value) -> ScimRequest {
+        self.request(Method::Patch, &self.resource_url(endpoint, id), Some(patch))
+    }
+
+    /// `DELETE {endpoint}/{id}`.
+    pub fn delete(&self, endpoint: &str, id: &str) -> ScimRequest {
+        self.request(Method::Delete, &self.resource_url(endpoint, id), None)
+    }
+
+    /// `GET {endpoint}` with the query's parameters. Empty parameters are left out, and `count` is lowered
+    /// to the page size of the negotiated capabilities, if any.
+    pub fn list(&self, endpoint: &str, query: &ListQuery) -> ScimRequest {
+        let numbers = |n: Option<i64>| n.map(|n| n.to_string());
+        let count = match &self.capabilities {
+            Some(capabilities) => capabilities.clamp_page_size(query.count),
+            None => query.count,
+        };
+        let parameters = [
+            ("filter", query.filter.clone()),
+            ("startIndex", numbers(query.start_index)),
+            ("count", numbers(count)),
+            ("attributes", query.attributes.clone()),
+            ("excludedAttributes", query.excluded_attributes.clone()),
+            ("sortBy", query.sort_by.clone()),
+            ("sortOrder", query.sort_order.clone()),
+            ("cursor", query.cursor.clone()),
+        ];
+        let query_string: Vec<String> = parameters
+            .iter()
+            .filter_map(|(name, value)| {
+                // An empty cursor asks for the first page, so it is sent.
+                let value = value
+                    .as_deref()
+                    .filter(|v| !v.is_empty() || *name == "cursor")?;
+                Some(format!("{}={}", name, percent_encode(value)))
+            })
+            .collect();
+        let mut url = self.url(endpoint);
+        if !query_string.is_empty() {
+            url.push('?');
+            url.push_str(&query_string.join("&"));
+        }
+        self.request(Method::Get, &url, None)
+    }
+
+    /// `POST {endpoint}/.search` with a `SearchRequest` message.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if the search request cannot be serialized.
+    pub fn search(&self, endpoint: &str, search: &SearchRequest) -> Result<ScimRequest, SCIMError> {
+        let body = serde_json::to_value(search).map_err(SCIMError::SerializationError)?;
+        let url = format!("{}/.search", self.url(endpoint));
+        Ok(self.request(Method::Post, &url, Some(&body)))
+    }
+
+    /// `GET /{path}` for the discovery endpoints, e.g. `ServiceProviderConfig` or `Schemas`.
+    pub fn discover(&self, path: &str) -> ScimRequest {
+        self.request(Method::Get, &self.url(path), None)
+    }
+
+    /// `POST` of a resource to the endpoint of its type.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if the resource cannot be serialized.
+    pub fn create_resource<R: ScimResource>(&self, resource: &R) -> Result<ScimRequest, SCIMError> {
+        Ok(self.create(R::ENDPOINT, &resource.to_value()?))
+    }
+
+    /// `PUT` of a resource to its path.
+    ///
+    /// # Errors
+    ///
+    /// * `SCIMError::MissingRequiredField` - If the resource has no id.
+    /// * `SCIMError::SerializationError` - If the resource cannot be serialized.
+    pub fn replace_resource<R: ScimResource>(
+        &self,
+        resource: &R,
+    ) -> Result<ScimRequest, SCIMError> {
+        let id = resource
+            .id()
+            .ok_or_else(|| SCIMError::MissingRequiredField("id".into()))?;
+        Ok(self.replace(R::ENDPOINT, id, &resource.to_value()?))
+    }
+
+    fn url(&self, endpoint: &str) -> String {
+        format!("{}/{}", self.base_url, endpoint.trim_matches('/'))
+    }
+
+    fn resource_url(&self, endpoint: &str, id: &str) -> String {
+        format!("{}/{}", self.url(endpoint), percent_encode(id))
+    }
+
+    fn request(&self, method: Method, url: &str, body: Option<&Value>) -> ScimRequest {
+        let mut headers = vec![("Accept".to_string(), SCIM_MEDIA_TYPE.to_string())];
+        if body.is_some() {
+            headers.push(("Content-Type".to_string(), SCIM_MEDIA_TYPE.to_string()));
+        }
+        if let Some(token) = &self.bearer_token {
+            headers.push(("Authorization".to_string(), format!("Bearer {}", token)));
+        }
+        ScimRequest {
+            method,
+            url: url.to_string(),
+            headers,
+            body: body.map(Value::to_string),
+        }
+    }
+}
+
+/// Interprets a response: the parsed body of a 2xx response (`null` if it is empty), or the error the
+/// service provider reported.
+///
+/// # Errors
+///
+/// * `SCIMError::DeserializationError` - If a 2xx body is not JSON.
+/// * The error matching the SCIM error response otherwise, as converted from [`ScimHttpError`].
+pub fn into_result(status: u16, body: &str) -> Result<Value, SCIMError> {
+    if (200..300).contains(&status) {
+        if body.trim().is_empty() {
+            return Ok(Value::Null);
+        }
+        return serde_json::from_str(body).map_err(SCIMError::DeserializationError);
+    }
+    let error = serde_json::from_str::<ScimHttpError>(body).unwrap_or_else(|_| ScimHttpError {
+        detail: Some(body.trim().to_string()).filter(|d| !d.is_empty()),
+        status: status.to_string(),
+        ..ScimHttpError::default()
+    });
+    Err(SCIMError::from(&error))
+}
+
+/// Interprets a response carrying a resource, as [`into_result`] does, and reads the resource from it.
+///
+/// # Errors
+///
+/// The errors of [`into_result`], and `SCIMError::DeserializationError` if the body does not describe the
+/// resource.
+pub fn into_resource<R: ScimResource>(status: u16, body: &str) -> Result<R, SCIMError> {
+    R::from_value(into_result(status, body)?)
+}
+
+// Percent-encodes everything but the unreserved characters of RFC 3986.
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::capabilities::{DesiredCapabilities, negotiate};
+    use crate::models::group::Group;
+    use crate::models::service_provider_config::ServiceProviderConfig;
+
+    #[test]
+    fn builds_requests_for_each_operation() {
+        let requests = Requests::new("https://example.com/v2");
+        let user = json!({"userName": "bjensen"});
+        let created = requests.create("Users", &user);
+        assert_eq!(created.url, "https://example.com/v2/Users");
+        assert_eq!(created.body.as_deref(), Some(r#"{"userName":"bjensen"}"#));
+        assert!(
+            created
+                .headers
+                .contains(&("Content-Type".to_string(), SCIM_MEDIA_TYPE.to_string()))
+        );
+
+        let deleted = requests.delete("/Users/", "a b/c");
+        assert_eq!(deleted.method, Method::Delete);
+        assert_eq!(deleted.url, "https://example.com/v2/Users/a%20b%2Fc");
+        assert_eq!(deleted.body, None);
+        assert_eq!(deleted.headers.len(), 1);
+
+        let first_page = requests.list(
+            "/Users",
+            &ListQuery {
+                count: None,
+                start_index: None,
+                cursor: Some(String::new()),
+                ..ListQuery::default()
+            },
+        );
+        assert_eq!(first_page.url, "https://example.com/v2/Users?cursor=");
+        let mut config = ServiceProviderConfig::default();
+        config.filter.max_results = 50;
+        let capped = Requests::new("https://example.com/v2")
+            .with_capabilities(negotiate(&DesiredCapabilities::default(), &config))
+            .list("/Users", &ListQuery::default());
+        assert!(capped.url.ends_with("startIndex=1&count=50"));
+
+        let search = requests
+            .search(
+                "/Groups",
+                &SearchRequest {
+                    filter: "displayName pr".to_string(),
+                    ..SearchRequest::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(search.url, "https://example.com/v2/Groups/.search");
+
+        let group = Group {
+            id: Some("e9e30dba".into()),
+            display_name: "Tour Guides".to_string(),
+            ..Group::default()
+        };
+        let replaced = requests.replace_resource(&group).unwrap();
+        assert_eq!(replaced.url, "https://example.com/v2/Groups/e9e30dba");
+        assert!(matches!(
+            requests.replace_resource(&Group::default()),
+            Err(SCIMError::MissingRequiredField(_))
+        ));
+    }
+
+    #[test]
+    fn maps_error_responses() {
+        assert_eq!(into_result(204, "").unwrap(), Value::Null);
+        assert_eq!(into_result(200, r#"{"id": "1"}"#).unwrap()["id"], "1");
+        assert!(matches!(
+            into_result(200, "<html>"),
+            Err(SCIMError::DeserializationError(_))
+        ));
+
+        let conflict = json!({
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:Error"],
+            "scimType": "uniqueness",
+            "detail": "userName bjensen is taken",
+            "status": "409"
+        });
+        assert!(matches!(
+            into_result(409, &conflict.to_string()),
+            Err(SCIMError::ConflictError(detail)) if detail == "userName bjensen is taken"
+        ));
+        assert!(matches!(
+            into_result(404, ""),
+            Err(SCIMError::NotFoundError(_))
+        ));
+        assert!(matches!(
+            into_result(502, "Bad Gateway"),
+            Err(SCIMError::RequestError(detail)) if detail == "502: Bad Gateway"
+        ));
+    }
+}
@@ -0,0 +1,150 @@
+//! Normalization steps shared by several provider profiles.
+
+use serde_json::{Map, Value, json};
+
+use crate::models::schema_uri::LIST_RESPONSE_URN;
+
+/// Lowercases the `op` of every PATCH operation (`Add` becomes `add`).
+pub(crate) fn lowercase_ops(patch: &mut Value) {
+    for operation in operations(patch) {
+        if let Some(Value::String(op)) = operation.get_mut("op") {
+            *op = op.to_ascii_lowercase();
+        }
+    }
+}
+
+/// Rewrites `{"op": "remove", "path": "members", "value": [{"value": "id"}, ...]}` into one operation per
+/// member with a value filter (`members[value eq "id"]`), the form RFC 7644, section 3.5.2.2 defines.
+/// Without it, a standard implementation removes all members.
+pub(crate) fn split_member_removals(patch: &mut Value) {
+    let Some(operations) = patch.get_mut("Operations").and_then(Value::as_array_mut) else {
+        return;
+    };
+    let mut rewritten = Vec::with_capacity(operations.len());
+    for operation in operations.drain(..) {
+        let is_member_removal = operation["op"]
+            .as_str()
+            .is_some_and(|op| op.eq_ignore_ascii_case("remove"))
+            && operation["path"]
+                .as_str()
+                .is_some_and(|path| path.eq_ignore_ascii_case("members"));
+        let ids: Option<Vec<&str>> = operation["value"]
+            .as_array()
+            .map(|members| members.iter().map(|m| m["value"].as_str()).collect())
+            .unwrap_or_default();
+        match ids {
+            Some(ids) if is_member_removal && !ids.is_empty() => {
+                for id in ids {
+                    let path = format!("members[value eq {}]", Value::String(id.to_string()));
+                    rewritten.push(json!({"op": "remove", "path": path}));
+                }
+            }
+            _ => rewritten.push(operation),
+        }
+    }
+    *operations = rewritten;
+}
+
+/// Brings a list response envelope into the `ListResponse` form of RFC 7644, section 3.4.2: member names
+/// get their standard casing, numeric strings become numbers, and a missing `schemas`, `Resources` or
+/// paging member is filled in from the page itself.
+pub(crate) fn normalize_list_envelope(response: &mut Value) {
+    let Some(object) = response.as_object_mut() else {
+        return;
+    };
+    for name in [
+        "schemas",
+        "Resources",
+        "totalResults",
+        "itemsPerPage",
+        "startIndex",
+    ] {
+        let key = object
+            .keys()
+            .find(|k| k.as_str() != name && k.eq_ignore_ascii_case(name))
+            .cloned();
+        if let Some(value) = key.and_then(|key| object.remove(&key)) {
+            object.entry(name).or_insert(value);
+        }
+    }
+    let resources = object
+        .entry("Resources")
+        .or_insert_with(|| Value::Array(Vec::new()));
+    let count = resources.as_array().map_or(0, Vec::len);
+    for (name, default) in [
+        ("totalResults", count),
+        ("itemsPerPage", count),
+        ("startIndex", 1),
+    ] {
+        let value = object.entry(name).or_insert_with(|| json!(default));
+        if let Some(number) = value.as_str().and_then(|s| s.trim().parse::<u64>().ok()) {
+            *value = json!(number);
+        }
+    }
+    object
+        .entry("schemas")
+        .or_insert_with(|| json!([LIST_RESPONSE_URN]));
+}
+
+/// Returns whether a value looks like an e-mail address (`local@domain.tld`).
+pub(crate) fn is_email(value: &str) -> bool {
+    match value.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !value.contains(char::is_whitespace)
+        }
+        None => false,
+    }
+}
+
+pub(crate) fn operations(patch: &mut Value) -> impl Iterator<Item = &mut Map<String, Value>> {
+    patch
+        .get_mut("Operations")
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_object_mut)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_in_list_envelopes() {
+        let mut response = json!({"resources": [{"id": "2819c223"}], "totalresults": "12"});
+        normalize_list_envelope(&mut response);
+        assert_eq!(
+            response,
+            json!({
+                "schemas": [LIST_RESPONSE_URN],
+                "Resources": [{"id": "2819c223"}],
+                "totalResults": 12,
+                "itemsPerPage": 1,
+                "startIndex": 1
+            })
+        );
+    }
+
+    #[test]
+    fn splits_member_removals_into_filtered_paths() {
+        let mut patch = json!({"Operations": [
+            {"op": "Remove", "path": "members", "value": [{"value": "2819c223"}, {"value": "902c246b"}]},
+            {"op": "remove", "path": "members"},
+            {"op": "add", "path": "members", "value": [{"value": "e9e30dba"}]}
+        ]});
+        split_member_removals(&mut patch);
+        assert_eq!(
+            patch["Operations"],
+            json!([
+                {"op": "remove", "path": "members[value eq \"2819c223\"]"},
+                {"op": "remove", "path": "members[value eq \"902c246b\"]"},
+                {"op": "remove", "path": "members"},
+                {"op": "add", "path": "members", "value": [{"value": "e9e30dba"}]}
+            ])
+        );
+    }
+}
@@ -0,0 +1,295 @@
+//! Normalization for Microsoft Entra ID provisioning payloads.
+
+use serde_json::{Map, Value};
+
+use crate::compat::common::{lowercase_ops, operations, split_member_removals};
+use crate::models::schema_coerce::coerce_resource;
+use crate::models::schema_uri::{ENTERPRISE_USER_URN, GROUP_URN, USER_URN};
+use crate::models::scim_schema::MergedSchema;
+
+pub(crate) fn normalize_resource(resource: &mut Value, schema: Option<&MergedSchema>) {
+    let Some(object) = resource.as_object_mut() else {
+        return;
+    };
+    for key in ["userName", "externalId"] {
+        if let Some(value) = object.get_mut(key) {
+            stringify_number(value);
+        }
+    }
+    if let Some(roles) = object.get_mut("roles") {
+        normalize_roles(roles);
+    }
+    if let Some(schema) = schema {
+        coerce_resource(resource, schema);
+    }
+}
+
+pub(crate) fn normalize_patch(patch: &mut Value, schema: Option<&MergedSchema>) {
+    lowercase_ops(patch);
+    split_member_removals(patch);
+    for operation in operations(patch) {
+        let path = operation
+            .get("path")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        let Some(value) = operation.get_mut("value") else {
+            continue;
+        };
+        match path {
+            None => {
+                if let Value::Object(attributes) = value {
+                    *attributes = expand_paths(std::mem::take(attributes), schema);
+                }
+                normalize_resource(value, schema);
+            }
+            Some(path) => normalize_path_value(value, &path, schema),
+        }
+    }
+}
+
+// Entra sends PATCH values without a `path` as flat maps keyed by attribute paths, e.g.
+// `{"name.givenName": "Barbara", "urn:...:enterprise:2.0:User:department": "Sales"}`. Extension objects in
+// the standard nested form (`{"urn:...:enterprise:2.0:User": {"department": "Sales"}}`) are kept as they are.
+fn expand_paths(
+    attributes: Map<String, Value>,
+    schema: Option<&MergedSchema>,
+) -> Map<String, Value> {
+    let mut expanded = Map::new();
+    for (key, value) in attributes {
+        let extension = known_urns(schema)
+            .into_iter()
+            .find(|urn| urn.eq_ignore_ascii_case(&key));
+        if let (Some(urn), Value::Object(members)) = (extension, &value) {
+            if let Some(target) = expanded
+                .entry(urn.to_string())
+                .or_insert_with(|| Value::Object(Map::new()))
+                .as_object_mut()
+            {
+                target.extend(members.clone());
+            }
+            continue;
+        }
+        let (urn, path) = split_urn(&key, schema);
+        let target = match urn {
+            Some(urn) => expanded
+                .entry(urn.to_string())
+                .or_insert_with(|| Value::Object(Map::new()))
+                .as_object_mut(),
+            None => Some(&mut expanded),
+        };
+        let Some(target) = target else {
+            continue;
+        };
+        match path.split_once('.') {
+            Some((parent, child)) => {
+                if let Some(parent) = target
+                    .entry(parent.to_string())
+                    .or_insert_with(|| Value::Object(Map::new()))
+                    .as_object_mut()
+                {
+                    parent.insert(child.to_string(), value);
+                }
+            }
+            None => {
+                target.insert(path.to_string(), value);
+            }
+        }
+    }
+    expanded
+}
+
+/// Splits a URN-qualified attribute path into the extension URN and the attribute path. Paths qualified with
+/// the base schema URN are returned unqualified.
+///
+/// The known schema URNs are matched first, since URNs contain colons themselves; only a URN the schema does
+/// not know is taken to end at the last colon.
+fn split_urn<'a>(key: &'a str, schema: Option<&MergedSchema>) -> (Option<&'a str>, &'a str) {
+    let is_urn = key.get(..4).is_some_and(|s| s.eq_ignore_ascii_case("urn:"));
+    if !is_urn {
+        return (None, key);
+    }
+    let known = known_urns(schema).into_iter().find_map(|urn| {
+        let path = key.get(urn.len()..)?.strip_prefix(':')?;
+        key[..urn.len()]
+            .eq_ignore_ascii_case(urn)
+            .then_some((&key[..urn.len()], path))
+    });
+    match known.or_else(|| key.rsplit_once(':')) {
+        Some((urn, path)) => {
+            let base = schema.is_some_and(|s| s.id.eq_ignore_ascii_case(urn))
+                || urn.eq_ignore_ascii_case(USER_URN)
+                || urn.eq_ignore_ascii_case(GROUP_URN);
+            if base {
+                (None, path)
+            } else {
+                (Some(urn), path)
+            }
+        }
+        None => (None, key),
+    }
+}
+
+// The schema URNs of the resource, or the built-in ones without a schema.
+fn known_urns(schema: Option<&MergedSchema>) -> Vec<&str> {
+    match schema {
+        Some(schema) => schema.schemas.iter().map(String::as_str).collect(),
+        None => vec![USER_URN, ENTERPRISE_USER_URN, GROUP_URN],
+    }
+}
+
+fn normalize_path_value(value: &mut Value, path: &str, schema: Option<&MergedSchema>) {
+    // Value filters (`emails[type eq "work"].value`) do not affect the attribute's type.
+    let mut attribute_path = String::new();
+    let mut depth = 0;
+    for c in path.chars() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            c if depth == 0 => attribute_path.push(c),
+            _ => {}
+        }
+    }
+    let (urn, attribute_path) = split_urn(&attribute_path, schema);
+    if attribute_path.eq_ignore_ascii_case("roles") {
+        normalize_roles(value);
+    }
+
+    // Coerce the value by wrapping it into a resource fragment, then unwrap it again.
+    let Some(schema) = schema else {
+        return;
+    };
+    let segments: Vec<&str> = urn.into_iter().chain(attribute_path.split('.')).collect();
+    let mut fragment = value.take();
+    for segment in segments.iter().rev() {
+        let mut object = Map::new();
+        object.insert(segment.to_string(), fragment);
+        fragment = Value::Object(object);
+    }
+    coerce_resource(&mut fragment, schema);
+    for (i, segment) in segments.iter().enumerate() {
+        fragment = match fragment {
+            Value::Object(mut object) => object.remove(*segment).unwrap_or(Value::Null),
+            other => other,
+        };
+        // Multi-valued parents were wrapped in an array by the coercion; only the leaf keeps its shape.
+        if i + 1 < segments.len() {
+            if let Value::Array(mut values) = fragment {
+                fragment = values.pop().unwrap_or(Value::Null);
+            }
+        }
+    }
+    *value = fragment;
+}
+
+// App role assignments arrive as a single object, and their `value` is sometimes the JSON-encoded role
+// (`{"id": "...", "displayName": "..."}`) rather than the role identifier.
+fn normalize_roles(roles: &mut Value) {
+    if roles.is_object() {
+        *roles = Value::Array(vec![roles.take()]);
+    }
+    for role in roles.as_array_mut().into_iter().flatten() {
+        let Some(role) = role.as_object_mut() else {
+            continue;
+        };
+        let encoded = role
+            .get("value")
+            .and_then(Value::as_str)
+            .and_then(|value| serde_json::from_str::<Map<String, Value>>(value).ok());
+        if let Some(encoded) = encoded {
+            if let Some(id) = encoded.get("id").or_else(|| encoded.get("value")) {
+                role.insert("value".to_string(), id.clone());
+            }
+            if let Some(display) = encoded.get("displayName") {
+                role.entry("display").or_insert_with(|| display.clone());
+            }
+        }
+        if let Some(Value::String(primary)) = role.get("primary") {
+            if primary.eq_ignore_ascii_case("true") || primary.eq_ignore_ascii_case("false") {
+                let primary = primary.eq_ignore_ascii_case("true");
+                role.insert("primary".to_string(), Value::Bool(primary));
+            }
+        }
+    }
+}
+
+fn stringify_number(value: &mut Value) {
+    if let Value::Number(n) = value {
+        *value = Value::String(n.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::scim_schema::get_schemas;
+
+    fn user_schema() -> MergedSchema {
+        let schemas = get_schemas(vec!["user", "enterprise_user"]).unwrap();
+        schemas[0].merge_extensions(&schemas[1..])
+    }
+
+    #[test]
+    fn expands_pathless_patch_values() {
+        let schema = user_schema();
+        let mut patch = json!({"Operations": [{
+            "op": "Add",
+            "value": {
+                "name.givenName": "Barbara",
+                "active": "True",
+                "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:employeeNumber": 701984,
+                "urn:ietf:params:scim:schemas:core:2.0:User:title": "Tour Guide"
+            }
+        }]});
+        normalize_patch(&mut patch, Some(&schema));
+        assert_eq!(
+            patch["Operations"][0],
+            json!({"op": "add", "value": {
+                "name": {"givenName": "Barbara"},
+                "active": true,
+                "title": "Tour Guide",
+                ENTERPRISE_USER_URN: {"employeeNumber": "701984"}
+            }})
+        );
+    }
+
+    #[test]
+    fn keeps_nested_extension_objects() {
+        let schema = user_schema();
+        for schema in [Some(&schema), None] {
+            let mut patch = json!({"Operations": [{
+                "op": "Replace",
+                "value": {
+                    "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User": {"department": "Sales"},
+                    "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:costCenter": "4130"
+                }
+            }]});
+            normalize_patch(&mut patch, schema);
+            assert_eq!(
+                patch["Operations"][0]["value"],
+                json!({ENTERPRISE_USER_URN: {"department": "Sales", "costCenter": "4130"}})
+            );
+        }
+    }
+
+    #[test]
+    fn normalizes_roles_and_filtered_paths() {
+        let schema = user_schema();
+        let mut patch = json!({"Operations": [
+            {
+                "op": "Add",
+                "path": "roles",
+                "value": {"primary": "True", "type": "WindowsAzureActiveDirectoryRole",
+                          "value": "{\"id\":\"7b2c\",\"displayName\":\"Admin\"}"}
+            },
+            {"op": "Replace", "path": "emails[type eq \"work\"].primary", "value": "False"}
+        ]});
+        normalize_patch(&mut patch, Some(&schema));
+        assert_eq!(
+            patch["Operations"][0]["value"],
+            json!([{"primary": true, "type": "WindowsAzureActiveDirectoryRole", "value": "7b2c", "display": "Admin"}])
+        );
+        assert_eq!(patch["Operations"][1]["value"], json!(false));
+    }
+}
@@ -0,0 +1,92 @@
+//! Outbound constraints and inbound fixes for GitHub Enterprise Managed Users provisioning.
+
+use serde_json::Value;
+
+use crate::compat::common::normalize_list_envelope;
+use crate::utils::error::SCIMError;
+
+pub(crate) fn validate_outbound(resource_type: &str, resource: &Value) -> Result<(), SCIMError> {
+    if resource_type.eq_ignore_ascii_case("Group") {
+        // Groups are linked to the identity provider's groups through externalId, which GitHub requires.
+        return ["displayName", "externalId"]
+            .into_iter()
+            .try_for_each(|attribute| require_text(resource, attribute));
+    }
+    if !resource_type.eq_ignore_ascii_case("User") {
+        return Ok(());
+    }
+    ["userName", "displayName"]
+        .into_iter()
+        .try_for_each(|attribute| require_text(resource, attribute))?;
+    let emails = resource
+        .get("emails")
+        .and_then(Value::as_array)
+        .filter(|emails| !emails.is_empty())
+        .ok_or_else(|| SCIMError::MissingRequiredField("emails".into()))?;
+    let primaries = emails
+        .iter()
+        .filter(|email| email.get("primary") == Some(&Value::Bool(true)))
+        .count();
+    if primaries != 1 {
+        return Err(SCIMError::InvalidFieldValue(
+            format!(
+                "exactly one e-mail address must be primary, found {}",
+                primaries
+            )
+            .into(),
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) fn normalize_list_response(response: &mut Value) {
+    normalize_list_envelope(response);
+}
+
+fn require_text(resource: &Value, attribute: &str) -> Result<(), SCIMError> {
+    let value = resource.get(attribute).and_then(Value::as_str);
+    if value.is_none_or(|v| v.trim().is_empty()) {
+        return Err(SCIMError::MissingRequiredField(attribute.into()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn users_need_a_display_name_and_one_primary_email() {
+        let mut user = json!({
+            "userName": "bjensen",
+            "displayName": "Barbara Jensen",
+            "emails": [{"value": "bjensen@example.com", "primary": true}, {"value": "babs@example.com"}]
+        });
+        assert!(validate_outbound("User", &user).is_ok());
+
+        user["emails"][1]["primary"] = json!(true);
+        assert!(matches!(
+            validate_outbound("User", &user),
+            Err(SCIMError::InvalidFieldValue(_))
+        ));
+
+        user.as_object_mut().unwrap().remove("displayName");
+        assert_eq!(
+            validate_outbound("User", &user).unwrap_err().to_string(),
+            SCIMError::MissingRequiredField("displayName".into()).to_string()
+        );
+    }
+
+    #[test]
+    fn groups_need_an_external_id() {
+        let group = json!({"displayName": "Tour Guides"});
+        assert_eq!(
+            validate_outbound("Group", &group).unwrap_err().to_string(),
+            SCIMError::MissingRequiredField("externalId".into()).to_string()
+        );
+        let group = json!({"displayName": "Tour Guides", "externalId": "8c1f36b0"});
+        assert!(validate_outbound("Group", &group).is_ok());
+    }
+}
@@ -0,0 +1,64 @@
+//! Outbound constraints of Google Cloud Identity and Google Workspace.
+
+use serde_json::Value;
+
+use crate::compat::common::is_email;
+use crate::utils::error::SCIMError;
+
+pub(crate) fn validate_outbound(resource_type: &str, resource: &Value) -> Result<(), SCIMError> {
+    if !resource_type.eq_ignore_ascii_case("User") {
+        return Ok(());
+    }
+    // Google identifies users by their primary e-mail address and rejects users without a full name.
+    let user_name = resource
+        .get("userName")
+        .and_then(Value::as_str)
+        .ok_or_else(|| SCIMError::MissingRequiredField("userName".into()))?;
+    if !is_email(user_name) {
+        return Err(SCIMError::InvalidFieldValue(
+            format!("userName '{}' must be an e-mail address", user_name).into(),
+        ));
+    }
+    for sub_attribute in ["givenName", "familyName"] {
+        let value = resource
+            .get("name")
+            .and_then(|name| name.get(sub_attribute))
+            .and_then(Value::as_str);
+        if value.is_none_or(|v| v.trim().is_empty()) {
+            return Err(SCIMError::MissingRequiredField(
+                format!("name.{}", sub_attribute).into(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn users_need_an_email_user_name_and_a_full_name() {
+        let user = json!({"userName": "bjensen@example.com", "name": {"givenName": "Barbara", "familyName": "Jensen"}});
+        assert!(validate_outbound("User", &user).is_ok());
+
+        let user = json!({"userName": "bjensen", "name": {"givenName": "Barbara", "familyName": "Jensen"}});
+        assert!(matches!(
+            validate_outbound("User", &user),
+            Err(SCIMError::InvalidFieldValue(_))
+        ));
+
+        let user = json!({"userName": "bjensen@example.com", "name": {"givenName": "Barbara"}});
+        assert_eq!(
+            validate_outbound("User", &user).unwrap_err().to_string(),
+            SCIMError::MissingRequiredField("name.familyName".into()).to_string()
+        );
+    }
+
+    #[test]
+    fn other_resource_types_are_not_constrained() {
+        assert!(validate_outbound("Group", &json!({"displayName": "Tour Guides"})).is_ok());
+    }
+}
@@ -0,0 +1,52 @@
+//! Normalization for OneLogin provisioning payloads.
+
+use serde_json::Value;
+
+use crate::compat::common::{lowercase_ops, operations, split_member_removals};
+use crate::models::schema_coerce::{canonicalize_attribute_names, coerce_resource};
+use crate::models::scim_schema::MergedSchema;
+
+pub(crate) fn normalize_resource(resource: &mut Value, schema: Option<&MergedSchema>) {
+    if let Some(schema) = schema {
+        canonicalize_attribute_names(resource, schema);
+        coerce_resource(resource, schema);
+    }
+}
+
+pub(crate) fn normalize_patch(patch: &mut Value, schema: Option<&MergedSchema>) {
+    lowercase_ops(patch);
+    split_member_removals(patch);
+    for operation in operations(patch) {
+        if operation.get("path").is_none() {
+            if let Some(value) = operation.get_mut("value") {
+                normalize_resource(value, schema);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::scim_schema::get_schemas;
+
+    #[test]
+    fn normalizes_casing_and_membership_removals() {
+        let schemas = get_schemas(vec!["group"]).unwrap();
+        let schema = schemas[0].merge_extensions(&[]);
+        let mut patch = json!({"Operations": [
+            {"op": "Replace", "value": {"DisplayName": "Tour Guides", "Members": {"Value": "2819c223"}}},
+            {"op": "Remove", "path": "members", "value": [{"value": "902c246b"}]}
+        ]});
+        normalize_patch(&mut patch, Some(&schema));
+        assert_eq!(
+            patch["Operations"],
+            json!([
+                {"op": "replace", "value": {"displayName": "Tour Guides", "members": [{"value": "2819c223"}]}},
+                {"op": "remove", "path": "members[value eq \"902c246b\"]"}
+            ])
+        );
+    }
+}
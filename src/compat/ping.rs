@@ -0,0 +1,113 @@
+//! Normalization for PingOne and PingFederate SCIM endpoints.
+
+use serde_json::{Value, json};
+
+use crate::compat::common::{lowercase_ops, operations};
+use crate::models::schema_uri::{GROUP_URN, USER_URN};
+use crate::models::scim_schema::MergedSchema;
+
+/// The largest `count` Ping serves in one page of a query.
+pub(crate) const MAX_PAGE_SIZE: i64 = 200;
+
+pub(crate) fn normalize_patch(patch: &mut Value, schema: Option<&MergedSchema>) {
+    lowercase_ops(patch);
+    // Ping qualifies paths of core attributes with the base schema URN
+    // (`urn:ietf:params:scim:schemas:core:2.0:User:name.givenName`).
+    for operation in operations(patch) {
+        if let Some(Value::String(path)) = operation.get_mut("path") {
+            if let Some(unqualified) = strip_base_urn(path, schema) {
+                *path = unqualified;
+            }
+        }
+    }
+}
+
+// Ping rejects operations without a `path`, so an operation replacing several attributes at once is split
+// into one operation per attribute. Extension attributes get URN-qualified paths.
+pub(crate) fn prepare_patch(patch: &mut Value) {
+    let Some(operations) = patch.get_mut("Operations").and_then(Value::as_array_mut) else {
+        return;
+    };
+    let mut rewritten = Vec::with_capacity(operations.len());
+    for operation in operations.drain(..) {
+        let pathless = operation.get("path").is_none_or(Value::is_null);
+        match (pathless, &operation["value"]) {
+            (true, Value::Object(attributes)) => {
+                let op = operation["op"].clone();
+                for (name, value) in attributes {
+                    match value {
+                        Value::Object(extension) if is_urn(name) => {
+                            for (attribute, value) in extension {
+                                let path = format!("{}:{}", name, attribute);
+                                rewritten.push(json!({"op": op, "path": path, "value": value}));
+                            }
+                        }
+                        value => rewritten.push(json!({"op": op, "path": name, "value": value})),
+                    }
+                }
+            }
+            _ => rewritten.push(operation),
+        }
+    }
+    *operations = rewritten;
+}
+
+fn strip_base_urn(path: &str, schema: Option<&MergedSchema>) -> Option<String> {
+    let (urn, attribute) = path
+        .split_once('[')
+        .map_or(path, |(head, _)| head)
+        .rsplit_once(':')?;
+    let base = schema.is_some_and(|s| s.id.eq_ignore_ascii_case(urn))
+        || urn.eq_ignore_ascii_case(USER_URN)
+        || urn.eq_ignore_ascii_case(GROUP_URN);
+    base.then(|| path[urn.len() + 1..].to_string())
+        .filter(|_| !attribute.is_empty())
+}
+
+fn is_urn(name: &str) -> bool {
+    name.get(..4)
+        .is_some_and(|s| s.eq_ignore_ascii_case("urn:"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_base_schema_urns_from_paths() {
+        let mut patch = json!({"Operations": [
+            {"op": "Replace", "path": "urn:ietf:params:scim:schemas:core:2.0:User:name.givenName", "value": "Babs"},
+            {"op": "add", "path": "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:department", "value": "Sales"}
+        ]});
+        normalize_patch(&mut patch, None);
+        assert_eq!(
+            patch["Operations"],
+            json!([
+                {"op": "replace", "path": "name.givenName", "value": "Babs"},
+                {"op": "add", "path": "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:department", "value": "Sales"}
+            ])
+        );
+    }
+
+    #[test]
+    fn splits_pathless_operations() {
+        let mut patch = json!({"Operations": [
+            {"op": "replace", "value": {
+                "active": false,
+                "name": {"givenName": "Babs"},
+                "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User": {"department": "Sales"}
+            }},
+            {"op": "remove", "path": "title"}
+        ]});
+        prepare_patch(&mut patch);
+        assert_eq!(
+            patch["Operations"],
+            json!([
+                {"op": "replace", "path": "active", "value": false},
+                {"op": "replace", "path": "name", "value": {"givenName": "Babs"}},
+                {"op": "replace", "path": "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:department", "value": "Sales"},
+                {"op": "remove", "path": "title"}
+            ])
+        );
+    }
+}
@@ -0,0 +1,434 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde_json::Value;
+
+use crate::compat::{entra, github, google, onelogin, ping, salesforce, slack};
+use crate::models::others::ListQuery;
+use crate::models::schema_registry::read_global;
+use crate::models::scim_schema::MergedSchema;
+use crate::utils::error::SCIMError;
+
+/// A SCIM peer whose dialect deviates from RFC 7643/7644 in known ways.
+///
+/// Service providers select a profile per client connection (typically from configuration) and run inbound
+/// payloads through [`ProviderProfile::normalize_resource`] and [`ProviderProfile::normalize_patch`] before
+/// deserializing them. Clients provisioning into a provider check outbound payloads with
+/// [`ProviderProfile::validate_outbound`], consult [`ProviderProfile::supports_patch`] to pick between
+/// `PATCH` and `PUT` updates, shape requests with [`ProviderProfile::prepare_resource`] and
+/// [`ProviderProfile::prepare_patch`], page queries with [`ProviderProfile::clamp_query`], turn error responses
+/// into a [`SCIMError`] with [`ProviderProfile::parse_error`], and run responses through [`ProviderProfile::normalize_resource`] and
+/// [`ProviderProfile::normalize_list_response`]. All of a provider's quirks are handled in one place, so application code can
+/// stay written against the standard.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::compat::profile::ProviderProfile;
+/// use serde_json::json;
+///
+/// let profile: ProviderProfile = "entra".parse().unwrap();
+/// let mut patch = json!({
+///     "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+///     "Operations": [{"op": "Replace", "path": "active", "value": "False"}]
+/// });
+/// profile.normalize_patch("User", &mut patch);
+/// assert_eq!(patch["Operations"][0], json!({"op": "replace", "path": "active", "value": false}));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ProviderProfile {
+    /// A peer that follows the specification; payloads are left untouched.
+    #[default]
+    Standard,
+    /// Microsoft Entra ID (formerly Azure AD) provisioning.
+    ///
+    /// Entra sends booleans as `"True"`/`"False"` strings, capitalizes PATCH operation names, sends PATCH
+    /// values keyed by dotted or URN-qualified attribute paths instead of nested objects, and delivers app
+    /// role assignments in `roles` as a single object or with a JSON-encoded `value`. It also maps numeric
+    /// directory attributes such as `employeeId` straight into `externalId` and `userName`, which must
+    /// be strings. Group member removals carry the members in `value` instead of a path filter.
+    Entra,
+    /// OneLogin provisioning.
+    ///
+    /// OneLogin does not preserve the casing of attribute names (`UserName`, `Emails[].Value`), capitalizes
+    /// PATCH operation names, sends single values for multi-valued attributes, and removes group members by
+    /// listing them in `value` instead of a path filter.
+    OneLogin,
+    /// Google Cloud Identity and Google Workspace.
+    ///
+    /// Google only partially implements PATCH, so updates should be sent as a `PUT` of the full resource.
+    /// Users are identified by their primary e-mail address, which must be sent as `userName`, and both
+    /// `name.givenName` and `name.familyName` are required.
+    Google,
+    /// GitHub Enterprise Managed Users.
+    ///
+    /// GitHub requires users to have a `displayName` and exactly one primary e-mail address, and groups to
+    /// have an `externalId` linking them to the identity provider's group. List responses are not always
+    /// complete `ListResponse` messages.
+    GitHub,
+    /// Slack.
+    ///
+    /// Slack usernames are at most 21 lowercase letters, digits, periods, hyphens or underscores, and users
+    /// need an e-mail address. Slack's original SCIM API answers in SCIM 1.1, with list responses that lack
+    /// members of the SCIM 2.0 `ListResponse` message.
+    Slack,
+    /// PingOne and PingFederate.
+    ///
+    /// Ping serves at most 200 resources per page, qualifies PATCH paths of core attributes with the base
+    /// schema URN, and rejects PATCH operations without a `path`.
+    Ping,
+    /// Salesforce.
+    ///
+    /// Salesforce rejects User attributes outside the subset it supports (such as `ims` and
+    /// `x509Certificates`), expects the user's profile as its single entitlement and at most one role, and
+    /// requires `userName` to have the form of an e-mail address and `name.familyName`. Its error bodies
+    /// follow SCIM 1.1 or its REST API rather than RFC 7644.
+    Salesforce,
+}
+
+impl ProviderProfile {
+    /// Normalizes an inbound resource representation (a `POST` or `PUT` body, or a provider's response) of
+    /// the given resource type.
+    ///
+    /// The resource type is looked up in the process-wide
+    /// [`SchemaRegistry`](crate::models::schema_registry::SchemaRegistry) so that values can be coerced to
+    /// their declared types; unknown resource types only get the schema-independent fixes.
+    pub fn normalize_resource(self, resource_type: &str, resource: &mut Value) {
+        match self {
+            ProviderProfile::Standard => {}
+            ProviderProfile::Entra => {
+                entra::normalize_resource(resource, merged_schema(resource_type).as_ref())
+            }
+            ProviderProfile::OneLogin => {
+                onelogin::normalize_resource(resource, merged_schema(resource_type).as_ref())
+            }
+            ProviderProfile::Slack => slack::normalize_resource(resource_type, resource),
+            ProviderProfile::Google
+            | ProviderProfile::GitHub
+            | ProviderProfile::Ping
+            | ProviderProfile::Salesforce => {}
+        }
+    }
+
+    /// Normalizes a provider's response to a query (RFC 7644, section 3.4.2) for resources of the given
+    /// resource type into a `ListResponse` message, normalizing every resource in it with
+    /// [`ProviderProfile::normalize_resource`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::compat::profile::ProviderProfile;
+    /// use scim_v2::models::others::ListResponse;
+    /// use serde_json::json;
+    ///
+    /// let mut response = json!({
+    ///     "schemas": ["urn:scim:schemas:core:1.0"],
+    ///     "totalResults": 1,
+    ///     "Resources": [{"schemas": ["urn:scim:schemas:core:1.0"], "id": "W1234567", "userName": "bjensen"}]
+    /// });
+    /// ProviderProfile::Slack.normalize_list_response("User", &mut response);
+    /// let response: ListResponse = serde_json::from_value(response).unwrap();
+    /// assert_eq!(response.items_per_page, 1);
+    /// ```
+    pub fn normalize_list_response(self, resource_type: &str, response: &mut Value) {
+        match self {
+            ProviderProfile::GitHub => github::normalize_list_response(response),
+            ProviderProfile::Slack => slack::normalize_list_response(resource_type, response),
+            _ => {}
+        }
+        for resource in response
+            .get_mut("Resources")
+            .and_then(Value::as_array_mut)
+            .into_iter()
+            .flatten()
+        {
+            self.normalize_resource(resource_type, resource);
+        }
+    }
+
+    /// Normalizes an inbound PATCH request body for a resource of the given resource type.
+    pub fn normalize_patch(self, resource_type: &str, patch: &mut Value) {
+        match self {
+            ProviderProfile::Standard => {}
+            ProviderProfile::Entra => {
+                entra::normalize_patch(patch, merged_schema(resource_type).as_ref())
+            }
+            ProviderProfile::OneLogin => {
+                onelogin::normalize_patch(patch, merged_schema(resource_type).as_ref())
+            }
+            ProviderProfile::Ping => {
+                ping::normalize_patch(patch, merged_schema(resource_type).as_ref())
+            }
+            ProviderProfile::Google
+            | ProviderProfile::GitHub
+            | ProviderProfile::Slack
+            | ProviderProfile::Salesforce => {}
+        }
+    }
+
+    /// Rewrites an outbound resource representation of the given resource type into the form the provider
+    /// expects, before it is checked with [`ProviderProfile::validate_outbound`] and sent in a `POST` or `PUT`
+    /// request.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::compat::profile::ProviderProfile;
+    /// use serde_json::json;
+    ///
+    /// let mut user = json!({
+    ///     "userName": "bjensen@example.com",
+    ///     "name": {"familyName": "Jensen"},
+    ///     "ims": [{"value": "bjensen", "type": "xmpp"}],
+    ///     "entitlements": [{"value": "00e000000000001"}]
+    /// });
+    /// ProviderProfile::Salesforce.prepare_resource("User", &mut user);
+    /// assert!(user.get("ims").is_none());
+    /// assert!(ProviderProfile::Salesforce.validate_outbound("User", &user).is_ok());
+    /// ```
+    pub fn prepare_resource(self, resource_type: &str, resource: &mut Value) {
+        if self == ProviderProfile::Salesforce {
+            salesforce::prepare_resource(resource_type, resource);
+        }
+    }
+
+    /// Rewrites an outbound PATCH request body into the form the provider expects, before it is sent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::compat::profile::ProviderProfile;
+    /// use serde_json::json;
+    ///
+    /// let mut patch = json!({
+    ///     "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+    ///     "Operations": [{"op": "replace", "value": {"active": false, "title": "Tour Guide"}}]
+    /// });
+    /// ProviderProfile::Ping.prepare_patch(&mut patch);
+    /// assert_eq!(patch["Operations"][0], json!({"op": "replace", "path": "active", "value": false}));
+    /// ```
+    pub fn prepare_patch(self, patch: &mut Value) {
+        if self == ProviderProfile::Ping {
+            ping::prepare_patch(patch);
+        }
+    }
+
+    /// Returns the largest number of resources the provider returns in one page of a query, if it has a
+    /// limit below the one the client asks for.
+    pub fn max_page_size(self) -> Option<i64> {
+        match self {
+            ProviderProfile::Ping => Some(ping::MAX_PAGE_SIZE),
+            _ => None,
+        }
+    }
+
+    /// Lowers the `count` of an outbound query to the provider's [page size limit](Self::max_page_size), so
+    /// that paging through the results by `startIndex` does not skip resources.
+    pub fn clamp_query(self, query: &mut ListQuery) {
+        if let Some(max) = self.max_page_size() {
+            query.count = Some(query.count.map_or(max, |count| count.min(max)));
+        }
+    }
+
+    /// Returns whether the provider reliably applies PATCH requests (RFC 7644, section 3.5.2).
+    ///
+    /// When this returns `false`, clients should fetch the resource, apply the changes locally and replace
+    /// it with a `PUT` instead.
+    pub fn supports_patch(self) -> bool {
+        !matches!(self, ProviderProfile::Google)
+    }
+
+    /// Checks an outbound resource representation of the given resource type against the provider's
+    /// constraints, before it is sent in a `POST` or `PUT` request.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::MissingRequiredField` if the provider requires an attribute the resource does not
+    /// have, or `SCIMError::InvalidFieldValue` if it would reject a value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::compat::profile::ProviderProfile;
+    /// use serde_json::json;
+    ///
+    /// let user = json!({"userName": "bjensen", "name": {"givenName": "Barbara", "familyName": "Jensen"}});
+    /// assert!(ProviderProfile::Standard.validate_outbound("User", &user).is_ok());
+    /// assert!(ProviderProfile::Google.validate_outbound("User", &user).is_err());
+    /// ```
+    pub fn validate_outbound(self, resource_type: &str, resource: &Value) -> Result<(), SCIMError> {
+        match self {
+            ProviderProfile::Google => google::validate_outbound(resource_type, resource),
+            ProviderProfile::GitHub => github::validate_outbound(resource_type, resource),
+            ProviderProfile::Slack => slack::validate_outbound(resource_type, resource),
+            ProviderProfile::Salesforce => salesforce::validate_outbound(resource_type, resource),
+            _ => Ok(()),
+        }
+    }
+
+    /// Converts an error response of the provider, with its HTTP status code and JSON body, into a
+    /// [`SCIMError`].
+    ///
+    /// Standard error bodies (RFC 7644, section 3.12) are mapped by status and `scimType`: `404` becomes
+    /// `SCIMError::NotFoundError`, `409` becomes `SCIMError::ConflictError`, and `invalidFilter` and
+    /// `invalidValue` become `SCIMError::InvalidFilter` and `SCIMError::InvalidFieldValue`. Anything else is
+    /// a `SCIMError::RequestError` carrying the error's detail.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::compat::profile::ProviderProfile;
+    /// use scim_v2::utils::error::SCIMError;
+    /// use serde_json::json;
+    ///
+    /// let body = json!([{"errorCode": "DUPLICATE_USERNAME", "message": "Duplicate Username."}]);
+    /// let error = ProviderProfile::Salesforce.parse_error(400, &body);
+    /// assert!(matches!(error, SCIMError::ConflictError(_)));
+    /// ```
+    pub fn parse_error(self, status: u16, body: &Value) -> SCIMError {
+        let parsed = match self {
+            ProviderProfile::Salesforce => salesforce::parse_error(body),
+            _ => None,
+        };
+        parsed.unwrap_or_else(|| standard_error(status, body))
+    }
+}
+
+fn standard_error(status: u16, body: &Value) -> SCIMError {
+    let field = |name: &str| body.get(name).and_then(Value::as_str);
+    let detail = field("detail")
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("HTTP status {}", status));
+    match (status, field("scimType")) {
+        (404, _) => SCIMError::NotFoundError(detail.into()),
+        (409, _) | (_, Some("uniqueness")) => SCIMError::ConflictError(detail.into()),
+        (_, Some("invalidFilter")) => SCIMError::InvalidFilter(detail.into()),
+        (_, Some("invalidValue")) => SCIMError::InvalidFieldValue(detail.into()),
+        _ => SCIMError::RequestError(detail.into()),
+    }
+}
+
+fn merged_schema(resource_type: &str) -> Option<MergedSchema> {
+    read_global().merged_schema(resource_type)
+}
+
+impl fmt::Display for ProviderProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ProviderProfile::Standard => "standard",
+            ProviderProfile::Entra => "entra",
+            ProviderProfile::OneLogin => "onelogin",
+            ProviderProfile::Google => "google",
+            ProviderProfile::GitHub => "github",
+            ProviderProfile::Slack => "slack",
+            ProviderProfile::Ping => "ping",
+            ProviderProfile::Salesforce => "salesforce",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Parses a profile name, as found in configuration files. Names are case-insensitive; `azure` and
+/// `azuread` are accepted for [`ProviderProfile::Entra`], `workspace` and `cloudidentity` for
+/// [`ProviderProfile::Google`], `ghe` and `emu` for [`ProviderProfile::GitHub`], and `pingone`,
+/// `pingfederate` and `pingidentity` for [`ProviderProfile::Ping`].
+impl FromStr for ProviderProfile {
+    type Err = SCIMError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "standard" => Ok(ProviderProfile::Standard),
+            "entra" | "azure" | "azuread" => Ok(ProviderProfile::Entra),
+            "onelogin" => Ok(ProviderProfile::OneLogin),
+            "google" | "workspace" | "cloudidentity" => Ok(ProviderProfile::Google),
+            "github" | "ghe" | "emu" => Ok(ProviderProfile::GitHub),
+            "slack" => Ok(ProviderProfile::Slack),
+            "ping" | "pingone" | "pingfederate" | "pingidentity" => Ok(ProviderProfile::Ping),
+            "salesforce" => Ok(ProviderProfile::Salesforce),
+            _ => Err(SCIMError::InvalidFieldValue(
+                format!("unknown provider profile '{}'", s).into(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn parses_profile_names() {
+        assert_eq!(
+            "AzureAD".parse::<ProviderProfile>().unwrap(),
+            ProviderProfile::Entra
+        );
+        assert_eq!(ProviderProfile::Entra.to_string(), "entra");
+        assert_eq!(
+            "Workspace".parse::<ProviderProfile>().unwrap(),
+            ProviderProfile::Google
+        );
+        assert_eq!(
+            "GitHub".parse::<ProviderProfile>().unwrap(),
+            ProviderProfile::GitHub
+        );
+        assert_eq!(ProviderProfile::Slack.to_string(), "slack");
+        assert!("okta2".parse::<ProviderProfile>().is_err());
+    }
+
+    #[test]
+    fn google_profile_falls_back_to_put() {
+        assert!(!ProviderProfile::Google.supports_patch());
+        assert!(ProviderProfile::Entra.supports_patch());
+    }
+
+    #[test]
+    fn ping_profile_limits_page_size() {
+        let mut query = ListQuery {
+            count: Some(1000),
+            ..ListQuery::default()
+        };
+        ProviderProfile::Ping.clamp_query(&mut query);
+        assert_eq!(query.count, Some(200));
+
+        let mut query = ListQuery {
+            count: Some(50),
+            ..ListQuery::default()
+        };
+        ProviderProfile::Ping.clamp_query(&mut query);
+        assert_eq!(query.count, Some(50));
+        ProviderProfile::Standard.clamp_query(&mut query);
+        assert_eq!(query.count, Some(50));
+    }
+
+    #[test]
+    fn maps_standard_error_bodies() {
+        let body = json!({
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:Error"],
+            "scimType": "invalidFilter",
+            "detail": "Unsupported operator 'xx'",
+            "status": "400"
+        });
+        assert!(matches!(
+            ProviderProfile::Standard.parse_error(400, &body),
+            SCIMError::InvalidFilter(detail) if detail == "Unsupported operator 'xx'"
+        ));
+        assert!(matches!(
+            ProviderProfile::Salesforce.parse_error(404, &json!({})),
+            SCIMError::NotFoundError(_)
+        ));
+        assert!(matches!(
+            ProviderProfile::Entra.parse_error(500, &json!({})),
+            SCIMError::RequestError(detail) if detail == "HTTP status 500"
+        ));
+    }
+
+    #[test]
+    fn standard_profile_leaves_payloads_untouched() {
+        let mut patch = json!({"Operations": [{"op": "Replace", "value": {"active": "False"}}]});
+        let original = patch.clone();
+        ProviderProfile::Standard.normalize_patch("User", &mut patch);
+        assert_eq!(patch, original);
+    }
+}
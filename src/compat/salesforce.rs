@@ -0,0 +1,201 @@
+//! Outbound shaping and error mapping for Salesforce's SCIM API.
+
+use serde_json::Value;
+
+use crate::compat::common::is_email;
+use crate::models::schema_uri::ENTERPRISE_USER_URN;
+use crate::utils::error::SCIMError;
+
+// The User attributes Salesforce accepts; it rejects requests carrying any other attribute.
+const USER_ATTRIBUTES: &[&str] = &[
+    "schemas",
+    "id",
+    "externalId",
+    "userName",
+    "name",
+    "displayName",
+    "nickName",
+    "emails",
+    "phoneNumbers",
+    "addresses",
+    "photos",
+    "userType",
+    "title",
+    "preferredLanguage",
+    "locale",
+    "timezone",
+    "active",
+    "password",
+    "groups",
+    "entitlements",
+    "roles",
+    ENTERPRISE_USER_URN,
+];
+
+pub(crate) fn prepare_resource(resource_type: &str, resource: &mut Value) {
+    if !resource_type.eq_ignore_ascii_case("User") {
+        return;
+    }
+    let Some(object) = resource.as_object_mut() else {
+        return;
+    };
+    object.retain(|name, _| USER_ATTRIBUTES.iter().any(|a| a.eq_ignore_ascii_case(name)));
+    // A Salesforce user has exactly one profile, sent as its entitlement, and at most one role.
+    for attribute in ["entitlements", "roles"] {
+        if let Some(values) = object.get_mut(attribute) {
+            keep_primary(values);
+        }
+    }
+}
+
+pub(crate) fn validate_outbound(resource_type: &str, resource: &Value) -> Result<(), SCIMError> {
+    if !resource_type.eq_ignore_ascii_case("User") {
+        return Ok(());
+    }
+    // Salesforce usernames have the form of an e-mail address, but need not be a deliverable one.
+    let user_name = resource
+        .get("userName")
+        .and_then(Value::as_str)
+        .ok_or_else(|| SCIMError::MissingRequiredField("userName".into()))?;
+    if !is_email(user_name) {
+        return Err(SCIMError::InvalidFieldValue(
+            format!(
+                "userName '{}' must have the form of an e-mail address",
+                user_name
+            )
+            .into(),
+        ));
+    }
+    let family_name = resource
+        .get("name")
+        .and_then(|name| name.get("familyName"))
+        .and_then(Value::as_str);
+    if family_name.is_none_or(|v| v.trim().is_empty()) {
+        return Err(SCIMError::MissingRequiredField("name.familyName".into()));
+    }
+    let profiles = resource
+        .get("entitlements")
+        .and_then(Value::as_array)
+        .map_or(0, Vec::len);
+    match profiles {
+        0 => Err(SCIMError::MissingRequiredField("entitlements".into())),
+        1 => Ok(()),
+        n => Err(SCIMError::InvalidFieldValue(
+            format!("a user has exactly one profile entitlement, found {}", n).into(),
+        )),
+    }
+}
+
+// Salesforce answers with SCIM 1.1 error bodies (`{"Errors": [{"description": ..., "code": ...}]}`) or,
+// from its REST layer, with `[{"errorCode": ..., "message": ...}]`.
+pub(crate) fn parse_error(body: &Value) -> Option<SCIMError> {
+    let entry = match body {
+        Value::Array(entries) => entries.first()?,
+        body => body.get("Errors").and_then(|e| match e {
+            Value::Array(entries) => entries.first(),
+            entry => Some(entry),
+        })?,
+    };
+    let message = entry
+        .get("message")
+        .or_else(|| entry.get("description"))
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let code = entry.get("errorCode").and_then(Value::as_str);
+    let status = entry.get("code").and_then(|code| match code {
+        Value::Number(n) => n.as_u64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    });
+    Some(match (code, status) {
+        (Some("REQUIRED_FIELD_MISSING"), _) => SCIMError::MissingRequiredField(message.into()),
+        (Some("DUPLICATE_USERNAME" | "DUPLICATE_VALUE" | "DUPLICATE_EXTERNAL_ID"), _)
+        | (None, Some(409)) => SCIMError::ConflictError(message.into()),
+        (Some("NOT_FOUND" | "ENTITY_IS_DELETED" | "INVALID_CROSS_REFERENCE_KEY"), _)
+        | (None, Some(404)) => SCIMError::NotFoundError(message.into()),
+        (
+            Some(
+                "INVALID_FIELD"
+                | "INVALID_EMAIL_ADDRESS"
+                | "INVALID_OR_NULL_FOR_RESTRICTED_PICKLIST"
+                | "FIELD_INTEGRITY_EXCEPTION"
+                | "STRING_TOO_LONG",
+            ),
+            _,
+        ) => SCIMError::InvalidFieldValue(message.into()),
+        _ => SCIMError::RequestError(message.into()),
+    })
+}
+
+// Reduces a multi-valued attribute to its primary (or first) value, marked primary.
+fn keep_primary(values: &mut Value) {
+    let Value::Array(entries) = values else {
+        return;
+    };
+    if entries.is_empty() {
+        return;
+    }
+    let index = entries
+        .iter()
+        .position(|e| e.get("primary") == Some(&Value::Bool(true)))
+        .unwrap_or(0);
+    let mut entry = entries.swap_remove(index);
+    if let Some(object) = entry.as_object_mut() {
+        object.insert("primary".to_string(), Value::Bool(true));
+    }
+    *entries = vec![entry];
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn keeps_supported_attributes_and_one_profile() {
+        let mut user = json!({
+            "userName": "bjensen@example.com",
+            "name": {"familyName": "Jensen"},
+            "ims": [{"value": "bjensen", "type": "xmpp"}],
+            "x509Certificates": [{"value": "MIIDQzCCAqygAwIBAgICEAAwDQYJKoZIhvcNAQEFBQAw"}],
+            "entitlements": [{"value": "00e000000000001"}, {"value": "00e000000000002", "primary": true}],
+            "roles": [{"value": "00E000000000001"}]
+        });
+        prepare_resource("User", &mut user);
+        assert!(user.get("ims").is_none());
+        assert!(user.get("x509Certificates").is_none());
+        assert_eq!(
+            user["entitlements"],
+            json!([{"value": "00e000000000002", "primary": true}])
+        );
+        assert_eq!(
+            user["roles"],
+            json!([{"value": "00E000000000001", "primary": true}])
+        );
+        assert!(validate_outbound("User", &user).is_ok());
+
+        user.as_object_mut().unwrap().remove("entitlements");
+        assert!(matches!(
+            validate_outbound("User", &user),
+            Err(SCIMError::MissingRequiredField(_))
+        ));
+    }
+
+    #[test]
+    fn maps_error_bodies() {
+        let body = json!([{"errorCode": "DUPLICATE_USERNAME", "message": "Duplicate Username."}]);
+        assert!(
+            matches!(parse_error(&body), Some(SCIMError::ConflictError(m)) if m == "Duplicate Username.")
+        );
+
+        let body = json!({"Errors": [{"description": "User not found", "code": 404}]});
+        assert!(matches!(
+            parse_error(&body),
+            Some(SCIMError::NotFoundError(_))
+        ));
+
+        assert!(parse_error(&json!({"detail": "nope"})).is_none());
+    }
+}
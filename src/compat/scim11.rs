@@ -0,0 +1,330 @@
+//! Conversion between SCIM 1.1 and SCIM 2.0 payloads.
+//!
+//! Gateways that front legacy SCIM 1.1 targets convert every payload on the way through: requests are
+//! downgraded before they are forwarded, and responses are upgraded before they are handed back to SCIM 2.0
+//! clients. The two versions share most attribute names; the differences handled here are the schema URIs
+//! (a single core URI in 1.1, which some implementations send as a plain string instead of an array), the
+//! enterprise `manager` sub-attributes, the `meta` attributes, list response schemas and the error format.
+
+use serde_json::{Map, Value};
+
+use crate::models::errors::ScimHttpError;
+use crate::models::schema_uri::{ENTERPRISE_USER_URN, GROUP_URN, LIST_RESPONSE_URN, USER_URN};
+use crate::utils::error::SCIMError;
+
+/// Schema URI of the SCIM 1.1 core resources, shared by Users and Groups.
+pub const SCIM11_CORE_URN: &str = "urn:scim:schemas:core:1.0";
+/// Schema URI of the SCIM 1.1 Enterprise User extension.
+pub const SCIM11_ENTERPRISE_USER_URN: &str = "urn:scim:schemas:extension:enterprise:1.0";
+
+/// Converts a SCIM 1.1 User or Group representation into its SCIM 2.0 form, in place.
+///
+/// SCIM 1.1 uses one core schema URI for all resource types, so the resource type (`User` or `Group`) has to
+/// be given to pick the SCIM 2.0 schema URI. `meta.resourceType` is filled in and the 1.1-only
+/// `meta.attributes` list is dropped.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::compat::scim11::upgrade_resource;
+/// use scim_v2::models::user::User;
+/// use serde_json::json;
+///
+/// let mut payload = json!({
+///     "schemas": "urn:scim:schemas:core:1.0",
+///     "userName": "bjensen",
+///     "urn:scim:schemas:extension:enterprise:1.0": {"manager": {"managerId": "26118915"}}
+/// });
+/// upgrade_resource("User", &mut payload);
+///
+/// assert_eq!(payload["schemas"][0], "urn:ietf:params:scim:schemas:core:2.0:User");
+/// let user = User::try_from(payload).unwrap();
+/// assert_eq!(user.user_name, "bjensen");
+/// ```
+pub fn upgrade_resource(resource_type: &str, resource: &mut Value) {
+    let Some(object) = resource.as_object_mut() else {
+        return;
+    };
+    let core = if resource_type.eq_ignore_ascii_case("Group") {
+        GROUP_URN
+    } else {
+        USER_URN
+    };
+    rename_schemas(
+        object,
+        &[
+            (SCIM11_CORE_URN, core),
+            (SCIM11_ENTERPRISE_USER_URN, ENTERPRISE_USER_URN),
+        ],
+    );
+    rename_key(object, SCIM11_ENTERPRISE_USER_URN, ENTERPRISE_USER_URN);
+    if let Some(manager) = object
+        .get_mut(ENTERPRISE_USER_URN)
+        .and_then(|extension| extension.get_mut("manager"))
+        .and_then(Value::as_object_mut)
+    {
+        rename_key(manager, "managerId", "value");
+    }
+    if let Some(meta) = object.get_mut("meta").and_then(Value::as_object_mut) {
+        meta.remove("attributes");
+        meta.entry("resourceType")
+            .or_insert_with(|| Value::String(resource_type.to_string()));
+    }
+}
+
+/// Converts a SCIM 2.0 User or Group representation into its SCIM 1.1 form, in place.
+///
+/// The reverse of [`upgrade_resource`]: `meta.resourceType` and the `$ref` sub-attributes, which SCIM 1.1
+/// does not define, are dropped.
+pub fn downgrade_resource(resource: &mut Value) {
+    let Some(object) = resource.as_object_mut() else {
+        return;
+    };
+    rename_schemas(
+        object,
+        &[
+            (USER_URN, SCIM11_CORE_URN),
+            (GROUP_URN, SCIM11_CORE_URN),
+            (ENTERPRISE_USER_URN, SCIM11_ENTERPRISE_USER_URN),
+        ],
+    );
+    rename_key(object, ENTERPRISE_USER_URN, SCIM11_ENTERPRISE_USER_URN);
+    if let Some(manager) = object
+        .get_mut(SCIM11_ENTERPRISE_USER_URN)
+        .and_then(|extension| extension.get_mut("manager"))
+        .and_then(Value::as_object_mut)
+    {
+        rename_key(manager, "value", "managerId");
+        manager.remove("$ref");
+    }
+    for attribute in ["members", "groups"] {
+        for reference in object
+            .get_mut(attribute)
+            .and_then(Value::as_array_mut)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_object_mut)
+        {
+            reference.remove("$ref");
+        }
+    }
+    if let Some(meta) = object.get_mut("meta").and_then(Value::as_object_mut) {
+        meta.remove("resourceType");
+    }
+}
+
+/// Converts a SCIM 1.1 list response into a SCIM 2.0 `ListResponse` message, in place, upgrading every
+/// resource with [`upgrade_resource`].
+pub fn upgrade_list_response(resource_type: &str, response: &mut Value) {
+    let Some(object) = response.as_object_mut() else {
+        return;
+    };
+    object.insert(
+        "schemas".to_string(),
+        Value::Array(vec![Value::String(LIST_RESPONSE_URN.to_string())]),
+    );
+    for resource in object
+        .get_mut("Resources")
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+    {
+        upgrade_resource(resource_type, resource);
+    }
+}
+
+/// Converts a SCIM 2.0 `ListResponse` message into a SCIM 1.1 list response, in place, downgrading every
+/// resource with [`downgrade_resource`].
+pub fn downgrade_list_response(response: &mut Value) {
+    let Some(object) = response.as_object_mut() else {
+        return;
+    };
+    object.insert(
+        "schemas".to_string(),
+        Value::Array(vec![Value::String(SCIM11_CORE_URN.to_string())]),
+    );
+    for resource in object
+        .get_mut("Resources")
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+    {
+        downgrade_resource(resource);
+    }
+}
+
+/// Converts a SCIM 1.1 error response (`{"Errors": [{"description": ..., "code": ...}]}`) into a SCIM 2.0
+/// error.
+///
+/// SCIM 2.0 errors carry a single status, so only the first error is kept.
+///
+/// # Errors
+///
+/// Returns `SCIMError::InvalidJsonFormat` if the value is not a SCIM 1.1 error response.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::compat::scim11::upgrade_error;
+/// use serde_json::json;
+///
+/// let error = upgrade_error(&json!({"Errors": [{"description": "Resource 2819c223 not found", "code": "404"}]})).unwrap();
+/// assert_eq!(error.status, "404");
+/// assert_eq!(error.detail.as_deref(), Some("Resource 2819c223 not found"));
+/// ```
+pub fn upgrade_error(error: &Value) -> Result<ScimHttpError, SCIMError> {
+    let first = error
+        .get("Errors")
+        .and_then(Value::as_array)
+        .and_then(|errors| errors.first())
+        .ok_or(SCIMError::InvalidJsonFormat)?;
+    let status = match first.get("code") {
+        Some(Value::String(code)) => code.clone(),
+        Some(Value::Number(code)) => code.to_string(),
+        _ => "500".to_string(),
+    };
+    Ok(ScimHttpError {
+        detail: first
+            .get("description")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        status,
+        ..Default::default()
+    })
+}
+
+/// Converts a SCIM 2.0 error into a SCIM 1.1 error response. The `scimType` keyword has no SCIM 1.1
+/// equivalent and is dropped.
+pub fn downgrade_error(error: &ScimHttpError) -> Value {
+    let mut entry = Map::new();
+    if let Some(detail) = &error.detail {
+        entry.insert("description".to_string(), Value::String(detail.clone()));
+    }
+    entry.insert("code".to_string(), Value::String(error.status.clone()));
+    let mut response = Map::new();
+    response.insert(
+        "Errors".to_string(),
+        Value::Array(vec![Value::Object(entry)]),
+    );
+    Value::Object(response)
+}
+
+// Replaces schema URIs in `schemas`, which is also normalized into an array.
+fn rename_schemas(object: &mut Map<String, Value>, renames: &[(&str, &str)]) {
+    let schemas = match object.remove("schemas") {
+        Some(Value::String(schema)) => vec![Value::String(schema)],
+        Some(Value::Array(schemas)) => schemas,
+        Some(other) => vec![other],
+        None => return,
+    };
+    let mut renamed: Vec<Value> = Vec::new();
+    for schema in schemas {
+        let schema = match schema {
+            Value::String(uri) => {
+                let uri = renames
+                    .iter()
+                    .find(|(from, _)| from.eq_ignore_ascii_case(&uri))
+                    .map_or(uri, |(_, to)| to.to_string());
+                Value::String(uri)
+            }
+            other => other,
+        };
+        if !renamed.contains(&schema) {
+            renamed.push(schema);
+        }
+    }
+    object.insert("schemas".to_string(), Value::Array(renamed));
+}
+
+fn rename_key(object: &mut Map<String, Value>, from: &str, to: &str) {
+    let key = object
+        .keys()
+        .find(|key| key.eq_ignore_ascii_case(from))
+        .cloned();
+    if let Some(value) = key.and_then(|key| object.remove(&key)) {
+        object.insert(to.to_string(), value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn user_round_trips_between_versions() {
+        let legacy = json!({
+            "schemas": [SCIM11_CORE_URN, SCIM11_ENTERPRISE_USER_URN],
+            "id": "2819c223",
+            "userName": "bjensen",
+            "meta": {"location": "https://example.com/v1/Users/2819c223", "attributes": ["nickName"]},
+            SCIM11_ENTERPRISE_USER_URN: {"manager": {"managerId": "26118915", "displayName": "John Smith"}}
+        });
+        let mut payload = legacy.clone();
+        upgrade_resource("User", &mut payload);
+        assert_eq!(
+            payload,
+            json!({
+                "schemas": [USER_URN, ENTERPRISE_USER_URN],
+                "id": "2819c223",
+                "userName": "bjensen",
+                "meta": {"location": "https://example.com/v1/Users/2819c223", "resourceType": "User"},
+                ENTERPRISE_USER_URN: {"manager": {"value": "26118915", "displayName": "John Smith"}}
+            })
+        );
+
+        downgrade_resource(&mut payload);
+        let mut expected = legacy;
+        expected["meta"]
+            .as_object_mut()
+            .unwrap()
+            .remove("attributes");
+        assert_eq!(payload, expected);
+    }
+
+    #[test]
+    fn list_responses_convert_their_resources() {
+        let mut response = json!({
+            "schemas": [LIST_RESPONSE_URN],
+            "totalResults": 1,
+            "Resources": [{
+                "schemas": [GROUP_URN],
+                "displayName": "Tour Guides",
+                "members": [{"value": "2819c223", "$ref": "https://example.com/v2/Users/2819c223"}]
+            }]
+        });
+        downgrade_list_response(&mut response);
+        assert_eq!(
+            response,
+            json!({
+                "schemas": [SCIM11_CORE_URN],
+                "totalResults": 1,
+                "Resources": [{"schemas": [SCIM11_CORE_URN], "displayName": "Tour Guides", "members": [{"value": "2819c223"}]}]
+            })
+        );
+
+        upgrade_list_response("Group", &mut response);
+        assert_eq!(response["schemas"], json!([LIST_RESPONSE_URN]));
+        assert_eq!(response["Resources"][0]["schemas"], json!([GROUP_URN]));
+    }
+
+    #[test]
+    fn errors_convert_between_formats() {
+        let error = ScimHttpError {
+            scim_type: Some("uniqueness".to_string()),
+            detail: Some("userName is already taken".to_string()),
+            status: "409".to_string(),
+            ..Default::default()
+        };
+        let legacy = downgrade_error(&error);
+        assert_eq!(
+            legacy,
+            json!({"Errors": [{"description": "userName is already taken", "code": "409"}]})
+        );
+        let upgraded = upgrade_error(&legacy).unwrap();
+        assert_eq!(upgraded.status, "409");
+        assert_eq!(upgraded.scim_type, None);
+        assert!(upgrade_error(&json!({"status": "409"})).is_err());
+    }
+}
@@ -0,0 +1,118 @@
+//! Outbound constraints and inbound fixes for Slack's SCIM API.
+
+use serde_json::Value;
+
+use crate::compat::common::normalize_list_envelope;
+use crate::compat::scim11;
+use crate::utils::error::SCIMError;
+
+// Slack usernames are at most 21 characters long.
+const MAX_USER_NAME_LENGTH: usize = 21;
+
+pub(crate) fn validate_outbound(resource_type: &str, resource: &Value) -> Result<(), SCIMError> {
+    if resource_type.eq_ignore_ascii_case("Group") {
+        let display_name = resource.get("displayName").and_then(Value::as_str);
+        if display_name.is_none_or(|v| v.trim().is_empty()) {
+            return Err(SCIMError::MissingRequiredField("displayName".into()));
+        }
+        return Ok(());
+    }
+    if !resource_type.eq_ignore_ascii_case("User") {
+        return Ok(());
+    }
+    let user_name = resource
+        .get("userName")
+        .and_then(Value::as_str)
+        .ok_or_else(|| SCIMError::MissingRequiredField("userName".into()))?;
+    let allowed = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || "._-".contains(c);
+    if user_name.is_empty()
+        || user_name.chars().count() > MAX_USER_NAME_LENGTH
+        || !user_name.chars().all(allowed)
+    {
+        return Err(SCIMError::InvalidFieldValue(format!(
+            "userName '{}' must be at most {} lowercase letters, digits, periods, hyphens or underscores",
+            user_name, MAX_USER_NAME_LENGTH
+        ).into()));
+    }
+    // Slack accounts are keyed by e-mail address.
+    let has_email = resource
+        .get("emails")
+        .and_then(Value::as_array)
+        .is_some_and(|emails| {
+            emails
+                .iter()
+                .any(|e| e.get("value").is_some_and(Value::is_string))
+        });
+    if !has_email {
+        return Err(SCIMError::MissingRequiredField("emails".into()));
+    }
+    Ok(())
+}
+
+pub(crate) fn normalize_resource(resource_type: &str, resource: &mut Value) {
+    // Slack's original SCIM API speaks SCIM 1.1.
+    if is_scim11(resource) {
+        scim11::upgrade_resource(resource_type, resource);
+    }
+}
+
+pub(crate) fn normalize_list_response(resource_type: &str, response: &mut Value) {
+    if is_scim11(response) {
+        scim11::upgrade_list_response(resource_type, response);
+    }
+    normalize_list_envelope(response);
+}
+
+fn is_scim11(payload: &Value) -> bool {
+    let is_scim11_urn = |uri: &Value| {
+        uri.as_str()
+            .is_some_and(|u| u.starts_with("urn:scim:schemas:"))
+    };
+    match payload.get("schemas") {
+        Some(Value::Array(schemas)) => schemas.iter().any(is_scim11_urn),
+        Some(uri) => is_scim11_urn(uri),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::schema_uri::{LIST_RESPONSE_URN, USER_URN};
+
+    #[test]
+    fn user_names_follow_slack_rules() {
+        let user = json!({"userName": "b.jensen", "emails": [{"value": "bjensen@example.com"}]});
+        assert!(validate_outbound("User", &user).is_ok());
+
+        for user_name in ["BJensen", "barbara jensen", "barbara.jensen.tour.guide"] {
+            let user = json!({"userName": user_name, "emails": [{"value": "bjensen@example.com"}]});
+            assert!(matches!(
+                validate_outbound("User", &user),
+                Err(SCIMError::InvalidFieldValue(_))
+            ));
+        }
+
+        let user = json!({"userName": "bjensen"});
+        assert!(matches!(
+            validate_outbound("User", &user),
+            Err(SCIMError::MissingRequiredField(_))
+        ));
+    }
+
+    #[test]
+    fn upgrades_scim11_list_responses() {
+        let mut response = json!({
+            "schemas": ["urn:scim:schemas:core:1.0"],
+            "totalResults": 1,
+            "Resources": [{"schemas": ["urn:scim:schemas:core:1.0"], "id": "W1234567", "userName": "bjensen"}]
+        });
+        normalize_list_response("User", &mut response);
+        assert_eq!(response["schemas"], json!([LIST_RESPONSE_URN]));
+        assert_eq!(response["Resources"][0]["schemas"], json!([USER_URN]));
+        assert_eq!(response["itemsPerPage"], json!(1));
+        assert_eq!(response["startIndex"], json!(1));
+    }
+}
@@ -37,9 +37,10 @@
 //!
 //! ```rust
 //! use scim_v2::models::user::User;
+//! use scim_v2::values;
 //!
 //! let user = User {
-//!     schemas: vec!["urn:ietf:params:scim:schemas:core:2.0:User".to_string()],
+//!     schemas: values!["urn:ietf:params:scim:schemas:core:2.0:User".to_string()],
 //!     user_name: "jdoe@example.com".to_string(),
 //!     // Initialize other fields as necessary...
 //!     ..Default::default()
@@ -77,24 +78,179 @@
 //! ```
 //! For more examples and usage details, refer to the documentation of each function and struct.
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+// Lets code generated by `scim_v2_derive` refer to `::scim_v2` from within this crate as well.
+extern crate self as scim_v2;
+
+#[cfg(feature = "derive")]
+pub use scim_v2_derive::{SchemaAttribute, ScimExtension, ScimSchema};
+
 // Include the schema files into the binary.
 const USER_SCHEMA: &str = include_str!("schemas/user.json");
 const GROUP_SCHEMA: &str = include_str!("schemas/group.json");
 const ENTERPRISE_USER_SCHEMA: &str = include_str!("schemas/enterprise_user.json");
+const PASSWORD_SYNC_SCHEMA: &str = include_str!("schemas/password_sync.json");
 
 /// Declaring the models module which contains various submodules
 pub mod models {
+    #[cfg(feature = "std")]
+    pub mod anonymize;
+    #[cfg(feature = "std")]
+    pub mod attribute_policy;
+    #[cfg(feature = "std")]
+    pub mod audit;
+    #[cfg(feature = "avro")]
+    pub mod avro;
+    #[cfg(feature = "std")]
+    pub mod borrowed;
+    #[cfg(feature = "std")]
+    pub mod capabilities;
+    #[cfg(feature = "std")]
+    pub mod context;
     pub mod enterprise_user;
+    #[cfg(feature = "jose")]
+    pub mod envelope;
     pub mod errors;
+    #[cfg(feature = "std")]
+    pub mod events;
+    pub mod extension;
+    pub mod filter;
     pub mod group;
+    #[cfg(feature = "std")]
+    pub mod group_graph;
+    #[cfg(feature = "std")]
+    pub mod manager_chain;
     pub mod others;
+    pub mod password;
+    pub mod patch;
+    #[cfg(feature = "protobuf")]
+    pub mod protobuf;
+    #[cfg(feature = "std")]
+    pub mod redaction;
+    pub mod resource;
+    pub mod resource_id;
     pub mod resource_types;
+    #[cfg(feature = "std")]
+    pub mod schema_builder;
+    #[cfg(feature = "std")]
+    pub mod schema_coerce;
+    #[cfg(feature = "std")]
+    pub mod schema_diff;
+    pub mod schema_registry;
+    #[cfg(feature = "std")]
+    pub mod schema_sample;
+    pub mod schema_uri;
     pub mod scim_schema;
     pub mod service_provider_config;
+    pub mod tombstone;
     pub mod user;
+    pub mod values;
+}
+
+/// Declaring the cli module which implements the `scim` binary
+#[cfg(feature = "cli")]
+pub mod cli;
+
+/// Declaring the client module which talks to SCIM service providers over HTTP
+#[cfg(feature = "std")]
+pub mod client {
+    pub mod cache;
+    #[cfg(feature = "wasm")]
+    pub mod fetch;
+    pub mod request;
+}
+
+/// Declaring the compat module which normalizes the SCIM dialects of specific providers
+#[cfg(feature = "std")]
+pub mod compat {
+    mod common;
+    mod entra;
+    mod github;
+    mod google;
+    mod onelogin;
+    mod ping;
+    pub mod profile;
+    mod salesforce;
+    pub mod scim11;
+    mod slack;
+}
+
+/// Declaring the mapping module which converts resources to and from other directory formats
+#[cfg(feature = "std")]
+pub mod mapping {
+    pub mod active_directory;
+    mod common;
+    pub mod csv;
+    pub mod graph;
+    pub mod hr;
+    pub mod keycloak;
+    pub mod saml;
+    pub mod vcard;
+}
+
+/// Declaring the provider module which stores and queries resources for a service provider
+#[cfg(feature = "std")]
+pub mod provider {
+    pub mod auditing;
+    pub mod caching;
+    pub(crate) mod common;
+    pub mod compliance;
+    #[cfg(feature = "graphql")]
+    pub mod graphql;
+    pub mod memory;
+    pub mod metered;
+    #[cfg(feature = "postgres")]
+    pub mod postgres;
+    mod query;
+    pub mod redacting;
+    pub mod resource_provider;
+    pub mod scoped;
+    #[cfg(feature = "sqlite")]
+    pub mod sqlite;
+}
+
+/// Declaring the sync module which converges directories for provisioning jobs
+#[cfg(feature = "std")]
+pub mod sync {
+    pub mod id_map;
+    pub mod matcher;
+    pub mod pipeline;
+    pub mod reconcile;
 }
 
-/// Declaring the utils module which contains the error submodule
+/// Declaring the utils module which contains the error submodule and shared helpers
 pub mod utils {
+    #[cfg(feature = "std")]
+    pub mod cache;
+    #[cfg(feature = "std")]
+    pub mod codegen;
+    #[cfg(feature = "std")]
+    pub mod cursor;
+    #[cfg(feature = "std")]
+    pub(crate) mod diff;
     pub mod error;
+    #[cfg(feature = "std")]
+    pub mod etag;
+    pub mod json;
+    pub mod limits;
+    #[cfg(feature = "std")]
+    pub mod metrics;
+    #[cfg(feature = "std")]
+    pub mod ndjson;
+    #[cfg(feature = "std")]
+    pub mod pagination;
+    #[cfg(feature = "rayon")]
+    pub mod parallel;
+    #[cfg(feature = "std")]
+    pub mod rate_limit;
+    #[cfg(feature = "std")]
+    pub mod routing;
+    pub(crate) mod sensitive;
+    #[cfg(feature = "std")]
+    pub mod stream;
+    #[cfg(feature = "std")]
+    pub mod time;
 }
@@ -0,0 +1,436 @@
+//! Mapping between SCIM `User` resources and on-premises Active Directory user attributes.
+
+use std::collections::BTreeMap;
+
+use crate::models::enterprise_user::EnterpriseUser;
+use crate::models::extension::ScimExtension;
+use crate::models::resource_id::ResourceId;
+use crate::models::user::{Address, Email, Group, Name, PhoneNumber, User};
+use crate::models::values::Values;
+use crate::utils::error::SCIMError;
+use crate::values;
+
+/// The attributes of an Active Directory object, as read from or written to LDAP. Every attribute is
+/// multi-valued at the protocol level; attribute names are matched case-insensitively on import.
+pub type AdAttributes = BTreeMap<String, Vec<String>>;
+
+// userAccountControl flags (MS-ADTS, section 2.2.16).
+const ACCOUNT_DISABLE: u32 = 0x2;
+const NORMAL_ACCOUNT: u32 = 0x200;
+
+// sAMAccountName is limited to 20 characters and may not contain these characters.
+const SAM_ACCOUNT_NAME_MAX_LEN: usize = 20;
+const SAM_ACCOUNT_NAME_INVALID: &[char] = &[
+    '"', '/', '\\', '[', ']', ':', ';', '|', '=', ',', '+', '*', '?', '<', '>', '@',
+];
+
+// (SCIM phone number type, AD attribute)
+const PHONE_ATTRIBUTES: [(&str, &str); 4] = [
+    ("work", "telephoneNumber"),
+    ("mobile", "mobile"),
+    ("fax", "facsimileTelephoneNumber"),
+    ("home", "homePhone"),
+];
+
+/// Converts a SCIM user into Active Directory user attributes.
+///
+/// * `userName` becomes the `userPrincipalName`, and `sAMAccountName` is derived from its local part,
+///   stripped of the characters AD rejects and cut to 20 characters.
+/// * The primary e-mail address becomes `mail`; all addresses are listed in `proxyAddresses`, with the
+///   primary one prefixed by `SMTP:` and the others by `smtp:`, as Exchange expects.
+/// * `active` becomes `userAccountControl` (a normal account, disabled when `active` is `false`).
+/// * Group memberships whose `value` is a distinguished name become `memberOf`. AD maintains `memberOf`
+///   itself, so agents write memberships to the groups' `member` attribute instead; the values are
+///   included so that both directions can be compared.
+/// * Names, `title`, `preferredLanguage`, `externalId` (as `objectGUID`), work phone numbers and the first
+///   work address, and the enterprise attributes `employeeNumber`, `department`, `organization` (as
+///   `company`) and `division` map to their AD counterparts.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::mapping::active_directory::to_ad_attributes;
+/// use scim_v2::models::user::{Email, User};
+/// use scim_v2::values;
+///
+/// let user = User {
+///     user_name: "barbara.jensen@example.com".to_string(),
+///     active: Some(false),
+///     emails: Some(values![Email {
+///         value: Some("bjensen@example.com".to_string()),
+///         primary: Some(true),
+///         ..Default::default()
+///     }]),
+///     ..Default::default()
+/// };
+/// let attributes = to_ad_attributes(&user);
+/// assert_eq!(attributes["sAMAccountName"], ["barbara.jensen"]);
+/// assert_eq!(attributes["proxyAddresses"], ["SMTP:bjensen@example.com"]);
+/// assert_eq!(attributes["userAccountControl"], ["514"]);
+/// ```
+pub fn to_ad_attributes(user: &User) -> AdAttributes {
+    let mut attributes = AdAttributes::new();
+    let mut set = |name: &str, value: Option<&String>| {
+        if let Some(value) = value.filter(|v| !v.is_empty()) {
+            attributes.insert(name.to_string(), vec![value.clone()]);
+        }
+    };
+
+    set("userPrincipalName", Some(&user.user_name));
+    set("sAMAccountName", Some(&sam_account_name(&user.user_name)));
+    set("objectGUID", user.external_id.as_ref());
+    set("displayName", user.display_name.as_ref());
+    if let Some(name) = &user.name {
+        set("givenName", name.given_name.as_ref());
+        set("sn", name.family_name.as_ref());
+        set("middleName", name.middle_name.as_ref());
+    }
+    set("title", user.title.as_ref());
+    set("preferredLanguage", user.preferred_language.as_ref());
+    for (kind, name) in PHONE_ATTRIBUTES {
+        let phone = user
+            .phone_numbers
+            .iter()
+            .flatten()
+            .find(|p| p.r#type.as_deref() == Some(kind));
+        set(name, phone.and_then(|p| p.value.as_ref()));
+    }
+    let address = user
+        .addresses
+        .iter()
+        .flatten()
+        .find(|a| a.r#type.as_deref().is_none_or(|t| t == "work"));
+    if let Some(address) = address {
+        set("streetAddress", address.street_address.as_ref());
+        set("l", address.locality.as_ref());
+        set("st", address.region.as_ref());
+        set("postalCode", address.postal_code.as_ref());
+        set("c", address.country.as_ref());
+    }
+    if let Some(enterprise) = &user.enterprise_user {
+        set("employeeNumber", enterprise.employee_number.as_ref());
+        set("department", enterprise.department.as_ref());
+        set("company", enterprise.organization.as_ref());
+        set("division", enterprise.division.as_ref());
+    }
+
+    let emails: Vec<&Email> = user.emails.iter().flatten().collect();
+    let primary = emails
+        .iter()
+        .find(|e| e.primary == Some(true))
+        .or_else(|| emails.first());
+    set("mail", primary.and_then(|e| e.value.as_ref()));
+    let proxy_addresses: Vec<String> = emails
+        .iter()
+        .filter_map(|e| {
+            let value = e.value.as_ref()?;
+            let prefix = if primary.is_some_and(|p| std::ptr::eq(*p, *e)) {
+                "SMTP"
+            } else {
+                "smtp"
+            };
+            Some(format!("{}:{}", prefix, value))
+        })
+        .collect();
+    if !proxy_addresses.is_empty() {
+        attributes.insert("proxyAddresses".to_string(), proxy_addresses);
+    }
+
+    let member_of: Vec<String> = user
+        .groups
+        .iter()
+        .flatten()
+        .filter_map(|g| g.value.as_ref())
+        .map(|dn| dn.as_str().to_string())
+        .filter(|dn| is_distinguished_name(dn))
+        .collect();
+    if !member_of.is_empty() {
+        attributes.insert("memberOf".to_string(), member_of);
+    }
+
+    if let Some(active) = user.active {
+        let flags = if active {
+            NORMAL_ACCOUNT
+        } else {
+            NORMAL_ACCOUNT | ACCOUNT_DISABLE
+        };
+        attributes.insert("userAccountControl".to_string(), vec![flags.to_string()]);
+    }
+    attributes
+}
+
+/// Converts Active Directory user attributes into a SCIM user. This is the reverse of
+/// [`to_ad_attributes`].
+///
+/// The `userName` is the `userPrincipalName`, or the `sAMAccountName` if the object has no UPN. E-mail
+/// addresses are read from `proxyAddresses` (only `smtp:` entries; the `SMTP:` entry is primary), falling back
+/// to `mail`. Each `memberOf` entry becomes a group membership whose `value` is the group's distinguished
+/// name and whose `display` is its common name; agents that know the groups' SCIM ids replace the values.
+///
+/// # Errors
+///
+/// * `SCIMError::MissingRequiredField` - If neither `userPrincipalName` nor `sAMAccountName` is present.
+/// * `SCIMError::InvalidFieldValue` - If `userAccountControl` is not a number.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::mapping::active_directory::{from_ad_attributes, AdAttributes};
+///
+/// let attributes = AdAttributes::from([
+///     ("sAMAccountName".to_string(), vec!["bjensen".to_string()]),
+///     ("proxyAddresses".to_string(), vec!["smtp:babs@example.com".to_string(), "SMTP:bjensen@example.com".to_string()]),
+///     ("memberOf".to_string(), vec!["CN=Tour Guides,OU=Groups,DC=example,DC=com".to_string()]),
+///     ("userAccountControl".to_string(), vec!["66050".to_string()]),
+/// ]);
+/// let user = from_ad_attributes(&attributes).unwrap();
+/// assert_eq!(user.user_name, "bjensen");
+/// assert_eq!(user.active, Some(false));
+/// let emails = user.emails.unwrap();
+/// assert_eq!(emails[1].value.as_deref(), Some("bjensen@example.com"));
+/// assert_eq!(emails[1].primary, Some(true));
+/// assert_eq!(user.groups.unwrap()[0].display.as_deref(), Some("Tour Guides"));
+/// ```
+pub fn from_ad_attributes(attributes: &AdAttributes) -> Result<User, SCIMError> {
+    let get = |name: &str| {
+        attributes
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, values)| values.as_slice())
+            .unwrap_or_default()
+    };
+    let first = |name: &str| get(name).first().cloned();
+
+    let user_name = first("userPrincipalName")
+        .or_else(|| first("sAMAccountName"))
+        .ok_or_else(|| SCIMError::MissingRequiredField("userPrincipalName".into()))?;
+    let mut user = User {
+        user_name,
+        external_id: first("objectGUID"),
+        display_name: first("displayName"),
+        title: first("title"),
+        preferred_language: first("preferredLanguage"),
+        ..Default::default()
+    };
+
+    let (given_name, family_name, middle_name) =
+        (first("givenName"), first("sn"), first("middleName"));
+    if given_name.is_some() || family_name.is_some() || middle_name.is_some() {
+        user.name = Some(Name {
+            given_name,
+            family_name,
+            middle_name,
+            ..Default::default()
+        });
+    }
+
+    let mut emails: Values<Email> = get("proxyAddresses")
+        .iter()
+        .filter_map(|address| {
+            let (prefix, value) = address.split_once(':')?;
+            prefix.eq_ignore_ascii_case("smtp").then(|| Email {
+                value: Some(value.to_string()),
+                primary: (prefix == "SMTP").then_some(true),
+                ..Default::default()
+            })
+        })
+        .collect();
+    if emails.is_empty() {
+        if let Some(mail) = first("mail") {
+            emails.push(Email {
+                value: Some(mail),
+                primary: Some(true),
+                ..Default::default()
+            });
+        }
+    }
+    user.emails = Some(emails).filter(|e| !e.is_empty());
+
+    let phone_numbers: Values<PhoneNumber> = PHONE_ATTRIBUTES
+        .iter()
+        .filter_map(|(kind, name)| {
+            Some(PhoneNumber {
+                value: Some(first(name)?),
+                r#type: Some(kind.to_string()),
+                ..Default::default()
+            })
+        })
+        .collect();
+    user.phone_numbers = Some(phone_numbers).filter(|p| !p.is_empty());
+
+    let address = Address {
+        street_address: first("streetAddress"),
+        locality: first("l"),
+        region: first("st"),
+        postal_code: first("postalCode"),
+        country: first("c"),
+        r#type: Some("work".to_string()),
+        ..Default::default()
+    };
+    let has_address = address.street_address.is_some()
+        || address.locality.is_some()
+        || address.region.is_some()
+        || address.postal_code.is_some()
+        || address.country.is_some();
+    if has_address {
+        user.addresses = Some(values![address]);
+    }
+
+    let groups: Values<Group> = get("memberOf")
+        .iter()
+        .map(|dn| Group {
+            value: Some(ResourceId::new(dn.as_str())),
+            display: common_name(dn),
+            ..Default::default()
+        })
+        .collect();
+    user.groups = Some(groups).filter(|g| !g.is_empty());
+
+    if let Some(flags) = first("userAccountControl") {
+        let flags: u32 = flags.trim().parse().map_err(|_| {
+            SCIMError::InvalidFieldValue(
+                format!("userAccountControl '{}' is not a number", flags).into(),
+            )
+        })?;
+        user.active = Some(flags & ACCOUNT_DISABLE == 0);
+    }
+
+    let enterprise = EnterpriseUser {
+        employee_number: first("employeeNumber").or_else(|| first("employeeID")),
+        department: first("department"),
+        organization: first("company"),
+        division: first("division"),
+        ..Default::default()
+    };
+    let has_enterprise = enterprise.employee_number.is_some()
+        || enterprise.department.is_some()
+        || enterprise.organization.is_some()
+        || enterprise.division.is_some();
+    if has_enterprise {
+        user.schemas.push(EnterpriseUser::URN.to_string());
+        user.enterprise_user = Some(enterprise);
+    }
+    Ok(user)
+}
+
+/// Derives a `sAMAccountName` from a user name: the local part of an e-mail style name, without the
+/// characters AD rejects, cut to 20 characters.
+pub fn sam_account_name(user_name: &str) -> String {
+    let local = user_name.split('@').next().unwrap_or_default();
+    local
+        .chars()
+        .filter(|c| !SAM_ACCOUNT_NAME_INVALID.contains(c) && !c.is_control())
+        .take(SAM_ACCOUNT_NAME_MAX_LEN)
+        .collect::<String>()
+        .trim_end_matches(['.', ' '])
+        .to_string()
+}
+
+fn is_distinguished_name(value: &str) -> bool {
+    value.split_once('=').is_some_and(|(attribute, _)| {
+        !attribute.is_empty() && attribute.chars().all(|c| c.is_ascii_alphanumeric())
+    })
+}
+
+// Returns the value of the first RDN if it is a CN, unescaping `\,`.
+fn common_name(dn: &str) -> Option<String> {
+    let (attribute, rest) = dn.split_once('=')?;
+    if !attribute.trim().eq_ignore_ascii_case("cn") {
+        return None;
+    }
+    let mut name = String::new();
+    let mut chars = rest.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => name.extend(chars.next()),
+            ',' => break,
+            c => name.push(c),
+        }
+    }
+    Some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derives_valid_sam_account_names() {
+        assert_eq!(sam_account_name("bjensen@example.com"), "bjensen");
+        assert_eq!(
+            sam_account_name("barbara.jensen-smith.tours"),
+            "barbara.jensen-smith"
+        );
+        assert_eq!(sam_account_name("o'neil[1]"), "o'neil1");
+        assert_eq!(
+            sam_account_name("babs.jensen.smith.junior"),
+            "babs.jensen.smith.ju"
+        );
+    }
+
+    #[test]
+    fn round_trips_through_ad_attributes() {
+        let user = User {
+            user_name: "bjensen@example.com".to_string(),
+            external_id: Some("5a1e6a7c-0c5c-4e8f-9d5e-1a2b3c4d5e6f".to_string()),
+            name: Some(Name {
+                given_name: Some("Barbara".to_string()),
+                family_name: Some("Jensen".to_string()),
+                ..Default::default()
+            }),
+            active: Some(true),
+            emails: Some(values![
+                Email {
+                    value: Some("bjensen@example.com".to_string()),
+                    primary: Some(true),
+                    ..Default::default()
+                },
+                Email {
+                    value: Some("babs@example.com".to_string()),
+                    ..Default::default()
+                },
+            ]),
+            groups: Some(values![Group {
+                value: Some(ResourceId::new(
+                    "CN=Tour Guides\\, West,OU=Groups,DC=example,DC=com",
+                )),
+                ..Default::default()
+            }]),
+            enterprise_user: Some(EnterpriseUser {
+                department: Some("Tour Operations".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let attributes = to_ad_attributes(&user);
+        assert_eq!(
+            attributes["proxyAddresses"],
+            ["SMTP:bjensen@example.com", "smtp:babs@example.com"]
+        );
+        assert_eq!(attributes["userAccountControl"], ["512"]);
+        assert_eq!(attributes["sn"], ["Jensen"]);
+
+        let imported = from_ad_attributes(&attributes).unwrap();
+        assert_eq!(imported.user_name, "bjensen@example.com");
+        assert_eq!(imported.active, Some(true));
+        assert_eq!(imported.emails.unwrap()[0].primary, Some(true));
+        assert_eq!(
+            imported.groups.unwrap()[0].display.as_deref(),
+            Some("Tour Guides, West")
+        );
+        assert_eq!(
+            imported.enterprise_user.unwrap().department.as_deref(),
+            Some("Tour Operations")
+        );
+    }
+
+    #[test]
+    fn rejects_objects_without_a_user_name() {
+        let attributes =
+            AdAttributes::from([("mail".to_string(), vec!["x@example.com".to_string()])]);
+        assert!(matches!(
+            from_ad_attributes(&attributes),
+            Err(SCIMError::MissingRequiredField(_))
+        ));
+    }
+}
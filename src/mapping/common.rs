@@ -0,0 +1,286 @@
+//! Helpers shared by the mappings.
+
+use serde_json::{Map, Value};
+
+use crate::models::scim_schema::MergedSchema;
+
+/// An attribute path resolved against a resource type's schema, used to write values into and read
+/// values out of a resource representation.
+///
+/// Paths may address an attribute (`userName`), a sub-attribute (`name.givenName`), an extension
+/// attribute (`urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:department`) or one entry of a
+/// multi-valued attribute selected by an `eq` filter (`emails[type eq "work"].value`).
+pub(crate) struct AttributeTarget {
+    // The extension URI the attribute is nested under, if it is not in the base schema.
+    extension: Option<String>,
+    attribute: String,
+    sub_attribute: Option<String>,
+    multi_valued: bool,
+    filter: Option<(String, String)>,
+}
+
+impl AttributeTarget {
+    /// Resolves `path` against `schema`, returning `None` if it names no attribute.
+    pub(crate) fn resolve(schema: &MergedSchema, path: &str) -> Option<Self> {
+        let (path, filter) = split_filter(path)?;
+        let attribute = schema.get(&path)?;
+        let (name, sub_attribute) = match attribute.path.split_once('.') {
+            Some((name, sub)) => (name.to_string(), Some(sub.to_string())),
+            None => (attribute.path.clone(), None),
+        };
+        let parent = schema.get(&format!("{}:{}", attribute.schema, name))?;
+        if filter.is_some() && (sub_attribute.is_none() || !parent.attribute.multi_valued) {
+            return None;
+        }
+        Some(AttributeTarget {
+            extension: (attribute.schema != schema.id).then(|| attribute.schema.clone()),
+            attribute: parent.attribute.name.clone(),
+            sub_attribute: sub_attribute.map(|_| attribute.attribute.name.clone()),
+            multi_valued: parent.attribute.multi_valued,
+            filter,
+        })
+    }
+
+    /// Whether the target takes several values, i.e. it is multi-valued and not narrowed by a filter.
+    pub(crate) fn accepts_several(&self) -> bool {
+        self.multi_valued && self.filter.is_none()
+    }
+
+    /// Writes `values` into `resource`; only the first value is kept unless the target
+    /// [accepts several](Self::accepts_several). Writing to an extension adds its URI to `schemas`.
+    pub(crate) fn set(&self, resource: &mut Map<String, Value>, values: &[&str]) {
+        if values.is_empty() {
+            return;
+        }
+        let object = match &self.extension {
+            Some(uri) => {
+                if let Some(Value::Array(schemas)) = resource.get_mut("schemas") {
+                    if !schemas.iter().any(|s| s == uri) {
+                        schemas.push(Value::String(uri.clone()));
+                    }
+                }
+                resource
+                    .entry(uri.clone())
+                    .or_insert_with(|| Value::Object(Map::new()))
+                    .as_object_mut()
+            }
+            None => Some(resource),
+        };
+        let Some(object) = object else {
+            return;
+        };
+        let strings = values.iter().map(|v| Value::String(v.to_string()));
+        match (&self.sub_attribute, self.multi_valued) {
+            (None, false) => {
+                object.insert(self.attribute.clone(), Value::String(values[0].to_string()));
+            }
+            (None, true) => push_entries(object, &self.attribute, strings),
+            (Some(sub), false) => {
+                if let Some(parent) = object
+                    .entry(self.attribute.clone())
+                    .or_insert_with(|| Value::Object(Map::new()))
+                    .as_object_mut()
+                {
+                    parent.insert(sub.clone(), Value::String(values[0].to_string()));
+                }
+            }
+            (Some(sub), true) => match &self.filter {
+                Some((name, expected)) => {
+                    let entries = object
+                        .entry(self.attribute.clone())
+                        .or_insert_with(|| Value::Array(Vec::new()));
+                    if let Value::Array(entries) = entries {
+                        let position = entries
+                            .iter()
+                            .position(|e| matches_filter(e, name, expected));
+                        let index = position.unwrap_or_else(|| {
+                            let mut entry = Map::new();
+                            entry.insert(name.clone(), Value::String(expected.clone()));
+                            entries.push(Value::Object(entry));
+                            entries.len() - 1
+                        });
+                        if let Some(entry) = entries[index].as_object_mut() {
+                            entry.insert(sub.clone(), Value::String(values[0].to_string()));
+                        }
+                    }
+                }
+                None => {
+                    let entries = strings.map(|value| {
+                        let mut entry = Map::new();
+                        entry.insert(sub.clone(), value);
+                        Value::Object(entry)
+                    });
+                    push_entries(object, &self.attribute, entries);
+                }
+            },
+        }
+    }
+
+    /// Reads the target's values out of `resource` as text. Unless `all` is set, a multi-valued
+    /// attribute yields only its primary (or first) entry.
+    pub(crate) fn get(&self, resource: &Value, all: bool) -> Vec<String> {
+        let object = match &self.extension {
+            Some(uri) => get_ignore_case(resource, uri),
+            None => Some(resource),
+        };
+        let Some(value) = object.and_then(|o| get_ignore_case(o, &self.attribute)) else {
+            return Vec::new();
+        };
+        let entries: Vec<&Value> = match value {
+            Value::Array(entries) => entries.iter().collect(),
+            value => vec![value],
+        };
+        let entries: Vec<&Value> = match &self.filter {
+            Some((name, expected)) => entries
+                .into_iter()
+                .filter(|e| matches_filter(e, name, expected))
+                .take(1)
+                .collect(),
+            None if !all && entries.len() > 1 => {
+                let primary = entries
+                    .iter()
+                    .find(|e| get_ignore_case(e, "primary") == Some(&Value::Bool(true)));
+                primary.or(entries.first()).into_iter().copied().collect()
+            }
+            None => entries,
+        };
+        entries
+            .into_iter()
+            .filter_map(|entry| match &self.sub_attribute {
+                Some(sub) => get_ignore_case(entry, sub),
+                None => Some(entry),
+            })
+            .filter_map(scalar_text)
+            .collect()
+    }
+}
+
+// Splits `emails[type eq "work"].value` into `emails.value` and the filter `(type, work)`.
+fn split_filter(path: &str) -> Option<(String, Option<(String, String)>)> {
+    let Some((attribute, rest)) = path.split_once('[') else {
+        return Some((path.to_string(), None));
+    };
+    let (filter, sub_attribute) = rest.split_once(']')?;
+    let mut parts = filter.trim().splitn(3, ' ');
+    let (name, op, value) = (parts.next()?, parts.next()?, parts.next()?);
+    if !op.eq_ignore_ascii_case("eq") {
+        return None;
+    }
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+    Some((
+        format!("{}{}", attribute, sub_attribute),
+        Some((name.to_string(), value.to_string())),
+    ))
+}
+
+fn push_entries(
+    object: &mut Map<String, Value>,
+    attribute: &str,
+    entries: impl Iterator<Item = Value>,
+) {
+    if let Value::Array(existing) = object
+        .entry(attribute.to_string())
+        .or_insert_with(|| Value::Array(Vec::new()))
+    {
+        existing.extend(entries);
+    }
+}
+
+fn matches_filter(entry: &Value, name: &str, expected: &str) -> bool {
+    get_ignore_case(entry, name)
+        .and_then(Value::as_str)
+        .is_some_and(|v| v.eq_ignore_ascii_case(expected))
+}
+
+/// Returns the values at an attribute path of a resource representation, such as `name.givenName`,
+/// `emails.value` or `urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:department`.
+///
+/// Multi-valued attributes yield one value per entry, with the primary entry first. Attribute names are
+/// matched case-insensitively; nulls are skipped.
+pub(crate) fn values_at<'a>(resource: &'a Value, path: &str) -> Vec<&'a Value> {
+    let (mut current, path) = match path.get(..4) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("urn:") => match path.rsplit_once(':') {
+            Some((urn, path)) => (get_ignore_case(resource, urn).into_iter().collect(), path),
+            None => (vec![], path),
+        },
+        _ => (vec![resource], path),
+    };
+    for segment in path.split('.') {
+        current = current
+            .into_iter()
+            .filter_map(|value| get_ignore_case(value, segment))
+            .flat_map(|value| match value {
+                Value::Array(entries) => {
+                    let mut entries: Vec<&Value> = entries.iter().collect();
+                    entries
+                        .sort_by_key(|e| get_ignore_case(e, "primary") != Some(&Value::Bool(true)));
+                    entries
+                }
+                value => vec![value],
+            })
+            .filter(|value| !value.is_null())
+            .collect();
+    }
+    current
+}
+
+/// Converts a scalar JSON value to its text; objects, arrays and nulls have none.
+pub(crate) fn scalar_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Returns the value of the primary (or first) entry of a multi-valued attribute.
+pub(crate) fn primary_or_first<'a, T>(
+    values: Option<&'a [T]>,
+    primary: impl Fn(&T) -> Option<bool>,
+    value: impl Fn(&'a T) -> &'a Option<String>,
+) -> Option<&'a String> {
+    let values = values?;
+    values
+        .iter()
+        .find(|v| primary(v) == Some(true))
+        .or_else(|| values.first())
+        .and_then(|v| value(v).as_ref())
+}
+
+pub(crate) fn get_ignore_case<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+    value
+        .as_object()?
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .map(|(_, v)| v)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn resolves_nested_multi_valued_and_extension_paths() {
+        let user = json!({
+            "name": {"givenName": "Barbara"},
+            "emails": [{"value": "babs@example.com"}, {"value": "bjensen@example.com", "primary": true}],
+            "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User": {"department": "Tour Operations"}
+        });
+        assert_eq!(values_at(&user, "name.GivenName"), [&json!("Barbara")]);
+        assert_eq!(
+            values_at(&user, "emails.value"),
+            [&json!("bjensen@example.com"), &json!("babs@example.com")]
+        );
+        assert_eq!(
+            values_at(
+                &user,
+                "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:department"
+            ),
+            [&json!("Tour Operations")]
+        );
+        assert!(values_at(&user, "title").is_empty());
+    }
+}
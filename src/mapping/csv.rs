@@ -0,0 +1,446 @@
+//! Import and export of resources as CSV (RFC 4180), driven by a column mapping.
+
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+use crate::mapping::common::AttributeTarget;
+use crate::models::schema_coerce::coerce_resource;
+use crate::models::schema_registry::read_global;
+use crate::models::scim_schema::MergedSchema;
+use crate::utils::error::SCIMError;
+
+/// Maps the columns of a CSV file to the attributes of a resource type.
+///
+/// Each column is mapped to an attribute path of the resource type's schema or one of its extensions:
+///
+/// * `userName`, `name.givenName` or `urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:department`
+///   address single-valued attributes and sub-attributes;
+/// * `emails[type eq "work"].value` addresses one entry of a multi-valued attribute, selected by a
+///   sub-attribute, so that spreadsheets with one column per e-mail type map naturally;
+/// * `members.value` or `emails.value` address every entry of a multi-valued attribute. Such columns are
+///   usually added with [`CsvMapping::with_multi_valued_column`], which flattens the entries into one cell
+///   using a separator (e.g. `"2819c223;902c246b"`). Without a separator, a single entry is imported and the
+///   primary (or first) entry is exported.
+///
+/// Cell values are converted to the attribute's type (`"true"` for a boolean, `"42"` for an integer) on
+/// import, and every imported resource is validated against the resource type in the process-wide
+/// [`SchemaRegistry`](crate::models::schema_registry::SchemaRegistry).
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::mapping::csv::CsvMapping;
+/// use scim_v2::models::user::User;
+///
+/// let mapping = CsvMapping::new("User")
+///     .with_column("Login", "userName")
+///     .with_column("First name", "name.givenName")
+///     .with_column("Work e-mail", "emails[type eq \"work\"].value")
+///     .with_column("Active", "active");
+///
+/// let csv = "Login,First name,Work e-mail,Active\n\
+///            bjensen,Barbara,bjensen@example.com,true\n\
+///            ,Jim,jim@example.com,false\n";
+/// let import = mapping.import(csv).unwrap();
+///
+/// let user = User::try_from(import.resources[0].clone()).unwrap();
+/// assert_eq!(user.active, Some(true));
+/// assert_eq!(user.emails.unwrap()[0].r#type.as_deref(), Some("work"));
+///
+/// // The second row has no userName; it is reported with its line number and skipped.
+/// assert_eq!(import.errors.len(), 1);
+/// assert_eq!(import.errors[0].line, 3);
+///
+/// let exported = mapping.export(&import.resources).unwrap();
+/// assert_eq!(exported, "Login,First name,Work e-mail,Active\r\nbjensen,Barbara,bjensen@example.com,true\r\n");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvMapping {
+    resource_type: String,
+    columns: Vec<CsvColumn>,
+    delimiter: char,
+}
+
+/// A column of a [`CsvMapping`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvColumn {
+    /// The column header, matched case-insensitively on import.
+    pub header: String,
+    /// The attribute path the column maps to.
+    pub path: String,
+    /// The separator between the entries of a flattened multi-valued attribute.
+    pub separator: Option<char>,
+}
+
+/// The result of [`CsvMapping::import`].
+#[derive(Debug, Default)]
+pub struct CsvImport {
+    /// The resources of the rows that were imported successfully, in file order.
+    pub resources: Vec<Value>,
+    /// The rows that could not be imported.
+    pub errors: Vec<CsvRowError>,
+}
+
+/// A CSV row that could not be imported.
+#[derive(Debug)]
+pub struct CsvRowError {
+    /// The 1-based line number the row starts on; the header is line 1.
+    pub line: usize,
+    /// Why the row was rejected.
+    pub error: SCIMError,
+}
+
+impl fmt::Display for CsvRowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
+
+// A column resolved against the resource type's schema.
+struct Target<'a> {
+    column: &'a CsvColumn,
+    attribute: AttributeTarget,
+}
+
+impl CsvMapping {
+    /// Creates an empty mapping for a registered resource type, such as `User` or `Group`.
+    pub fn new(resource_type: &str) -> Self {
+        CsvMapping {
+            resource_type: resource_type.to_string(),
+            columns: Vec::new(),
+            delimiter: ',',
+        }
+    }
+
+    /// Maps a column to an attribute path.
+    pub fn with_column(mut self, header: &str, path: &str) -> Self {
+        self.columns.push(CsvColumn {
+            header: header.to_string(),
+            path: path.to_string(),
+            separator: None,
+        });
+        self
+    }
+
+    /// Maps a column to a multi-valued attribute path whose entries are flattened into one cell, separated
+    /// by `separator`.
+    pub fn with_multi_valued_column(mut self, header: &str, path: &str, separator: char) -> Self {
+        self.columns.push(CsvColumn {
+            header: header.to_string(),
+            path: path.to_string(),
+            separator: Some(separator),
+        });
+        self
+    }
+
+    /// Sets the field delimiter, which defaults to a comma. Spreadsheets exported with some locales use `;`.
+    pub fn with_delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Returns the mapped columns, in order.
+    pub fn columns(&self) -> &[CsvColumn] {
+        &self.columns
+    }
+
+    /// Imports the rows of a CSV file, which must start with a header row.
+    ///
+    /// Columns that are not part of the mapping are ignored. Rows that cannot be imported, because a cell
+    /// does not fit its attribute or the resulting resource is invalid, are reported in
+    /// [`CsvImport::errors`] without stopping the import.
+    ///
+    /// # Errors
+    ///
+    /// * `SCIMError::ResourceTypeNotFound` - If the resource type is not registered.
+    /// * `SCIMError::InvalidFieldValue` - If a column maps to an unknown attribute, or the file is not valid CSV.
+    /// * `SCIMError::MissingRequiredField` - If the file has no header row or lacks a mapped column.
+    pub fn import(&self, input: &str) -> Result<CsvImport, SCIMError> {
+        let registry = read_global();
+        let schema = registry
+            .merged_schema(&self.resource_type)
+            .ok_or_else(|| SCIMError::ResourceTypeNotFound(self.resource_type.clone().into()))?;
+        let targets = self.resolve(&schema)?;
+
+        let mut records = parse_records(input.trim_start_matches('\u{feff}'), self.delimiter)?;
+        if records.is_empty() {
+            return Err(SCIMError::MissingRequiredField("header row".into()));
+        }
+        let (_, header) = records.remove(0);
+        let mut indices = Vec::with_capacity(targets.len());
+        for target in &targets {
+            let index = header
+                .iter()
+                .position(|h| h.trim().eq_ignore_ascii_case(target.column.header.trim()))
+                .ok_or_else(|| {
+                    SCIMError::MissingRequiredField(
+                        format!("column '{}'", target.column.header).into(),
+                    )
+                })?;
+            indices.push(index);
+        }
+
+        let mut import = CsvImport::default();
+        for (line, record) in records {
+            if record.len() != header.len() {
+                import.errors.push(CsvRowError {
+                    line,
+                    error: SCIMError::InvalidFieldValue(
+                        format!("expected {} fields, found {}", header.len(), record.len()).into(),
+                    ),
+                });
+                continue;
+            }
+            let mut resource = Map::new();
+            resource.insert(
+                "schemas".to_string(),
+                Value::Array(vec![Value::String(schema.id.clone())]),
+            );
+            let row = targets
+                .iter()
+                .zip(&indices)
+                .try_for_each(|(target, &index)| set_cell(&mut resource, target, &record[index]));
+            let mut resource = Value::Object(resource);
+            let row = row.and_then(|()| {
+                coerce_resource(&mut resource, &schema);
+                registry.validate_resource(&self.resource_type, &resource)
+            });
+            match row {
+                Ok(()) => import.resources.push(resource),
+                Err(error) => import.errors.push(CsvRowError { line, error }),
+            }
+        }
+        Ok(import)
+    }
+
+    /// Exports resources as a CSV file with a header row, one row per resource.
+    ///
+    /// Attributes a resource does not have are exported as empty cells.
+    ///
+    /// # Errors
+    ///
+    /// * `SCIMError::ResourceTypeNotFound` - If the resource type is not registered.
+    /// * `SCIMError::InvalidFieldValue` - If a column maps to an unknown attribute.
+    pub fn export(&self, resources: &[Value]) -> Result<String, SCIMError> {
+        let schema = read_global()
+            .merged_schema(&self.resource_type)
+            .ok_or_else(|| SCIMError::ResourceTypeNotFound(self.resource_type.clone().into()))?;
+        let targets = self.resolve(&schema)?;
+
+        let mut output = String::new();
+        let header: Vec<&str> = self.columns.iter().map(|c| c.header.as_str()).collect();
+        self.write_record(&mut output, &header);
+        for resource in resources {
+            let cells: Vec<String> = targets
+                .iter()
+                .map(|t| {
+                    let values = t.attribute.get(resource, t.column.separator.is_some());
+                    values.join(&t.column.separator.unwrap_or(',').to_string())
+                })
+                .collect();
+            let cells: Vec<&str> = cells.iter().map(String::as_str).collect();
+            self.write_record(&mut output, &cells);
+        }
+        Ok(output)
+    }
+
+    fn resolve<'a>(&'a self, schema: &MergedSchema) -> Result<Vec<Target<'a>>, SCIMError> {
+        self.columns
+            .iter()
+            .map(|column| {
+                let attribute =
+                    AttributeTarget::resolve(schema, &column.path).ok_or_else(|| {
+                        SCIMError::InvalidFieldValue(
+                            format!(
+                                "column '{}' maps to unknown attribute '{}'",
+                                column.header, column.path
+                            )
+                            .into(),
+                        )
+                    })?;
+                Ok(Target { column, attribute })
+            })
+            .collect()
+    }
+
+    fn write_record(&self, output: &mut String, fields: &[&str]) {
+        for (i, field) in fields.iter().enumerate() {
+            if i > 0 {
+                output.push(self.delimiter);
+            }
+            let quote = field.contains([self.delimiter, '"', '\r', '\n'])
+                || field.starts_with(' ')
+                || field.ends_with(' ');
+            if quote {
+                output.push('"');
+                output.push_str(&field.replace('"', "\"\""));
+                output.push('"');
+            } else {
+                output.push_str(field);
+            }
+        }
+        output.push_str("\r\n");
+    }
+}
+
+fn set_cell(
+    resource: &mut Map<String, Value>,
+    target: &Target,
+    cell: &str,
+) -> Result<(), SCIMError> {
+    let values: Vec<&str> = match target.column.separator {
+        Some(separator) => cell
+            .split(separator)
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .collect(),
+        None => Some(cell.trim())
+            .filter(|v| !v.is_empty())
+            .into_iter()
+            .collect(),
+    };
+    if values.len() > 1 && !target.attribute.accepts_several() {
+        return Err(SCIMError::InvalidFieldValue(
+            format!(
+                "column '{}' holds several values for a single-valued attribute",
+                target.column.header
+            )
+            .into(),
+        ));
+    }
+    target.attribute.set(resource, &values);
+    Ok(())
+}
+
+// Splits CSV text into records, each with the line number it starts on.
+fn parse_records(input: &str, delimiter: char) -> Result<Vec<(usize, Vec<String>)>, SCIMError> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut line = 1;
+    let mut start = 1;
+    let mut quoted = false;
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            '\n' if quoted => {
+                line += 1;
+                field.push('\n');
+            }
+            '\r' if !quoted => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                if record.iter().any(|f| !f.is_empty()) || record.len() > 1 {
+                    records.push((start, std::mem::take(&mut record)));
+                }
+                record.clear();
+                line += 1;
+                start = line;
+            }
+            c if c == delimiter && !quoted => record.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(SCIMError::InvalidFieldValue(
+            format!("unterminated quoted field starting on line {}", start).into(),
+        ));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push((start, record));
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::schema_uri::{ENTERPRISE_USER_URN, GROUP_URN, USER_URN};
+
+    #[test]
+    fn parses_quoted_fields_and_tracks_lines() {
+        let records =
+            parse_records("a,\"b,\"\"c\"\"\"\r\n\r\n\"multi\nline\",x\nlast,", ',').unwrap();
+        assert_eq!(
+            records,
+            vec![
+                (1, vec!["a".to_string(), "b,\"c\"".to_string()]),
+                (3, vec!["multi\nline".to_string(), "x".to_string()]),
+                (5, vec!["last".to_string(), "".to_string()]),
+            ]
+        );
+        assert!(parse_records("\"open", ',').is_err());
+    }
+
+    #[test]
+    fn flattens_multi_valued_attributes_and_extensions() {
+        let mapping = CsvMapping::new("User")
+            .with_column("login", "userName")
+            .with_multi_valued_column("emails", "emails.value", '|')
+            .with_column("department", &format!("{}:department", ENTERPRISE_USER_URN));
+        let import = mapping
+            .import("login;emails;department\nbjensen;bjensen@example.com|babs@example.com;Tour Operations\n")
+            .unwrap_err();
+        assert!(matches!(import, SCIMError::MissingRequiredField(_)));
+
+        let import = mapping
+            .with_delimiter(';')
+            .import("login;emails;department\nbjensen;bjensen@example.com|babs@example.com;Tour Operations\n")
+            .unwrap();
+        assert!(import.errors.is_empty());
+        assert_eq!(
+            import.resources[0],
+            json!({
+                "schemas": [USER_URN, ENTERPRISE_USER_URN],
+                "userName": "bjensen",
+                "emails": [{"value": "bjensen@example.com"}, {"value": "babs@example.com"}],
+                ENTERPRISE_USER_URN: {"department": "Tour Operations"}
+            })
+        );
+    }
+
+    #[test]
+    fn round_trips_group_members() {
+        let mapping = CsvMapping::new("Group")
+            .with_column("Name", "displayName")
+            .with_multi_valued_column("Members", "members.value", ';');
+        let group = json!({
+            "schemas": [GROUP_URN],
+            "displayName": "Tour Guides, West",
+            "members": [{"value": "2819c223"}, {"value": "902c246b"}]
+        });
+        let csv = mapping.export(std::slice::from_ref(&group)).unwrap();
+        assert_eq!(
+            csv,
+            "Name,Members\r\n\"Tour Guides, West\",2819c223;902c246b\r\n"
+        );
+        let import = mapping.import(&csv).unwrap();
+        assert_eq!(import.resources, vec![group]);
+    }
+
+    #[test]
+    fn reports_unknown_attributes_and_bad_rows() {
+        let mapping = CsvMapping::new("User").with_column("x", "shoeSize");
+        assert!(mapping.import("x\n1\n").is_err());
+
+        let mapping = CsvMapping::new("User")
+            .with_column("login", "userName")
+            .with_column("active", "active");
+        let import = mapping
+            .import("login,active\nbjensen,true,extra\nbjensen,true\n")
+            .unwrap();
+        assert_eq!(import.resources.len(), 1);
+        assert_eq!(import.errors[0].line, 2);
+        assert!(import.errors[0].to_string().starts_with("line 2: "));
+    }
+}
@@ -0,0 +1,518 @@
+//! Conversion between Microsoft Graph `user` objects and SCIM `User` resources.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::mapping::common::primary_or_first;
+use crate::models::enterprise_user::{EnterpriseUser, Manager};
+use crate::models::extension::ScimExtension;
+use crate::models::user::{Address, Email, Name, PhoneNumber, User};
+use crate::models::values::Values;
+use crate::values;
+
+/// Schema URI of the extension that carries Graph data without a counterpart in the SCIM core schemas.
+pub const GRAPH_USER_URN: &str = "urn:microsoft:params:scim:schemas:extension:graph:2.0:User";
+
+/// A user as represented by the Microsoft Graph API (`microsoft.graph.user`).
+///
+/// Only the properties relevant to provisioning are modeled; unknown properties are ignored when
+/// deserializing.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphUser {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_principal_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub given_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub surname: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub other_mails: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mail_nickname: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy_addresses: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub business_phones: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mobile_phone: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fax_number: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preferred_language: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage_location: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub street_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub city: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub postal_code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub employee_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub employee_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub department: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub company_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub employee_org_data: Option<EmployeeOrgData>,
+    /// The user's manager, present when requested with `$expand=manager`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manager: Option<DirectoryObject>,
+    /// An RFC 3339 timestamp.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_date_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_premises_immutable_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_premises_sam_account_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_premises_user_principal_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_premises_distinguished_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_premises_domain_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_premises_security_identifier: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_premises_sync_enabled: Option<bool>,
+    /// `extensionAttribute1` to `extensionAttribute15`, synchronized from on-premises Active Directory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_premises_extension_attributes: Option<BTreeMap<String, Option<String>>>,
+}
+
+/// The organization data of a Graph user (`microsoft.graph.employeeOrgData`).
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EmployeeOrgData {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub division: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost_center: Option<String>,
+}
+
+/// A reference to another directory object, such as a user's manager.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectoryObject {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+}
+
+/// The Graph data that has no counterpart in the SCIM core and enterprise schemas, mostly the properties
+/// synchronized from on-premises Active Directory.
+///
+/// It is attached to users as a schema extension under [`GRAPH_USER_URN`], so that a round trip through SCIM
+/// does not lose them.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphExtension {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mail_nickname: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy_addresses: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage_location: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_premises_immutable_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_premises_sam_account_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_premises_user_principal_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_premises_distinguished_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_premises_domain_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_premises_security_identifier: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_premises_sync_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_premises_extension_attributes: Option<BTreeMap<String, Option<String>>>,
+}
+
+impl ScimExtension for GraphExtension {
+    const URN: &'static str = GRAPH_USER_URN;
+
+    fn attribute_names() -> &'static [&'static str] {
+        &[
+            "mailNickname",
+            "proxyAddresses",
+            "usageLocation",
+            "onPremisesImmutableId",
+            "onPremisesSamAccountName",
+            "onPremisesUserPrincipalName",
+            "onPremisesDistinguishedName",
+            "onPremisesDomainName",
+            "onPremisesSecurityIdentifier",
+            "onPremisesSyncEnabled",
+            "onPremisesExtensionAttributes",
+        ]
+    }
+}
+
+/// Converts a Graph user into a SCIM `User`.
+///
+/// * `id` becomes `externalId`, `userPrincipalName` becomes `userName` and `accountEnabled` becomes `active`;
+/// * `givenName` and `surname` become `name`, and `displayName`, `userType`, `jobTitle` (as `title`) and
+///   `preferredLanguage` map to their namesakes;
+/// * `mail` becomes the primary work e-mail address and `otherMails` become e-mail addresses of type `other`;
+/// * `businessPhones`, `mobilePhone` and `faxNumber` become phone numbers of type `work`, `mobile` and `fax`;
+/// * the postal address properties become the work address;
+/// * `employeeId`, `companyName`, `department`, `employeeOrgData` and an expanded `manager` become the
+///   enterprise extension's `employeeNumber`, `organization`, `department`, `division`, `costCenter` and
+///   `manager`;
+/// * `createdDateTime` becomes `meta.created`.
+///
+/// Use [`GraphExtension::from`] to keep the on-premises properties and other unmapped data.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::mapping::graph::{GraphUser, to_user};
+/// use serde_json::json;
+///
+/// let graph: GraphUser = serde_json::from_value(json!({
+///     "id": "87d349ed-44d7-43e1-9a83-5f2406dee5bd",
+///     "userPrincipalName": "bjensen@contoso.com",
+///     "mail": "barbara.jensen@contoso.com",
+///     "accountEnabled": true,
+///     "businessPhones": ["+1 425 555 0109"],
+///     "department": "Tour Operations"
+/// }))
+/// .unwrap();
+///
+/// let user = to_user(&graph);
+/// assert_eq!(user.user_name, "bjensen@contoso.com");
+/// assert_eq!(user.external_id.as_deref(), Some("87d349ed-44d7-43e1-9a83-5f2406dee5bd"));
+/// assert_eq!(user.enterprise_user.unwrap().department.as_deref(), Some("Tour Operations"));
+/// ```
+pub fn to_user(graph: &GraphUser) -> User {
+    let mut user = User {
+        external_id: graph.id.clone(),
+        user_name: graph.user_principal_name.clone().unwrap_or_default(),
+        display_name: graph.display_name.clone(),
+        user_type: graph.user_type.clone(),
+        title: graph.job_title.clone(),
+        preferred_language: graph.preferred_language.clone(),
+        active: graph.account_enabled,
+        ..Default::default()
+    };
+    if graph.given_name.is_some() || graph.surname.is_some() {
+        user.name = Some(Name {
+            given_name: graph.given_name.clone(),
+            family_name: graph.surname.clone(),
+            ..Default::default()
+        });
+    }
+
+    let mut emails: Values<Email> = graph
+        .mail
+        .iter()
+        .map(|mail| email(mail, "work", true))
+        .collect();
+    emails.extend(
+        graph
+            .other_mails
+            .iter()
+            .flatten()
+            .map(|mail| email(mail, "other", false)),
+    );
+    user.emails = Some(emails).filter(|e| !e.is_empty());
+
+    let mut phones: Values<PhoneNumber> = graph
+        .business_phones
+        .iter()
+        .flatten()
+        .map(|number| phone(number, "work"))
+        .collect();
+    phones.extend(
+        graph
+            .mobile_phone
+            .iter()
+            .map(|number| phone(number, "mobile")),
+    );
+    phones.extend(graph.fax_number.iter().map(|number| phone(number, "fax")));
+    if let Some(first) = phones.first_mut() {
+        first.primary = Some(true);
+    }
+    user.phone_numbers = Some(phones).filter(|p| !p.is_empty());
+
+    let address = Address {
+        street_address: graph.street_address.clone(),
+        locality: graph.city.clone(),
+        region: graph.state.clone(),
+        postal_code: graph.postal_code.clone(),
+        country: graph.country.clone(),
+        r#type: Some("work".to_string()),
+        ..Default::default()
+    };
+    let has_address = address.street_address.is_some()
+        || address.locality.is_some()
+        || address.region.is_some()
+        || address.postal_code.is_some()
+        || address.country.is_some();
+    if has_address {
+        user.addresses = Some(values![address]);
+    }
+    if let Some(created) = &graph.created_date_time {
+        let meta = user.meta.get_or_insert_with(Default::default);
+        meta.resource_type = Some("User".to_string());
+        meta.created = Some(created.clone());
+    }
+
+    let org_data = graph.employee_org_data.as_ref();
+    let enterprise = EnterpriseUser {
+        employee_number: graph.employee_id.clone(),
+        cost_center: org_data.and_then(|o| o.cost_center.clone()),
+        organization: graph.company_name.clone(),
+        division: org_data.and_then(|o| o.division.clone()),
+        department: graph.department.clone(),
+        manager: graph.manager.as_ref().map(|manager| Manager {
+            value: manager.id.as_deref().map(Into::into),
+            r#ref: None,
+            display_name: manager.display_name.clone(),
+        }),
+    };
+    let has_enterprise = enterprise.employee_number.is_some()
+        || enterprise.cost_center.is_some()
+        || enterprise.organization.is_some()
+        || enterprise.division.is_some()
+        || enterprise.department.is_some()
+        || enterprise.manager.is_some();
+    if has_enterprise {
+        user.schemas.push(EnterpriseUser::URN.to_string());
+        user.enterprise_user = Some(enterprise);
+    }
+    user
+}
+
+/// Converts a SCIM `User`, and optionally the Graph data kept from an earlier conversion, into a Graph user.
+///
+/// This is the reverse of [`to_user`]: the primary (or first) work e-mail address becomes `mail` and the
+/// other addresses `otherMails`; work phone numbers become `businessPhones`, and the first mobile and fax
+/// numbers `mobilePhone` and `faxNumber` (numbers without a type are taken as work numbers); the work (or
+/// first) address provides the postal address properties.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::mapping::graph::{GraphExtension, GraphUser, to_graph, to_user};
+///
+/// let graph = GraphUser {
+///     user_principal_name: Some("bjensen@contoso.com".to_string()),
+///     on_premises_sam_account_name: Some("bjensen".to_string()),
+///     ..Default::default()
+/// };
+/// let user = to_user(&graph);
+/// let extension = GraphExtension::from(&graph);
+///
+/// assert_eq!(to_graph(&user, Some(&extension)), graph);
+/// ```
+pub fn to_graph(user: &User, extension: Option<&GraphExtension>) -> GraphUser {
+    let emails = user.emails.as_deref().unwrap_or_default();
+    let is_work = |e: &&Email| e.r#type.as_deref().is_none_or(|t| t == "work");
+    let mail = emails
+        .iter()
+        .filter(is_work)
+        .find(|e| e.primary == Some(true))
+        .or_else(|| emails.iter().find(is_work))
+        .and_then(|e| e.value.clone())
+        .or_else(|| primary_or_first(Some(emails), |e| e.primary, |e| &e.value).cloned());
+    let other_mails: Vec<String> = emails
+        .iter()
+        .filter_map(|e| e.value.clone())
+        .filter(|value| Some(value) != mail.as_ref())
+        .collect();
+
+    let phones = user.phone_numbers.as_deref().unwrap_or_default();
+    let phones_of = |kind: &'static str| {
+        phones
+            .iter()
+            .filter(move |p| p.r#type.as_deref().unwrap_or("work") == kind)
+            .filter_map(|p| p.value.clone())
+    };
+    let business_phones: Vec<String> = phones_of("work").collect();
+
+    let addresses = user.addresses.as_deref().unwrap_or_default();
+    let address = addresses
+        .iter()
+        .find(|a| a.r#type.as_deref() == Some("work"))
+        .or_else(|| addresses.first());
+
+    let enterprise = user.enterprise_user.as_ref();
+    let employee_org_data = EmployeeOrgData {
+        division: enterprise.and_then(|e| e.division.clone()),
+        cost_center: enterprise.and_then(|e| e.cost_center.clone()),
+    };
+    let extension = extension.cloned().unwrap_or_default();
+
+    GraphUser {
+        id: user.external_id.clone(),
+        user_principal_name: Some(user.user_name.clone()).filter(|u| !u.is_empty()),
+        display_name: user.display_name.clone(),
+        given_name: user.name.as_ref().and_then(|n| n.given_name.clone()),
+        surname: user.name.as_ref().and_then(|n| n.family_name.clone()),
+        mail,
+        other_mails: Some(other_mails).filter(|m| !m.is_empty()),
+        mail_nickname: extension.mail_nickname,
+        proxy_addresses: extension.proxy_addresses,
+        account_enabled: user.active,
+        user_type: user.user_type.clone(),
+        business_phones: Some(business_phones).filter(|p| !p.is_empty()),
+        mobile_phone: phones_of("mobile").next(),
+        fax_number: phones_of("fax").next(),
+        job_title: user.title.clone(),
+        preferred_language: user.preferred_language.clone(),
+        usage_location: extension.usage_location,
+        street_address: address.and_then(|a| a.street_address.clone()),
+        city: address.and_then(|a| a.locality.clone()),
+        state: address.and_then(|a| a.region.clone()),
+        postal_code: address.and_then(|a| a.postal_code.clone()),
+        country: address.and_then(|a| a.country.clone()),
+        employee_id: enterprise.and_then(|e| e.employee_number.clone()),
+        employee_type: None,
+        department: enterprise.and_then(|e| e.department.clone()),
+        company_name: enterprise.and_then(|e| e.organization.clone()),
+        employee_org_data: Some(employee_org_data)
+            .filter(|o| o.division.is_some() || o.cost_center.is_some()),
+        manager: enterprise
+            .and_then(|e| e.manager.as_ref())
+            .map(|manager| DirectoryObject {
+                id: manager.value.as_ref().map(|id| id.as_str().to_string()),
+                display_name: manager.display_name.clone(),
+            }),
+        created_date_time: user.meta.as_ref().and_then(|m| m.created.clone()),
+        on_premises_immutable_id: extension.on_premises_immutable_id,
+        on_premises_sam_account_name: extension.on_premises_sam_account_name,
+        on_premises_user_principal_name: extension.on_premises_user_principal_name,
+        on_premises_distinguished_name: extension.on_premises_distinguished_name,
+        on_premises_domain_name: extension.on_premises_domain_name,
+        on_premises_security_identifier: extension.on_premises_security_identifier,
+        on_premises_sync_enabled: extension.on_premises_sync_enabled,
+        on_premises_extension_attributes: extension.on_premises_extension_attributes,
+    }
+}
+
+/// Collects the Graph data that [`to_user`] cannot map: the on-premises properties, `mailNickname`,
+/// `proxyAddresses` and `usageLocation`.
+impl From<&GraphUser> for GraphExtension {
+    fn from(graph: &GraphUser) -> Self {
+        GraphExtension {
+            mail_nickname: graph.mail_nickname.clone(),
+            proxy_addresses: graph.proxy_addresses.clone(),
+            usage_location: graph.usage_location.clone(),
+            on_premises_immutable_id: graph.on_premises_immutable_id.clone(),
+            on_premises_sam_account_name: graph.on_premises_sam_account_name.clone(),
+            on_premises_user_principal_name: graph.on_premises_user_principal_name.clone(),
+            on_premises_distinguished_name: graph.on_premises_distinguished_name.clone(),
+            on_premises_domain_name: graph.on_premises_domain_name.clone(),
+            on_premises_security_identifier: graph.on_premises_security_identifier.clone(),
+            on_premises_sync_enabled: graph.on_premises_sync_enabled,
+            on_premises_extension_attributes: graph.on_premises_extension_attributes.clone(),
+        }
+    }
+}
+
+fn email(value: &str, kind: &str, primary: bool) -> Email {
+    Email {
+        value: Some(value.to_string()),
+        r#type: Some(kind.to_string()),
+        primary: primary.then_some(true),
+        ..Default::default()
+    }
+}
+
+fn phone(value: &str, kind: &str) -> PhoneNumber {
+    PhoneNumber {
+        value: Some(value.to_string()),
+        r#type: Some(kind.to_string()),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn graph_user() -> GraphUser {
+        serde_json::from_value(json!({
+            "@odata.type": "#microsoft.graph.user",
+            "id": "87d349ed-44d7-43e1-9a83-5f2406dee5bd",
+            "userPrincipalName": "bjensen@contoso.com",
+            "displayName": "Barbara Jensen",
+            "givenName": "Barbara",
+            "surname": "Jensen",
+            "mail": "barbara.jensen@contoso.com",
+            "otherMails": ["babs@example.com"],
+            "accountEnabled": true,
+            "businessPhones": ["+1 425 555 0109"],
+            "mobilePhone": "+1 425 555 0110",
+            "jobTitle": "Tour Guide",
+            "city": "Redmond",
+            "country": "US",
+            "employeeId": "701984",
+            "companyName": "Contoso",
+            "employeeOrgData": {"division": "Theme Park", "costCenter": "4130"},
+            "manager": {"id": "26118915", "displayName": "John Smith"},
+            "createdDateTime": "2011-08-01T18:29:49Z",
+            "onPremisesSamAccountName": "bjensen",
+            "onPremisesExtensionAttributes": {"extensionAttribute1": "Badge 42", "extensionAttribute2": null}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn maps_contact_details_and_enterprise_attributes() {
+        let user = to_user(&graph_user());
+        assert!(user.validate().is_ok());
+        let emails = user.emails.unwrap();
+        assert_eq!(emails[0].primary, Some(true));
+        assert_eq!(emails[1].r#type.as_deref(), Some("other"));
+        let phones = user.phone_numbers.unwrap();
+        assert_eq!(phones[1].r#type.as_deref(), Some("mobile"));
+        assert_eq!(
+            user.addresses.unwrap()[0].locality.as_deref(),
+            Some("Redmond")
+        );
+        let enterprise = user.enterprise_user.unwrap();
+        assert_eq!(enterprise.division.as_deref(), Some("Theme Park"));
+        assert_eq!(enterprise.manager.unwrap().value.unwrap(), "26118915");
+    }
+
+    #[test]
+    fn unmapped_data_round_trips_through_the_extension() {
+        let graph = graph_user();
+        let extension = GraphExtension::from(&graph);
+
+        let mut resource = serde_json::to_value(to_user(&graph)).unwrap();
+        extension.insert_into(&mut resource).unwrap();
+        let read_back = GraphExtension::from_resource(&resource).unwrap().unwrap();
+        let user = User::try_from(resource).unwrap();
+
+        assert_eq!(to_graph(&user, Some(&read_back)), graph);
+    }
+}
@@ -0,0 +1,435 @@
+//! A declarative mapping from HR feed records (Workday, SuccessFactors and similar exports) to resources.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::mapping::common::{AttributeTarget, scalar_text, values_at};
+use crate::models::schema_coerce::coerce_resource;
+use crate::models::schema_registry::read_global;
+use crate::utils::error::SCIMError;
+
+/// Maps the fields of HR feed records to the attributes of a resource type.
+///
+/// A mapping is a list of [`HrRule`]s, each reading one or more source fields of a record, passing the
+/// value through a chain of [`HrTransform`]s and writing it to an attribute path of the resource type's
+/// schema or one of its extensions. Target paths follow the same syntax as
+/// [`CsvMapping`](crate::mapping::csv::CsvMapping) columns, including `emails[type eq "work"].value`.
+///
+/// Mappings serialize to and from JSON, so that the mapping of each customer's feed can be kept as
+/// configuration rather than code.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::mapping::hr::HrMapping;
+/// use scim_v2::models::user::User;
+/// use serde_json::json;
+///
+/// let mapping: HrMapping = serde_json::from_value(json!({
+///     "resourceType": "User",
+///     "rules": [
+///         {"target": "userName", "source": "Worker.Email", "transforms": ["trim", "lowercase"]},
+///         {"target": "name.givenName", "source": "Worker.Legal_First_Name"},
+///         {"target": "name.familyName", "source": "Worker.Legal_Last_Name"},
+///         {"target": "displayName", "source": ["Worker.Legal_First_Name", "Worker.Legal_Last_Name"]},
+///         {"target": "active", "source": "Worker.Status", "transforms": [
+///             {"map": {"Active": "true", "Terminated": "false"}}
+///         ]},
+///         {"target": "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:employeeNumber",
+///          "source": "Worker.Employee_ID"}
+///     ]
+/// }))
+/// .unwrap();
+///
+/// let record = json!({"Worker": {
+///     "Email": " BJensen@Example.com ",
+///     "Legal_First_Name": "Barbara",
+///     "Legal_Last_Name": "Jensen",
+///     "Status": "Active",
+///     "Employee_ID": "701984"
+/// }});
+/// let user = User::try_from(mapping.apply(&record).unwrap()).unwrap();
+/// assert_eq!(user.user_name, "bjensen@example.com");
+/// assert_eq!(user.display_name.as_deref(), Some("Barbara Jensen"));
+/// assert_eq!(user.active, Some(true));
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HrMapping {
+    /// The resource type the records are mapped to, e.g. `User`.
+    pub resource_type: String,
+    /// The rules, applied in order; a later rule overwrites a single-valued attribute set by an earlier one.
+    pub rules: Vec<HrRule>,
+}
+
+/// A rule of an [`HrMapping`], mapping one or more source fields to an attribute.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HrRule {
+    /// The attribute path the value is written to.
+    pub target: String,
+    /// The source field, or fields to concatenate.
+    pub source: HrSource,
+    /// The separator placed between concatenated source fields.
+    #[serde(default = "default_separator")]
+    pub separator: String,
+    /// The transforms applied to the value, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transforms: Vec<HrTransform>,
+}
+
+/// The source of an [`HrRule`]'s value.
+///
+/// Field names are paths into the record, with `.` separating nested objects (`Worker.Email`) and are
+/// matched case-insensitively. Fields that are missing, null or empty yield no value.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum HrSource {
+    /// A single field.
+    Field(String),
+    /// Several fields, concatenated with the rule's separator. Fields without a value are skipped.
+    Concat(Vec<String>),
+}
+
+/// A transform applied to the value of an [`HrRule`].
+///
+/// Transforms other than [`Default`](HrTransform::Default) apply only to a present value; a transform
+/// that leaves an empty string removes the value.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum HrTransform {
+    /// Converts the value to lowercase.
+    Lowercase,
+    /// Converts the value to uppercase.
+    Uppercase,
+    /// Removes leading and trailing whitespace.
+    Trim,
+    /// Replaces every occurrence of `from` with `to`.
+    Replace {
+        /// The text to replace.
+        from: String,
+        /// The replacement.
+        to: String,
+    },
+    /// Replaces the value by its entry in a lookup table; values without an entry are kept.
+    Map(BTreeMap<String, String>),
+    /// Supplies a value when the source has none.
+    Default(String),
+    /// Prepends text to the value.
+    Prefix(String),
+    /// Appends text to the value.
+    Suffix(String),
+    /// Reformats a date from one format to another.
+    ///
+    /// Formats are strftime-style patterns supporting `%Y` (year), `%m` (month), `%d` (day), `%H` (hour),
+    /// `%M` (minute), `%S` (second) and `%%`; other characters are matched literally. When parsing,
+    /// numeric fields may have fewer digits than their width, so `%m/%d/%Y` accepts `3/7/2024`.
+    DateFormat {
+        /// The format of the source value, e.g. `%m/%d/%Y`.
+        from: String,
+        /// The format to produce, e.g. `%Y-%m-%dT%H:%M:%SZ` for a SCIM `dateTime`.
+        to: String,
+    },
+}
+
+fn default_separator() -> String {
+    " ".to_string()
+}
+
+impl HrMapping {
+    /// Creates a mapping without rules for the given resource type.
+    pub fn new(resource_type: impl Into<String>) -> Self {
+        HrMapping {
+            resource_type: resource_type.into(),
+            rules: Vec::new(),
+        }
+    }
+
+    /// Adds a rule.
+    pub fn with_rule(mut self, rule: HrRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Maps an HR feed record to a resource.
+    ///
+    /// The resulting resource has its values converted to the attributes' types and is validated against
+    /// the resource type in the process-wide
+    /// [`SchemaRegistry`](crate::models::schema_registry::SchemaRegistry).
+    ///
+    /// # Errors
+    ///
+    /// * `SCIMError::ResourceTypeNotFound` - If the resource type is not registered.
+    /// * `SCIMError::InvalidFieldValue` - If a rule targets an unknown attribute, a date does not match its
+    ///   format, or the resulting resource has an invalid value.
+    /// * `SCIMError::MissingRequiredField` - If the resulting resource lacks a required attribute.
+    pub fn apply(&self, record: &Value) -> Result<Value, SCIMError> {
+        let registry = read_global();
+        let schema = registry
+            .merged_schema(&self.resource_type)
+            .ok_or_else(|| SCIMError::ResourceTypeNotFound(self.resource_type.clone().into()))?;
+
+        let mut resource = Map::new();
+        resource.insert(
+            "schemas".to_string(),
+            Value::Array(vec![Value::String(schema.id.clone())]),
+        );
+        for rule in &self.rules {
+            let target = AttributeTarget::resolve(&schema, &rule.target).ok_or_else(|| {
+                SCIMError::InvalidFieldValue(
+                    format!("rule maps to unknown attribute '{}'", rule.target).into(),
+                )
+            })?;
+            if let Some(value) = rule.value(record)? {
+                target.set(&mut resource, &[&value]);
+            }
+        }
+
+        let mut resource = Value::Object(resource);
+        coerce_resource(&mut resource, &schema);
+        registry.validate_resource(&self.resource_type, &resource)?;
+        Ok(resource)
+    }
+}
+
+impl HrRule {
+    /// Creates a rule copying the `source` field to `target`.
+    pub fn new(target: impl Into<String>, source: impl Into<String>) -> Self {
+        HrRule {
+            target: target.into(),
+            source: HrSource::Field(source.into()),
+            separator: default_separator(),
+            transforms: Vec::new(),
+        }
+    }
+
+    /// Creates a rule concatenating the `sources` fields, separated by `separator`, into `target`.
+    pub fn concat<I, S>(target: impl Into<String>, sources: I, separator: impl Into<String>) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        HrRule {
+            target: target.into(),
+            source: HrSource::Concat(sources.into_iter().map(Into::into).collect()),
+            separator: separator.into(),
+            transforms: Vec::new(),
+        }
+    }
+
+    /// Adds a transform, applied after those already added.
+    pub fn with_transform(mut self, transform: HrTransform) -> Self {
+        self.transforms.push(transform);
+        self
+    }
+
+    fn value(&self, record: &Value) -> Result<Option<String>, SCIMError> {
+        let field = |name: &str| {
+            values_at(record, name)
+                .into_iter()
+                .find_map(scalar_text)
+                .filter(|v| !v.is_empty())
+        };
+        let mut value = match &self.source {
+            HrSource::Field(name) => field(name),
+            HrSource::Concat(names) => {
+                let parts: Vec<String> = names.iter().filter_map(|name| field(name)).collect();
+                Some(parts.join(&self.separator))
+            }
+        };
+        for transform in &self.transforms {
+            value = match (transform, value) {
+                (HrTransform::Default(default), None) => Some(default.clone()),
+                (_, None) => None,
+                (transform, Some(value)) => Some(transform.apply(value)?),
+            }
+            .filter(|v| !v.is_empty());
+        }
+        Ok(value.filter(|v| !v.is_empty()))
+    }
+}
+
+impl HrTransform {
+    fn apply(&self, value: String) -> Result<String, SCIMError> {
+        Ok(match self {
+            HrTransform::Lowercase => value.to_lowercase(),
+            HrTransform::Uppercase => value.to_uppercase(),
+            HrTransform::Trim => value.trim().to_string(),
+            HrTransform::Replace { from, to } if !from.is_empty() => value.replace(from, to),
+            HrTransform::Replace { .. } | HrTransform::Default(_) => value,
+            HrTransform::Map(table) => table.get(&value).cloned().unwrap_or(value),
+            HrTransform::Prefix(prefix) => format!("{}{}", prefix, value),
+            HrTransform::Suffix(suffix) => format!("{}{}", value, suffix),
+            HrTransform::DateFormat { from, to } => {
+                let fields = parse_date(&value, from).ok_or_else(|| {
+                    SCIMError::InvalidFieldValue(
+                        format!("'{}' does not match the date format '{}'", value, from).into(),
+                    )
+                })?;
+                format_date(&fields, to)?
+            }
+        })
+    }
+}
+
+// The fields of a date: year, month, day, hour, minute and second.
+type DateFields = [u32; 6];
+
+fn directive_index(directive: char) -> Option<(usize, usize)> {
+    // The field index and width of each supported directive.
+    match directive {
+        'Y' => Some((0, 4)),
+        'm' => Some((1, 2)),
+        'd' => Some((2, 2)),
+        'H' => Some((3, 2)),
+        'M' => Some((4, 2)),
+        'S' => Some((5, 2)),
+        _ => None,
+    }
+}
+
+fn parse_date(input: &str, format: &str) -> Option<DateFields> {
+    let mut fields: DateFields = [0, 1, 1, 0, 0, 0];
+    let mut input = input.trim();
+    let mut format = format.chars();
+    while let Some(c) = format.next() {
+        if c != '%' {
+            input = input.strip_prefix(c)?;
+            continue;
+        }
+        match format.next()? {
+            '%' => input = input.strip_prefix('%')?,
+            directive => {
+                let (index, width) = directive_index(directive)?;
+                let digits = input
+                    .bytes()
+                    .take(width)
+                    .take_while(u8::is_ascii_digit)
+                    .count();
+                if digits == 0 {
+                    return None;
+                }
+                fields[index] = input[..digits].parse().ok()?;
+                input = &input[digits..];
+            }
+        }
+    }
+    let [_, month, day, hour, minute, second] = fields;
+    let valid = input.is_empty()
+        && (1..=12).contains(&month)
+        && (1..=31).contains(&day)
+        && hour < 24
+        && minute < 60
+        && second < 61;
+    valid.then_some(fields)
+}
+
+fn format_date(fields: &DateFields, format: &str) -> Result<String, SCIMError> {
+    let unsupported = || {
+        SCIMError::InvalidFieldValue(
+            format!("unsupported directive in date format '{}'", format).into(),
+        )
+    };
+    let mut output = String::new();
+    let mut format_chars = format.chars();
+    while let Some(c) = format_chars.next() {
+        if c != '%' {
+            output.push(c);
+            continue;
+        }
+        match format_chars.next().ok_or_else(unsupported)? {
+            '%' => output.push('%'),
+            directive => {
+                let (index, width) = directive_index(directive).ok_or_else(unsupported)?;
+                output.push_str(&format!("{:0width$}", fields[index], width = width));
+            }
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::user::User;
+
+    #[test]
+    fn applies_transforms_in_order() {
+        let mapping = HrMapping::new("User")
+            .with_rule(
+                HrRule::concat("userName", ["first", "last"], ".")
+                    .with_transform(HrTransform::Lowercase)
+                    .with_transform(HrTransform::Suffix("@example.com".to_string())),
+            )
+            .with_rule(
+                HrRule::new("title", "jobCode")
+                    .with_transform(HrTransform::Map(BTreeMap::from([(
+                        "ENG2".to_string(),
+                        "Engineer II".to_string(),
+                    )])))
+                    .with_transform(HrTransform::Default("Staff".to_string())),
+            )
+            .with_rule(HrRule::new("emails[type eq \"work\"].value", "email"));
+
+        let record = json!({"first": "Barbara", "last": "Jensen", "jobCode": "ENG2", "email": "babs@example.com"});
+        let user = User::try_from(mapping.apply(&record).unwrap()).unwrap();
+        assert_eq!(user.user_name, "barbara.jensen@example.com");
+        assert_eq!(user.title.as_deref(), Some("Engineer II"));
+        assert_eq!(user.emails.unwrap()[0].r#type.as_deref(), Some("work"));
+
+        let record = json!({"first": "Jim", "last": "Smith"});
+        let user = User::try_from(mapping.apply(&record).unwrap()).unwrap();
+        assert_eq!(user.title.as_deref(), Some("Staff"));
+        assert!(user.emails.is_none());
+    }
+
+    #[test]
+    fn reformats_dates() {
+        let transform = HrTransform::DateFormat {
+            from: "%m/%d/%Y".to_string(),
+            to: "%Y-%m-%dT%H:%M:%SZ".to_string(),
+        };
+        assert_eq!(
+            transform.apply("3/7/2024".to_string()).unwrap(),
+            "2024-03-07T00:00:00Z"
+        );
+        assert!(matches!(
+            transform.apply("2024-03-07".to_string()),
+            Err(SCIMError::InvalidFieldValue(_))
+        ));
+        assert!(matches!(
+            transform.apply("13/7/2024".to_string()),
+            Err(SCIMError::InvalidFieldValue(_))
+        ));
+    }
+
+    #[test]
+    fn deserializes_configuration() {
+        let mapping: HrMapping = serde_json::from_value(json!({
+            "resourceType": "User",
+            "rules": [
+                {"target": "userName", "source": "login", "transforms": ["trim", {"prefix": "hr-"}]},
+                {"target": "displayName", "source": ["a", "b"], "separator": ", "}
+            ]
+        }))
+        .unwrap();
+        assert_eq!(
+            mapping.rules[0].transforms,
+            [HrTransform::Trim, HrTransform::Prefix("hr-".to_string())]
+        );
+        assert_eq!(
+            mapping.rules[1].source,
+            HrSource::Concat(vec!["a".to_string(), "b".to_string()])
+        );
+
+        let unknown = HrMapping::new("User").with_rule(HrRule::new("nickName.nope", "x"));
+        assert!(matches!(
+            unknown.apply(&json!({"x": "y"})),
+            Err(SCIMError::InvalidFieldValue(_))
+        ));
+    }
+}
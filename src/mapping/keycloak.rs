@@ -0,0 +1,363 @@
+//! Conversion between Keycloak's `UserRepresentation` and SCIM `User` resources.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::mapping::common::primary_or_first;
+use crate::models::enterprise_user::EnterpriseUser;
+use crate::models::extension::ScimExtension;
+use crate::models::user::{Email, Name, PhoneNumber, User};
+use crate::utils::time::format_rfc3339;
+use crate::values;
+
+/// Schema URI of the extension that carries Keycloak data without a counterpart in the SCIM core schemas.
+pub const KEYCLOAK_USER_URN: &str = "urn:keycloak:params:scim:schemas:extension:keycloak:2.0:User";
+
+/// A user as represented by the Keycloak Admin REST API (`UserRepresentation`).
+///
+/// Only the fields relevant to provisioning are modeled; unknown fields are ignored when deserializing.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct KeycloakUser {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email_verified: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    /// Milliseconds since the Unix epoch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_timestamp: Option<u64>,
+    /// Custom user attributes. Keycloak stores every attribute as a list of strings.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attributes: Option<BTreeMap<String, Vec<String>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub federated_identities: Option<Vec<FederatedIdentity>>,
+}
+
+/// A link between a Keycloak user and an account at an external identity provider.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FederatedIdentity {
+    /// The alias of the identity provider, e.g. `google` or `corporate-saml`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identity_provider: Option<String>,
+    /// The user's identifier at the identity provider.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    /// The user's name at the identity provider.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_name: Option<String>,
+}
+
+/// The Keycloak data that has no counterpart in the SCIM core and enterprise schemas.
+///
+/// It is attached to users as a schema extension under [`KEYCLOAK_USER_URN`], so that a round trip through
+/// SCIM does not lose federated identity links or custom attributes.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct KeycloakExtension {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email_verified: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub federated_identities: Option<Vec<FederatedIdentity>>,
+    /// Custom attributes that are not mapped to a SCIM attribute.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attributes: Option<BTreeMap<String, Vec<String>>>,
+}
+
+impl ScimExtension for KeycloakExtension {
+    const URN: &'static str = KEYCLOAK_USER_URN;
+
+    fn attribute_names() -> &'static [&'static str] {
+        &["emailVerified", "federatedIdentities", "attributes"]
+    }
+}
+
+// Keycloak attributes with a SCIM counterpart, besides the fields of `UserRepresentation` itself.
+const MAPPED_ATTRIBUTES: [&str; 11] = [
+    "phoneNumber",
+    "locale",
+    "zoneinfo",
+    "nickname",
+    "title",
+    "employeeNumber",
+    "costCenter",
+    "organization",
+    "division",
+    "department",
+    "middleName",
+];
+
+/// Converts a Keycloak user into a SCIM `User`.
+///
+/// The standard fields map to `id`, `userName`, `name`, `emails` (as the primary address), `active` and
+/// `meta.created`. Custom attributes following the OpenID Connect and Keycloak user profile conventions are
+/// mapped as well: `phoneNumber`, `locale`, `zoneinfo`, `nickname`, `middleName` and `title` to the core
+/// schema, and `employeeNumber`, `costCenter`, `organization`, `division` and `department` to the enterprise
+/// extension. Keycloak's lists are reduced to their first value. Use [`KeycloakExtension::from`] to keep the
+/// remaining data.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::mapping::keycloak::{KeycloakUser, to_user};
+/// use serde_json::json;
+///
+/// let keycloak: KeycloakUser = serde_json::from_value(json!({
+///     "id": "2819c223",
+///     "username": "bjensen",
+///     "firstName": "Barbara",
+///     "email": "bjensen@example.com",
+///     "enabled": true,
+///     "attributes": {"department": ["Tour Operations"], "locale": ["en-US"]}
+/// }))
+/// .unwrap();
+///
+/// let user = to_user(&keycloak);
+/// assert_eq!(user.user_name, "bjensen");
+/// assert_eq!(user.locale.as_deref(), Some("en-US"));
+/// assert_eq!(user.enterprise_user.unwrap().department.as_deref(), Some("Tour Operations"));
+/// ```
+pub fn to_user(keycloak: &KeycloakUser) -> User {
+    let attribute = |name: &str| {
+        keycloak
+            .attributes
+            .as_ref()
+            .and_then(|attributes| attributes.get(name))
+            .and_then(|values| values.first())
+            .cloned()
+    };
+
+    let mut user = User {
+        id: keycloak.id.as_deref().map(Into::into),
+        user_name: keycloak.username.clone().unwrap_or_default(),
+        active: keycloak.enabled,
+        locale: attribute("locale"),
+        timezone: attribute("zoneinfo"),
+        nick_name: attribute("nickname"),
+        title: attribute("title"),
+        ..Default::default()
+    };
+    let middle_name = attribute("middleName");
+    if keycloak.first_name.is_some() || keycloak.last_name.is_some() || middle_name.is_some() {
+        user.name = Some(Name {
+            given_name: keycloak.first_name.clone(),
+            family_name: keycloak.last_name.clone(),
+            middle_name,
+            ..Default::default()
+        });
+        user.display_name = [&keycloak.first_name, &keycloak.last_name]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .reduce(|first, last| format!("{} {}", first, last));
+    }
+    if let Some(email) = &keycloak.email {
+        user.emails = Some(values![Email {
+            value: Some(email.clone()),
+            primary: Some(true),
+            ..Default::default()
+        }]);
+    }
+    if let Some(phone) = attribute("phoneNumber") {
+        user.phone_numbers = Some(values![PhoneNumber {
+            value: Some(phone),
+            primary: Some(true),
+            ..Default::default()
+        }]);
+    }
+    if let Some(created) = keycloak.created_timestamp {
+        let meta = user.meta.get_or_insert_with(Default::default);
+        meta.resource_type = Some("User".to_string());
+        meta.created = Some(format_rfc3339(created / 1000));
+    }
+
+    let enterprise = EnterpriseUser {
+        employee_number: attribute("employeeNumber"),
+        cost_center: attribute("costCenter"),
+        organization: attribute("organization"),
+        division: attribute("division"),
+        department: attribute("department"),
+        manager: None,
+    };
+    let has_enterprise = enterprise.employee_number.is_some()
+        || enterprise.cost_center.is_some()
+        || enterprise.organization.is_some()
+        || enterprise.division.is_some()
+        || enterprise.department.is_some();
+    if has_enterprise {
+        user.schemas.push(EnterpriseUser::URN.to_string());
+        user.enterprise_user = Some(enterprise);
+    }
+    user
+}
+
+/// Converts a SCIM `User`, and optionally the Keycloak data kept from an earlier conversion, into a Keycloak
+/// user. This is the reverse of [`to_user`]: the primary (or first) e-mail address and phone number are used,
+/// and the enterprise extension attributes become custom attributes.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::mapping::keycloak::{to_keycloak, to_user, KeycloakExtension, KeycloakUser, FederatedIdentity};
+///
+/// let keycloak = KeycloakUser {
+///     username: Some("bjensen".to_string()),
+///     federated_identities: Some(vec![FederatedIdentity {
+///         identity_provider: Some("google".to_string()),
+///         user_id: Some("108159262817396487001".to_string()),
+///         ..Default::default()
+///     }]),
+///     ..Default::default()
+/// };
+/// let user = to_user(&keycloak);
+/// let extension = KeycloakExtension::from(&keycloak);
+///
+/// assert_eq!(to_keycloak(&user, Some(&extension)), keycloak);
+/// ```
+pub fn to_keycloak(user: &User, extension: Option<&KeycloakExtension>) -> KeycloakUser {
+    let mut attributes: BTreeMap<String, Vec<String>> = extension
+        .and_then(|e| e.attributes.clone())
+        .unwrap_or_default();
+    let mut set = |name: &str, value: Option<&String>| {
+        if let Some(value) = value {
+            attributes.insert(name.to_string(), vec![value.clone()]);
+        }
+    };
+    set("locale", user.locale.as_ref());
+    set("zoneinfo", user.timezone.as_ref());
+    set("nickname", user.nick_name.as_ref());
+    set("title", user.title.as_ref());
+    set(
+        "middleName",
+        user.name.as_ref().and_then(|n| n.middle_name.as_ref()),
+    );
+    let phone = primary_or_first(user.phone_numbers.as_deref(), |p| p.primary, |p| &p.value);
+    set("phoneNumber", phone);
+    if let Some(enterprise) = &user.enterprise_user {
+        set("employeeNumber", enterprise.employee_number.as_ref());
+        set("costCenter", enterprise.cost_center.as_ref());
+        set("organization", enterprise.organization.as_ref());
+        set("division", enterprise.division.as_ref());
+        set("department", enterprise.department.as_ref());
+    }
+
+    KeycloakUser {
+        id: user.id.as_ref().map(|id| id.as_str().to_string()),
+        username: Some(user.user_name.clone()).filter(|u| !u.is_empty()),
+        first_name: user.name.as_ref().and_then(|n| n.given_name.clone()),
+        last_name: user.name.as_ref().and_then(|n| n.family_name.clone()),
+        email: primary_or_first(user.emails.as_deref(), |e| e.primary, |e| &e.value).cloned(),
+        email_verified: extension.and_then(|e| e.email_verified),
+        enabled: user.active,
+        created_timestamp: None,
+        attributes: Some(attributes).filter(|a| !a.is_empty()),
+        federated_identities: extension.and_then(|e| e.federated_identities.clone()),
+    }
+}
+
+/// Collects the Keycloak data that [`to_user`] cannot map: the e-mail verification flag, federated
+/// identities and custom attributes without a SCIM counterpart.
+impl From<&KeycloakUser> for KeycloakExtension {
+    fn from(keycloak: &KeycloakUser) -> Self {
+        let attributes: BTreeMap<String, Vec<String>> = keycloak
+            .attributes
+            .iter()
+            .flatten()
+            .filter(|(name, _)| !MAPPED_ATTRIBUTES.contains(&name.as_str()))
+            .map(|(name, values)| (name.clone(), values.clone()))
+            .collect();
+        KeycloakExtension {
+            email_verified: keycloak.email_verified,
+            federated_identities: keycloak.federated_identities.clone(),
+            attributes: Some(attributes).filter(|a| !a.is_empty()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn keycloak_user() -> KeycloakUser {
+        serde_json::from_value(json!({
+            "id": "2819c223-7f76-453a-919d-413861904646",
+            "username": "bjensen",
+            "firstName": "Barbara",
+            "lastName": "Jensen",
+            "email": "bjensen@example.com",
+            "emailVerified": true,
+            "enabled": true,
+            "createdTimestamp": 1312223389000u64,
+            "attributes": {
+                "phoneNumber": ["+1 555 555 8377"],
+                "employeeNumber": ["701984"],
+                "shoeSize": ["42"]
+            },
+            "federatedIdentities": [{"identityProvider": "corporate-saml", "userId": "bjensen@corp", "userName": "bjensen"}],
+            "access": {"manage": true}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn maps_standard_fields_and_attributes() {
+        let user = to_user(&keycloak_user());
+        assert!(user.validate().is_ok());
+        assert_eq!(user.display_name.as_deref(), Some("Barbara Jensen"));
+        assert_eq!(
+            user.meta.unwrap().created.as_deref(),
+            Some("2011-08-01T18:29:49Z")
+        );
+        assert_eq!(
+            user.phone_numbers.unwrap()[0].value.as_deref(),
+            Some("+1 555 555 8377")
+        );
+        assert_eq!(user.schemas.len(), 2);
+        assert_eq!(
+            user.enterprise_user.unwrap().employee_number.as_deref(),
+            Some("701984")
+        );
+    }
+
+    #[test]
+    fn unmapped_data_round_trips_through_the_extension() {
+        let keycloak = keycloak_user();
+        let extension = KeycloakExtension::from(&keycloak);
+        assert_eq!(
+            extension.attributes,
+            Some(BTreeMap::from([(
+                "shoeSize".to_string(),
+                vec!["42".to_string()]
+            )]))
+        );
+
+        let mut resource = serde_json::to_value(to_user(&keycloak)).unwrap();
+        extension.insert_into(&mut resource).unwrap();
+        let read_back = KeycloakExtension::from_resource(&resource)
+            .unwrap()
+            .unwrap();
+        let user = User::try_from(resource).unwrap();
+
+        let converted = to_keycloak(&user, Some(&read_back));
+        assert_eq!(
+            converted,
+            KeycloakUser {
+                created_timestamp: None,
+                ..keycloak
+            }
+        );
+    }
+}
@@ -0,0 +1,375 @@
+//! Mapping of `User` resources to SAML 2.0 attribute statements.
+
+use std::fmt;
+use std::fmt::Write;
+
+use serde_json::Value;
+
+use crate::mapping::common::{scalar_text, values_at};
+use crate::models::schema_uri::ENTERPRISE_USER_URN;
+use crate::models::user::User;
+use crate::utils::error::SCIMError;
+
+/// How the `Name` of a SAML attribute is to be interpreted (SAML 2.0 Core, section 8.2).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NameFormat {
+    /// `urn:oasis:names:tc:SAML:2.0:attrname-format:unspecified`
+    Unspecified,
+    /// `urn:oasis:names:tc:SAML:2.0:attrname-format:uri`, used for OID and claim URI names.
+    Uri,
+    /// `urn:oasis:names:tc:SAML:2.0:attrname-format:basic`, used for simple names such as `mail`.
+    Basic,
+}
+
+impl NameFormat {
+    /// Returns the URI that identifies the name format in the `NameFormat` XML attribute.
+    pub fn as_uri(self) -> &'static str {
+        match self {
+            NameFormat::Unspecified => "urn:oasis:names:tc:SAML:2.0:attrname-format:unspecified",
+            NameFormat::Uri => "urn:oasis:names:tc:SAML:2.0:attrname-format:uri",
+            NameFormat::Basic => "urn:oasis:names:tc:SAML:2.0:attrname-format:basic",
+        }
+    }
+}
+
+impl fmt::Display for NameFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_uri())
+    }
+}
+
+/// A SAML attribute, as emitted in an `<AttributeStatement>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SamlAttribute {
+    pub name: String,
+    pub name_format: NameFormat,
+    pub friendly_name: Option<String>,
+    /// The attribute values; multi-valued SCIM attributes produce one value per entry, primary first.
+    pub values: Vec<String>,
+}
+
+// A SCIM attribute path mapped to a SAML attribute name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SamlMappingEntry {
+    path: String,
+    name: String,
+    friendly_name: Option<String>,
+}
+
+// (SCIM path, LDAP name, OID) of the attributes in the built-in mappings; see RFC 4519 and RFC 2798.
+const DIRECTORY_ATTRIBUTES: [(&str, &str, &str); 12] = [
+    ("userName", "uid", "urn:oid:0.9.2342.19200300.100.1.1"),
+    ("emails.value", "mail", "urn:oid:0.9.2342.19200300.100.1.3"),
+    ("name.givenName", "givenName", "urn:oid:2.5.4.42"),
+    ("name.familyName", "sn", "urn:oid:2.5.4.4"),
+    (
+        "displayName",
+        "displayName",
+        "urn:oid:2.16.840.1.113730.3.1.241",
+    ),
+    ("phoneNumbers.value", "telephoneNumber", "urn:oid:2.5.4.20"),
+    ("title", "title", "urn:oid:2.5.4.12"),
+    (
+        "preferredLanguage",
+        "preferredLanguage",
+        "urn:oid:2.16.840.1.113730.3.1.39",
+    ),
+    (
+        "groups.display",
+        "memberOf",
+        "urn:oid:1.2.840.113556.1.2.102",
+    ),
+    (
+        "employeeNumber",
+        "employeeNumber",
+        "urn:oid:2.16.840.1.113730.3.1.3",
+    ),
+    ("organization", "o", "urn:oid:2.5.4.10"),
+    ("department", "ou", "urn:oid:2.5.4.11"),
+];
+
+// Claim types of the WS-Federation / ADFS claims profile, which Microsoft relying parties expect.
+const CLAIMS: [(&str, &str); 6] = [
+    (
+        "userName",
+        "http://schemas.xmlsoap.org/ws/2005/05/identity/claims/name",
+    ),
+    (
+        "emails.value",
+        "http://schemas.xmlsoap.org/ws/2005/05/identity/claims/emailaddress",
+    ),
+    (
+        "name.givenName",
+        "http://schemas.xmlsoap.org/ws/2005/05/identity/claims/givenname",
+    ),
+    (
+        "name.familyName",
+        "http://schemas.xmlsoap.org/ws/2005/05/identity/claims/surname",
+    ),
+    (
+        "displayName",
+        "http://schemas.microsoft.com/identity/claims/displayname",
+    ),
+    (
+        "groups.display",
+        "http://schemas.microsoft.com/ws/2008/06/identity/claims/groups",
+    ),
+];
+
+/// Maps SCIM attribute paths of a `User` to SAML attributes.
+///
+/// Identity providers backed by a SCIM store use a mapping to emit the attribute statement of an assertion
+/// straight from the user's resource. The built-in mappings cover the common attribute naming profiles;
+/// [`SamlMapping::with_attribute`] adds or overrides attributes for a specific service provider.
+///
+/// Paths are resolved like attribute paths in SCIM filters: `name.givenName`, `emails.value` (every
+/// e-mail address, primary first) or `urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:department`.
+/// Attributes without values are left out of the statement.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::mapping::saml::SamlMapping;
+/// use scim_v2::models::user::{Email, User};
+/// use scim_v2::values;
+///
+/// let user = User {
+///     user_name: "bjensen".to_string(),
+///     emails: Some(values![Email { value: Some("bjensen@example.com".to_string()), ..Default::default() }]),
+///     ..Default::default()
+/// };
+/// let attributes = SamlMapping::basic()
+///     .with_attribute("active", "accountEnabled", None)
+///     .attributes(&user)
+///     .unwrap();
+/// assert_eq!(attributes[1].name, "mail");
+/// assert_eq!(attributes[1].values, ["bjensen@example.com"]);
+///
+/// let xml = SamlMapping::oid().attribute_statement(&user).unwrap();
+/// assert!(xml.contains(r#"<saml:Attribute Name="urn:oid:0.9.2342.19200300.100.1.3" NameFormat="urn:oasis:names:tc:SAML:2.0:attrname-format:uri" FriendlyName="mail">"#));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SamlMapping {
+    name_format: NameFormat,
+    entries: Vec<SamlMappingEntry>,
+}
+
+impl SamlMapping {
+    /// Creates an empty mapping whose attributes use the given name format.
+    pub fn new(name_format: NameFormat) -> Self {
+        SamlMapping {
+            name_format,
+            entries: Vec::new(),
+        }
+    }
+
+    /// The attributes of the SAML V2.0 X.500/LDAP attribute profile: OID names (`urn:oid:2.5.4.42`) in the
+    /// `uri` name format, with the LDAP names (`givenName`) as friendly names. This is what academic
+    /// federations and most enterprise service providers expect.
+    pub fn oid() -> Self {
+        let mut mapping = SamlMapping::new(NameFormat::Uri);
+        for (path, ldap_name, oid) in DIRECTORY_ATTRIBUTES {
+            mapping = mapping.with_attribute(&qualify(path), oid, Some(ldap_name));
+        }
+        mapping
+    }
+
+    /// The same attributes as [`SamlMapping::oid`], named by their LDAP names (`givenName`) in the `basic`
+    /// name format.
+    pub fn basic() -> Self {
+        let mut mapping = SamlMapping::new(NameFormat::Basic);
+        for (path, ldap_name, _) in DIRECTORY_ATTRIBUTES {
+            mapping = mapping.with_attribute(&qualify(path), ldap_name, None);
+        }
+        mapping
+    }
+
+    /// The claim types of the WS-Federation claims profile
+    /// (`http://schemas.xmlsoap.org/ws/2005/05/identity/claims/emailaddress`), in the `uri` name format.
+    pub fn claims() -> Self {
+        let mut mapping = SamlMapping::new(NameFormat::Uri);
+        for (path, claim) in CLAIMS {
+            mapping = mapping.with_attribute(path, claim, None);
+        }
+        mapping
+    }
+
+    /// Maps a SCIM attribute path to a SAML attribute. An existing mapping for the same SAML attribute name
+    /// is replaced.
+    pub fn with_attribute(mut self, path: &str, name: &str, friendly_name: Option<&str>) -> Self {
+        let entry = SamlMappingEntry {
+            path: path.to_string(),
+            name: name.to_string(),
+            friendly_name: friendly_name.map(str::to_string),
+        };
+        match self.entries.iter_mut().find(|e| e.name == name) {
+            Some(existing) => *existing = entry,
+            None => self.entries.push(entry),
+        }
+        self
+    }
+
+    /// Removes the SAML attribute with the given name from the mapping.
+    pub fn without_attribute(mut self, name: &str) -> Self {
+        self.entries.retain(|e| e.name != name);
+        self
+    }
+
+    /// Returns the SAML attributes for a user, in mapping order.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if the user cannot be serialized.
+    pub fn attributes(&self, user: &User) -> Result<Vec<SamlAttribute>, SCIMError> {
+        let resource = serde_json::to_value(user).map_err(SCIMError::SerializationError)?;
+        Ok(self.attributes_of(&resource))
+    }
+
+    /// Returns the SAML attributes for a resource representation, which may carry schema extensions that the
+    /// `User` struct does not model.
+    pub fn attributes_of(&self, resource: &Value) -> Vec<SamlAttribute> {
+        self.entries
+            .iter()
+            .filter_map(|entry| {
+                let values: Vec<String> = values_at(resource, &entry.path)
+                    .into_iter()
+                    .filter_map(scalar_text)
+                    .collect();
+                (!values.is_empty()).then(|| SamlAttribute {
+                    name: entry.name.clone(),
+                    name_format: self.name_format,
+                    friendly_name: entry.friendly_name.clone(),
+                    values,
+                })
+            })
+            .collect()
+    }
+
+    /// Renders the `<saml:AttributeStatement>` element for a user, ready to be embedded in an assertion
+    /// before it is signed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if the user cannot be serialized.
+    pub fn attribute_statement(&self, user: &User) -> Result<String, SCIMError> {
+        Ok(render_attribute_statement(&self.attributes(user)?))
+    }
+}
+
+/// Renders SAML attributes as a `<saml:AttributeStatement>` element. Values are typed as `xs:string`.
+pub fn render_attribute_statement(attributes: &[SamlAttribute]) -> String {
+    let mut xml = String::from(
+        "<saml:AttributeStatement xmlns:saml=\"urn:oasis:names:tc:SAML:2.0:assertion\" \
+         xmlns:xs=\"http://www.w3.org/2001/XMLSchema\" \
+         xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\">",
+    );
+    for attribute in attributes {
+        let _ = write!(
+            xml,
+            "<saml:Attribute Name=\"{}\" NameFormat=\"{}\"",
+            escape_xml(&attribute.name),
+            attribute.name_format
+        );
+        if let Some(friendly_name) = &attribute.friendly_name {
+            let _ = write!(xml, " FriendlyName=\"{}\"", escape_xml(friendly_name));
+        }
+        xml.push('>');
+        for value in &attribute.values {
+            let _ = write!(
+                xml,
+                "<saml:AttributeValue xsi:type=\"xs:string\">{}</saml:AttributeValue>",
+                escape_xml(value)
+            );
+        }
+        xml.push_str("</saml:Attribute>");
+    }
+    xml.push_str("</saml:AttributeStatement>");
+    xml
+}
+
+// The directory attributes of the enterprise extension live under its schema URI.
+fn qualify(path: &str) -> String {
+    match path {
+        "employeeNumber" | "organization" | "department" => {
+            format!("{}:{}", ENTERPRISE_USER_URN, path)
+        }
+        path => path.to_string(),
+    }
+}
+
+fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::enterprise_user::EnterpriseUser;
+    use crate::models::user::{Email, Group};
+    use crate::values;
+
+    fn user() -> User {
+        User {
+            user_name: "bjensen".to_string(),
+            display_name: Some("Babs \"B\" Jensen & Co".to_string()),
+            emails: Some(values![
+                Email {
+                    value: Some("babs@example.org".to_string()),
+                    ..Default::default()
+                },
+                Email {
+                    value: Some("bjensen@example.com".to_string()),
+                    primary: Some(true),
+                    ..Default::default()
+                },
+            ]),
+            groups: Some(values![Group {
+                display: Some("Tour Guides".to_string()),
+                ..Default::default()
+            }]),
+            enterprise_user: Some(EnterpriseUser {
+                department: Some("Tour Operations".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn maps_multi_valued_and_extension_attributes() {
+        let attributes = SamlMapping::basic().attributes(&user()).unwrap();
+        let names: Vec<&str> = attributes.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, ["uid", "mail", "displayName", "memberOf", "ou"]);
+        assert_eq!(
+            attributes[1].values,
+            ["bjensen@example.com", "babs@example.org"]
+        );
+        assert_eq!(attributes[4].values, ["Tour Operations"]);
+    }
+
+    #[test]
+    fn renders_escaped_xml() {
+        let mapping = SamlMapping::claims()
+            .without_attribute("http://schemas.microsoft.com/ws/2008/06/identity/claims/groups")
+            .with_attribute("displayName", "displayName", None);
+        let attributes = mapping.attributes(&user()).unwrap();
+        assert_eq!(attributes.len(), 4);
+        let xml = render_attribute_statement(&attributes[3..]);
+        assert!(xml.ends_with(
+            "<saml:Attribute Name=\"displayName\" NameFormat=\"urn:oasis:names:tc:SAML:2.0:attrname-format:uri\">\
+             <saml:AttributeValue xsi:type=\"xs:string\">Babs &quot;B&quot; Jensen &amp; Co</saml:AttributeValue>\
+             </saml:Attribute></saml:AttributeStatement>"
+        ));
+    }
+}
@@ -0,0 +1,536 @@
+//! Conversion between `User` resources and vCard 4.0 (RFC 6350).
+
+use std::fmt::Write;
+
+use crate::models::user::{Address, Email, Name, PhoneNumber, Photo, User};
+use crate::utils::error::SCIMError;
+
+// RFC 6350, section 3.2: lines longer than 75 octets should be folded.
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Exports a user as a vCard 4.0 (RFC 6350).
+///
+/// The card carries the user's name (`FN`, `N`, `NICKNAME`), title, preferred language, time zone, e-mail
+/// addresses, phone numbers, postal addresses and photos. The SCIM `type` of multi-valued attributes becomes
+/// the `TYPE` parameter, and the primary value gets `PREF=1`. The user's `id` is used as the card's `UID`.
+/// Cards of several users can simply be concatenated to export a directory.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::mapping::vcard::to_vcard;
+/// use scim_v2::models::user::{Email, User};
+/// use scim_v2::values;
+///
+/// let user = User {
+///     user_name: "bjensen".to_string(),
+///     display_name: Some("Babs Jensen".to_string()),
+///     emails: Some(values![Email {
+///         value: Some("bjensen@example.com".to_string()),
+///         r#type: Some("work".to_string()),
+///         primary: Some(true),
+///         ..Default::default()
+///     }]),
+///     ..Default::default()
+/// };
+/// let card = to_vcard(&user);
+/// assert!(card.starts_with("BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Babs Jensen\r\n"));
+/// assert!(card.contains("EMAIL;TYPE=work;PREF=1:bjensen@example.com\r\n"));
+/// ```
+pub fn to_vcard(user: &User) -> String {
+    let mut lines = vec!["BEGIN:VCARD".to_string(), "VERSION:4.0".to_string()];
+
+    let formatted_name = user
+        .display_name
+        .as_deref()
+        .or_else(|| user.name.as_ref().and_then(|n| n.formatted.as_deref()))
+        .unwrap_or(&user.user_name);
+    lines.push(format!("FN:{}", escape(formatted_name)));
+    if let Some(name) = &user.name {
+        let components = [
+            &name.family_name,
+            &name.given_name,
+            &name.middle_name,
+            &name.honorific_prefix,
+            &name.honorific_suffix,
+        ];
+        let components: Vec<String> = components
+            .iter()
+            .map(|c| escape(c.as_deref().unwrap_or_default()))
+            .collect();
+        lines.push(format!("N:{}", components.join(";")));
+    }
+    for (property, value) in [
+        ("NICKNAME", &user.nick_name),
+        ("TITLE", &user.title),
+        ("LANG", &user.preferred_language),
+        ("TZ", &user.timezone),
+    ] {
+        if let Some(value) = value {
+            lines.push(format!("{}:{}", property, escape(value)));
+        }
+    }
+    if let Some(id) = &user.id {
+        lines.push(format!("UID:{}", escape(id.as_str())));
+    }
+    for email in user.emails.iter().flatten() {
+        if let Some(value) = &email.value {
+            let params = parameters(email.r#type.as_deref(), email.primary);
+            lines.push(format!("EMAIL{}:{}", params, escape(value)));
+        }
+    }
+    for phone in user.phone_numbers.iter().flatten() {
+        if let Some(value) = &phone.value {
+            let kind = phone.r#type.as_deref().map(|t| match t {
+                "mobile" => "cell",
+                t => t,
+            });
+            let params = parameters(kind, phone.primary);
+            lines.push(format!("TEL;VALUE=uri{}:{}", params, telephone_uri(value)));
+        }
+    }
+    for address in user.addresses.iter().flatten() {
+        let components = [
+            &address.street_address,
+            &address.locality,
+            &address.region,
+            &address.postal_code,
+            &address.country,
+        ];
+        let components: Vec<String> = components
+            .iter()
+            .map(|c| escape(c.as_deref().unwrap_or_default()))
+            .collect();
+        let mut params = parameters(address.r#type.as_deref(), None);
+        if let Some(formatted) = &address.formatted {
+            let _ = write!(
+                params,
+                ";LABEL=\"{}\"",
+                formatted.replace('"', "'").replace('\n', "\\n")
+            );
+        }
+        lines.push(format!("ADR{}:;;{}", params, components.join(";")));
+    }
+    for photo in user.photos.iter().flatten() {
+        if let Some(value) = &photo.value {
+            let params = parameters(photo.r#type.as_deref(), photo.primary);
+            lines.push(format!("PHOTO{}:{}", params, value));
+        }
+    }
+    lines.push("END:VCARD".to_string());
+
+    let mut card = String::new();
+    for line in lines {
+        card.push_str(&fold(&line));
+        card.push_str("\r\n");
+    }
+    card
+}
+
+/// Imports users from one or more vCards.
+///
+/// vCard 3.0 and 4.0 cards are accepted. Folded lines are unfolded, property groups (`item1.EMAIL`) are
+/// ignored, and properties this mapping does not cover are skipped. Since vCards have no user name, the
+/// `userName` is taken from the first e-mail address, falling back to the formatted name. The card's `UID`
+/// becomes the `externalId`, since `id` is assigned by the service provider.
+///
+/// # Errors
+///
+/// Returns `SCIMError::InvalidFieldValue` if the input contains no card, if a card is not terminated by
+/// `END:VCARD`, or if a card has neither an e-mail address nor a formatted name to derive the `userName`
+/// from.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::mapping::vcard::from_vcards;
+///
+/// let card = "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Babs Jensen\r\nN:Jensen;Barbara;;;\r\n\
+///             TEL;VALUE=uri;TYPE=cell:tel:+1-555-555-8377\r\nEMAIL;TYPE=work:bjensen@example.com\r\nEND:VCARD\r\n";
+/// let users = from_vcards(card).unwrap();
+/// assert_eq!(users[0].user_name, "bjensen@example.com");
+/// assert_eq!(users[0].name.as_ref().unwrap().given_name.as_deref(), Some("Barbara"));
+/// let phone = &users[0].phone_numbers.as_ref().unwrap()[0];
+/// assert_eq!(phone.value.as_deref(), Some("+1-555-555-8377"));
+/// assert_eq!(phone.r#type.as_deref(), Some("mobile"));
+/// ```
+pub fn from_vcards(input: &str) -> Result<Vec<User>, SCIMError> {
+    let mut users = Vec::new();
+    let mut card: Option<Vec<Property>> = None;
+    for line in unfold(input) {
+        let Some(property) = Property::parse(&line) else {
+            continue;
+        };
+        match (property.name.as_str(), property.value.as_str(), &mut card) {
+            ("BEGIN", value, _) if value.eq_ignore_ascii_case("VCARD") => card = Some(Vec::new()),
+            ("END", value, Some(_)) if value.eq_ignore_ascii_case("VCARD") => {
+                users.push(user_from_properties(card.take().unwrap_or_default())?);
+            }
+            (_, _, Some(properties)) => properties.push(property),
+            _ => {}
+        }
+    }
+    if card.is_some() {
+        return Err(SCIMError::InvalidFieldValue(
+            "vCard is missing END:VCARD".into(),
+        ));
+    }
+    if users.is_empty() {
+        return Err(SCIMError::InvalidFieldValue(
+            "input contains no vCard".into(),
+        ));
+    }
+    Ok(users)
+}
+
+fn user_from_properties(properties: Vec<Property>) -> Result<User, SCIMError> {
+    let mut user = User::default();
+    for property in properties {
+        let kind = property.kind();
+        let primary = property.preferred().then_some(true);
+        match property.name.as_str() {
+            "FN" => user.display_name = Some(unescape(&property.value)),
+            "N" => {
+                let mut components = split_components(&property.value).into_iter();
+                let mut next = || components.next().filter(|c| !c.is_empty());
+                user.name = Some(Name {
+                    family_name: next(),
+                    given_name: next(),
+                    middle_name: next(),
+                    honorific_prefix: next(),
+                    honorific_suffix: next(),
+                    ..Default::default()
+                });
+            }
+            "NICKNAME" => user.nick_name = Some(unescape(&property.value)),
+            "TITLE" => user.title = Some(unescape(&property.value)),
+            "LANG" => user.preferred_language = Some(unescape(&property.value)),
+            "TZ" => user.timezone = Some(unescape(&property.value)),
+            "UID" => user.external_id = Some(unescape(&property.value)),
+            "EMAIL" => user
+                .emails
+                .get_or_insert_with(Default::default)
+                .push(Email {
+                    value: Some(unescape(&property.value)),
+                    r#type: kind,
+                    primary,
+                    ..Default::default()
+                }),
+            "TEL" => {
+                let value = unescape(&property.value);
+                let value = value.strip_prefix("tel:").unwrap_or(&value).to_string();
+                let kind = kind.map(|t| if t == "cell" { "mobile".to_string() } else { t });
+                user.phone_numbers
+                    .get_or_insert_with(Default::default)
+                    .push(PhoneNumber {
+                        value: Some(value),
+                        r#type: kind,
+                        primary,
+                        ..Default::default()
+                    });
+            }
+            "ADR" => {
+                // Post office box and extended address are not part of the SCIM address.
+                let mut components = split_components(&property.value).into_iter().skip(2);
+                let mut next = || components.next().filter(|c| !c.is_empty());
+                user.addresses
+                    .get_or_insert_with(Default::default)
+                    .push(Address {
+                        street_address: next(),
+                        locality: next(),
+                        region: next(),
+                        postal_code: next(),
+                        country: next(),
+                        formatted: property
+                            .parameter("LABEL")
+                            .map(|label| label.replace("\\n", "\n")),
+                        r#type: kind,
+                    });
+            }
+            "PHOTO" => user
+                .photos
+                .get_or_insert_with(Default::default)
+                .push(Photo {
+                    value: Some(property.value.clone()),
+                    r#type: kind,
+                    primary,
+                    ..Default::default()
+                }),
+            _ => {}
+        }
+    }
+    let user_name = user
+        .emails
+        .iter()
+        .flatten()
+        .find_map(|e| e.value.clone())
+        .or_else(|| user.display_name.clone())
+        .ok_or_else(|| {
+            SCIMError::InvalidFieldValue(
+                "vCard has neither EMAIL nor FN to derive a userName from".into(),
+            )
+        })?;
+    user.user_name = user_name;
+    Ok(user)
+}
+
+/// A content line of a vCard: `[group.]NAME[;PARAM=value...]:value`.
+struct Property {
+    name: String,
+    parameters: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn parse(line: &str) -> Option<Property> {
+        let mut quoted = false;
+        let colon = line.char_indices().find_map(|(i, c)| match c {
+            '"' => {
+                quoted = !quoted;
+                None
+            }
+            ':' if !quoted => Some(i),
+            _ => None,
+        })?;
+        let (head, value) = (&line[..colon], &line[colon + 1..]);
+        let mut parts = head.split(';');
+        let name = parts.next()?;
+        let name = name.rsplit('.').next().unwrap_or(name).to_ascii_uppercase();
+        let parameters = parts
+            .filter_map(|p| p.split_once('='))
+            .map(|(k, v)| (k.to_ascii_uppercase(), v.trim_matches('"').to_string()))
+            .collect();
+        Some(Property {
+            name,
+            parameters,
+            value: value.to_string(),
+        })
+    }
+
+    fn parameter(&self, name: &str) -> Option<&str> {
+        self.parameters
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// The first `TYPE` that describes the value's purpose, lowercased. `voice`, `pref` and `internet` are
+    /// vCard 3.0 leftovers that SCIM has no use for.
+    fn kind(&self) -> Option<String> {
+        self.parameters
+            .iter()
+            .filter(|(k, _)| k == "TYPE")
+            .flat_map(|(_, v)| v.split(','))
+            .map(str::to_ascii_lowercase)
+            .find(|t| !matches!(t.as_str(), "voice" | "pref" | "internet" | "text"))
+    }
+
+    fn preferred(&self) -> bool {
+        self.parameter("PREF") == Some("1")
+            || self
+                .parameters
+                .iter()
+                .any(|(k, v)| k == "TYPE" && v.split(',').any(|t| t.eq_ignore_ascii_case("pref")))
+    }
+}
+
+fn parameters(kind: Option<&str>, primary: Option<bool>) -> String {
+    let mut params = String::new();
+    if let Some(kind) = kind {
+        let _ = write!(params, ";TYPE={}", kind);
+    }
+    if primary == Some(true) {
+        params.push_str(";PREF=1");
+    }
+    params
+}
+
+fn telephone_uri(value: &str) -> String {
+    if value.starts_with("tel:") {
+        value.to_string()
+    } else {
+        format!("tel:{}", value.replace(' ', "-"))
+    }
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ',' => escaped.push_str("\\,"),
+            ';' => escaped.push_str("\\;"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n' | 'N') => unescaped.push('\n'),
+                Some(c) => unescaped.push(c),
+                None => unescaped.push('\\'),
+            }
+        } else {
+            unescaped.push(c);
+        }
+    }
+    unescaped
+}
+
+/// Splits a structured value (`N`, `ADR`) at unescaped semicolons and unescapes the components.
+fn split_components(value: &str) -> Vec<String> {
+    let mut components = vec![String::new()];
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    components.last_mut().unwrap().extend(['\\', next]);
+                }
+            }
+            ';' => components.push(String::new()),
+            c => components.last_mut().unwrap().push(c),
+        }
+    }
+    components.iter().map(|c| unescape(c)).collect()
+}
+
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            // The leading space of a continuation line counts towards its length.
+            octets = 1;
+        }
+        folded.push(c);
+        octets += c.len_utf8();
+    }
+    folded
+}
+
+fn unfold(input: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in input.split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match line.strip_prefix([' ', '\t']) {
+            Some(continuation) if !lines.is_empty() => {
+                lines.last_mut().unwrap().push_str(continuation)
+            }
+            _ if line.is_empty() => {}
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::resource_id::ResourceId;
+    use crate::values;
+
+    fn sample_user() -> User {
+        User {
+            id: Some(ResourceId::new("2819c223-7f76-453a-919d-413861904646")),
+            user_name: "bjensen".to_string(),
+            display_name: Some("Babs Jensen".to_string()),
+            name: Some(Name {
+                family_name: Some("Jensen".to_string()),
+                given_name: Some("Barbara".to_string()),
+                honorific_prefix: Some("Ms.".to_string()),
+                ..Default::default()
+            }),
+            title: Some("Tour Guide, Level 2".to_string()),
+            emails: Some(values![Email {
+                value: Some("bjensen@example.com".to_string()),
+                r#type: Some("work".to_string()),
+                primary: Some(true),
+                ..Default::default()
+            }]),
+            phone_numbers: Some(values![PhoneNumber {
+                value: Some("+1 555 555 8377".to_string()),
+                r#type: Some("mobile".to_string()),
+                ..Default::default()
+            }]),
+            addresses: Some(values![Address {
+                street_address: Some("100 Universal City Plaza".to_string()),
+                locality: Some("Hollywood".to_string()),
+                region: Some("CA".to_string()),
+                postal_code: Some("91608".to_string()),
+                country: Some("US".to_string()),
+                r#type: Some("work".to_string()),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn exported_card_round_trips() {
+        let card = to_vcard(&sample_user());
+        assert!(card.contains("N:Jensen;Barbara;;Ms.;\r\n"));
+        assert!(card.contains("TITLE:Tour Guide\\, Level 2\r\n"));
+        assert!(card.contains("TEL;VALUE=uri;TYPE=cell:tel:+1-555-555-8377\r\n"));
+        assert!(
+            card.contains("ADR;TYPE=work:;;100 Universal City Plaza;Hollywood;CA;91608;US\r\n")
+        );
+
+        let user = from_vcards(&card).unwrap().remove(0);
+        assert_eq!(user.user_name, "bjensen@example.com");
+        assert_eq!(
+            user.external_id.as_deref(),
+            Some("2819c223-7f76-453a-919d-413861904646")
+        );
+        assert_eq!(user.title.as_deref(), Some("Tour Guide, Level 2"));
+        assert_eq!(user.name.unwrap().honorific_prefix.as_deref(), Some("Ms."));
+        assert_eq!(user.emails.unwrap()[0].primary, Some(true));
+        assert_eq!(
+            user.addresses.unwrap()[0].postal_code.as_deref(),
+            Some("91608")
+        );
+    }
+
+    #[test]
+    fn long_lines_are_folded_and_unfolded() {
+        let mut user = sample_user();
+        user.title = Some(
+            "Senior Tour Guide for the Studio Backlot and the Jurassic World Expedition"
+                .to_string(),
+        );
+        let card = to_vcard(&user);
+        assert!(card.split("\r\n").all(|line| line.len() <= MAX_LINE_OCTETS));
+        let imported = from_vcards(&card).unwrap().remove(0);
+        assert_eq!(imported.title, user.title);
+    }
+
+    #[test]
+    fn imports_vcard_3_with_groups_and_reports_bad_input() {
+        let card = "BEGIN:VCARD\nVERSION:3.0\nFN:Babs Jensen\nitem1.EMAIL;TYPE=INTERNET,pref:babs@example.com\n\
+                    TEL;TYPE=WORK,VOICE:+1 555 555 5555\nEND:VCARD\nBEGIN:VCARD\nFN:Jim\nEND:VCARD\n";
+        let users = from_vcards(card).unwrap();
+        assert_eq!(users.len(), 2);
+        let email = &users[0].emails.as_ref().unwrap()[0];
+        assert_eq!(email.r#type, None);
+        assert_eq!(email.primary, Some(true));
+        assert_eq!(
+            users[0].phone_numbers.as_ref().unwrap()[0]
+                .r#type
+                .as_deref(),
+            Some("work")
+        );
+        assert_eq!(users[1].user_name, "Jim");
+
+        assert!(from_vcards("BEGIN:VCARD\nFN:Babs\n").is_err());
+        assert!(from_vcards("").is_err());
+    }
+}
@@ -0,0 +1,256 @@
+//! Masking and pseudonymizing the personal data in SCIM resources.
+//!
+//! An [`AnonymizationPolicy`] turns production resources into exports and test fixtures that do not
+//! identify anyone. Each configured attribute is masked, pseudonymized or removed. Pseudonyms are derived
+//! from the value and a key, so the same value gets the same pseudonym wherever it occurs, in one export
+//! and across exports made with the same key, and references between resources keep matching.
+//!
+//! Pseudonyms are 64-bit FNV-1a hashes of the key and the value. They are meant to keep data out of sight,
+//! not to withstand an attacker who knows the key: keep the key secret, and use a new one when the
+//! pseudonyms must not be linkable to earlier exports.
+
+use serde_json::Value;
+
+use crate::models::attribute_policy::{for_each_mut, keys, remove};
+use crate::utils::etag::fnv1a;
+
+/// What an [`AnonymizationPolicy`] does with an attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Treatment {
+    /// Replaces letters and digits with `*`, keeping the first character and domain of e-mail addresses,
+    /// e.g. `b******@example.com`, and the last two characters of other values longer than four.
+    Mask,
+    /// Replaces the value with a pseudonym of the same kind: e-mail addresses with an address at
+    /// `example.com`, phone numbers with other digits in the same format, other values with a hex string.
+    Pseudonymize,
+    /// Removes the attribute.
+    Remove,
+}
+
+/// The attributes to anonymize, and how.
+///
+/// Attributes are named the way an [`AttributePolicy`](crate::models::attribute_policy::AttributePolicy)
+/// names them, e.g. `emails`, `name.familyName` or an extension URI. Treatments apply to every string below
+/// the attribute, except the `type` and `$ref` of multi-valued attributes, which carry no personal data.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::anonymize::AnonymizationPolicy;
+/// use scim_v2::models::user::User;
+///
+/// let user = User::try_from(
+///     r#"{
+///         "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+///         "userName": "bjensen@example.com",
+///         "emails": [{"value": "bjensen@example.com", "type": "work"}],
+///         "phoneNumbers": [{"value": "+1 555 555 1234", "type": "work"}],
+///         "addresses": [{"locality": "Hollywood", "type": "home"}]
+///     }"#,
+/// )
+/// .unwrap();
+/// let policy = AnonymizationPolicy::personal_data(b"export key");
+/// let anonymized = user.anonymized(&policy).unwrap();
+///
+/// let emails = anonymized.emails.as_ref().unwrap();
+/// assert_eq!(emails[0].value.as_deref(), Some(anonymized.user_name.as_str()));
+/// assert!(anonymized.user_name.ends_with("@example.com"));
+/// assert_ne!(anonymized.user_name, user.user_name);
+/// assert_eq!(emails[0].r#type.as_deref(), Some("work"));
+/// assert_eq!(anonymized.phone_numbers.unwrap()[0].value.as_ref().unwrap().len(), 15);
+/// assert!(anonymized.addresses.is_none());
+/// assert_eq!(user.anonymized(&policy).unwrap().user_name, anonymized.user_name);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnonymizationPolicy {
+    key: Vec<u8>,
+    rules: Vec<(String, Treatment)>,
+}
+
+impl AnonymizationPolicy {
+    /// Creates a policy that changes nothing yet, deriving pseudonyms with `key`.
+    pub fn new(key: &[u8]) -> Self {
+        AnonymizationPolicy {
+            key: key.to_vec(),
+            rules: Vec::new(),
+        }
+    }
+
+    /// Creates a policy for the personal data of users: it pseudonymizes `userName`, `name`, `displayName`,
+    /// `nickName`, `externalId`, `emails`, `phoneNumbers` and `ims`, and removes `addresses`, `photos`,
+    /// `x509Certificates` and `password`.
+    pub fn personal_data(key: &[u8]) -> Self {
+        let pseudonymized = [
+            "userName",
+            "name",
+            "displayName",
+            "nickName",
+            "externalId",
+            "emails",
+            "phoneNumbers",
+            "ims",
+        ];
+        let removed = ["addresses", "photos", "x509Certificates", "password"];
+        let policy = pseudonymized
+            .into_iter()
+            .fold(AnonymizationPolicy::new(key), |policy, path| {
+                policy.pseudonymize(path)
+            });
+        removed
+            .into_iter()
+            .fold(policy, |policy, path| policy.remove(path))
+    }
+
+    /// Masks the attribute at `path`.
+    pub fn mask(self, path: impl Into<String>) -> Self {
+        self.with(path.into(), Treatment::Mask)
+    }
+
+    /// Pseudonymizes the attribute at `path`.
+    pub fn pseudonymize(self, path: impl Into<String>) -> Self {
+        self.with(path.into(), Treatment::Pseudonymize)
+    }
+
+    /// Removes the attribute at `path`.
+    pub fn remove(self, path: impl Into<String>) -> Self {
+        self.with(path.into(), Treatment::Remove)
+    }
+
+    // A later rule for the same path replaces the earlier one.
+    fn with(mut self, path: String, treatment: Treatment) -> Self {
+        self.rules.retain(|(p, _)| !p.eq_ignore_ascii_case(&path));
+        self.rules.push((path, treatment));
+        self
+    }
+
+    /// Anonymizes a resource in place.
+    pub fn apply(&self, resource: &mut Value) {
+        for (path, treatment) in &self.rules {
+            let keys = keys(resource, path);
+            match treatment {
+                Treatment::Remove => remove(resource, &keys),
+                Treatment::Mask => for_each_mut(resource, &keys, &mut |v| strings(v, &mut mask)),
+                Treatment::Pseudonymize => for_each_mut(resource, &keys, &mut |v| {
+                    strings(v, &mut |s| self.pseudonym(s))
+                }),
+            }
+        }
+    }
+
+    /// Returns the pseudonym of `value`. Values that only differ in case get the same pseudonym.
+    pub fn pseudonym(&self, value: &str) -> String {
+        let lowercase = value.to_lowercase();
+        let hash = |salt: &[u8]| fnv1a(&[self.key.as_slice(), salt, lowercase.as_bytes()].concat());
+        if let Some((_, domain)) = value.rsplit_once('@') {
+            if !domain.is_empty() {
+                return format!("{:012x}@example.com", hash(b"@") >> 16);
+            }
+        }
+        if is_phone_number(value) {
+            let mut index: u8 = 0;
+            return value
+                .chars()
+                .map(|c| match c.to_digit(10) {
+                    Some(_) => {
+                        index = index.wrapping_add(1);
+                        char::from(b'0' + (hash(&[b'#', index]) % 10) as u8)
+                    }
+                    None => c,
+                })
+                .collect();
+        }
+        format!("{:016x}", hash(b""))
+    }
+}
+
+// Replaces every string below `value` with `f` of it, leaving the `type` and `$ref` of objects alone.
+pub(crate) fn strings(value: &mut Value, f: &mut impl FnMut(&str) -> String) {
+    match value {
+        Value::String(s) => *s = f(s),
+        Value::Array(elements) => elements.iter_mut().for_each(|e| strings(e, f)),
+        Value::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                if !key.eq_ignore_ascii_case("type") && key != "$ref" {
+                    strings(value, f);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+pub(crate) fn mask(value: &str) -> String {
+    if let Some((local, domain)) = value.rsplit_once('@') {
+        let mut chars = local.chars();
+        let first = chars.next().map(String::from).unwrap_or_default();
+        return format!("{}{}@{}", first, "*".repeat(chars.count()), domain);
+    }
+    let count = value.chars().count();
+    let kept = if count > 4 { 2 } else { 0 };
+    value
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            if i < count - kept && c.is_alphanumeric() {
+                '*'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+fn is_phone_number(value: &str) -> bool {
+    let number = value.strip_prefix("tel:").unwrap_or(value);
+    number.chars().filter(char::is_ascii_digit).count() >= 4
+        && number
+            .chars()
+            .all(|c| c.is_ascii_digit() || "+-(). ".contains(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn treats_values_by_kind() {
+        let policy = AnonymizationPolicy::new(b"key")
+            .mask("emails")
+            .mask("phoneNumbers.value")
+            .pseudonymize("name")
+            .remove("urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:manager");
+        let mut user = json!({
+            "name": {"givenName": "Barbara", "familyName": "Jensen"},
+            "emails": [{"value": "bjensen@example.com", "display": "Babs", "type": "work"}],
+            "phoneNumbers": [{"value": "tel:+1-201-555-0123", "type": "work"}],
+            "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User": {
+                "employeeNumber": "701984",
+                "manager": {"value": "26118915-6090-4610-87e4-49d8ca9f808d"}
+            }
+        });
+        policy.apply(&mut user);
+
+        assert_eq!(
+            user["emails"],
+            json!([{"value": "b******@example.com", "display": "****", "type": "work"}])
+        );
+        assert_eq!(user["phoneNumbers"][0]["value"], "***:+*-***-***-**23");
+        let given_name = user["name"]["givenName"].as_str().unwrap();
+        assert_eq!(given_name, policy.pseudonym("barbara"));
+        assert_ne!(
+            given_name,
+            AnonymizationPolicy::new(b"other").pseudonym("Barbara")
+        );
+        assert_eq!(
+            user["urn:ietf:params:scim:schemas:extension:enterprise:2.0:User"],
+            json!({"employeeNumber": "701984"})
+        );
+
+        let phone = policy.pseudonym("+1 (201) 555-0123");
+        assert_eq!(phone.len(), 17);
+        assert!(phone.starts_with("+") && phone.contains(") ") && phone.contains('-'));
+        assert_ne!(phone, "+1 (201) 555-0123");
+    }
+}
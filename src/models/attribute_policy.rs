@@ -0,0 +1,468 @@
+//! Which attributes a caller may read and write, by the scopes it holds.
+//!
+//! An [`AttributePolicy`] restricts reading or writing single attributes, sub-attributes or whole schema
+//! extensions to callers that hold one of a set of scopes (or roles, the policy does not tell them apart),
+//! e.g. the salary attributes of an HR extension to `hr:read`, or password changes to `password:write`.
+//! Attributes without a rule are open to every caller. Servers apply the policy to every resource they
+//! return and every write they accept, with the scopes of [`RequestContext::scopes`]; the
+//! [`ScopedProvider`](crate::provider::scoped::ScopedProvider) decorator does it for a
+//! [`ResourceProvider`](crate::provider::resource_provider::ResourceProvider).
+//!
+//! [`RequestContext::scopes`]: crate::models::context::RequestContext::scopes
+
+use serde_json::{Map, Value};
+
+use crate::models::others::{ListQuery, Op, PatchOp};
+use crate::utils::error::SCIMError;
+
+/// What a rule of an [`AttributePolicy`] restricts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Rule {
+    path: String,
+    access: Access,
+    scopes: Vec<String>,
+}
+
+/// Restrictions on reading and writing attributes.
+///
+/// Paths are attribute names, dotted sub-attribute paths such as `name.familyName`, schema extension URIs,
+/// or attributes qualified by an extension URI such as
+/// `urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:employeeNumber`. A rule on a path covers
+/// everything below it. Names are compared case-insensitively.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::attribute_policy::AttributePolicy;
+/// use serde_json::json;
+///
+/// let enterprise = "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User";
+/// let policy = AttributePolicy::new()
+///     .restrict_read(enterprise, ["hr:read"])
+///     .restrict_write("password", ["password:write"]);
+///
+/// let mut user = json!({
+///     "userName": "bjensen",
+///     enterprise: {"employeeNumber": "701984"}
+/// });
+/// policy.redact(&["users:read".to_string()], &mut user);
+/// assert_eq!(user, json!({"userName": "bjensen"}));
+///
+/// let change = json!({"userName": "bjensen", "password": "t1meMa$heen"});
+/// assert!(policy.check_write(&[], &change).is_err());
+/// assert!(policy.check_write(&["password:write".to_string()], &change).is_ok());
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AttributePolicy {
+    rules: Vec<Rule>,
+}
+
+impl AttributePolicy {
+    /// Creates a policy that restricts nothing.
+    pub fn new() -> Self {
+        AttributePolicy::default()
+    }
+
+    /// Lets only callers holding one of `scopes` read the attribute at `path`. An empty list hides it from
+    /// every caller.
+    pub fn restrict_read<I, S>(self, path: impl Into<String>, scopes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.restrict(Access::Read, path.into(), scopes)
+    }
+
+    /// Lets only callers holding one of `scopes` write the attribute at `path`. An empty list makes it
+    /// read-only for every caller.
+    pub fn restrict_write<I, S>(self, path: impl Into<String>, scopes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.restrict(Access::Write, path.into(), scopes)
+    }
+
+    fn restrict<I, S>(mut self, access: Access, path: String, scopes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.rules.push(Rule {
+            path,
+            access,
+            scopes: scopes.into_iter().map(Into::into).collect(),
+        });
+        self
+    }
+
+    /// Returns `true` if a caller holding `scopes` may read the attribute at `path`.
+    pub fn can_read(&self, scopes: &[String], path: &str) -> bool {
+        self.denied(Access::Read, scopes)
+            .all(|rule| !covers(&rule.path, path))
+    }
+
+    /// Returns `true` if a caller holding `scopes` may write the attribute at `path`.
+    pub fn can_write(&self, scopes: &[String], path: &str) -> bool {
+        self.denied(Access::Write, scopes)
+            .all(|rule| !covers(&rule.path, path))
+    }
+
+    /// Removes the attributes a caller holding `scopes` may not read from a resource.
+    pub fn redact(&self, scopes: &[String], resource: &mut Value) {
+        for rule in self.denied(Access::Read, scopes) {
+            let keys = keys(resource, &rule.path);
+            remove(resource, &keys);
+        }
+    }
+
+    /// Checks that a new resource only sets attributes a caller holding `scopes` may write.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::InvalidFieldValue`, naming the attribute, if the resource sets one the caller may
+    /// not write.
+    pub fn check_write(&self, scopes: &[String], resource: &Value) -> Result<(), SCIMError> {
+        for rule in self.denied(Access::Write, scopes) {
+            if get(resource, &keys(resource, &rule.path)).is_some() {
+                return Err(not_writable(&rule.path));
+            }
+        }
+        Ok(())
+    }
+
+    /// Prepares the replacement of `stored` by `resource` on behalf of a caller holding `scopes`.
+    ///
+    /// The caller does not see the attributes it may not read, so it cannot send them back: if `resource`
+    /// leaves them out, their stored values are copied into it. Attributes it may not write must be left
+    /// out or sent unchanged, and keep their stored values as well. Values inside multi-valued attributes
+    /// are not copied, so rules on their sub-attributes only reject changes.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::InvalidFieldValue`, naming the attribute, if `resource` changes an attribute the
+    /// caller may not write.
+    pub fn guard_replace(
+        &self,
+        scopes: &[String],
+        stored: &Value,
+        resource: &mut Value,
+    ) -> Result<(), SCIMError> {
+        for rule in self.denied(Access::Write, scopes) {
+            let sent = get(resource, &keys(resource, &rule.path));
+            if sent.is_some() && sent != get(stored, &keys(stored, &rule.path)) {
+                return Err(not_writable(&rule.path));
+            }
+        }
+        for rule in self.rules.iter().filter(|rule| !rule.allows(scopes)) {
+            if get(resource, &keys(resource, &rule.path)).is_some() {
+                continue;
+            }
+            let stored_keys = keys(stored, &rule.path);
+            if let Some(value) = get(stored, &stored_keys) {
+                set(resource, &stored_keys, value);
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that a PATCH request only modifies attributes a caller holding `scopes` may write.
+    ///
+    /// # Errors
+    ///
+    /// * `SCIMError::InvalidFieldValue` - Naming the attribute, if an operation targets one the caller may not
+    ///   write, or sets or removes it along with an attribute above it.
+    /// * `SCIMError::InvalidPath` - If the path of an operation is not a PATCH path.
+    pub fn check_patch(&self, scopes: &[String], patch: &PatchOp) -> Result<(), SCIMError> {
+        for operation in &patch.operations {
+            let path = operation
+                .attribute_path()?
+                .map(|path| path.target().to_string());
+            let value = operation.value.as_ref().unwrap_or(&Value::Null);
+            for rule in self.denied(Access::Write, scopes) {
+                let touched = match &path {
+                    None => get(value, &keys(value, &rule.path)).is_some(),
+                    Some(path) if covers(&rule.path, path) => true,
+                    Some(path) if covers(path, &rule.path) => {
+                        let rest = &rule.path[path.len() + 1..];
+                        operation.op == Op::Remove || get(value, &keys(value, rest)).is_some()
+                    }
+                    Some(_) => false,
+                };
+                if touched {
+                    return Err(not_writable(&rule.path));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that a query does not filter or sort by attributes a caller holding `scopes` may not read,
+    /// which would reveal their values.
+    ///
+    /// # Errors
+    ///
+    /// * `SCIMError::InvalidFilter` - If the filter names an attribute the caller may not read.
+    /// * `SCIMError::InvalidFieldValue` - If `sortBy` does.
+    pub fn check_query(&self, scopes: &[String], query: &ListQuery) -> Result<(), SCIMError> {
+        let names = query.filter.as_deref().into_iter().flat_map(|filter| {
+            filter
+                .split(|c: char| c.is_whitespace() || "()[]".contains(c))
+                .filter(|token| !token.is_empty() && !token.starts_with(['"', '.']))
+        });
+        for name in names {
+            if !self.can_read(scopes, name) {
+                return Err(
+                    SCIMError::InvalidFilter(format!("cannot filter by {}", name).into())
+                        .with_attribute(name),
+                );
+            }
+        }
+        if let Some(sort_by) = &query.sort_by {
+            if !self.can_read(scopes, sort_by) {
+                return Err(SCIMError::InvalidFieldValue(
+                    format!("cannot sort by {}", sort_by).into(),
+                )
+                .with_attribute(sort_by.as_str()));
+            }
+        }
+        Ok(())
+    }
+
+    fn denied<'a>(
+        &'a self,
+        access: Access,
+        scopes: &'a [String],
+    ) -> impl Iterator<Item = &'a Rule> + 'a {
+        self.rules
+            .iter()
+            .filter(move |rule| rule.access == access && !rule.allows(scopes))
+    }
+}
+
+impl Rule {
+    fn allows(&self, scopes: &[String]) -> bool {
+        self.scopes.iter().any(|scope| scopes.contains(scope))
+    }
+}
+
+fn not_writable(path: &str) -> SCIMError {
+    SCIMError::InvalidFieldValue(format!("{} cannot be written by this client", path).into())
+        .with_attribute(path)
+}
+
+// Whether the rule on `rule` covers `path`: the same attribute, or one below it.
+pub(crate) fn covers(rule: &str, path: &str) -> bool {
+    path.len() >= rule.len()
+        && path.is_char_boundary(rule.len())
+        && path[..rule.len()].eq_ignore_ascii_case(rule)
+        && matches!(path.as_bytes().get(rule.len()), None | Some(b'.' | b':'))
+}
+
+// Splits `path` into the keys leading to it in `resource`. An extension URI is one key, recognised by the
+// resource carrying it; a path into an extension the resource does not carry leads nowhere.
+pub(crate) fn keys<'a>(resource: &Value, path: &'a str) -> Vec<&'a str> {
+    if !path
+        .get(..4)
+        .is_some_and(|s| s.eq_ignore_ascii_case("urn:"))
+    {
+        return path.split('.').collect();
+    }
+    let Some(object) = resource.as_object() else {
+        return Vec::new();
+    };
+    for key in object.keys() {
+        if covers(key, path) {
+            let (urn, rest) = path.split_at(key.len());
+            let mut keys = vec![urn];
+            keys.extend(rest.get(1..).into_iter().flat_map(|rest| rest.split('.')));
+            return keys;
+        }
+    }
+    Vec::new()
+}
+
+fn field<'a>(object: &'a Map<String, Value>, name: &str) -> Option<(&'a String, &'a Value)> {
+    object
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+}
+
+// The value at `keys`; below a multi-valued attribute, the array of the values of its elements.
+fn get(value: &Value, keys: &[&str]) -> Option<Value> {
+    let (first, rest) = keys.split_first()?;
+    match value {
+        Value::Object(object) => {
+            let (_, child) = field(object, first)?;
+            if rest.is_empty() {
+                Some(child.clone())
+            } else {
+                get(child, rest)
+            }
+        }
+        Value::Array(elements) => {
+            let found: Vec<Value> = elements.iter().filter_map(|e| get(e, keys)).collect();
+            (!found.is_empty()).then_some(Value::Array(found))
+        }
+        _ => None,
+    }
+}
+
+pub(crate) fn remove(value: &mut Value, keys: &[&str]) {
+    let Some((first, rest)) = keys.split_first() else {
+        return;
+    };
+    match value {
+        Value::Object(object) => {
+            let Some(key) = field(object, first).map(|(key, _)| key.clone()) else {
+                return;
+            };
+            if rest.is_empty() {
+                object.remove(&key);
+            } else if let Some(child) = object.get_mut(&key) {
+                remove(child, rest);
+            }
+        }
+        Value::Array(elements) => elements.iter_mut().for_each(|e| remove(e, keys)),
+        _ => {}
+    }
+}
+
+// Calls `f` with each value at `keys`; below a multi-valued attribute, with the value of each element.
+pub(crate) fn for_each_mut(value: &mut Value, keys: &[&str], f: &mut impl FnMut(&mut Value)) {
+    let Some((first, rest)) = keys.split_first() else {
+        return;
+    };
+    match value {
+        Value::Object(object) => {
+            let Some(key) = field(object, first).map(|(key, _)| key.clone()) else {
+                return;
+            };
+            let Some(child) = object.get_mut(&key) else {
+                return;
+            };
+            if rest.is_empty() {
+                f(child);
+            } else {
+                for_each_mut(child, rest, f);
+            }
+        }
+        Value::Array(elements) => elements.iter_mut().for_each(|e| for_each_mut(e, keys, f)),
+        _ => {}
+    }
+}
+
+// Sets the value at `keys`, creating the objects above it; does nothing below multi-valued attributes.
+fn set(value: &mut Value, keys: &[&str], new: Value) {
+    let (Some((first, rest)), Value::Object(object)) = (keys.split_first(), value) else {
+        return;
+    };
+    let key = field(object, first)
+        .map(|(key, _)| key.clone())
+        .unwrap_or_else(|| first.to_string());
+    if rest.is_empty() {
+        object.insert(key, new);
+    } else {
+        let child = object
+            .entry(key)
+            .or_insert_with(|| Value::Object(Map::new()));
+        set(child, rest, new);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::others::PatchOperations;
+
+    const ENTERPRISE: &str = "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User";
+
+    #[test]
+    fn keeps_hidden_and_read_only_attributes_across_replacements() {
+        let policy = AttributePolicy::new()
+            .restrict_read(format!("{}:costCenter", ENTERPRISE), ["hr"])
+            .restrict_write("name.familyName", ["admin"]);
+        let scopes = ["users".to_string()];
+        let stored = json!({
+            "userName": "bjensen",
+            "name": {"givenName": "Barbara", "familyName": "Jensen"},
+            ENTERPRISE: {"costCenter": "4130", "division": "Theme Park"}
+        });
+
+        let mut shown = stored.clone();
+        policy.redact(&scopes, &mut shown);
+        assert_eq!(shown[ENTERPRISE], json!({"division": "Theme Park"}));
+
+        let mut replacement = shown.clone();
+        replacement["name"] = json!({"givenName": "Babs", "familyName": "Jensen"});
+        policy
+            .guard_replace(&scopes, &stored, &mut replacement)
+            .unwrap();
+        assert_eq!(replacement[ENTERPRISE]["costCenter"], "4130");
+        assert_eq!(replacement["name"]["givenName"], "Babs");
+
+        let mut renamed = shown;
+        renamed["name"]["familyName"] = json!("Smith");
+        let err = policy
+            .guard_replace(&scopes, &stored, &mut renamed)
+            .unwrap_err();
+        assert_eq!(err.attribute(), Some("name.familyName"));
+
+        let patch = |path: &str, value: Value| PatchOp {
+            operations: vec![PatchOperations {
+                op: Op::Replace,
+                path: (!path.is_empty()).then(|| path.to_string()),
+                value: Some(value),
+            }],
+            ..PatchOp::default()
+        };
+        assert!(
+            policy
+                .check_patch(&scopes, &patch("name", json!({"familyName": "Smith"})))
+                .is_err()
+        );
+        assert!(
+            policy
+                .check_patch(&scopes, &patch("name", json!({"givenName": "Babs"})))
+                .is_ok()
+        );
+        assert!(
+            policy
+                .check_patch(
+                    &["admin".to_string()],
+                    &patch("name.familyName", json!("Smith"))
+                )
+                .is_ok()
+        );
+        let remove = PatchOp {
+            operations: vec![PatchOperations {
+                op: Op::Remove,
+                path: Some("name".to_string()),
+                value: None,
+            }],
+            ..PatchOp::default()
+        };
+        assert!(policy.check_patch(&scopes, &remove).is_err());
+
+        let query = ListQuery {
+            filter: Some(format!(
+                r#"userName sw "b" and {}:costCenter eq "4130""#,
+                ENTERPRISE
+            )),
+            ..ListQuery::default()
+        };
+        assert!(matches!(
+            policy.check_query(&scopes, &query),
+            Err(SCIMError::InvalidFilter(_))
+        ));
+        assert!(policy.check_query(&["hr".to_string()], &query).is_ok());
+    }
+}
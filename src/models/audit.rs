@@ -0,0 +1,277 @@
+//! Audit trail records of changes to resources.
+
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::models::context::RequestContext;
+use crate::utils::diff::diff_resources;
+use crate::utils::time::now_rfc3339;
+
+/// What was done to a resource.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditAction {
+    Create,
+    Replace,
+    Patch,
+    Delete,
+    Undelete,
+}
+
+impl Display for AuditAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AuditAction::Create => "create",
+            AuditAction::Replace => "replace",
+            AuditAction::Patch => "patch",
+            AuditAction::Delete => "delete",
+            AuditAction::Undelete => "undelete",
+        })
+    }
+}
+
+/// The resource an [`AuditEvent`] is about.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceRef {
+    pub resource_type: String,
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+}
+
+impl ResourceRef {
+    /// Refers to the resource with `id`.
+    pub fn new(resource_type: impl Into<String>, id: impl Into<String>) -> Self {
+        ResourceRef {
+            resource_type: resource_type.into(),
+            id: id.into(),
+            location: None,
+        }
+    }
+
+    /// Refers to a resource by its `id` and `meta.location`.
+    pub fn of(resource_type: impl Into<String>, resource: &Value) -> Self {
+        ResourceRef {
+            resource_type: resource_type.into(),
+            id: resource["id"].as_str().unwrap_or_default().to_string(),
+            location: resource
+                .pointer("/meta/location")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+        }
+    }
+}
+
+/// One changed attribute. Values are left out, so that audit logs do not copy passwords or personal data.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    /// The PATCH operation, `add`, `replace` or `remove`.
+    pub op: String,
+    /// The attribute path, e.g. `title` or `members[value eq "2819c223"]`. Empty if the operation had none.
+    pub path: String,
+}
+
+/// A record of a change to a resource, for compliance logging.
+///
+/// Events carry who made the change (from the [`RequestContext`]), when, to which resource, and which
+/// attributes changed. [`AuditingProvider`](crate::provider::auditing::AuditingProvider) emits them for every
+/// write that goes through a provider; code applying PATCH requests builds them with
+/// [`AuditEvent::changes_of_patch`].
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::audit::{AuditAction, AuditEvent, ResourceRef};
+/// use scim_v2::models::context::RequestContext;
+/// use serde_json::json;
+///
+/// let before = json!({"id": "1", "userName": "bjensen", "title": "Guide"});
+/// let after = json!({"id": "1", "userName": "bjensen", "nickName": "Babs"});
+/// let context = RequestContext::default().with_actor("okta");
+/// let event = AuditEvent::new(&context, AuditAction::Replace, ResourceRef::of("User", &after))
+///     .with_changes(AuditEvent::changes_between(&before, &after));
+/// assert_eq!(event.to_string(), "okta replace User 1: add nickName, remove title");
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEvent {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+    /// When the change was made, in RFC 3339 format.
+    pub timestamp: String,
+    pub action: AuditAction,
+    pub resource: ResourceRef,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<Change>,
+}
+
+impl AuditEvent {
+    /// Records `action` on `resource` now, by the actor of `context`.
+    pub fn new(context: &RequestContext, action: AuditAction, resource: ResourceRef) -> Self {
+        AuditEvent {
+            actor: context.actor.clone(),
+            tenant_id: context.tenant_id.clone(),
+            correlation_id: context.correlation_id.clone(),
+            timestamp: now_rfc3339(),
+            action,
+            resource,
+            changes: Vec::new(),
+        }
+    }
+
+    /// Sets the changed attributes.
+    pub fn with_changes(mut self, changes: Vec<Change>) -> Self {
+        self.changes = changes;
+        self
+    }
+
+    /// Summarizes the differences between two versions of a resource. `id`, `schemas` and `meta` are
+    /// ignored, and extension attributes are listed one by one.
+    pub fn changes_between(before: &Value, after: &Value) -> Vec<Change> {
+        let server_managed = ["id", "schemas", "meta"];
+        diff_resources(
+            before,
+            after,
+            |name| {
+                name.get(..4)
+                    .is_some_and(|u| u.eq_ignore_ascii_case("urn:"))
+            },
+            |path| !server_managed.iter().any(|m| m.eq_ignore_ascii_case(path)),
+        )
+        .iter()
+        .map(Change::from_operation)
+        .collect()
+    }
+
+    /// Summarizes the operations of a `PatchOp` request.
+    pub fn changes_of_patch(patch: &Value) -> Vec<Change> {
+        patch["Operations"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(Change::from_operation)
+            .collect()
+    }
+}
+
+impl Change {
+    fn from_operation(operation: &Value) -> Self {
+        Change {
+            op: operation["op"]
+                .as_str()
+                .unwrap_or_default()
+                .to_ascii_lowercase(),
+            path: operation["path"].as_str().unwrap_or_default().to_string(),
+        }
+    }
+}
+
+impl Display for AuditEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.actor.as_deref().unwrap_or("anonymous"),
+            self.action,
+            self.resource.resource_type,
+            self.resource.id
+        )?;
+        for (n, change) in self.changes.iter().enumerate() {
+            let separator = if n == 0 { ": " } else { ", " };
+            write!(f, "{}{} {}", separator, change.op, change.path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Where audit events are sent, e.g. a log or a message queue.
+///
+/// Closures taking an [`AuditEvent`] are sinks.
+pub trait AuditSink: Send + Sync {
+    fn record(&self, event: AuditEvent);
+}
+
+impl<F: Fn(AuditEvent) + Send + Sync> AuditSink for F {
+    fn record(&self, event: AuditEvent) {
+        self(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::schema_uri::{ENTERPRISE_USER_URN, PATCH_OP_URN};
+
+    #[test]
+    fn summarizes_changes_without_values() {
+        let before = json!({
+            "id": "1",
+            "userName": "bjensen",
+            "password": "old",
+            ENTERPRISE_USER_URN: {"manager": {"value": "2"}, "costCenter": "4130"},
+            "meta": {"version": "W/\"1\""}
+        });
+        let after = json!({
+            "id": "1",
+            "userName": "bjensen",
+            "password": "new",
+            ENTERPRISE_USER_URN: {"manager": {"value": "3"}, "costCenter": "4130"},
+            "meta": {"version": "W/\"2\""}
+        });
+        let changes = AuditEvent::changes_between(&before, &after);
+        let paths: Vec<&str> = changes.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "password",
+                format!("{}:manager", ENTERPRISE_USER_URN).as_str()
+            ]
+        );
+
+        let event = AuditEvent::new(
+            &RequestContext::default().with_tenant("acme"),
+            AuditAction::Replace,
+            ResourceRef::new("User", "1"),
+        )
+        .with_changes(changes);
+        let value = serde_json::to_value(&event).unwrap();
+        assert_eq!(value["tenantId"], "acme");
+        assert_eq!(
+            value["changes"][0],
+            json!({"op": "replace", "path": "password"})
+        );
+        assert!(!value.to_string().contains("new"));
+    }
+
+    #[test]
+    fn summarizes_patch_requests() {
+        let patch = json!({
+            "schemas": [PATCH_OP_URN],
+            "Operations": [
+                {"op": "Add", "path": "members", "value": [{"value": "2819c223"}]},
+                {"op": "remove", "path": "members[value eq \"902c246b\"]"},
+                {"op": "replace", "value": {"displayName": "Tour Guides"}}
+            ]
+        });
+        let event = AuditEvent::new(
+            &RequestContext::default(),
+            AuditAction::Patch,
+            ResourceRef::new("Group", "e9e30dba"),
+        )
+        .with_changes(AuditEvent::changes_of_patch(&patch));
+        assert_eq!(
+            event.to_string(),
+            "anonymous patch Group e9e30dba: add members, remove members[value eq \"902c246b\"], replace "
+        );
+    }
+}
@@ -0,0 +1,544 @@
+//! Avro schemas and binary encoding for resources.
+//!
+//! [`avro_schema`] derives an Avro record schema from a resource type's schema and extensions in the
+//! process-wide [`SchemaRegistry`](crate::models::schema_registry::SchemaRegistry), and [`encode`] and
+//! [`decode`] convert resource representations to and from the Avro binary encoding, so identity events can
+//! be published to Kafka topics or data-lake tables in a compact form.
+//!
+//! Every field of a generated schema is a union of `null` and its type with a `null` default, so readers can
+//! resolve data written with an older or newer version of a schema when attributes are added or removed.
+//! SCIM attribute names that are not valid Avro names (`$ref`, extension URIs) are renamed, and the original
+//! name is kept in the field's `scimName` property.
+//!
+//! # Examples
+//!
+//! ```
+//! use scim_v2::models::avro::{avro_schema, decode, encode};
+//! use scim_v2::models::user::User;
+//! use serde_json::json;
+//!
+//! let schema = avro_schema("User").unwrap();
+//! let user = json!({
+//!     "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+//!     "userName": "bjensen",
+//!     "emails": [{"value": "bjensen@example.com", "primary": true}]
+//! });
+//! let bytes = encode(&schema, &user).unwrap();
+//!
+//! let user = User::try_from(decode(&schema, &bytes).unwrap()).unwrap();
+//! assert_eq!(user.user_name, "bjensen");
+//! ```
+
+use serde_json::{Map, Number, Value, json};
+
+use crate::models::schema_registry::read_global;
+use crate::models::scim_schema::{Attributes, SubAttributes};
+use crate::utils::error::SCIMError;
+
+/// The namespace of the generated record schemas.
+pub const AVRO_NAMESPACE: &str = "scim.v2";
+
+/// Returns the Avro schema (as its JSON form) of a resource type registered with the process-wide
+/// [`SchemaRegistry`](crate::models::schema_registry::SchemaRegistry).
+///
+/// The record has a field per attribute of the base schema, the common attributes `schemas`, `id`,
+/// `externalId` and `meta`, and a nested record per schema extension. Attribute types map to `string`
+/// (`string`, `reference`, `dateTime` and `binary`), `boolean`, `long`, `double`, nested records (`complex`)
+/// and arrays (multi-valued attributes).
+///
+/// # Errors
+///
+/// Returns `SCIMError::ResourceTypeNotFound` if the resource type is not registered, or
+/// `SCIMError::SchemaNotFound` if one of its schemas is not.
+pub fn avro_schema(resource_type: &str) -> Result<Value, SCIMError> {
+    let registry = read_global();
+    let definition = registry
+        .resource_type(resource_type)
+        .ok_or_else(|| SCIMError::ResourceTypeNotFound(resource_type.into()))?;
+    let schema = registry
+        .schema(&definition.schema)
+        .ok_or_else(|| SCIMError::SchemaNotFound(definition.schema.clone().into()))?;
+    let record_name = avro_name(&definition.name);
+
+    let mut fields = vec![
+        field("schemas", json!({"type": "array", "items": "string"})),
+        field("id", json!("string")),
+        field("externalId", json!("string")),
+    ];
+    for attribute in &schema.attributes {
+        if !fields
+            .iter()
+            .any(|f| f["name"] == avro_name(&attribute.name))
+        {
+            fields.push(attribute_field(&record_name, attribute));
+        }
+    }
+    if !fields.iter().any(|f| f["name"] == "meta") {
+        let meta_fields = [
+            "resourceType",
+            "created",
+            "lastModified",
+            "version",
+            "location",
+        ]
+        .into_iter()
+        .map(|name| field(name, json!("string")))
+        .collect();
+        fields.push(field(
+            "meta",
+            record(&format!("{}_meta", record_name), meta_fields),
+        ));
+    }
+    for extension in definition.schema_extensions.iter().flatten() {
+        let schema = registry
+            .schema(&extension.schema)
+            .ok_or_else(|| SCIMError::SchemaNotFound(extension.schema.clone().into()))?;
+        let name = avro_name(&schema.name);
+        let extension_fields = schema
+            .attributes
+            .iter()
+            .map(|attribute| attribute_field(&format!("{}_{}", record_name, name), attribute))
+            .collect();
+        let mut extension_field = field(
+            &name,
+            record(&format!("{}_{}", record_name, name), extension_fields),
+        );
+        extension_field["scimName"] = Value::String(extension.schema.clone());
+        fields.push(extension_field);
+    }
+
+    let mut schema = record(&record_name, fields);
+    schema["namespace"] = Value::String(AVRO_NAMESPACE.to_string());
+    Ok(schema)
+}
+
+/// Encodes a resource representation with an Avro schema, in the Avro binary encoding.
+///
+/// Attributes the schema has no field for are left out.
+///
+/// # Errors
+///
+/// Returns `SCIMError::InvalidFieldValue` if a value does not match the type of its field, or the schema
+/// uses a type other than `null`, `boolean`, `int`, `long`, `float`, `double`, `string`, `bytes`, records,
+/// arrays, maps and unions.
+pub fn encode(schema: &Value, resource: &Value) -> Result<Vec<u8>, SCIMError> {
+    let mut output = Vec::new();
+    write_value(&mut output, schema, resource, "")?;
+    Ok(output)
+}
+
+/// Decodes a resource representation written in the Avro binary encoding with the given schema.
+///
+/// Fields with a `null` value are left out of the representation.
+///
+/// # Errors
+///
+/// Returns `SCIMError::InvalidFieldValue` if the data is truncated, has trailing bytes or does not match the
+/// schema, or the schema uses an unsupported type (see [`encode`]).
+pub fn decode(schema: &Value, bytes: &[u8]) -> Result<Value, SCIMError> {
+    let mut input = bytes;
+    let value = read_value(&mut input, schema, "")?;
+    if !input.is_empty() {
+        return Err(SCIMError::InvalidFieldValue(
+            format!("{} trailing bytes after the Avro datum", input.len()).into(),
+        ));
+    }
+    Ok(value)
+}
+
+fn field(name: &str, r#type: Value) -> Value {
+    json!({"name": name, "type": ["null", r#type], "default": null})
+}
+
+fn record(name: &str, fields: Vec<Value>) -> Value {
+    json!({"type": "record", "name": name, "fields": fields})
+}
+
+fn attribute_field(parent: &str, attribute: &Attributes) -> Value {
+    let name = avro_name(&attribute.name);
+    let r#type = match &attribute.sub_attributes {
+        Some(sub_attributes) if attribute.r#type == "complex" => {
+            let fields = sub_attributes.iter().map(sub_attribute_field).collect();
+            record(&format!("{}_{}", parent, name), fields)
+        }
+        _ => primitive_type(&attribute.r#type),
+    };
+    named_field(&attribute.name, r#type, attribute.multi_valued)
+}
+
+fn sub_attribute_field(sub_attribute: &SubAttributes) -> Value {
+    named_field(
+        &sub_attribute.name,
+        primitive_type(&sub_attribute.r#type),
+        sub_attribute.multi_valued,
+    )
+}
+
+fn named_field(scim_name: &str, r#type: Value, multi_valued: bool) -> Value {
+    let r#type = if multi_valued {
+        json!({"type": "array", "items": r#type})
+    } else {
+        r#type
+    };
+    let name = avro_name(scim_name);
+    let mut field = field(&name, r#type);
+    if name != scim_name {
+        field["scimName"] = Value::String(scim_name.to_string());
+    }
+    field
+}
+
+fn primitive_type(scim_type: &str) -> Value {
+    let r#type = match scim_type {
+        "boolean" => "boolean",
+        "integer" => "long",
+        "decimal" => "double",
+        _ => "string",
+    };
+    Value::String(r#type.to_string())
+}
+
+// Avro names match `[A-Za-z_][A-Za-z0-9_]*`.
+fn avro_name(name: &str) -> String {
+    let name: String = name
+        .trim_start_matches('$')
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    match name.chars().next() {
+        Some(c) if !c.is_ascii_digit() => name,
+        _ => format!("_{}", name),
+    }
+}
+
+fn mismatch(path: &str, expected: &str) -> SCIMError {
+    SCIMError::InvalidFieldValue(format!("'{}' is not a valid Avro {}", path, expected).into())
+}
+
+fn unsupported(schema: &Value) -> SCIMError {
+    SCIMError::InvalidFieldValue(format!("unsupported Avro schema {}", schema).into())
+}
+
+fn write_long(output: &mut Vec<u8>, value: i64) {
+    let mut zigzag = ((value << 1) ^ (value >> 63)) as u64;
+    while zigzag >= 0x80 {
+        output.push((zigzag as u8) | 0x80);
+        zigzag >>= 7;
+    }
+    output.push(zigzag as u8);
+}
+
+fn write_value(
+    output: &mut Vec<u8>,
+    schema: &Value,
+    value: &Value,
+    path: &str,
+) -> Result<(), SCIMError> {
+    match schema {
+        Value::Array(branches) => {
+            let index = branches
+                .iter()
+                .position(|branch| matches_branch(branch, value))
+                .ok_or_else(|| mismatch(path, "union value"))?;
+            write_long(output, index as i64);
+            write_value(output, &branches[index], value, path)
+        }
+        Value::Object(definition) => match definition.get("type").and_then(Value::as_str) {
+            Some("record") => {
+                for field in record_fields(schema)? {
+                    let (name, scim_name) = field_names(field)?;
+                    let field_value = value.get(scim_name).unwrap_or(&Value::Null);
+                    write_value(output, &field["type"], field_value, &join(path, name))?;
+                }
+                Ok(())
+            }
+            Some("array") => {
+                let items = value.as_array().ok_or_else(|| mismatch(path, "array"))?;
+                if !items.is_empty() {
+                    write_long(output, items.len() as i64);
+                    for item in items {
+                        write_value(output, &definition["items"], item, path)?;
+                    }
+                }
+                write_long(output, 0);
+                Ok(())
+            }
+            Some("map") => {
+                let entries = value.as_object().ok_or_else(|| mismatch(path, "map"))?;
+                if !entries.is_empty() {
+                    write_long(output, entries.len() as i64);
+                    for (key, entry) in entries {
+                        write_bytes(output, key.as_bytes());
+                        write_value(output, &definition["values"], entry, &join(path, key))?;
+                    }
+                }
+                write_long(output, 0);
+                Ok(())
+            }
+            Some(_) => write_value(output, &definition["type"], value, path),
+            None => Err(unsupported(schema)),
+        },
+        Value::String(name) => match (name.as_str(), value) {
+            ("null", Value::Null) => Ok(()),
+            ("boolean", Value::Bool(b)) => {
+                output.push(u8::from(*b));
+                Ok(())
+            }
+            ("int" | "long", Value::Number(n)) => {
+                let n = n.as_i64().ok_or_else(|| mismatch(path, name))?;
+                write_long(output, n);
+                Ok(())
+            }
+            ("float", Value::Number(n)) => {
+                let n = n.as_f64().ok_or_else(|| mismatch(path, name))?;
+                output.extend((n as f32).to_le_bytes());
+                Ok(())
+            }
+            ("double", Value::Number(n)) => {
+                let n = n.as_f64().ok_or_else(|| mismatch(path, name))?;
+                output.extend(n.to_le_bytes());
+                Ok(())
+            }
+            ("string" | "bytes", Value::String(s)) => {
+                write_bytes(output, s.as_bytes());
+                Ok(())
+            }
+            ("null" | "boolean" | "int" | "long" | "float" | "double" | "string" | "bytes", _) => {
+                Err(mismatch(path, name))
+            }
+            _ => Err(unsupported(schema)),
+        },
+        _ => Err(unsupported(schema)),
+    }
+}
+
+fn write_bytes(output: &mut Vec<u8>, bytes: &[u8]) {
+    write_long(output, bytes.len() as i64);
+    output.extend_from_slice(bytes);
+}
+
+fn matches_branch(branch: &Value, value: &Value) -> bool {
+    let r#type = match branch {
+        Value::Object(definition) => definition.get("type").and_then(Value::as_str),
+        branch => branch.as_str(),
+    };
+    match (r#type, value) {
+        (Some("null"), Value::Null) => true,
+        (Some("boolean"), Value::Bool(_)) => true,
+        (Some("int" | "long"), Value::Number(n)) => n.is_i64() || n.is_u64(),
+        (Some("float" | "double"), Value::Number(_)) => true,
+        (Some("string" | "bytes"), Value::String(_)) => true,
+        (Some("array"), Value::Array(_)) => true,
+        (Some("record" | "map"), Value::Object(_)) => true,
+        _ => false,
+    }
+}
+
+fn record_fields(schema: &Value) -> Result<&Vec<Value>, SCIMError> {
+    schema["fields"]
+        .as_array()
+        .ok_or_else(|| unsupported(schema))
+}
+
+// Returns the Avro name of a record field and the name of the attribute it holds.
+fn field_names(field: &Value) -> Result<(&str, &str), SCIMError> {
+    let name = field["name"].as_str().ok_or_else(|| unsupported(field))?;
+    let scim_name = field["scimName"].as_str().unwrap_or(name);
+    Ok((name, scim_name))
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+fn read_long(input: &mut &[u8], path: &str) -> Result<i64, SCIMError> {
+    let mut value: u64 = 0;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = input.split_first().ok_or_else(|| truncated(path))?;
+        *input = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(((value >> 1) as i64) ^ -((value & 1) as i64));
+        }
+    }
+    Err(mismatch(path, "long"))
+}
+
+fn read_bytes<'a>(input: &mut &'a [u8], length: usize, path: &str) -> Result<&'a [u8], SCIMError> {
+    if input.len() < length {
+        return Err(truncated(path));
+    }
+    let (bytes, rest) = input.split_at(length);
+    *input = rest;
+    Ok(bytes)
+}
+
+fn read_string(input: &mut &[u8], path: &str) -> Result<String, SCIMError> {
+    let length = usize::try_from(read_long(input, path)?).map_err(|_| mismatch(path, "string"))?;
+    let bytes = read_bytes(input, length, path)?;
+    String::from_utf8(bytes.to_vec()).map_err(|_| mismatch(path, "string"))
+}
+
+fn truncated(path: &str) -> SCIMError {
+    SCIMError::InvalidFieldValue(format!("Avro data ends inside '{}'", path).into())
+}
+
+// Reads the blocks of an array or map, calling `item` once per item.
+fn read_blocks(
+    input: &mut &[u8],
+    path: &str,
+    mut item: impl FnMut(&mut &[u8]) -> Result<(), SCIMError>,
+) -> Result<(), SCIMError> {
+    loop {
+        let count = read_long(input, path)?;
+        if count == 0 {
+            return Ok(());
+        }
+        if count < 0 {
+            // A negative count is followed by the block's size in bytes.
+            read_long(input, path)?;
+        }
+        for _ in 0..count.unsigned_abs() {
+            item(input)?;
+        }
+    }
+}
+
+fn read_value(input: &mut &[u8], schema: &Value, path: &str) -> Result<Value, SCIMError> {
+    match schema {
+        Value::Array(branches) => {
+            let index = read_long(input, path)?;
+            let branch = usize::try_from(index)
+                .ok()
+                .and_then(|index| branches.get(index))
+                .ok_or_else(|| mismatch(path, "union index"))?;
+            read_value(input, branch, path)
+        }
+        Value::Object(definition) => match definition.get("type").and_then(Value::as_str) {
+            Some("record") => {
+                let mut object = Map::new();
+                for field in record_fields(schema)? {
+                    let (name, scim_name) = field_names(field)?;
+                    let value = read_value(input, &field["type"], &join(path, name))?;
+                    if !value.is_null() {
+                        object.insert(scim_name.to_string(), value);
+                    }
+                }
+                Ok(Value::Object(object))
+            }
+            Some("array") => {
+                let mut items = Vec::new();
+                read_blocks(input, path, |input| {
+                    items.push(read_value(input, &definition["items"], path)?);
+                    Ok(())
+                })?;
+                Ok(Value::Array(items))
+            }
+            Some("map") => {
+                let mut entries = Map::new();
+                read_blocks(input, path, |input| {
+                    let key = read_string(input, path)?;
+                    let value = read_value(input, &definition["values"], &join(path, &key))?;
+                    entries.insert(key, value);
+                    Ok(())
+                })?;
+                Ok(Value::Object(entries))
+            }
+            Some(_) => read_value(input, &definition["type"], path),
+            None => Err(unsupported(schema)),
+        },
+        Value::String(name) => match name.as_str() {
+            "null" => Ok(Value::Null),
+            "boolean" => Ok(Value::Bool(read_bytes(input, 1, path)?[0] != 0)),
+            "int" | "long" => Ok(Value::Number(read_long(input, path)?.into())),
+            "float" => {
+                let bytes = read_bytes(input, 4, path)?;
+                let n = f32::from_le_bytes(bytes.try_into().map_err(|_| truncated(path))?);
+                Ok(Number::from_f64(f64::from(n)).map_or(Value::Null, Value::Number))
+            }
+            "double" => {
+                let bytes = read_bytes(input, 8, path)?;
+                let n = f64::from_le_bytes(bytes.try_into().map_err(|_| truncated(path))?);
+                Ok(Number::from_f64(n).map_or(Value::Null, Value::Number))
+            }
+            "string" | "bytes" => Ok(Value::String(read_string(input, path)?)),
+            _ => Err(unsupported(schema)),
+        },
+        _ => Err(unsupported(schema)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generates_nullable_fields_and_extension_records() {
+        let schema = avro_schema("User").unwrap();
+        assert_eq!(schema["name"], "User");
+        assert_eq!(schema["namespace"], AVRO_NAMESPACE);
+        let fields = schema["fields"].as_array().unwrap();
+        let user_name = fields.iter().find(|f| f["name"] == "userName").unwrap();
+        assert_eq!(user_name["type"], json!(["null", "string"]));
+        assert_eq!(user_name["default"], Value::Null);
+
+        let groups = fields.iter().find(|f| f["name"] == "groups").unwrap();
+        let group_fields = groups["type"][1]["items"]["fields"].as_array().unwrap();
+        assert!(
+            group_fields
+                .iter()
+                .any(|f| f["name"] == "ref" && f["scimName"] == "$ref")
+        );
+
+        let extension = fields
+            .iter()
+            .find(|f| f["name"] == "EnterpriseUser")
+            .unwrap();
+        assert_eq!(
+            extension["scimName"],
+            "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User"
+        );
+    }
+
+    #[test]
+    fn round_trips_resources() {
+        let schema = avro_schema("User").unwrap();
+        let user = json!({
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+            "id": "2819c223",
+            "userName": "bjensen",
+            "active": true,
+            "name": {"givenName": "Barbara", "familyName": "Jensen"},
+            "groups": [{"value": "e9e30dba", "$ref": "https://example.com/v2/Groups/e9e30dba"}],
+            "meta": {"resourceType": "User", "version": "W/\"3694e05e9dff590\""},
+            "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User": {"department": "Tour Operations"}
+        });
+        let bytes = encode(&schema, &user).unwrap();
+        assert_eq!(decode(&schema, &bytes).unwrap(), user);
+
+        assert!(decode(&schema, &bytes[..bytes.len() - 1]).is_err());
+        assert!(matches!(
+            encode(&schema, &json!({"userName": 42})),
+            Err(SCIMError::InvalidFieldValue(_))
+        ));
+    }
+
+    #[test]
+    fn encodes_longs_as_zigzag_varints() {
+        for (value, expected) in [
+            (0, vec![0]),
+            (-1, vec![1]),
+            (1, vec![2]),
+            (-64, vec![0x7f]),
+            (64, vec![0x80, 1]),
+        ] {
+            let bytes = encode(&json!("long"), &json!(value)).unwrap();
+            assert_eq!(bytes, expected);
+            assert_eq!(decode(&json!("long"), &bytes).unwrap(), json!(value));
+        }
+    }
+}
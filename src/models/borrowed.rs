@@ -0,0 +1,767 @@
+//! Borrowed representations of users, groups and list responses.
+//!
+//! Parsing a large `ListResponse` into the owned models allocates a `String` for every attribute value of
+//! every resource. The types in this module mirror the owned models, but their string values borrow from
+//! the JSON input wherever it contains no escape sequences, so a page of thousands of users can be read
+//! with a handful of allocations. Convert into the owned models with `From`/`Into` once a resource has to
+//! outlive the input.
+//!
+//! ```
+//! use scim_v2::models::borrowed::{ListResponse, User};
+//!
+//! let body = r#"{
+//!     "schemas": ["urn:ietf:params:scim:api:messages:2.0:ListResponse"],
+//!     "totalResults": 1,
+//!     "itemsPerPage": 1,
+//!     "startIndex": 1,
+//!     "Resources": [{
+//!         "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+//!         "userName": "bjensen",
+//!         "emails": [{"value": "bjensen@example.com"}]
+//!     }]
+//! }"#;
+//! let page = ListResponse::<User>::try_from(body).unwrap();
+//! assert_eq!(page.resources[0].user_name, "bjensen");
+//! assert!(page.resources[0].user_name.is_borrowed());
+//! ```
+
+use std::borrow::Cow;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::ops::Deref;
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::models::enterprise_user;
+use crate::models::group;
+use crate::models::others::{self, Resource};
+use crate::models::scim_schema;
+use crate::models::user;
+use crate::utils::error::SCIMError;
+use crate::utils::sensitive::{Secret, Url};
+
+/// A string value borrowed from the input when possible.
+///
+/// Values containing escape sequences have to be unescaped and are therefore owned; all other values
+/// point into the input.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Str<'a>(pub Cow<'a, str>);
+
+impl<'a> Str<'a> {
+    /// Returns the string value.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns whether the value points into the input rather than owning a copy.
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self.0, Cow::Borrowed(_))
+    }
+
+    /// Converts the value into an owned `String`, copying it if it was borrowed.
+    pub fn into_owned(self) -> String {
+        self.0.into_owned()
+    }
+}
+
+impl Deref for Str<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Str<'_> {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for Str<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl PartialEq<str> for Str<'_> {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Str<'_> {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl<'a> From<&'a str> for Str<'a> {
+    fn from(value: &'a str) -> Self {
+        Str(Cow::Borrowed(value))
+    }
+}
+
+impl From<String> for Str<'_> {
+    fn from(value: String) -> Self {
+        Str(Cow::Owned(value))
+    }
+}
+
+impl Serialize for Str<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+// `Cow<str>` only borrows when it is a field of its own marked `#[serde(borrow)]`; inside an `Option` or
+// a `Vec` it always allocates. This visitor borrows wherever the deserializer hands out borrowed input.
+impl<'de: 'a, 'a> Deserialize<'de> for Str<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct StrVisitor;
+
+        impl<'de> Visitor<'de> for StrVisitor {
+            type Value = Str<'de>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a string")
+            }
+
+            fn visit_borrowed_str<E: de::Error>(self, value: &'de str) -> Result<Self::Value, E> {
+                Ok(Str(Cow::Borrowed(value)))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                Ok(Str(Cow::Owned(value.to_string())))
+            }
+
+            fn visit_string<E: de::Error>(self, value: String) -> Result<Self::Value, E> {
+                Ok(Str(Cow::Owned(value)))
+            }
+        }
+
+        deserializer.deserialize_str(StrVisitor)
+    }
+}
+
+fn owned(value: Option<Str<'_>>) -> Option<String> {
+    value.map(Str::into_owned)
+}
+
+fn owned_all<T, U: From<T>, C: FromIterator<U>>(values: Option<Vec<T>>) -> Option<C> {
+    values.map(|values| values.into_iter().map(U::from).collect())
+}
+
+#[cfg(feature = "lenient")]
+fn default_user_schemas<'a>() -> Vec<Str<'a>> {
+    vec![Str::from(crate::models::schema_uri::USER_URN)]
+}
+
+#[cfg(feature = "lenient")]
+fn default_group_schemas<'a>() -> Vec<Str<'a>> {
+    vec![Str::from(crate::models::schema_uri::GROUP_URN)]
+}
+
+/// The borrowed counterpart of [`user::User`].
+///
+/// Like [`user::User`], its `Debug` output redacts `password` and the parts of URLs that may carry tokens.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct User<'a> {
+    #[serde(borrow)]
+    #[cfg_attr(feature = "lenient", serde(default = "default_user_schemas"))]
+    pub schemas: Vec<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub id: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "lenient", serde(alias = "externalID", alias = "externalid"))]
+    pub external_id: Option<Str<'a>>,
+    #[serde(borrow)]
+    #[cfg_attr(feature = "lenient", serde(alias = "username", alias = "UserName"))]
+    pub user_name: Str<'a>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub name: Option<Name<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(
+        feature = "lenient",
+        serde(alias = "displayname", alias = "DisplayName")
+    )]
+    pub display_name: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub nick_name: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub profile_url: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub title: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub user_type: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub preferred_language: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<Str<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active: Option<bool>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub password: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub emails: Option<Vec<MultiValued<'a>>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub addresses: Option<Vec<Address<'a>>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub phone_numbers: Option<Vec<MultiValued<'a>>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub ims: Option<Vec<MultiValued<'a>>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub photos: Option<Vec<MultiValued<'a>>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub groups: Option<Vec<Reference<'a>>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub entitlements: Option<Vec<MultiValued<'a>>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub roles: Option<Vec<MultiValued<'a>>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub x509_certificates: Option<Vec<MultiValued<'a>>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta<'a>>,
+    #[serde(
+        borrow,
+        rename = "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub enterprise_user: Option<EnterpriseUser<'a>>,
+}
+
+impl Debug for User<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let photos: Option<Vec<PhotoDebug<'_>>> = self
+            .photos
+            .as_ref()
+            .map(|photos| photos.iter().map(PhotoDebug).collect());
+        f.debug_struct("User")
+            .field("schemas", &self.schemas)
+            .field("id", &self.id)
+            .field("external_id", &self.external_id)
+            .field("user_name", &self.user_name)
+            .field("name", &self.name)
+            .field("display_name", &self.display_name)
+            .field("nick_name", &self.nick_name)
+            .field(
+                "profile_url",
+                &self
+                    .profile_url
+                    .as_deref()
+                    .map(|value| Url { value, full: false }),
+            )
+            .field("title", &self.title)
+            .field("user_type", &self.user_type)
+            .field("preferred_language", &self.preferred_language)
+            .field("locale", &self.locale)
+            .field("timezone", &self.timezone)
+            .field("active", &self.active)
+            .field(
+                "password",
+                &self
+                    .password
+                    .as_deref()
+                    .map(|value| Secret { value, full: false }),
+            )
+            .field("emails", &self.emails)
+            .field("addresses", &self.addresses)
+            .field("phone_numbers", &self.phone_numbers)
+            .field("ims", &self.ims)
+            .field("photos", &photos)
+            .field("groups", &self.groups)
+            .field("entitlements", &self.entitlements)
+            .field("roles", &self.roles)
+            .field("x509_certificates", &self.x509_certificates)
+            .field("meta", &self.meta)
+            .field("enterprise_user", &self.enterprise_user)
+            .finish()
+    }
+}
+
+// A photo in the `Debug` output of a user, with its URL redacted.
+struct PhotoDebug<'a>(&'a MultiValued<'a>);
+
+impl Debug for PhotoDebug<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let photo = self.0;
+        f.debug_struct("MultiValued")
+            .field(
+                "value",
+                &photo
+                    .value
+                    .as_deref()
+                    .map(|value| Url { value, full: false }),
+            )
+            .field("display", &photo.display)
+            .field("type", &photo.r#type)
+            .field("primary", &photo.primary)
+            .finish()
+    }
+}
+
+/// The borrowed counterpart of [`user::Name`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Name<'a> {
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub formatted: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub family_name: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub given_name: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub middle_name: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub honorific_prefix: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub honorific_suffix: Option<Str<'a>>,
+}
+
+/// The borrowed counterpart of the plain multi-valued user attributes: emails, phone numbers, instant
+/// messaging addresses, photos, entitlements, roles and certificates.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct MultiValued<'a> {
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub display: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub r#type: Option<Str<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primary: Option<bool>,
+}
+
+/// The borrowed counterpart of [`user::Address`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Address<'a> {
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub formatted: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub street_address: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub locality: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub region: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub postal_code: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub country: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub r#type: Option<Str<'a>>,
+}
+
+/// The borrowed counterpart of a reference to another resource: a user's [`user::Group`] or a group's
+/// [`group::Member`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Reference<'a> {
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Str<'a>>,
+    #[serde(borrow, rename = "$ref", skip_serializing_if = "Option::is_none")]
+    pub r#ref: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub display: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub r#type: Option<Str<'a>>,
+}
+
+/// The borrowed counterpart of [`scim_schema::Meta`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Meta<'a> {
+    #[serde(
+        borrow,
+        rename = "resourceType",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub resource_type: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub created: Option<Str<'a>>,
+    #[serde(
+        borrow,
+        rename = "lastModified",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub last_modified: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub version: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub location: Option<Str<'a>>,
+}
+
+/// The borrowed counterpart of [`enterprise_user::EnterpriseUser`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct EnterpriseUser<'a> {
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub employee_number: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub cost_center: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub organization: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub division: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub department: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub manager: Option<Manager<'a>>,
+}
+
+/// The borrowed counterpart of [`enterprise_user::Manager`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Manager<'a> {
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Str<'a>>,
+    #[serde(borrow, rename = "$ref", skip_serializing_if = "Option::is_none")]
+    pub r#ref: Option<Str<'a>>,
+    #[serde(
+        borrow,
+        rename = "displayName",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub display_name: Option<Str<'a>>,
+}
+
+/// The borrowed counterpart of [`group::Group`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Group<'a> {
+    #[serde(borrow)]
+    #[cfg_attr(feature = "lenient", serde(default = "default_group_schemas"))]
+    pub schemas: Vec<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub id: Option<Str<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "lenient", serde(alias = "externalID", alias = "externalid"))]
+    pub external_id: Option<Str<'a>>,
+    #[serde(borrow)]
+    #[cfg_attr(
+        feature = "lenient",
+        serde(alias = "displayname", alias = "DisplayName")
+    )]
+    pub display_name: Str<'a>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub members: Option<Vec<Reference<'a>>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta<'a>>,
+}
+
+/// The borrowed counterpart of [`others::ListResponse`], generic over the resource type of the page.
+///
+/// Use `ListResponse<User>` or `ListResponse<Group>` for pages of a single resource type, which is what
+/// servers return for `/Users` and `/Groups` queries.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ListResponse<'a, T> {
+    pub items_per_page: i64,
+    pub total_results: i64,
+    pub start_index: i64,
+    #[serde(borrow)]
+    pub schemas: Vec<Str<'a>>,
+    #[serde(rename = "Resources")]
+    pub resources: Vec<T>,
+}
+
+/// Parses a user from a JSON string, borrowing its string values from the input.
+///
+/// # Errors
+///
+/// Returns `SCIMError::DeserializationError` if the provided JSON string cannot be parsed into a `User` object.
+impl<'a> TryFrom<&'a str> for User<'a> {
+    type Error = SCIMError;
+
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        serde_json::from_str(value).map_err(SCIMError::DeserializationError)
+    }
+}
+
+/// Parses a group from a JSON string, borrowing its string values from the input.
+///
+/// # Errors
+///
+/// Returns `SCIMError::DeserializationError` if the provided JSON string cannot be parsed into a `Group` object.
+impl<'a> TryFrom<&'a str> for Group<'a> {
+    type Error = SCIMError;
+
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        serde_json::from_str(value).map_err(SCIMError::DeserializationError)
+    }
+}
+
+/// Parses a list response from a JSON string, borrowing the string values of its resources from the input.
+///
+/// # Errors
+///
+/// Returns `SCIMError::DeserializationError` if the provided JSON string cannot be parsed into a
+/// `ListResponse` of `T`.
+impl<'a, T: Deserialize<'a>> TryFrom<&'a str> for ListResponse<'a, T> {
+    type Error = SCIMError;
+
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        serde_json::from_str(value).map_err(SCIMError::DeserializationError)
+    }
+}
+
+impl From<User<'_>> for user::User {
+    fn from(user: User<'_>) -> Self {
+        user::User {
+            schemas: user.schemas.into_iter().map(Str::into_owned).collect(),
+            id: user.id.map(|id| id.into_owned().into()),
+            external_id: owned(user.external_id),
+            user_name: user.user_name.into_owned(),
+            name: user.name.map(Into::into),
+            display_name: owned(user.display_name),
+            nick_name: owned(user.nick_name),
+            profile_url: owned(user.profile_url),
+            title: owned(user.title),
+            user_type: owned(user.user_type),
+            preferred_language: owned(user.preferred_language),
+            locale: owned(user.locale),
+            timezone: owned(user.timezone),
+            active: user.active,
+            password: owned(user.password),
+            emails: owned_all(user.emails),
+            addresses: owned_all(user.addresses),
+            phone_numbers: owned_all(user.phone_numbers),
+            ims: owned_all(user.ims),
+            photos: owned_all(user.photos),
+            groups: owned_all(user.groups),
+            entitlements: owned_all(user.entitlements),
+            roles: owned_all(user.roles),
+            x509_certificates: owned_all(user.x509_certificates),
+            meta: user.meta.map(Into::into),
+            enterprise_user: user.enterprise_user.map(Into::into),
+        }
+    }
+}
+
+impl From<Name<'_>> for user::Name {
+    fn from(name: Name<'_>) -> Self {
+        user::Name {
+            formatted: owned(name.formatted),
+            family_name: owned(name.family_name),
+            given_name: owned(name.given_name),
+            middle_name: owned(name.middle_name),
+            honorific_prefix: owned(name.honorific_prefix),
+            honorific_suffix: owned(name.honorific_suffix),
+        }
+    }
+}
+
+macro_rules! impl_multi_valued_into_owned {
+    ($($owned:ident),* $(,)?) => {
+        $(
+            impl From<MultiValued<'_>> for user::$owned {
+                fn from(value: MultiValued<'_>) -> Self {
+                    user::$owned {
+                        value: owned(value.value),
+                        display: owned(value.display),
+                        r#type: owned(value.r#type),
+                        primary: value.primary,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_multi_valued_into_owned!(
+    Email,
+    PhoneNumber,
+    Im,
+    Photo,
+    Entitlement,
+    Role,
+    X509Certificate
+);
+
+impl From<Address<'_>> for user::Address {
+    fn from(address: Address<'_>) -> Self {
+        user::Address {
+            formatted: owned(address.formatted),
+            street_address: owned(address.street_address),
+            locality: owned(address.locality),
+            region: owned(address.region),
+            postal_code: owned(address.postal_code),
+            country: owned(address.country),
+            r#type: owned(address.r#type),
+        }
+    }
+}
+
+impl From<Reference<'_>> for user::Group {
+    fn from(reference: Reference<'_>) -> Self {
+        user::Group {
+            value: reference.value.map(|v| v.into_owned().into()),
+            r#ref: owned(reference.r#ref),
+            display: owned(reference.display),
+            r#type: owned(reference.r#type),
+        }
+    }
+}
+
+impl From<Reference<'_>> for group::Member {
+    fn from(reference: Reference<'_>) -> Self {
+        group::Member {
+            value: reference.value.map(|v| v.into_owned().into()),
+            r#ref: owned(reference.r#ref),
+            r#type: owned(reference.r#type),
+            display: owned(reference.display),
+        }
+    }
+}
+
+impl From<Manager<'_>> for enterprise_user::Manager {
+    fn from(manager: Manager<'_>) -> Self {
+        enterprise_user::Manager {
+            value: manager.value.map(|v| v.into_owned().into()),
+            r#ref: owned(manager.r#ref),
+            display_name: owned(manager.display_name),
+        }
+    }
+}
+
+impl From<Meta<'_>> for scim_schema::Meta {
+    fn from(meta: Meta<'_>) -> Self {
+        scim_schema::Meta {
+            resource_type: owned(meta.resource_type),
+            created: owned(meta.created),
+            last_modified: owned(meta.last_modified),
+            version: owned(meta.version),
+            location: owned(meta.location),
+        }
+    }
+}
+
+impl From<EnterpriseUser<'_>> for enterprise_user::EnterpriseUser {
+    fn from(enterprise: EnterpriseUser<'_>) -> Self {
+        enterprise_user::EnterpriseUser {
+            employee_number: owned(enterprise.employee_number),
+            cost_center: owned(enterprise.cost_center),
+            organization: owned(enterprise.organization),
+            division: owned(enterprise.division),
+            department: owned(enterprise.department),
+            manager: enterprise.manager.map(Into::into),
+        }
+    }
+}
+
+impl From<Group<'_>> for group::Group {
+    fn from(group: Group<'_>) -> Self {
+        group::Group {
+            schemas: group.schemas.into_iter().map(Str::into_owned).collect(),
+            id: group.id.map(|id| id.into_owned().into()),
+            external_id: owned(group.external_id),
+            display_name: group.display_name.into_owned(),
+            members: owned_all(group.members),
+            meta: group.meta.map(Into::into),
+        }
+    }
+}
+
+impl From<User<'_>> for Resource {
+    fn from(user: User<'_>) -> Self {
+        Resource::User(Box::new(user.into()))
+    }
+}
+
+impl From<Group<'_>> for Resource {
+    fn from(group: Group<'_>) -> Self {
+        Resource::Group(Box::new(group.into()))
+    }
+}
+
+impl<T: Into<Resource>> From<ListResponse<'_, T>> for others::ListResponse {
+    fn from(list: ListResponse<'_, T>) -> Self {
+        others::ListResponse {
+            items_per_page: list.items_per_page,
+            total_results: list.total_results,
+            start_index: list.start_index,
+            schemas: list.schemas.into_iter().map(Str::into_owned).collect(),
+            resources: list.resources.into_iter().map(Into::into).collect(),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"{
+        "schemas": ["urn:ietf:params:scim:api:messages:2.0:ListResponse"],
+        "totalResults": 2,
+        "itemsPerPage": 2,
+        "startIndex": 1,
+        "Resources": [
+            {
+                "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+                "id": "2819c223-7f76-453a-919d-413861904646",
+                "userName": "bjensen",
+                "name": {"givenName": "Barbara", "familyName": "Jensen"},
+                "emails": [{"value": "bjensen@example.com", "type": "work", "primary": true}],
+                "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User": {
+                    "department": "Tour Operations",
+                    "manager": {"value": "26118915-6090-4610-87e4-49d8ca9f808d", "displayName": "John Smith"}
+                }
+            },
+            {
+                "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+                "userName": "jsmith",
+                "displayName": "John \"Johnny\" Smith"
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn borrows_unescaped_values() {
+        let page = ListResponse::<User>::try_from(PAGE).unwrap();
+        assert_eq!(page.total_results, 2);
+
+        let bjensen = &page.resources[0];
+        assert!(bjensen.user_name.is_borrowed());
+        assert!(bjensen.id.as_ref().unwrap().is_borrowed());
+        let email = &bjensen.emails.as_ref().unwrap()[0];
+        assert_eq!(email.value.as_ref().unwrap(), "bjensen@example.com");
+        assert!(email.value.as_ref().unwrap().is_borrowed());
+
+        let display_name = page.resources[1].display_name.as_ref().unwrap();
+        assert_eq!(display_name.as_str(), "John \"Johnny\" Smith");
+        assert!(!display_name.is_borrowed());
+    }
+
+    #[test]
+    fn converts_into_owned_models() {
+        let page = ListResponse::<User>::try_from(PAGE).unwrap();
+        let list = others::ListResponse::from(page);
+        assert_eq!(list.resources.len(), 2);
+        let Resource::User(user) = &list.resources[0] else {
+            panic!("expected a user");
+        };
+        assert_eq!(user.user_name, "bjensen");
+        assert_eq!(
+            user.name.as_ref().and_then(|n| n.family_name.as_deref()),
+            Some("Jensen")
+        );
+        let enterprise = user.enterprise_user.as_ref().unwrap();
+        assert_eq!(enterprise.department.as_deref(), Some("Tour Operations"));
+        assert_eq!(
+            enterprise.manager.as_ref().unwrap().display_name.as_deref(),
+            Some("John Smith")
+        );
+
+        let group = Group::try_from(
+            r#"{"schemas": ["urn:ietf:params:scim:schemas:core:2.0:Group"], "displayName": "Tour Guides",
+                "members": [{"value": "2819c223-7f76-453a-919d-413861904646", "display": "Babs Jensen"}]}"#,
+        )
+        .unwrap();
+        let group = group::Group::from(group);
+        assert_eq!(
+            group.members.unwrap()[0].display.as_deref(),
+            Some("Babs Jensen")
+        );
+    }
+}
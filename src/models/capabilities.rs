@@ -0,0 +1,261 @@
+//! What a client can use of a service provider, given what it advertises.
+//!
+//! [`negotiate`] intersects the operations a client would like to use with the target's
+//! `ServiceProviderConfig` and returns [`NegotiatedCapabilities`], which the request builder
+//! ([`Requests::with_capabilities`](crate::client::request::Requests::with_capabilities)) and provisioning
+//! pipelines ([`Pipeline::with_capabilities`](crate::sync::pipeline::Pipeline::with_capabilities)) use to
+//! send only requests the target accepts.
+
+use crate::models::service_provider_config::ServiceProviderConfig;
+use crate::utils::pagination::{PageRequest, PageStrategy};
+
+/// The operations and limits a client would like to use.
+///
+/// The default asks for every optional operation, leaves the pagination method to the service provider and
+/// sets no limits of its own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DesiredCapabilities {
+    pub patch: bool,
+    pub bulk: bool,
+    pub filter: bool,
+    pub sort: bool,
+    pub etag: bool,
+    pub change_password: bool,
+    /// The preferred pagination method; `None` takes the service provider's default.
+    pub pagination: Option<PageStrategy>,
+    /// The preferred page size; `None` takes the service provider's default.
+    pub page_size: Option<i64>,
+    /// The largest number of operations to put in one bulk request.
+    pub max_operations: Option<i64>,
+    /// The largest bulk request body to send, in bytes.
+    pub max_payload_size: Option<i64>,
+}
+
+impl Default for DesiredCapabilities {
+    fn default() -> Self {
+        DesiredCapabilities {
+            patch: true,
+            bulk: true,
+            filter: true,
+            sort: true,
+            etag: true,
+            change_password: true,
+            pagination: None,
+            page_size: None,
+            max_operations: None,
+            max_payload_size: None,
+        }
+    }
+}
+
+/// The limits bulk requests have to stay within.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BulkLimits {
+    /// The largest number of operations in one bulk request.
+    pub max_operations: i64,
+    /// The largest bulk request body, in bytes.
+    pub max_payload_size: i64,
+}
+
+/// What a client and a service provider agreed on, as returned by [`negotiate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NegotiatedCapabilities {
+    /// Whether updates can be sent as PATCH; otherwise they have to be sent as PUT.
+    pub patch: bool,
+    /// The limits of bulk requests, or `None` if bulk requests cannot be used.
+    pub bulk: Option<BulkLimits>,
+    pub filter: bool,
+    pub sort: bool,
+    pub etag: bool,
+    pub change_password: bool,
+    /// The pagination method to list with.
+    pub pagination: PageStrategy,
+    /// The page size to ask for; `None` leaves it to the service provider.
+    pub page_size: Option<i64>,
+    /// The largest number of resources the service provider returns in one response, if it is limited.
+    pub max_results: Option<i64>,
+    /// The desired operations the service provider does not support, e.g. `patch`.
+    pub declined: Vec<&'static str>,
+}
+
+impl NegotiatedCapabilities {
+    /// Returns the request for the first page of a list.
+    pub fn first_page(&self) -> PageRequest {
+        PageRequest::first(self.pagination, self.page_size)
+    }
+
+    /// Lowers a page size to the largest the service provider returns. `None` becomes that largest size,
+    /// if there is one.
+    pub fn clamp_page_size(&self, count: Option<i64>) -> Option<i64> {
+        match (count, self.max_results) {
+            (Some(count), Some(max)) => Some(count.min(max)),
+            (count, max) => count.or(max),
+        }
+    }
+}
+
+/// Intersects what a client would like to use with what `config` advertises.
+///
+/// An optional operation is used if it is both desired and supported. Bulk limits and the page size are
+/// the lower of the client's and the service provider's, where the service provider's `maxResults` and
+/// `maxPageSize` both bound the page size. Without a `pagination` capability, the service provider is
+/// taken to support `startIndex` pagination only, as RFC 7644 does.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::capabilities::{DesiredCapabilities, negotiate};
+/// use scim_v2::models::service_provider_config::ServiceProviderConfig;
+/// use scim_v2::utils::pagination::PageStrategy;
+///
+/// let mut config = ServiceProviderConfig::default();
+/// config.bulk.supported = true;
+/// config.bulk.max_operations = 100;
+/// config.filter.supported = true;
+/// config.filter.max_results = 200;
+///
+/// let desired = DesiredCapabilities {
+///     pagination: Some(PageStrategy::Cursor),
+///     page_size: Some(500),
+///     ..DesiredCapabilities::default()
+/// };
+/// let negotiated = negotiate(&desired, &config);
+/// assert!(!negotiated.patch);
+/// assert_eq!(negotiated.bulk.unwrap().max_operations, 100);
+/// assert_eq!(negotiated.pagination, PageStrategy::Index);
+/// assert_eq!(negotiated.page_size, Some(200));
+/// assert!(negotiated.declined.contains(&"patch"));
+/// ```
+pub fn negotiate(
+    desired: &DesiredCapabilities,
+    config: &ServiceProviderConfig,
+) -> NegotiatedCapabilities {
+    let mut declined = Vec::new();
+    let mut agree = |name: &'static str, wanted: bool, supported: bool| {
+        if wanted && !supported {
+            declined.push(name);
+        }
+        wanted && supported
+    };
+    let patch = agree("patch", desired.patch, config.patch.supported);
+    let bulk = agree("bulk", desired.bulk, config.bulk.supported);
+    let filter = agree("filter", desired.filter, config.filter.supported);
+    let sort = agree("sort", desired.sort, config.sort.supported);
+    let etag = agree("etag", desired.etag, config.etag.supported);
+    let change_password = agree(
+        "changePassword",
+        desired.change_password,
+        config.change_password.supported,
+    );
+
+    let (index, cursor, default_method, default_page_size, max_page_size) = match &config.pagination
+    {
+        Some(pagination) => (
+            pagination.index,
+            pagination.cursor,
+            pagination.default_pagination_method.as_deref(),
+            pagination.default_page_size,
+            pagination.max_page_size,
+        ),
+        None => (true, false, None, None, None),
+    };
+    let preferred = desired.pagination.or(match default_method {
+        Some(method) if method.eq_ignore_ascii_case("cursor") => Some(PageStrategy::Cursor),
+        Some(method) if method.eq_ignore_ascii_case("index") => Some(PageStrategy::Index),
+        _ => None,
+    });
+    let pagination = match preferred {
+        Some(PageStrategy::Cursor) if cursor => PageStrategy::Cursor,
+        _ if index => PageStrategy::Index,
+        _ if cursor => PageStrategy::Cursor,
+        _ => PageStrategy::Index,
+    };
+    if desired.pagination == Some(PageStrategy::Cursor) && pagination != PageStrategy::Cursor {
+        declined.push("cursor");
+    }
+
+    let max_results = lowest([
+        Some(config.filter.max_results).filter(|max| *max > 0),
+        max_page_size.filter(|max| *max > 0),
+    ]);
+    let page_size = desired
+        .page_size
+        .or(default_page_size)
+        .map(|size| max_results.map_or(size, |max| size.min(max)));
+    let negotiated_bulk = bulk.then(|| BulkLimits {
+        max_operations: lowest([
+            desired.max_operations,
+            Some(config.bulk.max_operations).filter(|max| *max > 0),
+        ])
+        .unwrap_or(i64::MAX),
+        max_payload_size: lowest([
+            desired.max_payload_size,
+            Some(config.bulk.max_payload_size).filter(|max| *max > 0),
+        ])
+        .unwrap_or(i64::MAX),
+    });
+
+    NegotiatedCapabilities {
+        patch,
+        bulk: negotiated_bulk,
+        filter,
+        sort,
+        etag,
+        change_password,
+        pagination,
+        page_size,
+        max_results,
+        declined,
+    }
+}
+
+fn lowest<const N: usize>(limits: [Option<i64>; N]) -> Option<i64> {
+    limits.into_iter().flatten().min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::service_provider_config::Pagination;
+
+    #[test]
+    fn prefers_the_clients_pagination_method_when_offered() {
+        let config = ServiceProviderConfig {
+            pagination: Some(Pagination {
+                cursor: true,
+                index: true,
+                default_pagination_method: Some("cursor".to_string()),
+                default_page_size: Some(50),
+                max_page_size: Some(100),
+                ..Pagination::default()
+            }),
+            ..ServiceProviderConfig::default()
+        };
+
+        let negotiated = negotiate(&DesiredCapabilities::default(), &config);
+        assert_eq!(negotiated.pagination, PageStrategy::Cursor);
+        assert_eq!(negotiated.page_size, Some(50));
+        assert_eq!(negotiated.bulk, None);
+        assert_eq!(negotiated.clamp_page_size(Some(1000)), Some(100));
+        assert_eq!(
+            negotiated.declined,
+            ["patch", "bulk", "filter", "sort", "etag", "changePassword"]
+        );
+
+        let desired = DesiredCapabilities {
+            pagination: Some(PageStrategy::Index),
+            page_size: Some(500),
+            patch: false,
+            ..DesiredCapabilities::default()
+        };
+        let negotiated = negotiate(&desired, &config);
+        assert_eq!(
+            negotiated.first_page(),
+            PageRequest::Index {
+                start_index: 1,
+                count: Some(100)
+            }
+        );
+        assert!(!negotiated.declined.contains(&"patch"));
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::BTreeMap;
+
+use serde_json::{Map, Value};
+
+use crate::models::errors::ScimHttpError;
+use crate::models::service_provider_config::ServiceProviderConfig;
+use crate::utils::error::SCIMError;
+use crate::utils::rate_limit::RateLimit;
+
+/// Who a request is made by and on behalf of which tenant.
+///
+/// A server builds one context per request, from its authentication and routing, and passes it to the
+/// `*_in` methods of [`ResourceProvider`](crate::provider::resource_provider::ResourceProvider) and to
+/// [`RequestContext::error_response`]. Providers that serve several tenants keep their resources apart by
+/// [`RequestContext::tenant_id`]; the other fields are there for auditing and tracing.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::context::RequestContext;
+/// use scim_v2::models::service_provider_config::ServiceProviderConfig;
+/// use serde_json::json;
+///
+/// let context = RequestContext::default()
+///     .with_tenant("acme")
+///     .with_actor("okta-provisioning")
+///     .with_correlation_id("req-42")
+///     .with_capability("bulk.supported", json!(false));
+/// let config = context
+///     .service_provider_config(&ServiceProviderConfig::default())
+///     .unwrap();
+/// assert!(!config.bulk.supported);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RequestContext {
+    /// The tenant the request is for, if the service provider hosts several.
+    pub tenant_id: Option<String>,
+    /// The authenticated client or user making the request.
+    pub actor: Option<String>,
+    /// The scopes or roles granted to the actor, which an
+    /// [`AttributePolicy`](crate::models::attribute_policy::AttributePolicy) checks.
+    pub scopes: Vec<String>,
+    /// An identifier tying together the log lines, events and errors of one request.
+    pub correlation_id: Option<String>,
+    /// `ServiceProviderConfig` attributes that differ for this request, keyed by dotted attribute path
+    /// (e.g. `filter.maxResults`).
+    pub capability_overrides: BTreeMap<String, Value>,
+    /// The request rate the tenant is allowed, overriding the default of a
+    /// [`RateLimiter`](crate::utils::rate_limit::RateLimiter).
+    pub rate_limit: Option<RateLimit>,
+}
+
+impl RequestContext {
+    /// Sets the tenant.
+    pub fn with_tenant(mut self, tenant_id: impl Into<String>) -> Self {
+        self.tenant_id = Some(tenant_id.into());
+        self
+    }
+
+    /// Sets the actor.
+    pub fn with_actor(mut self, actor: impl Into<String>) -> Self {
+        self.actor = Some(actor.into());
+        self
+    }
+
+    /// Adds a scope or role granted to the actor.
+    pub fn with_scope(mut self, scope: impl Into<String>) -> Self {
+        self.scopes.push(scope.into());
+        self
+    }
+
+    /// Sets the correlation id.
+    pub fn with_correlation_id(mut self, correlation_id: impl Into<String>) -> Self {
+        self.correlation_id = Some(correlation_id.into());
+        self
+    }
+
+    /// Overrides the `ServiceProviderConfig` attribute at a dotted path, e.g. `patch.supported`.
+    pub fn with_capability(mut self, path: impl Into<String>, value: Value) -> Self {
+        self.capability_overrides.insert(path.into(), value);
+        self
+    }
+
+    /// Sets the tenant's rate limit.
+    pub fn with_rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
+    /// Returns `config` with this context's capability overrides applied.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::InvalidFieldValue` if an override does not fit the attribute it replaces, e.g. a
+    /// string for `patch.supported`.
+    pub fn service_provider_config(
+        &self,
+        config: &ServiceProviderConfig,
+    ) -> Result<ServiceProviderConfig, SCIMError> {
+        let mut value = serde_json::to_value(config).map_err(SCIMError::SerializationError)?;
+        for (path, replacement) in &self.capability_overrides {
+            let mut target = &mut value;
+            for name in path.split('.') {
+                let Value::Object(object) = target else {
+                    return Err(SCIMError::InvalidFieldValue(
+                        format!("capability '{}' is not inside an object", path).into(),
+                    ));
+                };
+                target = object
+                    .entry(name)
+                    .or_insert_with(|| Value::Object(Map::new()));
+            }
+            *target = replacement.clone();
+        }
+        serde_json::from_value(value).map_err(|e| {
+            SCIMError::InvalidFieldValue(format!("invalid capability override: {}", e).into())
+        })
+    }
+
+    /// Returns the SCIM error response for `error`, with the correlation id appended to the detail so
+    /// clients can quote it.
+    pub fn error_response(&self, error: &SCIMError) -> ScimHttpError {
+        let mut response = ScimHttpError::from(error);
+        if let Some(correlation_id) = &self.correlation_id {
+            let detail = response.detail.take().unwrap_or_default();
+            response.detail = Some(format!("{} (correlation id {})", detail, correlation_id));
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn applies_capability_overrides() {
+        let base = ServiceProviderConfig::default();
+        let context = RequestContext::default()
+            .with_capability("filter.maxResults", json!(50))
+            .with_capability("patch.supported", json!(true))
+            .with_capability("x-tenantQuota.users", json!(1000));
+        let config = context.service_provider_config(&base).unwrap();
+        assert_eq!(config.filter.max_results, 50);
+        assert!(config.patch.supported);
+        assert_eq!(config.extensions["x-tenantQuota"], json!({"users": 1000}));
+
+        let wrong = RequestContext::default().with_capability("patch.supported", json!("yes"));
+        assert!(matches!(
+            wrong.service_provider_config(&base),
+            Err(SCIMError::InvalidFieldValue(_))
+        ));
+        let nested = RequestContext::default().with_capability("etag.supported.value", json!(1));
+        assert!(nested.service_provider_config(&base).is_err());
+    }
+
+    #[test]
+    fn reports_errors_with_the_correlation_id() {
+        let context = RequestContext::default().with_correlation_id("req-42");
+        let response = context.error_response(&SCIMError::ConflictError("userName bjensen".into()));
+        assert_eq!(response.status, "409");
+        assert_eq!(response.scim_type.as_deref(), Some("uniqueness"));
+        assert_eq!(
+            response.detail.as_deref(),
+            Some("Conflict error: userName bjensen (correlation id req-42)")
+        );
+    }
+}
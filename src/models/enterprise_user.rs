@@ -1,8 +1,17 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io::Write;
+
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
+use crate::models::resource_id::ResourceId;
 use crate::utils::error::SCIMError;
+use crate::utils::json;
 
 #[derive(Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct EnterpriseUser {
     #[serde(rename = "employeeNumber", skip_serializing_if = "Option::is_none")]
     pub employee_number: Option<String>,
@@ -52,6 +61,48 @@ impl TryFrom<&str> for EnterpriseUser {
     }
 }
 
+/// Converts an already-parsed JSON value into a `EnterpriseUser` struct.
+///
+/// Web frameworks usually hand over request bodies as a `serde_json::Value`; this conversion avoids
+/// round-tripping that value through a string.
+///
+/// # Errors
+///
+/// Returns `SCIMError::DeserializationError` if the value does not describe a `EnterpriseUser` object.
+///
+/// # Examples
+///
+/// ```rust
+/// use scim_v2::models::enterprise_user::EnterpriseUser;
+/// use serde_json::json;
+///
+/// let value = json!({"employeeNumber": "701984", "department": "Tour Operations"});
+/// match EnterpriseUser::try_from(value) {
+///     Ok(resource) => println!("Successfully converted JSON to EnterpriseUser: {:?}", resource),
+///     Err(e) => println!("Error converting from JSON to EnterpriseUser: {}", e),
+/// }
+/// ```
+impl TryFrom<Value> for EnterpriseUser {
+    type Error = SCIMError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        serde_json::from_value(value).map_err(SCIMError::DeserializationError)
+    }
+}
+
+/// Converts a `EnterpriseUser` struct into a JSON value.
+///
+/// # Errors
+///
+/// Returns `SCIMError::SerializationError` if the `EnterpriseUser` cannot be represented as JSON.
+impl TryFrom<EnterpriseUser> for Value {
+    type Error = SCIMError;
+
+    fn try_from(value: EnterpriseUser) -> Result<Self, Self::Error> {
+        serde_json::to_value(value).map_err(SCIMError::SerializationError)
+    }
+}
+
 impl EnterpriseUser {
     /// Validates an enterprise user.
     ///
@@ -84,24 +135,22 @@ impl EnterpriseUser {
     /// ```
     pub fn validate(&self) -> Result<(), SCIMError> {
         if self.employee_number.is_none() {
-            return Err(SCIMError::MissingRequiredField(
-                "employee_number".to_string(),
-            ));
+            return Err(SCIMError::MissingRequiredField("employee_number".into()));
         }
         if self.cost_center.is_none() {
-            return Err(SCIMError::MissingRequiredField("cost_center".to_string()));
+            return Err(SCIMError::MissingRequiredField("cost_center".into()));
         }
         if self.organization.is_none() {
-            return Err(SCIMError::MissingRequiredField("organization".to_string()));
+            return Err(SCIMError::MissingRequiredField("organization".into()));
         }
         if self.division.is_none() {
-            return Err(SCIMError::MissingRequiredField("division".to_string()));
+            return Err(SCIMError::MissingRequiredField("division".into()));
         }
         if self.department.is_none() {
-            return Err(SCIMError::MissingRequiredField("department".to_string()));
+            return Err(SCIMError::MissingRequiredField("department".into()));
         }
         if self.manager.is_none() {
-            return Err(SCIMError::MissingRequiredField("manager".to_string()));
+            return Err(SCIMError::MissingRequiredField("manager".into()));
         }
         Ok(())
     }
@@ -133,6 +182,25 @@ impl EnterpriseUser {
         serde_json::to_string(&self).map_err(SCIMError::SerializationError)
     }
 
+    /// Serializes the `EnterpriseUser` instance as JSON into `buffer`, appending to its current content.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if the `EnterpriseUser` cannot be serialized.
+    pub fn serialize_into(&self, buffer: &mut Vec<u8>) -> Result<(), SCIMError> {
+        json::append_to(buffer, self)
+    }
+
+    /// Serializes the `EnterpriseUser` instance as JSON to `writer`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if the `EnterpriseUser` cannot be serialized or writing to `writer` fails.
+    #[cfg(feature = "std")]
+    pub fn to_writer(&self, writer: impl Write) -> Result<(), SCIMError> {
+        serde_json::to_writer(writer, self).map_err(SCIMError::SerializationError)
+    }
+
     /// Deserializes a JSON string into a `EnterpriseUser` instance, using the custom SCIMError for error handling.
     ///
     /// # Parameters
@@ -165,11 +233,71 @@ impl EnterpriseUser {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Manager {
-    pub value: Option<String>,
-    #[serde(rename = "$ref")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<ResourceId>,
+    #[serde(rename = "$ref", skip_serializing_if = "Option::is_none")]
     pub r#ref: Option<String>,
-    #[serde(rename = "displayName")]
+    #[serde(rename = "displayName", skip_serializing_if = "Option::is_none")]
     pub display_name: Option<String>,
 }
+
+impl Manager {
+    /// Creates a `Manager` referencing the user with the given id.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::enterprise_user::Manager;
+    ///
+    /// let manager = Manager::new("26118915-6090-4610-87e4-49d8ca9f808d")
+    ///     .with_ref("../Users/26118915-6090-4610-87e4-49d8ca9f808d")
+    ///     .with_display_name("John Smith");
+    /// assert_eq!(manager.display_name.as_deref(), Some("John Smith"));
+    /// ```
+    pub fn new(value: impl Into<ResourceId>) -> Self {
+        Manager {
+            value: Some(value.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the URI of the manager's User resource.
+    pub fn with_ref(mut self, r#ref: impl Into<String>) -> Self {
+        self.r#ref = Some(r#ref.into());
+        self
+    }
+
+    /// Sets the manager's display name.
+    pub fn with_display_name(mut self, display_name: impl Into<String>) -> Self {
+        self.display_name = Some(display_name.into());
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn manager_serialization_skips_missing_fields() {
+        let manager = Manager::new("26118915-6090-4610-87e4-49d8ca9f808d");
+        assert_eq!(
+            serde_json::to_value(&manager).unwrap(),
+            json!({"value": "26118915-6090-4610-87e4-49d8ca9f808d"})
+        );
+        assert_eq!(serde_json::to_value(Manager::default()).unwrap(), json!({}));
+    }
+
+    #[test]
+    fn manager_deserialization_accepts_partial_payloads() {
+        let manager: Manager =
+            serde_json::from_value(json!({"displayName": "John Smith"})).unwrap();
+        assert!(manager.value.is_none());
+        assert_eq!(manager.display_name, Some("John Smith".to_string()));
+    }
+}
@@ -0,0 +1,276 @@
+//! HMAC-signed webhook envelopes for SCIM provisioning events.
+//!
+//! Many webhook consumers authenticate deliveries with a secret shared with the sender rather than with the
+//! sender's public key. A [`SignedEnvelope`] carries a delivery in the form of the Standard Webhooks
+//! specification: the body, a message id, a timestamp, and an HMAC-SHA256 signature over all three, sent
+//! as the `webhook-id`, `webhook-timestamp` and `webhook-signature` headers. The body is usually a
+//! [`SecurityEventToken`] as JSON, but may be one already signed as a compact JWS with
+//! [`SecurityEventToken::sign`], for consumers that check both.
+
+use std::fmt;
+use std::fmt::{Debug, Formatter};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::models::events::SecurityEventToken;
+use crate::utils::error::SCIMError;
+use crate::utils::time::now_unix;
+
+/// The header carrying [`SignedEnvelope::id`].
+pub const WEBHOOK_ID: &str = "webhook-id";
+/// The header carrying [`SignedEnvelope::timestamp`].
+pub const WEBHOOK_TIMESTAMP: &str = "webhook-timestamp";
+/// The header carrying [`SignedEnvelope::signature`].
+pub const WEBHOOK_SIGNATURE: &str = "webhook-signature";
+
+/// One signed webhook delivery.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::envelope::{EnvelopeVerifier, SignedEnvelope};
+/// use scim_v2::models::events::{EventBuilder, EventType, SubjectId};
+///
+/// let token = EventBuilder::new("https://example.com/v2", EventType::Delete, SubjectId::new("/Users/2819c223"))
+///     .build();
+/// let envelope = SignedEnvelope::for_token(&token, b"shared secret").unwrap();
+///
+/// // The consumer rebuilds the envelope from the request it received.
+/// let headers = envelope.headers();
+/// let received = SignedEnvelope::from_headers(|name| {
+///     headers.iter().find(|(header, _)| *header == name).map(|(_, value)| value.as_str())
+/// }, &envelope.body)
+/// .unwrap();
+/// let verified = EnvelopeVerifier::new(b"shared secret").open_token(&received).unwrap();
+/// assert_eq!(verified, token);
+/// assert!(EnvelopeVerifier::new(b"other secret").open_token(&received).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedEnvelope {
+    /// A unique id of the delivery, the same for retries of it; consumers use it to drop duplicates.
+    pub id: String,
+    /// When the delivery was signed, in seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// The body of the request.
+    pub body: String,
+    /// Space-separated `v1,<base64 signature>` entries, one per secret the sender signed with.
+    pub signature: String,
+}
+
+impl SignedEnvelope {
+    /// Signs `body` with `secret`, as delivery `id`, at the current time.
+    pub fn seal(id: impl Into<String>, body: impl Into<String>, secret: &[u8]) -> Self {
+        let id = id.into();
+        let body = body.into();
+        let timestamp = now_unix();
+        let signature = format!(
+            "v1,{}",
+            STANDARD.encode(sign(secret, &id, timestamp, &body))
+        );
+        SignedEnvelope {
+            id,
+            timestamp,
+            body,
+            signature,
+        }
+    }
+
+    /// Signs a Security Event Token as JSON, with its `jti` as the delivery id.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if the token cannot be serialized.
+    pub fn for_token(token: &SecurityEventToken, secret: &[u8]) -> Result<Self, SCIMError> {
+        let body = serde_json::to_string(token).map_err(SCIMError::SerializationError)?;
+        Ok(SignedEnvelope::seal(token.jti.clone(), body, secret))
+    }
+
+    /// Returns the headers to send with the body.
+    pub fn headers(&self) -> [(&'static str, String); 3] {
+        [
+            (WEBHOOK_ID, self.id.clone()),
+            (WEBHOOK_TIMESTAMP, self.timestamp.to_string()),
+            (WEBHOOK_SIGNATURE, self.signature.clone()),
+        ]
+    }
+
+    /// Rebuilds an envelope from a received request, looking its headers up with `header`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::MissingRequiredField` if a header is missing, and `SCIMError::InvalidFieldValue` if
+    /// the timestamp is not a number.
+    pub fn from_headers<'a>(
+        header: impl Fn(&str) -> Option<&'a str>,
+        body: &str,
+    ) -> Result<Self, SCIMError> {
+        let required =
+            |name: &str| header(name).ok_or_else(|| SCIMError::MissingRequiredField(name.into()));
+        let timestamp = required(WEBHOOK_TIMESTAMP)?;
+        Ok(SignedEnvelope {
+            id: required(WEBHOOK_ID)?.to_string(),
+            timestamp: timestamp.trim().parse().map_err(|_| {
+                SCIMError::InvalidFieldValue(
+                    format!("{} '{}'", WEBHOOK_TIMESTAMP, timestamp).into(),
+                )
+            })?,
+            body: body.to_string(),
+            signature: required(WEBHOOK_SIGNATURE)?.to_string(),
+        })
+    }
+}
+
+/// Checks the signatures of received [`SignedEnvelope`]s.
+///
+/// A verifier can hold several secrets, so that a sender can rotate its secret without failing deliveries:
+/// an envelope is accepted if one of its signatures matches one of the secrets. Envelopes signed more than
+/// the tolerance (five minutes by default) before or after the current time are rejected, which stops
+/// replays of captured deliveries.
+#[derive(Clone)]
+pub struct EnvelopeVerifier {
+    secrets: Vec<Vec<u8>>,
+    tolerance: u64,
+}
+
+// Secrets stay out of the `Debug` output.
+impl Debug for EnvelopeVerifier {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EnvelopeVerifier")
+            .field("secrets", &self.secrets.len())
+            .field("tolerance", &self.tolerance)
+            .finish()
+    }
+}
+
+impl EnvelopeVerifier {
+    /// Creates a verifier for envelopes signed with `secret`.
+    pub fn new(secret: &[u8]) -> Self {
+        EnvelopeVerifier {
+            secrets: vec![secret.to_vec()],
+            tolerance: 300,
+        }
+    }
+
+    /// Also accepts envelopes signed with `secret`.
+    pub fn with_secret(mut self, secret: &[u8]) -> Self {
+        self.secrets.push(secret.to_vec());
+        self
+    }
+
+    /// Sets how many seconds the timestamp of an envelope may differ from the current time.
+    pub fn with_tolerance(mut self, seconds: u64) -> Self {
+        self.tolerance = seconds;
+        self
+    }
+
+    /// Checks the timestamp and signature of an envelope and returns its body.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::InvalidFieldValue` if the timestamp is outside the tolerance or no signature
+    /// matches.
+    pub fn open<'a>(&self, envelope: &'a SignedEnvelope) -> Result<&'a str, SCIMError> {
+        if now_unix().abs_diff(envelope.timestamp) > self.tolerance {
+            return Err(SCIMError::InvalidFieldValue(
+                format!(
+                    "event envelope: timestamp {} is outside the tolerance",
+                    envelope.timestamp
+                )
+                .into(),
+            ));
+        }
+        let signatures: Vec<Vec<u8>> = envelope
+            .signature
+            .split_whitespace()
+            .filter_map(|entry| entry.strip_prefix("v1,"))
+            .filter_map(|signature| STANDARD.decode(signature).ok())
+            .collect();
+        let verified = self.secrets.iter().any(|secret| {
+            signatures.iter().any(|signature| {
+                mac(secret, &envelope.id, envelope.timestamp, &envelope.body)
+                    .verify_slice(signature)
+                    .is_ok()
+            })
+        });
+        if verified {
+            Ok(&envelope.body)
+        } else {
+            Err(SCIMError::InvalidFieldValue(
+                "event envelope: signature does not match".into(),
+            ))
+        }
+    }
+
+    /// Checks an envelope with [`EnvelopeVerifier::open`] and parses its body as a Security Event Token in
+    /// JSON.
+    ///
+    /// # Errors
+    ///
+    /// The errors of [`EnvelopeVerifier::open`], and `SCIMError::DeserializationError` if the body is not a
+    /// SET.
+    pub fn open_token(&self, envelope: &SignedEnvelope) -> Result<SecurityEventToken, SCIMError> {
+        serde_json::from_str(self.open(envelope)?).map_err(SCIMError::DeserializationError)
+    }
+}
+
+// The signed content is `{id}.{timestamp}.{body}`.
+fn mac(secret: &[u8], id: &str, timestamp: u64, body: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(format!("{}.{}.", id, timestamp).as_bytes());
+    mac.update(body.as_bytes());
+    mac
+}
+
+fn sign(secret: &[u8], id: &str, timestamp: u64, body: &str) -> Vec<u8> {
+    mac(secret, id, timestamp, body)
+        .finalize()
+        .into_bytes()
+        .to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_tampered_stale_and_unsigned_envelopes() {
+        let envelope = SignedEnvelope::seal("msg_1", r#"{"iss":"https://example.com/v2"}"#, b"old");
+        let verifier = EnvelopeVerifier::new(b"new").with_secret(b"old");
+        assert_eq!(verifier.open(&envelope).unwrap(), envelope.body);
+
+        let mut tampered = envelope.clone();
+        tampered.body.push(' ');
+        assert!(verifier.open(&tampered).is_err());
+        let mut replayed = envelope.clone();
+        replayed.id = "msg_2".to_string();
+        assert!(verifier.open(&replayed).is_err());
+        let mut stale = envelope.clone();
+        stale.timestamp -= 301;
+        assert!(verifier.open(&stale).is_err());
+        let mut unsigned = envelope.clone();
+        unsigned.signature = "v1a,abc v1,!!".to_string();
+        assert!(verifier.open(&unsigned).is_err());
+
+        let rotated = SignedEnvelope {
+            signature: format!(
+                "v1,{} {}",
+                STANDARD.encode(sign(
+                    b"new",
+                    &envelope.id,
+                    envelope.timestamp,
+                    &envelope.body
+                )),
+                envelope.signature
+            ),
+            ..envelope.clone()
+        };
+        assert!(EnvelopeVerifier::new(b"new").open(&rotated).is_ok());
+        assert!(matches!(
+            SignedEnvelope::from_headers(|_| None, "{}"),
+            Err(SCIMError::MissingRequiredField(_))
+        ));
+    }
+}
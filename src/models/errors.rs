@@ -1,4 +1,13 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::models::schema_uri::ERROR_URN;
+use crate::utils::error::SCIMError;
 
 /// Represents a SCIM HTTP Error.
 ///
@@ -8,6 +17,7 @@ use serde::{Deserialize, Serialize};
 /// The `detail` field is an optional string that provides more detailed human-readable information.
 /// The `status` field is a required string that is the HTTP status code expressed as a JSON string.
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ScimHttpError {
     pub schemas: Vec<String>,
     #[serde(rename = "scimType", skip_serializing_if = "Option::is_none")]
@@ -26,7 +36,7 @@ pub struct ScimHttpError {
 impl Default for ScimHttpError {
     fn default() -> Self {
         ScimHttpError {
-            schemas: vec!["urn:ietf:params:scim:api:messages:2.0:Error".to_string()],
+            schemas: vec![ERROR_URN.to_string()],
             scim_type: None,
             detail: None,
             status: "".to_string(),
@@ -34,6 +44,108 @@ impl Default for ScimHttpError {
     }
 }
 
+/// Converts an already-parsed JSON value into a `ScimHttpError` struct.
+///
+/// Web frameworks usually hand over request bodies as a `serde_json::Value`; this conversion avoids
+/// round-tripping that value through a string.
+///
+/// # Errors
+///
+/// Returns `SCIMError::DeserializationError` if the value does not describe a `ScimHttpError` object.
+///
+/// # Examples
+///
+/// ```rust
+/// use scim_v2::models::errors::ScimHttpError;
+/// use serde_json::json;
+///
+/// let value = json!({"schemas": ["urn:ietf:params:scim:api:messages:2.0:Error"], "scimType": "invalidValue", "status": "400"});
+/// match ScimHttpError::try_from(value) {
+///     Ok(resource) => println!("Successfully converted JSON to ScimHttpError: {:?}", resource),
+///     Err(e) => println!("Error converting from JSON to ScimHttpError: {}", e),
+/// }
+/// ```
+/// Maps an error to the SCIM error response a server should send for it (RFC 7644 §3.12).
+///
+/// Errors caused by the request map to `400` with the matching `scimType`, missing resources to `404`,
+/// uniqueness conflicts to `409`, deleted resources to `410`, version mismatches to `412` and payloads over the configured limits to `413`; serialization and other
+/// errors map to `500`. The detail is the error's message.
+impl From<&SCIMError> for ScimHttpError {
+    fn from(error: &SCIMError) -> Self {
+        let (status, scim_type) = match error {
+            SCIMError::ConflictError(_) => ("409", Some("uniqueness")),
+            SCIMError::DeserializationError(_) | SCIMError::InvalidJsonFormat => {
+                ("400", Some("invalidSyntax"))
+            }
+            SCIMError::InvalidFieldValue(_)
+            | SCIMError::MissingRequiredField(_)
+            | SCIMError::SchemaNotFound(_) => ("400", Some("invalidValue")),
+            SCIMError::InvalidFilter(_) => ("400", Some("invalidFilter")),
+            SCIMError::InvalidPath(_) => ("400", Some("invalidPath")),
+            SCIMError::NoTarget(_) => ("400", Some("noTarget")),
+            SCIMError::RequestError(_) => ("400", None),
+            SCIMError::NotFoundError(_) | SCIMError::ResourceTypeNotFound(_) => ("404", None),
+            SCIMError::Gone(_) => ("410", None),
+            SCIMError::PreconditionFailed(_) => ("412", None),
+            SCIMError::PayloadTooComplex(_) => ("413", None),
+            SCIMError::OtherError(_) | SCIMError::SerializationError(_) => ("500", None),
+        };
+        ScimHttpError {
+            scim_type: scim_type.map(str::to_string),
+            detail: Some(error.to_string()),
+            status: status.to_string(),
+            ..ScimHttpError::default()
+        }
+    }
+}
+
+/// Maps an error response received from a service provider back to an error.
+///
+/// `404` maps to `NotFoundError`, `409` to `ConflictError`, `410` to `Gone`, `412` to `PreconditionFailed`,
+/// `413` to `PayloadTooComplex`, and `400` with the `invalidFilter`, `invalidPath`, `noTarget` or `invalidValue`
+/// `scimType` to `InvalidFilter`, `InvalidPath`, `NoTarget` or `InvalidFieldValue`, each carrying the response's detail. Other responses map to `RequestError`, carrying the status, `scimType` and detail.
+impl From<&ScimHttpError> for SCIMError {
+    fn from(error: &ScimHttpError) -> Self {
+        let detail = error.detail.clone().unwrap_or_default();
+        match (error.status.as_str(), error.scim_type.as_deref()) {
+            ("404", _) => SCIMError::NotFoundError(detail.into()),
+            ("409", _) => SCIMError::ConflictError(detail.into()),
+            ("410", _) => SCIMError::Gone(detail.into()),
+            ("412", _) => SCIMError::PreconditionFailed(detail.into()),
+            ("413", _) => SCIMError::PayloadTooComplex(detail.into()),
+            ("400", Some("invalidFilter")) => SCIMError::InvalidFilter(detail.into()),
+            ("400", Some("invalidPath")) => SCIMError::InvalidPath(detail.into()),
+            ("400", Some("noTarget")) => SCIMError::NoTarget(detail.into()),
+            ("400", Some("invalidValue")) => SCIMError::InvalidFieldValue(detail.into()),
+            (status, Some(scim_type)) => {
+                SCIMError::RequestError(format!("{} {}: {}", status, scim_type, detail).into())
+            }
+            (status, None) => SCIMError::RequestError(format!("{}: {}", status, detail).into()),
+        }
+    }
+}
+
+impl TryFrom<Value> for ScimHttpError {
+    type Error = SCIMError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        serde_json::from_value(value).map_err(SCIMError::DeserializationError)
+    }
+}
+
+/// Converts a `ScimHttpError` struct into a JSON value.
+///
+/// # Errors
+///
+/// Returns `SCIMError::SerializationError` if the `ScimHttpError` cannot be represented as JSON.
+impl TryFrom<ScimHttpError> for Value {
+    type Error = SCIMError;
+
+    fn try_from(value: ScimHttpError) -> Result<Self, Self::Error> {
+        serde_json::to_value(value).map_err(SCIMError::SerializationError)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use serde_json::json;
@@ -0,0 +1,563 @@
+//! SCIM provisioning events as Security Event Tokens.
+//!
+//! A service provider that notifies subscribers of changes sends each change as a Security Event Token (SET,
+//! RFC 8417) carrying one of the provisioning events of the SCIM Events draft (`draft-ietf-scim-events`):
+//! the token names the affected resource in `sub_id` and describes the change under its event URI in
+//! `events`. With the `jose` feature, tokens can be signed as and verified from compact JWS strings.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Value, json};
+
+use crate::models::resource_types::ResourceType;
+use crate::models::schema_uri::PATCH_OP_URN;
+use crate::provider::common::generate_id;
+use crate::utils::error::SCIMError;
+use crate::utils::time::now_unix;
+
+/// The provisioning events of the SCIM Events draft.
+///
+/// `Notice` events only name the changed attributes, `Full` events carry the changed data as well.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventType {
+    /// A resource was created; `attributes` lists the attributes it was created with.
+    CreateNotice,
+    /// A resource was created; `data` holds the resource.
+    CreateFull,
+    /// A resource was replaced; `attributes` lists the replaced attributes.
+    PutNotice,
+    /// A resource was replaced; `data` holds the resource as replaced.
+    PutFull,
+    /// A resource was patched; `attributes` lists the modified attributes.
+    PatchNotice,
+    /// A resource was patched; `data` holds the `PatchOp` message that was applied.
+    PatchFull,
+    /// A resource was deleted.
+    Delete,
+    /// A resource was activated.
+    Activate,
+    /// A resource was deactivated.
+    Deactivate,
+}
+
+impl EventType {
+    const ALL: [EventType; 9] = [
+        EventType::CreateNotice,
+        EventType::CreateFull,
+        EventType::PutNotice,
+        EventType::PutFull,
+        EventType::PatchNotice,
+        EventType::PatchFull,
+        EventType::Delete,
+        EventType::Activate,
+        EventType::Deactivate,
+    ];
+
+    /// Returns the event URI, e.g. `urn:ietf:params:SCIM:event:prov:create:full`.
+    pub fn uri(self) -> &'static str {
+        match self {
+            EventType::CreateNotice => "urn:ietf:params:SCIM:event:prov:create:notice",
+            EventType::CreateFull => "urn:ietf:params:SCIM:event:prov:create:full",
+            EventType::PutNotice => "urn:ietf:params:SCIM:event:prov:put:notice",
+            EventType::PutFull => "urn:ietf:params:SCIM:event:prov:put:full",
+            EventType::PatchNotice => "urn:ietf:params:SCIM:event:prov:patch:notice",
+            EventType::PatchFull => "urn:ietf:params:SCIM:event:prov:patch:full",
+            EventType::Delete => "urn:ietf:params:SCIM:event:prov:delete",
+            EventType::Activate => "urn:ietf:params:SCIM:event:prov:activate",
+            EventType::Deactivate => "urn:ietf:params:SCIM:event:prov:deactivate",
+        }
+    }
+}
+
+impl Display for EventType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.uri())
+    }
+}
+
+impl FromStr for EventType {
+    type Err = SCIMError;
+
+    /// Parses an event URI. URNs are case-insensitive, so is the comparison.
+    fn from_str(uri: &str) -> Result<Self, Self::Err> {
+        EventType::ALL
+            .into_iter()
+            .find(|event| event.uri().eq_ignore_ascii_case(uri))
+            .ok_or_else(|| {
+                SCIMError::InvalidFieldValue(format!("unknown SCIM event '{}'", uri).into())
+            })
+    }
+}
+
+/// Identifies the resource an event is about (the `scim` subject identifier format of the SCIM Events draft).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SubjectId {
+    /// Always `scim`.
+    pub format: String,
+    /// The path of the resource relative to the service provider's base URL, e.g. `/Users/2819c223`.
+    pub uri: String,
+    #[serde(rename = "externalId", skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+}
+
+impl SubjectId {
+    /// Creates a subject identifier for a resource path such as `/Users/2819c223`.
+    pub fn new(uri: impl Into<String>) -> Self {
+        SubjectId {
+            format: "scim".to_string(),
+            uri: uri.into(),
+            external_id: None,
+        }
+    }
+
+    /// Creates the subject identifier of a stored resource from the resource type's endpoint and the
+    /// resource's `id`, copying its `externalId`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::MissingRequiredField` if the resource has no `id`.
+    pub fn for_resource(resource_type: &ResourceType, resource: &Value) -> Result<Self, SCIMError> {
+        let id = resource["id"]
+            .as_str()
+            .ok_or_else(|| SCIMError::MissingRequiredField("id".into()))?;
+        Ok(SubjectId {
+            external_id: resource["externalId"].as_str().map(str::to_string),
+            ..SubjectId::new(format!(
+                "/{}/{}",
+                resource_type.endpoint.trim_matches('/'),
+                id
+            ))
+        })
+    }
+}
+
+/// The body of one event in a [`SecurityEventToken`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct EventPayload {
+    /// The names of the attributes the event is about.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attributes: Option<Vec<String>>,
+    /// The resource, or for `patch:full` events the `PatchOp` message.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+/// A Security Event Token (RFC 8417) carrying SCIM provisioning events.
+///
+/// Build tokens with [`EventBuilder`]; the claims serialize as the JWT payload.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SecurityEventToken {
+    /// The issuer, usually the service provider's base URL.
+    pub iss: String,
+    /// When the token was issued, in seconds since the Unix epoch.
+    pub iat: u64,
+    /// A unique identifier of the token.
+    pub jti: String,
+    /// The intended recipients. A single audience is accepted as a string when deserializing.
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        deserialize_with = "one_or_many"
+    )]
+    pub aud: Vec<String>,
+    /// A transaction identifier shared by the events of one change, e.g. a request's correlation id.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub txn: Option<String>,
+    /// When the change happened, in seconds since the Unix epoch, if that differs from `iat`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub toe: Option<u64>,
+    /// The resource the events are about.
+    pub sub_id: SubjectId,
+    /// The events, keyed by event URI.
+    pub events: BTreeMap<String, EventPayload>,
+}
+
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(audience) => vec![audience],
+        OneOrMany::Many(audiences) => audiences,
+    })
+}
+
+impl SecurityEventToken {
+    /// Returns the provisioning events of the token, skipping event URIs this crate does not know.
+    pub fn provisioning_events(&self) -> impl Iterator<Item = (EventType, &EventPayload)> {
+        self.events
+            .iter()
+            .filter_map(|(uri, payload)| Some((uri.parse().ok()?, payload)))
+    }
+}
+
+#[cfg(feature = "jose")]
+impl SecurityEventToken {
+    /// Signs the token as a compact JWS with the `typ` header `secevent+jwt` (RFC 8417 §2.3).
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::OtherError` if `key` cannot be used with `algorithm`.
+    pub fn sign(
+        &self,
+        algorithm: jsonwebtoken::Algorithm,
+        key: &jsonwebtoken::EncodingKey,
+    ) -> Result<String, SCIMError> {
+        let mut header = jsonwebtoken::Header::new(algorithm);
+        header.typ = Some("secevent+jwt".to_string());
+        jsonwebtoken::encode(&header, self, key)
+            .map_err(|e| SCIMError::OtherError(format!("jose: {}", e).into()))
+    }
+
+    /// Verifies a compact JWS signed with `algorithm` and returns its claims.
+    ///
+    /// SETs carry no expiry, so none is required. If `audience` is not empty, the token's `aud` must
+    /// contain one of its entries.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::InvalidFieldValue` if the signature, algorithm, `typ` header or audience do not
+    /// check out, or the claims are not a SET.
+    pub fn verify(
+        token: &str,
+        key: &jsonwebtoken::DecodingKey,
+        algorithm: jsonwebtoken::Algorithm,
+        audience: &[&str],
+    ) -> Result<Self, SCIMError> {
+        let invalid = |e: jsonwebtoken::errors::Error| {
+            SCIMError::InvalidFieldValue(format!("security event token: {}", e).into())
+        };
+        let header = jsonwebtoken::decode_header(token).map_err(invalid)?;
+        if !header
+            .typ
+            .as_deref()
+            .is_some_and(|typ| typ.eq_ignore_ascii_case("secevent+jwt"))
+        {
+            return Err(SCIMError::InvalidFieldValue(
+                "security event token: typ must be secevent+jwt".into(),
+            ));
+        }
+        let mut validation = jsonwebtoken::Validation::new(algorithm);
+        validation.required_spec_claims.clear();
+        validation.validate_exp = false;
+        if audience.is_empty() {
+            validation.validate_aud = false;
+        } else {
+            validation.set_audience(audience);
+        }
+        jsonwebtoken::decode(token, key, &validation)
+            .map(|data| data.claims)
+            .map_err(invalid)
+    }
+}
+
+/// Builds a [`SecurityEventToken`] carrying one provisioning event.
+///
+/// The constructors for common changes take the stored resource and fill in the subject and payload;
+/// [`EventBuilder::new`] covers the other events. `jti` and `iat` are assigned by [`EventBuilder::build`].
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::events::{EventBuilder, EventType};
+/// use scim_v2::models::schema_registry::SchemaRegistry;
+/// use serde_json::json;
+///
+/// let registry = SchemaRegistry::with_builtins();
+/// let user = json!({"id": "2819c223", "externalId": "bjensen", "userName": "bjensen"});
+/// let token = EventBuilder::created("https://example.com/v2", registry.resource_type("User").unwrap(), &user)
+///     .unwrap()
+///     .audience("https://subscriber.example.net")
+///     .build();
+///
+/// assert_eq!(token.sub_id.uri, "/Users/2819c223");
+/// let (event, payload) = token.provisioning_events().next().unwrap();
+/// assert_eq!(event, EventType::CreateFull);
+/// assert_eq!(payload.data.as_ref(), Some(&user));
+/// ```
+#[derive(Debug, Clone)]
+pub struct EventBuilder {
+    issuer: String,
+    audience: Vec<String>,
+    event_type: EventType,
+    subject: SubjectId,
+    payload: EventPayload,
+    transaction: Option<String>,
+    time_of_event: Option<u64>,
+}
+
+impl EventBuilder {
+    /// Starts a token from `issuer` carrying `event_type` about `subject`, with an empty payload.
+    pub fn new(issuer: impl Into<String>, event_type: EventType, subject: SubjectId) -> Self {
+        EventBuilder {
+            issuer: issuer.into(),
+            audience: Vec::new(),
+            event_type,
+            subject,
+            payload: EventPayload::default(),
+            transaction: None,
+            time_of_event: None,
+        }
+    }
+
+    /// Starts a `create:full` event for a newly created resource.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::MissingRequiredField` if the resource has no `id`.
+    pub fn created(
+        issuer: impl Into<String>,
+        resource_type: &ResourceType,
+        resource: &Value,
+    ) -> Result<Self, SCIMError> {
+        Ok(EventBuilder::new(
+            issuer,
+            EventType::CreateFull,
+            SubjectId::for_resource(resource_type, resource)?,
+        )
+        .data(resource.clone()))
+    }
+
+    /// Starts a `put:full` event for a replaced resource.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::MissingRequiredField` if the resource has no `id`.
+    pub fn replaced(
+        issuer: impl Into<String>,
+        resource_type: &ResourceType,
+        resource: &Value,
+    ) -> Result<Self, SCIMError> {
+        Ok(EventBuilder::new(
+            issuer,
+            EventType::PutFull,
+            SubjectId::for_resource(resource_type, resource)?,
+        )
+        .data(resource.clone()))
+    }
+
+    /// Starts a `delete` event for a deleted resource.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::MissingRequiredField` if the resource has no `id`.
+    pub fn deleted(
+        issuer: impl Into<String>,
+        resource_type: &ResourceType,
+        resource: &Value,
+    ) -> Result<Self, SCIMError> {
+        Ok(EventBuilder::new(
+            issuer,
+            EventType::Delete,
+            SubjectId::for_resource(resource_type, resource)?,
+        ))
+    }
+
+    /// Starts a `patch:full` event for a change of a group's members.
+    ///
+    /// The payload's `data` is the `PatchOp` message that adds the `added` member ids and removes the
+    /// `removed` ones, and `attributes` is `["members"]`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::MissingRequiredField` if the group has no `id`.
+    pub fn membership_changed(
+        issuer: impl Into<String>,
+        resource_type: &ResourceType,
+        group: &Value,
+        added: &[&str],
+        removed: &[&str],
+    ) -> Result<Self, SCIMError> {
+        let mut operations = Vec::new();
+        if !added.is_empty() {
+            let members: Vec<Value> = added.iter().map(|id| json!({"value": id})).collect();
+            operations.push(json!({"op": "add", "path": "members", "value": members}));
+        }
+        for id in removed {
+            operations.push(json!({
+                "op": "remove",
+                "path": format!("members[value eq {}]", Value::from(*id)),
+            }));
+        }
+        Ok(EventBuilder::new(
+            issuer,
+            EventType::PatchFull,
+            SubjectId::for_resource(resource_type, group)?,
+        )
+        .attributes(vec!["members".to_string()])
+        .data(json!({"schemas": [PATCH_OP_URN], "Operations": operations})))
+    }
+
+    /// Adds a recipient to `aud`.
+    pub fn audience(mut self, audience: impl Into<String>) -> Self {
+        self.audience.push(audience.into());
+        self
+    }
+
+    /// Sets the payload's `attributes`.
+    pub fn attributes(mut self, attributes: Vec<String>) -> Self {
+        self.payload.attributes = Some(attributes);
+        self
+    }
+
+    /// Sets the payload's `data`.
+    pub fn data(mut self, data: Value) -> Self {
+        self.payload.data = Some(data);
+        self
+    }
+
+    /// Sets the transaction identifier `txn`.
+    pub fn transaction(mut self, transaction: impl Into<String>) -> Self {
+        self.transaction = Some(transaction.into());
+        self
+    }
+
+    /// Sets the time of the change `toe`, in seconds since the Unix epoch.
+    pub fn time_of_event(mut self, secs: u64) -> Self {
+        self.time_of_event = Some(secs);
+        self
+    }
+
+    /// Finishes the token, issued now with a random `jti`.
+    pub fn build(self) -> SecurityEventToken {
+        SecurityEventToken {
+            iss: self.issuer,
+            iat: now_unix(),
+            jti: generate_id(),
+            aud: self.audience,
+            txn: self.transaction,
+            toe: self.time_of_event,
+            sub_id: self.subject,
+            events: BTreeMap::from([(self.event_type.uri().to_string(), self.payload)]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::schema_registry::SchemaRegistry;
+
+    #[test]
+    fn serializes_membership_changes_as_patch_events() {
+        let registry = SchemaRegistry::with_builtins();
+        let group = json!({"id": "e9e30dba", "displayName": "Tour Guides"});
+        let token = EventBuilder::membership_changed(
+            "https://example.com/v2",
+            registry.resource_type("Group").unwrap(),
+            &group,
+            &["2819c223"],
+            &["902c246b"],
+        )
+        .unwrap()
+        .audience("https://subscriber.example.net")
+        .transaction("req-42")
+        .build();
+
+        let value = serde_json::to_value(&token).unwrap();
+        assert_eq!(
+            value["sub_id"],
+            json!({"format": "scim", "uri": "/Groups/e9e30dba"})
+        );
+        assert_eq!(value["aud"], json!(["https://subscriber.example.net"]));
+        assert_eq!(value["txn"], "req-42");
+        let event = &value["events"]["urn:ietf:params:SCIM:event:prov:patch:full"];
+        assert_eq!(event["attributes"], json!(["members"]));
+        assert_eq!(
+            event["data"]["Operations"],
+            json!([
+                {"op": "add", "path": "members", "value": [{"value": "2819c223"}]},
+                {"op": "remove", "path": "members[value eq \"902c246b\"]"}
+            ])
+        );
+
+        let read_back: SecurityEventToken = serde_json::from_value(value).unwrap();
+        assert_eq!(read_back, token);
+    }
+
+    #[test]
+    fn parses_event_uris_and_single_audiences() {
+        assert_eq!(
+            "urn:ietf:params:scim:event:prov:DELETE"
+                .parse::<EventType>()
+                .unwrap(),
+            EventType::Delete
+        );
+        assert!(
+            "urn:ietf:params:SCIM:event:misc:asyncResp"
+                .parse::<EventType>()
+                .is_err()
+        );
+
+        let token: SecurityEventToken = serde_json::from_value(json!({
+            "iss": "https://example.com/v2",
+            "iat": 1_458_496_404,
+            "jti": "4d3559ec67504aaba65d40b0363faad8",
+            "aud": "https://subscriber.example.net",
+            "sub_id": {"format": "scim", "uri": "/Users/44f6142df96bd6ab61e7521d9"},
+            "events": {
+                "urn:ietf:params:SCIM:event:prov:deactivate": {},
+                "urn:example:event:custom": {}
+            }
+        }))
+        .unwrap();
+        assert_eq!(token.aud, ["https://subscriber.example.net"]);
+        let events: Vec<EventType> = token.provisioning_events().map(|(e, _)| e).collect();
+        assert_eq!(events, [EventType::Deactivate]);
+    }
+
+    #[cfg(feature = "jose")]
+    #[test]
+    fn signs_and_verifies_tokens() {
+        use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey};
+
+        let registry = SchemaRegistry::with_builtins();
+        let user = json!({"id": "2819c223", "userName": "bjensen"});
+        let token = EventBuilder::deleted(
+            "https://example.com/v2",
+            registry.resource_type("User").unwrap(),
+            &user,
+        )
+        .unwrap()
+        .audience("https://subscriber.example.net")
+        .build();
+        let jws = token
+            .sign(Algorithm::HS256, &EncodingKey::from_secret(b"secret"))
+            .unwrap();
+
+        let key = DecodingKey::from_secret(b"secret");
+        let verified = SecurityEventToken::verify(
+            &jws,
+            &key,
+            Algorithm::HS256,
+            &["https://subscriber.example.net"],
+        )
+        .unwrap();
+        assert_eq!(verified, token);
+        assert!(
+            SecurityEventToken::verify(
+                &jws,
+                &key,
+                Algorithm::HS256,
+                &["https://other.example.net"]
+            )
+            .is_err()
+        );
+        assert!(
+            SecurityEventToken::verify(
+                &jws,
+                &DecodingKey::from_secret(b"other"),
+                Algorithm::HS256,
+                &[]
+            )
+            .is_err()
+        );
+    }
+}
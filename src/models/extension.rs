@@ -0,0 +1,159 @@
+use alloc::string::ToString;
+use alloc::vec;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::models::enterprise_user::EnterpriseUser;
+use crate::models::resource_types::SchemaExtension;
+use crate::models::schema_uri::ENTERPRISE_USER_URN;
+use crate::utils::error::SCIMError;
+
+/// A schema extension that can be attached to a SCIM resource.
+///
+/// Extension attributes live under the extension's schema URI in the resource representation, and the URI
+/// is listed in the resource's `schemas` array. This trait binds a Rust type to its URI and provides the glue
+/// to read it from, and write it into, a resource's JSON representation.
+///
+/// With the `derive` feature enabled, `#[derive(ScimExtension)]` implements this trait from a
+/// `#[scim(urn = "...")]` attribute. The struct must also carry `#[serde(rename_all = "camelCase")]`, so that its
+/// serialized attribute names match the names reported by [`ScimExtension::attribute_names`]; the derive
+/// fails to compile otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::enterprise_user::EnterpriseUser;
+/// use scim_v2::models::extension::ScimExtension;
+/// use serde_json::json;
+///
+/// let mut user = json!({
+///     "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+///     "userName": "bjensen@example.com"
+/// });
+/// let extension = EnterpriseUser {
+///     employee_number: Some("701984".to_string()),
+///     ..Default::default()
+/// };
+/// extension.insert_into(&mut user).unwrap();
+///
+/// let read_back = EnterpriseUser::from_resource(&user).unwrap().unwrap();
+/// assert_eq!(read_back.employee_number.as_deref(), Some("701984"));
+/// ```
+pub trait ScimExtension: Serialize + DeserializeOwned {
+    /// The schema URI of the extension.
+    const URN: &'static str;
+
+    /// The attribute names of the extension, as they appear in the JSON representation.
+    fn attribute_names() -> &'static [&'static str];
+
+    /// Reads the extension from a resource representation.
+    ///
+    /// Returns `Ok(None)` if the resource does not carry the extension.
+    fn from_resource(resource: &Value) -> Result<Option<Self>, SCIMError> {
+        resource
+            .get(Self::URN)
+            .map(|value| Self::deserialize(value).map_err(SCIMError::DeserializationError))
+            .transpose()
+    }
+
+    /// Writes the extension into a resource representation and lists its URI in `schemas`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::InvalidJsonFormat` if the resource is not a JSON object, or
+    /// `SCIMError::SerializationError` if the extension cannot be serialized.
+    fn insert_into(&self, resource: &mut Value) -> Result<(), SCIMError> {
+        let object = resource
+            .as_object_mut()
+            .ok_or(SCIMError::InvalidJsonFormat)?;
+        let value = serde_json::to_value(self).map_err(SCIMError::SerializationError)?;
+        object.insert(Self::URN.to_string(), value);
+        let schemas = object
+            .entry("schemas")
+            .or_insert_with(|| Value::Array(vec![]));
+        if let Value::Array(schemas) = schemas {
+            if !schemas.iter().any(|s| s == Self::URN) {
+                schemas.push(Value::String(Self::URN.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the `schemaExtensions` entry that registers this extension on a resource type.
+    fn schema_extension(required: bool) -> SchemaExtension {
+        SchemaExtension {
+            schema: Self::URN.to_string(),
+            required,
+        }
+    }
+}
+
+impl ScimExtension for EnterpriseUser {
+    const URN: &'static str = ENTERPRISE_USER_URN;
+
+    fn attribute_names() -> &'static [&'static str] {
+        &[
+            "employeeNumber",
+            "costCenter",
+            "organization",
+            "division",
+            "department",
+            "manager",
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::*;
+    use scim_v2_derive::ScimExtension;
+
+    #[derive(Serialize, Deserialize, Debug, ScimExtension)]
+    #[serde(rename_all = "camelCase")]
+    #[scim(urn = "urn:example:params:scim:schemas:extension:license:2.0:User")]
+    struct LicenseExtension {
+        license_key: Option<String>,
+        #[serde(rename = "seatCount")]
+        seats: Option<i64>,
+        #[serde(skip)]
+        validated: bool,
+    }
+
+    #[test]
+    fn derived_extension_binds_urn_and_attribute_names() {
+        assert_eq!(
+            LicenseExtension::URN,
+            "urn:example:params:scim:schemas:extension:license:2.0:User"
+        );
+        assert_eq!(
+            LicenseExtension::attribute_names(),
+            &["licenseKey", "seatCount"]
+        );
+        let extension = LicenseExtension::schema_extension(false);
+        assert_eq!(extension.schema, LicenseExtension::URN);
+        assert!(!extension.required);
+    }
+
+    #[test]
+    fn derived_extension_round_trips_through_resource() {
+        let mut user = json!({"userName": "bjensen@example.com"});
+        let license = LicenseExtension {
+            license_key: Some("ABC-123".to_string()),
+            seats: Some(2),
+            validated: true,
+        };
+        license.insert_into(&mut user).unwrap();
+        assert_eq!(user["schemas"], json!([LicenseExtension::URN]));
+        assert_eq!(user[LicenseExtension::URN]["licenseKey"], "ABC-123");
+
+        let read_back = LicenseExtension::from_resource(&user).unwrap().unwrap();
+        assert_eq!(read_back.seats, Some(2));
+        assert!(!read_back.validated);
+        assert!(EnterpriseUser::from_resource(&user).unwrap().is_none());
+    }
+}
@@ -0,0 +1,926 @@
+//! SCIM filter expressions (RFC 7644 §3.4.2.2), parsed into a typed syntax tree.
+//!
+//! A filter such as `userType eq "Employee" and (emails co "example.com" or emails.value co "example.org")`
+//! parses into a [`FilterExpr`], whose leaves compare an [`AttrPath`] with a value. Formatting a
+//! `FilterExpr` writes it back as filter text, so expressions can also be built in code and sent to a
+//! service provider.
+//!
+//! `not` binds tighter than `and`, which binds tighter than `or`; parentheses group expressions, and
+//! brackets apply a filter to the values of a multi-valued attribute (`emails[type eq "work"]`).
+
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::str::FromStr;
+
+use serde_json::Value;
+
+use crate::models::resource::ScimResource;
+use crate::models::schema_registry::{SchemaRegistry, read_global};
+use crate::utils::error::SCIMError;
+
+/// An attribute path: an optional schema URI, an attribute name and an optional sub-attribute name, e.g.
+/// `name.familyName` or `urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:employeeNumber`.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::filter::AttrPath;
+///
+/// let path = AttrPath::parse("urn:ietf:params:scim:schemas:core:2.0:User:name.familyName").unwrap();
+/// assert_eq!(path.uri.as_deref(), Some("urn:ietf:params:scim:schemas:core:2.0:User"));
+/// assert_eq!(path.name, "name");
+/// assert_eq!(path.sub_attribute.as_deref(), Some("familyName"));
+/// assert!(AttrPath::parse("name.familyName.x").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AttrPath {
+    /// The schema URI the attribute belongs to, if the path is qualified with one.
+    pub uri: Option<String>,
+    pub name: String,
+    pub sub_attribute: Option<String>,
+}
+
+impl AttrPath {
+    /// Creates an unqualified path to the attribute `name`.
+    pub fn new(name: impl Into<String>) -> Self {
+        AttrPath {
+            uri: None,
+            name: name.into(),
+            sub_attribute: None,
+        }
+    }
+
+    /// Parses an attribute path.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::InvalidFilter` if `path` is not an attribute path.
+    pub fn parse(path: &str) -> Result<Self, SCIMError> {
+        let invalid =
+            || SCIMError::InvalidFilter(format!("invalid attribute path '{}'", path).into());
+        let (uri, attribute) = match path.rsplit_once(':') {
+            Some((uri, attribute)) if !uri.is_empty() => (Some(uri.to_string()), attribute),
+            Some(_) => return Err(invalid()),
+            None => (None, path),
+        };
+        let (name, sub_attribute) = match attribute.split_once('.') {
+            Some((name, sub_attribute)) => (name, Some(sub_attribute)),
+            None => (attribute, None),
+        };
+        if !is_attribute_name(name) || !sub_attribute.is_none_or(is_attribute_name) {
+            return Err(invalid());
+        }
+        Ok(AttrPath {
+            uri,
+            name: name.to_string(),
+            sub_attribute: sub_attribute.map(str::to_string),
+        })
+    }
+
+    /// Returns the path without its schema URI, e.g. `name.familyName`.
+    pub fn unqualified(&self) -> String {
+        match &self.sub_attribute {
+            Some(sub_attribute) => format!("{}.{}", self.name, sub_attribute),
+            None => self.name.clone(),
+        }
+    }
+}
+
+impl fmt::Display for AttrPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(uri) = &self.uri {
+            write!(f, "{}:", uri)?;
+        }
+        f.write_str(&self.name)?;
+        if let Some(sub_attribute) = &self.sub_attribute {
+            write!(f, ".{}", sub_attribute)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for AttrPath {
+    type Err = SCIMError;
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        AttrPath::parse(path)
+    }
+}
+
+// ATTRNAME = ALPHA *(nameChar), nameChar = "-" / "_" / DIGIT / ALPHA; `$ref` is allowed as well.
+fn is_attribute_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    name == "$ref"
+        || chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// A comparison operator of a filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Co,
+    Sw,
+    Ew,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl CompareOp {
+    /// Returns the operator as written in filters, e.g. `eq`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CompareOp::Eq => "eq",
+            CompareOp::Ne => "ne",
+            CompareOp::Co => "co",
+            CompareOp::Sw => "sw",
+            CompareOp::Ew => "ew",
+            CompareOp::Gt => "gt",
+            CompareOp::Ge => "ge",
+            CompareOp::Lt => "lt",
+            CompareOp::Le => "le",
+        }
+    }
+
+    /// Parses an operator, case-insensitively.
+    pub fn parse(operator: &str) -> Option<Self> {
+        [
+            CompareOp::Eq,
+            CompareOp::Ne,
+            CompareOp::Co,
+            CompareOp::Sw,
+            CompareOp::Ew,
+            CompareOp::Gt,
+            CompareOp::Ge,
+            CompareOp::Lt,
+            CompareOp::Le,
+        ]
+        .into_iter()
+        .find(|op| op.as_str().eq_ignore_ascii_case(operator))
+    }
+}
+
+impl fmt::Display for CompareOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A parsed filter.
+///
+/// Compared values are JSON strings, numbers, booleans or `null`. Within the brackets of a
+/// [`FilterExpr::ValuePath`], attribute paths name the sub-attributes of the multi-valued attribute.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::filter::{AttrPath, CompareOp, FilterExpr};
+/// use serde_json::json;
+///
+/// let filter = FilterExpr::parse(r#"title pr and not (userType eq "Intern" or emails[type eq "home"])"#).unwrap();
+/// let FilterExpr::And(left, right) = &filter else { panic!() };
+/// assert_eq!(**left, FilterExpr::Present(AttrPath::new("title")));
+/// assert!(matches!(**right, FilterExpr::Not(_)));
+/// assert_eq!(
+///     filter.to_string(),
+///     r#"title pr and not (userType eq "Intern" or emails[type eq "home"])"#
+/// );
+///
+/// let built = FilterExpr::Compare(AttrPath::new("userName"), CompareOp::Eq, json!("bjensen"));
+/// assert_eq!(built.to_string(), r#"userName eq "bjensen""#);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterExpr {
+    /// `attrPath pr`: the attribute has a value.
+    Present(AttrPath),
+    /// `attrPath op value`.
+    Compare(AttrPath, CompareOp, Value),
+    /// `attrPath[filter]`: some value of a multi-valued attribute matches the filter.
+    ValuePath(AttrPath, Box<FilterExpr>),
+    And(Box<FilterExpr>, Box<FilterExpr>),
+    Or(Box<FilterExpr>, Box<FilterExpr>),
+    Not(Box<FilterExpr>),
+}
+
+impl FilterExpr {
+    /// Parses a filter.
+    ///
+    /// Operators and the keywords `and`, `or`, `not`, `true`, `false` and `null` are case-insensitive.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::InvalidFilter`, naming the unexpected token, if `filter` is not a filter, or if
+    /// it nests groupings, `not` and value paths more than 64 levels deep.
+    pub fn parse(filter: &str) -> Result<Self, SCIMError> {
+        let tokens = tokenize(filter)?;
+        if tokens.is_empty() {
+            return Err(SCIMError::InvalidFilter("empty filter".into()));
+        }
+        let mut parser = Parser {
+            filter,
+            tokens,
+            next: 0,
+            depth: 0,
+        };
+        let expression = parser.or(false)?;
+        match parser.tokens.get(parser.next) {
+            Some(token) => Err(parser.unexpected(token)),
+            None => Ok(expression),
+        }
+    }
+
+    /// Returns `true` if `resource` matches the filter.
+    ///
+    /// Strings compare case-insensitively unless the schema defining the attribute marks it `caseExact`, as
+    /// it does `id`, and a comparison on a multi-valued attribute holds if it holds for any of its values. A
+    /// resource that cannot be represented as JSON matches no filter.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::filter::FilterExpr;
+    /// use scim_v2::models::group::Group;
+    /// use scim_v2::models::user::User;
+    ///
+    /// let user: User = serde_json::from_value(serde_json::json!({
+    ///     "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+    ///     "userName": "bjensen",
+    ///     "emails": [{"value": "bjensen@example.com", "type": "work"}]
+    /// }))
+    /// .unwrap();
+    /// let filter = FilterExpr::parse(r#"userName eq "BJensen" and emails[type eq "work"]"#).unwrap();
+    /// assert!(filter.matches(&user));
+    ///
+    /// let group: Group = serde_json::from_value(serde_json::json!({
+    ///     "schemas": ["urn:ietf:params:scim:schemas:core:2.0:Group"],
+    ///     "displayName": "Tour Guides"
+    /// }))
+    /// .unwrap();
+    /// assert!(FilterExpr::parse(r#"displayName sw "tour""#).unwrap().matches(&group));
+    /// ```
+    pub fn matches<R: ScimResource>(&self, resource: &R) -> bool {
+        resource
+            .to_value()
+            .is_ok_and(|resource| self.evaluate(&resource, Some(R::SCHEMA_URN)))
+    }
+
+    /// Returns `true` if the JSON representation of a resource matches the filter, as
+    /// [`FilterExpr::matches`] does. The attributes are looked up in the schemas listed in the resource's
+    /// `schemas`.
+    pub fn matches_value(&self, resource: &Value) -> bool {
+        self.evaluate(resource, None)
+    }
+
+    // Returns `true` if a value of the multi-valued attribute `parent` of `resource` matches the filter, as
+    // the values of `parent[filter]` do.
+    pub(crate) fn matches_entry(&self, resource: &Value, parent: &AttrPath, entry: &Value) -> bool {
+        let registry = read_global();
+        let evaluation = Evaluation {
+            resource,
+            core: None,
+            parent: Some(parent),
+            registry: &registry,
+        };
+        evaluation.evaluate(self, entry)
+    }
+
+    fn evaluate(&self, resource: &Value, core: Option<&str>) -> bool {
+        let registry = read_global();
+        let evaluation = Evaluation {
+            resource,
+            core,
+            parent: None,
+            registry: &registry,
+        };
+        evaluation.evaluate(self, resource)
+    }
+
+    // Writes the expression, parenthesizing an `or` where it is an operand of `and`.
+    fn write(&self, f: &mut fmt::Formatter<'_>, in_and: bool) -> fmt::Result {
+        match self {
+            FilterExpr::Present(path) => write!(f, "{} pr", path),
+            FilterExpr::Compare(path, op, value) => write!(f, "{} {} {}", path, op, value),
+            FilterExpr::ValuePath(path, filter) => write!(f, "{}[{}]", path, filter),
+            FilterExpr::And(left, right) => {
+                left.write(f, true)?;
+                f.write_str(" and ")?;
+                right.write(f, true)
+            }
+            FilterExpr::Or(left, right) if in_and => write!(f, "({} or {})", left, right),
+            FilterExpr::Or(left, right) => write!(f, "{} or {}", left, right),
+            FilterExpr::Not(filter) => write!(f, "not ({})", filter),
+        }
+    }
+}
+
+impl fmt::Display for FilterExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, false)
+    }
+}
+
+impl FromStr for FilterExpr {
+    type Err = SCIMError;
+
+    fn from_str(filter: &str) -> Result<Self, Self::Err> {
+        FilterExpr::parse(filter)
+    }
+}
+
+// The attributes common to all resources whose values compare case-sensitively (RFC 7643, section 3.1).
+const CASE_EXACT_COMMON: [&str; 5] = [
+    "id",
+    "externalId",
+    "meta.resourceType",
+    "meta.location",
+    "meta.version",
+];
+
+// The evaluation of a filter against one resource.
+struct Evaluation<'a> {
+    resource: &'a Value,
+    // The URI of the resource's core schema, if the caller knows it.
+    core: Option<&'a str>,
+    // The multi-valued attribute whose entries a value path filter is being evaluated against.
+    parent: Option<&'a AttrPath>,
+    registry: &'a SchemaRegistry,
+}
+
+impl<'a> Evaluation<'a> {
+    fn evaluate(&self, expression: &'a FilterExpr, value: &Value) -> bool {
+        match expression {
+            FilterExpr::Present(path) => path_values(value, path).iter().any(|v| is_present(v)),
+            FilterExpr::Compare(path, op, expected) => {
+                let case_exact = expected.is_string() && self.is_case_exact(path);
+                let values = path_values(value, path);
+                match op {
+                    CompareOp::Ne => !values
+                        .iter()
+                        .any(|v| compare(v, CompareOp::Eq, expected, case_exact)),
+                    op => values.iter().any(|v| compare(v, *op, expected, case_exact)),
+                }
+            }
+            FilterExpr::ValuePath(path, filter) => {
+                let entries = Evaluation {
+                    parent: Some(path),
+                    ..*self
+                };
+                path_entries(value, path)
+                    .into_iter()
+                    .any(|entry| entries.evaluate(filter, entry))
+            }
+            FilterExpr::And(left, right) => {
+                self.evaluate(left, value) && self.evaluate(right, value)
+            }
+            FilterExpr::Or(left, right) => {
+                self.evaluate(left, value) || self.evaluate(right, value)
+            }
+            FilterExpr::Not(filter) => !self.evaluate(filter, value),
+        }
+    }
+
+    // Returns `true` if the attribute at `path` is `caseExact`, according to the first of the resource's
+    // schemas to define it. Attributes no schema defines compare case-insensitively.
+    fn is_case_exact(&self, path: &AttrPath) -> bool {
+        let (uri, name, sub_attribute) = match self.parent {
+            Some(parent) => (
+                parent.uri.as_deref(),
+                &parent.name,
+                Some(path.name.as_str()),
+            ),
+            None => (
+                path.uri.as_deref(),
+                &path.name,
+                path.sub_attribute.as_deref(),
+            ),
+        };
+        let common = match sub_attribute {
+            Some(sub_attribute) => format!("{}.{}", name, sub_attribute),
+            None => name.clone(),
+        };
+        if CASE_EXACT_COMMON
+            .iter()
+            .any(|attribute| attribute.eq_ignore_ascii_case(&common))
+        {
+            return true;
+        }
+        let listed = self.resource["schemas"].as_array().into_iter().flatten();
+        let schemas: Vec<&str> = match uri {
+            Some(uri) => vec![uri],
+            None => self
+                .core
+                .into_iter()
+                .chain(listed.filter_map(Value::as_str))
+                .collect(),
+        };
+        let Some(attribute) = self.registry.attribute(schemas, name) else {
+            return false;
+        };
+        let case_exact = match sub_attribute {
+            Some(sub_attribute) => attribute
+                .sub_attributes
+                .iter()
+                .flatten()
+                .find(|s| s.name.eq_ignore_ascii_case(sub_attribute))
+                .and_then(|s| s.case_exact),
+            None => attribute.case_exact,
+        };
+        case_exact == Some(true)
+    }
+}
+
+// Returns the values at a path, as `values_below` does. A path qualified with the URI of the resource's core
+// schema, rather than of an extension it carries, names an attribute at the top of the resource.
+pub(crate) fn path_values<'a>(resource: &'a Value, path: &AttrPath) -> Vec<&'a Value> {
+    let Some(base) = path_base(resource, path) else {
+        return Vec::new();
+    };
+    let segments = [Some(path.name.as_str()), path.sub_attribute.as_deref()];
+    values_below(base, segments.into_iter().flatten())
+}
+
+// The entries of the multi-valued attribute at `path`, or its value if it is singular.
+fn path_entries<'a>(resource: &'a Value, path: &AttrPath) -> Vec<&'a Value> {
+    match path_base(resource, path).and_then(|base| get_ignore_case(base, &path.name)) {
+        Some(Value::Array(entries)) => entries.iter().collect(),
+        Some(value) => vec![value],
+        None => Vec::new(),
+    }
+}
+
+fn path_base<'a>(resource: &'a Value, path: &AttrPath) -> Option<&'a Value> {
+    let Some(uri) = &path.uri else {
+        return Some(resource);
+    };
+    if let Some(extension) = get_ignore_case(resource, uri) {
+        return Some(extension);
+    }
+    let core = resource["schemas"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .any(|schema| schema.eq_ignore_ascii_case(uri));
+    core.then_some(resource)
+}
+
+// Returns the values at `segments` below `base`, flattening multi-valued attributes. A multi-valued complex
+// attribute without a sub-attribute stands for the `value` of its entries, with the primary entry first.
+pub(crate) fn values_below<'a, 's>(
+    base: &'a Value,
+    segments: impl Iterator<Item = &'s str>,
+) -> Vec<&'a Value> {
+    let mut current = vec![base];
+    for segment in segments {
+        current = current
+            .into_iter()
+            .flat_map(|value| match value {
+                Value::Array(entries) => entries.iter().collect(),
+                value => vec![value],
+            })
+            .filter_map(|value| get_ignore_case(value, segment))
+            .collect();
+    }
+    let mut values = Vec::new();
+    for value in current {
+        match value {
+            Value::Array(entries) => {
+                let mut entries: Vec<&Value> = entries.iter().collect();
+                entries.sort_by_key(|e| e.get("primary") != Some(&Value::Bool(true)));
+                values.extend(entries.into_iter().map(|e| match e {
+                    Value::Object(_) => get_ignore_case(e, "value").unwrap_or(&Value::Null),
+                    e => e,
+                }));
+            }
+            Value::Object(_) => values.push(get_ignore_case(value, "value").unwrap_or(value)),
+            value => values.push(value),
+        }
+    }
+    values
+}
+
+fn get_ignore_case<'a>(value: &'a Value, name: &str) -> Option<&'a Value> {
+    value
+        .as_object()?
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value)
+}
+
+pub(crate) fn is_present(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::String(s) => !s.is_empty(),
+        Value::Array(entries) => !entries.is_empty(),
+        Value::Object(members) => !members.is_empty(),
+        _ => true,
+    }
+}
+
+fn compare(actual: &Value, operator: CompareOp, expected: &Value, case_exact: bool) -> bool {
+    match (actual, expected) {
+        (Value::String(actual), Value::String(expected)) if case_exact => {
+            compare_strings(actual, operator, expected)
+        }
+        (Value::String(actual), Value::String(expected)) => {
+            compare_strings(&actual.to_lowercase(), operator, &expected.to_lowercase())
+        }
+        (Value::Number(actual), Value::Number(expected)) => {
+            match (actual.as_f64(), expected.as_f64()) {
+                (Some(actual), Some(expected)) => actual
+                    .partial_cmp(&expected)
+                    .is_some_and(|ordering| ordered(ordering, operator)),
+                _ => false,
+            }
+        }
+        (Value::Bool(actual), Value::Bool(expected)) => {
+            operator == CompareOp::Eq && actual == expected
+        }
+        (actual, Value::Null) => operator == CompareOp::Eq && !is_present(actual),
+        _ => false,
+    }
+}
+
+fn compare_strings(actual: &str, operator: CompareOp, expected: &str) -> bool {
+    match operator {
+        CompareOp::Eq => actual == expected,
+        CompareOp::Co => actual.contains(expected),
+        CompareOp::Sw => actual.starts_with(expected),
+        CompareOp::Ew => actual.ends_with(expected),
+        operator => ordered(actual.cmp(expected), operator),
+    }
+}
+
+fn ordered(ordering: Ordering, operator: CompareOp) -> bool {
+    match operator {
+        CompareOp::Eq => ordering == Ordering::Equal,
+        CompareOp::Gt => ordering == Ordering::Greater,
+        CompareOp::Ge => ordering != Ordering::Less,
+        CompareOp::Lt => ordering == Ordering::Less,
+        CompareOp::Le => ordering != Ordering::Greater,
+        _ => false,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    OpenBracket,
+    CloseBracket,
+    // An attribute path, operator, keyword, number or literal name.
+    Word(String),
+    // A string literal, unescaped.
+    Str(String),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Open => f.write_str("("),
+            Token::Close => f.write_str(")"),
+            Token::OpenBracket => f.write_str("["),
+            Token::CloseBracket => f.write_str("]"),
+            Token::Word(word) => f.write_str(word),
+            Token::Str(string) => write!(f, "{}", Value::from(string.as_str())),
+        }
+    }
+}
+
+fn tokenize(filter: &str) -> Result<Vec<Token>, SCIMError> {
+    let mut tokens = Vec::new();
+    let mut chars = filter.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            '[' => tokens.push(Token::OpenBracket),
+            ']' => tokens.push(Token::CloseBracket),
+            '"' => {
+                let mut end = None;
+                while let Some((index, c)) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => {
+                            end = Some(index + 1);
+                            break;
+                        }
+                        _ => {}
+                    }
+                }
+                let end = end.ok_or_else(|| {
+                    SCIMError::InvalidFilter(format!("unterminated string in '{}'", filter).into())
+                })?;
+                let string = serde_json::from_str(&filter[start..end]).map_err(|_| {
+                    SCIMError::InvalidFilter(
+                        format!("invalid string {} in '{}'", &filter[start..end], filter).into(),
+                    )
+                })?;
+                tokens.push(Token::Str(string));
+            }
+            _ => {
+                let mut end = start + c.len_utf8();
+                while let Some(&(index, c)) = chars.peek() {
+                    if c.is_whitespace() || "()[]\"".contains(c) {
+                        break;
+                    }
+                    end = index + c.len_utf8();
+                    chars.next();
+                }
+                tokens.push(Token::Word(filter[start..end].to_string()));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+// How deeply groupings, `not` and value paths may nest. The parser recurses once per level, so a client
+// could otherwise overflow the stack with a few kilobytes of parentheses.
+const MAX_DEPTH: usize = 64;
+
+struct Parser<'a> {
+    filter: &'a str,
+    tokens: Vec<Token>,
+    next: usize,
+    // The number of expressions being parsed, one inside the other.
+    depth: usize,
+}
+
+impl Parser<'_> {
+    // FILTER = and-expression *("or" and-expression)
+    fn or(&mut self, in_brackets: bool) -> Result<FilterExpr, SCIMError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(SCIMError::InvalidFilter(
+                format!(
+                    "'{}' nests more than {} levels deep",
+                    self.filter, MAX_DEPTH
+                )
+                .into(),
+            ));
+        }
+        let mut expression = self.and(in_brackets)?;
+        while self.keyword("or") {
+            let right = self.and(in_brackets)?;
+            expression = FilterExpr::Or(Box::new(expression), Box::new(right));
+        }
+        self.depth -= 1;
+        Ok(expression)
+    }
+
+    fn and(&mut self, in_brackets: bool) -> Result<FilterExpr, SCIMError> {
+        let mut expression = self.unary(in_brackets)?;
+        while self.keyword("and") {
+            let right = self.unary(in_brackets)?;
+            expression = FilterExpr::And(Box::new(expression), Box::new(right));
+        }
+        Ok(expression)
+    }
+
+    fn unary(&mut self, in_brackets: bool) -> Result<FilterExpr, SCIMError> {
+        if self.keyword("not") {
+            self.expect(Token::Open)?;
+            let expression = self.or(in_brackets)?;
+            self.expect(Token::Close)?;
+            return Ok(FilterExpr::Not(Box::new(expression)));
+        }
+        match self.advance()? {
+            Token::Open => {
+                let expression = self.or(in_brackets)?;
+                self.expect(Token::Close)?;
+                Ok(expression)
+            }
+            Token::Word(word) => {
+                let path = AttrPath::parse(&word)?;
+                self.attribute_expression(path, in_brackets)
+            }
+            token => Err(self.unexpected(&token)),
+        }
+    }
+
+    // attrExp = (attrPath "pr") / (attrPath compareOp compValue), or a valuePath outside of brackets.
+    fn attribute_expression(
+        &mut self,
+        path: AttrPath,
+        in_brackets: bool,
+    ) -> Result<FilterExpr, SCIMError> {
+        match self.advance()? {
+            Token::OpenBracket if !in_brackets && path.sub_attribute.is_none() => {
+                let filter = self.or(true)?;
+                self.expect(Token::CloseBracket)?;
+                Ok(FilterExpr::ValuePath(path, Box::new(filter)))
+            }
+            Token::Word(word) if word.eq_ignore_ascii_case("pr") => Ok(FilterExpr::Present(path)),
+            Token::Word(word) => {
+                let op =
+                    CompareOp::parse(&word).ok_or_else(|| self.unexpected(&Token::Word(word)))?;
+                let value = self.value()?;
+                Ok(FilterExpr::Compare(path, op, value))
+            }
+            token => Err(self.unexpected(&token)),
+        }
+    }
+
+    // compValue = false / null / true / number / string
+    fn value(&mut self) -> Result<Value, SCIMError> {
+        match self.advance()? {
+            Token::Str(string) => Ok(Value::String(string)),
+            Token::Word(word) => match word.to_ascii_lowercase().as_str() {
+                "true" => Ok(Value::Bool(true)),
+                "false" => Ok(Value::Bool(false)),
+                "null" => Ok(Value::Null),
+                _ => match serde_json::from_str::<Value>(&word) {
+                    Ok(number @ Value::Number(_)) => Ok(number),
+                    _ => Err(self.unexpected(&Token::Word(word))),
+                },
+            },
+            token => Err(self.unexpected(&token)),
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        match self.tokens.get(self.next) {
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword) => {
+                self.next += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), SCIMError> {
+        match self.tokens.get(self.next) {
+            Some(token) if *token == expected => {
+                self.next += 1;
+                Ok(())
+            }
+            Some(token) => Err(self.unexpected(token)),
+            None => Err(SCIMError::InvalidFilter(
+                format!("missing '{}' in '{}'", expected, self.filter).into(),
+            )),
+        }
+    }
+
+    fn advance(&mut self) -> Result<Token, SCIMError> {
+        let token = self.tokens.get(self.next).cloned().ok_or_else(|| {
+            SCIMError::InvalidFilter(format!("unexpected end of '{}'", self.filter).into())
+        })?;
+        self.next += 1;
+        Ok(token)
+    }
+
+    fn unexpected(&self, token: &Token) -> SCIMError {
+        SCIMError::InvalidFilter(format!("unexpected '{}' in '{}'", token, self.filter).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn compare(path: &str, op: CompareOp, value: Value) -> Box<FilterExpr> {
+        Box::new(FilterExpr::Compare(
+            AttrPath::parse(path).unwrap(),
+            op,
+            value,
+        ))
+    }
+
+    #[test]
+    fn parses_precedence_groups_and_value_paths() {
+        let filter = FilterExpr::parse(
+            r#"a eq 1 OR b gt 2.5 and NOT (c ew "x\"y") and emails[type eq "work" and primary eq TRUE]"#,
+        )
+        .unwrap();
+        let expected = FilterExpr::Or(
+            compare("a", CompareOp::Eq, json!(1)),
+            Box::new(FilterExpr::And(
+                Box::new(FilterExpr::And(
+                    compare("b", CompareOp::Gt, json!(2.5)),
+                    Box::new(FilterExpr::Not(compare("c", CompareOp::Ew, json!("x\"y")))),
+                )),
+                Box::new(FilterExpr::ValuePath(
+                    AttrPath::new("emails"),
+                    Box::new(FilterExpr::And(
+                        compare("type", CompareOp::Eq, json!("work")),
+                        compare("primary", CompareOp::Eq, json!(true)),
+                    )),
+                )),
+            )),
+        );
+        assert_eq!(filter, expected);
+
+        let grouped = FilterExpr::parse(r#"(a eq null or b pr) and c sw "x""#).unwrap();
+        assert!(
+            matches!(&grouped, FilterExpr::And(left, _) if matches!(**left, FilterExpr::Or(..)))
+        );
+        assert_eq!(grouped.to_string(), r#"(a eq null or b pr) and c sw "x""#);
+        assert_eq!(
+            FilterExpr::parse(&filter.to_string()).unwrap(),
+            filter,
+            "{}",
+            filter
+        );
+    }
+
+    #[test]
+    fn matches_users_and_groups() {
+        let user: crate::models::user::User = serde_json::from_value(json!({
+            "schemas": [
+                "urn:ietf:params:scim:schemas:core:2.0:User",
+                "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User"
+            ],
+            "id": "2819c223",
+            "externalId": "bjensen",
+            "userName": "bjensen",
+            "name": {"familyName": "Jensen", "givenName": "Barbara"},
+            "active": true,
+            "emails": [{"value": "babs@jensen.org", "type": "home"}],
+            "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User": {"employeeNumber": "701984"}
+        }))
+        .unwrap();
+        let matches = |filter: &str| FilterExpr::parse(filter).unwrap().matches(&user);
+        assert!(matches(r#"name.familyName eq "jensen" and active eq true"#));
+        assert!(matches(
+            r#"emails[type eq "home" and value ew "jensen.org"]"#
+        ));
+        assert!(matches(
+            r#"urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:employeeNumber sw "70""#
+        ));
+        assert!(matches(r#"not (title pr) or userName eq "x""#));
+        assert!(matches(r#"userName eq "BJENSEN" and id eq "2819c223""#));
+        assert!(!matches(r#"id eq "2819C223""#));
+        assert!(matches(r#"id ne "2819C223" and externalId sw "bj""#));
+        assert!(!matches(r#"externalId sw "BJ""#));
+        assert!(!matches(r#"emails[type eq "work"]"#));
+
+        let group: crate::models::group::Group = serde_json::from_value(json!({
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:Group"],
+            "displayName": "Tour Guides",
+            "members": [{"value": "2819c223", "display": "Babs Jensen"}]
+        }))
+        .unwrap();
+        let filter =
+            FilterExpr::parse(r#"members[value eq "2819c223"] and displayName co "guide""#);
+        assert!(filter.unwrap().matches(&group));
+        assert!(
+            !FilterExpr::parse("members.display ne \"Babs Jensen\"")
+                .unwrap()
+                .matches(&group)
+        );
+    }
+
+    #[test]
+    fn reports_malformed_filters() {
+        for (filter, message) in [
+            ("", "empty filter"),
+            (r#"userName eq "bjensen"#, "unterminated string"),
+            ("userName eq", "unexpected end"),
+            ("userName is 1", "unexpected 'is'"),
+            ("(title pr", "missing ')'"),
+            ("title pr)", "unexpected ')'"),
+            ("not title pr", "unexpected 'title'"),
+            (r#"emails[type eq "work"[value pr]]"#, "unexpected '['"),
+            ("userName eq bjensen", "unexpected 'bjensen'"),
+            ("name.given.family pr", "invalid attribute path"),
+        ] {
+            let Err(SCIMError::InvalidFilter(detail)) = FilterExpr::parse(filter) else {
+                panic!("{} parsed", filter);
+            };
+            assert!(
+                detail.to_string().contains(message),
+                "{}: {}",
+                filter,
+                detail
+            );
+        }
+
+        let nested = |depth: usize, open: &str| {
+            format!("{}title pr{}", open.repeat(depth), ")".repeat(depth))
+        };
+        assert!(FilterExpr::parse(&nested(63, "(")).is_ok());
+        for filter in [nested(64, "("), nested(100_000, "not (")] {
+            let Err(SCIMError::InvalidFilter(detail)) = FilterExpr::parse(&filter) else {
+                panic!("a filter nested too deeply parsed");
+            };
+            assert!(
+                detail
+                    .to_string()
+                    .contains("nests more than 64 levels deep")
+            );
+        }
+    }
+}
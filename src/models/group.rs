@@ -1,17 +1,41 @@
 //Schema for group
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io::Write;
+
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
+use crate::models::others::PatchOp;
+use crate::models::patch;
+use crate::models::resource_id::ResourceId;
+use crate::models::schema_registry::read_global;
+use crate::models::schema_uri::GROUP_URN;
 use crate::models::scim_schema::Meta;
+use crate::models::values::Values;
 use crate::utils::error::SCIMError;
+use crate::utils::json;
+use crate::values;
 
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct Group {
-    pub schemas: Vec<String>,
+    #[cfg_attr(feature = "lenient", serde(default = "default_schemas"))]
+    pub schemas: Values<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub id: Option<String>,
+    pub id: Option<ResourceId>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "lenient", serde(alias = "externalID", alias = "externalid"))]
     pub external_id: Option<String>,
+    #[cfg_attr(
+        feature = "lenient",
+        serde(alias = "displayname", alias = "DisplayName")
+    )]
     pub display_name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub members: Option<Vec<Member>>,
@@ -19,10 +43,14 @@ pub struct Group {
     pub meta: Option<Meta>,
 }
 
+fn default_schemas() -> Values<String> {
+    values![GROUP_URN.to_string()]
+}
+
 impl Default for Group {
     fn default() -> Self {
         Group {
-            schemas: vec!["urn:ietf:params:scim:schemas:core:2.0:Group".to_string()],
+            schemas: default_schemas(),
             id: None,
             external_id: None,
             display_name: "default_display_name".to_string(),
@@ -32,10 +60,11 @@ impl Default for Group {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Default)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Member {
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub value: Option<String>,
+    pub value: Option<ResourceId>,
     #[serde(rename = "$ref", skip_serializing_if = "Option::is_none")]
     pub r#ref: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -44,6 +73,58 @@ pub struct Member {
     pub display: Option<String>,
 }
 
+/// Represents one page of a group's members.
+///
+/// Groups with very large memberships are impractical to transfer in a single representation, so servers
+/// commonly return members in pages. `MemberPage` carries the members of one page together with the same
+/// counters a `ListResponse` uses, which lets clients tell whether more members remain.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct MemberPage {
+    pub total_results: i64,
+    pub start_index: i64,
+    pub items_per_page: i64,
+    pub members: Vec<Member>,
+}
+
+impl Default for MemberPage {
+    fn default() -> Self {
+        MemberPage {
+            total_results: 0,
+            start_index: 1,
+            items_per_page: 0,
+            members: vec![],
+        }
+    }
+}
+
+impl MemberPage {
+    /// Returns the `startIndex` of the following page, or `None` if this is the last page.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::group::{Member, MemberPage};
+    ///
+    /// let page = MemberPage {
+    ///     total_results: 3,
+    ///     start_index: 1,
+    ///     items_per_page: 2,
+    ///     members: vec![Member::default(), Member::default()],
+    /// };
+    /// assert_eq!(page.next_start_index(), Some(3));
+    /// ```
+    pub fn next_start_index(&self) -> Option<i64> {
+        let next = self.start_index + self.members.len() as i64;
+        if self.members.is_empty() || next > self.total_results {
+            None
+        } else {
+            Some(next)
+        }
+    }
+}
+
 /// Converts a JSON string into a `Group` struct.
 ///
 /// This method attempts to parse a JSON string to construct a `Group` object. It's useful for scenarios where
@@ -69,7 +150,49 @@ impl TryFrom<&str> for Group {
     type Error = SCIMError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        serde_json::from_str(value).map_err(SCIMError::DeserializationError)
+        json::from_str(value)
+    }
+}
+
+/// Converts an already-parsed JSON value into a `Group` struct.
+///
+/// Web frameworks usually hand over request bodies as a `serde_json::Value`; this conversion avoids
+/// round-tripping that value through a string.
+///
+/// # Errors
+///
+/// Returns `SCIMError::DeserializationError` if the value does not describe a `Group` object.
+///
+/// # Examples
+///
+/// ```rust
+/// use scim_v2::models::group::Group;
+/// use serde_json::json;
+///
+/// let value = json!({"schemas": ["urn:ietf:params:scim:schemas:core:2.0:Group"], "displayName": "Tour Guides"});
+/// match Group::try_from(value) {
+///     Ok(resource) => println!("Successfully converted JSON to Group: {:?}", resource),
+///     Err(e) => println!("Error converting from JSON to Group: {}", e),
+/// }
+/// ```
+impl TryFrom<Value> for Group {
+    type Error = SCIMError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        serde_json::from_value(value).map_err(SCIMError::DeserializationError)
+    }
+}
+
+/// Converts a `Group` struct into a JSON value.
+///
+/// # Errors
+///
+/// Returns `SCIMError::SerializationError` if the `Group` cannot be represented as JSON.
+impl TryFrom<Group> for Value {
+    type Error = SCIMError;
+
+    fn try_from(value: Group) -> Result<Self, Self::Error> {
+        serde_json::to_value(value).map_err(SCIMError::SerializationError)
     }
 }
 
@@ -86,15 +209,18 @@ impl Group {
     ///
     /// * `Ok(())` - If the group is valid.
     /// * `Err(SCIMError::MissingRequiredField)` - If a required field is missing.
+    /// * `Err(SCIMError::InvalidFieldValue)` - If `schemas` does not match the `Group` resource type of the
+    ///   process-wide [`SchemaRegistry`](crate::models::schema_registry::SchemaRegistry).
     ///
     /// # Example
     ///
     /// ```
     /// use scim_v2::models::group::Group;
+    /// use scim_v2::values;
     ///
     /// let group = Group {
-    ///     schemas: vec!["urn:ietf:params:scim:schemas:core:2.0:Group".to_string()],
-    ///     id: Some("e9e30dba-f08f-4109-8486-d5c6a331660a".to_string()),
+    ///     schemas: values!["urn:ietf:params:scim:schemas:core:2.0:Group".to_string()],
+    ///     id: Some("e9e30dba-f08f-4109-8486-d5c6a331660a".into()),
     ///     display_name: "Tour Guides".to_string(),
     ///     // other fields...
     ///     ..Default::default()
@@ -107,11 +233,69 @@ impl Group {
     /// ```
     pub fn validate(&self) -> Result<(), SCIMError> {
         if self.schemas.is_empty() {
-            return Err(SCIMError::MissingRequiredField("schemas".to_string()));
+            return Err(SCIMError::MissingRequiredField("schemas".into()));
         }
         if self.display_name.is_empty() {
-            return Err(SCIMError::MissingRequiredField("display_name".to_string()));
+            return Err(SCIMError::MissingRequiredField("display_name".into()));
+        }
+        let value = serde_json::to_value(self).map_err(SCIMError::SerializationError)?;
+        read_global().validate_resource("Group", &value)
+    }
+
+    /// Applies the operations of a PATCH request to the group, in order (RFC 7644 §3.5.2).
+    ///
+    /// Identity providers manage memberships with PATCH, and this follows what they rely on: members are
+    /// identified by `value`, so adding a member the group already has, with or without a `display`, leaves
+    /// a single entry for it, and `remove` with a path such as `members[value eq "2819c223"]` removes only
+    /// that member. A `replace` without a path replaces the attributes it names, `members` included. The
+    /// patch is applied as a whole: if an operation fails, the group is left unchanged.
+    ///
+    /// # Errors
+    ///
+    /// The errors of [`User::apply_patch`](crate::models::user::User::apply_patch).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::group::Group;
+    /// use scim_v2::models::others::PatchOp;
+    /// use serde_json::json;
+    ///
+    /// let mut group = Group::try_from(json!({
+    ///     "schemas": ["urn:ietf:params:scim:schemas:core:2.0:Group"],
+    ///     "displayName": "Tour Guides",
+    ///     "members": [{"value": "2819c223", "display": "Babs Jensen"}]
+    /// }))
+    /// .unwrap();
+    /// let patch = PatchOp::try_from(json!({
+    ///     "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+    ///     "Operations": [
+    ///         {"op": "add", "path": "members", "value": [{"value": "2819C223"}, {"value": "902c246b"}]},
+    ///         {"op": "remove", "path": "members[value eq \"2819c223\"]"}
+    ///     ]
+    /// }))
+    /// .unwrap();
+    ///
+    /// group.apply_patch(&patch).unwrap();
+    /// let members = group.members.unwrap();
+    /// assert_eq!(members.len(), 1);
+    /// assert_eq!(members[0].value.as_deref(), Some("902c246b"));
+    /// ```
+    pub fn apply_patch(&mut self, patch: &PatchOp) -> Result<(), SCIMError> {
+        let mut value = serde_json::to_value(&*self).map_err(SCIMError::SerializationError)?;
+        patch::apply(&mut value, patch)?;
+        let mut group: Group = serde_json::from_value(value).map_err(|e| {
+            SCIMError::InvalidFieldValue(format!("the patched group is invalid: {}", e).into())
+        })?;
+        if let Some(members) = &mut group.members {
+            // `ResourceId`s compare case-insensitively, so they are keyed by their lowercase form.
+            let mut known = BTreeSet::new();
+            members.retain(|member| match &member.value {
+                Some(value) => known.insert(value.to_ascii_lowercase()),
+                None => true,
+            });
         }
+        *self = group;
         Ok(())
     }
 
@@ -127,10 +311,11 @@ impl Group {
     ///
     /// ```
     /// use scim_v2::models::group::Group;
+    /// use scim_v2::values;
     ///
     /// let group = Group {
-    ///     schemas: vec!["urn:ietf:params:scim:schemas:core:2.0:Group".to_string()],
-    ///     id: Some("e9e30dba-f08f-4109-8486-d5c6a331660a".to_string()),
+    ///     schemas: values!["urn:ietf:params:scim:schemas:core:2.0:Group".to_string()],
+    ///     id: Some("e9e30dba-f08f-4109-8486-d5c6a331660a".into()),
     ///     display_name: "Tour Guides".to_string(),
     ///     // other fields...
     ///     ..Default::default()
@@ -145,6 +330,25 @@ impl Group {
         serde_json::to_string(&self).map_err(SCIMError::SerializationError)
     }
 
+    /// Serializes the `Group` instance as JSON into `buffer`, appending to its current content.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if the `Group` cannot be serialized.
+    pub fn serialize_into(&self, buffer: &mut Vec<u8>) -> Result<(), SCIMError> {
+        json::append_to(buffer, self)
+    }
+
+    /// Serializes the `Group` instance as JSON to `writer`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if the `Group` cannot be serialized or writing to `writer` fails.
+    #[cfg(feature = "std")]
+    pub fn to_writer(&self, writer: impl Write) -> Result<(), SCIMError> {
+        serde_json::to_writer(writer, self).map_err(SCIMError::SerializationError)
+    }
+
     /// Deserializes a JSON string into a `Group` instance, using the custom SCIMError for error handling.
     ///
     /// # Parameters
@@ -170,6 +374,120 @@ impl Group {
     pub fn deserialize(json: &str) -> Result<Self, SCIMError> {
         serde_json::from_str(json).map_err(SCIMError::DeserializationError)
     }
+
+    /// Returns one page of the group's members.
+    ///
+    /// This is the server side of member pagination: it slices `members` starting at the 1-based
+    /// `start_index` and returns at most `count` members along with the paging counters.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(MemberPage)` - The requested page, which is empty if `start_index` is past the last member.
+    /// * `Err(SCIMError::InvalidFieldValue)` - If `start_index` is less than 1 or `count` is negative.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::group::{Group, Member};
+    ///
+    /// let group = Group {
+    ///     members: Some((0..5).map(|i| Member { value: Some(i.to_string().into()), ..Default::default() }).collect()),
+    ///     ..Default::default()
+    /// };
+    /// let page = group.member_page(3, 2).unwrap();
+    /// assert_eq!(page.members.len(), 2);
+    /// assert_eq!(page.next_start_index(), Some(5));
+    /// ```
+    pub fn member_page(&self, start_index: i64, count: i64) -> Result<MemberPage, SCIMError> {
+        if start_index < 1 {
+            return Err(SCIMError::InvalidFieldValue(
+                format!("startIndex must be at least 1, got {}", start_index).into(),
+            ));
+        }
+        if count < 0 {
+            return Err(SCIMError::InvalidFieldValue(
+                format!("count must not be negative, got {}", count).into(),
+            ));
+        }
+        let members = self.members.as_deref().unwrap_or_default();
+        let page: Vec<Member> = members
+            .iter()
+            .skip((start_index - 1) as usize)
+            .take(count as usize)
+            .cloned()
+            .collect();
+        Ok(MemberPage {
+            total_results: members.len() as i64,
+            start_index,
+            items_per_page: page.len() as i64,
+            members: page,
+        })
+    }
+
+    /// Merges a page of members into the group, skipping members that are already present.
+    ///
+    /// Members are matched by `value`; members without a `value` are always appended.
+    /// Returns the number of members that were added.
+    pub fn merge_member_page(&mut self, page: MemberPage) -> usize {
+        let members = self.members.get_or_insert_with(Vec::new);
+        // `ResourceId`s compare case-insensitively, so they are keyed by their lowercase form.
+        let mut known: BTreeSet<String> = members
+            .iter()
+            .filter_map(|m| m.value.as_deref().map(str::to_ascii_lowercase))
+            .collect();
+        let before = members.len();
+        for member in page.members {
+            match &member.value {
+                Some(value) if !known.insert(value.to_ascii_lowercase()) => {}
+                _ => members.push(member),
+            }
+        }
+        members.len() - before
+    }
+
+    /// Fetches every page of members and merges them into the group.
+    ///
+    /// This is the client side of member pagination. `fetch` is called with a `startIndex` and `count` and
+    /// should return the corresponding page, e.g. by issuing an HTTP request. Fetching stops once a page
+    /// reports that no members remain or comes back empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error produced by `fetch`, or `SCIMError::InvalidFieldValue` if `page_size` is not positive.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::group::{Group, Member};
+    ///
+    /// let server = Group {
+    ///     members: Some((0..5).map(|i| Member { value: Some(i.to_string().into()), ..Default::default() }).collect()),
+    ///     ..Default::default()
+    /// };
+    /// let mut client = Group::default();
+    /// client.fetch_all_members(2, |start, count| server.member_page(start, count)).unwrap();
+    /// assert_eq!(client.members.unwrap().len(), 5);
+    /// ```
+    pub fn fetch_all_members<F>(&mut self, page_size: i64, mut fetch: F) -> Result<(), SCIMError>
+    where
+        F: FnMut(i64, i64) -> Result<MemberPage, SCIMError>,
+    {
+        if page_size < 1 {
+            return Err(SCIMError::InvalidFieldValue(
+                format!("page size must be positive, got {}", page_size).into(),
+            ));
+        }
+        let mut start_index = 1;
+        loop {
+            let page = fetch(start_index, page_size)?;
+            let next = page.next_start_index();
+            self.merge_member_page(page);
+            match next {
+                Some(next) if next > start_index => start_index = next,
+                _ => return Ok(()),
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -216,8 +534,8 @@ mod tests {
         assert!(group.is_ok());
         let group = group.unwrap();
         assert_eq!(
-            group.schemas,
-            vec!["urn:ietf:params:scim:schemas:core:2.0:Group"]
+            group.schemas[..],
+            ["urn:ietf:params:scim:schemas:core:2.0:Group"]
         );
         assert_eq!(
             group.id,
@@ -229,7 +547,7 @@ mod tests {
         assert_eq!(group.members.as_ref().unwrap().len(), 2);
         assert_eq!(
             group.members.as_ref().unwrap()[0].value,
-            Some("2819c223-7f76-453a-919d-413861904646".to_string())
+            Some("2819c223-7f76-453a-919d-413861904646".into())
         );
         assert_eq!(
             group.members.as_ref().unwrap()[0].display,
@@ -237,7 +555,7 @@ mod tests {
         );
         assert_eq!(
             group.members.as_ref().unwrap()[1].value,
-            Some("902c246b-6245-4190-8e05-00816be7344a".to_string())
+            Some("902c246b-6245-4190-8e05-00816be7344a".into())
         );
         assert_eq!(
             group.members.as_ref().unwrap()[1].display,
@@ -279,8 +597,8 @@ mod tests {
         assert!(group.is_ok());
         let group = group.unwrap();
         assert_eq!(
-            group.schemas,
-            vec!["urn:ietf:params:scim:schemas:core:2.0:Group"]
+            group.schemas[..],
+            ["urn:ietf:params:scim:schemas:core:2.0:Group"]
         );
         assert_eq!(
             group.id,
@@ -309,6 +627,43 @@ mod tests {
         assert!(group.is_err());
     }
 
+    fn member(value: &str) -> Member {
+        Member {
+            value: Some(value.into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn group_member_page_slices_members() {
+        let group = Group {
+            members: Some(vec![member("a"), member("b"), member("c")]),
+            ..Default::default()
+        };
+        let page = group.member_page(2, 5).unwrap();
+        assert_eq!(page.total_results, 3);
+        assert_eq!(page.items_per_page, 2);
+        assert_eq!(page.next_start_index(), None);
+        assert!(group.member_page(0, 5).is_err());
+        assert!(group.member_page(4, 5).unwrap().members.is_empty());
+    }
+
+    #[test]
+    fn group_merge_member_page_deduplicates_by_value() {
+        let mut group = Group {
+            members: Some(vec![member("a")]),
+            ..Default::default()
+        };
+        let added = group.merge_member_page(MemberPage {
+            total_results: 2,
+            start_index: 1,
+            items_per_page: 2,
+            members: vec![member("A"), member("b")],
+        });
+        assert_eq!(added, 1);
+        assert_eq!(group.members.unwrap().len(), 2);
+    }
+
     #[test]
     fn group_deserialization_handles_missing_optional_fields() {
         let json_data = r#"{
@@ -322,8 +677,8 @@ mod tests {
         assert!(group.is_ok());
         let group = group.unwrap();
         assert_eq!(
-            group.schemas,
-            vec!["urn:ietf:params:scim:schemas:core:2.0:Group"]
+            group.schemas[..],
+            ["urn:ietf:params:scim:schemas:core:2.0:Group"]
         );
         assert_eq!(
             group.id,
@@ -333,4 +688,60 @@ mod tests {
         assert!(group.members.is_none());
         assert!(group.meta.is_none());
     }
+
+    #[test]
+    fn apply_patch_keeps_one_entry_per_member() {
+        let mut group = Group::try_from(serde_json::json!({
+            "schemas": [GROUP_URN],
+            "displayName": "Tour Guides",
+            "members": [
+                {"value": "2819c223", "display": "Babs Jensen"},
+                {"value": "902c246b", "display": "Mandy Pepperidge"}
+            ]
+        }))
+        .unwrap();
+        let patch = |operations: Value| {
+            PatchOp::try_from(serde_json::json!({
+                "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+                "Operations": operations
+            }))
+            .unwrap()
+        };
+        let values = |group: &Group| -> Vec<String> {
+            group
+                .members
+                .iter()
+                .flatten()
+                .filter_map(|m| m.value.as_deref().map(str::to_string))
+                .collect()
+        };
+
+        group
+            .apply_patch(&patch(serde_json::json!([
+                {"op": "add", "path": "members", "value": [{"value": "902C246B"}, {"value": "c3a26dd3"}]},
+                {"op": "Remove", "path": "members[value eq \"2819C223\"]"}
+            ])))
+            .unwrap();
+        assert_eq!(values(&group), ["902c246b", "c3a26dd3"]);
+        assert_eq!(
+            group.members.as_ref().unwrap()[0].display.as_deref(),
+            Some("Mandy Pepperidge")
+        );
+
+        group
+            .apply_patch(&patch(serde_json::json!([{"op": "replace", "value": {
+                "displayName": "Guides",
+                "members": [{"value": "2819c223"}, {"value": "2819C223", "display": "Babs"}]
+            }}])))
+            .unwrap();
+        assert_eq!(group.display_name, "Guides");
+        assert_eq!(values(&group), ["2819c223"]);
+
+        group
+            .apply_patch(&patch(
+                serde_json::json!([{"op": "remove", "path": "members"}]),
+            ))
+            .unwrap();
+        assert!(group.members.is_none());
+    }
 }
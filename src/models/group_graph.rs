@@ -0,0 +1,292 @@
+//! Nested group membership: transitive expansion, effective groups and cycle detection.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::models::group::{Group, Member};
+use crate::models::resource_id::ResourceId;
+
+/// The membership graph of a collection of groups.
+///
+/// SCIM groups can contain other groups, so the users a group grants access to are its direct user members
+/// plus those of every group nested in it. A member is taken to be a group when its `type` is `Group`, or
+/// when it has no `type` and its `value` is the id of a group in the collection; every other member is a
+/// user. Groups without an `id` cannot be referred to and are left out.
+///
+/// Nested memberships may form cycles, which SCIM does not forbid. Expansion visits every group once, so it
+/// terminates on cyclic graphs; [`GroupGraph::cycles`] reports them.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::group::Group;
+/// use scim_v2::models::group_graph::GroupGraph;
+/// use serde_json::json;
+///
+/// let groups: Vec<Group> = serde_json::from_value(json!([
+///     {"schemas": ["urn:ietf:params:scim:schemas:core:2.0:Group"], "id": "staff", "displayName": "Staff",
+///      "members": [{"value": "guides"}, {"value": "alice"}]},
+///     {"schemas": ["urn:ietf:params:scim:schemas:core:2.0:Group"], "id": "guides", "displayName": "Guides",
+///      "members": [{"value": "bjensen", "type": "User"}]}
+/// ]))
+/// .unwrap();
+/// let graph = GroupGraph::new(&groups);
+///
+/// let mut users: Vec<&str> = graph.users_of("staff").iter().map(|id| id.as_str()).collect();
+/// users.sort();
+/// assert_eq!(users, ["alice", "bjensen"]);
+///
+/// let mut groups: Vec<&str> = graph.groups_of("bjensen").iter().map(|id| id.as_str()).collect();
+/// groups.sort();
+/// assert_eq!(groups, ["guides", "staff"]);
+/// assert!(graph.cycles().is_empty());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct GroupGraph {
+    groups: Vec<ResourceId>,
+    index: HashMap<ResourceId, usize>,
+    // For each group, the indexes of the groups it contains and the ids of its user members.
+    nested: Vec<Vec<usize>>,
+    users: Vec<Vec<ResourceId>>,
+    // The indexes of the groups each group and each user is a direct member of.
+    group_parents: Vec<Vec<usize>>,
+    user_parents: HashMap<ResourceId, Vec<usize>>,
+}
+
+impl GroupGraph {
+    /// Builds the graph of `groups`.
+    pub fn new(groups: &[Group]) -> Self {
+        let mut graph = GroupGraph::default();
+        for id in groups.iter().filter_map(|group| group.id.as_ref()) {
+            if !graph.index.contains_key(id) {
+                graph.index.insert(id.clone(), graph.groups.len());
+                graph.groups.push(id.clone());
+            }
+        }
+        graph.nested = vec![Vec::new(); graph.groups.len()];
+        graph.users = vec![Vec::new(); graph.groups.len()];
+        graph.group_parents = vec![Vec::new(); graph.groups.len()];
+        for group in groups {
+            let Some(parent) = group.id.as_ref().map(|id| graph.index[id]) else {
+                continue;
+            };
+            for member in group.members.iter().flatten() {
+                let Some(value) = &member.value else {
+                    continue;
+                };
+                match graph.group_member(member, value) {
+                    Some(child) => {
+                        graph.nested[parent].push(child);
+                        graph.group_parents[child].push(parent);
+                    }
+                    None => {
+                        graph.users[parent].push(value.clone());
+                        graph
+                            .user_parents
+                            .entry(value.clone())
+                            .or_default()
+                            .push(parent);
+                    }
+                }
+            }
+        }
+        graph
+    }
+
+    // The index of the group a member refers to, or `None` if the member is a user.
+    fn group_member(&self, member: &Member, value: &ResourceId) -> Option<usize> {
+        match member.r#type.as_deref() {
+            Some(kind) if !kind.eq_ignore_ascii_case("Group") => None,
+            _ => self.index.get(value).copied(),
+        }
+    }
+
+    /// Returns the users that are members of the group with `group_id`, directly or through nested groups,
+    /// each once. Returns nothing for an unknown group.
+    pub fn users_of(&self, group_id: &str) -> Vec<&ResourceId> {
+        let Some(&start) = self.index.get(&ResourceId::new(group_id)) else {
+            return Vec::new();
+        };
+        let mut seen = HashSet::new();
+        let mut users = Vec::new();
+        for group in self.reachable(start, |group| &self.nested[group]) {
+            for user in &self.users[group] {
+                if seen.insert(user) {
+                    users.push(user);
+                }
+            }
+        }
+        users
+    }
+
+    /// Returns the groups `member_id` (a user or a group) belongs to, directly or through nesting, each
+    /// once. A group that is part of a cycle belongs to itself.
+    pub fn groups_of(&self, member_id: &str) -> Vec<&ResourceId> {
+        let member_id = ResourceId::new(member_id);
+        let as_user = self.user_parents.get(&member_id).into_iter().flatten();
+        let as_group = self
+            .index
+            .get(&member_id)
+            .into_iter()
+            .flat_map(|&group| &self.group_parents[group]);
+        let mut seen = HashSet::new();
+        let mut groups = Vec::new();
+        for &start in as_user.chain(as_group) {
+            for group in self.reachable(start, |group| &self.group_parents[group]) {
+                if seen.insert(group) {
+                    groups.push(&self.groups[group]);
+                }
+            }
+        }
+        groups
+    }
+
+    /// Returns the groups that contain themselves through nesting, one list per cycle. Groups that contain
+    /// each other are reported in the same list, in the order they were given to [`GroupGraph::new`].
+    pub fn cycles(&self) -> Vec<Vec<&ResourceId>> {
+        let mut cycles: Vec<Vec<&ResourceId>> = self
+            .strongly_connected()
+            .into_iter()
+            .filter(|component| {
+                component.len() > 1 || self.nested[component[0]].contains(&component[0])
+            })
+            .map(|mut component| {
+                component.sort_unstable();
+                component.into_iter().map(|g| &self.groups[g]).collect()
+            })
+            .collect();
+        cycles.sort_by_key(|cycle| self.index[cycle[0]]);
+        cycles
+    }
+
+    // The groups reachable from `start` along `edges`, including `start`, each once.
+    fn reachable<'a>(&'a self, start: usize, edges: impl Fn(usize) -> &'a [usize]) -> Vec<usize> {
+        let mut visited = vec![false; self.groups.len()];
+        visited[start] = true;
+        let mut order = vec![start];
+        let mut next = 0;
+        while let Some(&group) = order.get(next) {
+            next += 1;
+            for &child in edges(group) {
+                if !visited[child] {
+                    visited[child] = true;
+                    order.push(child);
+                }
+            }
+        }
+        order
+    }
+
+    // Tarjan's algorithm, with an explicit stack so that deep nesting cannot overflow the call stack.
+    fn strongly_connected(&self) -> Vec<Vec<usize>> {
+        let count = self.groups.len();
+        let mut index = vec![usize::MAX; count];
+        let mut low = vec![0; count];
+        let mut on_stack = vec![false; count];
+        let mut stack = Vec::new();
+        let mut components = Vec::new();
+        let mut next_index = 0;
+        for root in 0..count {
+            if index[root] != usize::MAX {
+                continue;
+            }
+            let mut work = vec![(root, 0)];
+            index[root] = next_index;
+            low[root] = next_index;
+            next_index += 1;
+            stack.push(root);
+            on_stack[root] = true;
+            while let Some((group, edge)) = work.last_mut() {
+                let group = *group;
+                if let Some(&child) = self.nested[group].get(*edge) {
+                    *edge += 1;
+                    if index[child] == usize::MAX {
+                        index[child] = next_index;
+                        low[child] = next_index;
+                        next_index += 1;
+                        stack.push(child);
+                        on_stack[child] = true;
+                        work.push((child, 0));
+                    } else if on_stack[child] {
+                        low[group] = low[group].min(index[child]);
+                    }
+                    continue;
+                }
+                work.pop();
+                if let Some(&(parent, _)) = work.last() {
+                    low[parent] = low[parent].min(low[group]);
+                }
+                if low[group] == index[group] {
+                    let mut component = Vec::new();
+                    while let Some(member) = stack.pop() {
+                        on_stack[member] = false;
+                        component.push(member);
+                        if member == group {
+                            break;
+                        }
+                    }
+                    components.push(component);
+                }
+            }
+        }
+        components
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn groups(value: serde_json::Value) -> Vec<Group> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn ids(ids: Vec<&ResourceId>) -> Vec<&str> {
+        let mut ids: Vec<&str> = ids.into_iter().map(|id| id.as_str()).collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn expands_nested_membership_through_cycles() {
+        let groups = groups(json!([
+            {"schemas": [], "id": "a", "displayName": "A", "members": [{"value": "b"}, {"value": "u1"}]},
+            {"schemas": [], "id": "b", "displayName": "B", "members": [{"value": "c", "type": "Group"}, {"value": "u2"}]},
+            {"schemas": [], "id": "c", "displayName": "C", "members": [{"value": "A"}, {"value": "U1"}]},
+            {"schemas": [], "id": "d", "displayName": "D", "members": [{"value": "d"}, {"value": "a", "type": "User"}]},
+            {"schemas": [], "id": "e", "displayName": "E", "members": [{"value": "u3"}]}
+        ]));
+        let graph = GroupGraph::new(&groups);
+        // Ids compare case-insensitively, so U1 and u1 are the same user.
+        assert_eq!(ids(graph.users_of("b")), ["U1", "u2"]);
+        assert_eq!(ids(graph.users_of("d")), ["a"]);
+        assert!(graph.users_of("missing").is_empty());
+        assert_eq!(ids(graph.groups_of("u1")), ["a", "b", "c"]);
+        assert_eq!(ids(graph.groups_of("u3")), ["e"]);
+        assert_eq!(ids(graph.groups_of("a")), ["a", "b", "c", "d"]);
+
+        let cycles = graph.cycles();
+        assert_eq!(cycles.len(), 2);
+        assert_eq!(ids(cycles[0].clone()), ["a", "b", "c"]);
+        assert_eq!(ids(cycles[1].clone()), ["d"]);
+    }
+
+    #[test]
+    fn handles_deep_nesting() {
+        let chain: Vec<Group> = (0..10_000)
+            .map(|n| Group {
+                id: Some(ResourceId::new(format!("g{}", n))),
+                members: Some(vec![Member {
+                    value: Some(ResourceId::new(format!("g{}", n + 1))),
+                    r#type: Some("Group".to_string()),
+                    ..Member::default()
+                }]),
+                ..Group::default()
+            })
+            .collect();
+        let graph = GroupGraph::new(&chain);
+        assert!(graph.cycles().is_empty());
+        assert_eq!(graph.groups_of("g10000").len(), 10_000);
+    }
+}
@@ -0,0 +1,213 @@
+//! Resolving the enterprise `manager` of a user, and walking the management chain above it.
+//!
+//! The enterprise extension only references a user's manager by id. The helpers here look managers up
+//! through a caller-supplied async function, so the same code serves a remote service provider (through a
+//! client) and a local [`ResourceProvider`] (through [`from_provider`]).
+
+use std::collections::HashSet;
+use std::future::{Future, Ready, ready};
+
+use crate::models::resource_id::ResourceId;
+use crate::models::user::User;
+use crate::provider::resource_provider::ResourceProvider;
+use crate::utils::error::SCIMError;
+
+/// Why a [`ManagerChain`] ends where it does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChainEnd {
+    /// The last manager has no manager.
+    Top,
+    /// The chain reached the maximum depth; the last manager may have a manager of their own.
+    MaxDepth,
+    /// The last manager's manager is the user with this id, who already is in the chain (or is the user
+    /// the chain starts from).
+    Cycle(ResourceId),
+}
+
+/// The managers above a user, nearest first.
+#[derive(Debug)]
+pub struct ManagerChain {
+    pub managers: Vec<User>,
+    pub end: ChainEnd,
+}
+
+/// Returns the id of a user's manager: the `value` of the enterprise `manager`, or else the last segment of
+/// its `$ref`.
+pub fn manager_id(user: &User) -> Option<ResourceId> {
+    let manager = user.enterprise_user.as_ref()?.manager.as_ref()?;
+    if let Some(value) = &manager.value {
+        return Some(value.clone());
+    }
+    let reference = manager.r#ref.as_deref()?.trim_end_matches('/');
+    let id = reference.rsplit('/').next()?;
+    (!id.is_empty()).then(|| ResourceId::from(id))
+}
+
+/// Looks up the manager of `user` with `lookup`, which fetches a user by id.
+///
+/// Returns `None` if the user has no manager.
+///
+/// # Errors
+///
+/// The errors of `lookup`, e.g. `SCIMError::NotFoundError` for a manager who no longer exists.
+pub async fn resolve_manager<F, Fut>(user: &User, mut lookup: F) -> Result<Option<User>, SCIMError>
+where
+    F: FnMut(ResourceId) -> Fut,
+    Fut: Future<Output = Result<User, SCIMError>>,
+{
+    match manager_id(user) {
+        Some(id) => lookup(id).await.map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Walks the management chain above `user`, looking up at most `max_depth` managers with `lookup`.
+///
+/// A manager who is already in the chain, or who is `user`, ends the chain with [`ChainEnd::Cycle`]
+/// instead of being looked up again.
+///
+/// # Errors
+///
+/// The errors of `lookup`.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use std::future::ready;
+/// use std::pin::pin;
+/// use std::task::{Context, Poll, Waker};
+///
+/// use scim_v2::models::manager_chain::{ChainEnd, management_chain};
+/// use scim_v2::models::resource_id::ResourceId;
+/// use scim_v2::models::user::User;
+/// use scim_v2::utils::error::SCIMError;
+/// use serde_json::json;
+///
+/// let user = |id: &str, manager: &str| {
+///     json!({
+///         "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+///         "id": id,
+///         "userName": id,
+///         "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User": {"manager": {"value": manager}}
+///     })
+/// };
+/// let directory = HashMap::from([("jsmith", user("jsmith", "ceo")), ("ceo", user("ceo", "jsmith"))]);
+/// let lookup = |id: ResourceId| {
+///     ready(match directory.get(id.as_str()) {
+///         Some(user) => User::try_from(user.clone()),
+///         None => Err(SCIMError::NotFoundError(id.as_str().into())),
+///     })
+/// };
+///
+/// let bjensen = User::try_from(user("bjensen", "jsmith")).unwrap();
+/// let chain = pin!(management_chain(&bjensen, lookup, 10));
+/// let Poll::Ready(Ok(chain)) = chain.poll(&mut Context::from_waker(Waker::noop())) else { panic!() };
+/// let names: Vec<&str> = chain.managers.iter().map(|m| m.user_name.as_str()).collect();
+/// assert_eq!(names, ["jsmith", "ceo"]);
+/// assert_eq!(chain.end, ChainEnd::Cycle(ResourceId::from("jsmith")));
+/// ```
+pub async fn management_chain<F, Fut>(
+    user: &User,
+    mut lookup: F,
+    max_depth: usize,
+) -> Result<ManagerChain, SCIMError>
+where
+    F: FnMut(ResourceId) -> Fut,
+    Fut: Future<Output = Result<User, SCIMError>>,
+{
+    let mut seen: HashSet<ResourceId> = user.id.iter().cloned().collect();
+    let mut managers: Vec<User> = Vec::new();
+    loop {
+        let current = managers.last().unwrap_or(user);
+        let Some(id) = manager_id(current) else {
+            return Ok(ManagerChain {
+                managers,
+                end: ChainEnd::Top,
+            });
+        };
+        if seen.contains(&id) {
+            return Ok(ManagerChain {
+                managers,
+                end: ChainEnd::Cycle(id),
+            });
+        }
+        if managers.len() == max_depth {
+            return Ok(ManagerChain {
+                managers,
+                end: ChainEnd::MaxDepth,
+            });
+        }
+        let manager = lookup(id.clone()).await?;
+        seen.insert(id);
+        seen.extend(manager.id.iter().cloned());
+        managers.push(manager);
+    }
+}
+
+/// Returns a lookup function for [`resolve_manager`] and [`management_chain`] that reads users from
+/// `provider`.
+pub fn from_provider<P: ResourceProvider>(
+    provider: &P,
+) -> impl FnMut(ResourceId) -> Ready<Result<User, SCIMError>> + '_ {
+    move |id| ready(provider.get_resource::<User>(id.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::provider::memory::MemoryStore;
+
+    fn block_on<T>(future: impl Future<Output = T>) -> T {
+        let mut future = pin!(future);
+        match future
+            .as_mut()
+            .poll(&mut Context::from_waker(Waker::noop()))
+        {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("the lookup is not ready"),
+        }
+    }
+
+    #[test]
+    fn walks_the_chain_through_a_provider() {
+        let store = MemoryStore::new("https://example.com/v2");
+        let mut manager = None;
+        let mut ids = Vec::new();
+        for name in ["ceo", "vp", "jsmith"] {
+            let mut user = json!({
+                "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+                "userName": name
+            });
+            if let Some(id) = &manager {
+                user["urn:ietf:params:scim:schemas:extension:enterprise:2.0:User"] =
+                    json!({"manager": {"$ref": format!("https://example.com/v2/Users/{}", id)}});
+            }
+            let created = store.create("User", user).unwrap();
+            manager = created["id"].as_str().map(str::to_string);
+            ids.push(manager.clone().unwrap());
+        }
+        let jsmith: User = store.get_resource(&ids[2]).unwrap();
+
+        let vp = block_on(resolve_manager(&jsmith, from_provider(&store))).unwrap();
+        assert_eq!(vp.unwrap().user_name, "vp");
+        let chain = block_on(management_chain(&jsmith, from_provider(&store), 5)).unwrap();
+        assert_eq!(chain.managers.len(), 2);
+        assert_eq!(chain.end, ChainEnd::Top);
+        let chain = block_on(management_chain(&jsmith, from_provider(&store), 1)).unwrap();
+        assert_eq!(chain.managers[0].user_name, "vp");
+        assert_eq!(chain.end, ChainEnd::MaxDepth);
+
+        let ceo: User = store.get_resource(&ids[0]).unwrap();
+        assert!(
+            block_on(resolve_manager(&ceo, from_provider(&store)))
+                .unwrap()
+                .is_none()
+        );
+    }
+}
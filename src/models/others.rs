@@ -1,40 +1,144 @@
-use std::collections::HashMap;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+#[cfg(feature = "std")]
+use std::io::Write;
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::models::filter::FilterExpr;
 use crate::models::group::Group;
+use crate::models::patch::{self, AttributePath};
 use crate::models::resource_types::ResourceType;
+use crate::models::schema_uri::{LIST_RESPONSE_URN, PATCH_OP_URN, SEARCH_REQUEST_URN};
 use crate::models::scim_schema::Schema;
 use crate::models::user::User;
+#[cfg(feature = "std")]
+use crate::utils::diff::diff_attributes;
+use crate::utils::error::SCIMError;
+use crate::utils::json;
 
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct SearchRequest {
+    #[cfg_attr(feature = "lenient", serde(default = "default_search_request_schemas"))]
     pub schemas: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub attributes: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    excluded_attributes: Option<Vec<String>>,
+    pub excluded_attributes: Option<Vec<String>>,
     pub filter: String,
     pub start_index: i64,
     pub count: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+fn default_search_request_schemas() -> Vec<String> {
+    vec![SEARCH_REQUEST_URN.to_string()]
 }
 
 impl Default for SearchRequest {
     fn default() -> Self {
         SearchRequest {
-            schemas: vec!["urn:ietf:params:scim:api:messages:2.0:SearchRequest".to_string()],
+            schemas: default_search_request_schemas(),
             attributes: None,
             excluded_attributes: None,
             filter: "".to_string(),
             start_index: 1,
             count: 100,
+            cursor: None,
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl SearchRequest {
+    /// Validates a search request.
+    ///
+    /// This function checks that the request declares the `SearchRequest` message schema, that `count` is not
+    /// negative, that `startIndex` is at least 1 and not combined with a `cursor`, that the filter parses
+    /// with [`FilterExpr::parse`] when one is given, and that `attributes` and `excludedAttributes` are not
+    /// both set.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the search request is valid.
+    /// * `Err(SCIMError::MissingRequiredField)` - If the `SearchRequest` schema URN is missing.
+    /// * `Err(SCIMError::InvalidFieldValue)` - If `count`, `startIndex`, or the attribute lists are invalid.
+    /// * `Err(SCIMError::InvalidFilter)` - If the filter is malformed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use scim_v2::models::others::SearchRequest;
+    ///
+    /// let request = SearchRequest {
+    ///     filter: "userName eq \"bjensen\"".to_string(),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// match request.validate() {
+    ///     Ok(_) => println!("SearchRequest is valid."),
+    ///     Err(e) => println!("SearchRequest is invalid: {}", e),
+    /// }
+    /// ```
+    pub fn validate(&self) -> Result<(), SCIMError> {
+        if !self.schemas.iter().any(|s| s == SEARCH_REQUEST_URN) {
+            return Err(SCIMError::MissingRequiredField(
+                format!("schemas must contain {}", SEARCH_REQUEST_URN).into(),
+            ));
+        }
+        if self.count < 0 {
+            return Err(SCIMError::InvalidFieldValue(
+                format!("count must not be negative, got {}", self.count).into(),
+            ));
+        }
+        if self.start_index < 1 {
+            return Err(SCIMError::InvalidFieldValue(
+                format!("startIndex must be at least 1, got {}", self.start_index).into(),
+            ));
+        }
+        if self.cursor.is_some() && self.start_index != 1 {
+            return Err(SCIMError::InvalidFieldValue(
+                "startIndex and cursor must not both be set".into(),
+            ));
+        }
+        if self.attributes.is_some() && self.excluded_attributes.is_some() {
+            return Err(SCIMError::InvalidFieldValue(
+                "attributes and excludedAttributes must not both be set".into(),
+            ));
+        }
+        self.parsed_filter()?;
+        Ok(())
+    }
+
+    /// Parses the filter, or returns `None` if the request has none.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::InvalidFilter` if the filter is malformed.
+    pub fn parsed_filter(&self) -> Result<Option<FilterExpr>, SCIMError> {
+        parse_filter(&self.filter)
+    }
+}
+
+// Parses a filter parameter; blank filters stand for no filter.
+fn parse_filter(filter: &str) -> Result<Option<FilterExpr>, SCIMError> {
+    if filter.trim().is_empty() {
+        Ok(None)
+    } else {
+        FilterExpr::parse(filter).map(Some)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct ListQuery {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -47,6 +151,37 @@ pub struct ListQuery {
     pub attributes: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub excluded_attributes: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_order: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+impl ListQuery {
+    /// Parses the `filter` parameter, or returns `None` if the query has none.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::InvalidFilter` if the filter is malformed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::filter::FilterExpr;
+    /// use scim_v2::models::others::ListQuery;
+    ///
+    /// let query = ListQuery {
+    ///     filter: Some(r#"userName eq "bjensen""#.to_string()),
+    ///     ..ListQuery::default()
+    /// };
+    /// assert!(matches!(query.parsed_filter(), Ok(Some(FilterExpr::Compare(..)))));
+    /// assert!(matches!(ListQuery::default().parsed_filter(), Ok(None)));
+    /// ```
+    pub fn parsed_filter(&self) -> Result<Option<FilterExpr>, SCIMError> {
+        parse_filter(self.filter.as_deref().unwrap_or_default())
+    }
 }
 
 impl Default for ListQuery {
@@ -57,11 +192,15 @@ impl Default for ListQuery {
             count: Some(100),
             attributes: Some("".to_string()),
             excluded_attributes: Some("".to_string()),
+            sort_by: None,
+            sort_order: None,
+            cursor: None,
         }
     }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[serde(untagged)]
 pub enum Resource {
     User(Box<User>),
@@ -71,14 +210,24 @@ pub enum Resource {
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct ListResponse {
     pub items_per_page: i64,
     pub total_results: i64,
     pub start_index: i64,
+    #[cfg_attr(feature = "lenient", serde(default = "default_list_response_schemas"))]
     pub schemas: Vec<String>,
     #[serde(rename = "Resources")]
     pub resources: Vec<Resource>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_cursor: Option<String>,
+}
+
+fn default_list_response_schemas() -> Vec<String> {
+    vec![LIST_RESPONSE_URN.to_string()]
 }
 
 impl Default for ListResponse {
@@ -87,39 +236,769 @@ impl Default for ListResponse {
             items_per_page: 0,
             total_results: 0,
             start_index: 1,
-            schemas: vec!["urn:ietf:params:scim:api:messages:2.0:ListResponse".to_string()],
+            schemas: default_list_response_schemas(),
             resources: vec![],
+            next_cursor: None,
+            previous_cursor: None,
+        }
+    }
+}
+
+impl ListResponse {
+    /// Builds a `ListResponse` for one page of results.
+    ///
+    /// This function fills in `itemsPerPage` from the number of resources on the page and sets the
+    /// `ListResponse` message schema, so servers don't have to keep the counters consistent by hand.
+    ///
+    /// # Parameters
+    ///
+    /// * `resources` - The resources on this page.
+    /// * `total_results` - The total number of results matching the query, across all pages.
+    /// * `start_index` - The 1-based index of the first resource on this page.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ListResponse)` - If the page is consistent with the totals.
+    /// * `Err(SCIMError::InvalidFieldValue)` - If `start_index` is less than 1, `total_results` is negative,
+    ///   or the page extends beyond `total_results`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::others::{ListResponse, Resource};
+    /// use scim_v2::models::user::User;
+    ///
+    /// let users = vec![Resource::User(Box::new(User {
+    ///     user_name: "jdoe@example.com".to_string(),
+    ///     ..Default::default()
+    /// }))];
+    /// let response = ListResponse::from_page(users, 10, 3).unwrap();
+    /// assert_eq!(response.items_per_page, 1);
+    /// assert_eq!(response.total_results, 10);
+    /// ```
+    pub fn from_page(
+        resources: Vec<Resource>,
+        total_results: i64,
+        start_index: i64,
+    ) -> Result<Self, SCIMError> {
+        if start_index < 1 {
+            return Err(SCIMError::InvalidFieldValue(
+                format!("startIndex must be at least 1, got {}", start_index).into(),
+            ));
+        }
+        if total_results < 0 {
+            return Err(SCIMError::InvalidFieldValue(
+                format!("totalResults must not be negative, got {}", total_results).into(),
+            ));
+        }
+        let items_per_page = resources.len() as i64;
+        if !resources.is_empty() && start_index - 1 + items_per_page > total_results {
+            return Err(SCIMError::InvalidFieldValue(
+                format!(
+                    "page of {} resources starting at {} exceeds totalResults {}",
+                    items_per_page, start_index, total_results
+                )
+                .into(),
+            ));
         }
+        Ok(ListResponse {
+            items_per_page,
+            total_results,
+            start_index,
+            resources,
+            ..Default::default()
+        })
+    }
+
+    /// Serializes the `ListResponse` as JSON into `buffer`, appending to its current content.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if the `ListResponse` cannot be serialized.
+    pub fn serialize_into(&self, buffer: &mut Vec<u8>) -> Result<(), SCIMError> {
+        json::append_to(buffer, self)
+    }
+
+    /// Serializes the `ListResponse` as JSON to `writer`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if the `ListResponse` cannot be serialized or writing to `writer`
+    /// fails.
+    #[cfg(feature = "std")]
+    pub fn to_writer(&self, writer: impl Write) -> Result<(), SCIMError> {
+        serde_json::to_writer(writer, self).map_err(SCIMError::SerializationError)
     }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct PatchOp {
+    #[cfg_attr(feature = "lenient", serde(default = "default_patch_op_schemas"))]
     pub schemas: Vec<String>,
     #[serde(rename = "Operations")]
     pub operations: Vec<PatchOperations>,
 }
 
+fn default_patch_op_schemas() -> Vec<String> {
+    vec![PATCH_OP_URN.to_string()]
+}
+
 impl Default for PatchOp {
     fn default() -> Self {
         PatchOp {
-            schemas: vec!["urn:ietf:params:scim:api:messages:2.0:PatchOp".to_string()],
+            schemas: default_patch_op_schemas(),
             operations: vec![PatchOperations::default()],
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl PatchOp {
+    /// Validates a `PatchOp` message before it is applied (RFC 7644 §3.5.2).
+    ///
+    /// This checks that `schemas` contains the `PatchOp` schema URN, that there is at least one operation,
+    /// that every path parses, that `remove` operations have a path and that `add` and `replace` operations
+    /// have a value, which must be an object of attributes when they have no path. Paths are resolved
+    /// against the registered schemas: a filter must select values of a multi-valued attribute, and a
+    /// sub-attribute must be one the attribute defines. Attributes no schema defines are not checked. Operation names are
+    /// checked when the message is read: an unknown `op` is an `SCIMError::InvalidFieldValue`. The errors
+    /// map to the `scimType` a server answers with through [`ScimHttpError`](crate::models::errors::ScimHttpError).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the message is valid.
+    /// * `Err(SCIMError::InvalidPath)` - If a path is malformed or does not fit the schema (`invalidPath`).
+    /// * `Err(SCIMError::NoTarget)` - If a `remove` operation has no path (`noTarget`).
+    /// * `Err(SCIMError::InvalidFieldValue)` - If a value is missing or is not an object where one is
+    ///   required, or there are no operations (`invalidValue`).
+    /// * `Err(SCIMError::MissingRequiredField)` - If the `PatchOp` schema URN is missing (`invalidValue`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use scim_v2::models::errors::ScimHttpError;
+    /// use scim_v2::models::others::PatchOp;
+    /// use scim_v2::utils::error::SCIMError;
+    /// use serde_json::json;
+    ///
+    /// let patch = |operations| {
+    ///     PatchOp::try_from(json!({
+    ///         "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+    ///         "Operations": operations
+    ///     }))
+    /// };
+    ///
+    /// let valid = patch(json!([{"op": "replace", "path": "emails[type eq \"work\"].value", "value": "b@example.com"}]));
+    /// assert!(valid.unwrap().validate().is_ok());
+    ///
+    /// let error = patch(json!([{"op": "remove"}])).unwrap().validate().unwrap_err();
+    /// assert_eq!(ScimHttpError::from(&error).scim_type.as_deref(), Some("noTarget"));
+    ///
+    /// let error = patch(json!([{"op": "add", "path": "emails[type eq]", "value": "b@example.com"}]));
+    /// assert!(matches!(error.unwrap().validate(), Err(SCIMError::InvalidPath(_))));
+    ///
+    /// assert!(matches!(patch(json!([{"op": "merge"}])), Err(SCIMError::InvalidFieldValue(_))));
+    /// ```
+    pub fn validate(&self) -> Result<(), SCIMError> {
+        if !self.schemas.iter().any(|s| s == PATCH_OP_URN) {
+            return Err(SCIMError::MissingRequiredField(
+                format!("schemas must contain {}", PATCH_OP_URN).into(),
+            ));
+        }
+        if self.operations.is_empty() {
+            return Err(SCIMError::InvalidFieldValue(
+                "Operations must not be empty".into(),
+            ));
+        }
+        for operation in &self.operations {
+            if let Some(path) = patch::check(operation)? {
+                patch::check_schema(&path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the smallest `PatchOp` message that turns `old` into `new`.
+    ///
+    /// Attributes `new` adds are added, those it changes are replaced and those it drops are removed.
+    /// Complex attributes are patched sub-attribute by sub-attribute, the values added to a multi-valued
+    /// attribute are added on their own, and enterprise extension attributes are addressed by their schema
+    /// URI; an extension `new` drops is removed as a whole. `id`, `meta` and `schemas` are left out, as
+    /// patching an extension lists or unlists its schema. Applying the message to `old` with
+    /// [`User::apply_patch`] gives `new`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if one of the users cannot be represented as JSON.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::others::PatchOp;
+    /// use scim_v2::models::user::User;
+    /// use serde_json::json;
+    ///
+    /// let old = User::try_from(json!({
+    ///     "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+    ///     "userName": "bjensen",
+    ///     "name": {"givenName": "Barbara", "familyName": "Jensen"}
+    /// }))
+    /// .unwrap();
+    /// let new = User::try_from(json!({
+    ///     "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+    ///     "userName": "bjensen",
+    ///     "name": {"givenName": "Babs", "familyName": "Jensen"},
+    ///     "title": "Tour Guide"
+    /// }))
+    /// .unwrap();
+    ///
+    /// let patch = PatchOp::diff(&old, &new).unwrap();
+    /// assert_eq!(
+    ///     serde_json::to_value(&patch.operations).unwrap(),
+    ///     json!([
+    ///         {"op": "replace", "path": "name.givenName", "value": "Babs"},
+    ///         {"op": "add", "path": "title", "value": "Tour Guide"}
+    ///     ])
+    /// );
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn diff(old: &User, new: &User) -> Result<PatchOp, SCIMError> {
+        let old = serde_json::to_value(old).map_err(SCIMError::SerializationError)?;
+        let new = serde_json::to_value(new).map_err(SCIMError::SerializationError)?;
+        // Attribute names cannot contain ':', so the members that do are extensions.
+        let operations = diff_attributes(
+            &old,
+            &new,
+            |name| name.contains(':'),
+            |path| !["id", "meta", "schemas"].contains(&path),
+        );
+        Ok(PatchOp {
+            schemas: default_patch_op_schemas(),
+            operations: operations
+                .into_iter()
+                .map(serde_json::from_value)
+                .collect::<Result<_, _>>()
+                .map_err(SCIMError::DeserializationError)?,
+        })
+    }
+}
+
+/// One operation of a [`PatchOp`] message (RFC 7644 §3.5.2).
+///
+/// `path` names the attribute the operation targets and may filter the values of a multi-valued attribute;
+/// it parses into an [`AttributePath`]. Without a path, the `value` of an `add` or `replace` operation is an
+/// object of attributes to set. `remove` operations take no value.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::others::{Op, PatchOperations};
+/// use serde_json::json;
+///
+/// let operation: PatchOperations =
+///     serde_json::from_value(json!({"op": "Remove", "path": "emails[type eq \"work\"]"})).unwrap();
+/// assert_eq!(operation.op, Op::Remove);
+/// assert_eq!(operation.value, None);
+/// assert_eq!(
+///     serde_json::to_value(&operation).unwrap(),
+///     json!({"op": "remove", "path": "emails[type eq \"work\"]"})
+/// );
+/// ```
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct PatchOperations {
-    pub op: String,
-    pub value: HashMap<String, Value>,
+    pub op: Op,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
 }
 
-impl Default for PatchOperations {
-    fn default() -> Self {
-        PatchOperations {
-            op: "".to_string(),
-            value: HashMap::new(),
+impl PatchOperations {
+    /// Parses the operation's path, if it has one.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::InvalidPath` if the path is not a PATCH path.
+    pub fn attribute_path(&self) -> Result<Option<AttributePath>, SCIMError> {
+        self.path.as_deref().map(AttributePath::parse).transpose()
+    }
+}
+
+/// The kind of a PATCH operation. It is written in lowercase and read case-insensitively, as some clients
+/// send `Add` or `Replace`.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase", try_from = "String")]
+pub enum Op {
+    #[default]
+    Add,
+    Remove,
+    Replace,
+}
+
+impl Op {
+    /// Returns the operation as written in a PATCH request, e.g. `add`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Op::Add => "add",
+            Op::Remove => "remove",
+            Op::Replace => "replace",
+        }
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Op {
+    type Err = SCIMError;
+
+    fn from_str(op: &str) -> Result<Self, Self::Err> {
+        [Op::Add, Op::Remove, Op::Replace]
+            .into_iter()
+            .find(|known| known.as_str().eq_ignore_ascii_case(op))
+            .ok_or_else(|| {
+                SCIMError::InvalidFieldValue(format!("unknown PATCH operation '{}'", op).into())
+            })
+    }
+}
+
+impl TryFrom<String> for Op {
+    type Error = SCIMError;
+
+    fn try_from(op: String) -> Result<Self, Self::Error> {
+        op.parse()
+    }
+}
+
+/// Converts an already-parsed JSON value into a `SearchRequest` struct.
+///
+/// Web frameworks usually hand over request bodies as a `serde_json::Value`; this conversion avoids
+/// round-tripping that value through a string.
+///
+/// # Errors
+///
+/// Returns `SCIMError::DeserializationError` if the value does not describe a `SearchRequest` object.
+///
+/// # Examples
+///
+/// ```rust
+/// use scim_v2::models::others::SearchRequest;
+/// use serde_json::json;
+///
+/// let value = json!({"schemas": ["urn:ietf:params:scim:api:messages:2.0:SearchRequest"], "filter": "userName eq \"bjensen\"", "startIndex": 1, "count": 10});
+/// match SearchRequest::try_from(value) {
+///     Ok(resource) => println!("Successfully converted JSON to SearchRequest: {:?}", resource),
+///     Err(e) => println!("Error converting from JSON to SearchRequest: {}", e),
+/// }
+/// ```
+impl TryFrom<Value> for SearchRequest {
+    type Error = SCIMError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        serde_json::from_value(value).map_err(SCIMError::DeserializationError)
+    }
+}
+
+/// Converts a `SearchRequest` struct into a JSON value.
+///
+/// # Errors
+///
+/// Returns `SCIMError::SerializationError` if the `SearchRequest` cannot be represented as JSON.
+impl TryFrom<SearchRequest> for Value {
+    type Error = SCIMError;
+
+    fn try_from(value: SearchRequest) -> Result<Self, Self::Error> {
+        serde_json::to_value(value).map_err(SCIMError::SerializationError)
+    }
+}
+
+/// Converts a JSON string into a `ListResponse` struct.
+///
+/// # Errors
+///
+/// Returns `SCIMError::DeserializationError` if the provided JSON string cannot be parsed into a `ListResponse` object.
+///
+/// # Examples
+///
+/// ```rust
+/// use scim_v2::models::others::ListResponse;
+///
+/// let json = r#"{"schemas": ["urn:ietf:params:scim:api:messages:2.0:ListResponse"], "totalResults": 0, "itemsPerPage": 0, "startIndex": 1, "Resources": []}"#;
+/// let list = ListResponse::try_from(json).unwrap();
+/// assert_eq!(list.total_results, 0);
+/// ```
+impl TryFrom<&str> for ListResponse {
+    type Error = SCIMError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        json::from_str(value)
+    }
+}
+
+/// Converts an already-parsed JSON value into a `ListResponse` struct.
+///
+/// Web frameworks usually hand over request bodies as a `serde_json::Value`; this conversion avoids
+/// round-tripping that value through a string.
+///
+/// # Errors
+///
+/// Returns `SCIMError::DeserializationError` if the value does not describe a `ListResponse` object.
+///
+/// # Examples
+///
+/// ```rust
+/// use scim_v2::models::others::ListResponse;
+/// use serde_json::json;
+///
+/// let value = json!({"schemas": ["urn:ietf:params:scim:api:messages:2.0:ListResponse"], "totalResults": 0, "itemsPerPage": 0, "startIndex": 1, "Resources": []});
+/// match ListResponse::try_from(value) {
+///     Ok(resource) => println!("Successfully converted JSON to ListResponse: {:?}", resource),
+///     Err(e) => println!("Error converting from JSON to ListResponse: {}", e),
+/// }
+/// ```
+impl TryFrom<Value> for ListResponse {
+    type Error = SCIMError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        serde_json::from_value(value).map_err(SCIMError::DeserializationError)
+    }
+}
+
+/// Converts a `ListResponse` struct into a JSON value.
+///
+/// # Errors
+///
+/// Returns `SCIMError::SerializationError` if the `ListResponse` cannot be represented as JSON.
+impl TryFrom<ListResponse> for Value {
+    type Error = SCIMError;
+
+    fn try_from(value: ListResponse) -> Result<Self, Self::Error> {
+        serde_json::to_value(value).map_err(SCIMError::SerializationError)
+    }
+}
+
+/// Converts an already-parsed JSON value into a `PatchOp` struct.
+///
+/// Web frameworks usually hand over request bodies as a `serde_json::Value`; this conversion avoids
+/// round-tripping that value through a string.
+///
+/// # Errors
+///
+/// Returns `SCIMError::InvalidFieldValue` if an operation's `op` is not `add`, `remove` or `replace`, and
+/// `SCIMError::DeserializationError` if the value does not otherwise describe a `PatchOp` object.
+///
+/// # Examples
+///
+/// ```rust
+/// use scim_v2::models::others::PatchOp;
+/// use serde_json::json;
+///
+/// let value = json!({"schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"], "Operations": [{"op": "add", "value": {"nickName": "Babs"}}]});
+/// match PatchOp::try_from(value) {
+///     Ok(resource) => println!("Successfully converted JSON to PatchOp: {:?}", resource),
+///     Err(e) => println!("Error converting from JSON to PatchOp: {}", e),
+/// }
+/// ```
+impl TryFrom<Value> for PatchOp {
+    type Error = SCIMError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        // Unknown operations are invalid values rather than malformed messages.
+        let operations = value["Operations"].as_array().into_iter().flatten();
+        for op in operations.filter_map(|operation| operation["op"].as_str()) {
+            op.parse::<Op>()?;
+        }
+        serde_json::from_value(value).map_err(SCIMError::DeserializationError)
+    }
+}
+
+/// Converts a `PatchOp` struct into a JSON value.
+///
+/// # Errors
+///
+/// Returns `SCIMError::SerializationError` if the `PatchOp` cannot be represented as JSON.
+impl TryFrom<PatchOp> for Value {
+    type Error = SCIMError;
+
+    fn try_from(value: PatchOp) -> Result<Self, Self::Error> {
+        serde_json::to_value(value).map_err(SCIMError::SerializationError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn users(count: usize) -> Vec<Resource> {
+        (0..count)
+            .map(|i| {
+                Resource::User(Box::new(User {
+                    user_name: format!("user{}", i),
+                    ..Default::default()
+                }))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn search_request_validate_accepts_valid_request() {
+        let request = SearchRequest {
+            attributes: Some(vec!["userName".to_string()]),
+            filter: r#"emails[type eq "work" and value co "(x"] or not (userName sw "b")"#
+                .to_string(),
+            ..Default::default()
+        };
+        assert!(request.validate().is_ok());
+        assert!(SearchRequest::default().validate().is_ok());
+    }
+
+    #[test]
+    fn search_request_validate_rejects_malformed_requests() {
+        let cases = [
+            SearchRequest {
+                schemas: vec![],
+                ..Default::default()
+            },
+            SearchRequest {
+                count: -1,
+                ..Default::default()
+            },
+            SearchRequest {
+                start_index: 0,
+                ..Default::default()
+            },
+            SearchRequest {
+                attributes: Some(vec!["userName".to_string()]),
+                excluded_attributes: Some(vec!["emails".to_string()]),
+                ..Default::default()
+            },
+            SearchRequest {
+                filter: r#"(userName eq "bjensen""#.to_string(),
+                ..Default::default()
+            },
+            SearchRequest {
+                filter: "userName eq bjensen".to_string(),
+                ..Default::default()
+            },
+            SearchRequest {
+                filter: r#"userName eq "bjensen" and"#.to_string(),
+                ..Default::default()
+            },
+        ];
+        for request in cases {
+            assert!(
+                request.validate().is_err(),
+                "{:?} should be invalid",
+                request
+            );
+        }
+    }
+
+    #[test]
+    fn list_response_from_page_fills_bookkeeping() {
+        let response = ListResponse::from_page(users(2), 5, 4).unwrap();
+        assert_eq!(response.items_per_page, 2);
+        assert_eq!(response.total_results, 5);
+        assert_eq!(response.start_index, 4);
+        assert_eq!(response.schemas, vec![LIST_RESPONSE_URN.to_string()]);
+    }
+
+    #[test]
+    fn list_response_from_page_rejects_inconsistent_pages() {
+        assert!(ListResponse::from_page(users(1), 1, 0).is_err());
+        assert!(ListResponse::from_page(users(0), -1, 1).is_err());
+        assert!(ListResponse::from_page(users(3), 2, 1).is_err());
+        assert!(ListResponse::from_page(users(0), 0, 1).is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn patch_op_diff_turns_the_old_user_into_the_new_one() {
+        let user = |value: Value| User::try_from(value).unwrap();
+        let old = user(serde_json::json!({
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+            "id": "2819c223",
+            "userName": "bjensen",
+            "nickName": "Babs",
+            "name": {"givenName": "Barbara", "familyName": "Jensen"},
+            "emails": [{"value": "bjensen@example.com", "type": "work", "primary": true}]
+        }));
+        let new = user(serde_json::json!({
+            "schemas": [
+                "urn:ietf:params:scim:schemas:core:2.0:User",
+                "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User"
+            ],
+            "id": "2819c223",
+            "userName": "bjensen",
+            "name": {"givenName": "Barbara"},
+            "emails": [
+                {"value": "bjensen@example.com", "type": "work", "primary": true},
+                {"value": "babs@example.org", "type": "home"}
+            ],
+            "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User": {"employeeNumber": "701984"}
+        }));
+
+        let patch = PatchOp::diff(&old, &new).unwrap();
+        let paths: Vec<_> = patch
+            .operations
+            .iter()
+            .map(|operation| format!("{} {}", operation.op, operation.path.as_deref().unwrap()))
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                "add emails",
+                "remove name.familyName",
+                "add urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:employeeNumber",
+                "remove nickName",
+            ]
+        );
+        assert_eq!(
+            patch.operations[0].value,
+            Some(serde_json::json!([{"value": "babs@example.org", "type": "home"}]))
+        );
+
+        let mut patched = old;
+        patched.apply_patch(&patch).unwrap();
+        assert_eq!(
+            serde_json::to_value(&patched).unwrap(),
+            serde_json::to_value(&new).unwrap()
+        );
+        assert!(PatchOp::diff(&new, &new).unwrap().operations.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn patch_op_diff_round_trips_extensions_and_multi_valued_attributes() {
+        const CORE: &str = "urn:ietf:params:scim:schemas:core:2.0:User";
+        const ENTERPRISE: &str = "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User";
+        let plain = serde_json::json!({
+            "schemas": [CORE],
+            "userName": "bjensen",
+            "emails": [
+                {"value": "bjensen@example.com", "type": "work", "primary": true},
+                {"value": "babs@example.org", "type": "home"}
+            ]
+        });
+        let extended = serde_json::json!({
+            "schemas": [CORE, ENTERPRISE],
+            "userName": "bjensen",
+            "emails": [
+                {"value": "bjensen@example.com", "type": "work", "primary": true},
+                {"value": "babs@example.org", "type": "home"}
+            ],
+            ENTERPRISE: {"department": "Tour Operations"}
+        });
+        let changed = serde_json::json!({
+            "schemas": [CORE],
+            "userName": "bjensen",
+            "emails": [{"value": "barbara@example.com", "type": "work", "primary": true}]
+        });
+
+        for (old, new) in [(&plain, &extended), (&extended, &plain), (&plain, &changed)] {
+            let (mut patched, new) = (
+                User::try_from(old.clone()).unwrap(),
+                User::try_from(new.clone()).unwrap(),
+            );
+            let patch = PatchOp::diff(&patched, &new).unwrap();
+            patched.apply_patch(&patch).unwrap();
+            assert_eq!(
+                serde_json::to_value(&patched).unwrap(),
+                serde_json::to_value(&new).unwrap(),
+                "{:?}",
+                patch.operations
+            );
+        }
+
+        let removed = PatchOp::diff(
+            &User::try_from(extended).unwrap(),
+            &User::try_from(plain).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            removed.operations,
+            [PatchOperations {
+                op: Op::Remove,
+                path: Some(ENTERPRISE.to_string()),
+                value: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn patch_op_validate_rejects_malformed_operations() {
+        let scim_type = |operations: Value| {
+            let patch = PatchOp::try_from(serde_json::json!({
+                "schemas": [PATCH_OP_URN],
+                "Operations": operations
+            }))
+            .and_then(|patch| patch.validate());
+            patch.map_err(|error| crate::models::errors::ScimHttpError::from(&error).scim_type)
+        };
+
+        assert_eq!(
+            scim_type(serde_json::json!([
+                {"op": "Add", "path": "members", "value": [{"value": "2819c223"}]},
+                {"op": "remove", "path": "emails[type eq \"work\" and primary eq true]"},
+                {"op": "replace", "value": {"displayName": "Tour Guides"}}
+            ])),
+            Ok(())
+        );
+        for (operations, expected) in [
+            (serde_json::json!([]), "invalidValue"),
+            (
+                serde_json::json!([{"op": "merge", "path": "title"}]),
+                "invalidValue",
+            ),
+            (serde_json::json!([{"op": "remove"}]), "noTarget"),
+            (
+                serde_json::json!([{"op": "replace", "path": "title"}]),
+                "invalidValue",
+            ),
+            (
+                serde_json::json!([{"op": "add", "value": "Director"}]),
+                "invalidValue",
+            ),
+            (
+                serde_json::json!([{"op": "add", "path": "emails[type eq \"work\"", "value": "x"}]),
+                "invalidPath",
+            ),
+            (
+                serde_json::json!([{"op": "remove", "path": "name..givenName"}]),
+                "invalidPath",
+            ),
+            (
+                serde_json::json!([{"op": "remove", "path": "name[givenName eq \"Barbara\"]"}]),
+                "invalidPath",
+            ),
+            (
+                serde_json::json!([{"op": "remove", "path": "userName[value eq \"b\"]"}]),
+                "invalidPath",
+            ),
+            (
+                serde_json::json!([{"op": "add", "path": "emails[type eq \"work\"].country", "value": "x"}]),
+                "invalidPath",
+            ),
+        ] {
+            assert_eq!(
+                scim_type(operations.clone()),
+                Err(Some(expected.to_string())),
+                "{}",
+                operations
+            );
         }
+        let unlabeled = PatchOp {
+            schemas: vec![],
+            ..PatchOp::default()
+        };
+        assert!(matches!(
+            unlabeled.validate(),
+            Err(SCIMError::MissingRequiredField(_))
+        ));
     }
 }
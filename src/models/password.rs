@@ -0,0 +1,385 @@
+//! Pre-hashed passwords for password synchronization.
+//!
+//! Directory sync agents, such as those copying Active Directory accounts to a cloud directory, never see
+//! the plaintext password and cannot send it as `password`. They send the hash the source holds instead,
+//! together with its scheme, in the [`PasswordSync`] extension; the service provider decides with a
+//! [`PasswordPolicy`] which schemes it accepts.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::{Debug, Display, Formatter};
+use core::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::PASSWORD_SYNC_SCHEMA;
+use crate::models::extension::ScimExtension;
+use crate::models::schema_uri::PASSWORD_SYNC_URN;
+use crate::models::scim_schema::Schema;
+use crate::utils::error::SCIMError;
+
+/// A password hashing scheme.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum HashAlgorithm {
+    /// Argon2id, in PHC string form (`$argon2id$...`).
+    Argon2id,
+    /// bcrypt, in modular crypt form (`$2b$...`, also `$2a$` and `$2y$`).
+    Bcrypt,
+    /// scrypt, in PHC string form (`$scrypt$...`).
+    Scrypt,
+    /// PBKDF2 with HMAC-SHA-256, in PHC string form (`$pbkdf2-sha256$...`).
+    Pbkdf2Sha256,
+    /// PBKDF2 with HMAC-SHA-512, in PHC string form (`$pbkdf2-sha512$...`).
+    Pbkdf2Sha512,
+    /// SHA-512 crypt, in modular crypt form (`$6$...`).
+    Sha512Crypt,
+    /// SHA-256 crypt, in modular crypt form (`$5$...`).
+    Sha256Crypt,
+    /// Salted SHA-512 as stored by LDAP servers (`{SSHA512}...`).
+    Ssha512,
+    /// The NT hash Active Directory keeps (unsalted MD4), as 32 hexadecimal digits.
+    Nt,
+    /// Unsalted SHA-1, as 40 hexadecimal digits.
+    Sha1,
+}
+
+impl HashAlgorithm {
+    /// Every scheme, strongest first.
+    pub const ALL: [HashAlgorithm; 10] = [
+        HashAlgorithm::Argon2id,
+        HashAlgorithm::Bcrypt,
+        HashAlgorithm::Scrypt,
+        HashAlgorithm::Pbkdf2Sha512,
+        HashAlgorithm::Pbkdf2Sha256,
+        HashAlgorithm::Sha512Crypt,
+        HashAlgorithm::Sha256Crypt,
+        HashAlgorithm::Ssha512,
+        HashAlgorithm::Nt,
+        HashAlgorithm::Sha1,
+    ];
+
+    /// Returns the name of the scheme, as in the `algorithm` attribute.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Argon2id => "argon2id",
+            HashAlgorithm::Bcrypt => "bcrypt",
+            HashAlgorithm::Scrypt => "scrypt",
+            HashAlgorithm::Pbkdf2Sha256 => "pbkdf2-sha256",
+            HashAlgorithm::Pbkdf2Sha512 => "pbkdf2-sha512",
+            HashAlgorithm::Sha512Crypt => "sha512-crypt",
+            HashAlgorithm::Sha256Crypt => "sha256-crypt",
+            HashAlgorithm::Ssha512 => "ssha512",
+            HashAlgorithm::Nt => "nt",
+            HashAlgorithm::Sha1 => "sha1",
+        }
+    }
+
+    /// Returns `false` for the unsalted schemes, which are open to precomputed tables.
+    pub fn is_salted(&self) -> bool {
+        !matches!(self, HashAlgorithm::Nt | HashAlgorithm::Sha1)
+    }
+
+    /// Recognizes the scheme of a hash by its prefix. Hexadecimal hashes carry no prefix and are not
+    /// recognized.
+    pub fn detect(hash: &str) -> Option<HashAlgorithm> {
+        HashAlgorithm::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.prefixes().iter().any(|p| hash.starts_with(p)))
+    }
+
+    /// Returns `true` if `hash` has the form of the scheme: its prefix, or the number of hexadecimal
+    /// digits of the unsalted schemes.
+    pub fn accepts(&self, hash: &str) -> bool {
+        match self {
+            HashAlgorithm::Nt => is_hex(hash, 32),
+            HashAlgorithm::Sha1 => is_hex(hash, 40),
+            _ => self
+                .prefixes()
+                .iter()
+                .any(|prefix| hash.len() > prefix.len() && hash.starts_with(prefix)),
+        }
+    }
+
+    fn prefixes(&self) -> &'static [&'static str] {
+        match self {
+            HashAlgorithm::Argon2id => &["$argon2id$"],
+            HashAlgorithm::Bcrypt => &["$2b$", "$2a$", "$2y$"],
+            HashAlgorithm::Scrypt => &["$scrypt$"],
+            HashAlgorithm::Pbkdf2Sha256 => &["$pbkdf2-sha256$"],
+            HashAlgorithm::Pbkdf2Sha512 => &["$pbkdf2-sha512$"],
+            HashAlgorithm::Sha512Crypt => &["$6$"],
+            HashAlgorithm::Sha256Crypt => &["$5$"],
+            HashAlgorithm::Ssha512 => &["{SSHA512}"],
+            HashAlgorithm::Nt | HashAlgorithm::Sha1 => &[],
+        }
+    }
+}
+
+fn is_hex(hash: &str, digits: usize) -> bool {
+    hash.len() == digits && hash.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+impl Display for HashAlgorithm {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Parses a scheme name, ignoring case.
+impl FromStr for HashAlgorithm {
+    type Err = SCIMError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        HashAlgorithm::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                SCIMError::InvalidFieldValue(format!("unknown hash algorithm '{}'", s).into())
+            })
+    }
+}
+
+/// A password hash and its scheme.
+///
+/// The `Debug` output leaves out the hash, so that credentials do not end up in logs.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct HashedPassword {
+    pub algorithm: HashAlgorithm,
+    /// The hash, in the scheme's PHC, modular crypt or hexadecimal form.
+    pub value: String,
+}
+
+impl HashedPassword {
+    /// Pairs a hash with its scheme.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::InvalidFieldValue` if the hash does not have the form of the scheme.
+    pub fn new(algorithm: HashAlgorithm, value: impl Into<String>) -> Result<Self, SCIMError> {
+        let hashed = HashedPassword {
+            algorithm,
+            value: value.into(),
+        };
+        hashed.check_form()?;
+        Ok(hashed)
+    }
+
+    /// Reads a hash in PHC or modular crypt form, recognizing the scheme by its prefix.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::InvalidFieldValue` if the scheme is not recognized.
+    pub fn parse(value: &str) -> Result<Self, SCIMError> {
+        let algorithm = HashAlgorithm::detect(value)
+            .ok_or_else(|| SCIMError::InvalidFieldValue("unrecognized password hash".into()))?;
+        HashedPassword::new(algorithm, value)
+    }
+
+    fn check_form(&self) -> Result<(), SCIMError> {
+        if self.algorithm.accepts(&self.value) {
+            Ok(())
+        } else {
+            Err(SCIMError::InvalidFieldValue(
+                format!("hashedPassword.value is not a {} hash", self.algorithm).into(),
+            ))
+        }
+    }
+}
+
+impl Debug for HashedPassword {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HashedPassword")
+            .field("algorithm", &self.algorithm)
+            .field("value", &"<redacted>")
+            .finish()
+    }
+}
+
+/// The password synchronization extension of a User, carrying a pre-hashed password in place of
+/// `password`.
+///
+/// The extension is defined by this crate; register [`PasswordSync::schema`] and add
+/// `PasswordSync::schema_extension(false)` to the `User` resource type to accept it.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::extension::ScimExtension;
+/// use scim_v2::models::password::{HashedPassword, PasswordPolicy, PasswordSync};
+/// use serde_json::json;
+///
+/// let mut user = json!({
+///     "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+///     "userName": "bjensen",
+/// });
+/// let hashed = HashedPassword::parse("$2b$12$R9h/cIPz0gi.URNNX3kh2OPST9/PgBkqquzi.Ss7KIUgO2t0jWMUW").unwrap();
+/// PasswordSync { hashed_password: hashed }.insert_into(&mut user).unwrap();
+///
+/// assert!(PasswordPolicy::default().check(&user).is_ok());
+/// assert!(PasswordPolicy::new([]).check(&user).is_err());
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordSync {
+    pub hashed_password: HashedPassword,
+}
+
+impl PasswordSync {
+    /// Returns the schema of the extension, for registering it with a
+    /// [`SchemaRegistry`](crate::models::schema_registry::SchemaRegistry).
+    pub fn schema() -> Schema {
+        serde_json::from_str(PASSWORD_SYNC_SCHEMA).expect("bundled schema is valid JSON")
+    }
+}
+
+impl ScimExtension for PasswordSync {
+    const URN: &'static str = PASSWORD_SYNC_URN;
+
+    fn attribute_names() -> &'static [&'static str] {
+        &["hashedPassword"]
+    }
+}
+
+/// Which credentials a service provider accepts in User resources.
+///
+/// The default policy accepts plaintext `password`s and hashes of the salted schemes; the NT and SHA-1
+/// hashes must be permitted explicitly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasswordPolicy {
+    /// The schemes accepted in the [`PasswordSync`] extension.
+    pub permitted: Vec<HashAlgorithm>,
+    /// Whether the plaintext `password` attribute is accepted.
+    pub allow_plaintext: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        PasswordPolicy {
+            permitted: HashAlgorithm::ALL
+                .into_iter()
+                .filter(HashAlgorithm::is_salted)
+                .collect(),
+            allow_plaintext: true,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// Creates a policy accepting plaintext passwords and hashes of the given schemes.
+    pub fn new(permitted: impl IntoIterator<Item = HashAlgorithm>) -> Self {
+        PasswordPolicy {
+            permitted: permitted.into_iter().collect(),
+            allow_plaintext: true,
+        }
+    }
+
+    /// Rejects plaintext passwords.
+    pub fn without_plaintext(mut self) -> Self {
+        self.allow_plaintext = false;
+        self
+    }
+
+    /// Checks that a hash has a permitted scheme and the form of that scheme.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::InvalidFieldValue` if it does not.
+    pub fn permits(&self, hashed: &HashedPassword) -> Result<(), SCIMError> {
+        if !self.permitted.contains(&hashed.algorithm) {
+            return Err(SCIMError::InvalidFieldValue(
+                format!("hash algorithm '{}' is not permitted", hashed.algorithm).into(),
+            ));
+        }
+        hashed.check_form()
+    }
+
+    /// Checks the credentials of a User resource: a plaintext `password` only if the policy allows it, a
+    /// hash only if [`PasswordPolicy::permits`] it, and not both.
+    ///
+    /// # Errors
+    ///
+    /// * `SCIMError::InvalidFieldValue` - If the credentials are not accepted.
+    /// * `SCIMError::DeserializationError` - If the extension is malformed.
+    pub fn check(&self, resource: &Value) -> Result<(), SCIMError> {
+        let plaintext = resource.get("password").is_some_and(|p| !p.is_null());
+        let extension = PasswordSync::from_resource(resource)?;
+        if plaintext && !self.allow_plaintext {
+            return Err(SCIMError::InvalidFieldValue(
+                "plaintext passwords are not accepted".into(),
+            ));
+        }
+        match extension {
+            Some(_) if plaintext => Err(SCIMError::InvalidFieldValue(
+                "password and hashedPassword are mutually exclusive".into(),
+            )),
+            Some(extension) => self.permits(&extension.hashed_password),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::schema_registry::SchemaRegistry;
+
+    const NT_HASH: &str = "8846f7eaee8fb117ad06bdd830b7586c";
+
+    #[test]
+    fn recognizes_schemes_and_redacts_hashes() {
+        let hashed = HashedPassword::parse("$argon2id$v=19$m=65536,t=3,p=4$c2FsdA$aGFzaA").unwrap();
+        assert_eq!(hashed.algorithm, HashAlgorithm::Argon2id);
+        assert!(!format!("{:?}", hashed).contains("aGFzaA"));
+        assert!(HashedPassword::parse(NT_HASH).is_err());
+        assert!(HashedPassword::new(HashAlgorithm::Nt, NT_HASH).is_ok());
+        assert!(HashedPassword::new(HashAlgorithm::Sha1, NT_HASH).is_err());
+        assert!(HashedPassword::new(HashAlgorithm::Bcrypt, "$2b$").is_err());
+        assert_eq!(
+            "PBKDF2-SHA256".parse::<HashAlgorithm>().unwrap(),
+            HashAlgorithm::Pbkdf2Sha256
+        );
+        assert_eq!(
+            json!(HashAlgorithm::Sha512Crypt),
+            json!(HashAlgorithm::Sha512Crypt.as_str())
+        );
+
+        let mut registry = SchemaRegistry::with_builtins();
+        registry.register_schema(PasswordSync::schema()).unwrap();
+        assert!(registry.schema(PASSWORD_SYNC_URN).is_some());
+    }
+
+    #[test]
+    fn enforces_the_permitted_schemes() {
+        let mut user = json!({"userName": "bjensen"});
+        PasswordSync {
+            hashed_password: HashedPassword::new(HashAlgorithm::Nt, NT_HASH).unwrap(),
+        }
+        .insert_into(&mut user)
+        .unwrap();
+        assert!(matches!(
+            PasswordPolicy::default().check(&user),
+            Err(SCIMError::InvalidFieldValue(_))
+        ));
+        let ad_sync = PasswordPolicy::new(vec![HashAlgorithm::Nt]).without_plaintext();
+        assert!(ad_sync.check(&user).is_ok());
+
+        user[PASSWORD_SYNC_URN]["hashedPassword"]["value"] = json!("not-a-hash");
+        assert!(ad_sync.check(&user).is_err());
+        user["password"] = json!("t1meMa$heen");
+        assert!(PasswordPolicy::default().check(&user).is_err());
+        let plaintext = json!({"userName": "bjensen", "password": "t1meMa$heen"});
+        assert!(PasswordPolicy::default().check(&plaintext).is_ok());
+        assert!(ad_sync.check(&plaintext).is_err());
+    }
+}
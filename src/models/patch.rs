@@ -0,0 +1,833 @@
+//! The paths of SCIM PATCH operations (RFC 7644 §3.5.2).
+//!
+//! A PATCH path names the attribute an operation targets. Besides an attribute path such as `name.givenName`,
+//! it can select values of a multi-valued attribute with a filter and name a sub-attribute of the selected
+//! values, as in `emails[type eq "work"].value`; it parses into an [`AttributePath`].
+//!
+//! The operations of a `PatchOp` message are applied to the JSON representation of a resource, so that
+//! [`User::apply_patch`](crate::models::user::User::apply_patch) and the other resources share the same
+//! semantics: attribute names are matched case-insensitively, `add` appends to multi-valued attributes
+//! values they do not have yet and merges complex values, only one value of a multi-valued attribute stays
+//! `primary`, and removing an extension by its schema URI also drops the URI from `schemas`. Resource types
+//! without a struct of their own are patched with [`apply_patch_json`].
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+use serde_json::{Map, Value};
+
+use crate::models::filter::{AttrPath, FilterExpr};
+use crate::models::others::{Op, PatchOp, PatchOperations};
+use crate::models::schema_registry::read_global;
+use crate::utils::error::SCIMError;
+
+/// The path of a PATCH operation: an attribute, optionally qualified with a schema URI, an optional filter
+/// selecting values of the attribute, and an optional sub-attribute.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::patch::AttributePath;
+///
+/// let path = AttributePath::parse(r#"emails[type eq "work"].value"#).unwrap();
+/// assert_eq!(path.attribute, "emails");
+/// assert_eq!(path.filter.unwrap().to_string(), r#"type eq "work""#);
+/// assert_eq!(path.sub_attribute.as_deref(), Some("value"));
+///
+/// let path = AttributePath::parse("name.givenName").unwrap();
+/// assert_eq!((path.attribute.as_str(), path.sub_attribute.as_deref()), ("name", Some("givenName")));
+/// assert!(path.filter.is_none());
+///
+/// assert!(AttributePath::parse("emails[type eq]").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributePath {
+    /// The schema URI the attribute belongs to, if the path is qualified with one.
+    pub uri: Option<String>,
+    pub attribute: String,
+    /// The filter selecting values of a multi-valued attribute, e.g. `type eq "work"`.
+    pub filter: Option<FilterExpr>,
+    pub sub_attribute: Option<String>,
+}
+
+impl AttributePath {
+    /// Parses a PATCH path.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::InvalidPath` if `path` is not a PATCH path, including when its filter is
+    /// malformed or itself contains a filtered attribute.
+    pub fn parse(path: &str) -> Result<Self, SCIMError> {
+        let invalid = |reason: &str| {
+            SCIMError::InvalidPath(format!("invalid path '{}': {}", path, reason).into())
+        };
+        let Some((attribute, rest)) = path.split_once('[') else {
+            let AttrPath {
+                uri,
+                name,
+                sub_attribute,
+            } = AttrPath::parse(path).map_err(|_| invalid("not an attribute path"))?;
+            return Ok(AttributePath {
+                uri,
+                attribute: name,
+                filter: None,
+                sub_attribute,
+            });
+        };
+        // Only a sub-attribute may follow the filter, so the last bracket closes it.
+        let (filter, sub_attribute) = rest
+            .rsplit_once(']')
+            .ok_or_else(|| invalid("missing ']'"))?;
+        let sub_attribute = match sub_attribute {
+            "" => None,
+            sub_attribute => Some(
+                sub_attribute
+                    .strip_prefix('.')
+                    .and_then(|name| AttrPath::parse(name).ok())
+                    .filter(|name| name.uri.is_none() && name.sub_attribute.is_none())
+                    .ok_or_else(|| invalid("expected a sub-attribute after ']'"))?
+                    .name,
+            ),
+        };
+        let attribute = AttrPath::parse(attribute)
+            .ok()
+            .filter(|attribute| attribute.sub_attribute.is_none())
+            .ok_or_else(|| invalid("expected an attribute name before '['"))?;
+        let filter = FilterExpr::parse(filter).map_err(|error| invalid(&error.to_string()))?;
+        if has_value_path(&filter) {
+            return Err(invalid("filters cannot be nested"));
+        }
+        Ok(AttributePath {
+            uri: attribute.uri,
+            attribute: attribute.name,
+            filter: Some(filter),
+            sub_attribute,
+        })
+    }
+
+    /// Returns the path of the targeted attribute without the filter, e.g. `emails.value` for
+    /// `emails[type eq "work"].value`.
+    pub fn target(&self) -> AttrPath {
+        AttrPath {
+            uri: self.uri.clone(),
+            name: self.attribute.clone(),
+            sub_attribute: self.sub_attribute.clone(),
+        }
+    }
+}
+
+impl fmt::Display for AttributePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(uri) = &self.uri {
+            write!(f, "{}:", uri)?;
+        }
+        f.write_str(&self.attribute)?;
+        if let Some(filter) = &self.filter {
+            write!(f, "[{}]", filter)?;
+        }
+        if let Some(sub_attribute) = &self.sub_attribute {
+            write!(f, ".{}", sub_attribute)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for AttributePath {
+    type Err = SCIMError;
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        AttributePath::parse(path)
+    }
+}
+
+/// Applies the operations of a `PatchOp` message to the JSON representation of a resource of any type.
+///
+/// Paths are resolved against the schemas the resource lists in `schemas`, as for
+/// [`User::apply_patch`](crate::models::user::User::apply_patch). Either every operation is applied or the
+/// resource is left unchanged.
+///
+/// # Errors
+///
+/// Returns `SCIMError::InvalidPath` if a path does not parse or filters the values of an attribute that is
+/// not multi-valued, `SCIMError::NoTarget` if a `remove` has no
+/// path or a filter matches no value to add to or replace, and `SCIMError::InvalidFieldValue` if an `add`
+/// or `replace` has no value.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::others::PatchOp;
+/// use scim_v2::models::patch::apply_patch_json;
+/// use serde_json::json;
+///
+/// let mut device = json!({"schemas": ["urn:example:scim:schemas:Device"], "id": "d1", "tags": ["lab"]});
+/// let patch = PatchOp::try_from(json!({
+///     "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+///     "Operations": [
+///         {"op": "add", "path": "tags", "value": ["lab", "eu"]},
+///         {"op": "replace", "path": "location.room", "value": "B12"}
+///     ]
+/// }))
+/// .unwrap();
+/// apply_patch_json(&mut device, &patch).unwrap();
+/// assert_eq!(device["tags"], json!(["lab", "eu"]));
+/// assert_eq!(device["location"], json!({"room": "B12"}));
+/// ```
+pub fn apply_patch_json(resource: &mut Value, patch: &PatchOp) -> Result<(), SCIMError> {
+    let mut patched = resource.clone();
+    apply(&mut patched, patch)?;
+    *resource = patched;
+    Ok(())
+}
+
+// Applies the operations of `patch` to the JSON representation of a resource, in order. An operation that
+// fails leaves the resource partly patched, so callers patch a copy.
+pub(crate) fn apply(resource: &mut Value, patch: &PatchOp) -> Result<(), SCIMError> {
+    patch
+        .operations
+        .iter()
+        .try_for_each(|operation| apply_operation(resource, operation))
+}
+
+// Checks that an operation can be applied to some resource, returning its parsed path.
+pub(crate) fn check(operation: &PatchOperations) -> Result<Option<AttributePath>, SCIMError> {
+    let path = operation.attribute_path()?;
+    match (operation.op, &path, &operation.value) {
+        (Op::Remove, None, _) => Err(SCIMError::NoTarget(
+            "a remove operation requires a path".into(),
+        )),
+        (Op::Remove, Some(_), _) | (_, Some(_), Some(_)) | (_, None, Some(Value::Object(_))) => {
+            Ok(path)
+        }
+        (op, Some(path), None) => Err(SCIMError::InvalidFieldValue(
+            format!("the {} operation on '{}' has no value", op, path).into(),
+        )
+        .with_attribute(path.to_string())),
+        (op, None, _) => Err(SCIMError::InvalidFieldValue(
+            format!("the {} operation without a path needs an object value", op).into(),
+        )),
+    }
+}
+
+// Checks `path` against the registered schemas: a filter needs a multi-valued attribute and a sub-attribute
+// one the attribute defines. Attributes no schema defines are not checked.
+pub(crate) fn check_schema(path: &AttributePath) -> Result<(), SCIMError> {
+    let registry = read_global();
+    let definitions: Vec<_> = registry
+        .schemas()
+        .iter()
+        .filter(|schema| {
+            path.uri
+                .as_deref()
+                .is_none_or(|uri| schema.id.eq_ignore_ascii_case(uri))
+        })
+        .filter_map(|schema| {
+            schema
+                .attributes
+                .iter()
+                .find(|attribute| attribute.name.eq_ignore_ascii_case(&path.attribute))
+        })
+        .collect();
+    if definitions.is_empty() {
+        return Ok(());
+    }
+    if path.filter.is_some() && !definitions.iter().any(|attribute| attribute.multi_valued) {
+        return Err(not_multi_valued(path));
+    }
+    if let Some(sub_attribute) = &path.sub_attribute {
+        let defined = definitions.iter().any(|attribute| {
+            attribute
+                .sub_attributes
+                .iter()
+                .flatten()
+                .any(|defined| defined.name.eq_ignore_ascii_case(sub_attribute))
+        });
+        if !defined {
+            return Err(SCIMError::InvalidPath(
+                format!(
+                    "'{}' names a sub-attribute '{}' does not define",
+                    path, path.attribute
+                )
+                .into(),
+            )
+            .with_attribute(path.to_string()));
+        }
+    }
+    Ok(())
+}
+
+fn apply_operation(resource: &mut Value, operation: &PatchOperations) -> Result<(), SCIMError> {
+    match (operation.op, check(operation)?, &operation.value) {
+        (Op::Remove, Some(path), value) => remove(resource, &path, value.as_ref()),
+        (op, Some(path), Some(value)) => write_path(resource, &path, value, op),
+        (op, None, Some(Value::Object(attributes))) => {
+            for (name, value) in attributes {
+                match value {
+                    Value::Object(extension) if is_schema(resource, name) => {
+                        for (attribute, value) in extension {
+                            let path = AttributePath {
+                                uri: Some(name.clone()),
+                                attribute: attribute.clone(),
+                                filter: None,
+                                sub_attribute: None,
+                            };
+                            write_path(resource, &path, value, op)?;
+                        }
+                    }
+                    value => write_path(resource, &AttributePath::parse(name)?, value, op)?,
+                }
+            }
+            Ok(())
+        }
+        _ => unreachable!("checked operations have a path or an object value"),
+    }
+}
+
+fn write_path(
+    resource: &mut Value,
+    path: &AttributePath,
+    value: &Value,
+    op: Op,
+) -> Result<(), SCIMError> {
+    let (path, multi_valued) = resolve(resource, path);
+    let selected = select(resource, &path)?;
+    if selected.as_ref().is_some_and(Vec::is_empty) {
+        return Err(
+            SCIMError::NoTarget(format!("no value matches '{}'", path).into())
+                .with_attribute(path.to_string()),
+        );
+    }
+    let Some(holder) = holder_mut(resource, path.uri.as_deref(), true)? else {
+        unreachable!("the holder of the attribute was created");
+    };
+    match (selected, &path.sub_attribute) {
+        (None, None) => write(holder, &path.attribute, value, op, multi_valued),
+        (None, Some(sub_attribute)) => {
+            let key = key_of(holder, &path.attribute).unwrap_or_else(|| path.attribute.clone());
+            let target = holder.entry(key).or_insert(Value::Null);
+            if target.is_null() {
+                *target = if multi_valued {
+                    Value::Array(vec![Value::Object(Map::new())])
+                } else {
+                    Value::Object(Map::new())
+                };
+            }
+            match target {
+                Value::Array(entries) => {
+                    for entry in entries {
+                        write(object_mut(entry, &path)?, sub_attribute, value, op, false);
+                    }
+                }
+                target => write(object_mut(target, &path)?, sub_attribute, value, op, false),
+            }
+        }
+        (Some(indexes), sub_attribute) => {
+            let entries = entries_mut(holder, &path.attribute);
+            for &index in &indexes {
+                let entry = &mut entries[index];
+                match (sub_attribute, value) {
+                    (Some(sub_attribute), value) => {
+                        write(object_mut(entry, &path)?, sub_attribute, value, op, false)
+                    }
+                    (None, Value::Object(attributes)) if op == Op::Add => {
+                        let entry = object_mut(entry, &path)?;
+                        for (name, value) in attributes {
+                            write(entry, name, value, op, false);
+                        }
+                    }
+                    (None, value) => *entry = value.clone(),
+                }
+            }
+            if indexes.iter().any(|&index| is_primary(&entries[index])) {
+                for (index, entry) in entries.iter_mut().enumerate() {
+                    if !indexes.contains(&index) {
+                        clear_primary(entry);
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// Sets the attribute `name` of `object` to `value`, or, for an `add` to a multi-valued attribute, appends the
+// values it does not have yet. Complex values are merged.
+fn write(object: &mut Map<String, Value>, name: &str, value: &Value, op: Op, multi_valued: bool) {
+    let value = match value {
+        Value::Array(_) => value.clone(),
+        value if multi_valued => Value::Array(vec![value.clone()]),
+        value => value.clone(),
+    };
+    let Some(existing) = key_of(object, name).and_then(|key| object.get_mut(&key)) else {
+        object.insert(name.to_string(), value);
+        return;
+    };
+    match (existing, value) {
+        (Value::Array(entries), Value::Array(added)) if op == Op::Add => {
+            let start = entries.len();
+            for value in added {
+                if !entries.contains(&value) {
+                    entries.push(value);
+                }
+            }
+            if entries[start..].iter().any(is_primary) {
+                entries[..start].iter_mut().for_each(clear_primary);
+            }
+        }
+        (Value::Object(existing), Value::Object(attributes)) => {
+            for (name, value) in &attributes {
+                write(existing, name, value, op, false);
+            }
+        }
+        (existing, value) => *existing = value,
+    }
+}
+
+fn remove(
+    resource: &mut Value,
+    path: &AttributePath,
+    value: Option<&Value>,
+) -> Result<(), SCIMError> {
+    if let Some(uri) = extension_uri(resource, path) {
+        remove_extension(resource, &uri);
+        return Ok(());
+    }
+    let (path, _) = resolve(resource, path);
+    let selected = select(resource, &path)?;
+    if selected.as_ref().is_some_and(Vec::is_empty) {
+        return Ok(());
+    }
+    let Some(holder) = holder_mut(resource, path.uri.as_deref(), false)? else {
+        return Ok(());
+    };
+    let Some(key) = key_of(holder, &path.attribute) else {
+        return Ok(());
+    };
+    match (selected, &path.sub_attribute, value) {
+        // Some clients name the values to remove instead of filtering them.
+        (None, None, Some(value)) if holder[&key].is_array() => {
+            let removed = match value {
+                Value::Array(values) => values.as_slice(),
+                value => core::slice::from_ref(value),
+            };
+            entries_mut(holder, &key).retain(|entry| !removed.iter().any(|r| same_value(entry, r)));
+        }
+        (None, None, _) => {
+            holder.remove(&key);
+        }
+        (None, Some(sub_attribute), _) => match &mut holder[&key] {
+            Value::Array(entries) => {
+                for entry in entries {
+                    remove_key(entry, sub_attribute);
+                }
+            }
+            target => remove_key(target, sub_attribute),
+        },
+        (Some(indexes), None, _) => {
+            let mut index = 0;
+            entries_mut(holder, &key).retain(|_| {
+                index += 1;
+                !indexes.contains(&(index - 1))
+            });
+        }
+        (Some(indexes), Some(sub_attribute), _) => {
+            let entries = entries_mut(holder, &key);
+            for index in indexes {
+                remove_key(&mut entries[index], sub_attribute);
+            }
+        }
+    }
+    // A multi-valued attribute without values is unassigned.
+    if holder
+        .get(&key)
+        .and_then(Value::as_array)
+        .is_some_and(Vec::is_empty)
+    {
+        holder.remove(&key);
+    }
+    Ok(())
+}
+
+// Spells the attribute and sub-attribute of `path` as the schemas of `resource` define them, and returns
+// whether the attribute is multi-valued.
+fn resolve(resource: &Value, path: &AttributePath) -> (AttributePath, bool) {
+    let mut path = path.clone();
+    let registry = read_global();
+    let schemas: Vec<&str> = match &path.uri {
+        Some(uri) => vec![uri],
+        None => resource["schemas"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .collect(),
+    };
+    let Some(attribute) = registry.attribute(schemas, &path.attribute) else {
+        return (path, false);
+    };
+    path.attribute.clone_from(&attribute.name);
+    if let Some(sub_attribute) = &mut path.sub_attribute {
+        let defined = attribute.sub_attributes.iter().flatten();
+        if let Some(defined) = defined
+            .clone()
+            .find(|s| s.name.eq_ignore_ascii_case(sub_attribute))
+        {
+            sub_attribute.clone_from(&defined.name);
+        }
+    }
+    (path, attribute.multi_valued)
+}
+
+// Returns the indexes of the values of a multi-valued attribute that the filter of `path` selects, or `None`
+// if it has no filter.
+fn select(resource: &Value, path: &AttributePath) -> Result<Option<Vec<usize>>, SCIMError> {
+    let Some(filter) = &path.filter else {
+        return Ok(None);
+    };
+    let parent = AttrPath {
+        uri: path.uri.clone(),
+        name: path.attribute.clone(),
+        sub_attribute: None,
+    };
+    let entries = match holder(resource, path.uri.as_deref())
+        .and_then(|holder| get(holder, &path.attribute))
+    {
+        None | Some(Value::Null) => &[][..],
+        Some(Value::Array(entries)) => entries.as_slice(),
+        Some(_) => return Err(not_multi_valued(path)),
+    };
+    Ok(Some(
+        entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| filter.matches_entry(resource, &parent, entry))
+            .map(|(index, _)| index)
+            .collect(),
+    ))
+}
+
+fn not_multi_valued(path: &AttributePath) -> SCIMError {
+    SCIMError::InvalidPath(
+        format!(
+            "'{}' filters the values of an attribute that is not multi-valued",
+            path
+        )
+        .into(),
+    )
+    .with_attribute(path.to_string())
+}
+
+// Returns the object holding the attributes of the schema `uri`: the resource itself for its base schema,
+// or the object of an extension.
+fn holder<'a>(resource: &'a Value, uri: Option<&str>) -> Option<&'a Map<String, Value>> {
+    let object = resource.as_object()?;
+    match uri {
+        Some(uri) if is_extension(object, uri) => get(object, uri)?.as_object(),
+        _ => Some(object),
+    }
+}
+
+// `holder` for writing. A missing extension object is created and its schema listed in `schemas` if
+// `create` is set.
+fn holder_mut<'a>(
+    resource: &'a mut Value,
+    uri: Option<&str>,
+    create: bool,
+) -> Result<Option<&'a mut Map<String, Value>>, SCIMError> {
+    let Value::Object(object) = resource else {
+        return Err(SCIMError::InvalidFieldValue(
+            "only objects can be patched".into(),
+        ));
+    };
+    let Some(uri) = uri.filter(|uri| is_extension(object, uri)) else {
+        return Ok(Some(object));
+    };
+    let key = match key_of(object, uri) {
+        Some(key) => key,
+        None if create => {
+            object.insert(uri.to_string(), Value::Object(Map::new()));
+            let schemas = object
+                .entry("schemas")
+                .or_insert_with(|| Value::Array(Vec::new()));
+            if let Value::Array(schemas) = schemas {
+                schemas.push(Value::String(uri.to_string()));
+            }
+            uri.to_string()
+        }
+        None => return Ok(None),
+    };
+    match object.get_mut(&key) {
+        Some(Value::Object(extension)) => Ok(Some(extension)),
+        _ => Err(SCIMError::InvalidPath(
+            format!("'{}' is not an extension object", uri).into(),
+        )),
+    }
+}
+
+// Returns the URI of the extension `path` names as a whole, as in a `remove` of
+// `urn:ietf:params:scim:schemas:extension:enterprise:2.0:User`, which parses as the attribute `User`.
+fn extension_uri(resource: &Value, path: &AttributePath) -> Option<String> {
+    if path.filter.is_some() || path.sub_attribute.is_some() {
+        return None;
+    }
+    let uri = format!("{}:{}", path.uri.as_deref()?, path.attribute);
+    let named = is_schema(resource, &uri) && !read_global().is_base_schema(&uri);
+    named.then_some(uri)
+}
+
+// Removes an extension object together with its schema in `schemas`.
+fn remove_extension(resource: &mut Value, uri: &str) {
+    let Value::Object(object) = resource else {
+        return;
+    };
+    if let Some(key) = key_of(object, uri) {
+        object.remove(&key);
+    }
+    if let Some(Value::Array(schemas)) = object.get_mut("schemas") {
+        schemas.retain(|schema| !schema.as_str().is_some_and(|s| s.eq_ignore_ascii_case(uri)));
+    }
+}
+
+fn is_extension(object: &Map<String, Value>, uri: &str) -> bool {
+    key_of(object, uri).is_some() || !read_global().is_base_schema(uri)
+}
+
+// Returns `true` if the member `name` of a value without a path names a schema extension rather than an
+// attribute.
+fn is_schema(resource: &Value, name: &str) -> bool {
+    let listed = resource["schemas"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .any(|schema| schema.eq_ignore_ascii_case(name));
+    listed || read_global().schema(name).is_some()
+}
+
+fn entries_mut<'a>(holder: &'a mut Map<String, Value>, name: &str) -> &'a mut Vec<Value> {
+    match key_of(holder, name).and_then(|key| holder.get_mut(&key)) {
+        Some(Value::Array(entries)) => entries,
+        _ => unreachable!("'{}' was selected as a multi-valued attribute", name),
+    }
+}
+
+fn object_mut<'a>(
+    value: &'a mut Value,
+    path: &AttributePath,
+) -> Result<&'a mut Map<String, Value>, SCIMError> {
+    match value {
+        Value::Object(object) => Ok(object),
+        _ => Err(SCIMError::InvalidPath(
+            format!("'{}' is not an attribute of a complex value", path).into(),
+        )
+        .with_attribute(path.to_string())),
+    }
+}
+
+fn key_of(object: &Map<String, Value>, name: &str) -> Option<String> {
+    object
+        .keys()
+        .find(|key| key.eq_ignore_ascii_case(name))
+        .cloned()
+}
+
+fn get<'a>(object: &'a Map<String, Value>, name: &str) -> Option<&'a Value> {
+    object
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value)
+}
+
+fn remove_key(value: &mut Value, name: &str) {
+    if let Value::Object(object) = value {
+        if let Some(key) = key_of(object, name) {
+            object.remove(&key);
+        }
+    }
+}
+
+fn is_primary(value: &Value) -> bool {
+    value.as_object().and_then(|object| get(object, "primary")) == Some(&Value::Bool(true))
+}
+
+fn clear_primary(value: &mut Value) {
+    if let Value::Object(object) = value {
+        if let Some(key) = key_of(object, "primary") {
+            object.insert(key, Value::Bool(false));
+        }
+    }
+}
+
+// Returns `true` if `entry` is the value `removed` names: the same value, or a complex value with the same
+// `value` sub-attribute.
+fn same_value(entry: &Value, removed: &Value) -> bool {
+    let value = |v: &Value| {
+        v.as_object()
+            .and_then(|object| get(object, "value"))
+            .cloned()
+    };
+    entry == removed || value(removed).is_some_and(|removed| value(entry) == Some(removed))
+}
+
+fn has_value_path(filter: &FilterExpr) -> bool {
+    match filter {
+        FilterExpr::ValuePath(..) => true,
+        FilterExpr::And(left, right) | FilterExpr::Or(left, right) => {
+            has_value_path(left) || has_value_path(right)
+        }
+        FilterExpr::Not(filter) => has_value_path(filter),
+        FilterExpr::Present(_) | FilterExpr::Compare(..) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn patch(operations: Value) -> PatchOp {
+        serde_json::from_value(json!({
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": operations
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn applies_operations_to_json() {
+        let mut user = json!({
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+            "userName": "bjensen",
+            "name": {"givenName": "Barbara", "familyName": "Jensen"},
+            "emails": [
+                {"value": "bjensen@example.com", "type": "work", "primary": true},
+                {"value": "babs@jensen.org", "type": "home"}
+            ],
+            "phoneNumbers": [{"value": "555-555-8377", "type": "work"}]
+        });
+        let operations = patch(json!([
+            {"op": "add", "path": "NAME", "value": {"middleName": "Jane"}},
+            {"op": "add", "path": "emails", "value": [
+                {"value": "babs@jensen.org", "type": "home"},
+                {"value": "bj@example.org", "type": "other", "primary": true}
+            ]},
+            {"op": "replace", "path": "emails[type eq \"HOME\"].display", "value": "Babs"},
+            {"op": "remove", "path": "phoneNumbers[type eq \"work\"]"},
+            {"op": "add", "path": "nickname", "value": "Babs"},
+            {"op": "replace", "value": {
+                "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User": {"employeeNumber": "701984"},
+                "title": "Tour Guide"
+            }},
+            {"op": "remove", "path": "name.givenName"}
+        ]));
+        apply(&mut user, &operations).unwrap();
+        assert_eq!(
+            user,
+            json!({
+                "schemas": [
+                    "urn:ietf:params:scim:schemas:core:2.0:User",
+                    "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User"
+                ],
+                "userName": "bjensen",
+                "name": {"familyName": "Jensen", "middleName": "Jane"},
+                "emails": [
+                    {"value": "bjensen@example.com", "type": "work", "primary": false},
+                    {"value": "babs@jensen.org", "type": "home", "display": "Babs"},
+                    {"value": "bj@example.org", "type": "other", "primary": true}
+                ],
+                "nickName": "Babs",
+                "title": "Tour Guide",
+                "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User": {"employeeNumber": "701984"}
+            })
+        );
+
+        for (operation, expected) in [
+            (json!({"op": "remove"}), "noTarget"),
+            (
+                json!({"op": "replace", "path": "emails[type eq \"pager\"].value", "value": "x"}),
+                "noTarget",
+            ),
+            (json!({"op": "add", "path": "title"}), "invalidValue"),
+            (json!({"op": "add", "value": "x"}), "invalidValue"),
+            (
+                json!({"op": "remove", "path": "emails[type]"}),
+                "invalidPath",
+            ),
+            (
+                json!({"op": "add", "path": "userName.x", "value": "x"}),
+                "invalidPath",
+            ),
+            (
+                json!({"op": "replace", "path": "name[givenName eq \"Barbara\"].familyName", "value": "x"}),
+                "invalidPath",
+            ),
+        ] {
+            let error = apply(&mut user, &patch(json!([operation]))).unwrap_err();
+            let response = crate::models::errors::ScimHttpError::from(&error);
+            assert_eq!(
+                response.scim_type.as_deref(),
+                Some(expected),
+                "{}",
+                operation
+            );
+        }
+
+        // Value filters on attributes that are not multi-valued select nothing to remove from.
+        for path in [
+            r#"name[givenName eq "Barbara"]"#,
+            r#"userName[value eq "b"]"#,
+        ] {
+            let before = user.clone();
+            let remove = patch(json!([{"op": "remove", "path": path}]));
+            assert!(matches!(
+                apply_patch_json(&mut user, &remove),
+                Err(SCIMError::InvalidPath(_))
+            ));
+            assert_eq!(user, before);
+        }
+    }
+
+    #[test]
+    fn parses_qualified_and_filtered_paths() {
+        let path = AttributePath::parse(
+            r#"urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:manager.value"#,
+        )
+        .unwrap();
+        assert_eq!(
+            path.uri.as_deref(),
+            Some("urn:ietf:params:scim:schemas:extension:enterprise:2.0:User")
+        );
+        assert_eq!(path.attribute, "manager");
+        assert_eq!(path.sub_attribute.as_deref(), Some("value"));
+
+        let text = r#"urn:ietf:params:scim:schemas:core:2.0:User:addresses[type eq "a]b" and not (primary eq true)].locality"#;
+        let path = AttributePath::parse(text).unwrap();
+        assert_eq!(path.attribute, "addresses");
+        assert_eq!(path.sub_attribute.as_deref(), Some("locality"));
+        assert_eq!(path.to_string(), text);
+
+        for (path, reason) in [
+            ("", "not an attribute path"),
+            ("name.givenName.x", "not an attribute path"),
+            (r#"emails[type eq "work""#, "missing ']'"),
+            (r#"emails[type eq "work"]value"#, "expected a sub-attribute"),
+            (
+                r#"emails[type eq "work"].value.x"#,
+                "expected a sub-attribute",
+            ),
+            (r#"name.x[type eq "work"]"#, "expected an attribute name"),
+            ("emails[]", "empty filter"),
+            (r#"emails[x[type eq "work"]]"#, "cannot be nested"),
+        ] {
+            let Err(SCIMError::InvalidPath(detail)) = AttributePath::parse(path) else {
+                panic!("{} parsed", path);
+            };
+            assert!(detail.to_string().contains(reason), "{}: {}", path, detail);
+        }
+    }
+}
@@ -0,0 +1,677 @@
+//! Protobuf messages for the `User` and `Group` resources and the `ListResponse` message.
+//!
+//! The messages are [prost] types matching `proto/scim.proto` (package `scim.v2`), so SCIM data can travel
+//! over gRPC or other protobuf pipelines without bespoke mapping code. Each message converts to and from its
+//! model with `From`/`Into`.
+//!
+//! Protobuf does not distinguish an empty repeated field from an absent one: empty multi-valued attributes
+//! convert to `None`.
+//!
+//! # Examples
+//!
+//! ```
+//! use prost::Message;
+//! use scim_v2::models::protobuf;
+//! use scim_v2::models::user::User;
+//!
+//! let user = User {
+//!     user_name: "bjensen".to_string(),
+//!     active: Some(true),
+//!     ..User::default()
+//! };
+//! let bytes = protobuf::User::from(user).encode_to_vec();
+//!
+//! let user = User::from(protobuf::User::decode(bytes.as_slice()).unwrap());
+//! assert_eq!(user.user_name, "bjensen");
+//! assert_eq!(user.active, Some(true));
+//! ```
+
+use crate::models::resource_id::ResourceId;
+use crate::models::{enterprise_user, group, others, scim_schema, user};
+use crate::utils::error::SCIMError;
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Meta {
+    #[prost(string, optional, tag = "1")]
+    pub resource_type: Option<String>,
+    #[prost(string, optional, tag = "2")]
+    pub created: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    pub last_modified: Option<String>,
+    #[prost(string, optional, tag = "4")]
+    pub version: Option<String>,
+    #[prost(string, optional, tag = "5")]
+    pub location: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Name {
+    #[prost(string, optional, tag = "1")]
+    pub formatted: Option<String>,
+    #[prost(string, optional, tag = "2")]
+    pub family_name: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    pub given_name: Option<String>,
+    #[prost(string, optional, tag = "4")]
+    pub middle_name: Option<String>,
+    #[prost(string, optional, tag = "5")]
+    pub honorific_prefix: Option<String>,
+    #[prost(string, optional, tag = "6")]
+    pub honorific_suffix: Option<String>,
+}
+
+/// An entry of a multi-valued attribute such as `emails`, `phoneNumbers` or `roles`.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct MultiValuedAttribute {
+    #[prost(string, optional, tag = "1")]
+    pub value: Option<String>,
+    #[prost(string, optional, tag = "2")]
+    pub display: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    pub r#type: Option<String>,
+    #[prost(bool, optional, tag = "4")]
+    pub primary: Option<bool>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Address {
+    #[prost(string, optional, tag = "1")]
+    pub formatted: Option<String>,
+    #[prost(string, optional, tag = "2")]
+    pub street_address: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    pub locality: Option<String>,
+    #[prost(string, optional, tag = "4")]
+    pub region: Option<String>,
+    #[prost(string, optional, tag = "5")]
+    pub postal_code: Option<String>,
+    #[prost(string, optional, tag = "6")]
+    pub country: Option<String>,
+    #[prost(string, optional, tag = "7")]
+    pub r#type: Option<String>,
+}
+
+/// A reference to another resource: a user's group, a group's member, or a manager.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Reference {
+    #[prost(string, optional, tag = "1")]
+    pub value: Option<String>,
+    #[prost(string, optional, tag = "2")]
+    pub r#ref: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    pub display: Option<String>,
+    #[prost(string, optional, tag = "4")]
+    pub r#type: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct EnterpriseUser {
+    #[prost(string, optional, tag = "1")]
+    pub employee_number: Option<String>,
+    #[prost(string, optional, tag = "2")]
+    pub cost_center: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    pub organization: Option<String>,
+    #[prost(string, optional, tag = "4")]
+    pub division: Option<String>,
+    #[prost(string, optional, tag = "5")]
+    pub department: Option<String>,
+    #[prost(message, optional, tag = "6")]
+    pub manager: Option<Reference>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct User {
+    #[prost(string, repeated, tag = "1")]
+    pub schemas: Vec<String>,
+    #[prost(string, optional, tag = "2")]
+    pub id: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    pub external_id: Option<String>,
+    #[prost(string, tag = "4")]
+    pub user_name: String,
+    #[prost(message, optional, tag = "5")]
+    pub name: Option<Name>,
+    #[prost(string, optional, tag = "6")]
+    pub display_name: Option<String>,
+    #[prost(string, optional, tag = "7")]
+    pub nick_name: Option<String>,
+    #[prost(string, optional, tag = "8")]
+    pub profile_url: Option<String>,
+    #[prost(string, optional, tag = "9")]
+    pub title: Option<String>,
+    #[prost(string, optional, tag = "10")]
+    pub user_type: Option<String>,
+    #[prost(string, optional, tag = "11")]
+    pub preferred_language: Option<String>,
+    #[prost(string, optional, tag = "12")]
+    pub locale: Option<String>,
+    #[prost(string, optional, tag = "13")]
+    pub timezone: Option<String>,
+    #[prost(bool, optional, tag = "14")]
+    pub active: Option<bool>,
+    #[prost(string, optional, tag = "15")]
+    pub password: Option<String>,
+    #[prost(message, repeated, tag = "16")]
+    pub emails: Vec<MultiValuedAttribute>,
+    #[prost(message, repeated, tag = "17")]
+    pub addresses: Vec<Address>,
+    #[prost(message, repeated, tag = "18")]
+    pub phone_numbers: Vec<MultiValuedAttribute>,
+    #[prost(message, repeated, tag = "19")]
+    pub ims: Vec<MultiValuedAttribute>,
+    #[prost(message, repeated, tag = "20")]
+    pub photos: Vec<MultiValuedAttribute>,
+    #[prost(message, repeated, tag = "21")]
+    pub groups: Vec<Reference>,
+    #[prost(message, repeated, tag = "22")]
+    pub entitlements: Vec<MultiValuedAttribute>,
+    #[prost(message, repeated, tag = "23")]
+    pub roles: Vec<MultiValuedAttribute>,
+    #[prost(message, repeated, tag = "24")]
+    pub x509_certificates: Vec<MultiValuedAttribute>,
+    #[prost(message, optional, tag = "25")]
+    pub meta: Option<Meta>,
+    #[prost(message, optional, tag = "26")]
+    pub enterprise_user: Option<EnterpriseUser>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Group {
+    #[prost(string, repeated, tag = "1")]
+    pub schemas: Vec<String>,
+    #[prost(string, optional, tag = "2")]
+    pub id: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    pub external_id: Option<String>,
+    #[prost(string, tag = "4")]
+    pub display_name: String,
+    #[prost(message, repeated, tag = "5")]
+    pub members: Vec<Reference>,
+    #[prost(message, optional, tag = "6")]
+    pub meta: Option<Meta>,
+}
+
+/// A resource of a [`ListResponse`].
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Resource {
+    #[prost(oneof = "resource::Resource", tags = "1, 2")]
+    pub resource: Option<resource::Resource>,
+}
+
+/// The variants of [`Resource`].
+pub mod resource {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Resource {
+        #[prost(message, tag = "1")]
+        User(Box<super::User>),
+        #[prost(message, tag = "2")]
+        Group(Box<super::Group>),
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ListResponse {
+    #[prost(string, repeated, tag = "1")]
+    pub schemas: Vec<String>,
+    #[prost(int64, tag = "2")]
+    pub total_results: i64,
+    #[prost(int64, tag = "3")]
+    pub items_per_page: i64,
+    #[prost(int64, tag = "4")]
+    pub start_index: i64,
+    #[prost(message, repeated, tag = "5")]
+    pub resources: Vec<Resource>,
+    #[prost(string, optional, tag = "6")]
+    pub next_cursor: Option<String>,
+    #[prost(string, optional, tag = "7")]
+    pub previous_cursor: Option<String>,
+}
+
+fn to_repeated<T, U: From<T>>(values: Option<impl IntoIterator<Item = T>>) -> Vec<U> {
+    values.into_iter().flatten().map(U::from).collect()
+}
+
+fn from_repeated<T, U: From<T>, C: FromIterator<U>>(values: Vec<T>) -> Option<C> {
+    (!values.is_empty()).then(|| values.into_iter().map(U::from).collect())
+}
+
+fn to_id(id: Option<ResourceId>) -> Option<String> {
+    id.map(ResourceId::into_inner)
+}
+
+macro_rules! impl_multi_valued_conversions {
+    ($($ty:ty),+) => {
+        $(
+            impl From<$ty> for MultiValuedAttribute {
+                fn from(value: $ty) -> Self {
+                    MultiValuedAttribute {
+                        value: value.value,
+                        display: value.display,
+                        r#type: value.r#type,
+                        primary: value.primary,
+                    }
+                }
+            }
+
+            impl From<MultiValuedAttribute> for $ty {
+                fn from(value: MultiValuedAttribute) -> Self {
+                    Self {
+                        value: value.value,
+                        display: value.display,
+                        r#type: value.r#type,
+                        primary: value.primary,
+                    }
+                }
+            }
+        )+
+    };
+}
+
+impl_multi_valued_conversions!(
+    user::Email,
+    user::PhoneNumber,
+    user::Im,
+    user::Photo,
+    user::Entitlement,
+    user::Role,
+    user::X509Certificate
+);
+
+impl From<scim_schema::Meta> for Meta {
+    fn from(meta: scim_schema::Meta) -> Self {
+        Meta {
+            resource_type: meta.resource_type,
+            created: meta.created,
+            last_modified: meta.last_modified,
+            version: meta.version,
+            location: meta.location,
+        }
+    }
+}
+
+impl From<Meta> for scim_schema::Meta {
+    fn from(meta: Meta) -> Self {
+        scim_schema::Meta {
+            resource_type: meta.resource_type,
+            created: meta.created,
+            last_modified: meta.last_modified,
+            version: meta.version,
+            location: meta.location,
+        }
+    }
+}
+
+impl From<user::Name> for Name {
+    fn from(name: user::Name) -> Self {
+        Name {
+            formatted: name.formatted,
+            family_name: name.family_name,
+            given_name: name.given_name,
+            middle_name: name.middle_name,
+            honorific_prefix: name.honorific_prefix,
+            honorific_suffix: name.honorific_suffix,
+        }
+    }
+}
+
+impl From<Name> for user::Name {
+    fn from(name: Name) -> Self {
+        user::Name {
+            formatted: name.formatted,
+            family_name: name.family_name,
+            given_name: name.given_name,
+            middle_name: name.middle_name,
+            honorific_prefix: name.honorific_prefix,
+            honorific_suffix: name.honorific_suffix,
+        }
+    }
+}
+
+impl From<user::Address> for Address {
+    fn from(address: user::Address) -> Self {
+        Address {
+            formatted: address.formatted,
+            street_address: address.street_address,
+            locality: address.locality,
+            region: address.region,
+            postal_code: address.postal_code,
+            country: address.country,
+            r#type: address.r#type,
+        }
+    }
+}
+
+impl From<Address> for user::Address {
+    fn from(address: Address) -> Self {
+        user::Address {
+            formatted: address.formatted,
+            street_address: address.street_address,
+            locality: address.locality,
+            region: address.region,
+            postal_code: address.postal_code,
+            country: address.country,
+            r#type: address.r#type,
+        }
+    }
+}
+
+impl From<user::Group> for Reference {
+    fn from(group: user::Group) -> Self {
+        Reference {
+            value: to_id(group.value),
+            r#ref: group.r#ref,
+            display: group.display,
+            r#type: group.r#type,
+        }
+    }
+}
+
+impl From<Reference> for user::Group {
+    fn from(reference: Reference) -> Self {
+        user::Group {
+            value: reference.value.map(ResourceId::new),
+            r#ref: reference.r#ref,
+            display: reference.display,
+            r#type: reference.r#type,
+        }
+    }
+}
+
+impl From<group::Member> for Reference {
+    fn from(member: group::Member) -> Self {
+        Reference {
+            value: to_id(member.value),
+            r#ref: member.r#ref,
+            display: member.display,
+            r#type: member.r#type,
+        }
+    }
+}
+
+impl From<Reference> for group::Member {
+    fn from(reference: Reference) -> Self {
+        group::Member {
+            value: reference.value.map(ResourceId::new),
+            r#ref: reference.r#ref,
+            r#type: reference.r#type,
+            display: reference.display,
+        }
+    }
+}
+
+impl From<enterprise_user::EnterpriseUser> for EnterpriseUser {
+    fn from(extension: enterprise_user::EnterpriseUser) -> Self {
+        EnterpriseUser {
+            employee_number: extension.employee_number,
+            cost_center: extension.cost_center,
+            organization: extension.organization,
+            division: extension.division,
+            department: extension.department,
+            manager: extension.manager.map(|manager| Reference {
+                value: to_id(manager.value),
+                r#ref: manager.r#ref,
+                display: manager.display_name,
+                r#type: None,
+            }),
+        }
+    }
+}
+
+impl From<EnterpriseUser> for enterprise_user::EnterpriseUser {
+    fn from(extension: EnterpriseUser) -> Self {
+        enterprise_user::EnterpriseUser {
+            employee_number: extension.employee_number,
+            cost_center: extension.cost_center,
+            organization: extension.organization,
+            division: extension.division,
+            department: extension.department,
+            manager: extension.manager.map(|manager| enterprise_user::Manager {
+                value: manager.value.map(ResourceId::new),
+                r#ref: manager.r#ref,
+                display_name: manager.display,
+            }),
+        }
+    }
+}
+
+impl From<user::User> for User {
+    fn from(user: user::User) -> Self {
+        User {
+            schemas: user.schemas.into_iter().collect(),
+            id: to_id(user.id),
+            external_id: user.external_id,
+            user_name: user.user_name,
+            name: user.name.map(Name::from),
+            display_name: user.display_name,
+            nick_name: user.nick_name,
+            profile_url: user.profile_url,
+            title: user.title,
+            user_type: user.user_type,
+            preferred_language: user.preferred_language,
+            locale: user.locale,
+            timezone: user.timezone,
+            active: user.active,
+            password: user.password,
+            emails: to_repeated(user.emails),
+            addresses: to_repeated(user.addresses),
+            phone_numbers: to_repeated(user.phone_numbers),
+            ims: to_repeated(user.ims),
+            photos: to_repeated(user.photos),
+            groups: to_repeated(user.groups),
+            entitlements: to_repeated(user.entitlements),
+            roles: to_repeated(user.roles),
+            x509_certificates: to_repeated(user.x509_certificates),
+            meta: user.meta.map(Meta::from),
+            enterprise_user: user.enterprise_user.map(EnterpriseUser::from),
+        }
+    }
+}
+
+impl From<User> for user::User {
+    fn from(user: User) -> Self {
+        user::User {
+            schemas: user.schemas.into_iter().collect(),
+            id: user.id.map(ResourceId::new),
+            external_id: user.external_id,
+            user_name: user.user_name,
+            name: user.name.map(user::Name::from),
+            display_name: user.display_name,
+            nick_name: user.nick_name,
+            profile_url: user.profile_url,
+            title: user.title,
+            user_type: user.user_type,
+            preferred_language: user.preferred_language,
+            locale: user.locale,
+            timezone: user.timezone,
+            active: user.active,
+            password: user.password,
+            emails: from_repeated(user.emails),
+            addresses: from_repeated(user.addresses),
+            phone_numbers: from_repeated(user.phone_numbers),
+            ims: from_repeated(user.ims),
+            photos: from_repeated(user.photos),
+            groups: from_repeated(user.groups),
+            entitlements: from_repeated(user.entitlements),
+            roles: from_repeated(user.roles),
+            x509_certificates: from_repeated(user.x509_certificates),
+            meta: user.meta.map(scim_schema::Meta::from),
+            enterprise_user: user
+                .enterprise_user
+                .map(enterprise_user::EnterpriseUser::from),
+        }
+    }
+}
+
+impl From<group::Group> for Group {
+    fn from(group: group::Group) -> Self {
+        Group {
+            schemas: group.schemas.into_iter().collect(),
+            id: to_id(group.id),
+            external_id: group.external_id,
+            display_name: group.display_name,
+            members: to_repeated(group.members),
+            meta: group.meta.map(Meta::from),
+        }
+    }
+}
+
+impl From<Group> for group::Group {
+    fn from(group: Group) -> Self {
+        group::Group {
+            schemas: group.schemas.into_iter().collect(),
+            id: group.id.map(ResourceId::new),
+            external_id: group.external_id,
+            display_name: group.display_name,
+            members: from_repeated(group.members),
+            meta: group.meta.map(scim_schema::Meta::from),
+        }
+    }
+}
+
+/// Converts a `ListResponse` into its protobuf message.
+///
+/// # Errors
+///
+/// Returns `SCIMError::InvalidFieldValue` if the response holds resources other than users and groups, which
+/// the protobuf definitions do not cover.
+impl TryFrom<others::ListResponse> for ListResponse {
+    type Error = SCIMError;
+
+    fn try_from(response: others::ListResponse) -> Result<Self, Self::Error> {
+        let resources = response
+            .resources
+            .into_iter()
+            .map(|resource| {
+                let resource = match resource {
+                    others::Resource::User(user) => {
+                        resource::Resource::User(Box::new(User::from(*user)))
+                    }
+                    others::Resource::Group(group) => {
+                        resource::Resource::Group(Box::new(Group::from(*group)))
+                    }
+                    others::Resource::Schema(_) | others::Resource::ResourceType(_) => {
+                        return Err(SCIMError::InvalidFieldValue(
+                            "only User and Group resources have a protobuf representation"
+                                .to_string()
+                                .into(),
+                        ));
+                    }
+                };
+                Ok(Resource {
+                    resource: Some(resource),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(ListResponse {
+            schemas: response.schemas,
+            total_results: response.total_results,
+            items_per_page: response.items_per_page,
+            start_index: response.start_index,
+            resources,
+            next_cursor: response.next_cursor,
+            previous_cursor: response.previous_cursor,
+        })
+    }
+}
+
+/// Converts a protobuf message into a `ListResponse`. Resources without a value are skipped.
+impl From<ListResponse> for others::ListResponse {
+    fn from(response: ListResponse) -> Self {
+        others::ListResponse {
+            schemas: response.schemas,
+            total_results: response.total_results,
+            items_per_page: response.items_per_page,
+            start_index: response.start_index,
+            resources: response
+                .resources
+                .into_iter()
+                .filter_map(|resource| match resource.resource? {
+                    resource::Resource::User(user) => {
+                        Some(others::Resource::User(Box::new(user::User::from(*user))))
+                    }
+                    resource::Resource::Group(group) => Some(others::Resource::Group(Box::new(
+                        group::Group::from(*group),
+                    ))),
+                })
+                .collect(),
+            next_cursor: response.next_cursor,
+            previous_cursor: response.previous_cursor,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prost::Message;
+
+    use super::*;
+    use crate::values;
+
+    #[test]
+    fn list_responses_round_trip_through_bytes() {
+        let user = user::User {
+            id: Some(ResourceId::new("2819c223")),
+            user_name: "bjensen".to_string(),
+            emails: Some(values![user::Email {
+                value: Some("bjensen@example.com".to_string()),
+                primary: Some(true),
+                ..user::Email::default()
+            }]),
+            enterprise_user: Some(enterprise_user::EnterpriseUser {
+                department: Some("Tour Operations".to_string()),
+                ..enterprise_user::EnterpriseUser::default()
+            }),
+            ..user::User::default()
+        };
+        let group = group::Group {
+            display_name: "Tour Guides".to_string(),
+            members: Some(vec![group::Member {
+                value: Some(ResourceId::new("2819c223")),
+                ..group::Member::default()
+            }]),
+            ..group::Group::default()
+        };
+        let response = others::ListResponse::from_page(
+            vec![
+                others::Resource::User(Box::new(user)),
+                others::Resource::Group(Box::new(group)),
+            ],
+            2,
+            1,
+        )
+        .unwrap();
+
+        let bytes = ListResponse::try_from(response).unwrap().encode_to_vec();
+        let response = others::ListResponse::from(ListResponse::decode(bytes.as_slice()).unwrap());
+        assert_eq!(response.total_results, 2);
+        let others::Resource::User(user) = &response.resources[0] else {
+            panic!("expected a user");
+        };
+        assert_eq!(user.id.as_ref().unwrap(), "2819c223");
+        assert_eq!(user.emails.as_ref().unwrap()[0].primary, Some(true));
+        assert_eq!(
+            user.enterprise_user.as_ref().unwrap().department.as_deref(),
+            Some("Tour Operations")
+        );
+        assert!(user.phone_numbers.is_none());
+        let others::Resource::Group(group) = &response.resources[1] else {
+            panic!("expected a group");
+        };
+        assert_eq!(group.members.as_ref().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn rejects_resources_without_a_protobuf_representation() {
+        let response = others::ListResponse::from_page(
+            vec![others::Resource::ResourceType(Box::default())],
+            1,
+            1,
+        )
+        .unwrap();
+        assert!(matches!(
+            ListResponse::try_from(response),
+            Err(SCIMError::InvalidFieldValue(_))
+        ));
+    }
+}
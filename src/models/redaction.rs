@@ -0,0 +1,191 @@
+//! Removing and masking the attributes a service provider must not reveal.
+//!
+//! RFC 7643 §7 has service providers never return attributes whose `returned` characteristic is `never`,
+//! such as a User's `password`, and deployments often add attributes of their own, such as national
+//! identification numbers or custom extension secrets. A [`RedactionPolicy`] collects both and applies
+//! them to a resource just before it is serialized; the
+//! [`RedactingProvider`](crate::provider::redacting::RedactingProvider) decorator applies it to every
+//! resource a [`ResourceProvider`](crate::provider::resource_provider::ResourceProvider) returns.
+
+use serde_json::Value;
+
+use crate::models::anonymize::{mask, strings};
+use crate::models::attribute_policy::{for_each_mut, keys, remove};
+use crate::models::schema_registry::SchemaRegistry;
+
+/// What a [`RedactionPolicy`] does with an attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Redaction {
+    /// Removes the attribute.
+    Strip,
+    /// Masks every string below the attribute, as [`Treatment::Mask`](crate::models::anonymize::Treatment)
+    /// does.
+    Mask,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Rule {
+    // The resource type the rule applies to, or `None` for every resource type.
+    resource_type: Option<String>,
+    path: String,
+    redaction: Redaction,
+}
+
+/// The attributes to remove or mask from resources before they leave the service provider.
+///
+/// Attributes are named the way an [`AttributePolicy`](crate::models::attribute_policy::AttributePolicy)
+/// names them, e.g. `password`, `name.familyName` or
+/// `urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:employeeNumber`.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::redaction::RedactionPolicy;
+/// use scim_v2::models::schema_registry::SchemaRegistry;
+/// use serde_json::json;
+///
+/// let policy = RedactionPolicy::from_registry(&SchemaRegistry::with_builtins())
+///     .mask("phoneNumbers.value")
+///     .strip_for("Group", "externalId");
+///
+/// let mut user = json!({
+///     "userName": "bjensen",
+///     "password": "t1meMa$heen",
+///     "externalId": "701984",
+///     "phoneNumbers": [{"value": "555-555-8377", "type": "work"}]
+/// });
+/// policy.apply("User", &mut user);
+/// assert_eq!(
+///     user,
+///     json!({"userName": "bjensen", "externalId": "701984", "phoneNumbers": [{"value": "***-***-**77", "type": "work"}]})
+/// );
+///
+/// let mut group = json!({"displayName": "Tour Guides", "externalId": "g1"});
+/// policy.apply("Group", &mut group);
+/// assert_eq!(group, json!({"displayName": "Tour Guides"}));
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RedactionPolicy {
+    rules: Vec<Rule>,
+}
+
+impl RedactionPolicy {
+    /// Creates a policy that redacts nothing.
+    pub fn new() -> Self {
+        RedactionPolicy::default()
+    }
+
+    /// Creates a policy that strips, from the resources of each resource type registered with `registry`,
+    /// the attributes and sub-attributes its schemas declare `returned: never`.
+    pub fn from_registry(registry: &SchemaRegistry) -> Self {
+        let mut policy = RedactionPolicy::new();
+        for resource_type in registry.resource_types() {
+            let Some(schema) = registry.merged_schema(&resource_type.name) else {
+                continue;
+            };
+            for merged in schema.attributes() {
+                let never = merged
+                    .attribute
+                    .returned
+                    .as_deref()
+                    .is_some_and(|returned| returned.eq_ignore_ascii_case("never"));
+                if !never {
+                    continue;
+                }
+                let path = if merged.schema.eq_ignore_ascii_case(&schema.id) {
+                    merged.path.clone()
+                } else {
+                    merged.qualified_path()
+                };
+                policy = policy.with(Some(resource_type.name.clone()), path, Redaction::Strip);
+            }
+        }
+        policy
+    }
+
+    /// Strips the attribute at `path` from resources of every type.
+    pub fn strip(self, path: impl Into<String>) -> Self {
+        self.with(None, path.into(), Redaction::Strip)
+    }
+
+    /// Masks the attribute at `path` in resources of every type.
+    pub fn mask(self, path: impl Into<String>) -> Self {
+        self.with(None, path.into(), Redaction::Mask)
+    }
+
+    /// Strips the attribute at `path` from resources of `resource_type`.
+    pub fn strip_for(self, resource_type: impl Into<String>, path: impl Into<String>) -> Self {
+        self.with(Some(resource_type.into()), path.into(), Redaction::Strip)
+    }
+
+    /// Masks the attribute at `path` in resources of `resource_type`.
+    pub fn mask_for(self, resource_type: impl Into<String>, path: impl Into<String>) -> Self {
+        self.with(Some(resource_type.into()), path.into(), Redaction::Mask)
+    }
+
+    fn with(mut self, resource_type: Option<String>, path: String, redaction: Redaction) -> Self {
+        self.rules.push(Rule {
+            resource_type,
+            path,
+            redaction,
+        });
+        self
+    }
+
+    /// Returns `true` if the policy redacts nothing.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Redacts a resource of `resource_type` in place. Attributes are stripped before any are masked.
+    pub fn apply(&self, resource_type: &str, resource: &mut Value) {
+        let rules = self.rules.iter().filter(|rule| {
+            rule.resource_type
+                .as_deref()
+                .is_none_or(|name| name.eq_ignore_ascii_case(resource_type))
+        });
+        let (stripped, masked): (Vec<&Rule>, Vec<&Rule>) =
+            rules.partition(|rule| rule.redaction == Redaction::Strip);
+        for rule in stripped {
+            let keys = keys(resource, &rule.path);
+            remove(resource, &keys);
+        }
+        for rule in masked {
+            let keys = keys(resource, &rule.path);
+            for_each_mut(resource, &keys, &mut |value| strings(value, &mut mask));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::schema_uri::ENTERPRISE_USER_URN;
+
+    #[test]
+    fn redacts_by_resource_type_and_extension() {
+        let policy = RedactionPolicy::new()
+            .mask_for("user", format!("{}:employeeNumber", ENTERPRISE_USER_URN))
+            .strip(format!("{}:manager", ENTERPRISE_USER_URN))
+            .mask("name");
+        let mut user = json!({
+            "name": {"givenName": "Barbara", "familyName": "Jensen"},
+            ENTERPRISE_USER_URN: {"employeeNumber": "701984", "manager": {"value": "26118915"}}
+        });
+        policy.apply("User", &mut user);
+        assert_eq!(
+            user,
+            json!({
+                "name": {"givenName": "*****ra", "familyName": "****en"},
+                ENTERPRISE_USER_URN: {"employeeNumber": "****84"}
+            })
+        );
+
+        let mut group = json!({ENTERPRISE_USER_URN: {"employeeNumber": "701984"}});
+        policy.apply("Group", &mut group);
+        assert_eq!(group[ENTERPRISE_USER_URN]["employeeNumber"], "701984");
+        assert!(RedactionPolicy::from_registry(&SchemaRegistry::new()).is_empty());
+    }
+}
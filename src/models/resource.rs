@@ -0,0 +1,248 @@
+//! The handling all SCIM resource models share.
+
+use alloc::format;
+use alloc::string::String;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::models::group::Group;
+use crate::models::resource_types::ResourceType;
+use crate::models::schema_uri::{
+    GROUP_URN, RESOURCE_TYPE_URN, SCHEMA_URN, SERVICE_PROVIDER_CONFIG_URN, USER_URN,
+};
+use crate::models::scim_schema::{Meta, Schema};
+use crate::models::service_provider_config::ServiceProviderConfig;
+use crate::models::user::User;
+use crate::utils::error::SCIMError;
+
+/// A resource model, bound to its schema, its resource type and its endpoint.
+///
+/// Code that handles resources of any type, such as the typed methods of
+/// [`Requests`](crate::client::request::Requests) and
+/// [`ResourceProvider`](crate::provider::resource_provider::ResourceProvider), is written once against this
+/// trait instead of once per model.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::group::Group;
+/// use scim_v2::models::resource::ScimResource;
+/// use scim_v2::models::user::User;
+///
+/// fn describe<R: ScimResource>(resource: &R) -> String {
+///     format!("{} at {}", R::RESOURCE_TYPE, resource.path().unwrap_or_else(|| R::ENDPOINT.to_string()))
+/// }
+///
+/// let user = User::from_value(serde_json::json!({
+///     "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+///     "id": "2819c223",
+///     "userName": "bjensen@example.com"
+/// }))
+/// .unwrap();
+/// assert_eq!(describe(&user), "User at /Users/2819c223");
+/// assert_eq!(describe(&Group::default()), "Group at /Groups");
+/// ```
+pub trait ScimResource: Serialize + DeserializeOwned {
+    /// The URI of the core schema of the resource.
+    const SCHEMA_URN: &'static str;
+
+    /// The name of the resource type, e.g. `User`.
+    const RESOURCE_TYPE: &'static str;
+
+    /// The endpoint of the resource type, relative to the base URL, e.g. `/Users`.
+    const ENDPOINT: &'static str;
+
+    /// Returns the id of the resource, if it has one.
+    fn id(&self) -> Option<&str>;
+
+    /// Returns the metadata of the resource, if it has any.
+    fn meta(&self) -> Option<&Meta>;
+
+    /// Returns the metadata of the resource, setting empty metadata if it has none.
+    fn meta_mut(&mut self) -> &mut Meta;
+
+    /// Validates the resource.
+    ///
+    /// # Errors
+    ///
+    /// The errors of the model's own `validate` method.
+    fn validate(&self) -> Result<(), SCIMError>;
+
+    /// Returns the path of the resource, relative to the base URL, e.g. `/Users/2819c223`.
+    ///
+    /// Returns `None` if the resource has no id.
+    fn path(&self) -> Option<String> {
+        self.id().map(|id| format!("{}/{}", Self::ENDPOINT, id))
+    }
+
+    /// Converts the resource into its JSON representation.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if the resource cannot be represented as JSON.
+    fn to_value(&self) -> Result<Value, SCIMError> {
+        serde_json::to_value(self).map_err(SCIMError::SerializationError)
+    }
+
+    /// Reads the resource from its JSON representation.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::DeserializationError` if the value does not describe the resource.
+    fn from_value(value: Value) -> Result<Self, SCIMError> {
+        serde_json::from_value(value).map_err(SCIMError::DeserializationError)
+    }
+}
+
+impl ScimResource for User {
+    const SCHEMA_URN: &'static str = USER_URN;
+    const RESOURCE_TYPE: &'static str = "User";
+    const ENDPOINT: &'static str = "/Users";
+
+    fn id(&self) -> Option<&str> {
+        self.id.as_ref().map(|id| id.as_str())
+    }
+
+    fn meta(&self) -> Option<&Meta> {
+        self.meta.as_ref()
+    }
+
+    fn meta_mut(&mut self) -> &mut Meta {
+        self.meta.get_or_insert_with(Meta::default)
+    }
+
+    fn validate(&self) -> Result<(), SCIMError> {
+        User::validate(self)
+    }
+
+    // Also completes the `schemas` of the user, as `User::try_from` does.
+    fn from_value(value: Value) -> Result<Self, SCIMError> {
+        User::try_from(value)
+    }
+}
+
+impl ScimResource for Group {
+    const SCHEMA_URN: &'static str = GROUP_URN;
+    const RESOURCE_TYPE: &'static str = "Group";
+    const ENDPOINT: &'static str = "/Groups";
+
+    fn id(&self) -> Option<&str> {
+        self.id.as_ref().map(|id| id.as_str())
+    }
+
+    fn meta(&self) -> Option<&Meta> {
+        self.meta.as_ref()
+    }
+
+    fn meta_mut(&mut self) -> &mut Meta {
+        self.meta.get_or_insert_with(Meta::default)
+    }
+
+    fn validate(&self) -> Result<(), SCIMError> {
+        Group::validate(self)
+    }
+}
+
+impl ScimResource for ResourceType {
+    const SCHEMA_URN: &'static str = RESOURCE_TYPE_URN;
+    const RESOURCE_TYPE: &'static str = "ResourceType";
+    const ENDPOINT: &'static str = "/ResourceTypes";
+
+    fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    fn meta(&self) -> Option<&Meta> {
+        self.meta.as_ref()
+    }
+
+    fn meta_mut(&mut self) -> &mut Meta {
+        self.meta.get_or_insert_with(Meta::default)
+    }
+
+    fn validate(&self) -> Result<(), SCIMError> {
+        ResourceType::validate(self)
+    }
+}
+
+impl ScimResource for Schema {
+    const SCHEMA_URN: &'static str = SCHEMA_URN;
+    const RESOURCE_TYPE: &'static str = "Schema";
+    const ENDPOINT: &'static str = "/Schemas";
+
+    fn id(&self) -> Option<&str> {
+        Some(&self.id)
+    }
+
+    fn meta(&self) -> Option<&Meta> {
+        Some(&self.meta)
+    }
+
+    fn meta_mut(&mut self) -> &mut Meta {
+        &mut self.meta
+    }
+
+    fn validate(&self) -> Result<(), SCIMError> {
+        Schema::validate(self)
+    }
+}
+
+// The service provider configuration is a singleton: it has no id, and its endpoint is its path.
+impl ScimResource for ServiceProviderConfig {
+    const SCHEMA_URN: &'static str = SERVICE_PROVIDER_CONFIG_URN;
+    const RESOURCE_TYPE: &'static str = "ServiceProviderConfig";
+    const ENDPOINT: &'static str = "/ServiceProviderConfig";
+
+    fn id(&self) -> Option<&str> {
+        None
+    }
+
+    fn meta(&self) -> Option<&Meta> {
+        self.meta.as_ref()
+    }
+
+    fn meta_mut(&mut self) -> &mut Meta {
+        self.meta.get_or_insert_with(Meta::default)
+    }
+
+    fn validate(&self) -> Result<(), SCIMError> {
+        ServiceProviderConfig::validate(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn round_trip<R: ScimResource>(value: Value) -> R {
+        let mut resource = R::from_value(value).unwrap();
+        resource.meta_mut().resource_type = Some(R::RESOURCE_TYPE.into());
+        let value = resource.to_value().unwrap();
+        assert_eq!(value["meta"]["resourceType"], R::RESOURCE_TYPE);
+        R::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn handles_models_generically() {
+        let user: User =
+            round_trip(json!({"schemas": [USER_URN], "id": "2819c223", "userName": "bjensen"}));
+        assert_eq!(user.path().as_deref(), Some("/Users/2819c223"));
+        assert!(ScimResource::validate(&user).is_ok());
+
+        let group: Group =
+            round_trip(json!({"schemas": [GROUP_URN], "displayName": "Tour Guides"}));
+        assert_eq!(group.id(), None);
+        assert_eq!(group.path(), None);
+
+        let resource_type: ResourceType = round_trip(json!({
+            "id": "User",
+            "name": "User",
+            "endpoint": "/Users",
+            "schema": USER_URN,
+        }));
+        assert_eq!(resource_type.path().as_deref(), Some("/ResourceTypes/User"));
+    }
+}
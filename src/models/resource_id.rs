@@ -0,0 +1,162 @@
+use alloc::string::{String, ToString};
+use core::fmt;
+use core::fmt::{Display, Formatter};
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
+
+use serde::{Deserialize, Serialize};
+
+/// Represents the identifier of a SCIM resource.
+///
+/// `ResourceId` wraps the `id` of a resource (and the `value` of references to it, such as group members)
+/// so that every lookup and deduplication in the crate compares identifiers the same way.
+/// Comparison is ASCII case-insensitive, since most service providers hand out UUIDs whose casing is
+/// not significant, and the `Hash` implementation is consistent with that comparison.
+///
+/// Equality inspects every byte of equal-length identifiers instead of returning at the first mismatch,
+/// which avoids leaking the position of a difference through timing.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(transparent)]
+pub struct ResourceId(String);
+
+impl ResourceId {
+    /// Creates a new `ResourceId` from anything that can be turned into a `String`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::resource_id::ResourceId;
+    ///
+    /// let id = ResourceId::new("2819C223-7F76-453A-919D-413861904646");
+    /// assert_eq!(id, "2819c223-7f76-453a-919d-413861904646");
+    /// ```
+    pub fn new(id: impl Into<String>) -> Self {
+        ResourceId(id.into())
+    }
+
+    /// Returns the identifier as a string slice, exactly as it was received.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Consumes the `ResourceId` and returns the inner `String`.
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+fn ids_equal(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| {
+        acc | (x.to_ascii_lowercase() ^ y.to_ascii_lowercase())
+    }) == 0
+}
+
+impl PartialEq for ResourceId {
+    fn eq(&self, other: &Self) -> bool {
+        ids_equal(&self.0, &other.0)
+    }
+}
+
+impl Eq for ResourceId {}
+
+impl PartialEq<str> for ResourceId {
+    fn eq(&self, other: &str) -> bool {
+        ids_equal(&self.0, other)
+    }
+}
+
+impl PartialEq<&str> for ResourceId {
+    fn eq(&self, other: &&str) -> bool {
+        ids_equal(&self.0, other)
+    }
+}
+
+impl PartialEq<String> for ResourceId {
+    fn eq(&self, other: &String) -> bool {
+        ids_equal(&self.0, other)
+    }
+}
+
+impl Hash for ResourceId {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for byte in self.0.bytes() {
+            state.write_u8(byte.to_ascii_lowercase());
+        }
+        state.write_usize(self.0.len());
+    }
+}
+
+impl Deref for ResourceId {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for ResourceId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for ResourceId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<String> for ResourceId {
+    fn from(value: String) -> Self {
+        ResourceId(value)
+    }
+}
+
+impl From<&str> for ResourceId {
+    fn from(value: &str) -> Self {
+        ResourceId(value.to_string())
+    }
+}
+
+impl From<ResourceId> for String {
+    fn from(value: ResourceId) -> Self {
+        value.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn resource_id_comparison_ignores_ascii_case() {
+        let upper = ResourceId::new("2819C223-7F76-453A-919D-413861904646");
+        let lower = ResourceId::new("2819c223-7f76-453a-919d-413861904646");
+        assert_eq!(upper, lower);
+        assert_eq!(upper, "2819c223-7f76-453a-919d-413861904646");
+        assert_ne!(upper, ResourceId::new("2819c223"));
+    }
+
+    #[test]
+    fn resource_id_hash_is_consistent_with_equality() {
+        let mut ids = HashSet::new();
+        ids.insert(ResourceId::new("ABC"));
+        ids.insert(ResourceId::new("abc"));
+        ids.insert(ResourceId::new("abd"));
+        assert_eq!(ids.len(), 2);
+    }
+
+    #[test]
+    fn resource_id_serializes_as_plain_string() {
+        let id: ResourceId = serde_json::from_str(r#""e9e30dba""#).unwrap();
+        assert_eq!(id.as_str(), "e9e30dba");
+        assert_eq!(serde_json::to_string(&id).unwrap(), r#""e9e30dba""#);
+    }
+}
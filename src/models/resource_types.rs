@@ -1,9 +1,20 @@
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io::Write;
+
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
+use crate::models::schema_registry::read_global;
+use crate::models::schema_uri::{ENTERPRISE_USER_URN, GROUP_URN, USER_URN};
 use crate::models::scim_schema::Meta;
 use crate::utils::error::SCIMError;
+use crate::utils::json;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ResourceType {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<String>,
@@ -32,7 +43,8 @@ impl Default for ResourceType {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct SchemaExtension {
     pub schema: String,
     pub required: bool,
@@ -51,10 +63,12 @@ impl Default for SchemaExtension {
 ///
 /// This function creates `ResourceType` instances for "user" and "group" with default values if their names are included in the `resource_type_names` vector.
 /// If "enterprise_user" is included in the `resource_type_names` vector, the "user" `ResourceType` will include the enterprise user schema extension.
+/// Any other name is looked up (case-insensitively) among the resource types registered with the process-wide
+/// [`SchemaRegistry`](crate::models::schema_registry::SchemaRegistry).
 ///
 /// # Parameters
 ///
-/// * `resource_type_names` - A vector of string slices that represent the names of the resource types to be returned. Options are: user, group, enterprise_user
+/// * `resource_type_names` - A vector of string slices that represent the names of the resource types to be returned. Options are: user, group, enterprise_user, or the name of a registered resource type
 ///
 /// # Returns
 ///
@@ -94,11 +108,10 @@ pub fn get_resource_types(
                     name: "User".to_string(),
                     endpoint: "/Users".to_string(),
                     description: Some("User Account".to_string()),
-                    schema: "urn:ietf:params:scim:schemas:core:2.0:User".to_string(),
+                    schema: USER_URN.to_string(),
                     schema_extensions: if has_enterprise_user {
                         Some(vec![SchemaExtension {
-                            schema: "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User"
-                                .to_string(),
+                            schema: ENTERPRISE_USER_URN.to_string(),
                             required: true,
                         }])
                     } else {
@@ -120,7 +133,7 @@ pub fn get_resource_types(
                     name: "Group".to_string(),
                     endpoint: "/Groups".to_string(),
                     description: Some("Group".to_string()),
-                    schema: "urn:ietf:params:scim:schemas:core:2.0:Group".to_string(),
+                    schema: GROUP_URN.to_string(),
                     schema_extensions: None,
                     meta: Some(Meta {
                         location: Some("https://example.com/v2/ResourceTypes/Group".to_string()),
@@ -132,11 +145,10 @@ pub fn get_resource_types(
                 };
                 resource_types.push(group_resource_type);
             }
-            _ => {
-                return Err(SCIMError::ResourceTypeNotFound(
-                    resource_type_name.to_string(),
-                ));
-            }
+            name => match read_global().resource_type(name) {
+                Some(resource_type) => resource_types.push(resource_type.clone()),
+                None => return Err(SCIMError::ResourceTypeNotFound(name.into())),
+            },
         }
     }
     Ok(resource_types)
@@ -192,6 +204,48 @@ impl TryFrom<&str> for ResourceType {
     }
 }
 
+/// Converts an already-parsed JSON value into a `ResourceType` struct.
+///
+/// Web frameworks usually hand over request bodies as a `serde_json::Value`; this conversion avoids
+/// round-tripping that value through a string.
+///
+/// # Errors
+///
+/// Returns `SCIMError::DeserializationError` if the value does not describe a `ResourceType` object.
+///
+/// # Examples
+///
+/// ```rust
+/// use scim_v2::models::resource_types::ResourceType;
+/// use serde_json::json;
+///
+/// let value = json!({"id": "User", "name": "User", "endpoint": "/Users", "schema": "urn:ietf:params:scim:schemas:core:2.0:User"});
+/// match ResourceType::try_from(value) {
+///     Ok(resource) => println!("Successfully converted JSON to ResourceType: {:?}", resource),
+///     Err(e) => println!("Error converting from JSON to ResourceType: {}", e),
+/// }
+/// ```
+impl TryFrom<Value> for ResourceType {
+    type Error = SCIMError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        serde_json::from_value(value).map_err(SCIMError::DeserializationError)
+    }
+}
+
+/// Converts a `ResourceType` struct into a JSON value.
+///
+/// # Errors
+///
+/// Returns `SCIMError::SerializationError` if the `ResourceType` cannot be represented as JSON.
+impl TryFrom<ResourceType> for Value {
+    type Error = SCIMError;
+
+    fn try_from(value: ResourceType) -> Result<Self, Self::Error> {
+        serde_json::to_value(value).map_err(SCIMError::SerializationError)
+    }
+}
+
 impl ResourceType {
     /// Validates a resource type.
     ///
@@ -226,13 +280,13 @@ impl ResourceType {
     /// ```
     pub fn validate(&self) -> Result<(), SCIMError> {
         if self.name.is_empty() {
-            return Err(SCIMError::MissingRequiredField("name".to_string()));
+            return Err(SCIMError::MissingRequiredField("name".into()));
         }
         if self.endpoint.is_empty() {
-            return Err(SCIMError::MissingRequiredField("endpoint".to_string()));
+            return Err(SCIMError::MissingRequiredField("endpoint".into()));
         }
         if self.schema.is_empty() {
-            return Err(SCIMError::MissingRequiredField("schema".to_string()));
+            return Err(SCIMError::MissingRequiredField("schema".into()));
         }
         Ok(())
     }
@@ -266,6 +320,25 @@ impl ResourceType {
         serde_json::to_string(&self).map_err(SCIMError::SerializationError)
     }
 
+    /// Serializes the `ResourceType` instance as JSON into `buffer`, appending to its current content.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if the `ResourceType` cannot be serialized.
+    pub fn serialize_into(&self, buffer: &mut Vec<u8>) -> Result<(), SCIMError> {
+        json::append_to(buffer, self)
+    }
+
+    /// Serializes the `ResourceType` instance as JSON to `writer`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if the `ResourceType` cannot be serialized or writing to `writer` fails.
+    #[cfg(feature = "std")]
+    pub fn to_writer(&self, writer: impl Write) -> Result<(), SCIMError> {
+        serde_json::to_writer(writer, self).map_err(SCIMError::SerializationError)
+    }
+
     /// Deserializes a JSON string into a `ResourceType` instance, using the custom SCIMError for error handling.
     ///
     /// # Parameters
@@ -0,0 +1,345 @@
+use crate::models::resource_id::ResourceId;
+use crate::models::schema_uri::SchemaUri;
+use crate::models::scim_schema::{Attributes, Meta, Schema, SubAttributes};
+
+/// Builds a `Schema` document attribute by attribute.
+///
+/// The builder fills in the schema's `meta` the same way the bundled schemas do, so a built schema can be
+/// served from a `/Schemas` endpoint as is. It does not validate the result; call [`Schema::validate`] for that.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::schema_builder::SchemaBuilder;
+/// use scim_v2::models::scim_schema::Attributes;
+///
+/// let schema = SchemaBuilder::new("urn:example:params:scim:schemas:core:1.0:Device", "Device")
+///     .description("Managed device")
+///     .attribute(Attributes::new("serialNumber", "string").with_required(true))
+///     .attribute(Attributes::new("tags", "string").with_multi_valued(true))
+///     .build();
+///
+/// assert_eq!(schema.attributes.len(), 2);
+/// assert!(schema.validate().is_ok());
+/// ```
+#[derive(Debug, Clone)]
+pub struct SchemaBuilder {
+    id: String,
+    name: String,
+    description: String,
+    attributes: Vec<Attributes>,
+}
+
+impl SchemaBuilder {
+    /// Starts a schema with the given schema URI and name.
+    pub fn new(id: impl Into<String>, name: impl Into<String>) -> Self {
+        SchemaBuilder {
+            id: id.into(),
+            name: name.into(),
+            description: String::new(),
+            attributes: Vec::new(),
+        }
+    }
+
+    /// Sets the human-readable description of the schema.
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    /// Appends an attribute definition.
+    pub fn attribute(mut self, attribute: Attributes) -> Self {
+        self.attributes.push(attribute);
+        self
+    }
+
+    /// Finishes the schema.
+    pub fn build(self) -> Schema {
+        Schema {
+            meta: Meta {
+                resource_type: Some("Schema".to_string()),
+                location: Some(format!("/v2/Schemas/{}", self.id)),
+                ..Default::default()
+            },
+            id: self.id,
+            name: self.name,
+            description: self.description,
+            attributes: self.attributes,
+        }
+    }
+}
+
+impl Attributes {
+    /// Creates a single-valued, optional attribute of the given SCIM type (`string`, `complex`, ...).
+    ///
+    /// All other characteristics are left unset, which SCIM interprets as their defaults.
+    pub fn new(name: impl Into<String>, r#type: impl Into<String>) -> Self {
+        Attributes {
+            name: name.into(),
+            r#type: r#type.into(),
+            multi_valued: false,
+            description: None,
+            required: None,
+            canonical_values: None,
+            case_exact: None,
+            mutability: None,
+            returned: None,
+            uniqueness: None,
+            sub_attributes: None,
+            reference_types: None,
+        }
+    }
+
+    /// Sets `multiValued`.
+    pub fn with_multi_valued(mut self, multi_valued: bool) -> Self {
+        self.multi_valued = multi_valued;
+        self
+    }
+
+    /// Sets `description`.
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Sets `required`.
+    pub fn with_required(mut self, required: bool) -> Self {
+        self.required = Some(required);
+        self
+    }
+
+    /// Sets `caseExact`.
+    pub fn with_case_exact(mut self, case_exact: bool) -> Self {
+        self.case_exact = Some(case_exact);
+        self
+    }
+
+    /// Sets `mutability`, e.g. `readOnly`.
+    pub fn with_mutability(mut self, mutability: impl Into<String>) -> Self {
+        self.mutability = Some(mutability.into());
+        self
+    }
+
+    /// Sets `returned`, e.g. `never`.
+    pub fn with_returned(mut self, returned: impl Into<String>) -> Self {
+        self.returned = Some(returned.into());
+        self
+    }
+
+    /// Sets `uniqueness`, e.g. `server`.
+    pub fn with_uniqueness(mut self, uniqueness: impl Into<String>) -> Self {
+        self.uniqueness = Some(uniqueness.into());
+        self
+    }
+
+    /// Sets `canonicalValues`.
+    pub fn with_canonical_values(mut self, values: &[&str]) -> Self {
+        self.canonical_values = Some(values.iter().map(|v| v.to_string()).collect());
+        self
+    }
+
+    /// Sets `referenceTypes`.
+    pub fn with_reference_types(mut self, types: &[&str]) -> Self {
+        self.reference_types = Some(types.iter().map(|t| t.to_string()).collect());
+        self
+    }
+
+    /// Appends a sub-attribute. Any sub-attributes of `sub_attribute` itself are dropped, since SCIM does not
+    /// allow complex attributes to nest.
+    pub fn with_sub_attribute(mut self, sub_attribute: Attributes) -> Self {
+        self.sub_attributes
+            .get_or_insert_with(Vec::new)
+            .push(sub_attribute.into());
+        self
+    }
+}
+
+impl From<Attributes> for SubAttributes {
+    fn from(value: Attributes) -> Self {
+        SubAttributes {
+            name: value.name,
+            r#type: value.r#type,
+            multi_valued: value.multi_valued,
+            description: value.description,
+            required: value.required,
+            canonical_values: value.canonical_values,
+            case_exact: value.case_exact,
+            mutability: value.mutability,
+            returned: value.returned,
+            uniqueness: value.uniqueness,
+            reference_types: value.reference_types,
+        }
+    }
+}
+
+/// A Rust type that describes itself as a SCIM schema.
+///
+/// With the `derive` feature enabled, `#[derive(ScimSchema)]` implements this trait for a struct with named
+/// fields, taking the schema URI, name and description from `#[scim(urn = "...", name = "...", description =
+/// "...")]`. Each field becomes an attribute named like its camelCase serialization (honouring
+/// `#[serde(rename = "...")]`), with its type, `multiValued` and `required` characteristics derived from the
+/// field's Rust type through [`SchemaAttribute`]. Fields accept `#[scim(mutability = "...", returned = "...",
+/// uniqueness = "...", description = "...", case_exact)]` to set the remaining characteristics, and
+/// `#[scim(skip)]` to leave a field out.
+///
+/// # Examples
+///
+/// ```ignore
+/// use scim_v2::ScimSchema;
+/// use scim_v2::models::schema_builder::ScimSchema as _;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize, ScimSchema)]
+/// #[serde(rename_all = "camelCase")]
+/// #[scim(urn = "urn:example:params:scim:schemas:core:1.0:Device", name = "Device")]
+/// struct Device {
+///     #[scim(mutability = "immutable", uniqueness = "server")]
+///     serial_number: String,
+///     tags: Vec<String>,
+///     owner: Option<String>,
+/// }
+///
+/// let schema = Device::scim_schema();
+/// assert_eq!(schema.attributes[0].name, "serialNumber");
+/// ```
+pub trait ScimSchema {
+    /// Returns the schema document describing this type.
+    fn scim_schema() -> Schema;
+}
+
+/// A Rust type that can be described as a SCIM attribute.
+///
+/// Implemented for the primitive types SCIM knows about, for `Option<T>` (which makes the attribute optional)
+/// and for `Vec<T>` (which makes it multi-valued). `#[derive(SchemaAttribute)]` implements it for a struct as a
+/// `complex` attribute whose sub-attributes are the struct's fields, following the same rules as
+/// `#[derive(ScimSchema)]`.
+pub trait SchemaAttribute {
+    /// Describes an attribute of this type with the given name.
+    fn attribute(name: &str) -> Attributes;
+}
+
+macro_rules! impl_schema_attribute {
+    ($scim_type:literal => $($ty:ty),+) => {
+        $(
+            impl SchemaAttribute for $ty {
+                fn attribute(name: &str) -> Attributes {
+                    Attributes::new(name, $scim_type).with_required(true)
+                }
+            }
+        )+
+    };
+}
+
+impl_schema_attribute!("string" => String, ResourceId);
+impl_schema_attribute!("boolean" => bool);
+impl_schema_attribute!("integer" => i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+impl_schema_attribute!("decimal" => f32, f64);
+
+impl SchemaAttribute for SchemaUri {
+    fn attribute(name: &str) -> Attributes {
+        Attributes::new(name, "reference")
+            .with_required(true)
+            .with_reference_types(&["uri"])
+    }
+}
+
+impl<T: SchemaAttribute> SchemaAttribute for Option<T> {
+    fn attribute(name: &str) -> Attributes {
+        T::attribute(name).with_required(false)
+    }
+}
+
+impl<T: SchemaAttribute> SchemaAttribute for Vec<T> {
+    fn attribute(name: &str) -> Attributes {
+        T::attribute(name)
+            .with_multi_valued(true)
+            .with_required(false)
+    }
+}
+
+impl<T: SchemaAttribute> SchemaAttribute for Box<T> {
+    fn attribute(name: &str) -> Attributes {
+        T::attribute(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use scim_v2_derive::{SchemaAttribute, ScimSchema};
+
+    #[derive(Serialize, Deserialize, SchemaAttribute)]
+    #[serde(rename_all = "camelCase")]
+    struct Owner {
+        display_name: Option<String>,
+        #[serde(rename = "$ref")]
+        reference: Option<String>,
+    }
+
+    #[derive(Serialize, Deserialize, ScimSchema)]
+    #[serde(rename_all = "camelCase")]
+    #[scim(
+        urn = "urn:example:params:scim:schemas:core:1.0:Device",
+        name = "Device",
+        description = "Managed device"
+    )]
+    struct Device {
+        #[scim(mutability = "immutable", uniqueness = "server", case_exact)]
+        serial_number: String,
+        tags: Vec<String>,
+        owner: Option<Owner>,
+        #[scim(skip)]
+        cached: bool,
+    }
+
+    #[test]
+    fn derived_schema_describes_fields() {
+        let schema = Device::scim_schema();
+        assert_eq!(schema.id, "urn:example:params:scim:schemas:core:1.0:Device");
+        assert_eq!(schema.description, "Managed device");
+        let names: Vec<&str> = schema.attributes.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, ["serialNumber", "tags", "owner"]);
+
+        let serial = &schema.attributes[0];
+        assert_eq!(serial.required, Some(true));
+        assert_eq!(serial.mutability.as_deref(), Some("immutable"));
+        assert_eq!(serial.case_exact, Some(true));
+        assert!(schema.attributes[1].multi_valued);
+
+        let owner = &schema.attributes[2];
+        assert_eq!(owner.r#type, "complex");
+        assert_eq!(owner.required, Some(false));
+        let sub_names: Vec<&str> = owner
+            .sub_attributes
+            .iter()
+            .flatten()
+            .map(|a| a.name.as_str())
+            .collect();
+        assert_eq!(sub_names, ["displayName", "$ref"]);
+        assert!(schema.validate().is_ok());
+    }
+
+    #[test]
+    fn builder_matches_bundled_schema_metadata() {
+        let schema = SchemaBuilder::new(
+            "urn:example:params:scim:schemas:core:1.0:Printer",
+            "Printer",
+        )
+        .attribute(
+            Attributes::new("status", "string").with_canonical_values(&["online", "offline"]),
+        )
+        .build();
+        assert_eq!(schema.meta.resource_type.as_deref(), Some("Schema"));
+        assert_eq!(
+            schema.meta.location.as_deref(),
+            Some("/v2/Schemas/urn:example:params:scim:schemas:core:1.0:Printer")
+        );
+        assert_eq!(
+            schema.attributes[0].canonical_values,
+            Some(vec!["online".to_string(), "offline".to_string()])
+        );
+    }
+}
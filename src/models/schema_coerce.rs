@@ -0,0 +1,330 @@
+use serde_json::{Map, Number, Value};
+
+use crate::models::scim_schema::{Attributes, MergedSchema};
+
+/// Coerces attribute values in a resource representation to the types its schema declares.
+///
+/// Some SCIM clients send `"true"` for booleans, numbers as strings, or a single value where the schema
+/// defines a multi-valued attribute. This pass repairs those payloads in place so that strict
+/// deserialization succeeds afterwards:
+///
+/// * `boolean` attributes accept `"true"` and `"false"` in any casing;
+/// * `integer` and `decimal` attributes accept numeric strings;
+/// * `string` attributes accept numbers and booleans, which are converted to their text;
+/// * multi-valued attributes given a single value are wrapped in a one-element array.
+///
+/// Sub-attributes of complex attributes, extension attributes (nested under their schema URI) and the common
+/// `id` and `externalId` attributes are coerced as well. Attributes the schema does not know, and values that cannot be converted, are left
+/// untouched for validation to report.
+///
+/// # Parameters
+///
+/// * `resource` - The resource representation to repair.
+/// * `schema` - The resource's schema merged with its extensions, see
+///   [`Schema::merge_extensions`](crate::models::scim_schema::Schema::merge_extensions).
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::schema_coerce::coerce_resource;
+/// use scim_v2::models::scim_schema::get_schemas;
+/// use scim_v2::models::user::User;
+/// use serde_json::json;
+///
+/// let schemas = get_schemas(vec!["user"]).unwrap();
+/// let merged = schemas[0].merge_extensions(&[]);
+///
+/// let mut payload = json!({
+///     "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+///     "userName": "bjensen",
+///     "active": "True",
+///     "emails": {"value": "bjensen@example.com", "primary": "true"}
+/// });
+/// coerce_resource(&mut payload, &merged);
+///
+/// let user = User::try_from(payload).unwrap();
+/// assert_eq!(user.active, Some(true));
+/// assert_eq!(user.emails.unwrap()[0].primary, Some(true));
+/// ```
+pub fn coerce_resource(resource: &mut Value, schema: &MergedSchema) {
+    let Some(object) = resource.as_object_mut() else {
+        return;
+    };
+    for (key, value) in object.iter_mut() {
+        let extension = schema
+            .schemas
+            .iter()
+            .skip(1)
+            .find(|uri| uri.eq_ignore_ascii_case(key));
+        match extension {
+            Some(uri) => {
+                if let Some(attributes) = value.as_object_mut() {
+                    coerce_object(attributes, schema, uri, "");
+                }
+            }
+            None if is_common_string_attribute(key) => {
+                if let Value::Number(n) = value {
+                    *value = Value::String(n.to_string());
+                }
+            }
+            None => coerce_attribute(value, schema, &schema.id, key),
+        }
+    }
+}
+
+/// Renames attributes in a resource representation to the casing their schema declares.
+///
+/// Attribute names are case-insensitive in SCIM, but serde matches field names exactly, so `Username` or
+/// `emails[].Value` would otherwise be dropped during deserialization. Sub-attributes, extension attributes
+/// and the extension keys themselves are renamed as well; the common attributes `id`, `externalId`, `meta`
+/// and `schemas` are normalized too. Attributes the schema does not know keep their names.
+///
+/// If several keys of one object differ only in case (`userName` and `username`), one value is kept: the one
+/// under the declared casing if there is one, otherwise the one whose key sorts first. The choice does not
+/// depend on the order of the object's members.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::schema_coerce::canonicalize_attribute_names;
+/// use scim_v2::models::scim_schema::get_schemas;
+/// use serde_json::json;
+///
+/// let schemas = get_schemas(vec!["user"]).unwrap();
+/// let merged = schemas[0].merge_extensions(&[]);
+///
+/// let mut payload = json!({"UserName": "bjensen", "Emails": [{"Value": "bjensen@example.com"}]});
+/// canonicalize_attribute_names(&mut payload, &merged);
+/// assert_eq!(payload, json!({"userName": "bjensen", "emails": [{"value": "bjensen@example.com"}]}));
+/// ```
+pub fn canonicalize_attribute_names(resource: &mut Value, schema: &MergedSchema) {
+    let Some(object) = resource.as_object_mut() else {
+        return;
+    };
+    let renamed = std::mem::take(object).into_iter().map(|(key, mut value)| {
+        let original = key.clone();
+        let extension = schema
+            .schemas
+            .iter()
+            .skip(1)
+            .find(|uri| uri.eq_ignore_ascii_case(&key));
+        let (key, value) = match extension {
+            Some(uri) => {
+                rename_object(&mut value, schema, uri, "");
+                (uri.clone(), value)
+            }
+            None => {
+                let common = ["id", "externalId", "meta", "schemas"]
+                    .into_iter()
+                    .find(|name| name.eq_ignore_ascii_case(&key));
+                match common {
+                    Some(name) => (name.to_string(), value),
+                    None => rename_attribute(key, value, schema, &schema.id, ""),
+                }
+            }
+        };
+        (original, key, value)
+    });
+    *object = collect_renamed(renamed);
+}
+
+// Collects renamed members. When several keys were renamed to the same name, keeps the value of the key
+// that already had that name, or else of the key that sorts first.
+fn collect_renamed(members: impl Iterator<Item = (String, String, Value)>) -> Map<String, Value> {
+    let mut chosen: Map<String, Value> = Map::new();
+    let mut originals: Vec<(String, String)> = Vec::new();
+    for (original, key, value) in members {
+        let rank = |original: &str| (original != key, original.to_string());
+        match originals.iter_mut().find(|(renamed, _)| *renamed == key) {
+            Some((_, kept)) if rank(kept) <= rank(&original) => {}
+            Some((_, kept)) => {
+                *kept = original;
+                chosen.insert(key, value);
+            }
+            None => {
+                originals.push((key.clone(), original));
+                chosen.insert(key, value);
+            }
+        }
+    }
+    chosen
+}
+
+fn rename_attribute(
+    key: String,
+    mut value: Value,
+    schema: &MergedSchema,
+    uri: &str,
+    parent: &str,
+) -> (String, Value) {
+    let Some(attribute) = schema.get(&format!("{}:{}{}", uri, parent, key)) else {
+        return (key, value);
+    };
+    let path = attribute.path.clone();
+    if attribute.attribute.r#type == "complex" {
+        match &mut value {
+            Value::Array(values) => {
+                for value in values {
+                    rename_object(value, schema, uri, &format!("{}.", path));
+                }
+            }
+            value => rename_object(value, schema, uri, &format!("{}.", path)),
+        }
+    }
+    (attribute.attribute.name.clone(), value)
+}
+
+fn rename_object(value: &mut Value, schema: &MergedSchema, uri: &str, parent: &str) {
+    if let Value::Object(object) = value {
+        *object = collect_renamed(std::mem::take(object).into_iter().map(|(key, value)| {
+            let original = key.clone();
+            let (key, value) = rename_attribute(key, value, schema, uri, parent);
+            (original, key, value)
+        }));
+    }
+}
+
+// `id` and `externalId` are common attributes (RFC 7643, section 3.1) that schemas do not list.
+fn is_common_string_attribute(key: &str) -> bool {
+    key.eq_ignore_ascii_case("id") || key.eq_ignore_ascii_case("externalId")
+}
+
+fn coerce_object(object: &mut Map<String, Value>, schema: &MergedSchema, uri: &str, parent: &str) {
+    for (key, value) in object.iter_mut() {
+        coerce_attribute(value, schema, uri, &format!("{}{}", parent, key));
+    }
+}
+
+fn coerce_attribute(value: &mut Value, schema: &MergedSchema, uri: &str, path: &str) {
+    let Some(attribute) = schema.get(&format!("{}:{}", uri, path)) else {
+        return;
+    };
+    let attribute = &attribute.attribute;
+    if attribute.multi_valued && !value.is_array() && !value.is_null() {
+        *value = Value::Array(vec![value.take()]);
+    }
+    match value {
+        Value::Array(values) if attribute.multi_valued => {
+            for value in values {
+                coerce_single(value, attribute, schema, uri, path);
+            }
+        }
+        value => coerce_single(value, attribute, schema, uri, path),
+    }
+}
+
+fn coerce_single(
+    value: &mut Value,
+    attribute: &Attributes,
+    schema: &MergedSchema,
+    uri: &str,
+    path: &str,
+) {
+    let coerced = match (attribute.r#type.as_str(), &*value) {
+        ("boolean", Value::String(s)) if s.eq_ignore_ascii_case("true") => Some(Value::Bool(true)),
+        ("boolean", Value::String(s)) if s.eq_ignore_ascii_case("false") => {
+            Some(Value::Bool(false))
+        }
+        ("integer", Value::String(s)) => s.trim().parse::<i64>().ok().map(Value::from),
+        ("decimal", Value::String(s)) => s
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .and_then(Number::from_f64)
+            .map(Value::Number),
+        ("string", Value::Number(n)) => Some(Value::String(n.to_string())),
+        ("string", Value::Bool(b)) => Some(Value::String(b.to_string())),
+        ("complex", Value::Object(_)) => {
+            if let Value::Object(object) = value {
+                coerce_object(object, schema, uri, &format!("{}.", path));
+            }
+            None
+        }
+        _ => None,
+    };
+    if let Some(coerced) = coerced {
+        *value = coerced;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::schema_uri::ENTERPRISE_USER_URN;
+    use crate::models::scim_schema::get_schemas;
+
+    #[test]
+    fn coerces_core_and_extension_attributes() {
+        let schemas = get_schemas(vec!["user", "enterprise_user"]).unwrap();
+        let merged = schemas[0].merge_extensions(&schemas[1..]);
+        let mut payload = json!({
+            "userName": "bjensen",
+            "externalId": 701984,
+            "active": "FALSE",
+            "phoneNumbers": [{"value": "555-555-8377", "primary": "false"}],
+            "x-custom": "true",
+            ENTERPRISE_USER_URN: {"employeeNumber": 42, "manager": {"value": 26118915}}
+        });
+        coerce_resource(&mut payload, &merged);
+        assert_eq!(
+            payload,
+            json!({
+                "userName": "bjensen",
+                "externalId": "701984",
+                "active": false,
+                "phoneNumbers": [{"value": "555-555-8377", "primary": false}],
+                "x-custom": "true",
+                ENTERPRISE_USER_URN: {"employeeNumber": "42", "manager": {"value": "26118915"}}
+            })
+        );
+    }
+
+    #[test]
+    fn canonicalizes_nested_and_extension_names() {
+        let schemas = get_schemas(vec!["user", "enterprise_user"]).unwrap();
+        let merged = schemas[0].merge_extensions(&schemas[1..]);
+        let mut payload = json!({
+            "ID": "2819c223",
+            "name": {"GivenName": "Barbara"},
+            "urn:ietf:params:scim:schemas:extension:enterprise:2.0:user": {"Manager": {"VALUE": "26118915"}},
+            "x-Custom": 1
+        });
+        canonicalize_attribute_names(&mut payload, &merged);
+        assert_eq!(
+            payload,
+            json!({
+                "id": "2819c223",
+                "name": {"givenName": "Barbara"},
+                ENTERPRISE_USER_URN: {"manager": {"value": "26118915"}},
+                "x-Custom": 1
+            })
+        );
+    }
+
+    #[test]
+    fn keeps_one_value_of_keys_differing_only_in_case() {
+        let schemas = get_schemas(vec!["user"]).unwrap();
+        let merged = schemas[0].merge_extensions(&[]);
+        let mut payload = json!({
+            "username": "other",
+            "userName": "declared",
+            "name": {"givenname": "Barbara", "GivenName": "Babs"}
+        });
+        canonicalize_attribute_names(&mut payload, &merged);
+        assert_eq!(
+            payload,
+            json!({"userName": "declared", "name": {"givenName": "Babs"}})
+        );
+    }
+
+    #[test]
+    fn leaves_unconvertible_values_alone() {
+        let schemas = get_schemas(vec!["user"]).unwrap();
+        let merged = schemas[0].merge_extensions(&[]);
+        let mut payload = json!({"active": "yes", "emails": null});
+        coerce_resource(&mut payload, &merged);
+        assert_eq!(payload, json!({"active": "yes", "emails": null}));
+    }
+}
@@ -0,0 +1,154 @@
+use crate::models::scim_schema::{Attributes, MergedAttribute, Schema};
+
+/// The differences between two versions of a schema, as reported by [`diff_schemas`].
+///
+/// Attributes are identified by their dotted path within the schema, e.g. `name.givenName`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SchemaDiff {
+    /// Paths of attributes present only in the newer schema.
+    pub added: Vec<String>,
+    /// Paths of attributes present only in the older schema.
+    pub removed: Vec<String>,
+    /// Attributes present in both schemas whose definition differs.
+    pub changed: Vec<AttributeChange>,
+}
+
+/// An attribute whose definition differs between two versions of a schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeChange {
+    /// The dotted path of the attribute.
+    pub path: String,
+    /// The names of the characteristics that differ, as they appear in the JSON representation, e.g.
+    /// `type` or `mutability`.
+    pub characteristics: Vec<&'static str>,
+}
+
+impl SchemaDiff {
+    /// Returns `true` if the two schemas define the same attributes.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compares two versions of a schema attribute by attribute.
+///
+/// Use this to detect drift between the schema a service provider advertises and the one a client was built
+/// against. Attributes and sub-attributes are matched by path, case-insensitively; descriptions are compared
+/// like any other characteristic, so callers that only care about behavioural changes can ignore
+/// `description` entries.
+///
+/// # Parameters
+///
+/// * `old` - The schema the client expects.
+/// * `new` - The schema the server advertises.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::schema_diff::diff_schemas;
+/// use scim_v2::models::scim_schema::get_schema;
+///
+/// let expected = get_schema("urn:ietf:params:scim:schemas:core:2.0:Group").unwrap();
+/// let mut advertised = expected.clone();
+/// advertised.attributes[0].mutability = Some("immutable".to_string());
+/// advertised.attributes.pop();
+///
+/// let diff = diff_schemas(&expected, &advertised);
+/// assert_eq!(diff.removed[0], "members");
+/// assert_eq!(diff.changed[0].path, "displayName");
+/// assert_eq!(diff.changed[0].characteristics, ["mutability"]);
+/// ```
+pub fn diff_schemas(old: &Schema, new: &Schema) -> SchemaDiff {
+    let old = old.merge_extensions(&[]);
+    let new = new.merge_extensions(&[]);
+    let find = |attributes: &[MergedAttribute], path: &str| {
+        attributes
+            .iter()
+            .find(|a| a.path.eq_ignore_ascii_case(path))
+            .map(|a| a.attribute.clone())
+    };
+
+    let mut diff = SchemaDiff::default();
+    for attribute in old.attributes() {
+        match find(new.attributes(), &attribute.path) {
+            None => diff.removed.push(attribute.path.clone()),
+            Some(other) => {
+                let characteristics = changed_characteristics(&attribute.attribute, &other);
+                if !characteristics.is_empty() {
+                    diff.changed.push(AttributeChange {
+                        path: attribute.path.clone(),
+                        characteristics,
+                    });
+                }
+            }
+        }
+    }
+    for attribute in new.attributes() {
+        if find(old.attributes(), &attribute.path).is_none() {
+            diff.added.push(attribute.path.clone());
+        }
+    }
+    diff
+}
+
+fn changed_characteristics(old: &Attributes, new: &Attributes) -> Vec<&'static str> {
+    let checks = [
+        ("type", old.r#type != new.r#type),
+        ("multiValued", old.multi_valued != new.multi_valued),
+        ("description", old.description != new.description),
+        ("required", old.required != new.required),
+        (
+            "canonicalValues",
+            old.canonical_values != new.canonical_values,
+        ),
+        ("caseExact", old.case_exact != new.case_exact),
+        ("mutability", old.mutability != new.mutability),
+        ("returned", old.returned != new.returned),
+        ("uniqueness", old.uniqueness != new.uniqueness),
+        ("referenceTypes", old.reference_types != new.reference_types),
+    ];
+    checks
+        .into_iter()
+        .filter(|(_, changed)| *changed)
+        .map(|(name, _)| name)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::models::schema_uri::USER_URN;
+    use crate::models::scim_schema::get_schema;
+
+    #[test]
+    fn identical_schemas_have_no_diff() {
+        let user = get_schema(USER_URN).unwrap();
+        assert!(diff_schemas(&user, &user.clone()).is_empty());
+    }
+
+    #[test]
+    fn reports_added_removed_and_changed_sub_attributes() {
+        let old = get_schema(USER_URN).unwrap();
+        let mut new = old.clone();
+        let name = new
+            .attributes
+            .iter_mut()
+            .find(|a| a.name == "name")
+            .unwrap();
+        let sub_attributes = name.sub_attributes.as_mut().unwrap();
+        sub_attributes.retain(|s| s.name != "honorificSuffix");
+        sub_attributes[0].r#type = "complex".to_string();
+        sub_attributes[0].required = Some(true);
+        new.attributes
+            .push(Attributes::new("badgeNumber", "integer"));
+
+        let diff = diff_schemas(&old, &new);
+        assert_eq!(diff.added, ["badgeNumber"]);
+        assert_eq!(diff.removed, ["name.honorificSuffix"]);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].path, "name.formatted");
+        assert_eq!(diff.changed[0].characteristics, ["type", "required"]);
+    }
+}
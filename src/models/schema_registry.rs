@@ -0,0 +1,827 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::sync::{OnceLock, PoisonError, RwLock, RwLockReadGuard};
+
+use serde_json::Value;
+
+use crate::models::others::{ListResponse, Resource};
+use crate::models::resource_types::{ResourceType, SchemaExtension};
+use crate::models::schema_uri::{ENTERPRISE_USER_URN, GROUP_URN, USER_URN};
+use crate::models::scim_schema::{Attributes, MergedSchema, Meta, Schema};
+use crate::utils::error::SCIMError;
+use crate::{ENTERPRISE_USER_SCHEMA, GROUP_SCHEMA, USER_SCHEMA};
+
+/// The schemas and resource types known to a service provider.
+///
+/// A registry starts out either empty ([`SchemaRegistry::new`]) or with the built-in `User`, `EnterpriseUser`
+/// and `Group` definitions ([`SchemaRegistry::with_builtins`]). Applications register their own schemas and
+/// resource types on top of that. A resource type can only be registered once its base schema and schema
+/// extensions are known to the registry.
+///
+/// The crate keeps a process-wide registry, see [`SchemaRegistry::global`], which is what
+/// [`get_schemas`](crate::models::scim_schema::get_schemas) and
+/// [`get_resource_types`](crate::models::resource_types::get_resource_types) consult for anything other
+/// than the built-in names.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::resource_types::ResourceType;
+/// use scim_v2::models::schema_registry::SchemaRegistry;
+/// use scim_v2::models::scim_schema::{Meta, Schema};
+/// use serde_json::json;
+///
+/// let mut registry = SchemaRegistry::with_builtins();
+/// registry
+///     .register_schema(Schema {
+///         id: "urn:example:params:scim:schemas:core:1.0:Device".to_string(),
+///         name: "Device".to_string(),
+///         description: "Managed device".to_string(),
+///         attributes: vec![],
+///         meta: Meta::default(),
+///     })
+///     .unwrap();
+/// registry
+///     .register_resource_type(ResourceType {
+///         name: "Device".to_string(),
+///         endpoint: "/Devices".to_string(),
+///         schema: "urn:example:params:scim:schemas:core:1.0:Device".to_string(),
+///         ..Default::default()
+///     })
+///     .unwrap();
+///
+/// let device = json!({"schemas": ["urn:example:params:scim:schemas:core:1.0:Device"]});
+/// assert!(registry.validate_resource("Device", &device).is_ok());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SchemaRegistry {
+    schemas: Vec<Schema>,
+    resource_types: Vec<ResourceType>,
+    enforce_canonical_values: bool,
+}
+
+#[cfg(feature = "std")]
+static GLOBAL_REGISTRY: OnceLock<RwLock<SchemaRegistry>> = OnceLock::new();
+
+impl SchemaRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        SchemaRegistry::default()
+    }
+
+    /// Creates a registry holding the built-in `User`, `EnterpriseUser` and `Group` schemas, and the `User`
+    /// and `Group` resource types. The enterprise extension is registered as optional on `User`.
+    pub fn with_builtins() -> Self {
+        let schemas = [USER_SCHEMA, ENTERPRISE_USER_SCHEMA, GROUP_SCHEMA]
+            .iter()
+            .map(|content| serde_json::from_str(content).expect("bundled schema is valid JSON"))
+            .collect();
+        let resource_types = vec![
+            builtin_resource_type(
+                "User",
+                "User Account",
+                "/Users",
+                USER_URN,
+                Some(vec![SchemaExtension {
+                    schema: ENTERPRISE_USER_URN.to_string(),
+                    required: false,
+                }]),
+            ),
+            builtin_resource_type("Group", "Group", "/Groups", GROUP_URN, None),
+        ];
+        SchemaRegistry {
+            schemas,
+            resource_types,
+            enforce_canonical_values: false,
+        }
+    }
+
+    /// Returns the process-wide registry, initialised with [`SchemaRegistry::with_builtins`] on first use.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::schema_registry::SchemaRegistry;
+    ///
+    /// let registry = SchemaRegistry::global().read().unwrap();
+    /// assert!(registry.resource_type("User").is_some());
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn global() -> &'static RwLock<SchemaRegistry> {
+        GLOBAL_REGISTRY.get_or_init(|| RwLock::new(SchemaRegistry::with_builtins()))
+    }
+
+    /// Registers a schema.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of [`Schema::validate`] if the schema definition is invalid, or
+    /// `SCIMError::ConflictError` if a schema with the same id is already registered.
+    pub fn register_schema(&mut self, schema: Schema) -> Result<(), SCIMError> {
+        schema.validate()?;
+        if self.schema(&schema.id).is_some() {
+            return Err(SCIMError::ConflictError(
+                format!("schema {} is already registered", schema.id).into(),
+            ));
+        }
+        self.schemas.push(schema);
+        Ok(())
+    }
+
+    /// Registers a resource type.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of [`ResourceType::validate`] if the resource type is incomplete,
+    /// `SCIMError::SchemaNotFound` if its base schema or one of its extensions has not been registered, or
+    /// `SCIMError::ConflictError` if a resource type with the same name is already registered.
+    pub fn register_resource_type(&mut self, resource_type: ResourceType) -> Result<(), SCIMError> {
+        resource_type.validate()?;
+        let extensions = resource_type.schema_extensions.iter().flatten();
+        for uri in core::iter::once(&resource_type.schema).chain(extensions.map(|e| &e.schema)) {
+            if self.schema(uri).is_none() {
+                return Err(SCIMError::SchemaNotFound(uri.as_str().into()));
+            }
+        }
+        if self.resource_type(&resource_type.name).is_some() {
+            return Err(SCIMError::ConflictError(
+                format!("resource type {} is already registered", resource_type.name).into(),
+            ));
+        }
+        self.resource_types.push(resource_type);
+        Ok(())
+    }
+
+    /// Adds values to the canonical values of an attribute of a registered schema, e.g. further e-mail
+    /// `type`s a deployment uses besides `work`, `home` and `other`.
+    ///
+    /// `path` is an attribute name or a dotted sub-attribute path such as `emails.type`, compared
+    /// case-insensitively. Values the attribute already lists are skipped. The schema is changed in place,
+    /// so the values are served by the `/Schemas` endpoint and accepted by
+    /// [`SchemaRegistry::validate_resource`] and [`SchemaRegistry::canonical_value`] from then on.
+    ///
+    /// # Errors
+    ///
+    /// * `SCIMError::SchemaNotFound` - If the schema is not registered.
+    /// * `SCIMError::InvalidFieldValue` - If the schema has no attribute at `path`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::schema_registry::SchemaRegistry;
+    /// use scim_v2::models::schema_uri::USER_URN;
+    /// use serde_json::json;
+    ///
+    /// let mut registry = SchemaRegistry::with_builtins();
+    /// registry.enforce_canonical_values(true);
+    /// let user = json!({
+    ///     "schemas": [USER_URN],
+    ///     "userName": "bjensen",
+    ///     "emails": [{"value": "bjensen@example.com", "type": "contractor"}]
+    /// });
+    /// assert!(registry.validate_resource("User", &user).is_err());
+    ///
+    /// registry.register_canonical_values(USER_URN, "emails.type", ["contractor"]).unwrap();
+    /// assert!(registry.validate_resource("User", &user).is_ok());
+    /// assert_eq!(registry.canonical_value(USER_URN, "emails.type", "Contractor"), Some("contractor"));
+    /// ```
+    pub fn register_canonical_values<I, S>(
+        &mut self,
+        schema: &str,
+        path: &str,
+        values: I,
+    ) -> Result<(), SCIMError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let uri = schema;
+        let schema = self
+            .schemas
+            .iter_mut()
+            .find(|s| s.id.eq_ignore_ascii_case(uri))
+            .ok_or_else(|| SCIMError::SchemaNotFound(uri.into()))?;
+        let (name, sub_name) = match path.split_once('.') {
+            Some((name, sub_name)) => (name, Some(sub_name)),
+            None => (path, None),
+        };
+        let attribute = schema
+            .attributes
+            .iter_mut()
+            .find(|a| a.name.eq_ignore_ascii_case(name));
+        let canonical_values = match (attribute, sub_name) {
+            (Some(attribute), None) => Some(&mut attribute.canonical_values),
+            (Some(attribute), Some(sub_name)) => attribute
+                .sub_attributes
+                .iter_mut()
+                .flatten()
+                .find(|a| a.name.eq_ignore_ascii_case(sub_name))
+                .map(|a| &mut a.canonical_values),
+            (None, _) => None,
+        }
+        .ok_or_else(|| {
+            SCIMError::InvalidFieldValue(format!("schema {} has no attribute {}", uri, path).into())
+                .with_attribute(path)
+        })?;
+        let canonical_values = canonical_values.get_or_insert_with(Vec::new);
+        for value in values {
+            let value = value.into();
+            if !canonical_values
+                .iter()
+                .any(|v| v.eq_ignore_ascii_case(&value))
+            {
+                canonical_values.push(value);
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets whether [`SchemaRegistry::validate_resource`] rejects values outside the canonical values of an
+    /// attribute. It does not by default, as RFC 7643 leaves canonical values advisory.
+    pub fn enforce_canonical_values(&mut self, enforce: bool) {
+        self.enforce_canonical_values = enforce;
+    }
+
+    /// Returns the canonical spelling of `value` for the attribute at `path` of a registered schema, or
+    /// `None` if the attribute does not list it, in which case callers treat the value as "other".
+    /// Values are compared case-insensitively. See [`SchemaRegistry::register_canonical_values`] for the
+    /// path syntax.
+    pub fn canonical_value(&self, schema: &str, path: &str, value: &str) -> Option<&str> {
+        let (name, sub_name) = match path.split_once('.') {
+            Some((name, sub_name)) => (name, Some(sub_name)),
+            None => (path, None),
+        };
+        let attribute = self
+            .schema(schema)?
+            .attributes
+            .iter()
+            .find(|a| a.name.eq_ignore_ascii_case(name))?;
+        let canonical_values = match sub_name {
+            None => attribute.canonical_values.as_ref(),
+            Some(sub_name) => attribute
+                .sub_attributes
+                .iter()
+                .flatten()
+                .find(|a| a.name.eq_ignore_ascii_case(sub_name))?
+                .canonical_values
+                .as_ref(),
+        }?;
+        canonical_values
+            .iter()
+            .find(|v| v.eq_ignore_ascii_case(value))
+            .map(String::as_str)
+    }
+
+    /// Returns the schema with the given id. Schema URIs are compared case-insensitively.
+    pub fn schema(&self, id: &str) -> Option<&Schema> {
+        self.schemas.iter().find(|s| s.id.eq_ignore_ascii_case(id))
+    }
+
+    /// Returns the definition of the attribute `name` in the first of `schemas` that defines it.
+    pub(crate) fn attribute<'s>(
+        &self,
+        schemas: impl IntoIterator<Item = &'s str>,
+        name: &str,
+    ) -> Option<&Attributes> {
+        schemas
+            .into_iter()
+            .filter_map(|id| self.schema(id))
+            .find_map(|schema| {
+                schema
+                    .attributes
+                    .iter()
+                    .find(|a| a.name.eq_ignore_ascii_case(name))
+            })
+    }
+
+    /// Returns `true` if `uri` is the base schema of a registered resource type rather than an extension.
+    pub(crate) fn is_base_schema(&self, uri: &str) -> bool {
+        self.resource_types
+            .iter()
+            .any(|r| r.schema.eq_ignore_ascii_case(uri))
+    }
+
+    /// Returns the schema with the given name, e.g. `User`. Names are compared case-insensitively.
+    pub fn schema_by_name(&self, name: &str) -> Option<&Schema> {
+        self.schemas
+            .iter()
+            .find(|s| s.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the resource type with the given name, e.g. `User`. Names are compared case-insensitively.
+    pub fn resource_type(&self, name: &str) -> Option<&ResourceType> {
+        self.resource_types
+            .iter()
+            .find(|r| r.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the base schema of a registered resource type merged with its extension schemas. See
+    /// [`Schema::merge_extensions`].
+    pub fn merged_schema(&self, resource_type: &str) -> Option<MergedSchema> {
+        let resource_type = self.resource_type(resource_type)?;
+        let extensions: Vec<Schema> = resource_type
+            .schema_extensions
+            .iter()
+            .flatten()
+            .filter_map(|e| self.schema(&e.schema).cloned())
+            .collect();
+        Some(
+            self.schema(&resource_type.schema)?
+                .merge_extensions(&extensions),
+        )
+    }
+
+    /// Returns all registered schemas, in registration order.
+    pub fn schemas(&self) -> &[Schema] {
+        &self.schemas
+    }
+
+    /// Returns all registered resource types, in registration order.
+    pub fn resource_types(&self) -> &[ResourceType] {
+        &self.resource_types
+    }
+
+    /// Builds the `ListResponse` served by the `/Schemas` discovery endpoint.
+    ///
+    /// Every registered schema is listed, with `meta.resourceType` set to `Schema` and `meta.location` pointing
+    /// at `{base_url}/Schemas/{id}`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::others::Resource;
+    /// use scim_v2::models::schema_registry::SchemaRegistry;
+    ///
+    /// let response = SchemaRegistry::with_builtins().schemas_list_response("https://example.com/v2/");
+    /// assert_eq!(response.total_results, 3);
+    /// if let Resource::Schema(schema) = &response.resources[0] {
+    ///     assert_eq!(
+    ///         schema.meta.location.as_deref(),
+    ///         Some("https://example.com/v2/Schemas/urn:ietf:params:scim:schemas:core:2.0:User")
+    ///     );
+    /// }
+    /// ```
+    pub fn schemas_list_response(&self, base_url: &str) -> ListResponse {
+        let base_url = base_url.trim_end_matches('/');
+        let resources = self
+            .schemas
+            .iter()
+            .map(|schema| {
+                let mut schema = schema.clone();
+                schema.meta.resource_type = Some("Schema".to_string());
+                schema.meta.location = Some(format!("{}/Schemas/{}", base_url, schema.id));
+                Resource::Schema(Box::new(schema))
+            })
+            .collect();
+        full_list_response(resources)
+    }
+
+    /// Builds the `ListResponse` served by the `/ResourceTypes` discovery endpoint.
+    ///
+    /// Every registered resource type is listed, with `id` defaulting to its name, `meta.resourceType` set to
+    /// `ResourceType` and `meta.location` pointing at `{base_url}/ResourceTypes/{id}`.
+    pub fn resource_types_list_response(&self, base_url: &str) -> ListResponse {
+        let resources = self
+            .resource_types
+            .iter()
+            .map(|resource_type| Resource::ResourceType(Box::new(locate(resource_type, base_url))))
+            .collect();
+        full_list_response(resources)
+    }
+
+    /// Returns a registered resource type as the `/ResourceTypes/{id}` endpoint serves it: with `id`
+    /// defaulting to its name and `meta.location` pointing at `{base_url}/ResourceTypes/{id}`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::schema_registry::SchemaRegistry;
+    ///
+    /// let registry = SchemaRegistry::with_builtins();
+    /// let user = registry.located_resource_type("User", "https://example.com/v2").unwrap();
+    /// assert_eq!(
+    ///     user.meta.unwrap().location.as_deref(),
+    ///     Some("https://example.com/v2/ResourceTypes/User")
+    /// );
+    /// ```
+    pub fn located_resource_type(&self, name: &str, base_url: &str) -> Option<ResourceType> {
+        self.resource_type(name)
+            .map(|resource_type| locate(resource_type, base_url))
+    }
+
+    /// Validates a resource representation against a registered resource type.
+    ///
+    /// The resource's `schemas` must list the resource type's base schema, must only list schemas the
+    /// resource type declares, and must list every required extension. Every attribute marked as required
+    /// in the base schema, and in each extension the resource carries, must be present. Attribute names are
+    /// matched case-insensitively, as SCIM requires. If [`SchemaRegistry::enforce_canonical_values`] is set,
+    /// string values of attributes that list canonical values must be among them.
+    ///
+    /// # Errors
+    ///
+    /// * `SCIMError::ResourceTypeNotFound` - If the resource type is not registered.
+    /// * `SCIMError::MissingRequiredField` - If `schemas`, a required extension or a required attribute is missing.
+    /// * `SCIMError::InvalidFieldValue` - If `schemas` lacks the base schema or lists an undeclared schema, or
+    ///   if canonical values are enforced and a value is not canonical.
+    pub fn validate_resource(
+        &self,
+        resource_type: &str,
+        resource: &Value,
+    ) -> Result<(), SCIMError> {
+        let resource_type = self.resource_type(resource_type).ok_or_else(|| {
+            SCIMError::ResourceTypeNotFound(resource_type.into()).with_resource_type(resource_type)
+        })?;
+        let listed: Vec<&str> = resource
+            .get("schemas")
+            .and_then(Value::as_array)
+            .ok_or_else(|| {
+                SCIMError::MissingRequiredField("schemas".into()).with_attribute("schemas")
+            })?
+            .iter()
+            .filter_map(Value::as_str)
+            .collect();
+        let extensions: &[SchemaExtension] =
+            resource_type.schema_extensions.as_deref().unwrap_or(&[]);
+
+        if !listed
+            .iter()
+            .any(|s| s.eq_ignore_ascii_case(&resource_type.schema))
+        {
+            return Err(SCIMError::InvalidFieldValue(
+                format!("schemas must contain {}", resource_type.schema).into(),
+            )
+            .with_attribute("schemas"));
+        }
+        for uri in &listed {
+            let declared = uri.eq_ignore_ascii_case(&resource_type.schema)
+                || extensions
+                    .iter()
+                    .any(|e| e.schema.eq_ignore_ascii_case(uri));
+            if !declared {
+                return Err(SCIMError::InvalidFieldValue(
+                    format!(
+                        "schema {} is not declared by resource type {}",
+                        uri, resource_type.name
+                    )
+                    .into(),
+                )
+                .with_attribute("schemas")
+                .with_value(Value::String(uri.to_string())));
+            }
+        }
+        for extension in extensions.iter().filter(|e| e.required) {
+            if !listed
+                .iter()
+                .any(|s| s.eq_ignore_ascii_case(&extension.schema))
+            {
+                return Err(
+                    SCIMError::MissingRequiredField(extension.schema.as_str().into())
+                        .with_attribute(&extension.schema),
+                );
+            }
+        }
+
+        if let Some(schema) = self.schema(&resource_type.schema) {
+            check_required_attributes(schema, resource)?;
+            if self.enforce_canonical_values {
+                check_canonical_values(schema, resource)?;
+            }
+        }
+        for extension in extensions {
+            let body = field(resource, &extension.schema);
+            if let (Some(schema), Some(body)) = (self.schema(&extension.schema), body) {
+                check_required_attributes(schema, body)?;
+                if self.enforce_canonical_values {
+                    check_canonical_values(schema, body)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Registers a schema with the process-wide registry. See [`SchemaRegistry::register_schema`].
+#[cfg(feature = "std")]
+pub fn register_schema(schema: Schema) -> Result<(), SCIMError> {
+    SchemaRegistry::global()
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .register_schema(schema)
+}
+
+/// Registers a resource type with the process-wide registry. See [`SchemaRegistry::register_resource_type`].
+#[cfg(feature = "std")]
+pub fn register_resource_type(resource_type: ResourceType) -> Result<(), SCIMError> {
+    SchemaRegistry::global()
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .register_resource_type(resource_type)
+}
+
+/// Adds canonical values to an attribute of a schema of the process-wide registry. See
+/// [`SchemaRegistry::register_canonical_values`].
+#[cfg(feature = "std")]
+pub fn register_canonical_values<I, S>(schema: &str, path: &str, values: I) -> Result<(), SCIMError>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    SchemaRegistry::global()
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .register_canonical_values(schema, path, values)
+}
+
+/// Takes a read lock on the process-wide registry, recovering it if a writer panicked.
+#[cfg(feature = "std")]
+pub(crate) fn read_global() -> RwLockReadGuard<'static, SchemaRegistry> {
+    SchemaRegistry::global()
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Returns the built-in registry. Without the `std` feature there is no process-wide registry to lock, so
+/// it is built on each call.
+#[cfg(not(feature = "std"))]
+pub(crate) fn read_global() -> SchemaRegistry {
+    SchemaRegistry::with_builtins()
+}
+
+// Discovery endpoints return everything in one page.
+fn full_list_response(resources: Vec<Resource>) -> ListResponse {
+    let total_results = resources.len() as i64;
+    ListResponse::from_page(resources, total_results, 1)
+        .expect("a single page is always consistent")
+}
+
+// The location of a built-in resource type depends on the service provider's base URL, so it is only
+// filled in by `SchemaRegistry::located_resource_type` and the `/ResourceTypes` list response.
+fn builtin_resource_type(
+    name: &str,
+    description: &str,
+    endpoint: &str,
+    schema: &str,
+    schema_extensions: Option<Vec<SchemaExtension>>,
+) -> ResourceType {
+    ResourceType {
+        id: Some(name.to_string()),
+        name: name.to_string(),
+        description: Some(description.to_string()),
+        endpoint: endpoint.to_string(),
+        schema: schema.to_string(),
+        schema_extensions,
+        meta: Some(Meta {
+            resource_type: Some("ResourceType".to_string()),
+            ..Default::default()
+        }),
+    }
+}
+
+// Fills in the `id` and `meta` a resource type is served with from `{base_url}/ResourceTypes/{id}`.
+fn locate(resource_type: &ResourceType, base_url: &str) -> ResourceType {
+    let mut resource_type = resource_type.clone();
+    let id = resource_type
+        .id
+        .get_or_insert_with(|| resource_type.name.clone())
+        .clone();
+    let meta = resource_type.meta.get_or_insert_with(Meta::default);
+    meta.resource_type = Some("ResourceType".to_string());
+    meta.location = Some(format!(
+        "{}/ResourceTypes/{}",
+        base_url.trim_end_matches('/'),
+        id
+    ));
+    resource_type
+}
+
+fn check_required_attributes(schema: &Schema, body: &Value) -> Result<(), SCIMError> {
+    for attribute in schema
+        .attributes
+        .iter()
+        .filter(|a| a.required == Some(true))
+    {
+        if field(body, &attribute.name).is_none_or(Value::is_null) {
+            return Err(
+                SCIMError::MissingRequiredField(attribute.name.as_str().into())
+                    .with_attribute(&attribute.name),
+            );
+        }
+    }
+    Ok(())
+}
+
+// Checks the string values of the attributes and sub-attributes of `schema` that list canonical values.
+fn check_canonical_values(schema: &Schema, body: &Value) -> Result<(), SCIMError> {
+    for attribute in &schema.attributes {
+        let Some(value) = field(body, &attribute.name) else {
+            continue;
+        };
+        if let Some(canonical_values) = &attribute.canonical_values {
+            check_canonical(&attribute.name, canonical_values, value)?;
+        }
+        for sub_attribute in attribute.sub_attributes.iter().flatten() {
+            let Some(canonical_values) = &sub_attribute.canonical_values else {
+                continue;
+            };
+            let path = format!("{}.{}", attribute.name, sub_attribute.name);
+            for element in elements(value) {
+                if let Some(sub_value) = field(element, &sub_attribute.name) {
+                    check_canonical(&path, canonical_values, sub_value)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_canonical(
+    path: &str,
+    canonical_values: &[String],
+    value: &Value,
+) -> Result<(), SCIMError> {
+    for value in elements(value) {
+        let Some(text) = value.as_str() else {
+            continue;
+        };
+        if !canonical_values
+            .iter()
+            .any(|v| v.eq_ignore_ascii_case(text))
+        {
+            return Err(SCIMError::InvalidFieldValue(
+                format!("{} is not a canonical value of {}", text, path).into(),
+            )
+            .with_attribute(path)
+            .with_value(value.clone()));
+        }
+    }
+    Ok(())
+}
+
+// The attribute `name` of an object, matched case-insensitively.
+fn field<'a>(body: &'a Value, name: &str) -> Option<&'a Value> {
+    body.as_object()?
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value)
+}
+
+// The elements of a multi-valued attribute, or the value itself.
+fn elements(value: &Value) -> &[Value] {
+    match value {
+        Value::Array(values) => values,
+        value => core::slice::from_ref(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    const DEVICE_URN: &str = "urn:example:params:scim:schemas:core:1.0:Device";
+
+    fn device_schema() -> Schema {
+        serde_json::from_value(json!({
+            "id": DEVICE_URN,
+            "name": "Device",
+            "description": "Managed device",
+            "attributes": [
+                {"name": "serialNumber", "type": "string", "multiValued": false, "required": true}
+            ],
+            "meta": {"resourceType": "Schema"}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn registers_custom_schema_and_resource_type() {
+        let mut registry = SchemaRegistry::with_builtins();
+        let device = ResourceType {
+            name: "Device".to_string(),
+            endpoint: "/Devices".to_string(),
+            schema: DEVICE_URN.to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            registry.register_resource_type(device.clone()),
+            Err(SCIMError::SchemaNotFound(_))
+        ));
+        registry.register_schema(device_schema()).unwrap();
+        registry.register_resource_type(device.clone()).unwrap();
+
+        assert!(registry.schema_by_name("device").is_some());
+        assert_eq!(registry.resource_type("DEVICE"), Some(&device));
+        assert!(matches!(
+            registry.register_schema(device_schema()),
+            Err(SCIMError::ConflictError(_))
+        ));
+    }
+
+    #[test]
+    fn validates_resources_against_registered_types() {
+        let registry = SchemaRegistry::with_builtins();
+        let user = json!({
+            "schemas": [USER_URN, ENTERPRISE_USER_URN],
+            "UserName": "bjensen",
+            ENTERPRISE_USER_URN: {"employeeNumber": "701984"}
+        });
+        assert!(registry.validate_resource("User", &user).is_ok());
+
+        let missing = json!({"schemas": [USER_URN]});
+        assert!(matches!(
+            registry.validate_resource("User", &missing),
+            Err(SCIMError::MissingRequiredField(field)) if field == "userName"
+        ));
+        let undeclared = json!({"schemas": [GROUP_URN, USER_URN], "userName": "bjensen"});
+        assert!(matches!(
+            registry.validate_resource("User", &undeclared),
+            Err(SCIMError::InvalidFieldValue(_))
+        ));
+        assert!(matches!(
+            registry.validate_resource("Device", &user),
+            Err(SCIMError::ResourceTypeNotFound(_))
+        ));
+
+        let merged = registry.merged_schema("User").unwrap();
+        assert_eq!(
+            merged.get("manager.value").unwrap().schema,
+            ENTERPRISE_USER_URN
+        );
+    }
+
+    #[test]
+    fn enforces_extended_canonical_values() {
+        let mut registry = SchemaRegistry::with_builtins();
+        let user = json!({
+            "schemas": [USER_URN],
+            "userName": "bjensen",
+            "addresses": [{"type": "work"}, {"Type": "Branch"}]
+        });
+        assert!(registry.validate_resource("User", &user).is_ok());
+        registry.enforce_canonical_values(true);
+        let err = registry.validate_resource("User", &user).unwrap_err();
+        assert_eq!(err.attribute(), Some("addresses.type"));
+
+        registry
+            .register_canonical_values(USER_URN, "Addresses.Type", ["branch", "work"])
+            .unwrap();
+        assert!(registry.validate_resource("User", &user).is_ok());
+        let types = registry
+            .schema(USER_URN)
+            .unwrap()
+            .canonical_values("addresses.type");
+        assert_eq!(types.unwrap().iter().filter(|t| *t == "work").count(), 1);
+        assert_eq!(
+            registry.canonical_value(USER_URN, "addresses.type", "Home"),
+            Some("home")
+        );
+        assert_eq!(
+            registry.canonical_value(USER_URN, "userName", "bjensen"),
+            None
+        );
+
+        assert!(matches!(
+            registry.register_canonical_values(USER_URN, "emails.kind", ["x"]),
+            Err(SCIMError::InvalidFieldValue(_))
+        ));
+        assert!(matches!(
+            registry.register_canonical_values(DEVICE_URN, "serialNumber", ["x"]),
+            Err(SCIMError::SchemaNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn builds_discovery_list_responses() {
+        let mut registry = SchemaRegistry::with_builtins();
+        registry.register_schema(device_schema()).unwrap();
+        registry
+            .register_resource_type(ResourceType {
+                name: "Device".to_string(),
+                endpoint: "/Devices".to_string(),
+                schema: DEVICE_URN.to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let schemas = registry.schemas_list_response("https://example.com/v2");
+        assert_eq!(schemas.total_results, 4);
+        assert_eq!(schemas.items_per_page, 4);
+
+        let resource_types = registry.resource_types_list_response("https://example.com/v2/");
+        let Resource::ResourceType(device) = &resource_types.resources[2] else {
+            panic!("expected a resource type");
+        };
+        assert_eq!(device.id.as_deref(), Some("Device"));
+        let meta = device.meta.as_ref().unwrap();
+        assert_eq!(
+            meta.location.as_deref(),
+            Some("https://example.com/v2/ResourceTypes/Device")
+        );
+        assert_eq!(meta.resource_type.as_deref(), Some("ResourceType"));
+    }
+}
@@ -0,0 +1,185 @@
+use serde_json::{Map, Value, json};
+
+use crate::models::scim_schema::{Attributes, Schema};
+
+/// Generates an example resource for a schema and its extensions.
+///
+/// Every attribute is populated with a plausible value for its type: canonical values are preferred, well-known
+/// attribute names (e-mail addresses, phone numbers, names, ...) get realistic values, and multi-valued
+/// attributes hold a single entry. Attributes that are never returned by a service provider, such as
+/// `password`, are left out, so the result looks like a resource served by a `GET` request. The output is
+/// deterministic, which makes it suitable for documentation endpoints, contract tests and seed data.
+///
+/// # Parameters
+///
+/// * `schema` - The base schema of the resource.
+/// * `extensions` - Extension schemas to populate under their schema URIs.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::schema_sample::sample_resource;
+/// use scim_v2::models::scim_schema::get_schemas;
+/// use scim_v2::models::user::User;
+///
+/// let schemas = get_schemas(vec!["user", "enterprise_user"]).unwrap();
+/// let sample = sample_resource(&schemas[0], &schemas[1..]);
+///
+/// let user = User::try_from(sample.clone()).unwrap();
+/// assert!(user.validate().is_ok());
+/// assert_eq!(sample["emails"][0]["value"], "bjensen@example.com");
+/// ```
+pub fn sample_resource(schema: &Schema, extensions: &[Schema]) -> Value {
+    let mut resource = Map::new();
+    let schemas = std::iter::once(schema)
+        .chain(extensions)
+        .map(|s| Value::String(s.id.clone()))
+        .collect();
+    resource.insert("schemas".to_string(), Value::Array(schemas));
+    resource.extend(sample_attributes(None, &schema.attributes));
+    for extension in extensions {
+        resource.insert(
+            extension.id.clone(),
+            Value::Object(sample_attributes(None, &extension.attributes)),
+        );
+    }
+    Value::Object(resource)
+}
+
+fn sample_attributes(parent: Option<&str>, attributes: &[Attributes]) -> Map<String, Value> {
+    attributes
+        .iter()
+        .filter(|a| a.returned.as_deref() != Some("never"))
+        .map(|a| (a.name.clone(), sample_attribute(parent, a)))
+        .collect()
+}
+
+fn sample_attribute(parent: Option<&str>, attribute: &Attributes) -> Value {
+    let value = if attribute.r#type == "complex" {
+        let sub_attributes: Vec<Attributes> = attribute
+            .sub_attributes
+            .iter()
+            .flatten()
+            .cloned()
+            .map(Attributes::from)
+            .collect();
+        let mut object = sample_attributes(Some(&attribute.name), &sub_attributes);
+        // Reference sub-attributes describe the same entity as `value`; keep them consistent.
+        if let Some(Value::String(value)) = object.get("value") {
+            if object.contains_key("$ref") && !value.contains("://") {
+                let endpoint = if attribute.name == "groups" {
+                    "Groups"
+                } else {
+                    "Users"
+                };
+                let reference = format!("https://example.com/v2/{}/{}", endpoint, value);
+                object.insert("$ref".to_string(), Value::String(reference));
+            }
+        }
+        Value::Object(object)
+    } else {
+        sample_scalar(parent, attribute)
+    };
+    if attribute.multi_valued {
+        Value::Array(vec![value])
+    } else {
+        value
+    }
+}
+
+fn sample_scalar(parent: Option<&str>, attribute: &Attributes) -> Value {
+    if let Some(value) = attribute.canonical_values.iter().flatten().next() {
+        return Value::String(value.clone());
+    }
+    match attribute.r#type.as_str() {
+        "boolean" => Value::Bool(true),
+        "integer" => json!(1),
+        "decimal" => json!(1.5),
+        "dateTime" => json!("2011-08-01T18:29:49Z"),
+        "binary" => json!("TUlJRENqQ0NBZmFnQXdJQkFnSUJBREFOQmdrcWhraUc5dzBCQVFRRkFEQTVNUXN3"),
+        "reference" => {
+            let types = attribute.reference_types.as_deref().unwrap_or_default();
+            if types.iter().any(|t| t == "User" || t == "Group") {
+                json!("https://example.com/v2/Users/2819c223-7f76-453a-919d-413861904646")
+            } else {
+                json!(format!(
+                    "https://example.com/{}",
+                    attribute.name.trim_start_matches('$')
+                ))
+            }
+        }
+        _ => Value::String(sample_string(parent, &attribute.name)),
+    }
+}
+
+fn sample_string(parent: Option<&str>, name: &str) -> String {
+    let value = match (parent, name) {
+        (Some("emails"), "value") => "bjensen@example.com",
+        (Some("phoneNumbers"), "value") => "+1 555 555 5555",
+        (Some("ims"), "value") => "babs_jensen",
+        (Some("entitlements" | "roles"), "value") => "Tour Operator",
+        (_, name) => sample_name(name),
+    };
+    value.to_string()
+}
+
+fn sample_name(name: &str) -> &'static str {
+    match name {
+        "userName" => "bjensen",
+        "displayName" => "Babs Jensen",
+        "nickName" => "Babs",
+        "formatted" => "Ms. Barbara J Jensen, III",
+        "familyName" => "Jensen",
+        "givenName" => "Barbara",
+        "middleName" => "Jane",
+        "honorificPrefix" => "Ms.",
+        "honorificSuffix" => "III",
+        "title" => "Tour Guide",
+        "userType" => "Employee",
+        "preferredLanguage" | "locale" => "en-US",
+        "timezone" => "America/Los_Angeles",
+        "externalId" => "701984",
+        "value" | "id" => "2819c223-7f76-453a-919d-413861904646",
+        "display" => "Babs Jensen",
+        "streetAddress" => "100 Universal City Plaza",
+        "locality" => "Hollywood",
+        "region" => "CA",
+        "postalCode" => "91608",
+        "country" => "US",
+        "employeeNumber" => "701984",
+        "costCenter" => "4130",
+        "organization" => "Universal Studios",
+        "division" => "Theme Park",
+        "department" => "Tour Operations",
+        _ => "example",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::group::Group;
+    use crate::models::scim_schema::get_schemas;
+    use crate::models::user::User;
+
+    #[test]
+    fn sample_user_is_a_valid_user() {
+        let schemas = get_schemas(vec!["user"]).unwrap();
+        let sample = sample_resource(&schemas[0], &[]);
+        assert!(sample.get("password").is_none());
+        assert_eq!(sample["phoneNumbers"][0]["type"], "work");
+
+        let user = User::try_from(sample).unwrap();
+        assert!(user.validate().is_ok());
+        assert_eq!(user.user_name, "bjensen");
+    }
+
+    #[test]
+    fn sample_group_is_a_valid_group() {
+        let schemas = get_schemas(vec!["group"]).unwrap();
+        let sample = sample_resource(&schemas[0], &[]);
+        let group = Group::try_from(sample.clone()).unwrap();
+        assert!(group.validate().is_ok());
+        assert_eq!(sample, sample_resource(&schemas[0], &[]));
+    }
+}
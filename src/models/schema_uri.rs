@@ -0,0 +1,406 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use core::fmt;
+use core::fmt::{Display, Formatter};
+use core::str::FromStr;
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::utils::error::SCIMError;
+
+/// Schema URI of the core User resource.
+pub const USER_URN: &str = "urn:ietf:params:scim:schemas:core:2.0:User";
+/// Schema URI of the core Group resource.
+pub const GROUP_URN: &str = "urn:ietf:params:scim:schemas:core:2.0:Group";
+/// Schema URI of the Enterprise User extension.
+pub const ENTERPRISE_USER_URN: &str = "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User";
+/// Schema URI of the password synchronization extension defined by this crate (see
+/// [`PasswordSync`](crate::models::password::PasswordSync)); it is not part of RFC 7643.
+pub const PASSWORD_SYNC_URN: &str = "urn:ietf:params:scim:schemas:extension:passwordSync:2.0:User";
+/// Schema URI of the ResourceType resource.
+pub const RESOURCE_TYPE_URN: &str = "urn:ietf:params:scim:schemas:core:2.0:ResourceType";
+/// Schema URI of the Schema resource.
+pub const SCHEMA_URN: &str = "urn:ietf:params:scim:schemas:core:2.0:Schema";
+/// Schema URI of the ServiceProviderConfig resource.
+pub const SERVICE_PROVIDER_CONFIG_URN: &str =
+    "urn:ietf:params:scim:schemas:core:2.0:ServiceProviderConfig";
+/// Message URI of a PATCH request body.
+pub const PATCH_OP_URN: &str = "urn:ietf:params:scim:api:messages:2.0:PatchOp";
+/// Message URI of a list response.
+pub const LIST_RESPONSE_URN: &str = "urn:ietf:params:scim:api:messages:2.0:ListResponse";
+/// Message URI of a `/.search` request body.
+pub const SEARCH_REQUEST_URN: &str = "urn:ietf:params:scim:api:messages:2.0:SearchRequest";
+/// Message URI of an error response.
+pub const ERROR_URN: &str = "urn:ietf:params:scim:api:messages:2.0:Error";
+/// Message URI of a bulk request.
+pub const BULK_REQUEST_URN: &str = "urn:ietf:params:scim:api:messages:2.0:BulkRequest";
+/// Message URI of a bulk response.
+pub const BULK_RESPONSE_URN: &str = "urn:ietf:params:scim:api:messages:2.0:BulkResponse";
+
+/// Represents a validated schema URI.
+///
+/// A `SchemaUri` is guaranteed to be a URN of the form `urn:<namespace>:<specific-string>` without whitespace,
+/// which is what SCIM uses to identify schemas and message types. Use it when accepting schema URIs from
+/// configuration or user input; the well-known URIs are available as constants in this module.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::schema_uri::{SchemaUri, USER_URN};
+///
+/// let uri: SchemaUri = USER_URN.parse().unwrap();
+/// assert_eq!(uri, USER_URN);
+/// assert!("not a urn".parse::<SchemaUri>().is_err());
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(try_from = "String", into = "String")]
+pub struct SchemaUri(String);
+
+impl SchemaUri {
+    /// Returns the URI as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+fn validate_schema_uri(uri: &str) -> Result<(), SCIMError> {
+    let mut parts = uri.splitn(3, ':');
+    let scheme = parts.next().unwrap_or_default();
+    let namespace = parts.next().unwrap_or_default();
+    let specific = parts.next().unwrap_or_default();
+    if !scheme.eq_ignore_ascii_case("urn")
+        || namespace.is_empty()
+        || specific.is_empty()
+        || uri.chars().any(char::is_whitespace)
+    {
+        return Err(SCIMError::InvalidFieldValue(
+            format!("'{}' is not a valid schema URI", uri).into(),
+        ));
+    }
+    Ok(())
+}
+
+impl TryFrom<String> for SchemaUri {
+    type Error = SCIMError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        validate_schema_uri(&value)?;
+        Ok(SchemaUri(value))
+    }
+}
+
+impl TryFrom<&str> for SchemaUri {
+    type Error = SCIMError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        SchemaUri::try_from(value.to_string())
+    }
+}
+
+impl FromStr for SchemaUri {
+    type Err = SCIMError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SchemaUri::try_from(s)
+    }
+}
+
+impl From<SchemaUri> for String {
+    fn from(value: SchemaUri) -> Self {
+        value.0
+    }
+}
+
+impl AsRef<str> for SchemaUri {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<str> for SchemaUri {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for SchemaUri {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl Display for SchemaUri {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A schema or message URN, with the well-known URNs represented without a string.
+///
+/// Comparing and cloning a `KnownUrn` never copies the URN: the well-known URNs are plain enum variants and
+/// other URNs are shared through an `Arc<str>`. Parsing recognizes the well-known URNs case-insensitively,
+/// as SCIM compares schema URIs without regard to case, and only allocates for other URNs.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::schema_uri::{KnownUrn, USER_URN};
+///
+/// let schemas: Vec<KnownUrn> = serde_json::from_str(
+///     r#"["urn:ietf:params:scim:schemas:core:2.0:User", "urn:example:params:scim:schemas:extension:badge:2.0:User"]"#,
+/// )
+/// .unwrap();
+/// assert_eq!(schemas[0], KnownUrn::User);
+/// assert_eq!(schemas[0], USER_URN);
+/// assert!(matches!(&schemas[1], KnownUrn::Custom(urn) if urn.ends_with("badge:2.0:User")));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum KnownUrn {
+    /// `urn:ietf:params:scim:schemas:core:2.0:User`
+    User,
+    /// `urn:ietf:params:scim:schemas:core:2.0:Group`
+    Group,
+    /// `urn:ietf:params:scim:schemas:extension:enterprise:2.0:User`
+    EnterpriseUser,
+    /// `urn:ietf:params:scim:schemas:core:2.0:ResourceType`
+    ResourceType,
+    /// `urn:ietf:params:scim:schemas:core:2.0:Schema`
+    Schema,
+    /// `urn:ietf:params:scim:schemas:core:2.0:ServiceProviderConfig`
+    ServiceProviderConfig,
+    /// `urn:ietf:params:scim:api:messages:2.0:PatchOp`
+    PatchOp,
+    /// `urn:ietf:params:scim:api:messages:2.0:ListResponse`
+    ListResponse,
+    /// `urn:ietf:params:scim:api:messages:2.0:SearchRequest`
+    SearchRequest,
+    /// `urn:ietf:params:scim:api:messages:2.0:Error`
+    Error,
+    /// `urn:ietf:params:scim:api:messages:2.0:BulkRequest`
+    BulkRequest,
+    /// `urn:ietf:params:scim:api:messages:2.0:BulkResponse`
+    BulkResponse,
+    /// Any other URN, such as the URN of a custom schema extension.
+    Custom(Arc<str>),
+}
+
+const KNOWN_URNS: [(&str, KnownUrn); 12] = [
+    (USER_URN, KnownUrn::User),
+    (GROUP_URN, KnownUrn::Group),
+    (ENTERPRISE_USER_URN, KnownUrn::EnterpriseUser),
+    (RESOURCE_TYPE_URN, KnownUrn::ResourceType),
+    (SCHEMA_URN, KnownUrn::Schema),
+    (SERVICE_PROVIDER_CONFIG_URN, KnownUrn::ServiceProviderConfig),
+    (PATCH_OP_URN, KnownUrn::PatchOp),
+    (LIST_RESPONSE_URN, KnownUrn::ListResponse),
+    (SEARCH_REQUEST_URN, KnownUrn::SearchRequest),
+    (ERROR_URN, KnownUrn::Error),
+    (BULK_REQUEST_URN, KnownUrn::BulkRequest),
+    (BULK_RESPONSE_URN, KnownUrn::BulkResponse),
+];
+
+impl KnownUrn {
+    /// Returns the URN as a string slice.
+    pub fn as_str(&self) -> &str {
+        match self {
+            KnownUrn::User => USER_URN,
+            KnownUrn::Group => GROUP_URN,
+            KnownUrn::EnterpriseUser => ENTERPRISE_USER_URN,
+            KnownUrn::ResourceType => RESOURCE_TYPE_URN,
+            KnownUrn::Schema => SCHEMA_URN,
+            KnownUrn::ServiceProviderConfig => SERVICE_PROVIDER_CONFIG_URN,
+            KnownUrn::PatchOp => PATCH_OP_URN,
+            KnownUrn::ListResponse => LIST_RESPONSE_URN,
+            KnownUrn::SearchRequest => SEARCH_REQUEST_URN,
+            KnownUrn::Error => ERROR_URN,
+            KnownUrn::BulkRequest => BULK_REQUEST_URN,
+            KnownUrn::BulkResponse => BULK_RESPONSE_URN,
+            KnownUrn::Custom(urn) => urn,
+        }
+    }
+
+    /// Returns the well-known URN equal to `urn`, ignoring case.
+    pub fn known(urn: &str) -> Option<Self> {
+        KNOWN_URNS
+            .iter()
+            .find(|(known, _)| known.eq_ignore_ascii_case(urn))
+            .map(|(_, variant)| variant.clone())
+    }
+}
+
+impl From<&str> for KnownUrn {
+    fn from(value: &str) -> Self {
+        KnownUrn::known(value).unwrap_or_else(|| KnownUrn::Custom(Arc::from(value)))
+    }
+}
+
+impl From<String> for KnownUrn {
+    fn from(value: String) -> Self {
+        KnownUrn::known(&value).unwrap_or_else(|| KnownUrn::Custom(Arc::from(value)))
+    }
+}
+
+impl From<&KnownUrn> for String {
+    fn from(value: &KnownUrn) -> Self {
+        value.as_str().to_string()
+    }
+}
+
+impl From<KnownUrn> for String {
+    fn from(value: KnownUrn) -> Self {
+        value.as_str().to_string()
+    }
+}
+
+impl FromStr for KnownUrn {
+    type Err = core::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(KnownUrn::from(s))
+    }
+}
+
+impl AsRef<str> for KnownUrn {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl PartialEq<str> for KnownUrn {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for KnownUrn {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for KnownUrn {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl Display for KnownUrn {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for KnownUrn {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+// Deserializes from the borrowed string where possible, so well-known URNs are recognized without allocating.
+impl<'de> Deserialize<'de> for KnownUrn {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct KnownUrnVisitor;
+
+        impl Visitor<'_> for KnownUrnVisitor {
+            type Value = KnownUrn;
+
+            fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                f.write_str("a URN")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                Ok(KnownUrn::from(value))
+            }
+
+            fn visit_string<E: de::Error>(self, value: String) -> Result<Self::Value, E> {
+                Ok(KnownUrn::from(value))
+            }
+        }
+
+        deserializer.deserialize_str(KnownUrnVisitor)
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for KnownUrn {
+    fn schema_name() -> alloc::borrow::Cow<'static, str> {
+        "KnownUrn".into()
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        String::json_schema(generator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema_uri_accepts_well_known_urns() {
+        for urn in [
+            USER_URN,
+            GROUP_URN,
+            ENTERPRISE_USER_URN,
+            PATCH_OP_URN,
+            ERROR_URN,
+        ] {
+            assert!(SchemaUri::try_from(urn).is_ok(), "{} should be valid", urn);
+        }
+    }
+
+    #[test]
+    fn schema_uri_rejects_malformed_values() {
+        for uri in [
+            "",
+            "urn:",
+            "urn:ietf",
+            "http://example.com",
+            "urn:ietf:params: scim",
+        ] {
+            assert!(
+                SchemaUri::try_from(uri).is_err(),
+                "{} should be invalid",
+                uri
+            );
+        }
+    }
+
+    #[test]
+    fn schema_uri_deserialization_validates() {
+        let uri: Result<SchemaUri, _> = serde_json::from_str(r#""urn:example:custom:1.0""#);
+        assert!(uri.is_ok());
+        let uri: Result<SchemaUri, _> = serde_json::from_str(r#""custom""#);
+        assert!(uri.is_err());
+    }
+
+    #[test]
+    fn known_urn_recognizes_well_known_urns_ignoring_case() {
+        assert_eq!(KnownUrn::from(USER_URN), KnownUrn::User);
+        assert_eq!(
+            KnownUrn::from("URN:IETF:PARAMS:SCIM:SCHEMAS:CORE:2.0:GROUP"),
+            KnownUrn::Group
+        );
+        for (urn, variant) in KNOWN_URNS {
+            assert_eq!(variant.as_str(), urn);
+        }
+    }
+
+    #[test]
+    fn known_urn_round_trips_custom_urns() {
+        let urn = KnownUrn::from("urn:example:params:scim:schemas:extension:badge:2.0:User");
+        let copy = urn.clone();
+        assert!(
+            matches!((&urn, &copy), (KnownUrn::Custom(a), KnownUrn::Custom(b)) if Arc::ptr_eq(a, b))
+        );
+        let json = serde_json::to_string(&urn).unwrap();
+        assert_eq!(
+            json,
+            r#""urn:example:params:scim:schemas:extension:badge:2.0:User""#
+        );
+        assert_eq!(serde_json::from_str::<KnownUrn>(&json).unwrap(), urn);
+    }
+}
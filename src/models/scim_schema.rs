@@ -1,9 +1,25 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io::Write;
+
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
+#[cfg(feature = "std")]
+use crate::models::resource_types::ResourceType;
+use crate::models::schema_registry::{SchemaRegistry, read_global};
+use crate::models::schema_uri::{ENTERPRISE_USER_URN, GROUP_URN, SchemaUri, USER_URN};
 use crate::utils::error::SCIMError;
-use crate::{ENTERPRISE_USER_SCHEMA, GROUP_SCHEMA, USER_SCHEMA};
+#[cfg(feature = "std")]
+use crate::utils::etag::content_version;
+use crate::utils::json;
+#[cfg(feature = "std")]
+use crate::utils::time::now_rfc3339;
 
-#[derive(Serialize, Deserialize, Debug, Default)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Meta {
     #[serde(rename = "resourceType", skip_serializing_if = "Option::is_none")]
     pub resource_type: Option<String>,
@@ -17,7 +33,80 @@ pub struct Meta {
     pub location: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl Meta {
+    /// Creates the `Meta` for a newly created resource.
+    ///
+    /// Sets `resourceType` to the name of `resource_type`, sets `created` and `lastModified` to the current
+    /// time, and derives `location` from the service provider's base URL and the resource type's endpoint.
+    /// The `version` is left unset until [`Meta::touch`] is called with the resource content.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::schema_registry::SchemaRegistry;
+    /// use scim_v2::models::scim_schema::Meta;
+    ///
+    /// let registry = SchemaRegistry::with_builtins();
+    /// let user = registry.resource_type("User").unwrap();
+    /// let meta = Meta::for_resource(user, "https://example.com/v2/", "2819c223");
+    /// assert_eq!(meta.location.as_deref(), Some("https://example.com/v2/Users/2819c223"));
+    /// assert_eq!(meta.created, meta.last_modified);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn for_resource(resource_type: &ResourceType, base_url: &str, id: &str) -> Self {
+        let now = now_rfc3339();
+        Meta {
+            resource_type: Some(resource_type.name.clone()),
+            created: Some(now.clone()),
+            last_modified: Some(now),
+            version: None,
+            location: Some(format!(
+                "{}/{}/{}",
+                base_url.trim_end_matches('/'),
+                resource_type.endpoint.trim_matches('/'),
+                id
+            )),
+        }
+    }
+
+    /// Marks the resource as modified.
+    ///
+    /// Sets `lastModified` to the current time and recomputes `version` as a weak ETag over the canonical
+    /// content of `resource` (see [`content_version`]). The resource's own `meta` attribute is excluded from
+    /// the hash, so it is fine to pass the resource that owns this `Meta`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if the resource cannot be serialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::schema_registry::SchemaRegistry;
+    /// use scim_v2::models::scim_schema::Meta;
+    /// use scim_v2::models::user::User;
+    ///
+    /// let mut user = User {
+    ///     user_name: "jdoe@example.com".to_string(),
+    ///     ..Default::default()
+    /// };
+    /// let registry = SchemaRegistry::with_builtins();
+    /// let user_type = registry.resource_type("User").unwrap();
+    /// let mut meta = Meta::for_resource(user_type, "https://example.com/v2", "2819c223");
+    /// meta.touch(&user).unwrap();
+    /// user.meta = Some(meta);
+    /// assert!(user.meta.unwrap().version.unwrap().starts_with("W/"));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn touch<T: Serialize>(&mut self, resource: &T) -> Result<(), SCIMError> {
+        self.version = Some(content_version(resource)?);
+        self.last_modified = Some(now_rfc3339());
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Schema {
     pub id: String,
     pub name: String,
@@ -26,7 +115,8 @@ pub struct Schema {
     pub meta: Meta,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Attributes {
     pub name: String,
     pub r#type: String,
@@ -52,7 +142,8 @@ pub struct Attributes {
     pub reference_types: Option<Vec<String>>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct SubAttributes {
     pub name: String,
     pub r#type: String,
@@ -76,10 +167,32 @@ pub struct SubAttributes {
     pub reference_types: Option<Vec<String>>,
 }
 
+impl From<SubAttributes> for Attributes {
+    fn from(value: SubAttributes) -> Self {
+        Attributes {
+            name: value.name,
+            r#type: value.r#type,
+            multi_valued: value.multi_valued,
+            description: value.description,
+            required: value.required,
+            canonical_values: value.canonical_values,
+            case_exact: value.case_exact,
+            mutability: value.mutability,
+            returned: value.returned,
+            uniqueness: value.uniqueness,
+            sub_attributes: None,
+            reference_types: value.reference_types,
+        }
+    }
+}
+
 /// Retrieves a list of `Schema` instances based on the provided schema names.
 ///
 /// This function takes a vector of schema names as input and attempts to retrieve the corresponding `Schema` instances.
-/// It uses a predefined list of schema contents to match the input schema names.
+/// The built-in schemas are available as `user`, `enterprise_user` and `group`. Names starting with `urn:` are looked
+/// up by schema URI, e.g. `urn:ietf:params:scim:schemas:core:2.0:User`; any other name is looked up by schema
+/// name. Both lookups are case-insensitive and go through the process-wide [`SchemaRegistry`](crate::models::schema_registry::SchemaRegistry),
+/// so schemas registered at runtime can be retrieved as well.
 ///
 /// # Parameters
 ///
@@ -93,7 +206,7 @@ pub struct SubAttributes {
 ///
 /// # Errors
 ///
-/// This function will return an error if any of the provided schema names do not match any of the predefined schema contents.
+/// This function will return an error if any of the provided schema names do not match a built-in or registered schema.
 /// The error will be of type `SCIMError::SchemaNotFound` and will contain the name of the schema that could not be found.
 ///
 /// # Examples
@@ -101,35 +214,142 @@ pub struct SubAttributes {
 /// ```rust
 /// use scim_v2::models::scim_schema::get_schemas;
 ///
-/// let schemas = get_schemas(vec!["user", "group"]);
+/// let schemas = get_schemas(vec!["user", "urn:ietf:params:scim:schemas:core:2.0:Group"]);
 /// match schemas {
 ///     Ok(schemas) => println!("Successfully retrieved schemas: {:?}", schemas),
 ///     Err(e) => println!("Error retrieving schemas: {}", e),
 /// }
 /// ```
 pub fn get_schemas(schema_names: Vec<&str>) -> Result<Vec<Schema>, SCIMError> {
-    let mut schemas = Vec::new();
+    schemas_in(&read_global(), schema_names)
+}
 
-    let schema_contents = [
-        ("user", USER_SCHEMA),
-        ("enterprise_user", ENTERPRISE_USER_SCHEMA),
-        ("group", GROUP_SCHEMA),
-    ]
-    .iter()
-    .cloned()
-    .collect::<std::collections::HashMap<_, _>>();
+// Looks up schemas by name or URI in `registry`, as `get_schemas` does in the process-wide registry.
+fn schemas_in(
+    registry: &SchemaRegistry,
+    schema_names: Vec<&str>,
+) -> Result<Vec<Schema>, SCIMError> {
+    let mut schemas = Vec::new();
 
     for schema_name in schema_names {
-        if let Some(schema_content) = schema_contents.get(schema_name) {
-            let schema: Schema = serde_json::from_str(schema_content)?;
-            schemas.push(schema);
-        } else {
-            return Err(SCIMError::SchemaNotFound(schema_name.to_string()));
+        let schema = match schema_name {
+            "user" => registry.schema(USER_URN),
+            "enterprise_user" => registry.schema(ENTERPRISE_USER_URN),
+            "group" => registry.schema(GROUP_URN),
+            uri if is_urn(uri) => registry.schema(uri),
+            name => registry.schema_by_name(name),
+        };
+        match schema {
+            Some(schema) => schemas.push(schema.clone()),
+            None => return Err(SCIMError::SchemaNotFound(schema_name.into())),
         }
     }
     Ok(schemas)
 }
 
+/// Retrieves a single `Schema` by its schema URI.
+///
+/// This is the lookup a `/Schemas/{id}` endpoint needs: `id` is the schema's canonical URI, compared
+/// case-insensitively against the built-in schemas and those registered with the process-wide
+/// [`SchemaRegistry`](crate::models::schema_registry::SchemaRegistry).
+///
+/// # Parameters
+///
+/// * `urn` - The schema URI, e.g. `urn:ietf:params:scim:schemas:core:2.0:User`.
+///
+/// # Errors
+///
+/// Returns `SCIMError::SchemaNotFound` if no schema with the given URI is known.
+///
+/// # Examples
+///
+/// ```rust
+/// use scim_v2::models::scim_schema::get_schema;
+///
+/// let schema = get_schema("urn:ietf:params:scim:schemas:core:2.0:User").unwrap();
+/// assert_eq!(schema.name, "User");
+/// assert!(get_schema("urn:example:unknown").is_err());
+/// ```
+pub fn get_schema(urn: &str) -> Result<Schema, SCIMError> {
+    read_global()
+        .schema(urn)
+        .cloned()
+        .ok_or_else(|| SCIMError::SchemaNotFound(urn.into()))
+}
+
+const ATTRIBUTE_TYPES: [&str; 8] = [
+    "string",
+    "boolean",
+    "decimal",
+    "integer",
+    "dateTime",
+    "binary",
+    "reference",
+    "complex",
+];
+const MUTABILITY_VALUES: [&str; 4] = ["readOnly", "readWrite", "immutable", "writeOnly"];
+const RETURNED_VALUES: [&str; 4] = ["always", "never", "default", "request"];
+const UNIQUENESS_VALUES: [&str; 3] = ["none", "server", "global"];
+
+// ATTRNAME = ALPHA *(nameChar), nameChar = "-" / "_" / DIGIT / ALPHA (RFC 7643, section 2.1).
+fn check_attribute_name(path: &str, name: &str, allow_ref: bool) -> Result<(), SCIMError> {
+    let valid = (allow_ref && name == "$ref")
+        || (name.starts_with(|c: char| c.is_ascii_alphabetic())
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+    if !valid {
+        return Err(SCIMError::InvalidFieldValue(
+            format!("{}: '{}' is not a valid attribute name", path, name).into(),
+        )
+        .with_attribute(path));
+    }
+    Ok(())
+}
+
+fn check_unique<'a>(seen: &mut Vec<&'a str>, path: &str, name: &'a str) -> Result<(), SCIMError> {
+    if seen.iter().any(|s| s.eq_ignore_ascii_case(name)) {
+        return Err(SCIMError::InvalidFieldValue(
+            format!("{}: duplicate attribute name", path).into(),
+        )
+        .with_attribute(path));
+    }
+    seen.push(name);
+    Ok(())
+}
+
+fn check_characteristics(
+    path: &str,
+    r#type: &str,
+    mutability: Option<&str>,
+    returned: Option<&str>,
+    uniqueness: Option<&str>,
+) -> Result<(), SCIMError> {
+    let checks = [
+        ("type", Some(r#type), &ATTRIBUTE_TYPES[..]),
+        ("mutability", mutability, &MUTABILITY_VALUES[..]),
+        ("returned", returned, &RETURNED_VALUES[..]),
+        ("uniqueness", uniqueness, &UNIQUENESS_VALUES[..]),
+    ];
+    for (characteristic, value, allowed) in checks {
+        if let Some(value) = value {
+            if !allowed.contains(&value) {
+                return Err(SCIMError::InvalidFieldValue(
+                    format!("{}: '{}' is not a valid {}", path, value, characteristic).into(),
+                )
+                .with_attribute(path)
+                .with_value(Value::String(value.to_string())));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_urn(name: &str) -> bool {
+    name.get(..4)
+        .is_some_and(|scheme| scheme.eq_ignore_ascii_case("urn:"))
+}
+
 /// Converts a JSON string into a `Schema` struct.
 ///
 /// This method attempts to parse a JSON string to construct a `Schema` object. It's useful for scenarios where
@@ -260,7 +480,137 @@ impl TryFrom<&str> for Schema {
     }
 }
 
+/// Converts an already-parsed JSON value into a `Schema` struct.
+///
+/// Web frameworks usually hand over request bodies as a `serde_json::Value`; this conversion avoids
+/// round-tripping that value through a string.
+///
+/// # Errors
+///
+/// Returns `SCIMError::DeserializationError` if the value does not describe a `Schema` object.
+///
+/// # Examples
+///
+/// ```rust
+/// use scim_v2::models::scim_schema::Schema;
+/// use serde_json::json;
+///
+/// let value = json!({"id": "urn:example:custom:1.0:Device", "name": "Device", "description": "Device", "attributes": [], "meta": {"resourceType": "Schema"}});
+/// match Schema::try_from(value) {
+///     Ok(resource) => println!("Successfully converted JSON to Schema: {:?}", resource),
+///     Err(e) => println!("Error converting from JSON to Schema: {}", e),
+/// }
+/// ```
+impl TryFrom<Value> for Schema {
+    type Error = SCIMError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        serde_json::from_value(value).map_err(SCIMError::DeserializationError)
+    }
+}
+
+/// Converts a `Schema` struct into a JSON value.
+///
+/// # Errors
+///
+/// Returns `SCIMError::SerializationError` if the `Schema` cannot be represented as JSON.
+impl TryFrom<Schema> for Value {
+    type Error = SCIMError;
+
+    fn try_from(value: Schema) -> Result<Self, Self::Error> {
+        serde_json::to_value(value).map_err(SCIMError::SerializationError)
+    }
+}
+
 impl Schema {
+    /// Validates a schema definition against the rules of RFC 7643, section 7.
+    ///
+    /// This checks that the schema id is a URN, that every attribute and sub-attribute name is a valid
+    /// attribute name (`$ref` is also accepted for sub-attributes), that names are unique within their
+    /// level, that `type`, `mutability`, `returned` and `uniqueness` use one of the values defined by the
+    /// specification, and that complex attributes declare sub-attributes which are not themselves complex.
+    /// Use it to vet schemas loaded at runtime before handing them to a
+    /// [`SchemaRegistry`](crate::models::schema_registry::SchemaRegistry), which runs it on registration.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the schema is valid.
+    /// * `Err(SCIMError::MissingRequiredField)` - If the schema id or a complex attribute's sub-attributes are missing.
+    /// * `Err(SCIMError::InvalidFieldValue)` - If a name or characteristic has an invalid value. The message names
+    ///   the offending attribute.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use scim_v2::models::scim_schema::Schema;
+    /// use serde_json::json;
+    ///
+    /// let schema = Schema::try_from(json!({
+    ///     "id": "urn:example:params:scim:schemas:core:1.0:Device",
+    ///     "name": "Device",
+    ///     "description": "Managed device",
+    ///     "attributes": [
+    ///         {"name": "serialNumber", "type": "string", "multiValued": false, "mutability": "immutable"},
+    ///         {"name": "owner", "type": "complex", "multiValued": false}
+    ///     ],
+    ///     "meta": {"resourceType": "Schema"}
+    /// }))
+    /// .unwrap();
+    ///
+    /// match schema.validate() {
+    ///     Ok(_) => println!("Schema is valid."),
+    ///     Err(e) => println!("Schema is invalid: {}", e),
+    /// }
+    /// ```
+    pub fn validate(&self) -> Result<(), SCIMError> {
+        if self.id.is_empty() {
+            return Err(SCIMError::MissingRequiredField("id".into()));
+        }
+        SchemaUri::try_from(self.id.as_str())?;
+
+        let mut seen: Vec<&str> = Vec::new();
+        for attribute in &self.attributes {
+            let path = attribute.name.as_str();
+            check_attribute_name(path, &attribute.name, false)?;
+            check_unique(&mut seen, path, &attribute.name)?;
+            check_characteristics(
+                path,
+                &attribute.r#type,
+                attribute.mutability.as_deref(),
+                attribute.returned.as_deref(),
+                attribute.uniqueness.as_deref(),
+            )?;
+
+            let sub_attributes = attribute.sub_attributes.as_deref().unwrap_or_default();
+            if attribute.r#type == "complex" && sub_attributes.is_empty() {
+                return Err(SCIMError::MissingRequiredField(
+                    format!("{}.subAttributes", path).into(),
+                )
+                .with_attribute(path));
+            }
+            let mut seen_sub: Vec<&str> = Vec::new();
+            for sub_attribute in sub_attributes {
+                let sub_path = format!("{}.{}", path, sub_attribute.name);
+                check_attribute_name(&sub_path, &sub_attribute.name, true)?;
+                check_unique(&mut seen_sub, &sub_path, &sub_attribute.name)?;
+                if sub_attribute.r#type == "complex" {
+                    return Err(SCIMError::InvalidFieldValue(
+                        format!("{}: sub-attributes cannot be complex", sub_path).into(),
+                    )
+                    .with_attribute(sub_path));
+                }
+                check_characteristics(
+                    &sub_path,
+                    &sub_attribute.r#type,
+                    sub_attribute.mutability.as_deref(),
+                    sub_attribute.returned.as_deref(),
+                    sub_attribute.uniqueness.as_deref(),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
     /// Serializes the `Schema` instance to a JSON string, using the custom SCIMError for error handling.
     ///
     /// # Returns
@@ -312,6 +662,25 @@ impl Schema {
         serde_json::to_string(&self).map_err(SCIMError::SerializationError)
     }
 
+    /// Serializes the `Schema` instance as JSON into `buffer`, appending to its current content.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if the `Schema` cannot be serialized.
+    pub fn serialize_into(&self, buffer: &mut Vec<u8>) -> Result<(), SCIMError> {
+        json::append_to(buffer, self)
+    }
+
+    /// Serializes the `Schema` instance as JSON to `writer`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if the `Schema` cannot be serialized or writing to `writer` fails.
+    #[cfg(feature = "std")]
+    pub fn to_writer(&self, writer: impl Write) -> Result<(), SCIMError> {
+        serde_json::to_writer(writer, self).map_err(SCIMError::SerializationError)
+    }
+
     /// Deserializes a JSON string into a `Schema` instance, using the custom SCIMError for error handling.
     ///
     /// # Parameters
@@ -438,6 +807,163 @@ impl Schema {
     pub fn deserialize(json: &str) -> Result<Self, SCIMError> {
         serde_json::from_str(json).map_err(SCIMError::DeserializationError)
     }
+
+    /// Returns `true` if the attribute at `path` is marked as required.
+    ///
+    /// `path` is an attribute name or a dotted sub-attribute path such as `name.familyName`, optionally
+    /// prefixed with this schema's URI. Names are compared case-insensitively. Unknown attributes are not
+    /// required.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::scim_schema::get_schema;
+    ///
+    /// let user = get_schema("urn:ietf:params:scim:schemas:core:2.0:User").unwrap();
+    /// assert!(user.is_required("userName"));
+    /// assert!(!user.is_required("name.familyName"));
+    /// assert!(user.is_read_only("groups"));
+    /// assert_eq!(
+    ///     user.canonical_values("emails.type"),
+    ///     Some(vec!["work".to_string(), "home".to_string(), "other".to_string()])
+    /// );
+    /// ```
+    pub fn is_required(&self, path: &str) -> bool {
+        self.find_attribute(path)
+            .is_some_and(|a| a.attribute.required == Some(true))
+    }
+
+    /// Returns `true` if the attribute at `path` has `readOnly` mutability. See [`Schema::is_required`] for
+    /// the path syntax.
+    pub fn is_read_only(&self, path: &str) -> bool {
+        self.find_attribute(path)
+            .is_some_and(|a| a.attribute.mutability.as_deref() == Some("readOnly"))
+    }
+
+    /// Returns the canonical values of the attribute at `path`, if it declares any. See
+    /// [`Schema::is_required`] for the path syntax.
+    pub fn canonical_values(&self, path: &str) -> Option<Vec<String>> {
+        self.find_attribute(path)?.attribute.canonical_values
+    }
+
+    // Resolves `path` the way a merged view of this schema alone does.
+    fn find_attribute(&self, path: &str) -> Option<MergedAttribute> {
+        self.merge_extensions(&[]).get(path).cloned()
+    }
+
+    /// Merges this schema with its extension schemas into one attribute tree.
+    ///
+    /// The result lists every attribute and sub-attribute of the base schema followed by those of each
+    /// extension, each tagged with the schema it belongs to and its dotted path within that schema. It can
+    /// resolve attribute paths the way SCIM filters, projections and PATCH paths write them, qualified by
+    /// schema URI or not.
+    ///
+    /// # Parameters
+    ///
+    /// * `extensions` - The extension schemas, e.g. the enterprise user schema for `User`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::scim_schema::get_schemas;
+    ///
+    /// let schemas = get_schemas(vec!["user", "enterprise_user"]).unwrap();
+    /// let merged = schemas[0].merge_extensions(&schemas[1..]);
+    ///
+    /// let given_name = merged.get("name.givenName").unwrap();
+    /// assert_eq!(given_name.schema, "urn:ietf:params:scim:schemas:core:2.0:User");
+    /// let manager = merged
+    ///     .get("urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:manager.value")
+    ///     .unwrap();
+    /// assert_eq!(manager.path, "manager.value");
+    /// ```
+    pub fn merge_extensions(&self, extensions: &[Schema]) -> MergedSchema {
+        let mut attributes = Vec::new();
+        for schema in core::iter::once(self).chain(extensions) {
+            for attribute in &schema.attributes {
+                attributes.push(MergedAttribute {
+                    schema: schema.id.clone(),
+                    path: attribute.name.clone(),
+                    attribute: attribute.clone(),
+                });
+                for sub_attribute in attribute.sub_attributes.iter().flatten() {
+                    attributes.push(MergedAttribute {
+                        schema: schema.id.clone(),
+                        path: format!("{}.{}", attribute.name, sub_attribute.name),
+                        attribute: sub_attribute.clone().into(),
+                    });
+                }
+            }
+        }
+        MergedSchema {
+            id: self.id.clone(),
+            schemas: core::iter::once(self)
+                .chain(extensions)
+                .map(|s| s.id.clone())
+                .collect(),
+            attributes,
+        }
+    }
+}
+
+/// A base schema combined with its extension schemas, as produced by [`Schema::merge_extensions`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergedSchema {
+    /// The URI of the base schema.
+    pub id: String,
+    /// The URIs of the base schema followed by its extensions.
+    pub schemas: Vec<String>,
+    attributes: Vec<MergedAttribute>,
+}
+
+/// An attribute or sub-attribute of a [`MergedSchema`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergedAttribute {
+    /// The URI of the schema that defines the attribute.
+    pub schema: String,
+    /// The dotted path of the attribute within its schema, e.g. `name.givenName`.
+    pub path: String,
+    /// The attribute definition. Sub-attributes are converted, with no sub-attributes of their own.
+    pub attribute: Attributes,
+}
+
+impl MergedAttribute {
+    /// Returns the fully qualified path of the attribute, e.g.
+    /// `urn:ietf:params:scim:schemas:core:2.0:User:name.givenName`.
+    pub fn qualified_path(&self) -> String {
+        format!("{}:{}", self.schema, self.path)
+    }
+}
+
+impl MergedSchema {
+    /// Returns all attributes and sub-attributes, base schema first.
+    pub fn attributes(&self) -> &[MergedAttribute] {
+        &self.attributes
+    }
+
+    /// Resolves an attribute path.
+    ///
+    /// The path may be prefixed with a schema URI (`urn:...:User:userName`); an unqualified path is
+    /// resolved against the base schema first and then against the extensions in order. Paths are compared
+    /// case-insensitively, as SCIM requires.
+    pub fn get(&self, path: &str) -> Option<&MergedAttribute> {
+        let qualified = self.schemas.iter().find_map(|schema| {
+            let rest = path.get(schema.len()..)?.strip_prefix(':')?;
+            path[..schema.len()]
+                .eq_ignore_ascii_case(schema)
+                .then_some((schema, rest))
+        });
+        match qualified {
+            Some((schema, rest)) => self
+                .attributes
+                .iter()
+                .find(|a| &a.schema == schema && a.path.eq_ignore_ascii_case(rest)),
+            None => self
+                .attributes
+                .iter()
+                .find(|a| a.path.eq_ignore_ascii_case(path)),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -468,6 +994,135 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn meta_touch_versions_by_content() {
+        let registry = SchemaRegistry::with_builtins();
+        let group = registry.resource_type("Group").unwrap();
+        let mut first = Meta::for_resource(group, "https://example.com/v2", "e9e30dba");
+        let mut second = first.clone();
+        first
+            .touch(&serde_json::json!({"displayName": "Tour Guides"}))
+            .unwrap();
+        second
+            .touch(&serde_json::json!({"displayName": "Tour Guides"}))
+            .unwrap();
+        assert_eq!(first.version, second.version);
+        second
+            .touch(&serde_json::json!({"displayName": "Guides"}))
+            .unwrap();
+        assert_ne!(first.version, second.version);
+        assert_eq!(
+            first.location,
+            Some("https://example.com/v2/Groups/e9e30dba".to_string())
+        );
+    }
+
+    #[test]
+    fn meta_location_uses_the_resource_type_endpoint() {
+        let policy = ResourceType {
+            name: "Policy".to_string(),
+            endpoint: "/Policies".to_string(),
+            ..Default::default()
+        };
+        let meta = Meta::for_resource(&policy, "https://example.com/v2/", "42");
+        assert_eq!(
+            meta.location.as_deref(),
+            Some("https://example.com/v2/Policies/42")
+        );
+        assert_eq!(meta.resource_type.as_deref(), Some("Policy"));
+    }
+
+    #[test]
+    fn get_schemas_accepts_canonical_urns() {
+        let schemas = get_schemas(vec![
+            "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User",
+            "URN:IETF:PARAMS:SCIM:SCHEMAS:CORE:2.0:GROUP",
+        ])
+        .unwrap();
+        assert_eq!(schemas[0].name, "EnterpriseUser");
+        assert_eq!(schemas[1].id, GROUP_URN);
+        assert_eq!(get_schema(USER_URN).unwrap().name, "User");
+        assert!(matches!(
+            get_schema("urn:example:missing"),
+            Err(SCIMError::SchemaNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn bundled_schemas_pass_validation() {
+        for schema in get_schemas(vec!["user", "enterprise_user", "group"]).unwrap() {
+            assert!(schema.validate().is_ok(), "{} is invalid", schema.name);
+        }
+    }
+
+    #[test]
+    fn validate_rejects_invalid_attribute_definitions() {
+        let mut schema = get_schema(GROUP_URN).unwrap();
+        schema.attributes[0].mutability = Some("sometimes".to_string());
+        assert!(matches!(
+            schema.validate(),
+            Err(SCIMError::InvalidFieldValue(_))
+        ));
+
+        let mut schema = get_schema(GROUP_URN).unwrap();
+        schema.attributes[1].sub_attributes = None;
+        assert!(matches!(
+            schema.validate(),
+            Err(SCIMError::MissingRequiredField(field)) if field == "members.subAttributes"
+        ));
+
+        let mut schema = get_schema(GROUP_URN).unwrap();
+        schema.attributes[0].name = "display name".to_string();
+        assert!(schema.validate().is_err());
+    }
+
+    #[test]
+    fn merged_schema_resolves_qualified_and_unqualified_paths() {
+        let schemas = get_schemas(vec!["user", "enterprise_user"]).unwrap();
+        let merged = schemas[0].merge_extensions(&schemas[1..]);
+        assert_eq!(merged.schemas, [USER_URN, ENTERPRISE_USER_URN]);
+        assert_eq!(merged.attributes()[0].path, "userName");
+
+        let user_name = merged.get("USERNAME").unwrap();
+        assert_eq!(user_name.qualified_path(), format!("{}:userName", USER_URN));
+        assert_eq!(
+            merged.get("emails.value").unwrap().attribute.r#type,
+            "string"
+        );
+        let department = merged.get(&format!("{}:department", ENTERPRISE_USER_URN));
+        assert_eq!(department.unwrap().schema, ENTERPRISE_USER_URN);
+        assert!(merged.get(&format!("{}:department", USER_URN)).is_none());
+    }
+
+    #[test]
+    fn introspection_helpers_resolve_paths() {
+        let user = get_schema(USER_URN).unwrap();
+        assert!(user.is_required(&format!("{}:userName", USER_URN)));
+        assert!(user.is_required("USERNAME"));
+        assert!(!user.is_required("nonexistent"));
+        assert!(user.is_read_only("groups.value"));
+        assert!(!user.is_read_only("displayName"));
+        assert_eq!(user.canonical_values("displayName"), None);
+        assert!(user.canonical_values("addresses.type").is_some());
+    }
+
+    #[test]
+    fn get_schemas_finds_registered_schemas() {
+        let mut registry = SchemaRegistry::with_builtins();
+        registry
+            .register_schema(Schema {
+                id: "urn:example:params:scim:schemas:core:1.0:Printer".to_string(),
+                name: "Printer".to_string(),
+                description: "Printer".to_string(),
+                attributes: vec![],
+                meta: Meta::default(),
+            })
+            .unwrap();
+        let schemas = schemas_in(&registry, vec!["user", "printer"]).unwrap();
+        assert_eq!(schemas[1].name, "Printer");
+        assert!(get_schemas(vec!["printer"]).is_err());
+    }
+
     #[test]
     fn get_schemas_returns_error_for_missing_file() {
         let result = get_schemas(vec!["missing"]);
@@ -1,10 +1,24 @@
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io::Write;
+
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
+use crate::models::schema_uri::SERVICE_PROVIDER_CONFIG_URN;
 use crate::models::scim_schema::Meta;
 use crate::utils::error::SCIMError;
+use crate::utils::json;
 
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ServiceProviderConfig {
+    #[serde(default = "default_schemas")]
+    pub schemas: Vec<String>,
     #[serde(rename = "documentationUri", skip_serializing_if = "Option::is_none")]
     pub documentation_uri: Option<String>,
     pub patch: Supported,
@@ -17,12 +31,23 @@ pub struct ServiceProviderConfig {
     #[serde(rename = "authenticationSchemes")]
     pub authentication_schemes: Vec<AuthenticationScheme>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub pagination: Option<Pagination>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub meta: Option<Meta>,
+    /// Additional capabilities advertised by the service provider, keyed by attribute name.
+    /// Use [`ServiceProviderConfig::extension`] and [`ServiceProviderConfig::set_extension`] for typed access.
+    #[serde(flatten)]
+    pub extensions: BTreeMap<String, Value>,
+}
+
+fn default_schemas() -> Vec<String> {
+    vec![SERVICE_PROVIDER_CONFIG_URN.to_string()]
 }
 
 impl Default for ServiceProviderConfig {
     fn default() -> Self {
         ServiceProviderConfig {
+            schemas: default_schemas(),
             documentation_uri: None,
             patch: Supported { supported: false },
             bulk: Bulk {
@@ -38,12 +63,82 @@ impl Default for ServiceProviderConfig {
             sort: Supported { supported: false },
             etag: Supported { supported: false },
             authentication_schemes: vec![],
+            pagination: None,
             meta: None,
+            extensions: BTreeMap::new(),
         }
     }
 }
 
+/// Describes the pagination methods supported by the service provider, as proposed by the SCIM cursor
+/// pagination draft.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct Pagination {
+    pub cursor: bool,
+    pub index: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_pagination_method: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_page_size: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_page_size: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor_timeout: Option<i64>,
+}
+
+impl Default for Pagination {
+    fn default() -> Self {
+        Pagination {
+            cursor: false,
+            index: true,
+            default_pagination_method: None,
+            default_page_size: None,
+            max_page_size: None,
+            cursor_timeout: None,
+        }
+    }
+}
+
+/// A typed capability that can be stored in [`ServiceProviderConfig::extensions`].
+///
+/// Implementors declare the attribute name they are advertised under, which lets the configuration be
+/// read and written without handling raw JSON.
+pub trait ConfigExtension: Serialize + DeserializeOwned {
+    /// The attribute name of the capability in the `ServiceProviderConfig` document.
+    const KEY: &'static str;
+}
+
+/// Advertises the sort applied when a request does not specify `sortBy`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct SortDefaults {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_order: Option<String>,
+}
+
+impl ConfigExtension for SortDefaults {
+    const KEY: &'static str = "sortDefaults";
+}
+
+/// Advertises the `scimType` error keywords the service provider may return.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct SupportedScimTypes {
+    pub scim_types: Vec<String>,
+}
+
+impl ConfigExtension for SupportedScimTypes {
+    const KEY: &'static str = "supportedScimTypes";
+}
+
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct AuthenticationScheme {
     pub name: String,
     pub r#type: String,
@@ -70,6 +165,7 @@ impl Default for AuthenticationScheme {
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Filter {
     pub supported: bool,
     #[serde(rename = "maxResults")]
@@ -86,6 +182,7 @@ impl Default for Filter {
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Bulk {
     pub supported: bool,
     #[serde(rename = "maxOperations")]
@@ -105,6 +202,7 @@ impl Default for Bulk {
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Supported {
     pub supported: bool,
 }
@@ -168,6 +266,48 @@ impl TryFrom<&str> for ServiceProviderConfig {
     }
 }
 
+/// Converts an already-parsed JSON value into a `ServiceProviderConfig` struct.
+///
+/// Web frameworks usually hand over request bodies as a `serde_json::Value`; this conversion avoids
+/// round-tripping that value through a string.
+///
+/// # Errors
+///
+/// Returns `SCIMError::DeserializationError` if the value does not describe a `ServiceProviderConfig` object.
+///
+/// # Examples
+///
+/// ```rust
+/// use scim_v2::models::service_provider_config::ServiceProviderConfig;
+/// use serde_json::json;
+///
+/// let value = json!({"patch": {"supported": true}, "bulk": {"supported": false, "maxOperations": 0, "maxPayloadSize": 0}, "filter": {"supported": true, "maxResults": 200}, "changePassword": {"supported": false}, "sort": {"supported": false}, "etag": {"supported": false}, "authenticationSchemes": []});
+/// match ServiceProviderConfig::try_from(value) {
+///     Ok(resource) => println!("Successfully converted JSON to ServiceProviderConfig: {:?}", resource),
+///     Err(e) => println!("Error converting from JSON to ServiceProviderConfig: {}", e),
+/// }
+/// ```
+impl TryFrom<Value> for ServiceProviderConfig {
+    type Error = SCIMError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        serde_json::from_value(value).map_err(SCIMError::DeserializationError)
+    }
+}
+
+/// Converts a `ServiceProviderConfig` struct into a JSON value.
+///
+/// # Errors
+///
+/// Returns `SCIMError::SerializationError` if the `ServiceProviderConfig` cannot be represented as JSON.
+impl TryFrom<ServiceProviderConfig> for Value {
+    type Error = SCIMError;
+
+    fn try_from(value: ServiceProviderConfig) -> Result<Self, Self::Error> {
+        serde_json::to_value(value).map_err(SCIMError::SerializationError)
+    }
+}
+
 impl ServiceProviderConfig {
     /// Validates a service provider config.
     ///
@@ -200,24 +340,22 @@ impl ServiceProviderConfig {
     /// ```
     pub fn validate(&self) -> Result<(), SCIMError> {
         if !self.patch.supported {
-            return Err(SCIMError::MissingRequiredField("patch".to_string()));
+            return Err(SCIMError::MissingRequiredField("patch".into()));
         }
         if !self.bulk.supported {
-            return Err(SCIMError::MissingRequiredField("bulk".to_string()));
+            return Err(SCIMError::MissingRequiredField("bulk".into()));
         }
         if !self.filter.supported {
-            return Err(SCIMError::MissingRequiredField("filter".to_string()));
+            return Err(SCIMError::MissingRequiredField("filter".into()));
         }
         if !self.change_password.supported {
-            return Err(SCIMError::MissingRequiredField(
-                "change_password".to_string(),
-            ));
+            return Err(SCIMError::MissingRequiredField("change_password".into()));
         }
         if !self.sort.supported {
-            return Err(SCIMError::MissingRequiredField("sort".to_string()));
+            return Err(SCIMError::MissingRequiredField("sort".into()));
         }
         if !self.etag.supported {
-            return Err(SCIMError::MissingRequiredField("etag".to_string()));
+            return Err(SCIMError::MissingRequiredField("etag".into()));
         }
         Ok(())
     }
@@ -250,6 +388,25 @@ impl ServiceProviderConfig {
         serde_json::to_string(&self).map_err(SCIMError::SerializationError)
     }
 
+    /// Serializes the `ServiceProviderConfig` instance as JSON into `buffer`, appending to its current content.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if the `ServiceProviderConfig` cannot be serialized.
+    pub fn serialize_into(&self, buffer: &mut Vec<u8>) -> Result<(), SCIMError> {
+        json::append_to(buffer, self)
+    }
+
+    /// Serializes the `ServiceProviderConfig` instance as JSON to `writer`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if the `ServiceProviderConfig` cannot be serialized or writing to `writer` fails.
+    #[cfg(feature = "std")]
+    pub fn to_writer(&self, writer: impl Write) -> Result<(), SCIMError> {
+        serde_json::to_writer(writer, self).map_err(SCIMError::SerializationError)
+    }
+
     /// Deserializes a JSON string into a `ServiceProviderConfig` instance, using the custom SCIMError for error handling.
     ///
     /// # Parameters
@@ -305,6 +462,47 @@ impl ServiceProviderConfig {
     pub fn deserialize(json: &str) -> Result<Self, SCIMError> {
         serde_json::from_str(json).map_err(SCIMError::DeserializationError)
     }
+
+    /// Reads a typed capability from the extensions map.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(T))` - If the capability is advertised.
+    /// * `Ok(None)` - If the capability is absent.
+    /// * `Err(SCIMError::DeserializationError)` - If the capability is present but has an unexpected shape.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::service_provider_config::{ServiceProviderConfig, SortDefaults};
+    ///
+    /// let mut config = ServiceProviderConfig::default();
+    /// config
+    ///     .set_extension(&SortDefaults {
+    ///         sort_by: Some("userName".to_string()),
+    ///         sort_order: Some("ascending".to_string()),
+    ///     })
+    ///     .unwrap();
+    /// let defaults = config.extension::<SortDefaults>().unwrap().unwrap();
+    /// assert_eq!(defaults.sort_by.as_deref(), Some("userName"));
+    /// ```
+    pub fn extension<T: ConfigExtension>(&self) -> Result<Option<T>, SCIMError> {
+        self.extensions
+            .get(T::KEY)
+            .map(|value| T::deserialize(value).map_err(SCIMError::DeserializationError))
+            .transpose()
+    }
+
+    /// Stores a typed capability in the extensions map, replacing any previous value.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if the capability cannot be serialized.
+    pub fn set_extension<T: ConfigExtension>(&mut self, extension: &T) -> Result<(), SCIMError> {
+        let value = serde_json::to_value(extension).map_err(SCIMError::SerializationError)?;
+        self.extensions.insert(T::KEY.to_string(), value);
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -403,5 +601,40 @@ mod tests {
             Some("http://example.com/help/httpBasic.html".to_string())
         );
         assert_eq!(http_scheme.r#type, "httpbasic");
+        assert_eq!(
+            config.schemas,
+            vec!["urn:ietf:params:scim:schemas:core:2.0:ServiceProviderConfig"]
+        );
+        assert!(config.extensions.is_empty());
+    }
+
+    #[test]
+    fn service_provider_config_round_trips_capability_extensions() {
+        let json_data = r#"{
+            "patch": { "supported": true },
+            "bulk": { "supported": false, "maxOperations": 0, "maxPayloadSize": 0 },
+            "filter": { "supported": true, "maxResults": 200 },
+            "changePassword": { "supported": false },
+            "sort": { "supported": true },
+            "etag": { "supported": false },
+            "authenticationSchemes": [],
+            "pagination": { "cursor": true, "index": false, "maxPageSize": 500 },
+            "supportedScimTypes": { "scimTypes": ["invalidFilter", "uniqueness"] },
+            "urn:example:params:scim:feature:deltaSync": { "supported": true }
+        }"#;
+
+        let config = ServiceProviderConfig::deserialize(json_data).unwrap();
+        let pagination = config.pagination.as_ref().unwrap();
+        assert!(pagination.cursor);
+        assert_eq!(pagination.max_page_size, Some(500));
+        let scim_types = config.extension::<SupportedScimTypes>().unwrap().unwrap();
+        assert_eq!(scim_types.scim_types, vec!["invalidFilter", "uniqueness"]);
+        assert!(config.extension::<SortDefaults>().unwrap().is_none());
+
+        let json: Value = serde_json::from_str(&config.serialize().unwrap()).unwrap();
+        assert_eq!(
+            json["urn:example:params:scim:feature:deltaSync"]["supported"],
+            true
+        );
     }
 }
@@ -0,0 +1,88 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::utils::error::SCIMError;
+
+/// What is left of a deleted resource in a provider that retains deletions.
+///
+/// A tombstone keeps the `id` and `externalId` of the resource and when it was deleted, so that a service
+/// provider can answer requests for the id with `410 Gone` rather than `404 Not Found`, refuse to create a
+/// new resource for an `externalId` that was deleted, and report deletions to syncing clients. See
+/// [`ResourceProvider::undelete`](crate::provider::resource_provider::ResourceProvider::undelete) for bringing
+/// the resource back.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::errors::ScimHttpError;
+/// use scim_v2::models::tombstone::Tombstone;
+/// use serde_json::json;
+///
+/// let user = json!({"id": "2819c223", "externalId": "bjensen", "userName": "bjensen"});
+/// let tombstone = Tombstone::of("User", &user, "2024-05-13T04:42:34Z");
+/// assert_eq!(tombstone.external_id.as_deref(), Some("bjensen"));
+/// assert_eq!(ScimHttpError::from(&tombstone.gone()).status, "410");
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct Tombstone {
+    /// The name of the resource type, e.g. `User`.
+    pub resource_type: String,
+    /// The `id` the resource had.
+    pub id: String,
+    /// The `externalId` the resource had, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+    /// When the resource was deleted, as a `dateTime`.
+    pub deleted: String,
+}
+
+impl Tombstone {
+    /// Records the deletion of `resource` at `deleted`.
+    pub fn of(
+        resource_type: impl Into<String>,
+        resource: &Value,
+        deleted: impl Into<String>,
+    ) -> Self {
+        Tombstone {
+            resource_type: resource_type.into(),
+            id: resource["id"].as_str().unwrap_or_default().to_string(),
+            external_id: resource["externalId"].as_str().map(str::to_string),
+            deleted: deleted.into(),
+        }
+    }
+
+    /// Returns the error for a request addressing the deleted resource.
+    pub fn gone(&self) -> SCIMError {
+        SCIMError::Gone(
+            format!(
+                "{} {} was deleted at {}",
+                self.resource_type, self.id, self.deleted
+            )
+            .into(),
+        )
+        .with_resource_type(&self.resource_type)
+        .with_resource_id(&self.id)
+    }
+
+    /// Returns the error for a request creating a resource with the `externalId` of the deleted resource.
+    pub fn conflict(&self) -> SCIMError {
+        SCIMError::ConflictError(
+            format!(
+                "externalId '{}' belongs to {} {}, deleted at {}; undelete it instead",
+                self.external_id.as_deref().unwrap_or_default(),
+                self.resource_type,
+                self.id,
+                self.deleted
+            )
+            .into(),
+        )
+        .with_attribute("externalId")
+        .with_resource_type(&self.resource_type)
+        .with_resource_id(&self.id)
+    }
+}
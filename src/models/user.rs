@@ -1,24 +1,54 @@
-use std::convert::TryFrom;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt::{self, Debug, Formatter};
+#[cfg(feature = "std")]
+use std::io::Write;
 
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
+#[cfg(feature = "std")]
+use crate::models::anonymize::AnonymizationPolicy;
 use crate::models::enterprise_user::EnterpriseUser;
+use crate::models::others::PatchOp;
+use crate::models::patch;
+use crate::models::resource_id::ResourceId;
+use crate::models::schema_registry::read_global;
+use crate::models::schema_uri::{ENTERPRISE_USER_URN, USER_URN};
 use crate::models::scim_schema::Meta;
+use crate::models::values::Values;
 use crate::utils::error::SCIMError;
+use crate::utils::json;
+use crate::utils::sensitive::{Secret, Url};
+use crate::values;
 
-#[derive(Serialize, Deserialize, Debug)]
+/// A SCIM User resource (RFC 7643 §4.1).
+///
+/// The `Debug` output redacts `password` and the parts of URLs that may carry tokens, such as the query of
+/// a photo URL; [`User::full_debug`] prints everything.
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct User {
     // urn:ietf:params:scim:schemas:core:2.0:User
-    pub schemas: Vec<String>,
+    #[cfg_attr(feature = "lenient", serde(default = "default_schemas"))]
+    pub schemas: Values<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub id: Option<String>,
+    pub id: Option<ResourceId>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "lenient", serde(alias = "externalID", alias = "externalid"))]
     pub external_id: Option<String>,
+    #[cfg_attr(feature = "lenient", serde(alias = "username", alias = "UserName"))]
     pub user_name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<Name>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(
+        feature = "lenient",
+        serde(alias = "displayname", alias = "DisplayName")
+    )]
     pub display_name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub nick_name: Option<String>,
@@ -39,23 +69,23 @@ pub struct User {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub password: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub emails: Option<Vec<Email>>,
+    pub emails: Option<Values<Email>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub addresses: Option<Vec<Address>>,
+    pub addresses: Option<Values<Address>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub phone_numbers: Option<Vec<PhoneNumber>>,
+    pub phone_numbers: Option<Values<PhoneNumber>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub ims: Option<Vec<Im>>,
+    pub ims: Option<Values<Im>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub photos: Option<Vec<Photo>>,
+    pub photos: Option<Values<Photo>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub groups: Option<Vec<Group>>,
+    pub groups: Option<Values<Group>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub entitlements: Option<Vec<Entitlement>>,
+    pub entitlements: Option<Values<Entitlement>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub roles: Option<Vec<Role>>,
+    pub roles: Option<Values<Role>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub x509_certificates: Option<Vec<X509Certificate>>,
+    pub x509_certificates: Option<Values<X509Certificate>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub meta: Option<Meta>,
     #[serde(
@@ -65,10 +95,14 @@ pub struct User {
     pub enterprise_user: Option<EnterpriseUser>,
 }
 
+fn default_schemas() -> Values<String> {
+    values![USER_URN.to_string()]
+}
+
 impl Default for User {
     fn default() -> Self {
         User {
-            schemas: vec!["urn:ietf:params:scim:schemas:core:2.0:User".to_string()],
+            schemas: default_schemas(),
             user_name: "".to_string(),
             id: None,
             external_id: None,
@@ -99,6 +133,7 @@ impl Default for User {
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct Name {
     pub formatted: Option<String>,
@@ -115,6 +150,7 @@ pub struct Name {
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Email {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub value: Option<String>,
@@ -127,6 +163,7 @@ pub struct Email {
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct Address {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -146,6 +183,7 @@ pub struct Address {
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct PhoneNumber {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub value: Option<String>,
@@ -158,6 +196,7 @@ pub struct PhoneNumber {
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Im {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub value: Option<String>,
@@ -169,7 +208,9 @@ pub struct Im {
     pub primary: Option<bool>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Default)]
+/// A photo of a user. The `Debug` output redacts the parts of `value` that may carry tokens.
+#[derive(Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Photo {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub value: Option<String>,
@@ -182,9 +223,10 @@ pub struct Photo {
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Group {
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub value: Option<String>,
+    pub value: Option<ResourceId>,
     #[serde(rename = "$ref", skip_serializing_if = "Option::is_none")]
     pub r#ref: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -194,6 +236,7 @@ pub struct Group {
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Entitlement {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub value: Option<String>,
@@ -206,6 +249,7 @@ pub struct Entitlement {
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Role {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub value: Option<String>,
@@ -218,6 +262,7 @@ pub struct Role {
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct X509Certificate {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub value: Option<String>,
@@ -254,11 +299,238 @@ impl TryFrom<&str> for User {
     type Error = SCIMError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        serde_json::from_str(value).map_err(SCIMError::DeserializationError)
+        json::from_str(value).map(User::complete_schemas)
+    }
+}
+
+/// Converts an already-parsed JSON value into a `User` struct.
+///
+/// Web frameworks usually hand over request bodies as a `serde_json::Value`; this conversion avoids
+/// round-tripping that value through a string.
+///
+/// # Errors
+///
+/// Returns `SCIMError::DeserializationError` if the value does not describe a `User` object.
+///
+/// # Examples
+///
+/// ```rust
+/// use scim_v2::models::user::User;
+/// use serde_json::json;
+///
+/// let value = json!({"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "userName": "jdoe@example.com"});
+/// match User::try_from(value) {
+///     Ok(resource) => println!("Successfully converted JSON to User: {:?}", resource),
+///     Err(e) => println!("Error converting from JSON to User: {}", e),
+/// }
+/// ```
+impl TryFrom<Value> for User {
+    type Error = SCIMError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        serde_json::from_value(value)
+            .map(User::complete_schemas)
+            .map_err(SCIMError::DeserializationError)
+    }
+}
+
+/// Converts a `User` struct into a JSON value.
+///
+/// # Errors
+///
+/// Returns `SCIMError::SerializationError` if the `User` cannot be represented as JSON.
+impl TryFrom<User> for Value {
+    type Error = SCIMError;
+
+    fn try_from(value: User) -> Result<Self, Self::Error> {
+        serde_json::to_value(value).map_err(SCIMError::SerializationError)
+    }
+}
+
+impl Debug for User {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.debug(f, false)
+    }
+}
+
+impl Debug for Photo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.debug(f, false)
+    }
+}
+
+// The unredacted `Debug` output of a user, see `User::full_debug`.
+struct FullDebug<'a>(&'a User);
+
+impl Debug for FullDebug<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.debug(f, true)
+    }
+}
+
+// A photo in the `Debug` output of a user.
+struct PhotoDebug<'a> {
+    photo: &'a Photo,
+    full: bool,
+}
+
+impl Debug for PhotoDebug<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.photo.debug(f, self.full)
+    }
+}
+
+impl Photo {
+    fn debug(&self, f: &mut Formatter<'_>, full: bool) -> fmt::Result {
+        f.debug_struct("Photo")
+            .field(
+                "value",
+                &self.value.as_deref().map(|value| Url { value, full }),
+            )
+            .field("display", &self.display)
+            .field("type", &self.r#type)
+            .field("primary", &self.primary)
+            .finish()
     }
 }
 
 impl User {
+    /// Returns a `Debug` view of the user that prints `password` and URLs in full, for debugging in
+    /// environments where credentials may be shown. The user's own `Debug` output redacts them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::user::User;
+    ///
+    /// let user = User {
+    ///     user_name: "bjensen".to_string(),
+    ///     password: Some("t1meMa$heen".to_string()),
+    ///     profile_url: Some("https://example.com/bjensen?token=abc".to_string()),
+    ///     ..Default::default()
+    /// };
+    /// let logged = format!("{:?}", user);
+    /// assert!(logged.contains(r#"password: Some("<redacted>")"#));
+    /// assert!(logged.contains(r#"profile_url: Some("https://example.com/bjensen?<redacted>")"#));
+    /// assert!(!logged.contains("t1meMa$heen"));
+    /// assert!(format!("{:?}", user.full_debug()).contains("t1meMa$heen"));
+    /// ```
+    pub fn full_debug(&self) -> impl Debug + '_ {
+        FullDebug(self)
+    }
+
+    fn debug(&self, f: &mut Formatter<'_>, full: bool) -> fmt::Result {
+        let photos: Option<Vec<PhotoDebug<'_>>> = self.photos.as_ref().map(|photos| {
+            photos
+                .iter()
+                .map(|photo| PhotoDebug { photo, full })
+                .collect()
+        });
+        f.debug_struct("User")
+            .field("schemas", &self.schemas)
+            .field("id", &self.id)
+            .field("external_id", &self.external_id)
+            .field("user_name", &self.user_name)
+            .field("name", &self.name)
+            .field("display_name", &self.display_name)
+            .field("nick_name", &self.nick_name)
+            .field(
+                "profile_url",
+                &self.profile_url.as_deref().map(|value| Url { value, full }),
+            )
+            .field("title", &self.title)
+            .field("user_type", &self.user_type)
+            .field("preferred_language", &self.preferred_language)
+            .field("locale", &self.locale)
+            .field("timezone", &self.timezone)
+            .field("active", &self.active)
+            .field(
+                "password",
+                &self.password.as_deref().map(|value| Secret { value, full }),
+            )
+            .field("emails", &self.emails)
+            .field("addresses", &self.addresses)
+            .field("phone_numbers", &self.phone_numbers)
+            .field("ims", &self.ims)
+            .field("photos", &photos)
+            .field("groups", &self.groups)
+            .field("entitlements", &self.entitlements)
+            .field("roles", &self.roles)
+            .field("x509_certificates", &self.x509_certificates)
+            .field("meta", &self.meta)
+            .field("enterprise_user", &self.enterprise_user)
+            .finish()
+    }
+
+    /// Returns a copy of the user with its personal data masked, pseudonymized or removed as `policy`
+    /// says. See [`AnonymizationPolicy`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` or `SCIMError::DeserializationError` if the user cannot be
+    /// converted to and from JSON, e.g. because a policy pseudonymized a value that has to be a URI.
+    #[cfg(feature = "std")]
+    pub fn anonymized(&self, policy: &AnonymizationPolicy) -> Result<User, SCIMError> {
+        let mut value = serde_json::to_value(self).map_err(SCIMError::SerializationError)?;
+        policy.apply(&mut value);
+        User::try_from(value)
+    }
+
+    /// Applies the operations of a PATCH request to the user, in order (RFC 7644 §3.5.2).
+    ///
+    /// Paths may filter the values of multi-valued attributes (`emails[type eq "work"].value`) and address
+    /// the enterprise extension by its schema URI. The patch is applied as a whole: if an operation fails,
+    /// the user is left unchanged. See [`models::patch`](crate::models::patch) for the details.
+    ///
+    /// # Errors
+    ///
+    /// * `SCIMError::InvalidPath` - If the path of an operation is not a PATCH path.
+    /// * `SCIMError::NoTarget` - If a `remove` operation has no path, or the filter of an `add` or `replace`
+    ///   operation matches no value.
+    /// * `SCIMError::InvalidFieldValue` - If an `add` or `replace` operation has no value, or the patched
+    ///   user is not a valid `User`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::others::PatchOp;
+    /// use scim_v2::models::user::User;
+    /// use serde_json::json;
+    ///
+    /// let mut user = User::try_from(json!({
+    ///     "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+    ///     "userName": "bjensen",
+    ///     "emails": [
+    ///         {"value": "bjensen@example.com", "type": "work", "primary": true},
+    ///         {"value": "babs@jensen.org", "type": "home"}
+    ///     ]
+    /// }))
+    /// .unwrap();
+    /// let patch = PatchOp::try_from(json!({
+    ///     "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+    ///     "Operations": [
+    ///         {"op": "replace", "path": "emails[type eq \"work\"].value", "value": "barbara@example.com"},
+    ///         {"op": "remove", "path": "emails[type eq \"home\"]"},
+    ///         {"op": "Add", "value": {"nickName": "Babs"}}
+    ///     ]
+    /// }))
+    /// .unwrap();
+    ///
+    /// user.apply_patch(&patch).unwrap();
+    /// let emails = user.emails.as_ref().unwrap();
+    /// assert_eq!(emails.len(), 1);
+    /// assert_eq!(emails[0].value.as_deref(), Some("barbara@example.com"));
+    /// assert_eq!(user.nick_name.as_deref(), Some("Babs"));
+    /// ```
+    pub fn apply_patch(&mut self, patch: &PatchOp) -> Result<(), SCIMError> {
+        let mut value = serde_json::to_value(&*self).map_err(SCIMError::SerializationError)?;
+        patch::apply(&mut value, patch)?;
+        *self = serde_json::from_value(value).map_err(|e| {
+            SCIMError::InvalidFieldValue(format!("the patched user is invalid: {}", e).into())
+        })?;
+        Ok(())
+    }
+
     /// Validates a user.
     ///
     /// This function checks if the user has a `name` and `user_name`. If either is missing, it returns an error.
@@ -294,15 +566,19 @@ impl User {
     /// # Note
     ///
     /// The actual validation requirements will depend on the specifics of your application and the SCIM (System for Cross-domain Identity Management) protocol you are implementing.
+    /// Beyond the two fields checked here, the user is validated against the `User` resource type of the
+    /// process-wide [`SchemaRegistry`](crate::models::schema_registry::SchemaRegistry), so extensions an
+    /// application registered as required are enforced as well.
     pub fn validate(&self) -> Result<(), SCIMError> {
         // Pretty much every field is optional in the schema except for 2. We'll check for those here.
         if self.schemas.is_empty() {
-            return Err(SCIMError::MissingRequiredField("schemas".to_string()));
+            return Err(SCIMError::MissingRequiredField("schemas".into()));
         }
         if self.user_name.is_empty() {
-            return Err(SCIMError::MissingRequiredField("user_name".to_string()));
+            return Err(SCIMError::MissingRequiredField("user_name".into()));
         }
-        Ok(())
+        let value = serde_json::to_value(self).map_err(SCIMError::SerializationError)?;
+        read_global().validate_resource("User", &value)
     }
 
     /// Serializes the `User` instance to a JSON string, using the custom SCIMError for error handling.
@@ -317,9 +593,10 @@ impl User {
     ///
     /// ```
     /// use scim_v2::models::user::User;
+    /// use scim_v2::values;
     ///
     /// let user = User {
-    ///     schemas: vec!["urn:ietf:params:scim:schemas:core:2.0:User".to_string()],
+    ///     schemas: values!["urn:ietf:params:scim:schemas:core:2.0:User".to_string()],
     ///     user_name: "jdoe@example.com".to_string(),
     ///     // Initialize other fields as necessary...
     ///     ..Default::default()
@@ -334,6 +611,42 @@ impl User {
         serde_json::to_string(&self).map_err(SCIMError::SerializationError)
     }
 
+    /// Serializes the `User` instance as JSON into `buffer`, appending to its current content.
+    ///
+    /// Servers can keep one buffer per connection and clear it between responses, instead of allocating a
+    /// new `String` for every response.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::user::User;
+    ///
+    /// let mut buffer = Vec::with_capacity(1024);
+    /// for name in ["bjensen", "jsmith"] {
+    ///     buffer.clear();
+    ///     let user = User { user_name: name.to_string(), ..Default::default() };
+    ///     user.serialize_into(&mut buffer).unwrap();
+    ///     assert!(buffer.starts_with(b"{"));
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if the `User` cannot be serialized.
+    pub fn serialize_into(&self, buffer: &mut Vec<u8>) -> Result<(), SCIMError> {
+        json::append_to(buffer, self)
+    }
+
+    /// Serializes the `User` instance as JSON to `writer`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if the `User` cannot be serialized or writing to `writer` fails.
+    #[cfg(feature = "std")]
+    pub fn to_writer(&self, writer: impl Write) -> Result<(), SCIMError> {
+        serde_json::to_writer(writer, self).map_err(SCIMError::SerializationError)
+    }
+
     /// Deserializes a JSON string into a `User` instance, using the custom SCIMError for error handling.
     ///
     /// # Parameters
@@ -357,7 +670,22 @@ impl User {
     /// }
     /// ```
     pub fn deserialize(json: &str) -> Result<Self, SCIMError> {
-        serde_json::from_str(json).map_err(SCIMError::DeserializationError)
+        serde_json::from_str(json)
+            .map(User::complete_schemas)
+            .map_err(SCIMError::DeserializationError)
+    }
+
+    // With the `lenient` feature a missing `schemas` defaults to the core User schema, as serde cannot look
+    // at the other members while supplying a default. Lists the enterprise extension as well when the
+    // payload carries it; serde_json used directly leaves `schemas` as defaulted.
+    fn complete_schemas(mut self) -> Self {
+        if cfg!(feature = "lenient")
+            && self.enterprise_user.is_some()
+            && !self.schemas.iter().any(|s| s == ENTERPRISE_USER_URN)
+        {
+            self.schemas.push(ENTERPRISE_USER_URN.to_string());
+        }
+        self
     }
 }
 
@@ -368,6 +696,29 @@ mod tests {
 
     use super::*;
 
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn json_schema_follows_serde_names() {
+        let schema = schemars::schema_for!(User);
+        let value = serde_json::to_value(&schema).unwrap();
+        assert_eq!(
+            value["$schema"],
+            "https://json-schema.org/draft/2020-12/schema"
+        );
+        assert!(value["properties"].get("userName").is_some());
+        assert!(
+            value["properties"]
+                .get("urn:ietf:params:scim:schemas:extension:enterprise:2.0:User")
+                .is_some()
+        );
+        assert!(
+            value["required"]
+                .as_array()
+                .unwrap()
+                .contains(&"userName".into())
+        );
+    }
+
     #[test]
     fn user_deserialization_with_minimum_fields() {
         let json_data = r#"{
@@ -391,13 +742,10 @@ mod tests {
         assert!(user.is_ok());
         let user = user.unwrap();
         assert_eq!(
-            user.schemas,
-            vec!["urn:ietf:params:scim:schemas:core:2.0:User"]
-        );
-        assert_eq!(
-            user.id,
-            Some("2819c223-7f76-453a-919d-413861904646".to_string())
+            user.schemas[..],
+            ["urn:ietf:params:scim:schemas:core:2.0:User"]
         );
+        assert_eq!(user.id, Some("2819c223-7f76-453a-919d-413861904646".into()));
         assert_eq!(user.user_name, "bjensen@example.com");
         let meta = user.meta.unwrap();
         assert_eq!(meta.resource_type, Some("User".to_string()));
@@ -535,13 +883,10 @@ mod tests {
         assert!(user.is_ok());
         let user = user.unwrap();
         assert_eq!(
-            user.schemas,
-            vec!["urn:ietf:params:scim:schemas:core:2.0:User"]
-        );
-        assert_eq!(
-            user.id,
-            Some("2819c223-7f76-453a-919d-413861904646".to_string())
+            user.schemas[..],
+            ["urn:ietf:params:scim:schemas:core:2.0:User"]
         );
+        assert_eq!(user.id, Some("2819c223-7f76-453a-919d-413861904646".into()));
         assert_eq!(user.external_id, Some("701984".to_string()));
         assert_eq!(user.user_name, "bjensen@example.com");
         assert_eq!(
@@ -581,7 +926,7 @@ mod tests {
         assert_eq!(user.groups.as_ref().unwrap().len(), 3);
         assert_eq!(
             user.groups.as_ref().unwrap()[0].value,
-            Some("e9e30dba-f08f-4109-8486-d5c6a331660a".to_string())
+            Some("e9e30dba-f08f-4109-8486-d5c6a331660a".into())
         );
         assert_eq!(user.x509_certificates.as_ref().unwrap().len(), 1);
         assert_eq!(user.x509_certificates.as_ref().unwrap()[0].value, Some("MIIDQzCCAqygAwIBAgICEAAwDQYJKoZIhvcNAQEFBQAwTjELMAkGA1UEBhMCVVMxEzARBgNVBAgMCkNhbGlmb3JuaWExFDASBgNVBAoMC2V4YW1wbGUuY29tMRQwEgYDVQQDDAtleGFtcGxlLmNvbTAeFw0xMTEwMjIwNjI0MzFaFw0xMjEwMDQwNjI0MzFaMH8xCzAJBgNVBAYTAlVTMRMwEQYDVQQIDApDYWxpZm9ybmlhMRQwEgYDVQQKDAtleGFtcGxlLmNvbTEhMB8GA1UEAwwYTXMuIEJhcmJhcmEgSiBKZW5zZW4gSUlJMSIwIAYJKoZIhvcNAQkBFhNiamVuc2VuQGV4YW1wbGUuY29tMIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEA7Kr+Dcds/JQ5GwejJFcBIP682X3xpjis56AK02bc1FLgzdLI8auoR+cC9/Vrh5t66HkQIOdA4unHh0AaZ4xL5PhVbXIPMB5vAPKpzz5iPSi8xO8SL7I7SDhcBVJhqVqr3HgllEG6UClDdHO7nkLuwXq8HcISKkbT5WFTVfFZzidPl8HZ7DhXkZIRtJwBweq4bvm3hM1Os7UQH05ZS6cVDgweKNwdLLrT51ikSQG3DYrl+ft781UQRIqxgwqCfXEuDiinPh0kkvIi5jivVu1Z9QiwlYEdRbLJ4zJQBmDrSGTMYn4lRc2HgHO4DqB/bnMVorHB0CC6AV1QoFK4GPe1LwIDAQABo3sweTAJBgNVHRMEAjAAMCwGCWCGSAGG+EIBDQQfFh1PcGVuU1NMIEdlbmVyYXRlZCBDZXJ0aWZpY2F0ZTAdBgNVHQ4EFgQU8pD0U0vsZIsaA16lL8En8bx0F/gwHwYDVR0jBBgwFoAUdGeKitcaF7gnzsNwDx708kqaVt0wDQYJKoZIhvcNAQEFBQADgYEAA81SsFnOdYJtNg5Tcq+/ByEDrBgnusx0jloUhByPMEVkoMZ3J7j1ZgI8rAbOkNngX8+pKfTiDz1RC4+dx8oU6Za+4NJXUjlL5CvV6BEYb1+QAEJwitTVvxB/A67g42/vzgAtoRUeDov1+GFiBZ+GNF/cAYKcMtGcrs2i97ZkJMo=".to_string()), "x509_certificates[0].value did not match expected value");
@@ -760,7 +1105,7 @@ mod tests {
         let manager = enterprise_user.manager.unwrap();
         assert_eq!(
             manager.value,
-            Some("26118915-6090-4610-87e4-49d8ca9f808d".to_string())
+            Some("26118915-6090-4610-87e4-49d8ca9f808d".into())
         );
         assert_eq!(manager.display_name, Some("John Smith".to_string()));
     }
@@ -782,4 +1127,147 @@ mod tests {
         let user = user.unwrap();
         assert!(user.enterprise_user.is_none());
     }
+
+    #[test]
+    fn user_converts_to_and_from_json_value() {
+        let value = serde_json::json!({
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+            "id": "2819c223-7f76-453a-919d-413861904646",
+            "userName": "bjensen@example.com"
+        });
+
+        let user = User::try_from(value.clone()).unwrap();
+        assert_eq!(user.user_name, "bjensen@example.com");
+        assert_eq!(Value::try_from(user).unwrap(), value);
+        assert!(User::try_from(serde_json::json!({"userName": 1})).is_err());
+    }
+
+    #[test]
+    fn validate_checks_schemas_against_the_registry() {
+        let user = User {
+            schemas: values![USER_URN.to_string(), "urn:example:unregistered".to_string()],
+            user_name: "bjensen".to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            user.validate(),
+            Err(SCIMError::InvalidFieldValue(_))
+        ));
+    }
+
+    #[test]
+    fn serialize_into_appends_to_the_buffer() {
+        let user = User {
+            user_name: "bjensen".to_string(),
+            ..Default::default()
+        };
+        let mut buffer = b"[".to_vec();
+        user.serialize_into(&mut buffer).unwrap();
+        assert_eq!(&buffer[1..], user.serialize().unwrap().as_bytes());
+    }
+
+    #[cfg(not(feature = "lenient"))]
+    #[test]
+    fn user_deserialization_requires_schemas() {
+        let user: Result<User, _> = serde_json::from_str(r#"{"userName": "bjensen"}"#);
+        assert!(user.is_err());
+    }
+
+    #[cfg(feature = "lenient")]
+    #[test]
+    fn user_deserialization_supplies_missing_schemas() {
+        let user: User = serde_json::from_str(r#"{"userName": "bjensen"}"#).unwrap();
+        assert_eq!(user.schemas[..], [USER_URN.to_string()]);
+    }
+
+    #[cfg(feature = "lenient")]
+    #[test]
+    fn user_deserialization_lists_the_enterprise_extension_in_missing_schemas() {
+        let json_data = r#"{
+            "userName": "bjensen",
+            "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User": {"department": "Sales"}
+        }"#;
+        let expected = [USER_URN.to_string(), ENTERPRISE_USER_URN.to_string()];
+        assert_eq!(User::deserialize(json_data).unwrap().schemas[..], expected);
+        assert_eq!(User::try_from(json_data).unwrap().schemas[..], expected);
+        let value: Value = serde_json::from_str(json_data).unwrap();
+        assert_eq!(User::try_from(value).unwrap().schemas[..], expected);
+    }
+
+    #[cfg(feature = "lenient")]
+    #[test]
+    fn user_deserialization_accepts_nonstandard_casings() {
+        let json_data = r#"{
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+            "username": "bjensen@example.com",
+            "externalID": "701984",
+            "displayname": "Babs Jensen"
+        }"#;
+
+        let user: User = serde_json::from_str(json_data).unwrap();
+        assert_eq!(user.user_name, "bjensen@example.com");
+        assert_eq!(user.external_id, Some("701984".to_string()));
+        assert_eq!(user.display_name, Some("Babs Jensen".to_string()));
+    }
+
+    #[test]
+    fn apply_patch_updates_the_enterprise_extension_or_nothing() {
+        let mut user = User::try_from(serde_json::json!({
+            "schemas": [USER_URN],
+            "userName": "bjensen",
+            "active": true
+        }))
+        .unwrap();
+        let patch = |operations: Value| {
+            PatchOp::try_from(serde_json::json!({
+                "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+                "Operations": operations
+            }))
+            .unwrap()
+        };
+
+        let manager = format!("{}:manager.value", ENTERPRISE_USER_URN);
+        user.apply_patch(&patch(serde_json::json!([
+            {"op": "add", "path": manager, "value": "26118915-6090-4610-87e4-49d8ca9f808d"},
+            {"op": "replace", "path": "active", "value": false}
+        ])))
+        .unwrap();
+        assert_eq!(user.active, Some(false));
+        assert_eq!(user.schemas[..], [USER_URN, ENTERPRISE_USER_URN]);
+        let enterprise = user.enterprise_user.as_ref().unwrap();
+        assert_eq!(
+            enterprise
+                .manager
+                .as_ref()
+                .unwrap()
+                .value
+                .as_ref()
+                .unwrap()
+                .to_string(),
+            "26118915-6090-4610-87e4-49d8ca9f808d"
+        );
+
+        let error = user
+            .apply_patch(&patch(serde_json::json!([
+                {"op": "replace", "path": "userName", "value": "babs"},
+                {"op": "replace", "path": "active", "value": "no"}
+            ])))
+            .unwrap_err();
+        assert!(matches!(error, SCIMError::InvalidFieldValue(_)));
+        assert_eq!(user.user_name, "bjensen");
+
+        for path in [
+            r#"name[givenName eq "Barbara"]"#,
+            r#"userName[value eq "b"]"#,
+        ] {
+            let error = user
+                .apply_patch(&patch(serde_json::json!([
+                    {"op": "add", "path": "name.givenName", "value": "Barbara"},
+                    {"op": "remove", "path": path}
+                ])))
+                .unwrap_err();
+            assert!(matches!(error, SCIMError::InvalidPath(_)), "{}", path);
+            assert!(user.name.is_none());
+        }
+    }
 }
@@ -0,0 +1,62 @@
+//! The collection type of the `schemas` list and the multi-valued attributes of users and groups.
+//!
+//! Most resources carry one or two schema URNs, e-mail addresses or phone numbers. With the `smallvec`
+//! feature these lists are stored inline in the resource (`SmallVec<[T; 2]>`) and only spill to the heap
+//! when they grow longer, which removes most of the per-resource allocations in bulk processing. Without
+//! the feature, `Values<T>` is a plain `Vec<T>`.
+//!
+//! Code that has to build with and without the feature constructs values with [`values!`](crate::values)
+//! instead of `vec!`, or by collecting an iterator.
+
+#[cfg(not(feature = "smallvec"))]
+#[doc(hidden)]
+pub use alloc::vec;
+#[cfg(not(feature = "smallvec"))]
+use alloc::vec::Vec;
+
+#[cfg(feature = "smallvec")]
+#[doc(hidden)]
+pub use smallvec;
+
+/// The collection type of `schemas` and multi-valued attributes.
+#[cfg(not(feature = "smallvec"))]
+pub type Values<T> = Vec<T>;
+
+/// The collection type of `schemas` and multi-valued attributes.
+#[cfg(feature = "smallvec")]
+pub type Values<T> = smallvec::SmallVec<[T; 2]>;
+
+/// Creates a [`Values`] containing the arguments, like `vec!`.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::user::{Email, User};
+/// use scim_v2::values;
+///
+/// let user = User {
+///     user_name: "bjensen".to_string(),
+///     emails: Some(values![Email {
+///         value: Some("bjensen@example.com".to_string()),
+///         ..Default::default()
+///     }]),
+///     ..Default::default()
+/// };
+/// assert_eq!(user.emails.unwrap().len(), 1);
+/// ```
+#[cfg(not(feature = "smallvec"))]
+#[macro_export]
+macro_rules! values {
+    ($($values:tt)*) => {
+        $crate::models::values::vec![$($values)*]
+    };
+}
+
+/// Creates a [`Values`] containing the arguments, like `vec!`.
+#[cfg(feature = "smallvec")]
+#[macro_export]
+macro_rules! values {
+    ($($values:tt)*) => {
+        $crate::models::values::smallvec::smallvec![$($values)*]
+    };
+}
@@ -0,0 +1,274 @@
+//! A [`ResourceProvider`] decorator that records an audit trail of every write.
+
+use serde_json::Value;
+
+use crate::models::audit::{AuditAction, AuditEvent, AuditSink, ResourceRef};
+use crate::models::context::RequestContext;
+use crate::models::others::ListQuery;
+use crate::models::tombstone::Tombstone;
+use crate::provider::resource_provider::{Changes, Page, ResourceProvider, Watermark};
+use crate::utils::error::SCIMError;
+
+/// Wraps a [`ResourceProvider`] and sends an [`AuditEvent`] to a sink for every create, replace, delete and
+/// undelete that succeeds.
+///
+/// Replacements read the resource first, so that the event lists the attributes that changed; the event of
+/// a create lists the attributes the resource was created with. Events carry the actor, tenant and
+/// correlation id of the `*_in` method's context, so servers should call those.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Mutex;
+///
+/// use scim_v2::models::audit::AuditEvent;
+/// use scim_v2::models::context::RequestContext;
+/// use scim_v2::provider::auditing::AuditingProvider;
+/// use scim_v2::provider::memory::MemoryStore;
+/// use scim_v2::provider::resource_provider::ResourceProvider;
+/// use serde_json::json;
+///
+/// let log = Mutex::new(Vec::new());
+/// let provider = AuditingProvider::new(MemoryStore::new("https://example.com/v2"), |event: AuditEvent| {
+///     log.lock().unwrap().push(event.to_string())
+/// });
+/// let context = RequestContext::default().with_actor("okta");
+/// let user = provider
+///     .create_in(
+///         &context,
+///         "User",
+///         json!({"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "userName": "bjensen"}),
+///     )
+///     .unwrap();
+/// provider.delete_in(&context, "User", user["id"].as_str().unwrap()).unwrap();
+///
+/// let log = log.lock().unwrap();
+/// assert!(log[0].starts_with("okta create User"));
+/// assert!(log[0].ends_with(": add userName"));
+/// assert!(log[1].starts_with("okta delete User"));
+/// ```
+pub struct AuditingProvider<P, S> {
+    inner: P,
+    sink: S,
+}
+
+impl<P: ResourceProvider, S: AuditSink> AuditingProvider<P, S> {
+    /// Wraps `inner`, sending its audit events to `sink`.
+    pub fn new(inner: P, sink: S) -> Self {
+        AuditingProvider { inner, sink }
+    }
+
+    /// Returns the wrapped provider.
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+}
+
+impl<P: ResourceProvider, S: AuditSink> ResourceProvider for AuditingProvider<P, S> {
+    fn create(&self, resource_type: &str, resource: Value) -> Result<Value, SCIMError> {
+        self.create_in(&RequestContext::default(), resource_type, resource)
+    }
+
+    fn get(&self, resource_type: &str, id: &str) -> Result<Value, SCIMError> {
+        self.inner.get(resource_type, id)
+    }
+
+    fn replace(&self, resource_type: &str, id: &str, resource: Value) -> Result<Value, SCIMError> {
+        self.replace_in(&RequestContext::default(), resource_type, id, resource)
+    }
+
+    fn delete(&self, resource_type: &str, id: &str) -> Result<(), SCIMError> {
+        self.delete_in(&RequestContext::default(), resource_type, id)
+    }
+
+    fn list(&self, resource_type: &str, query: &ListQuery) -> Result<Page, SCIMError> {
+        self.inner.list(resource_type, query)
+    }
+
+    fn changes_since(
+        &self,
+        resource_type: &str,
+        since: Option<&Watermark>,
+    ) -> Result<Changes, SCIMError> {
+        self.inner.changes_since(resource_type, since)
+    }
+
+    fn deleted(&self, resource_type: &str) -> Result<Vec<Tombstone>, SCIMError> {
+        self.inner.deleted(resource_type)
+    }
+
+    fn undelete(&self, resource_type: &str, id: &str) -> Result<Value, SCIMError> {
+        self.undelete_in(&RequestContext::default(), resource_type, id)
+    }
+
+    fn create_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        resource: Value,
+    ) -> Result<Value, SCIMError> {
+        let created = self.inner.create_in(context, resource_type, resource)?;
+        let changes = AuditEvent::changes_between(&Value::Null, &created);
+        self.sink.record(
+            AuditEvent::new(
+                context,
+                AuditAction::Create,
+                ResourceRef::of(resource_type, &created),
+            )
+            .with_changes(changes),
+        );
+        Ok(created)
+    }
+
+    fn get_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+    ) -> Result<Value, SCIMError> {
+        self.inner.get_in(context, resource_type, id)
+    }
+
+    fn replace_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+        resource: Value,
+    ) -> Result<Value, SCIMError> {
+        let before = self.inner.get_in(context, resource_type, id)?;
+        let replaced = self
+            .inner
+            .replace_in(context, resource_type, id, resource)?;
+        let changes = AuditEvent::changes_between(&before, &replaced);
+        self.sink.record(
+            AuditEvent::new(
+                context,
+                AuditAction::Replace,
+                ResourceRef::of(resource_type, &replaced),
+            )
+            .with_changes(changes),
+        );
+        Ok(replaced)
+    }
+
+    fn delete_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+    ) -> Result<(), SCIMError> {
+        self.inner.delete_in(context, resource_type, id)?;
+        self.sink.record(AuditEvent::new(
+            context,
+            AuditAction::Delete,
+            ResourceRef::new(resource_type, id),
+        ));
+        Ok(())
+    }
+
+    fn list_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        query: &ListQuery,
+    ) -> Result<Page, SCIMError> {
+        self.inner.list_in(context, resource_type, query)
+    }
+
+    fn changes_since_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        since: Option<&Watermark>,
+    ) -> Result<Changes, SCIMError> {
+        self.inner.changes_since_in(context, resource_type, since)
+    }
+
+    fn deleted_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+    ) -> Result<Vec<Tombstone>, SCIMError> {
+        self.inner.deleted_in(context, resource_type)
+    }
+
+    fn undelete_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+    ) -> Result<Value, SCIMError> {
+        let restored = self.inner.undelete_in(context, resource_type, id)?;
+        self.sink.record(AuditEvent::new(
+            context,
+            AuditAction::Undelete,
+            ResourceRef::of(resource_type, &restored),
+        ));
+        Ok(restored)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::schema_registry::SchemaRegistry;
+    use crate::models::schema_uri::USER_URN;
+    use crate::provider::memory::MemoryStore;
+
+    #[test]
+    fn records_successful_writes_only() {
+        let events = Mutex::new(Vec::new());
+        let provider = AuditingProvider::new(
+            MemoryStore::with_registry("https://example.com/v2", SchemaRegistry::with_builtins()),
+            |event: AuditEvent| events.lock().unwrap().push(event),
+        );
+        let context = RequestContext::default()
+            .with_actor("okta")
+            .with_correlation_id("req-1");
+        let user = provider
+            .create_in(
+                &context,
+                "User",
+                json!({"schemas": [USER_URN], "userName": "bjensen", "title": "Guide"}),
+            )
+            .unwrap();
+        let id = user["id"].as_str().unwrap();
+        provider
+            .replace(
+                "User",
+                id,
+                json!({"schemas": [USER_URN], "userName": "bjensen", "nickName": "Babs"}),
+            )
+            .unwrap();
+        assert!(
+            provider
+                .create(
+                    "User",
+                    json!({"schemas": [USER_URN], "userName": "bjensen"})
+                )
+                .is_err()
+        );
+        assert!(provider.delete("User", "missing").is_err());
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].correlation_id.as_deref(), Some("req-1"));
+        assert_eq!(
+            events[0].resource.location,
+            user["meta"]["location"].as_str().map(str::to_string)
+        );
+        assert_eq!(events[1].action, AuditAction::Replace);
+        assert_eq!(events[1].actor, None);
+        let changes: Vec<(&str, &str)> = events[1]
+            .changes
+            .iter()
+            .map(|c| (c.op.as_str(), c.path.as_str()))
+            .collect();
+        assert_eq!(changes, [("add", "nickName"), ("remove", "title")]);
+    }
+}
@@ -0,0 +1,620 @@
+//! A [`ResourceProvider`] decorator that caches resources by id and `userName`.
+//!
+//! Identity providers reconcile by reading every user they manage, often once per user by id or with a
+//! `userName eq "..."` filter, far more often than they write. [`CachingProvider`] answers those reads from a
+//! [`ResourceCache`] and keeps it current on every write that goes through it.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use serde_json::Value;
+
+use crate::models::context::RequestContext;
+use crate::models::others::ListQuery;
+use crate::models::tombstone::Tombstone;
+use crate::provider::common;
+use crate::provider::resource_provider::{Changes, Page, ResourceProvider, Watermark};
+use crate::utils::cache::{CacheStats, Lru};
+use crate::utils::error::SCIMError;
+
+/// Where a [`CachingProvider`] keeps resources.
+///
+/// Caches may drop entries at any time; a failing backend should behave like an empty cache rather than
+/// fail the request.
+pub trait ResourceCache: Send + Sync {
+    /// Returns the resource cached under `key`.
+    fn get(&self, key: &str) -> Option<Value>;
+
+    /// Caches `resource` under `key`.
+    fn put(&self, key: &str, resource: &Value);
+
+    /// Removes the entry under `key`, if any.
+    fn remove(&self, key: &str);
+}
+
+/// A thread-safe least-recently-used [`ResourceCache`] in process memory.
+pub struct MemoryCache {
+    inner: Mutex<Lru<Value>>,
+}
+
+impl MemoryCache {
+    /// Creates a cache holding at most `capacity` entries; a capacity of zero disables caching. Each
+    /// resource takes up to two entries, one per key.
+    pub fn new(capacity: usize) -> Self {
+        MemoryCache {
+            inner: Mutex::new(Lru::new(capacity)),
+        }
+    }
+
+    /// Returns the number of cached entries.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.lock().len() == 0
+    }
+
+    /// Returns the hit and miss counters accumulated since the cache was created.
+    pub fn stats(&self) -> CacheStats {
+        self.lock().stats()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Lru<Value>> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl ResourceCache for MemoryCache {
+    fn get(&self, key: &str) -> Option<Value> {
+        self.lock().get(key).cloned()
+    }
+
+    fn put(&self, key: &str, resource: &Value) {
+        self.lock().insert(key, resource.clone());
+    }
+
+    fn remove(&self, key: &str) {
+        self.lock().remove(key);
+    }
+}
+
+/// A [`ResourceCache`] in Redis, which the instances of a service provider can share.
+///
+/// Entries expire after a fixed time, which bounds how stale they get when resources are also written
+/// without going through a [`CachingProvider`]. Redis errors are treated as cache misses, and the
+/// connection is reopened on the next request.
+#[cfg(feature = "redis")]
+pub struct RedisCache {
+    client: redis::Client,
+    connection: Mutex<Option<redis::Connection>>,
+    prefix: String,
+    ttl_seconds: u64,
+}
+
+#[cfg(feature = "redis")]
+impl RedisCache {
+    /// Creates a cache on the Redis server at `url` (e.g. `redis://127.0.0.1/`), prefixing its keys with
+    /// `prefix` and expiring entries after `ttl_seconds`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::OtherError` if the URL is invalid.
+    pub fn open(url: &str, prefix: impl Into<String>, ttl_seconds: u64) -> Result<Self, SCIMError> {
+        let client = redis::Client::open(url)
+            .map_err(|e| SCIMError::OtherError(format!("redis: {}", e).into()))?;
+        Ok(RedisCache {
+            client,
+            connection: Mutex::new(None),
+            prefix: prefix.into(),
+            ttl_seconds,
+        })
+    }
+
+    // Runs a command, dropping the connection if it fails.
+    fn run<T>(
+        &self,
+        command: impl FnOnce(&mut redis::Connection) -> redis::RedisResult<T>,
+    ) -> Option<T> {
+        let mut connection = self
+            .connection
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if connection.is_none() {
+            *connection = self.client.get_connection().ok();
+        }
+        let result = command(connection.as_mut()?);
+        if result.is_err() {
+            *connection = None;
+        }
+        result.ok()
+    }
+}
+
+#[cfg(feature = "redis")]
+impl ResourceCache for RedisCache {
+    fn get(&self, key: &str) -> Option<Value> {
+        let key = format!("{}{}", self.prefix, key);
+        let data: Option<String> = self.run(|c| redis::Commands::get(c, &key))?;
+        serde_json::from_str(&data?).ok()
+    }
+
+    fn put(&self, key: &str, resource: &Value) {
+        let key = format!("{}{}", self.prefix, key);
+        let data = resource.to_string();
+        self.run(|c| redis::Commands::set_ex::<_, _, ()>(c, &key, data, self.ttl_seconds));
+    }
+
+    fn remove(&self, key: &str) {
+        let key = format!("{}{}", self.prefix, key);
+        self.run(|c| redis::Commands::del::<_, ()>(c, &key));
+    }
+}
+
+/// Wraps a [`ResourceProvider`] with a cache of resources keyed by id and by `userName`.
+///
+/// `get` and lists whose filter is just `userName eq "..."` are answered from the cache when they can be,
+/// and fill it when they cannot. Replacing or deleting a resource drops its cache entries both before the
+/// write reaches the wrapped provider and after it succeeds, so a read racing the write cannot leave the old
+/// resource cached, and created and replaced resources are cached as the provider returns them. Other lists
+/// are passed through.
+///
+/// The decorator remembers the `userName` each cached id was stored under, so the `userName` entry is
+/// dropped even when the cache has already evicted the id entry.
+///
+/// Writes that bypass the decorator are not seen by the cache; use [`CachingProvider::invalidate`] for
+/// them, or a cache whose entries expire.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::provider::caching::{CachingProvider, MemoryCache};
+/// use scim_v2::provider::memory::MemoryStore;
+/// use scim_v2::provider::resource_provider::ResourceProvider;
+/// use serde_json::json;
+///
+/// let provider = CachingProvider::new(MemoryStore::new("https://example.com/v2"), MemoryCache::new(10_000));
+/// let user = provider
+///     .create(
+///         "User",
+///         json!({"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "userName": "bjensen"}),
+///     )
+///     .unwrap();
+/// provider.get("User", user["id"].as_str().unwrap()).unwrap();
+/// assert_eq!(provider.cache().stats().hits, 1);
+/// ```
+pub struct CachingProvider<P, C = MemoryCache> {
+    inner: P,
+    cache: C,
+    // The `userName` key each id key was last stored with.
+    user_names: Mutex<HashMap<String, String>>,
+}
+
+impl<P: ResourceProvider, C: ResourceCache> CachingProvider<P, C> {
+    /// Wraps `inner`, caching its resources in `cache`.
+    pub fn new(inner: P, cache: C) -> Self {
+        CachingProvider {
+            inner,
+            cache,
+            user_names: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the wrapped provider.
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Returns the cache.
+    pub fn cache(&self) -> &C {
+        &self.cache
+    }
+
+    /// Drops the cache entries of a resource, e.g. after it was changed without going through this
+    /// provider.
+    pub fn invalidate(&self, resource_type: &str, id: &str) {
+        self.invalidate_in(&RequestContext::default(), resource_type, id);
+    }
+
+    /// Drops the cache entries of a tenant's resource.
+    pub fn invalidate_in(&self, context: &RequestContext, resource_type: &str, id: &str) {
+        let id_key = id_key(context, resource_type, id);
+        if let Some(user_name_key) = self.user_names().remove(&id_key) {
+            self.cache.remove(&user_name_key);
+        }
+        // The cache may be shared with other instances, which record their `userName` keys themselves.
+        let cached = self.cache.get(&id_key);
+        if let Some(user_name) = cached.as_ref().and_then(|c| c["userName"].as_str()) {
+            self.cache
+                .remove(&user_name_key(context, resource_type, user_name));
+        }
+        self.cache.remove(&id_key);
+    }
+
+    fn store(&self, context: &RequestContext, resource_type: &str, resource: &Value) {
+        let id_key = resource["id"]
+            .as_str()
+            .map(|id| id_key(context, resource_type, id));
+        let user_name_key = resource["userName"]
+            .as_str()
+            .map(|user_name| user_name_key(context, resource_type, user_name));
+        if let Some(id_key) = &id_key {
+            self.cache.put(id_key, resource);
+        }
+        if let Some(user_name_key) = &user_name_key {
+            self.cache.put(user_name_key, resource);
+        }
+        if let (Some(id_key), Some(user_name_key)) = (id_key, user_name_key) {
+            let previous = self.user_names().insert(id_key, user_name_key.clone());
+            if let Some(previous) = previous.filter(|previous| *previous != user_name_key) {
+                self.cache.remove(&previous);
+            }
+        }
+    }
+
+    fn user_names(&self) -> MutexGuard<'_, HashMap<String, String>> {
+        self.user_names
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<P: ResourceProvider, C: ResourceCache> ResourceProvider for CachingProvider<P, C> {
+    fn create(&self, resource_type: &str, resource: Value) -> Result<Value, SCIMError> {
+        self.create_in(&RequestContext::default(), resource_type, resource)
+    }
+
+    fn get(&self, resource_type: &str, id: &str) -> Result<Value, SCIMError> {
+        self.get_in(&RequestContext::default(), resource_type, id)
+    }
+
+    fn replace(&self, resource_type: &str, id: &str, resource: Value) -> Result<Value, SCIMError> {
+        self.replace_in(&RequestContext::default(), resource_type, id, resource)
+    }
+
+    fn delete(&self, resource_type: &str, id: &str) -> Result<(), SCIMError> {
+        self.delete_in(&RequestContext::default(), resource_type, id)
+    }
+
+    fn list(&self, resource_type: &str, query: &ListQuery) -> Result<Page, SCIMError> {
+        self.list_in(&RequestContext::default(), resource_type, query)
+    }
+
+    fn changes_since(
+        &self,
+        resource_type: &str,
+        since: Option<&Watermark>,
+    ) -> Result<Changes, SCIMError> {
+        self.inner.changes_since(resource_type, since)
+    }
+
+    fn deleted(&self, resource_type: &str) -> Result<Vec<Tombstone>, SCIMError> {
+        self.inner.deleted(resource_type)
+    }
+
+    fn undelete(&self, resource_type: &str, id: &str) -> Result<Value, SCIMError> {
+        self.undelete_in(&RequestContext::default(), resource_type, id)
+    }
+
+    fn create_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        resource: Value,
+    ) -> Result<Value, SCIMError> {
+        let created = self.inner.create_in(context, resource_type, resource)?;
+        self.store(context, resource_type, &created);
+        Ok(created)
+    }
+
+    fn get_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+    ) -> Result<Value, SCIMError> {
+        if let Some(cached) = self.cache.get(&id_key(context, resource_type, id)) {
+            return Ok(cached);
+        }
+        let resource = self.inner.get_in(context, resource_type, id)?;
+        self.store(context, resource_type, &resource);
+        Ok(resource)
+    }
+
+    fn replace_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+        resource: Value,
+    ) -> Result<Value, SCIMError> {
+        self.invalidate_in(context, resource_type, id);
+        let replaced = self
+            .inner
+            .replace_in(context, resource_type, id, resource)?;
+        self.invalidate_in(context, resource_type, id);
+        self.store(context, resource_type, &replaced);
+        Ok(replaced)
+    }
+
+    fn delete_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+    ) -> Result<(), SCIMError> {
+        self.invalidate_in(context, resource_type, id);
+        self.inner.delete_in(context, resource_type, id)?;
+        self.invalidate_in(context, resource_type, id);
+        Ok(())
+    }
+
+    fn list_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        query: &ListQuery,
+    ) -> Result<Page, SCIMError> {
+        let user_name = common::filter(query)?.and_then(|filter| match filter.comparisons() {
+            [_] if filter.is_conjunction() => filter.equality("userName").map(str::to_string),
+            _ => None,
+        });
+        let Some(user_name) = user_name else {
+            return self.inner.list_in(context, resource_type, query);
+        };
+        if let Some(cached) = self
+            .cache
+            .get(&user_name_key(context, resource_type, &user_name))
+        {
+            return common::page(vec![cached], query);
+        }
+        let page = self.inner.list_in(context, resource_type, query)?;
+        if let ([resource], 1) = (page.resources.as_slice(), page.total_results) {
+            self.store(context, resource_type, resource);
+        }
+        Ok(page)
+    }
+
+    fn changes_since_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        since: Option<&Watermark>,
+    ) -> Result<Changes, SCIMError> {
+        self.inner.changes_since_in(context, resource_type, since)
+    }
+
+    fn deleted_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+    ) -> Result<Vec<Tombstone>, SCIMError> {
+        self.inner.deleted_in(context, resource_type)
+    }
+
+    fn undelete_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+    ) -> Result<Value, SCIMError> {
+        let restored = self.inner.undelete_in(context, resource_type, id)?;
+        self.store(context, resource_type, &restored);
+        Ok(restored)
+    }
+}
+
+// Keys are prefixed with the tenant, if there is one.
+fn id_key(context: &RequestContext, resource_type: &str, id: &str) -> String {
+    format!(
+        "{}{}:id:{}",
+        tenant_prefix(context),
+        resource_type.to_ascii_lowercase(),
+        id.to_lowercase()
+    )
+}
+
+fn user_name_key(context: &RequestContext, resource_type: &str, user_name: &str) -> String {
+    format!(
+        "{}{}:userName:{}",
+        tenant_prefix(context),
+        resource_type.to_ascii_lowercase(),
+        user_name.to_lowercase()
+    )
+}
+
+fn tenant_prefix(context: &RequestContext) -> String {
+    context
+        .tenant_id
+        .as_ref()
+        .map(|tenant_id| format!("{}/", tenant_id))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::schema_registry::SchemaRegistry;
+    use crate::models::schema_uri::USER_URN;
+    use crate::provider::memory::MemoryStore;
+
+    fn provider() -> CachingProvider<MemoryStore> {
+        let store =
+            MemoryStore::with_registry("https://example.com/v2", SchemaRegistry::with_builtins());
+        CachingProvider::new(store, MemoryCache::new(100))
+    }
+
+    fn by_user_name(user_name: &str) -> ListQuery {
+        ListQuery {
+            filter: Some(format!(r#"userName eq "{}""#, user_name)),
+            ..ListQuery::default()
+        }
+    }
+
+    #[test]
+    fn answers_reads_from_the_cache_and_refreshes_it_on_writes() {
+        let provider = provider();
+        let user = provider
+            .create(
+                "User",
+                json!({"schemas": [USER_URN], "userName": "bjensen"}),
+            )
+            .unwrap();
+        let id = user["id"].as_str().unwrap();
+        assert_eq!(provider.get("user", id).unwrap(), user);
+        let page = provider.list("User", &by_user_name("BJENSEN")).unwrap();
+        assert_eq!(page.resources, std::slice::from_ref(&user));
+        assert_eq!(provider.cache().stats().hits, 2);
+
+        let replaced = provider
+            .replace(
+                "User",
+                id,
+                json!({"schemas": [USER_URN], "userName": "babs"}),
+            )
+            .unwrap();
+        assert_eq!(provider.get("User", id).unwrap(), replaced);
+        assert_eq!(
+            provider
+                .list("User", &by_user_name("bjensen"))
+                .unwrap()
+                .total_results,
+            0
+        );
+
+        provider.delete("User", id).unwrap();
+        assert!(matches!(
+            provider.get("User", id),
+            Err(SCIMError::NotFoundError(_))
+        ));
+        assert_eq!(
+            provider
+                .list("User", &by_user_name("babs"))
+                .unwrap()
+                .total_results,
+            0
+        );
+    }
+
+    #[test]
+    fn fills_the_cache_on_misses_and_evicts_the_least_recently_used() {
+        let provider = provider();
+        let id = provider
+            .inner()
+            .create("User", json!({"schemas": [USER_URN], "userName": "jsmith"}))
+            .unwrap()["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            provider
+                .list("User", &by_user_name("jsmith"))
+                .unwrap()
+                .total_results,
+            1
+        );
+        assert_eq!(provider.cache().len(), 2);
+        provider.get("User", &id).unwrap();
+        assert_eq!(provider.cache().stats().hits, 1);
+
+        let cache = MemoryCache::new(2);
+        cache.put("a", &json!(1));
+        cache.put("b", &json!(2));
+        cache.put("c", &json!(3));
+        assert_eq!(cache.get("a"), None);
+
+        let cache = MemoryCache::new(2);
+        cache.put("a", &json!(1));
+        cache.put("b", &json!(2));
+        cache.get("a");
+        cache.put("c", &json!(3));
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("a"), Some(json!(1)));
+    }
+
+    #[test]
+    fn drops_the_user_name_entry_after_the_id_entry_was_evicted() {
+        let store =
+            MemoryStore::with_registry("https://example.com/v2", SchemaRegistry::with_builtins());
+        let provider = CachingProvider::new(store, MemoryCache::new(3));
+        let user = provider
+            .create(
+                "User",
+                json!({"schemas": [USER_URN], "userName": "bjensen"}),
+            )
+            .unwrap();
+        let id = user["id"].as_str().unwrap();
+        assert_eq!(
+            provider
+                .list("User", &by_user_name("bjensen"))
+                .unwrap()
+                .total_results,
+            1
+        );
+        provider
+            .create("User", json!({"schemas": [USER_URN], "userName": "jsmith"}))
+            .unwrap();
+        assert_eq!(
+            provider
+                .cache()
+                .get(&id_key(&RequestContext::default(), "User", id)),
+            None
+        );
+        assert_eq!(
+            provider
+                .list("User", &by_user_name("bjensen"))
+                .unwrap()
+                .total_results,
+            1
+        );
+
+        provider
+            .replace(
+                "User",
+                id,
+                json!({"schemas": [USER_URN], "userName": "babs"}),
+            )
+            .unwrap();
+        assert_eq!(
+            provider
+                .list("User", &by_user_name("bjensen"))
+                .unwrap()
+                .total_results,
+            0
+        );
+    }
+
+    #[test]
+    fn keeps_tenants_apart() {
+        let provider = provider();
+        let acme = RequestContext::default().with_tenant("acme");
+        let globex = RequestContext::default().with_tenant("globex");
+        for context in [&acme, &globex] {
+            provider
+                .create_in(
+                    context,
+                    "User",
+                    json!({"schemas": [USER_URN], "userName": "bjensen"}),
+                )
+                .unwrap();
+        }
+        let acme_page = provider
+            .list_in(&acme, "User", &by_user_name("bjensen"))
+            .unwrap();
+        let globex_page = provider
+            .list_in(&globex, "User", &by_user_name("bjensen"))
+            .unwrap();
+        assert_eq!(provider.cache().stats().hits, 2);
+        assert_ne!(acme_page.resources[0]["id"], globex_page.resources[0]["id"]);
+        assert_eq!(
+            provider
+                .list("User", &by_user_name("bjensen"))
+                .unwrap()
+                .total_results,
+            0
+        );
+    }
+}
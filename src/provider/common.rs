@@ -0,0 +1,297 @@
+//! Helpers shared by the providers in this module: validation, server-assigned attributes and paging.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde_json::{Map, Value};
+
+use crate::models::context::RequestContext;
+use crate::models::others::{ListQuery, PatchOp};
+use crate::models::patch::apply_patch_json;
+use crate::models::resource_types::ResourceType;
+use crate::models::schema_registry::{SchemaRegistry, read_global};
+use crate::models::scim_schema::Meta;
+use crate::provider::query::{self, Filter};
+use crate::provider::resource_provider::{Page, ResourceProvider};
+use crate::utils::cursor::CursorCodec;
+use crate::utils::error::SCIMError;
+use crate::utils::pagination::{PageRequest, PageStrategy};
+
+/// A validated resource, ready to be stored.
+pub(crate) struct Prepared {
+    /// The registered resource type.
+    pub(crate) resource_type: ResourceType,
+    /// The members of the resource, without `id` and `meta`.
+    pub(crate) members: Map<String, Value>,
+    /// The attributes that must be unique among the resources of the type.
+    pub(crate) unique: Vec<UniqueAttribute>,
+}
+
+#[derive(Clone)]
+pub(crate) struct UniqueAttribute {
+    /// The attribute path, qualified with the schema URI for extension attributes.
+    pub(crate) path: String,
+    pub(crate) case_exact: bool,
+}
+
+/// Runs `f` with `registry`, or with the global registry if there is none.
+pub(crate) fn with_registry<R>(
+    registry: Option<&SchemaRegistry>,
+    f: impl FnOnce(&SchemaRegistry) -> R,
+) -> R {
+    match registry {
+        Some(registry) => f(registry),
+        None => f(&read_global()),
+    }
+}
+
+/// Returns the registered name of a resource type.
+pub(crate) fn resource_type_name(
+    registry: Option<&SchemaRegistry>,
+    resource_type: &str,
+) -> Result<String, SCIMError> {
+    with_registry(registry, |registry| {
+        registry
+            .resource_type(resource_type)
+            .map(|rt| rt.name.clone())
+            .ok_or_else(|| {
+                SCIMError::ResourceTypeNotFound(resource_type.into())
+                    .with_resource_type(resource_type)
+            })
+    })
+}
+
+/// Drops the client's `id` and `meta`, and validates the resource against the registry.
+pub(crate) fn prepare(
+    registry: Option<&SchemaRegistry>,
+    resource_type: &str,
+    resource: Value,
+) -> Result<Prepared, SCIMError> {
+    let Value::Object(mut members) = resource else {
+        return Err(SCIMError::InvalidJsonFormat);
+    };
+    members.retain(|key, _| !key.eq_ignore_ascii_case("id") && !key.eq_ignore_ascii_case("meta"));
+    let resource = Value::Object(members);
+    let (registered, unique) = with_registry(registry, |registry| {
+        registry.validate_resource(resource_type, &resource)?;
+        let registered = registry
+            .resource_type(resource_type)
+            .cloned()
+            .ok_or_else(|| {
+                SCIMError::ResourceTypeNotFound(resource_type.into())
+                    .with_resource_type(resource_type)
+            })?;
+        let unique = registry
+            .merged_schema(&registered.name)
+            .map(|schema| {
+                schema
+                    .attributes()
+                    .iter()
+                    .filter(|a| {
+                        matches!(a.attribute.uniqueness.as_deref(), Some("server" | "global"))
+                    })
+                    .map(|a| UniqueAttribute {
+                        path: if a.schema.eq_ignore_ascii_case(&schema.id) {
+                            a.path.clone()
+                        } else {
+                            a.qualified_path()
+                        },
+                        case_exact: a.attribute.case_exact == Some(true),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok::<_, SCIMError>((registered, unique))
+    })?;
+    let Value::Object(members) = resource else {
+        unreachable!("the resource was built from an object")
+    };
+    Ok(Prepared {
+        resource_type: registered,
+        members,
+        unique,
+    })
+}
+
+/// Fails with `SCIMError::ConflictError` if one of `others` has the same value as `resource` for a unique
+/// attribute.
+pub(crate) fn check_unique<'a>(
+    resource: &Value,
+    unique: &[UniqueAttribute],
+    others: impl Iterator<Item = &'a Value> + Clone,
+) -> Result<(), SCIMError> {
+    for attribute in unique {
+        let Some(value) = query::values_at(resource, &attribute.path)
+            .into_iter()
+            .find_map(Value::as_str)
+        else {
+            continue;
+        };
+        let conflict = others
+            .clone()
+            .flat_map(|other| query::values_at(other, &attribute.path))
+            .filter_map(Value::as_str)
+            .any(|other| {
+                if attribute.case_exact {
+                    other == value
+                } else {
+                    other.to_lowercase() == value.to_lowercase()
+                }
+            });
+        if conflict {
+            return Err(SCIMError::ConflictError(
+                format!("{} '{}' is already in use", attribute.path, value).into(),
+            )
+            .with_attribute(&attribute.path)
+            .with_value(Value::String(value.to_string())));
+        }
+    }
+    Ok(())
+}
+
+/// Assigns a new `id` and `meta` to a resource being created.
+pub(crate) fn stamp_created(
+    mut members: Map<String, Value>,
+    resource_type: &ResourceType,
+    base_url: &str,
+) -> Result<Value, SCIMError> {
+    let id = generate_id();
+    members.insert("id".to_string(), Value::String(id.clone()));
+    let meta = Meta::for_resource(resource_type, base_url, &id);
+    stamp(members, meta)
+}
+
+/// Carries `id` and `meta` over from the stored resource to its replacement.
+pub(crate) fn stamp_replaced(
+    mut members: Map<String, Value>,
+    existing: &Value,
+) -> Result<Value, SCIMError> {
+    let meta: Meta = serde_json::from_value(existing["meta"].clone())
+        .map_err(SCIMError::DeserializationError)?;
+    members.insert("id".to_string(), existing["id"].clone());
+    stamp(members, meta)
+}
+
+fn stamp(mut members: Map<String, Value>, mut meta: Meta) -> Result<Value, SCIMError> {
+    meta.touch(&members)?;
+    members.insert(
+        "meta".to_string(),
+        serde_json::to_value(meta).map_err(SCIMError::SerializationError)?,
+    );
+    Ok(Value::Object(members))
+}
+
+/// Patches a stored resource: validates the `PatchOp` message `patch`, reads the resource, applies the
+/// message to it and replaces it with the result, which is returned.
+pub(crate) fn patch<P: ResourceProvider + ?Sized>(
+    provider: &P,
+    context: &RequestContext,
+    resource_type: &str,
+    id: &str,
+    patch: Value,
+) -> Result<Value, SCIMError> {
+    let patch = PatchOp::try_from(patch)?;
+    patch.validate()?;
+    let mut resource = provider.get_in(context, resource_type, id)?;
+    apply_patch_json(&mut resource, &patch)?;
+    provider.replace_in(context, resource_type, id, resource)
+}
+
+/// Parses the filter of a query, if it has one.
+pub(crate) fn filter(query: &ListQuery) -> Result<Option<Filter>, SCIMError> {
+    match query.filter.as_deref().map(str::trim) {
+        Some(filter) if !filter.is_empty() => Filter::parse(filter).map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// Returns the 1-based start index and the page size requested by a query; no `count` means no limit. A
+/// `cursor` takes precedence over `startIndex`.
+pub(crate) fn window(query: &ListQuery) -> Result<(i64, usize), SCIMError> {
+    let start_index = match PageRequest::of(query) {
+        PageRequest::Cursor { cursor, .. } => {
+            CursorCodec::default().decode(&cursor, query)? as i64 + 1
+        }
+        PageRequest::Index { start_index, .. } => start_index,
+    };
+    Ok((start_index, page_size(query)))
+}
+
+fn page_size(query: &ListQuery) -> usize {
+    query
+        .count
+        .map_or(usize::MAX, |count| count.max(0) as usize)
+}
+
+/// Assembles a page cut out at `start_index`, adding `nextCursor` and `previousCursor` when the query
+/// paginates by cursor.
+pub(crate) fn assemble(
+    resources: Vec<Value>,
+    total_results: i64,
+    start_index: i64,
+    query: &ListQuery,
+) -> Page {
+    let (mut next_cursor, mut previous_cursor) = (None, None);
+    if PageRequest::of(query).strategy() == PageStrategy::Cursor {
+        let codec = CursorCodec::default();
+        let offset = start_index as usize - 1;
+        let end = offset + resources.len();
+        if !resources.is_empty() && (end as i64) < total_results {
+            next_cursor = Some(codec.mint(query, end));
+        }
+        if offset > 0 {
+            previous_cursor = Some(codec.mint(query, offset.saturating_sub(page_size(query))));
+        }
+    }
+    Page {
+        resources,
+        total_results,
+        start_index,
+        next_cursor,
+        previous_cursor,
+    }
+}
+
+/// Sorts the resources matching a query and cuts out the requested page.
+pub(crate) fn page(mut matching: Vec<Value>, query: &ListQuery) -> Result<Page, SCIMError> {
+    if let Some(sort_by) = query.sort_by.as_deref().filter(|s| !s.is_empty()) {
+        let descending = query
+            .sort_order
+            .as_deref()
+            .is_some_and(|order| order.eq_ignore_ascii_case("descending"));
+        query::sort(&mut matching, sort_by, descending)?;
+    }
+    let total_results = matching.len() as i64;
+    let (start_index, count) = window(query)?;
+    let resources = matching
+        .into_iter()
+        .skip(start_index as usize - 1)
+        .take(count)
+        .collect();
+    Ok(assemble(resources, total_results, start_index, query))
+}
+
+// Returns a random version 4 UUID. The randomness comes from the standard library's hasher seeds, mixed
+// with a process-wide counter so that ids stay distinct even if the seeds repeat.
+pub(crate) fn generate_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut halves = [0u64; 2];
+    for half in &mut halves {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+        *half = hasher.finish();
+    }
+    let mut bytes = (u128::from(halves[0]) << 64) | u128::from(halves[1]);
+    bytes = (bytes & !(0xf << 76)) | (0x4 << 76);
+    bytes = (bytes & !(0x3 << 62)) | (0x2 << 62);
+    let hex = format!("{:032x}", bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
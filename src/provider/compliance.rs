@@ -0,0 +1,916 @@
+//! Behavioral checks of RFC 7644 for service providers.
+//!
+//! A [`ComplianceKit`] runs a battery of checks (CRUD semantics, PATCH edge cases, filter operators,
+//! pagination invariants and error formats) against a [`ResourceProvider`] or a live endpoint, and returns a
+//! [`ComplianceReport`] naming the section of RFC 7644 each check covers. The checks use the `User` resource
+//! type: they create a few users whose `userName`s share a random prefix, scope their queries to that
+//! prefix, and delete the users again afterwards.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use serde_json::{Value, json};
+
+use crate::client::request::{Requests, ScimRequest};
+use crate::models::context::RequestContext;
+use crate::models::errors::ScimHttpError;
+use crate::models::others::ListQuery;
+use crate::models::schema_uri::{ERROR_URN, PATCH_OP_URN, USER_URN};
+use crate::provider::common::{self, generate_id};
+use crate::provider::resource_provider::ResourceProvider;
+use crate::utils::error::SCIMError;
+
+/// What a check is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Category {
+    Crud,
+    Patch,
+    Filter,
+    Pagination,
+    Errors,
+}
+
+impl Category {
+    /// Returns the category name, e.g. `crud`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Category::Crud => "crud",
+            Category::Patch => "patch",
+            Category::Filter => "filter",
+            Category::Pagination => "pagination",
+            Category::Errors => "errors",
+        }
+    }
+}
+
+impl Display for Category {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The outcome of a check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Passed,
+    /// The check failed, for the given reason.
+    Failed(String),
+    /// The check could not run, for the given reason.
+    Skipped(String),
+}
+
+/// One check of a [`ComplianceReport`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub category: Category,
+    /// What the check expects, e.g. `delete removes the resource`.
+    pub name: String,
+    /// The section of RFC 7644 the check covers, e.g. `3.6`.
+    pub section: &'static str,
+    pub outcome: Outcome,
+}
+
+impl Display for Check {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (label, reason) = match &self.outcome {
+            Outcome::Passed => ("PASS", None),
+            Outcome::Failed(reason) => ("FAIL", Some(reason)),
+            Outcome::Skipped(reason) => ("SKIP", Some(reason)),
+        };
+        write!(
+            f,
+            "{} [{}] {} (RFC 7644 section {})",
+            label, self.category, self.name, self.section
+        )?;
+        if let Some(reason) = reason {
+            write!(f, ": {}", reason)?;
+        }
+        Ok(())
+    }
+}
+
+/// The checks run by a [`ComplianceKit`], in the order they ran. Displays one line per check.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ComplianceReport {
+    pub checks: Vec<Check>,
+}
+
+impl ComplianceReport {
+    /// Returns the number of checks that passed.
+    pub fn passed(&self) -> usize {
+        self.checks
+            .iter()
+            .filter(|check| check.outcome == Outcome::Passed)
+            .count()
+    }
+
+    /// Returns the checks that failed.
+    pub fn failures(&self) -> impl Iterator<Item = &Check> {
+        self.checks
+            .iter()
+            .filter(|check| matches!(check.outcome, Outcome::Failed(_)))
+    }
+
+    /// Returns whether no check failed. Skipped checks do not count against compliance.
+    pub fn is_compliant(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    /// Returns the check with the given name.
+    pub fn check(&self, name: &str) -> Option<&Check> {
+        self.checks.iter().find(|check| check.name == name)
+    }
+}
+
+impl Display for ComplianceReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            writeln!(f, "{}", check)?;
+        }
+        write!(
+            f,
+            "{} of {} checks passed, {} failed",
+            self.passed(),
+            self.checks.len(),
+            self.failures().count()
+        )
+    }
+}
+
+/// Runs the RFC 7644 checks against a provider or a live endpoint.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::provider::compliance::ComplianceKit;
+/// use scim_v2::provider::memory::MemoryStore;
+///
+/// let report = ComplianceKit::new().check_provider(&MemoryStore::new("https://example.com/v2"));
+/// let created = report.check("create assigns an id and meta").unwrap();
+/// assert_eq!(created.outcome, scim_v2::provider::compliance::Outcome::Passed);
+/// println!("{}", report);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComplianceKit {
+    endpoint: String,
+    context: RequestContext,
+}
+
+impl Default for ComplianceKit {
+    fn default() -> Self {
+        ComplianceKit {
+            endpoint: "/Users".to_string(),
+            context: RequestContext::default(),
+        }
+    }
+}
+
+impl ComplianceKit {
+    /// Creates a kit with the `/Users` endpoint and an empty request context.
+    pub fn new() -> Self {
+        ComplianceKit::default()
+    }
+
+    /// Sets the endpoint of the `User` resource type on live endpoints.
+    pub fn with_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = endpoint.into();
+        self
+    }
+
+    /// Sets the context providers are called with, e.g. the tenant to run the checks in.
+    pub fn with_context(mut self, context: RequestContext) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Runs the checks against a provider. PATCH requests are applied the way
+    /// [`ProviderConnector`](crate::sync::pipeline::ProviderConnector) applies them: the resource is read,
+    /// patched with [`apply_patch_json`](crate::models::patch::apply_patch_json) and replaced.
+    pub fn check_provider<P: ResourceProvider + ?Sized>(&self, provider: &P) -> ComplianceReport {
+        run(&Provider {
+            provider,
+            context: &self.context,
+        })
+    }
+
+    /// Runs the checks against the service provider `requests` targets, sending each request with
+    /// `transport`, which returns the status and body of the response.
+    pub fn check_endpoint<T>(&self, requests: &Requests, transport: T) -> ComplianceReport
+    where
+        T: Fn(&ScimRequest) -> Result<(u16, String), SCIMError>,
+    {
+        run(&Endpoint {
+            requests,
+            endpoint: &self.endpoint,
+            transport,
+        })
+    }
+}
+
+// A response of the system under test, as an HTTP status and a JSON body.
+struct Reply {
+    status: u16,
+    body: Value,
+}
+
+// The operations the checks need. An `Err` means the request could not be made at all.
+trait Subject {
+    fn create(&self, resource: &Value) -> Result<Reply, SCIMError>;
+    fn get(&self, id: &str) -> Result<Reply, SCIMError>;
+    fn replace(&self, id: &str, resource: &Value) -> Result<Reply, SCIMError>;
+    fn patch(&self, id: &str, patch: &Value) -> Result<Reply, SCIMError>;
+    fn delete(&self, id: &str) -> Result<Reply, SCIMError>;
+    fn list(&self, query: &ListQuery) -> Result<Reply, SCIMError>;
+}
+
+struct Provider<'a, P: ?Sized> {
+    provider: &'a P,
+    context: &'a RequestContext,
+}
+
+impl<P: ResourceProvider + ?Sized> Provider<'_, P> {
+    // Answers the way a server fronting the provider would.
+    fn reply(status: u16, result: Result<Value, SCIMError>) -> Result<Reply, SCIMError> {
+        Ok(match result {
+            Ok(body) => Reply { status, body },
+            Err(error) => {
+                let error = ScimHttpError::from(&error);
+                Reply {
+                    status: error.status.parse().unwrap_or(500),
+                    body: serde_json::to_value(&error).map_err(SCIMError::SerializationError)?,
+                }
+            }
+        })
+    }
+}
+
+impl<P: ResourceProvider + ?Sized> Subject for Provider<'_, P> {
+    fn create(&self, resource: &Value) -> Result<Reply, SCIMError> {
+        let created = self
+            .provider
+            .create_in(self.context, "User", resource.clone());
+        Self::reply(201, created)
+    }
+
+    fn get(&self, id: &str) -> Result<Reply, SCIMError> {
+        Self::reply(200, self.provider.get_in(self.context, "User", id))
+    }
+
+    fn replace(&self, id: &str, resource: &Value) -> Result<Reply, SCIMError> {
+        let replaced = self
+            .provider
+            .replace_in(self.context, "User", id, resource.clone());
+        Self::reply(200, replaced)
+    }
+
+    fn patch(&self, id: &str, patch: &Value) -> Result<Reply, SCIMError> {
+        let patched = common::patch(self.provider, self.context, "User", id, patch.clone());
+        Self::reply(200, patched)
+    }
+
+    fn delete(&self, id: &str) -> Result<Reply, SCIMError> {
+        let deleted = self.provider.delete_in(self.context, "User", id);
+        Self::reply(204, deleted.map(|_| Value::Null))
+    }
+
+    fn list(&self, query: &ListQuery) -> Result<Reply, SCIMError> {
+        let page = self.provider.list_in(self.context, "User", query);
+        Self::reply(200, page.map(|page| page.to_json()))
+    }
+}
+
+struct Endpoint<'a, T> {
+    requests: &'a Requests,
+    endpoint: &'a str,
+    transport: T,
+}
+
+impl<T> Endpoint<'_, T>
+where
+    T: Fn(&ScimRequest) -> Result<(u16, String), SCIMError>,
+{
+    fn send(&self, request: ScimRequest) -> Result<Reply, SCIMError> {
+        let (status, body) = (self.transport)(&request)?;
+        // Bodies that are not JSON are kept as strings, which fail the checks of the error format.
+        let body = if body.trim().is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_str(&body).unwrap_or(Value::String(body))
+        };
+        Ok(Reply { status, body })
+    }
+}
+
+impl<T> Subject for Endpoint<'_, T>
+where
+    T: Fn(&ScimRequest) -> Result<(u16, String), SCIMError>,
+{
+    fn create(&self, resource: &Value) -> Result<Reply, SCIMError> {
+        self.send(self.requests.create(self.endpoint, resource))
+    }
+
+    fn get(&self, id: &str) -> Result<Reply, SCIMError> {
+        self.send(self.requests.get(self.endpoint, id))
+    }
+
+    fn replace(&self, id: &str, resource: &Value) -> Result<Reply, SCIMError> {
+        self.send(self.requests.replace(self.endpoint, id, resource))
+    }
+
+    fn patch(&self, id: &str, patch: &Value) -> Result<Reply, SCIMError> {
+        self.send(self.requests.patch(self.endpoint, id, patch))
+    }
+
+    fn delete(&self, id: &str) -> Result<Reply, SCIMError> {
+        self.send(self.requests.delete(self.endpoint, id))
+    }
+
+    fn list(&self, query: &ListQuery) -> Result<Reply, SCIMError> {
+        self.send(self.requests.list(self.endpoint, query))
+    }
+}
+
+// The users the checks work with, created up front.
+struct Fixture {
+    prefix: String,
+    // `alice` (title Engineer, work and home emails), `bob` (title Manager, a work email) and `carol`
+    // (no title, no emails), in that order.
+    ids: Vec<String>,
+}
+
+impl Fixture {
+    fn user_name(&self, name: &str) -> String {
+        format!("{}-{}", self.prefix, name)
+    }
+
+    fn user(&self, name: &str) -> Value {
+        let mut user = json!({
+            "schemas": [USER_URN],
+            "userName": self.user_name(name),
+            "displayName": name,
+        });
+        match name {
+            "alice" => {
+                user["title"] = json!("Engineer");
+                user["emails"] = json!([
+                    {"value": format!("{}@work.example.com", self.user_name(name)), "type": "work"},
+                    {"value": format!("{}@home.example.com", self.user_name(name)), "type": "home"},
+                ]);
+            }
+            "bob" => {
+                user["title"] = json!("Manager");
+                user["emails"] = json!([
+                    {"value": format!("{}@work.example.com", self.user_name(name)), "type": "work"},
+                ]);
+            }
+            _ => {}
+        }
+        user
+    }
+
+    // Scopes a filter to the users of the fixture.
+    fn scoped(&self, filter: &str) -> String {
+        format!(r#"userName sw "{}-" and {}"#, self.prefix, filter)
+    }
+}
+
+const USERS: [&str; 3] = ["alice", "bob", "carol"];
+
+struct Run<'s, S: ?Sized> {
+    subject: &'s S,
+    fixture: Fixture,
+    // Every resource created, to delete at the end.
+    created: Vec<String>,
+    checks: Vec<Check>,
+}
+
+fn run<S: Subject + ?Sized>(subject: &S) -> ComplianceReport {
+    let mut run = Run {
+        subject,
+        fixture: Fixture {
+            prefix: format!("compliance-{}", &generate_id()[..8]),
+            ids: Vec::new(),
+        },
+        created: Vec::new(),
+        checks: Vec::new(),
+    };
+    run.check(
+        Category::Crud,
+        "create assigns an id and meta",
+        "3.3",
+        |run| run.create_fixture(),
+    );
+    if run.fixture.ids.len() == USERS.len() {
+        run.crud();
+        run.errors();
+        run.patch();
+        run.filters();
+        run.pagination();
+    } else {
+        run.checks.push(Check {
+            category: Category::Crud,
+            name: "the remaining checks".to_string(),
+            section: "3",
+            outcome: Outcome::Skipped("the test users could not be created".to_string()),
+        });
+    }
+    for id in &run.created {
+        let _ = subject.delete(id);
+    }
+    ComplianceReport { checks: run.checks }
+}
+
+impl<S: Subject + ?Sized> Run<'_, S> {
+    fn check(
+        &mut self,
+        category: Category,
+        name: &str,
+        section: &'static str,
+        check: impl FnOnce(&mut Self) -> Result<(), String>,
+    ) {
+        let outcome = match check(self) {
+            Ok(()) => Outcome::Passed,
+            Err(reason) => Outcome::Failed(reason),
+        };
+        self.checks.push(Check {
+            category,
+            name: name.to_string(),
+            section,
+            outcome,
+        });
+    }
+
+    fn create(&mut self, resource: &Value) -> Result<Value, String> {
+        let reply = expect(self.subject.create(resource), &[201])?;
+        let id = reply["id"]
+            .as_str()
+            .ok_or("the created resource has no id")?;
+        self.created.push(id.to_string());
+        Ok(reply)
+    }
+
+    fn create_fixture(&mut self) -> Result<(), String> {
+        for name in USERS {
+            let mut user = self.fixture.user(name);
+            user["id"] = json!("client-assigned");
+            let created = self.create(&user)?;
+            let id = created["id"].as_str().unwrap_or_default();
+            if id == "client-assigned" {
+                return Err("the id sent by the client was kept".to_string());
+            }
+            if created["meta"]["resourceType"] != "User" {
+                return Err(format!(
+                    "meta.resourceType is {}, not \"User\"",
+                    created["meta"]["resourceType"]
+                ));
+            }
+            self.fixture.ids.push(id.to_string());
+        }
+        Ok(())
+    }
+
+    fn crud(&mut self) {
+        let alice = self.fixture.ids[0].clone();
+        self.check(Category::Crud, "get returns the resource", "3.4.1", |run| {
+            let user = expect(run.subject.get(&alice), &[200])?;
+            if user["id"] != alice.as_str() || user["userName"] != run.fixture.user_name("alice") {
+                return Err(format!("got {}", user));
+            }
+            Ok(())
+        });
+        self.check(
+            Category::Crud,
+            "replace updates the resource and keeps its id",
+            "3.5.1",
+            |run| {
+                let mut user = run.fixture.user("alice");
+                user["displayName"] = json!("Alice Replaced");
+                let replaced = expect(run.subject.replace(&alice, &user), &[200])?;
+                let fetched = expect(run.subject.get(&alice), &[200])?;
+                for user in [&replaced, &fetched] {
+                    if user["id"] != alice.as_str() || user["displayName"] != "Alice Replaced" {
+                        return Err(format!("got {}", user));
+                    }
+                }
+                Ok(())
+            },
+        );
+        self.check(
+            Category::Crud,
+            "delete removes the resource",
+            "3.6",
+            |run| {
+                let created = run.create(&run.fixture.user("deleted"))?;
+                let id = created["id"].as_str().unwrap_or_default();
+                expect(run.subject.delete(id), &[204])?;
+                expect(run.subject.get(id), &[404, 410])?;
+                Ok(())
+            },
+        );
+    }
+
+    fn errors(&mut self) {
+        self.check(
+            Category::Errors,
+            "unknown ids are answered with 404",
+            "3.12",
+            |run| {
+                let unknown = format!("{}-unknown", run.fixture.prefix);
+                expect(run.subject.get(&unknown), &[404])?;
+                expect(
+                    run.subject.replace(&unknown, &run.fixture.user("unknown")),
+                    &[404],
+                )?;
+                expect(run.subject.delete(&unknown), &[404])?;
+                Ok(())
+            },
+        );
+        self.check(
+            Category::Errors,
+            "errors are Error messages with the HTTP status",
+            "3.12",
+            |run| {
+                let reply = run
+                    .subject
+                    .get(&format!("{}-unknown", run.fixture.prefix))
+                    .map_err(|e| e.to_string())?;
+                check_error(&reply, None)
+            },
+        );
+        self.check(
+            Category::Errors,
+            "a duplicate userName is rejected with 409 uniqueness",
+            "3.3",
+            |run| {
+                let reply = run
+                    .subject
+                    .create(&run.fixture.user("alice"))
+                    .map_err(|e| e.to_string())?;
+                if reply.status != 409 {
+                    if let Some(id) = reply.body["id"].as_str() {
+                        run.created.push(id.to_string());
+                    }
+                }
+                check_error(&reply, Some((409, "uniqueness")))
+            },
+        );
+        self.check(
+            Category::Errors,
+            "a malformed filter is rejected with 400 invalidFilter",
+            "3.4.2.2",
+            |run| {
+                let reply = run
+                    .subject
+                    .list(&query(Some(r#"userName eq"#), None, None))
+                    .map_err(|e| e.to_string())?;
+                check_error(&reply, Some((400, "invalidFilter")))
+            },
+        );
+    }
+
+    fn patch(&mut self) {
+        let bob = self.fixture.ids[1].clone();
+        let checks: [(&str, Value, &[u16]); 5] = [
+            (
+                "add appends to a multi-valued attribute",
+                json!([{"op": "add", "path": "emails", "value": [{"value": "bob@other.example.com", "type": "other"}]}]),
+                &[200, 204],
+            ),
+            (
+                "replace sets a single-valued attribute",
+                json!([{"op": "replace", "path": "title", "value": "Director"}]),
+                &[200, 204],
+            ),
+            (
+                "remove with a value filter removes the matching values",
+                json!([{"op": "remove", "path": "emails[type eq \"work\"]"}]),
+                &[200, 204],
+            ),
+            (
+                "remove without a path is rejected with 400",
+                json!([{"op": "remove"}]),
+                &[400],
+            ),
+            (
+                "an unknown op is rejected with 400",
+                json!([{"op": "merge", "path": "title", "value": "Director"}]),
+                &[400],
+            ),
+        ];
+        for (name, operations, statuses) in checks {
+            let patch = json!({"schemas": [PATCH_OP_URN], "Operations": operations});
+            let reply = self.subject.patch(&bob, &patch);
+            self.check(Category::Patch, name, "3.5.2", |run| {
+                expect(reply, statuses)?;
+                if statuses == [400] {
+                    return Ok(());
+                }
+                let user = expect(run.subject.get(&bob), &[200])?;
+                let emails = user["emails"].as_array().cloned().unwrap_or_default();
+                let has = |kind: &str| emails.iter().any(|email| email["type"] == kind);
+                let patched = match name {
+                    "add appends to a multi-valued attribute" => has("work") && has("other"),
+                    "replace sets a single-valued attribute" => user["title"] == "Director",
+                    _ => !has("work") && has("other"),
+                };
+                if patched {
+                    Ok(())
+                } else {
+                    Err(format!("got {}", user))
+                }
+            });
+        }
+    }
+
+    fn filters(&mut self) {
+        let fixture = &self.fixture;
+        let checks = [
+            (
+                "eq",
+                format!(r#"userName eq "{}""#, fixture.user_name("alice")),
+                &["alice"][..],
+            ),
+            (
+                "eq on a case-insensitive attribute",
+                format!(
+                    r#"USERNAME eq "{}""#,
+                    fixture.user_name("alice").to_uppercase()
+                ),
+                &["alice"],
+            ),
+            (
+                "ne",
+                fixture.scoped(&format!(r#"userName ne "{}""#, fixture.user_name("alice"))),
+                &["bob", "carol"],
+            ),
+            ("co", fixture.scoped(r#"userName co "aro""#), &["carol"]),
+            (
+                "sw",
+                format!(r#"userName sw "{}""#, fixture.user_name("b")),
+                &["bob"],
+            ),
+            ("ew", fixture.scoped(r#"userName ew "-alice""#), &["alice"]),
+            ("pr", fixture.scoped("title pr"), &["alice", "bob"]),
+            (
+                "gt",
+                fixture.scoped(&format!(r#"userName gt "{}""#, fixture.user_name("alice"))),
+                &["bob", "carol"],
+            ),
+            (
+                "le",
+                fixture.scoped(&format!(r#"userName le "{}""#, fixture.user_name("bob"))),
+                &["alice", "bob"],
+            ),
+            (
+                "or",
+                format!(
+                    r#"userName eq "{}" or userName eq "{}""#,
+                    fixture.user_name("alice"),
+                    fixture.user_name("carol")
+                ),
+                &["alice", "carol"],
+            ),
+            ("not", fixture.scoped("not (title pr)"), &["carol"]),
+            (
+                "a value path",
+                fixture.scoped(r#"emails[type eq "home"]"#),
+                &["alice"],
+            ),
+        ];
+        for (operator, filter, expected) in checks {
+            let name = format!("filter: {}", operator);
+            self.check(Category::Filter, &name, "3.4.2.2", |run| {
+                let page = expect(run.subject.list(&query(Some(&filter), None, None)), &[200])?;
+                let found = run.names(&page);
+                let expected: BTreeSet<String> = expected.iter().map(|s| s.to_string()).collect();
+                if found == expected {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "{} matched {:?}, expected {:?}",
+                        filter, found, expected
+                    ))
+                }
+            });
+        }
+    }
+
+    fn pagination(&mut self) {
+        let filter = format!(r#"userName sw "{}-""#, self.fixture.prefix);
+        self.check(
+            Category::Pagination,
+            "totalResults counts every match",
+            "3.4.2.4",
+            |run| {
+                let page = expect(run.subject.list(&query(Some(&filter), None, None)), &[200])?;
+                check_page(&page, 3, 3)
+            },
+        );
+        self.check(
+            Category::Pagination,
+            "count limits the page size",
+            "3.4.2.4",
+            |run| {
+                let page = expect(
+                    run.subject.list(&query(Some(&filter), Some(1), Some(2))),
+                    &[200],
+                )?;
+                check_page(&page, 3, 2)?;
+                if page["startIndex"] != 1 {
+                    return Err(format!("startIndex is {}, not 1", page["startIndex"]));
+                }
+                Ok(())
+            },
+        );
+        self.check(
+            Category::Pagination,
+            "pages are disjoint and cover every match",
+            "3.4.2.4",
+            |run| {
+                let mut ids = BTreeSet::new();
+                for start_index in [1, 3] {
+                    let page = expect(
+                        run.subject
+                            .list(&query(Some(&filter), Some(start_index), Some(2))),
+                        &[200],
+                    )?;
+                    for resource in page["Resources"].as_array().into_iter().flatten() {
+                        if !ids.insert(resource["id"].to_string()) {
+                            return Err(format!("{} is on two pages", resource["id"]));
+                        }
+                    }
+                }
+                if ids.len() == 3 {
+                    Ok(())
+                } else {
+                    Err(format!("the pages hold {} of 3 resources", ids.len()))
+                }
+            },
+        );
+        self.check(
+            Category::Pagination,
+            "count=0 returns only totalResults",
+            "3.4.2.4",
+            |run| {
+                let page = expect(
+                    run.subject.list(&query(Some(&filter), None, Some(0))),
+                    &[200],
+                )?;
+                check_page(&page, 3, 0)
+            },
+        );
+        self.check(
+            Category::Pagination,
+            "a startIndex below 1 is interpreted as 1",
+            "3.4.2.4",
+            |run| {
+                let page = expect(
+                    run.subject.list(&query(Some(&filter), Some(0), None)),
+                    &[200],
+                )?;
+                check_page(&page, 3, 3)
+            },
+        );
+    }
+
+    // Returns the names of the fixture users in a list response.
+    fn names(&self, page: &Value) -> BTreeSet<String> {
+        page["Resources"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|resource| resource["userName"].as_str())
+            .map(|user_name| {
+                user_name
+                    .strip_prefix(&format!("{}-", self.fixture.prefix))
+                    .unwrap_or(user_name)
+                    .to_string()
+            })
+            .collect()
+    }
+}
+
+fn query(filter: Option<&str>, start_index: Option<i64>, count: Option<i64>) -> ListQuery {
+    ListQuery {
+        filter: filter.map(str::to_string),
+        start_index,
+        count,
+        attributes: None,
+        excluded_attributes: None,
+        ..ListQuery::default()
+    }
+}
+
+// Returns the body of a reply with one of the expected statuses.
+fn expect(reply: Result<Reply, SCIMError>, statuses: &[u16]) -> Result<Value, String> {
+    let reply = reply.map_err(|e| format!("the request failed: {}", e))?;
+    if statuses.contains(&reply.status) {
+        Ok(reply.body)
+    } else {
+        Err(format!(
+            "status {}, expected {:?}: {}",
+            reply.status, statuses, reply.body
+        ))
+    }
+}
+
+// Checks that a reply is an Error message, optionally with the given status and `scimType`.
+fn check_error(reply: &Reply, expected: Option<(u16, &str)>) -> Result<(), String> {
+    if let Some((status, scim_type)) = expected {
+        if reply.status != status {
+            return Err(format!("status {}, expected {}", reply.status, status));
+        }
+        if reply.body["scimType"] != scim_type {
+            return Err(format!(
+                "scimType is {}, expected \"{}\"",
+                reply.body["scimType"], scim_type
+            ));
+        }
+    }
+    let listed = reply.body["schemas"]
+        .as_array()
+        .is_some_and(|schemas| schemas.iter().any(|schema| schema == ERROR_URN));
+    if !listed {
+        return Err(format!(
+            "the body does not list {}: {}",
+            ERROR_URN, reply.body
+        ));
+    }
+    if reply.body["status"].as_str().and_then(|s| s.parse().ok()) != Some(reply.status) {
+        return Err(format!(
+            "status is {} in the body, {} in the response",
+            reply.body["status"], reply.status
+        ));
+    }
+    Ok(())
+}
+
+fn check_page(page: &Value, total_results: u64, resources: usize) -> Result<(), String> {
+    let found = page["Resources"].as_array().map_or(0, Vec::len);
+    if page["totalResults"] != total_results || found != resources {
+        return Err(format!(
+            "totalResults {} with {} resources, expected {} with {}",
+            page["totalResults"], found, total_results, resources
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::memory::MemoryStore;
+
+    #[test]
+    fn reports_the_behavior_of_a_provider() {
+        let store = MemoryStore::new("https://example.com/v2");
+        let report = ComplianceKit::new().check_provider(&store);
+        for name in [
+            "create assigns an id and meta",
+            "delete removes the resource",
+            "a duplicate userName is rejected with 409 uniqueness",
+            "a malformed filter is rejected with 400 invalidFilter",
+            "filter: eq",
+            "filter: or",
+            "filter: not",
+            "filter: a value path",
+            "pages are disjoint and cover every match",
+            "a startIndex below 1 is interpreted as 1",
+            "add appends to a multi-valued attribute",
+            "replace sets a single-valued attribute",
+            "remove with a value filter removes the matching values",
+            "remove without a path is rejected with 400",
+            "an unknown op is rejected with 400",
+        ] {
+            assert_eq!(
+                report.check(name).unwrap().outcome,
+                Outcome::Passed,
+                "{}",
+                report
+            );
+        }
+        assert!(
+            store
+                .list("User", &query(None, None, None))
+                .unwrap()
+                .resources
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn checks_error_responses_of_live_endpoints() {
+        let requests = Requests::new("https://example.com/v2");
+        // An endpoint that answers everything with HTML.
+        let report = ComplianceKit::new()
+            .check_endpoint(&requests, |_: &ScimRequest| Ok((500, "<html>".to_string())));
+        assert!(!report.is_compliant());
+        assert_eq!(report.checks.len(), 2);
+        assert!(
+            report
+                .to_string()
+                .ends_with("0 of 2 checks passed, 1 failed")
+        );
+    }
+}
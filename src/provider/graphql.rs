@@ -0,0 +1,542 @@
+//! GraphQL object types and resolvers for the Users and Groups of a [`ResourceProvider`] (async-graphql).
+//!
+//! Admin consoles built over a SCIM directory often prefer GraphQL to the SCIM protocol. [`schema`] exposes
+//! the resources of a provider as `User` and `Group` objects, read-only, under a `Query` root with `user`,
+//! `users`, `group` and `groups` fields. The fields selected for `users` and `groups` are passed to the
+//! provider as the `attributes` of the [`ListQuery`], so that providers which project resources return only
+//! what the query asks for. The enterprise User extension is the `enterprise` field of `User`.
+
+use std::marker::PhantomData;
+
+use async_graphql::{
+    Context, EmptyMutation, EmptySubscription, Error, ErrorExtensions, Object, Result, Schema,
+    SelectionField,
+};
+use serde_json::Value;
+
+use crate::models::errors::ScimHttpError;
+use crate::models::others::ListQuery;
+use crate::models::schema_uri::ENTERPRISE_USER_URN;
+use crate::provider::resource_provider::{Page, ResourceProvider};
+use crate::utils::error::SCIMError;
+
+/// The schema [`schema`] builds.
+pub type ScimSchema<P> = Schema<ScimQuery<P>, EmptyMutation, EmptySubscription>;
+
+/// Builds a read-only GraphQL schema over the Users and Groups of `provider`.
+///
+/// Errors of the provider are GraphQL errors whose `status` and `scimType` extensions are those of the SCIM
+/// error response for them.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::provider::graphql::schema;
+/// use scim_v2::provider::memory::MemoryStore;
+/// use scim_v2::provider::resource_provider::ResourceProvider;
+/// use serde_json::json;
+///
+/// let store = MemoryStore::new("https://example.com/v2");
+/// store
+///     .create(
+///         "User",
+///         json!({
+///             "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+///             "userName": "bjensen",
+///             "name": {"givenName": "Barbara", "familyName": "Jensen"},
+///         }),
+///     )
+///     .unwrap();
+///
+/// let schema = schema(store);
+/// let query = r#"{ users(filter: "userName eq \"bjensen\"") { totalResults resources { name { familyName } } } }"#;
+/// # let response = std::pin::pin!(schema.execute(query));
+/// # let std::task::Poll::Ready(response) =
+/// #     response.poll(&mut std::task::Context::from_waker(std::task::Waker::noop()))
+/// # else {
+/// #     panic!()
+/// # };
+/// // With an async runtime: let response = schema.execute(query).await;
+/// assert_eq!(
+///     response.data.into_json().unwrap(),
+///     json!({"users": {"totalResults": 1, "resources": [{"name": {"familyName": "Jensen"}}]}})
+/// );
+/// ```
+pub fn schema<P: ResourceProvider + 'static>(provider: P) -> ScimSchema<P> {
+    Schema::build(ScimQuery::default(), EmptyMutation, EmptySubscription)
+        .data(provider)
+        .finish()
+}
+
+/// The `Query` root of a [`ScimSchema`]. The provider is read from the schema's data.
+pub struct ScimQuery<P> {
+    provider: PhantomData<fn() -> P>,
+}
+
+impl<P> Default for ScimQuery<P> {
+    fn default() -> Self {
+        ScimQuery {
+            provider: PhantomData,
+        }
+    }
+}
+
+#[Object(name = "Query")]
+impl<P: ResourceProvider + 'static> ScimQuery<P> {
+    /// The user with the given id.
+    async fn user(&self, ctx: &Context<'_>, id: String) -> Result<User> {
+        let provider = ctx.data::<P>()?;
+        Ok(User(provider.get("User", &id).map_err(graphql_error)?))
+    }
+
+    /// The users matching a SCIM filter, in pages.
+    #[allow(clippy::too_many_arguments)]
+    async fn users(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<String>,
+        start_index: Option<i64>,
+        count: Option<i64>,
+        sort_by: Option<String>,
+        sort_order: Option<String>,
+    ) -> Result<UserList> {
+        let query = list_query(ctx, filter, start_index, count, sort_by, sort_order);
+        let provider = ctx.data::<P>()?;
+        Ok(UserList(
+            provider.list("User", &query).map_err(graphql_error)?,
+        ))
+    }
+
+    /// The group with the given id.
+    async fn group(&self, ctx: &Context<'_>, id: String) -> Result<Group> {
+        let provider = ctx.data::<P>()?;
+        Ok(Group(provider.get("Group", &id).map_err(graphql_error)?))
+    }
+
+    /// The groups matching a SCIM filter, in pages.
+    #[allow(clippy::too_many_arguments)]
+    async fn groups(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<String>,
+        start_index: Option<i64>,
+        count: Option<i64>,
+        sort_by: Option<String>,
+        sort_order: Option<String>,
+    ) -> Result<GroupList> {
+        let query = list_query(ctx, filter, start_index, count, sort_by, sort_order);
+        let provider = ctx.data::<P>()?;
+        Ok(GroupList(
+            provider.list("Group", &query).map_err(graphql_error)?,
+        ))
+    }
+}
+
+fn list_query(
+    ctx: &Context<'_>,
+    filter: Option<String>,
+    start_index: Option<i64>,
+    count: Option<i64>,
+    sort_by: Option<String>,
+    sort_order: Option<String>,
+) -> ListQuery {
+    let mut attributes = Vec::new();
+    for field in ctx.field().selection_set() {
+        if field.name() == "resources" {
+            selected(field, "", &mut attributes);
+        }
+    }
+    ListQuery {
+        filter,
+        start_index: start_index.or(Some(1)),
+        count: count.or(Some(100)),
+        attributes: (!attributes.is_empty()).then(|| attributes.join(",")),
+        excluded_attributes: None,
+        sort_by,
+        sort_order,
+        cursor: None,
+    }
+}
+
+// Adds the SCIM attribute paths of the fields selected below `field` to `paths`. The `enterprise` field
+// stands for the enterprise User extension, whose attributes are qualified with its URN.
+fn selected(field: SelectionField<'_>, prefix: &str, paths: &mut Vec<String>) {
+    for child in field.selection_set() {
+        let name = child.name();
+        if name.starts_with("__") {
+            continue;
+        }
+        let path = match prefix {
+            "" if name == "enterprise" => {
+                selected(child, ENTERPRISE_USER_URN, paths);
+                continue;
+            }
+            "" => name.to_string(),
+            ENTERPRISE_USER_URN => format!("{}:{}", prefix, name),
+            prefix => format!("{}.{}", prefix, name),
+        };
+        if child.selection_set().next().is_some() {
+            selected(child, &path, paths);
+        } else if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+}
+
+fn graphql_error(error: SCIMError) -> Error {
+    let response = ScimHttpError::from(&error);
+    Error::new(error.to_string()).extend_with(|_, extensions| {
+        extensions.set("status", response.status.clone());
+        if let Some(scim_type) = &response.scim_type {
+            extensions.set("scimType", scim_type.clone());
+        }
+    })
+}
+
+fn string(value: &Value, key: &str) -> Option<String> {
+    value[key].as_str().map(str::to_string)
+}
+
+fn objects<T>(value: &Value, key: &str, wrap: fn(Value) -> T) -> Vec<T> {
+    value[key]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .cloned()
+        .map(wrap)
+        .collect()
+}
+
+/// A User resource.
+pub struct User(pub Value);
+
+#[Object]
+impl User {
+    async fn id(&self) -> Option<String> {
+        string(&self.0, "id")
+    }
+
+    async fn external_id(&self) -> Option<String> {
+        string(&self.0, "externalId")
+    }
+
+    async fn user_name(&self) -> Option<String> {
+        string(&self.0, "userName")
+    }
+
+    async fn name(&self) -> Option<Name> {
+        self.0.get("name").cloned().map(Name)
+    }
+
+    async fn display_name(&self) -> Option<String> {
+        string(&self.0, "displayName")
+    }
+
+    async fn nick_name(&self) -> Option<String> {
+        string(&self.0, "nickName")
+    }
+
+    async fn title(&self) -> Option<String> {
+        string(&self.0, "title")
+    }
+
+    async fn user_type(&self) -> Option<String> {
+        string(&self.0, "userType")
+    }
+
+    async fn preferred_language(&self) -> Option<String> {
+        string(&self.0, "preferredLanguage")
+    }
+
+    async fn locale(&self) -> Option<String> {
+        string(&self.0, "locale")
+    }
+
+    async fn timezone(&self) -> Option<String> {
+        string(&self.0, "timezone")
+    }
+
+    async fn active(&self) -> Option<bool> {
+        self.0["active"].as_bool()
+    }
+
+    async fn emails(&self) -> Vec<MultiValued> {
+        objects(&self.0, "emails", MultiValued)
+    }
+
+    async fn phone_numbers(&self) -> Vec<MultiValued> {
+        objects(&self.0, "phoneNumbers", MultiValued)
+    }
+
+    /// The groups the user belongs to, as maintained by the service provider.
+    async fn groups(&self) -> Vec<MultiValued> {
+        objects(&self.0, "groups", MultiValued)
+    }
+
+    /// The attributes of the enterprise User extension.
+    async fn enterprise(&self) -> Option<EnterpriseUser> {
+        self.0.get(ENTERPRISE_USER_URN).cloned().map(EnterpriseUser)
+    }
+}
+
+/// The components of a user's name.
+pub struct Name(pub Value);
+
+#[Object]
+impl Name {
+    async fn formatted(&self) -> Option<String> {
+        string(&self.0, "formatted")
+    }
+
+    async fn family_name(&self) -> Option<String> {
+        string(&self.0, "familyName")
+    }
+
+    async fn given_name(&self) -> Option<String> {
+        string(&self.0, "givenName")
+    }
+
+    async fn middle_name(&self) -> Option<String> {
+        string(&self.0, "middleName")
+    }
+
+    async fn honorific_prefix(&self) -> Option<String> {
+        string(&self.0, "honorificPrefix")
+    }
+
+    async fn honorific_suffix(&self) -> Option<String> {
+        string(&self.0, "honorificSuffix")
+    }
+}
+
+/// A value of a multi-valued attribute, such as an e-mail address or a group member.
+pub struct MultiValued(pub Value);
+
+#[Object]
+impl MultiValued {
+    async fn value(&self) -> Option<String> {
+        string(&self.0, "value")
+    }
+
+    async fn display(&self) -> Option<String> {
+        string(&self.0, "display")
+    }
+
+    #[graphql(name = "type")]
+    async fn kind(&self) -> Option<String> {
+        string(&self.0, "type")
+    }
+
+    async fn primary(&self) -> Option<bool> {
+        self.0["primary"].as_bool()
+    }
+
+    #[graphql(name = "ref")]
+    async fn reference(&self) -> Option<String> {
+        string(&self.0, "$ref")
+    }
+}
+
+/// The attributes of the enterprise User extension.
+pub struct EnterpriseUser(pub Value);
+
+#[Object]
+impl EnterpriseUser {
+    async fn employee_number(&self) -> Option<String> {
+        string(&self.0, "employeeNumber")
+    }
+
+    async fn cost_center(&self) -> Option<String> {
+        string(&self.0, "costCenter")
+    }
+
+    async fn organization(&self) -> Option<String> {
+        string(&self.0, "organization")
+    }
+
+    async fn division(&self) -> Option<String> {
+        string(&self.0, "division")
+    }
+
+    async fn department(&self) -> Option<String> {
+        string(&self.0, "department")
+    }
+
+    async fn manager(&self) -> Option<MultiValued> {
+        self.0.get("manager").cloned().map(MultiValued)
+    }
+}
+
+/// A Group resource.
+pub struct Group(pub Value);
+
+#[Object]
+impl Group {
+    async fn id(&self) -> Option<String> {
+        string(&self.0, "id")
+    }
+
+    async fn external_id(&self) -> Option<String> {
+        string(&self.0, "externalId")
+    }
+
+    async fn display_name(&self) -> Option<String> {
+        string(&self.0, "displayName")
+    }
+
+    async fn members(&self) -> Vec<MultiValued> {
+        objects(&self.0, "members", MultiValued)
+    }
+}
+
+/// A page of users.
+pub struct UserList(pub Page);
+
+#[Object]
+impl UserList {
+    async fn total_results(&self) -> i64 {
+        self.0.total_results
+    }
+
+    async fn start_index(&self) -> i64 {
+        self.0.start_index
+    }
+
+    async fn items_per_page(&self) -> usize {
+        self.0.resources.len()
+    }
+
+    async fn resources(&self) -> Vec<User> {
+        self.0.resources.iter().cloned().map(User).collect()
+    }
+}
+
+/// A page of groups.
+pub struct GroupList(pub Page);
+
+#[Object]
+impl GroupList {
+    async fn total_results(&self) -> i64 {
+        self.0.total_results
+    }
+
+    async fn start_index(&self) -> i64 {
+        self.0.start_index
+    }
+
+    async fn items_per_page(&self) -> usize {
+        self.0.resources.len()
+    }
+
+    async fn resources(&self) -> Vec<Group> {
+        self.0.resources.iter().cloned().map(Group).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::pin;
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context, Poll, Waker};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::provider::memory::MemoryStore;
+
+    // Records the `attributes` of the list queries it answers.
+    struct Recording {
+        store: MemoryStore,
+        attributes: Arc<Mutex<Vec<Option<String>>>>,
+    }
+
+    impl ResourceProvider for Recording {
+        fn create(&self, resource_type: &str, resource: Value) -> Result<Value, SCIMError> {
+            self.store.create(resource_type, resource)
+        }
+
+        fn get(&self, resource_type: &str, id: &str) -> Result<Value, SCIMError> {
+            self.store.get(resource_type, id)
+        }
+
+        fn replace(
+            &self,
+            resource_type: &str,
+            id: &str,
+            resource: Value,
+        ) -> Result<Value, SCIMError> {
+            self.store.replace(resource_type, id, resource)
+        }
+
+        fn delete(&self, resource_type: &str, id: &str) -> Result<(), SCIMError> {
+            self.store.delete(resource_type, id)
+        }
+
+        fn list(&self, resource_type: &str, query: &ListQuery) -> Result<Page, SCIMError> {
+            self.attributes
+                .lock()
+                .unwrap()
+                .push(query.attributes.clone());
+            self.store.list(resource_type, query)
+        }
+    }
+
+    fn execute<P: ResourceProvider + 'static>(schema: &ScimSchema<P>, query: &str) -> Value {
+        let mut response = pin!(schema.execute(query));
+        let Poll::Ready(response) = response
+            .as_mut()
+            .poll(&mut Context::from_waker(Waker::noop()))
+        else {
+            panic!("the provider is synchronous");
+        };
+        serde_json::to_value(response).unwrap()
+    }
+
+    #[test]
+    fn projects_selected_fields_and_reports_errors() {
+        let store = MemoryStore::new("https://example.com/v2");
+        store
+            .create(
+                "User",
+                json!({
+                    "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User", ENTERPRISE_USER_URN],
+                    "userName": "bjensen",
+                    "emails": [{"value": "bjensen@example.com", "type": "work", "primary": true}],
+                    ENTERPRISE_USER_URN: {"employeeNumber": "701984"}
+                }),
+            )
+            .unwrap();
+        let attributes = Arc::new(Mutex::new(Vec::new()));
+        let schema = schema(Recording {
+            store,
+            attributes: attributes.clone(),
+        });
+
+        let response = execute(
+            &schema,
+            "{ users { resources { userName emails { value type } enterprise { employeeNumber } } } }",
+        );
+        assert_eq!(
+            response["data"]["users"]["resources"],
+            json!([{
+                "userName": "bjensen",
+                "emails": [{"value": "bjensen@example.com", "type": "work"}],
+                "enterprise": {"employeeNumber": "701984"}
+            }])
+        );
+        let recorded = attributes.lock().unwrap();
+        assert_eq!(
+            recorded[0].as_deref(),
+            Some(
+                format!(
+                    "userName,emails.value,emails.type,{}:employeeNumber",
+                    ENTERPRISE_USER_URN
+                )
+                .as_str()
+            )
+        );
+
+        let response = execute(&schema, r#"{ group(id: "missing") { displayName } }"#);
+        assert_eq!(response["errors"][0]["extensions"]["status"], "404");
+    }
+}
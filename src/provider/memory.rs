@@ -0,0 +1,912 @@
+//! An in-memory [`ResourceProvider`] for tests, prototypes and small deployments.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use serde_json::{Value, json};
+
+use crate::models::context::RequestContext;
+use crate::models::others::ListQuery;
+use crate::models::schema_registry::SchemaRegistry;
+use crate::models::schema_uri::PATCH_OP_URN;
+use crate::models::tombstone::Tombstone;
+use crate::provider::common::{self, Prepared};
+use crate::provider::resource_provider::{Page, ResourceProvider};
+use crate::utils::diff::diff_resources;
+use crate::utils::error::SCIMError;
+use crate::utils::etag::check_version;
+use crate::utils::time::now_rfc3339;
+
+/// Stores resources in memory, keyed by resource type and id.
+///
+/// Resources are validated against the schema registry on every write, and attributes whose schema declares
+/// `uniqueness` as `server` or `global` (such as `userName`) must be unique among the resources of a type.
+/// Ids are random UUIDs, and `meta` is maintained with [`Meta::for_resource`] and [`Meta::touch`].
+///
+/// Listing supports the full filter syntax of RFC 7644 §3.4.2.2 (see [`FilterExpr`](crate::models::filter::FilterExpr)), `sortBy`/`sortOrder`,
+/// and `startIndex`/`count` or `cursor`/`count` pagination. Without a sort order, resources are listed in
+/// creation order.
+///
+/// Deleted resources are dropped unless the store is built [`MemoryStore::with_tombstones`], in which case
+/// they are kept as [`Tombstone`]s and can be restored with [`ResourceProvider::undelete`]. Replaced
+/// revisions are dropped unless the store is built [`MemoryStore::with_revisions`].
+///
+/// The resources of each tenant ([`RequestContext::tenant_id`]) are kept apart when accessed through the
+/// `*_in` methods; the plain methods use the resources without a tenant.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::others::ListQuery;
+/// use scim_v2::provider::memory::MemoryStore;
+/// use scim_v2::provider::resource_provider::ResourceProvider;
+/// use serde_json::json;
+///
+/// let store = MemoryStore::new("https://example.com/v2");
+/// let user = store
+///     .create(
+///         "User",
+///         json!({"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "userName": "bjensen"}),
+///     )
+///     .unwrap();
+/// let id = user["id"].as_str().unwrap();
+/// assert_eq!(store.get("User", id).unwrap()["userName"], "bjensen");
+///
+/// let query = ListQuery {
+///     filter: Some(r#"userName eq "BJENSEN""#.to_string()),
+///     ..ListQuery::default()
+/// };
+/// assert_eq!(store.list("User", &query).unwrap().total_results, 1);
+/// ```
+#[derive(Debug)]
+pub struct MemoryStore {
+    base_url: String,
+    registry: Option<SchemaRegistry>,
+    retain_deleted: bool,
+    revision_limit: usize,
+    state: RwLock<HashMap<String, Collection>>,
+}
+
+#[derive(Debug, Default)]
+struct Collection {
+    next: u64,
+    resources: BTreeMap<u64, Value>,
+    ids: HashMap<String, u64>,
+    // The retained deletions, oldest first.
+    deleted: Vec<Deleted>,
+    // The retained prior revisions of each resource, oldest first.
+    history: HashMap<u64, VecDeque<Revision>>,
+}
+
+/// A revision of a resource kept by a [`MemoryStore`] built [`MemoryStore::with_revisions`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Revision {
+    /// The number of the revision: 1 for the resource as created, counting up with every replacement.
+    pub number: u64,
+    pub resource: Value,
+}
+
+#[derive(Debug)]
+struct Deleted {
+    seq: u64,
+    tombstone: Tombstone,
+    resource: Value,
+}
+
+impl MemoryStore {
+    /// Creates an empty store validating against the global schema registry. `base_url` is the service
+    /// provider's base URL, used for `meta.location`.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        MemoryStore {
+            base_url: base_url.into(),
+            registry: None,
+            retain_deleted: false,
+            revision_limit: 0,
+            state: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Creates an empty store validating against `registry` instead of the global schema registry.
+    pub fn with_registry(base_url: impl Into<String>, registry: SchemaRegistry) -> Self {
+        MemoryStore {
+            registry: Some(registry),
+            ..MemoryStore::new(base_url)
+        }
+    }
+
+    /// Keeps deleted resources as tombstones: requests for their ids fail with `SCIMError::Gone`, creating a
+    /// resource with the `externalId` of a deleted one fails with `SCIMError::ConflictError`, and
+    /// [`ResourceProvider::undelete`] restores them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::provider::memory::MemoryStore;
+    /// use scim_v2::provider::resource_provider::ResourceProvider;
+    /// use scim_v2::utils::error::SCIMError;
+    /// use serde_json::json;
+    ///
+    /// let store = MemoryStore::new("https://example.com/v2").with_tombstones();
+    /// let user = json!({
+    ///     "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+    ///     "userName": "bjensen",
+    ///     "externalId": "701984",
+    /// });
+    /// let id = store.create("User", user.clone()).unwrap()["id"].as_str().unwrap().to_string();
+    /// store.delete("User", &id).unwrap();
+    ///
+    /// assert!(matches!(store.get("User", &id), Err(SCIMError::Gone(_))));
+    /// assert!(matches!(store.create("User", user), Err(SCIMError::ConflictError(_))));
+    /// assert_eq!(store.deleted("User").unwrap()[0].external_id.as_deref(), Some("701984"));
+    /// assert_eq!(store.undelete("User", &id).unwrap()["id"], id.as_str());
+    /// ```
+    pub fn with_tombstones(mut self) -> Self {
+        self.retain_deleted = true;
+        self
+    }
+
+    /// Keeps up to `limit` prior revisions of every resource, dropping the oldest beyond that. The revisions
+    /// can be listed with [`MemoryStore::revisions`], compared with [`MemoryStore::diff_revisions`] and
+    /// restored with [`MemoryStore::revert`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::provider::memory::MemoryStore;
+    /// use scim_v2::provider::resource_provider::ResourceProvider;
+    /// use serde_json::json;
+    ///
+    /// let store = MemoryStore::new("https://example.com/v2").with_revisions(10);
+    /// let user = |title: &str| {
+    ///     json!({
+    ///         "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+    ///         "userName": "bjensen",
+    ///         "title": title,
+    ///     })
+    /// };
+    /// let id = store.create("User", user("Tour Guide")).unwrap()["id"].as_str().unwrap().to_string();
+    /// store.replace("User", &id, user("Tour Lead")).unwrap();
+    ///
+    /// let numbers: Vec<u64> = store.revisions("User", &id).unwrap().iter().map(|r| r.number).collect();
+    /// assert_eq!(numbers, [1, 2]);
+    /// assert_eq!(
+    ///     store.diff_revisions("User", &id, 1, 2).unwrap()["Operations"],
+    ///     json!([{"op": "replace", "path": "title", "value": "Tour Lead"}])
+    /// );
+    /// assert_eq!(store.revert("User", &id, 1).unwrap()["title"], "Tour Guide");
+    /// ```
+    pub fn with_revisions(mut self, limit: usize) -> Self {
+        self.revision_limit = limit;
+        self
+    }
+
+    /// Returns the number of resources stored for a resource type.
+    pub fn len(&self, resource_type: &str) -> usize {
+        self.read()
+            .get(&resource_type.to_ascii_lowercase())
+            .map_or(0, |collection| collection.resources.len())
+    }
+
+    /// Returns `true` if no resources are stored for a resource type.
+    pub fn is_empty(&self, resource_type: &str) -> bool {
+        self.len(resource_type) == 0
+    }
+
+    /// Replaces a resource only if its `meta.version` still matches `if_match`, the value of an `If-Match`
+    /// request header.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::PreconditionFailed` if the resource has another version, and the errors of
+    /// [`ResourceProvider::replace`] otherwise.
+    pub fn replace_if_match(
+        &self,
+        resource_type: &str,
+        id: &str,
+        resource: Value,
+        if_match: &str,
+    ) -> Result<Value, SCIMError> {
+        self.replace_checked(
+            &RequestContext::default(),
+            resource_type,
+            id,
+            resource,
+            Some(if_match),
+        )
+    }
+
+    /// Returns the retained revisions of a resource, oldest first; the last is the current resource.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::NotFoundError` (or `SCIMError::Gone`) if no such resource is stored.
+    pub fn revisions(&self, resource_type: &str, id: &str) -> Result<Vec<Revision>, SCIMError> {
+        let state = self.read();
+        let collection = state.get(&collection_key(&RequestContext::default(), resource_type));
+        let Some((seq, current)) = collection.and_then(|collection| collection.get(id)) else {
+            return Err(missing(collection, resource_type, id));
+        };
+        let mut revisions: Vec<Revision> = collection
+            .and_then(|collection| collection.history.get(&seq))
+            .map(|history| history.iter().cloned().collect())
+            .unwrap_or_default();
+        revisions.push(Revision {
+            number: revisions.last().map_or(1, |last| last.number + 1),
+            resource: current.clone(),
+        });
+        Ok(revisions)
+    }
+
+    /// Returns the `PatchOp` message that turns revision `from` of a resource into revision `to`. `id`,
+    /// `meta` and `schemas` are not compared.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::NotFoundError` if the resource, or either revision, is not retained.
+    pub fn diff_revisions(
+        &self,
+        resource_type: &str,
+        id: &str,
+        from: u64,
+        to: u64,
+    ) -> Result<Value, SCIMError> {
+        let revisions = self.revisions(resource_type, id)?;
+        let from = revision(&revisions, resource_type, id, from)?;
+        let to = revision(&revisions, resource_type, id, to)?;
+        let operations = diff_resources(
+            from,
+            to,
+            |name| name.starts_with("urn:"),
+            |path| {
+                !["id", "meta", "schemas"]
+                    .iter()
+                    .any(|name| name.eq_ignore_ascii_case(path))
+            },
+        );
+        Ok(json!({"schemas": [PATCH_OP_URN], "Operations": operations}))
+    }
+
+    /// Replaces a resource with its revision `number`, which is recorded as a new revision.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::NotFoundError` if the resource or the revision is not retained, and the errors of
+    /// [`ResourceProvider::replace`] otherwise.
+    pub fn revert(&self, resource_type: &str, id: &str, number: u64) -> Result<Value, SCIMError> {
+        let revisions = self.revisions(resource_type, id)?;
+        let resource = revision(&revisions, resource_type, id, number)?.clone();
+        self.replace(resource_type, id, resource)
+    }
+
+    // Replaces a resource, first checking its version if `if_match` is given.
+    fn replace_checked(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+        resource: Value,
+        if_match: Option<&str>,
+    ) -> Result<Value, SCIMError> {
+        let Prepared {
+            resource_type: registered,
+            members,
+            unique,
+        } = common::prepare(self.registry.as_ref(), resource_type, resource)?;
+        let name = &registered.name;
+        let mut state = self.write();
+        let collection = state
+            .get_mut(&collection_key(context, name))
+            .ok_or_else(|| missing(None, resource_type, id))?;
+        let (seq, existing) = collection
+            .get(id)
+            .map(|(seq, resource)| (seq, resource.clone()))
+            .ok_or_else(|| missing(Some(collection), resource_type, id))?;
+        if let Some(if_match) = if_match {
+            check_version(
+                if_match,
+                existing["meta"]["version"].as_str().unwrap_or_default(),
+            )
+            .map_err(|error| error.with_resource_type(name).with_resource_id(id))?;
+        }
+        common::check_unique(
+            &Value::Object(members.clone()),
+            &unique,
+            collection.others(Some(seq)),
+        )?;
+
+        let resource = common::stamp_replaced(members, &existing)?;
+        collection.resources.insert(seq, resource.clone());
+        if self.revision_limit > 0 {
+            let history = collection.history.entry(seq).or_default();
+            let number = history.back().map_or(1, |last| last.number + 1);
+            history.push_back(Revision {
+                number,
+                resource: existing,
+            });
+            if history.len() > self.revision_limit {
+                history.pop_front();
+            }
+        }
+        Ok(resource)
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, HashMap<String, Collection>> {
+        self.state.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, HashMap<String, Collection>> {
+        self.state.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+// The revision `number` among the retained `revisions` of a resource.
+fn revision<'a>(
+    revisions: &'a [Revision],
+    resource_type: &str,
+    id: &str,
+    number: u64,
+) -> Result<&'a Value, SCIMError> {
+    revisions
+        .iter()
+        .find(|revision| revision.number == number)
+        .map(|revision| &revision.resource)
+        .ok_or_else(|| {
+            SCIMError::NotFoundError(
+                format!("revision {} of {} {}", number, resource_type, id).into(),
+            )
+        })
+}
+
+// Collections are kept per tenant and resource type.
+fn collection_key(context: &RequestContext, resource_type: &str) -> String {
+    match &context.tenant_id {
+        Some(tenant_id) => format!("{}/{}", tenant_id, resource_type.to_ascii_lowercase()),
+        None => resource_type.to_ascii_lowercase(),
+    }
+}
+
+impl Collection {
+    fn get(&self, id: &str) -> Option<(u64, &Value)> {
+        let seq = *self.ids.get(&id.to_lowercase())?;
+        self.resources.get(&seq).map(|resource| (seq, resource))
+    }
+
+    fn tombstone(&self, id: &str) -> Option<&Tombstone> {
+        self.deleted
+            .iter()
+            .map(|deleted| &deleted.tombstone)
+            .find(|tombstone| tombstone.id.eq_ignore_ascii_case(id))
+    }
+
+    fn others(&self, except: Option<u64>) -> impl Iterator<Item = &Value> + Clone {
+        self.resources
+            .iter()
+            .filter(move |(seq, _)| Some(**seq) != except)
+            .map(|(_, resource)| resource)
+    }
+}
+
+impl ResourceProvider for MemoryStore {
+    fn create(&self, resource_type: &str, resource: Value) -> Result<Value, SCIMError> {
+        self.create_in(&RequestContext::default(), resource_type, resource)
+    }
+
+    fn get(&self, resource_type: &str, id: &str) -> Result<Value, SCIMError> {
+        self.get_in(&RequestContext::default(), resource_type, id)
+    }
+
+    fn replace(&self, resource_type: &str, id: &str, resource: Value) -> Result<Value, SCIMError> {
+        self.replace_in(&RequestContext::default(), resource_type, id, resource)
+    }
+
+    fn delete(&self, resource_type: &str, id: &str) -> Result<(), SCIMError> {
+        self.delete_in(&RequestContext::default(), resource_type, id)
+    }
+
+    fn list(&self, resource_type: &str, query: &ListQuery) -> Result<Page, SCIMError> {
+        self.list_in(&RequestContext::default(), resource_type, query)
+    }
+
+    fn create_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        resource: Value,
+    ) -> Result<Value, SCIMError> {
+        let Prepared {
+            resource_type: registered,
+            members,
+            unique,
+        } = common::prepare(self.registry.as_ref(), resource_type, resource)?;
+        let name = &registered.name;
+        let mut state = self.write();
+        let collection = state.entry(collection_key(context, name)).or_default();
+        common::check_unique(
+            &Value::Object(members.clone()),
+            &unique,
+            collection.others(None),
+        )?;
+        if let Some(external_id) = members.get("externalId").and_then(Value::as_str) {
+            let deleted = collection
+                .deleted
+                .iter()
+                .map(|deleted| &deleted.tombstone)
+                .find(|tombstone| tombstone.external_id.as_deref() == Some(external_id));
+            if let Some(tombstone) = deleted {
+                return Err(tombstone.conflict());
+            }
+        }
+
+        let resource = common::stamp_created(members, &registered, &self.base_url)?;
+        let seq = collection.next;
+        collection.next += 1;
+        let id = resource["id"].as_str().unwrap_or_default().to_lowercase();
+        collection.ids.insert(id, seq);
+        collection.resources.insert(seq, resource.clone());
+        Ok(resource)
+    }
+
+    fn get_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+    ) -> Result<Value, SCIMError> {
+        let state = self.read();
+        let collection = state.get(&collection_key(context, resource_type));
+        match collection.and_then(|collection| collection.get(id)) {
+            Some((_, resource)) => Ok(resource.clone()),
+            None => Err(missing(collection, resource_type, id)),
+        }
+    }
+
+    fn replace_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+        resource: Value,
+    ) -> Result<Value, SCIMError> {
+        self.replace_checked(context, resource_type, id, resource, None)
+    }
+
+    fn delete_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+    ) -> Result<(), SCIMError> {
+        let mut state = self.write();
+        let Some(collection) = state.get_mut(&collection_key(context, resource_type)) else {
+            return Err(missing(None, resource_type, id));
+        };
+        let Some(seq) = collection.ids.remove(&id.to_lowercase()) else {
+            return Err(missing(Some(collection), resource_type, id));
+        };
+        let Some(resource) = collection.resources.remove(&seq) else {
+            return Err(missing(None, resource_type, id));
+        };
+        if self.retain_deleted {
+            let name = common::resource_type_name(self.registry.as_ref(), resource_type)
+                .unwrap_or_else(|_| resource_type.to_string());
+            collection.deleted.push(Deleted {
+                seq,
+                tombstone: Tombstone::of(name, &resource, now_rfc3339()),
+                resource,
+            });
+        } else {
+            collection.history.remove(&seq);
+        }
+        Ok(())
+    }
+
+    fn list_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        query: &ListQuery,
+    ) -> Result<Page, SCIMError> {
+        let name = common::resource_type_name(self.registry.as_ref(), resource_type)?;
+        let filter = common::filter(query)?;
+        let matching = self
+            .read()
+            .get(&collection_key(context, &name))
+            .map(|collection| {
+                collection
+                    .resources
+                    .values()
+                    .filter(|r| filter.as_ref().is_none_or(|f| f.matches(r)))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+        common::page(matching, query)
+    }
+
+    fn deleted_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+    ) -> Result<Vec<Tombstone>, SCIMError> {
+        let name = common::resource_type_name(self.registry.as_ref(), resource_type)?;
+        Ok(self
+            .read()
+            .get(&collection_key(context, &name))
+            .map(|collection| {
+                collection
+                    .deleted
+                    .iter()
+                    .map(|deleted| deleted.tombstone.clone())
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    fn undelete_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+    ) -> Result<Value, SCIMError> {
+        let mut state = self.write();
+        let not_found =
+            || SCIMError::NotFoundError(format!("deleted {} {}", resource_type, id).into());
+        let collection = state
+            .get_mut(&collection_key(context, resource_type))
+            .ok_or_else(not_found)?;
+        let position = collection
+            .deleted
+            .iter()
+            .position(|deleted| deleted.tombstone.id.eq_ignore_ascii_case(id))
+            .ok_or_else(not_found)?;
+        let stored = collection.deleted[position].resource.clone();
+        let Prepared {
+            members, unique, ..
+        } = common::prepare(self.registry.as_ref(), resource_type, stored.clone())?;
+        common::check_unique(
+            &Value::Object(members.clone()),
+            &unique,
+            collection.others(None),
+        )?;
+
+        let resource = common::stamp_replaced(members, &stored)?;
+        let Deleted { seq, .. } = collection.deleted.remove(position);
+        collection.ids.insert(id.to_lowercase(), seq);
+        collection.resources.insert(seq, resource.clone());
+        Ok(resource)
+    }
+}
+
+// The error for an id that is not stored: `Gone` if the collection retains a tombstone for it.
+fn missing(collection: Option<&Collection>, resource_type: &str, id: &str) -> SCIMError {
+    match collection.and_then(|collection| collection.tombstone(id)) {
+        Some(tombstone) => tombstone.gone(),
+        None => SCIMError::NotFoundError(format!("{} {}", resource_type, id).into())
+            .with_resource_type(resource_type)
+            .with_resource_id(id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::schema_uri::USER_URN;
+    use crate::models::user::User;
+    use crate::provider::resource_provider::{Changes, Watermark};
+    use crate::utils::cursor::CursorCodec;
+
+    fn user(user_name: &str) -> Value {
+        json!({"schemas": [USER_URN], "userName": user_name})
+    }
+
+    #[test]
+    fn assigns_ids_and_enforces_uniqueness() {
+        let store =
+            MemoryStore::with_registry("https://example.com/v2", SchemaRegistry::with_builtins());
+        let created = store
+            .create(
+                "User",
+                json!({"schemas": [USER_URN], "userName": "bjensen", "id": "mine"}),
+            )
+            .unwrap();
+        let id = created["id"].as_str().unwrap();
+        assert_ne!(id, "mine");
+        assert_eq!(id.len(), 36);
+        assert_eq!(&id[14..15], "4");
+        assert_eq!(
+            created["meta"]["location"],
+            format!("https://example.com/v2/Users/{}", id)
+        );
+
+        assert!(matches!(
+            store.create("user", user("BJensen")),
+            Err(SCIMError::ConflictError(_))
+        ));
+        let other = store.create("User", user("jsmith")).unwrap();
+        let other_id = other["id"].as_str().unwrap();
+        assert!(matches!(
+            store.replace("User", other_id, user("bjensen")),
+            Err(SCIMError::ConflictError(_))
+        ));
+        let replaced = store.replace("User", id, user("bjensen")).unwrap();
+        assert_eq!(replaced["meta"]["created"], created["meta"]["created"]);
+
+        store.delete("User", id).unwrap();
+        assert!(matches!(
+            store.get("User", id),
+            Err(SCIMError::NotFoundError(_))
+        ));
+        assert!(store.create("User", user("bjensen")).is_ok());
+
+        let mut typed: User = store.get_resource(other_id).unwrap();
+        typed.display_name = Some("Jim Smith".to_string());
+        let replaced = store.replace_resource(&typed).unwrap();
+        assert_eq!(replaced.display_name.as_deref(), Some("Jim Smith"));
+        assert!(matches!(
+            store.replace_resource(&User::default()),
+            Err(SCIMError::MissingRequiredField(_))
+        ));
+    }
+
+    #[test]
+    fn replaces_only_the_version_a_client_read() {
+        let store = MemoryStore::new("https://example.com/v2");
+        let created = store.create("User", user("bjensen")).unwrap();
+        let id = created["id"].as_str().unwrap();
+        let read = created["meta"]["version"].as_str().unwrap();
+
+        let mut renamed = user("bjensen");
+        renamed["displayName"] = json!("Babs");
+        let replaced = store.replace_if_match("User", id, renamed, read).unwrap();
+        assert_ne!(replaced["meta"]["version"], created["meta"]["version"]);
+
+        let error = store
+            .replace_if_match("User", id, user("barbara"), read)
+            .unwrap_err();
+        assert!(matches!(error, SCIMError::PreconditionFailed(_)));
+        assert_eq!(error.resource_id(), Some(id));
+        assert_eq!(error.value(), Some(&replaced["meta"]["version"]));
+        assert_eq!(store.get("User", id).unwrap()["userName"], "bjensen");
+        assert!(
+            store
+                .replace_if_match("User", id, user("barbara"), "*")
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn lists_with_filter_sort_and_pagination() {
+        let store =
+            MemoryStore::with_registry("https://example.com/v2", SchemaRegistry::with_builtins());
+        for name in ["carol", "alice", "dave", "bob"] {
+            store.create("User", user(name)).unwrap();
+        }
+        let page = store
+            .list(
+                "User",
+                &ListQuery {
+                    filter: Some(r#"userName ne "dave""#.to_string()),
+                    sort_by: Some("userName".to_string()),
+                    sort_order: Some("descending".to_string()),
+                    start_index: Some(2),
+                    count: Some(1),
+                    ..ListQuery::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(page.total_results, 3);
+        assert_eq!(page.start_index, 2);
+        assert_eq!(page.resources.len(), 1);
+        assert_eq!(page.resources[0]["userName"], "bob");
+
+        let all = store.list("User", &ListQuery::default()).unwrap();
+        let names: Vec<&str> = all
+            .resources
+            .iter()
+            .map(|r| r["userName"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["carol", "alice", "dave", "bob"]);
+        assert_eq!(all.into_list_response().unwrap().items_per_page, 4);
+        assert!(matches!(
+            store.list("Device", &ListQuery::default()),
+            Err(SCIMError::ResourceTypeNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn keeps_tenants_apart() {
+        let store =
+            MemoryStore::with_registry("https://example.com/v2", SchemaRegistry::with_builtins());
+        let acme = RequestContext::default().with_tenant("acme");
+        let globex = RequestContext::default().with_tenant("globex");
+        let created = store.create_in(&acme, "User", user("bjensen")).unwrap();
+        let id = created["id"].as_str().unwrap();
+        store.create_in(&globex, "User", user("bjensen")).unwrap();
+
+        assert_eq!(store.get_in(&acme, "User", id).unwrap(), created);
+        assert!(matches!(
+            store.get_in(&globex, "User", id),
+            Err(SCIMError::NotFoundError(_))
+        ));
+        assert!(store.get("User", id).is_err());
+        assert!(store.is_empty("User"));
+        let changes = store.changes_since_in(&globex, "User", None).unwrap();
+        assert_eq!(changes.resources.len(), 1);
+        assert_ne!(changes.resources[0]["id"], created["id"]);
+        store.delete_in(&acme, "User", id).unwrap();
+        assert_eq!(
+            store
+                .list_in(&globex, "User", &ListQuery::default())
+                .unwrap()
+                .total_results,
+            1
+        );
+    }
+
+    #[test]
+    fn reports_changes_since_a_watermark() {
+        let store =
+            MemoryStore::with_registry("https://example.com/v2", SchemaRegistry::with_builtins());
+        assert_eq!(
+            store.changes_since("User", None).unwrap(),
+            Changes::default()
+        );
+        let created = store.create("User", user("bjensen")).unwrap();
+
+        let changes = store.changes_since("User", None).unwrap();
+        assert_eq!(changes.resources, std::slice::from_ref(&created));
+        let watermark = changes.watermark.unwrap();
+        assert_eq!(
+            watermark,
+            Watermark::LastModified(
+                created["meta"]["lastModified"]
+                    .as_str()
+                    .unwrap()
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            store
+                .changes_since("User", Some(&watermark))
+                .unwrap()
+                .resources
+                .len(),
+            1
+        );
+
+        let later = Watermark::LastModified("9999-01-01T00:00:00Z".to_string());
+        let changes = store.changes_since("User", Some(&later)).unwrap();
+        assert!(changes.resources.is_empty());
+        assert_eq!(changes.watermark, Some(later));
+        assert!(matches!(
+            store.changes_since("User", Some(&Watermark::Token("abc".to_string()))),
+            Err(SCIMError::InvalidFieldValue(_))
+        ));
+    }
+
+    #[test]
+    fn retains_deleted_resources_as_tombstones() {
+        let store =
+            MemoryStore::with_registry("https://example.com/v2", SchemaRegistry::with_builtins())
+                .with_tombstones();
+        let mut pushed = user("bjensen");
+        pushed["externalId"] = json!("701984");
+        let created = store.create("User", pushed.clone()).unwrap();
+        let id = created["id"].as_str().unwrap();
+        store.delete("User", id).unwrap();
+
+        assert!(matches!(store.get("User", id), Err(SCIMError::Gone(_))));
+        assert!(matches!(
+            store.replace("User", id, user("bjensen")),
+            Err(SCIMError::Gone(_))
+        ));
+        assert!(matches!(store.delete("User", id), Err(SCIMError::Gone(_))));
+        assert!(matches!(
+            store.create("User", pushed),
+            Err(SCIMError::ConflictError(_))
+        ));
+        let tombstones = store.deleted("User").unwrap();
+        assert_eq!(tombstones.len(), 1);
+        assert_eq!(tombstones[0].id, id);
+        assert_eq!(tombstones[0].external_id.as_deref(), Some("701984"));
+
+        let taken = store.create("User", user("bjensen")).unwrap();
+        assert!(matches!(
+            store.undelete("User", id),
+            Err(SCIMError::ConflictError(_))
+        ));
+        store.delete("User", taken["id"].as_str().unwrap()).unwrap();
+        let restored = store.undelete("User", id).unwrap();
+        assert_eq!(restored["id"], created["id"]);
+        assert_eq!(restored["meta"]["created"], created["meta"]["created"]);
+        assert_eq!(store.get("User", id).unwrap(), restored);
+        assert_eq!(store.deleted("User").unwrap().len(), 1);
+        assert!(matches!(
+            store.undelete("User", id),
+            Err(SCIMError::NotFoundError(_))
+        ));
+    }
+
+    #[test]
+    fn paginates_by_cursor() {
+        let store =
+            MemoryStore::with_registry("https://example.com/v2", SchemaRegistry::with_builtins());
+        for name in ["carol", "alice", "dave", "bob", "erin"] {
+            store.create("User", user(name)).unwrap();
+        }
+        let query = |cursor: &str| ListQuery {
+            sort_by: Some("userName".to_string()),
+            count: Some(2),
+            cursor: Some(cursor.to_string()),
+            ..ListQuery::default()
+        };
+        let names = |page: &Page| -> Vec<String> {
+            page.resources
+                .iter()
+                .map(|r| r["userName"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        let first = store.list("User", &query("")).unwrap();
+        assert_eq!(names(&first), ["alice", "bob"]);
+        assert_eq!(first.previous_cursor, None);
+        let second = store
+            .list("User", &query(first.next_cursor.as_deref().unwrap()))
+            .unwrap();
+        assert_eq!(names(&second), ["carol", "dave"]);
+        let third = store
+            .list("User", &query(second.next_cursor.as_deref().unwrap()))
+            .unwrap();
+        assert_eq!(names(&third), ["erin"]);
+        assert_eq!(third.next_cursor, None);
+        let back = store
+            .list("User", &query(third.previous_cursor.as_deref().unwrap()))
+            .unwrap();
+        assert_eq!(names(&back), ["carol", "dave"]);
+        assert_eq!(second.to_json()["nextCursor"], json!(second.next_cursor));
+        assert!(matches!(
+            store.list("User", &query("not-a-cursor")),
+            Err(SCIMError::InvalidFieldValue(_))
+        ));
+        let forged = CursorCodec::default().mint(&query(""), usize::MAX);
+        assert!(matches!(
+            store.list("User", &query(&forged)),
+            Err(SCIMError::InvalidFieldValue(_))
+        ));
+    }
+
+    #[test]
+    fn keeps_a_bounded_history_of_revisions() {
+        let store = MemoryStore::new("https://example.com/v2").with_revisions(2);
+        let id = store.create("User", user("bjensen")).unwrap()["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        for name in ["babs", "barbara", "bjensen2"] {
+            store.replace("User", &id, user(name)).unwrap();
+        }
+        let revisions = store.revisions("User", &id).unwrap();
+        let numbers: Vec<u64> = revisions.iter().map(|r| r.number).collect();
+        assert_eq!(numbers, [2, 3, 4]);
+        assert_eq!(revisions[0].resource["userName"], "babs");
+        assert!(matches!(
+            store.diff_revisions("User", &id, 1, 4),
+            Err(SCIMError::NotFoundError(_))
+        ));
+        assert_eq!(
+            store.diff_revisions("User", &id, 4, 2).unwrap()["Operations"],
+            json!([{"op": "replace", "path": "userName", "value": "babs"}])
+        );
+
+        store.revert("User", &id, 2).unwrap();
+        assert_eq!(store.get("User", &id).unwrap()["userName"], "babs");
+        assert_eq!(
+            store.revisions("User", &id).unwrap().last().unwrap().number,
+            5
+        );
+        store.delete("User", &id).unwrap();
+        assert!(store.read()["user"].history.is_empty());
+    }
+}
@@ -0,0 +1,292 @@
+//! A [`ResourceProvider`] decorator that reports operation metrics.
+
+use std::time::Instant;
+
+use serde_json::Value;
+
+use crate::models::context::RequestContext;
+use crate::models::others::ListQuery;
+use crate::models::tombstone::Tombstone;
+use crate::provider::resource_provider::{Changes, Page, ResourceProvider, Watermark};
+use crate::utils::error::SCIMError;
+use crate::utils::metrics::{Metrics, record_operation};
+
+/// Wraps a [`ResourceProvider`] and reports the count, latency and errors of every operation to a
+/// [`Metrics`] implementation, under the names defined in [`crate::utils::metrics`].
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Mutex;
+///
+/// use scim_v2::provider::memory::MemoryStore;
+/// use scim_v2::provider::metered::MeteredProvider;
+/// use scim_v2::provider::resource_provider::ResourceProvider;
+/// use scim_v2::utils::metrics::{ERRORS, Metrics};
+///
+/// #[derive(Default)]
+/// struct Errors(Mutex<Vec<String>>);
+///
+/// impl Metrics for Errors {
+///     fn increment(&self, name: &str, labels: &[(&str, &str)]) {
+///         if name == ERRORS {
+///             self.0.lock().unwrap().push(format!("{:?}", labels));
+///         }
+///     }
+///
+///     fn observe(&self, _name: &str, _value: f64, _labels: &[(&str, &str)]) {}
+/// }
+///
+/// let provider = MeteredProvider::new(MemoryStore::new("https://example.com/v2"), Errors::default());
+/// assert!(provider.get("User", "missing").is_err());
+/// assert!(provider.metrics().0.lock().unwrap()[0].contains(r#"("status", "404")"#));
+/// ```
+pub struct MeteredProvider<P, M> {
+    inner: P,
+    metrics: M,
+}
+
+impl<P: ResourceProvider, M: Metrics> MeteredProvider<P, M> {
+    /// Wraps `inner`, reporting to `metrics`.
+    pub fn new(inner: P, metrics: M) -> Self {
+        MeteredProvider { inner, metrics }
+    }
+
+    /// Returns the wrapped provider.
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Returns the metrics implementation.
+    pub fn metrics(&self) -> &M {
+        &self.metrics
+    }
+
+    fn measure<T>(
+        &self,
+        operation: &str,
+        resource_type: &str,
+        f: impl FnOnce() -> Result<T, SCIMError>,
+    ) -> Result<T, SCIMError> {
+        let started = Instant::now();
+        let result = f();
+        record_operation(
+            &self.metrics,
+            operation,
+            resource_type,
+            started.elapsed(),
+            &result,
+        );
+        result
+    }
+}
+
+impl<P: ResourceProvider, M: Metrics> ResourceProvider for MeteredProvider<P, M> {
+    fn create(&self, resource_type: &str, resource: Value) -> Result<Value, SCIMError> {
+        self.measure("create", resource_type, || {
+            self.inner.create(resource_type, resource)
+        })
+    }
+
+    fn get(&self, resource_type: &str, id: &str) -> Result<Value, SCIMError> {
+        self.measure("get", resource_type, || self.inner.get(resource_type, id))
+    }
+
+    fn replace(&self, resource_type: &str, id: &str, resource: Value) -> Result<Value, SCIMError> {
+        self.measure("replace", resource_type, || {
+            self.inner.replace(resource_type, id, resource)
+        })
+    }
+
+    fn delete(&self, resource_type: &str, id: &str) -> Result<(), SCIMError> {
+        self.measure("delete", resource_type, || {
+            self.inner.delete(resource_type, id)
+        })
+    }
+
+    fn list(&self, resource_type: &str, query: &ListQuery) -> Result<Page, SCIMError> {
+        self.measure("list", resource_type, || {
+            self.inner.list(resource_type, query)
+        })
+    }
+
+    fn changes_since(
+        &self,
+        resource_type: &str,
+        since: Option<&Watermark>,
+    ) -> Result<Changes, SCIMError> {
+        self.measure("changes", resource_type, || {
+            self.inner.changes_since(resource_type, since)
+        })
+    }
+
+    fn deleted(&self, resource_type: &str) -> Result<Vec<Tombstone>, SCIMError> {
+        self.measure("deleted", resource_type, || {
+            self.inner.deleted(resource_type)
+        })
+    }
+
+    fn undelete(&self, resource_type: &str, id: &str) -> Result<Value, SCIMError> {
+        self.measure("undelete", resource_type, || {
+            self.inner.undelete(resource_type, id)
+        })
+    }
+
+    fn create_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        resource: Value,
+    ) -> Result<Value, SCIMError> {
+        self.measure("create", resource_type, || {
+            self.inner.create_in(context, resource_type, resource)
+        })
+    }
+
+    fn get_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+    ) -> Result<Value, SCIMError> {
+        self.measure("get", resource_type, || {
+            self.inner.get_in(context, resource_type, id)
+        })
+    }
+
+    fn replace_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+        resource: Value,
+    ) -> Result<Value, SCIMError> {
+        self.measure("replace", resource_type, || {
+            self.inner.replace_in(context, resource_type, id, resource)
+        })
+    }
+
+    fn delete_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+    ) -> Result<(), SCIMError> {
+        self.measure("delete", resource_type, || {
+            self.inner.delete_in(context, resource_type, id)
+        })
+    }
+
+    fn list_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        query: &ListQuery,
+    ) -> Result<Page, SCIMError> {
+        self.measure("list", resource_type, || {
+            self.inner.list_in(context, resource_type, query)
+        })
+    }
+
+    fn changes_since_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        since: Option<&Watermark>,
+    ) -> Result<Changes, SCIMError> {
+        self.measure("changes", resource_type, || {
+            self.inner.changes_since_in(context, resource_type, since)
+        })
+    }
+
+    fn deleted_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+    ) -> Result<Vec<Tombstone>, SCIMError> {
+        self.measure("deleted", resource_type, || {
+            self.inner.deleted_in(context, resource_type)
+        })
+    }
+
+    fn undelete_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+    ) -> Result<Value, SCIMError> {
+        self.measure("undelete", resource_type, || {
+            self.inner.undelete_in(context, resource_type, id)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::sync::Mutex;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::schema_registry::SchemaRegistry;
+    use crate::models::schema_uri::USER_URN;
+    use crate::provider::memory::MemoryStore;
+    use crate::utils::metrics::{ERRORS, OPERATION_SECONDS, OPERATIONS};
+
+    #[derive(Default)]
+    struct Recorder(Mutex<BTreeMap<String, usize>>);
+
+    impl Metrics for Recorder {
+        fn increment(&self, name: &str, labels: &[(&str, &str)]) {
+            let values: Vec<&str> = labels.iter().map(|(_, value)| *value).collect();
+            *self
+                .0
+                .lock()
+                .unwrap()
+                .entry(format!("{} {}", name, values.join(",")))
+                .or_default() += 1;
+        }
+
+        fn observe(&self, name: &str, value: f64, _labels: &[(&str, &str)]) {
+            assert!(value >= 0.0);
+            *self.0.lock().unwrap().entry(name.to_string()).or_default() += 1;
+        }
+    }
+
+    #[test]
+    fn counts_operations_and_errors_by_scim_type() {
+        let provider = MeteredProvider::new(
+            MemoryStore::with_registry("https://example.com/v2", SchemaRegistry::with_builtins()),
+            Recorder::default(),
+        );
+        let user = json!({"schemas": [USER_URN], "userName": "bjensen"});
+        provider.create("User", user.clone()).unwrap();
+        assert!(provider.create("User", user).is_err());
+        assert!(
+            provider
+                .list_in(
+                    &RequestContext::default(),
+                    "User",
+                    &ListQuery {
+                        filter: Some("userName eq".to_string()),
+                        ..ListQuery::default()
+                    }
+                )
+                .is_err()
+        );
+
+        let recorded = provider.metrics().0.lock().unwrap();
+        assert_eq!(recorded[&format!("{} create,User", OPERATIONS)], 2);
+        assert_eq!(recorded[&format!("{} list,User", OPERATIONS)], 1);
+        assert_eq!(recorded[OPERATION_SECONDS], 3);
+        assert_eq!(
+            recorded[&format!("{} create,User,409,uniqueness", ERRORS)],
+            1
+        );
+        assert_eq!(
+            recorded[&format!("{} list,User,400,invalidFilter", ERRORS)],
+            1
+        );
+    }
+}
@@ -0,0 +1,762 @@
+//! A [`ResourceProvider`] that stores resources as JSONB in PostgreSQL.
+
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use postgres::error::SqlState;
+use postgres::types::ToSql;
+use postgres::{Client, GenericClient, NoTls};
+use serde_json::{Map, Value};
+
+use crate::models::others::ListQuery;
+use crate::models::schema_coerce::canonicalize_attribute_names;
+use crate::models::schema_registry::SchemaRegistry;
+use crate::models::scim_schema::{MergedAttribute, MergedSchema};
+use crate::provider::common::{self, Prepared, UniqueAttribute};
+use crate::provider::query::{Comparison, Filter, Operator};
+use crate::provider::resource_provider::{Page, ResourceProvider};
+use crate::utils::error::SCIMError;
+use crate::utils::etag::check_version;
+
+const CREATE_TABLES: &str = "
+    CREATE TABLE IF NOT EXISTS scim_resources (
+        seq BIGSERIAL PRIMARY KEY,
+        resource_type TEXT NOT NULL,
+        id TEXT NOT NULL,
+        version TEXT NOT NULL,
+        data JSONB NOT NULL,
+        UNIQUE (resource_type, id)
+    );
+    CREATE UNIQUE INDEX IF NOT EXISTS scim_resources_user_name
+        ON scim_resources (resource_type, lower(data ->> 'userName'));
+    CREATE INDEX IF NOT EXISTS scim_resources_data ON scim_resources USING GIN (data jsonb_path_ops);
+";
+
+/// Stores resources as JSONB documents in PostgreSQL.
+///
+/// Behaves like [`MemoryStore`](crate::provider::memory::MemoryStore): resources are validated against the
+/// schema registry, unique attributes are enforced, and `id` and `meta` are assigned by the store. Attribute
+/// names are canonicalized with [`canonicalize_attribute_names`] before a resource is stored, so filters
+/// translate to JSONB predicates: comparisons on attributes the schema defines, and sorting by singular
+/// attributes, run in the database, together with the pagination. Comparisons on unknown attributes, and
+/// `or`, `not` and value paths, are evaluated on the rows the comparisons joined by `and` at the top of the
+/// filter select.
+///
+/// Every row carries the resource's `meta.version`, and writes only succeed if the row still has the
+/// version they read, so concurrent writers from several processes cannot overwrite each other's changes:
+/// the later writer gets `SCIMError::PreconditionFailed`. [`PostgresStore::replace_if_match`] exposes the same check to clients sending `If-Match`.
+///
+/// The resources live in the `scim_resources` table, which is created if it does not exist. Database errors
+/// are reported as `SCIMError::OtherError`, except unique violations, which are `SCIMError::ConflictError`.
+/// The store does not keep tenants apart; use one database or schema per tenant.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scim_v2::models::others::ListQuery;
+/// use scim_v2::provider::postgres::PostgresStore;
+/// use scim_v2::provider::resource_provider::ResourceProvider;
+///
+/// let store = PostgresStore::connect("host=localhost user=scim", "https://example.com/v2").unwrap();
+/// let query = ListQuery {
+///     filter: Some(r#"emails.value ew "@example.com""#.to_string()),
+///     sort_by: Some("name.familyName".to_string()),
+///     ..ListQuery::default()
+/// };
+/// let page = store.list("User", &query).unwrap();
+/// ```
+pub struct PostgresStore {
+    base_url: String,
+    registry: Option<SchemaRegistry>,
+    client: Mutex<Client>,
+}
+
+impl std::fmt::Debug for PostgresStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PostgresStore")
+            .field("base_url", &self.base_url)
+            .field("registry", &self.registry)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PostgresStore {
+    /// Connects without TLS using a connection string such as `host=localhost user=scim`, validating
+    /// against the global schema registry.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::OtherError` if the connection fails or the table cannot be created.
+    pub fn connect(params: &str, base_url: impl Into<String>) -> Result<Self, SCIMError> {
+        let client = Client::connect(params, NoTls).map_err(storage_error)?;
+        PostgresStore::from_client(client, base_url)
+    }
+
+    /// Creates a store on a connected client, validating against the global schema registry.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::OtherError` if the table cannot be created.
+    pub fn from_client(mut client: Client, base_url: impl Into<String>) -> Result<Self, SCIMError> {
+        client.batch_execute(CREATE_TABLES).map_err(storage_error)?;
+        Ok(PostgresStore {
+            base_url: base_url.into(),
+            registry: None,
+            client: Mutex::new(client),
+        })
+    }
+
+    /// Creates a store on a connected client, validating against `registry` instead of the global schema
+    /// registry.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::OtherError` if the table cannot be created.
+    pub fn with_registry(
+        client: Client,
+        base_url: impl Into<String>,
+        registry: SchemaRegistry,
+    ) -> Result<Self, SCIMError> {
+        Ok(PostgresStore {
+            registry: Some(registry),
+            ..PostgresStore::from_client(client, base_url)?
+        })
+    }
+
+    /// Replaces a resource only if its `meta.version` still matches `version`, e.g. the value of an
+    /// `If-Match` request header.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::PreconditionFailed` if the resource has another version, and the errors of
+    /// [`ResourceProvider::replace`] otherwise.
+    pub fn replace_if_match(
+        &self,
+        resource_type: &str,
+        id: &str,
+        resource: Value,
+        version: &str,
+    ) -> Result<Value, SCIMError> {
+        self.write(resource_type, Some(id), resource, Some(version))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Client> {
+        self.client.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn merged_schema(&self, name: &str) -> Option<MergedSchema> {
+        common::with_registry(self.registry.as_ref(), |registry| {
+            registry.merged_schema(name)
+        })
+    }
+
+    // Creates a resource, or replaces the resource with the given id.
+    fn write(
+        &self,
+        resource_type: &str,
+        id: Option<&str>,
+        resource: Value,
+        expected_version: Option<&str>,
+    ) -> Result<Value, SCIMError> {
+        let Prepared {
+            resource_type: registered,
+            members,
+            unique,
+        } = common::prepare(self.registry.as_ref(), resource_type, resource)?;
+        let name = &registered.name;
+        let members = match self.merged_schema(name) {
+            Some(schema) => {
+                let mut resource = Value::Object(members);
+                canonicalize_attribute_names(&mut resource, &schema);
+                into_members(resource)
+            }
+            None => members,
+        };
+        let key = name.to_ascii_lowercase();
+        let mut client = self.lock();
+        let mut transaction = client.transaction().map_err(storage_error)?;
+
+        let existing = match id {
+            Some(id) => {
+                let existing = find(&mut transaction, &key, id)?.ok_or_else(|| {
+                    SCIMError::NotFoundError(format!("{} {}", resource_type, id).into())
+                })?;
+                if let Some(expected) = expected_version {
+                    check_version(expected, &existing.version)
+                        .map_err(|error| error.with_resource_type(name).with_resource_id(id))?;
+                }
+                Some(existing)
+            }
+            None => None,
+        };
+        check_unique(
+            &mut transaction,
+            &key,
+            &Value::Object(members.clone()),
+            &unique,
+            existing.as_ref().map(|e| e.seq),
+        )?;
+
+        let resource = match &existing {
+            Some(existing) => common::stamp_replaced(members, &existing.data)?,
+            None => common::stamp_created(members, &registered, &self.base_url)?,
+        };
+        let version = resource["meta"]["version"].as_str().unwrap_or_default();
+        let written = match &existing {
+            Some(existing) => transaction.execute(
+                "UPDATE scim_resources SET version = $1, data = $2 WHERE seq = $3 AND version = $4",
+                &[&version, &resource, &existing.seq, &existing.version],
+            ),
+            None => transaction.execute(
+                "INSERT INTO scim_resources (resource_type, id, version, data) VALUES ($1, $2, $3, $4)",
+                &[
+                    &key,
+                    &resource["id"].as_str().unwrap_or_default().to_lowercase(),
+                    &version,
+                    &resource,
+                ],
+            ),
+        }
+        .map_err(storage_error)?;
+        if written == 0 {
+            return Err(SCIMError::PreconditionFailed(
+                format!(
+                    "{} {} was modified concurrently",
+                    resource_type,
+                    id.unwrap_or_default()
+                )
+                .into(),
+            ));
+        }
+        transaction.commit().map_err(storage_error)?;
+        Ok(resource)
+    }
+}
+
+impl ResourceProvider for PostgresStore {
+    fn create(&self, resource_type: &str, resource: Value) -> Result<Value, SCIMError> {
+        self.write(resource_type, None, resource, None)
+    }
+
+    fn get(&self, resource_type: &str, id: &str) -> Result<Value, SCIMError> {
+        find(&mut *self.lock(), &resource_type.to_ascii_lowercase(), id)?
+            .map(|row| row.data)
+            .ok_or_else(|| SCIMError::NotFoundError(format!("{} {}", resource_type, id).into()))
+    }
+
+    fn replace(&self, resource_type: &str, id: &str, resource: Value) -> Result<Value, SCIMError> {
+        self.write(resource_type, Some(id), resource, None)
+    }
+
+    fn delete(&self, resource_type: &str, id: &str) -> Result<(), SCIMError> {
+        let deleted = self
+            .lock()
+            .execute(
+                "DELETE FROM scim_resources WHERE resource_type = $1 AND id = $2",
+                &[&resource_type.to_ascii_lowercase(), &id.to_lowercase()],
+            )
+            .map_err(storage_error)?;
+        if deleted == 0 {
+            return Err(SCIMError::NotFoundError(
+                format!("{} {}", resource_type, id).into(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn list(&self, resource_type: &str, query: &ListQuery) -> Result<Page, SCIMError> {
+        let name = common::resource_type_name(self.registry.as_ref(), resource_type)?;
+        let filter = common::filter(query)?;
+        let schema = self.merged_schema(&name);
+
+        let mut sql = Sql::default();
+        let key = sql.param(name.to_ascii_lowercase());
+        sql.conditions.push(format!("resource_type = {}", key));
+        // The comparisons at the top of the filter narrow the candidates even if the rest of it, such as an
+        // `or`, is left to Rust.
+        let mut translated = filter.as_ref().is_none_or(Filter::is_conjunction);
+        for comparison in filter.iter().flat_map(Filter::comparisons) {
+            match schema.as_ref().and_then(|s| sql.comparison(s, comparison)) {
+                Some(condition) => sql.conditions.push(condition),
+                None => translated = false,
+            }
+        }
+        let order = match query.sort_by.as_deref().filter(|s| !s.is_empty()) {
+            Some(sort_by) => schema.as_ref().and_then(|schema| {
+                let descending = query
+                    .sort_order
+                    .as_deref()
+                    .is_some_and(|order| order.eq_ignore_ascii_case("descending"));
+                sql.order(schema, sort_by, descending)
+            }),
+            None => Some(String::new()),
+        };
+
+        let mut client = self.lock();
+        let (Some(order), true) = (order, translated) else {
+            // Let the filter and sort order in Rust finish what the database could not.
+            let candidates = sql.select(&mut *client, "ORDER BY seq", &[])?;
+            let matching = candidates
+                .into_iter()
+                .filter(|resource| filter.as_ref().is_none_or(|f| f.matches(resource)))
+                .collect();
+            return common::page(matching, query);
+        };
+
+        let total_results: i64 = client
+            .query_one(
+                &format!(
+                    "SELECT count(*) FROM scim_resources WHERE {}",
+                    sql.conditions.join(" AND ")
+                ),
+                &sql.params(),
+            )
+            .map_err(storage_error)?
+            .get(0);
+        let (start_index, count) = common::window(query)?;
+        let limit = i64::try_from(count).ok();
+        let offset = start_index - 1;
+        let tail = format!(
+            "ORDER BY {}seq LIMIT ${} OFFSET ${}",
+            order,
+            sql.params.len() + 1,
+            sql.params.len() + 2
+        );
+        let resources = sql.select(&mut *client, &tail, &[&limit, &offset])?;
+        Ok(common::assemble(
+            resources,
+            total_results,
+            start_index,
+            query,
+        ))
+    }
+}
+
+struct Row {
+    seq: i64,
+    version: String,
+    data: Value,
+}
+
+fn find(client: &mut impl GenericClient, key: &str, id: &str) -> Result<Option<Row>, SCIMError> {
+    let row = client
+        .query_opt(
+            "SELECT seq, version, data FROM scim_resources WHERE resource_type = $1 AND id = $2",
+            &[&key, &id.to_lowercase()],
+        )
+        .map_err(storage_error)?;
+    Ok(row.map(|row| Row {
+        seq: row.get(0),
+        version: row.get(1),
+        data: row.get(2),
+    }))
+}
+
+fn check_unique(
+    client: &mut impl GenericClient,
+    key: &str,
+    resource: &Value,
+    unique: &[UniqueAttribute],
+    except: Option<i64>,
+) -> Result<(), SCIMError> {
+    let (indexed, scanned): (Vec<&UniqueAttribute>, Vec<&UniqueAttribute>) = unique
+        .iter()
+        .partition(|a| a.path == "userName" && !a.case_exact);
+    if let (Some(attribute), Some(user_name)) = (indexed.first(), resource["userName"].as_str()) {
+        let taken = client
+            .query_opt(
+                "SELECT 1 FROM scim_resources WHERE resource_type = $1
+                 AND lower(data ->> 'userName') = lower($2) AND seq IS DISTINCT FROM $3",
+                &[&key, &user_name, &except],
+            )
+            .map_err(storage_error)?
+            .is_some();
+        if taken {
+            return Err(SCIMError::ConflictError(
+                format!("{} '{}' is already in use", attribute.path, user_name).into(),
+            ));
+        }
+    }
+    if scanned.is_empty() {
+        return Ok(());
+    }
+    let others: Vec<Value> = client
+        .query(
+            "SELECT data FROM scim_resources WHERE resource_type = $1 AND seq IS DISTINCT FROM $2",
+            &[&key, &except],
+        )
+        .map_err(storage_error)?
+        .into_iter()
+        .map(|row| row.get(0))
+        .collect();
+    let scanned: Vec<UniqueAttribute> = scanned.into_iter().cloned().collect();
+    common::check_unique(resource, &scanned, others.iter())
+}
+
+/// The conditions of a query on `scim_resources` and the text parameters they refer to.
+#[derive(Debug, Default)]
+struct Sql {
+    conditions: Vec<String>,
+    params: Vec<String>,
+}
+
+impl Sql {
+    // Adds a parameter and returns the expression referring to it.
+    fn param(&mut self, value: impl Into<String>) -> String {
+        self.params.push(value.into());
+        format!("${}::text", self.params.len())
+    }
+
+    fn params(&self) -> Vec<&(dyn ToSql + Sync)> {
+        self.params
+            .iter()
+            .map(|param| param as &(dyn ToSql + Sync))
+            .collect()
+    }
+
+    // Runs `SELECT data` with the conditions, followed by `tail`, whose parameters `extra` are numbered
+    // after those of the conditions.
+    fn select(
+        &self,
+        client: &mut impl GenericClient,
+        tail: &str,
+        extra: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<Value>, SCIMError> {
+        let sql = format!(
+            "SELECT data FROM scim_resources WHERE {} {}",
+            self.conditions.join(" AND "),
+            tail
+        );
+        let mut params = self.params();
+        params.extend_from_slice(extra);
+        Ok(client
+            .query(&sql, &params)
+            .map_err(storage_error)?
+            .into_iter()
+            .map(|row| row.get(0))
+            .collect())
+    }
+
+    // Translates a comparison on an attribute of the schema into a condition on `data`.
+    fn comparison(&mut self, schema: &MergedSchema, comparison: &Comparison) -> Option<String> {
+        let mark = self.params.len();
+        let condition = self.translate(schema, comparison);
+        if condition.is_none() {
+            self.params.truncate(mark);
+        }
+        condition
+    }
+
+    fn translate(&mut self, schema: &MergedSchema, comparison: &Comparison) -> Option<String> {
+        let (location, attribute) = self.locate(schema, &comparison.path)?;
+        let (operator, negated) = match comparison.operator {
+            Operator::Ne => (Operator::Eq, true),
+            operator => (operator, false),
+        };
+        let case_exact = attribute.attribute.case_exact == Some(true);
+        let condition = match location {
+            Location::Single(value) => {
+                if attribute.attribute.r#type == "complex" {
+                    return None;
+                }
+                let predicate = self.predicate(&value, operator, &comparison.value, case_exact)?;
+                format!("COALESCE({}, false)", predicate)
+            }
+            Location::Each { array, element } => {
+                let predicate =
+                    self.predicate(&element, operator, &comparison.value, case_exact)?;
+                format!(
+                    "EXISTS (SELECT 1 FROM jsonb_array_elements(CASE WHEN jsonb_typeof({array}) = 'array' \
+                     THEN {array} ELSE '[]'::jsonb END) AS e(v) WHERE COALESCE({predicate}, false))",
+                )
+            }
+        };
+        Some(if negated {
+            format!("NOT {}", condition)
+        } else {
+            condition
+        })
+    }
+
+    // Returns the `ORDER BY` terms sorting by a singular attribute, ending with a comma.
+    fn order(&mut self, schema: &MergedSchema, path: &str, descending: bool) -> Option<String> {
+        let mark = self.params.len();
+        let order = self.sort_terms(schema, path, descending);
+        if order.is_none() {
+            self.params.truncate(mark);
+        }
+        order
+    }
+
+    fn sort_terms(
+        &mut self,
+        schema: &MergedSchema,
+        path: &str,
+        descending: bool,
+    ) -> Option<String> {
+        let (Location::Single(value), attribute) = self.locate(schema, path)? else {
+            return None;
+        };
+        let key = match attribute.attribute.r#type.as_str() {
+            "complex" => return None,
+            "string" | "reference" | "binary" | "dateTime" => {
+                format!("lower({} #>> '{{}}') COLLATE \"C\"", value)
+            }
+            _ => value.clone(),
+        };
+        // Missing values sort last in ascending order, and the whole order flips when descending.
+        let (presence, direction) = if descending {
+            ("ASC", "DESC")
+        } else {
+            ("DESC", "ASC")
+        };
+        Some(format!(
+            "{} {}, {} {}, ",
+            present(&value),
+            presence,
+            key,
+            direction
+        ))
+    }
+
+    // Resolves an attribute path against the schema into JSONB expressions on `data`.
+    fn locate<'s>(
+        &mut self,
+        schema: &'s MergedSchema,
+        path: &str,
+    ) -> Option<(Location, &'s MergedAttribute)> {
+        let attribute = schema.get(path)?;
+        let mut keys: Vec<&str> = Vec::new();
+        if !attribute.schema.eq_ignore_ascii_case(&schema.id) {
+            keys.push(&attribute.schema);
+        }
+        let segments: Vec<&str> = attribute.path.split('.').collect();
+        let parent = match segments.as_slice() {
+            [_] => None,
+            [parent, _] => Some(schema.get(&format!("{}:{}", attribute.schema, parent))?),
+            _ => return None,
+        };
+        let location = match parent {
+            Some(parent) if parent.attribute.multi_valued => {
+                keys.push(segments[0]);
+                let array = format!("(data #> {})", self.path_param(&keys));
+                let element = format!("(v -> {})", self.param(segments[1]));
+                Location::Each { array, element }
+            }
+            _ => {
+                keys.extend(&segments);
+                let value = format!("(data #> {})", self.path_param(&keys));
+                if attribute.attribute.multi_valued {
+                    let element = if attribute.attribute.r#type == "complex" {
+                        "(v -> 'value')".to_string()
+                    } else {
+                        "v".to_string()
+                    };
+                    Location::Each {
+                        array: value,
+                        element,
+                    }
+                } else {
+                    Location::Single(value)
+                }
+            }
+        };
+        Some((location, attribute))
+    }
+
+    fn path_param(&mut self, keys: &[&str]) -> String {
+        let keys: Vec<String> = keys
+            .iter()
+            .map(|key| format!("\"{}\"", key.replace('\\', "\\\\").replace('"', "\\\"")))
+            .collect();
+        format!(
+            "({})::text[]",
+            self.param(format!("{{{}}}", keys.join(",")))
+        )
+    }
+
+    // Returns a condition comparing the JSONB expression `value` with a filter value, mirroring the
+    // comparisons the in-memory filter makes.
+    fn predicate(
+        &mut self,
+        value: &str,
+        operator: Operator,
+        expected: &Value,
+        case_exact: bool,
+    ) -> Option<String> {
+        let text = format!("({} #>> '{{}}')", value);
+        let fold = |text: String| {
+            if case_exact {
+                text
+            } else {
+                format!("lower({})", text)
+            }
+        };
+        Some(match (expected, operator) {
+            (_, Operator::Pr) => present(value),
+            (Value::Null, Operator::Eq) => format!("NOT {}", present(value)),
+            (Value::String(expected), operator) => {
+                let typed = format!("jsonb_typeof({}) = 'string' AND ", value);
+                let condition = match operator {
+                    Operator::Eq => format!("{} = {}", fold(text), fold(self.param(expected))),
+                    Operator::Co | Operator::Sw | Operator::Ew => {
+                        let escaped = expected
+                            .replace('\\', "\\\\")
+                            .replace('%', "\\%")
+                            .replace('_', "\\_");
+                        let pattern = match operator {
+                            Operator::Co => format!("%{}%", escaped),
+                            Operator::Sw => format!("{}%", escaped),
+                            _ => format!("%{}", escaped),
+                        };
+                        format!(
+                            "{} LIKE {} ESCAPE '\\'",
+                            fold(text),
+                            fold(self.param(pattern))
+                        )
+                    }
+                    operator => format!(
+                        "{} COLLATE \"C\" {} {}",
+                        fold(text),
+                        sql_operator(operator)?,
+                        fold(self.param(expected))
+                    ),
+                };
+                format!("({}{})", typed, condition)
+            }
+            (Value::Number(expected), operator) => format!(
+                "(CASE WHEN jsonb_typeof({}) = 'number' THEN {}::numeric {} ({})::numeric ELSE false END)",
+                value,
+                text,
+                sql_operator(operator)?,
+                self.param(expected.to_string())
+            ),
+            (Value::Bool(_), Operator::Eq) => {
+                format!(
+                    "({} = ({})::jsonb)",
+                    value,
+                    self.param(expected.to_string())
+                )
+            }
+            _ => "false".to_string(),
+        })
+    }
+}
+
+enum Location {
+    /// A singular value.
+    Single(String),
+    /// The elements `v` of a JSON array, and the expression on `v` to compare.
+    Each { array: String, element: String },
+}
+
+fn present(value: &str) -> String {
+    format!(
+        "({value} IS NOT NULL AND {value} NOT IN ('null'::jsonb, '\"\"'::jsonb, '[]'::jsonb, '{{}}'::jsonb))"
+    )
+}
+
+fn sql_operator(operator: Operator) -> Option<&'static str> {
+    Some(match operator {
+        Operator::Eq => "=",
+        Operator::Gt => ">",
+        Operator::Ge => ">=",
+        Operator::Lt => "<",
+        Operator::Le => "<=",
+        _ => return None,
+    })
+}
+
+fn into_members(resource: Value) -> Map<String, Value> {
+    match resource {
+        Value::Object(members) => members,
+        _ => Map::new(),
+    }
+}
+
+fn storage_error(error: postgres::Error) -> SCIMError {
+    if error.code() == Some(&SqlState::UNIQUE_VIOLATION) {
+        return SCIMError::ConflictError(format!("postgres: {}", error).into());
+    }
+    SCIMError::OtherError(format!("postgres: {}", error).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translate(filter: &str) -> (Vec<Option<String>>, Vec<String>) {
+        let schema = SchemaRegistry::with_builtins()
+            .merged_schema("User")
+            .unwrap();
+        let filter = Filter::parse(filter).unwrap();
+        let mut sql = Sql::default();
+        let conditions = filter
+            .comparisons()
+            .iter()
+            .map(|comparison| sql.comparison(&schema, comparison))
+            .collect();
+        (conditions, sql.params)
+    }
+
+    #[test]
+    fn translates_comparisons_on_schema_attributes() {
+        let (conditions, params) = translate(
+            r#"USERNAME eq "BJensen" and emails.value co "50%" and urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:employeeNumber pr"#,
+        );
+        assert_eq!(
+            conditions[0].as_deref(),
+            Some(
+                "COALESCE((jsonb_typeof((data #> ($1::text)::text[])) = 'string' AND \
+                 lower(((data #> ($1::text)::text[]) #>> '{}')) = lower($2::text)), false)"
+            )
+        );
+        assert!(
+            conditions[1]
+                .as_deref()
+                .unwrap()
+                .starts_with("EXISTS (SELECT 1 FROM jsonb_array_elements(")
+        );
+        assert!(
+            conditions[2]
+                .as_deref()
+                .unwrap()
+                .starts_with("COALESCE(((data #> ($6::text)::text[]) IS NOT NULL")
+        );
+        assert_eq!(
+            params,
+            [
+                r#"{"userName"}"#,
+                "BJensen",
+                r#"{"emails"}"#,
+                "value",
+                r"%50\%%",
+                r#"{"urn:ietf:params:scim:schemas:extension:enterprise:2.0:User","employeeNumber"}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn leaves_unknown_attributes_and_complex_values_to_rust() {
+        let (conditions, _) =
+            translate(r#"nickName ne "Babs" and favoriteColor eq "red" and name eq "x""#);
+        assert!(
+            conditions[0]
+                .as_deref()
+                .unwrap()
+                .starts_with("NOT COALESCE(")
+        );
+        assert_eq!(conditions[1], None);
+        assert_eq!(conditions[2], None);
+
+        let schema = SchemaRegistry::with_builtins()
+            .merged_schema("User")
+            .unwrap();
+        let mut sql = Sql::default();
+        assert!(
+            sql.order(&schema, "name.familyName", true)
+                .unwrap()
+                .contains("DESC")
+        );
+        assert_eq!(sql.order(&schema, "emails", false), None);
+    }
+}
@@ -0,0 +1,249 @@
+//! Filtering and sorting of JSON resources for the providers in this module.
+//!
+//! Filters are parsed into a [`FilterExpr`] and evaluated against the JSON of each resource with
+//! [`FilterExpr::matches_value`].
+
+use std::cmp::Ordering;
+use std::sync::{Arc, LazyLock};
+
+use serde_json::Value;
+
+use crate::models::filter::{
+    AttrPath, CompareOp, FilterExpr, is_present, path_values, values_below,
+};
+use crate::utils::cache::ParseCache;
+use crate::utils::error::SCIMError;
+
+// The filters and `sortBy` paths recently parsed by any store; identity providers repeat the same few.
+static FILTERS: LazyLock<ParseCache<FilterExpr>> = LazyLock::new(|| ParseCache::new(1024));
+static PATHS: LazyLock<ParseCache<AttrPath>> = LazyLock::new(|| ParseCache::new(256));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Operator {
+    Eq,
+    Ne,
+    Co,
+    Sw,
+    Ew,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Pr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Comparison {
+    pub(crate) path: String,
+    pub(crate) operator: Operator,
+    /// The compared value, or `null` for `pr`.
+    pub(crate) value: Value,
+}
+
+/// A parsed filter, with the comparisons at its top level that a store can use to narrow the candidates.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Filter {
+    expression: Arc<FilterExpr>,
+    // The comparisons joined by `and` at the top of the expression.
+    comparisons: Vec<Comparison>,
+    // Whether the comparisons are the whole expression.
+    conjunction: bool,
+}
+
+impl Filter {
+    pub(crate) fn parse(filter: &str) -> Result<Self, SCIMError> {
+        let expression = FILTERS.filter(filter)?;
+        let mut comparisons = Vec::new();
+        let conjunction = conjuncts(&expression, &mut comparisons);
+        Ok(Filter {
+            expression,
+            comparisons,
+            conjunction,
+        })
+    }
+
+    /// Returns the comparisons joined by `and` at the top of the filter, all of which a resource must
+    /// satisfy. They are the whole filter only if [`Filter::is_conjunction`] holds.
+    pub(crate) fn comparisons(&self) -> &[Comparison] {
+        &self.comparisons
+    }
+
+    /// Returns `true` if the filter is nothing but its [`Filter::comparisons`], so that a store which
+    /// evaluates them has evaluated the filter.
+    pub(crate) fn is_conjunction(&self) -> bool {
+        self.conjunction
+    }
+
+    /// Returns the string a top-level `eq` comparison requires at `path`, which a store can look up in an
+    /// index before matching the whole filter.
+    pub(crate) fn equality(&self, path: &str) -> Option<&str> {
+        self.comparisons
+            .iter()
+            .filter(|c| c.operator == Operator::Eq && c.path.eq_ignore_ascii_case(path))
+            .find_map(|c| c.value.as_str())
+    }
+
+    pub(crate) fn matches(&self, resource: &Value) -> bool {
+        self.expression.matches_value(resource)
+    }
+}
+
+/// Sorts resources by the attribute at `path`; resources without a value sort last in ascending order.
+///
+/// # Errors
+///
+/// Returns `SCIMError::InvalidFieldValue` if `path` is not an attribute path.
+pub(crate) fn sort(resources: &mut [Value], path: &str, descending: bool) -> Result<(), SCIMError> {
+    let path = PATHS.path(path).map_err(|_| {
+        SCIMError::InvalidFieldValue(format!("sortBy '{}' is not an attribute path", path).into())
+    })?;
+    resources.sort_by(|a, b| {
+        let a = path_values(a, &path).into_iter().find(|v| is_present(v));
+        let b = path_values(b, &path).into_iter().find(|v| is_present(v));
+        let ordering = match (a, b) {
+            (Some(a), Some(b)) => order(a, b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+    Ok(())
+}
+
+/// Returns the values at an attribute path, flattening multi-valued attributes. A multi-valued complex
+/// attribute without a sub-attribute stands for the `value` of its entries, with the primary entry first.
+pub(crate) fn values_at<'a>(resource: &'a Value, path: &str) -> Vec<&'a Value> {
+    let (base, path) = match extension_of(resource, path) {
+        Some((extension, rest)) => (extension, rest),
+        None => (resource, path),
+    };
+    values_below(base, path.split('.'))
+}
+
+// Splits a URN-qualified path into the extension object of the resource and the path within it.
+fn extension_of<'a, 'p>(resource: &'a Value, path: &'p str) -> Option<(&'a Value, &'p str)> {
+    let object = resource.as_object()?;
+    object.iter().find_map(|(key, value)| {
+        let rest = path.get(key.len()..)?.strip_prefix(':')?;
+        (key.contains(':') && path[..key.len()].eq_ignore_ascii_case(key)).then_some((value, rest))
+    })
+}
+
+fn order(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::String(a), Value::String(b)) => a.to_lowercase().cmp(&b.to_lowercase()),
+        (Value::Number(a), Value::Number(b)) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        _ => Ordering::Equal,
+    }
+}
+
+// Collects the comparisons joined by `and` at the top of `expression`, returning whether there is nothing
+// else to it.
+fn conjuncts(expression: &FilterExpr, comparisons: &mut Vec<Comparison>) -> bool {
+    match expression {
+        FilterExpr::And(left, right) => {
+            let left = conjuncts(left, comparisons);
+            conjuncts(right, comparisons) && left
+        }
+        FilterExpr::Present(path) => {
+            comparisons.push(Comparison {
+                path: path.to_string(),
+                operator: Operator::Pr,
+                value: Value::Null,
+            });
+            true
+        }
+        FilterExpr::Compare(path, op, value) => {
+            comparisons.push(Comparison {
+                path: path.to_string(),
+                operator: operator(*op),
+                value: value.clone(),
+            });
+            true
+        }
+        _ => false,
+    }
+}
+
+fn operator(op: CompareOp) -> Operator {
+    match op {
+        CompareOp::Eq => Operator::Eq,
+        CompareOp::Ne => Operator::Ne,
+        CompareOp::Co => Operator::Co,
+        CompareOp::Sw => Operator::Sw,
+        CompareOp::Ew => Operator::Ew,
+        CompareOp::Gt => Operator::Gt,
+        CompareOp::Ge => Operator::Ge,
+        CompareOp::Lt => Operator::Lt,
+        CompareOp::Le => Operator::Le,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn matches_filter_expressions() {
+        let user = json!({
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+            "userName": "BJensen",
+            "title": "Tour Guide",
+            "emails": [{"value": "babs@jensen.org"}, {"value": "bjensen@example.com", "primary": true}],
+            "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User": {"employeeNumber": "701984"}
+        });
+        let matches = |filter: &str| Filter::parse(filter).unwrap().matches(&user);
+        assert!(matches(r#"userName eq "bjensen""#));
+        assert!(matches(r#"emails co "example.com" and title pr"#));
+        assert!(matches(r#"emails.value ew "jensen.org""#));
+        assert!(matches(
+            r#"urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:employeeNumber eq "701984""#
+        ));
+        assert!(!matches(r#"userName ne "bjensen""#));
+        assert!(!matches("nickName pr"));
+        assert!(matches(r#"userName eq "a" or not (nickName pr)"#));
+        assert!(matches(
+            r#"emails[value ew "example.com" and primary eq true] and not (emails[value sw "x"])"#
+        ));
+        assert!(!matches(
+            r#"emails[value ew "jensen.org" and primary eq true]"#
+        ));
+        assert!(matches(
+            r#"urn:ietf:params:scim:schemas:core:2.0:User:userName sw "b" and (title eq "x" or title co "guide")"#
+        ));
+
+        let filter = Filter::parse(r#"userName eq "a" and (title pr or nickName pr)"#).unwrap();
+        assert_eq!(filter.equality("userName"), Some("a"));
+        assert!(!filter.is_conjunction());
+    }
+
+    #[test]
+    fn sorts_missing_values_last() {
+        let mut resources = vec![
+            json!({"userName": "c"}),
+            json!({"userName": "a", "name": {"familyName": "Smith"}}),
+            json!({"userName": "b", "name": {"familyName": "jensen"}}),
+        ];
+        sort(&mut resources, "name.familyName", false).unwrap();
+        let names: Vec<&str> = resources
+            .iter()
+            .map(|r| r["userName"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["b", "a", "c"]);
+        assert!(matches!(
+            sort(&mut resources, "name[familyName pr]", false),
+            Err(SCIMError::InvalidFieldValue(_))
+        ));
+    }
+}
@@ -0,0 +1,186 @@
+//! A [`ResourceProvider`] decorator that redacts the resources it returns.
+
+use serde_json::Value;
+
+use crate::models::context::RequestContext;
+use crate::models::others::ListQuery;
+use crate::models::redaction::RedactionPolicy;
+use crate::models::tombstone::Tombstone;
+use crate::provider::resource_provider::{Changes, Page, ResourceProvider, Watermark};
+use crate::utils::error::SCIMError;
+
+/// Wraps a [`ResourceProvider`] and applies a [`RedactionPolicy`] to every resource it returns, so that
+/// attributes such as `password` never reach a response, whichever store holds them.
+///
+/// Writes are passed through unchanged. Clients that replace a resource they read send its redacted
+/// form, so masked attributes should be read-only to them.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::redaction::RedactionPolicy;
+/// use scim_v2::models::schema_registry::SchemaRegistry;
+/// use scim_v2::provider::memory::MemoryStore;
+/// use scim_v2::provider::redacting::RedactingProvider;
+/// use scim_v2::provider::resource_provider::ResourceProvider;
+/// use serde_json::json;
+///
+/// let provider = RedactingProvider::new(
+///     MemoryStore::new("https://example.com/v2"),
+///     RedactionPolicy::from_registry(&SchemaRegistry::with_builtins()),
+/// );
+/// let user = json!({
+///     "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+///     "userName": "bjensen",
+///     "password": "t1meMa$heen"
+/// });
+/// let created = provider.create("User", user).unwrap();
+/// assert!(created.get("password").is_none());
+/// let stored = provider.inner().get("User", created["id"].as_str().unwrap()).unwrap();
+/// assert_eq!(stored["password"], "t1meMa$heen");
+/// ```
+pub struct RedactingProvider<P> {
+    inner: P,
+    policy: RedactionPolicy,
+}
+
+impl<P: ResourceProvider> RedactingProvider<P> {
+    /// Wraps `inner`, redacting with `policy`.
+    pub fn new(inner: P, policy: RedactionPolicy) -> Self {
+        RedactingProvider { inner, policy }
+    }
+
+    /// Returns the wrapped provider.
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    fn redact(&self, resource_type: &str, mut resource: Value) -> Value {
+        self.policy.apply(resource_type, &mut resource);
+        resource
+    }
+}
+
+impl<P: ResourceProvider> ResourceProvider for RedactingProvider<P> {
+    fn create(&self, resource_type: &str, resource: Value) -> Result<Value, SCIMError> {
+        self.create_in(&RequestContext::default(), resource_type, resource)
+    }
+
+    fn get(&self, resource_type: &str, id: &str) -> Result<Value, SCIMError> {
+        self.get_in(&RequestContext::default(), resource_type, id)
+    }
+
+    fn replace(&self, resource_type: &str, id: &str, resource: Value) -> Result<Value, SCIMError> {
+        self.replace_in(&RequestContext::default(), resource_type, id, resource)
+    }
+
+    fn delete(&self, resource_type: &str, id: &str) -> Result<(), SCIMError> {
+        self.delete_in(&RequestContext::default(), resource_type, id)
+    }
+
+    fn list(&self, resource_type: &str, query: &ListQuery) -> Result<Page, SCIMError> {
+        self.list_in(&RequestContext::default(), resource_type, query)
+    }
+
+    fn changes_since(
+        &self,
+        resource_type: &str,
+        since: Option<&Watermark>,
+    ) -> Result<Changes, SCIMError> {
+        self.changes_since_in(&RequestContext::default(), resource_type, since)
+    }
+
+    fn deleted(&self, resource_type: &str) -> Result<Vec<Tombstone>, SCIMError> {
+        self.deleted_in(&RequestContext::default(), resource_type)
+    }
+
+    fn undelete(&self, resource_type: &str, id: &str) -> Result<Value, SCIMError> {
+        self.undelete_in(&RequestContext::default(), resource_type, id)
+    }
+
+    fn create_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        resource: Value,
+    ) -> Result<Value, SCIMError> {
+        let created = self.inner.create_in(context, resource_type, resource)?;
+        Ok(self.redact(resource_type, created))
+    }
+
+    fn get_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+    ) -> Result<Value, SCIMError> {
+        let resource = self.inner.get_in(context, resource_type, id)?;
+        Ok(self.redact(resource_type, resource))
+    }
+
+    fn replace_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+        resource: Value,
+    ) -> Result<Value, SCIMError> {
+        let replaced = self
+            .inner
+            .replace_in(context, resource_type, id, resource)?;
+        Ok(self.redact(resource_type, replaced))
+    }
+
+    fn delete_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+    ) -> Result<(), SCIMError> {
+        self.inner.delete_in(context, resource_type, id)
+    }
+
+    fn list_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        query: &ListQuery,
+    ) -> Result<Page, SCIMError> {
+        let mut page = self.inner.list_in(context, resource_type, query)?;
+        for resource in &mut page.resources {
+            self.policy.apply(resource_type, resource);
+        }
+        Ok(page)
+    }
+
+    fn changes_since_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        since: Option<&Watermark>,
+    ) -> Result<Changes, SCIMError> {
+        let mut changes = self.inner.changes_since_in(context, resource_type, since)?;
+        for resource in &mut changes.resources {
+            self.policy.apply(resource_type, resource);
+        }
+        Ok(changes)
+    }
+
+    fn deleted_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+    ) -> Result<Vec<Tombstone>, SCIMError> {
+        self.inner.deleted_in(context, resource_type)
+    }
+
+    fn undelete_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+    ) -> Result<Value, SCIMError> {
+        let restored = self.inner.undelete_in(context, resource_type, id)?;
+        Ok(self.redact(resource_type, restored))
+    }
+}
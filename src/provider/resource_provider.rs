@@ -0,0 +1,405 @@
+//! The storage interface behind a SCIM service provider.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::models::context::RequestContext;
+use crate::models::others::{ListQuery, ListResponse};
+use crate::models::resource::ScimResource;
+use crate::models::schema_uri::LIST_RESPONSE_URN;
+use crate::models::tombstone::Tombstone;
+use crate::utils::error::SCIMError;
+use crate::utils::pagination::{PageRequest, PageResponse};
+
+/// Stores and queries the resources of a SCIM service provider.
+///
+/// Resources are exchanged as JSON values, so one provider serves `User`, `Group` and custom resource types
+/// alike, extensions included. The provider owns the server-assigned attributes: it assigns `id` and
+/// maintains `meta`, ignoring any values the client sent for them.
+///
+/// Resource type names are those registered with the schema registry, e.g. `User`, and are compared
+/// case-insensitively.
+pub trait ResourceProvider: Send + Sync {
+    /// Stores a new resource and returns it as stored, with `id` and `meta` assigned.
+    ///
+    /// # Errors
+    ///
+    /// * `SCIMError::ResourceTypeNotFound` - If the resource type is not registered.
+    /// * `SCIMError::ConflictError` - If the resource duplicates the value of a unique attribute.
+    /// * Any error of [`SchemaRegistry::validate_resource`](crate::models::schema_registry::SchemaRegistry::validate_resource).
+    fn create(&self, resource_type: &str, resource: Value) -> Result<Value, SCIMError>;
+
+    /// Returns the resource with the given id.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::NotFoundError` if there is no such resource.
+    fn get(&self, resource_type: &str, id: &str) -> Result<Value, SCIMError>;
+
+    /// Replaces the resource with the given id and returns it as stored.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::NotFoundError` if there is no such resource, and the errors of
+    /// [`ResourceProvider::create`] otherwise.
+    fn replace(&self, resource_type: &str, id: &str, resource: Value) -> Result<Value, SCIMError>;
+
+    /// Deletes the resource with the given id.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::NotFoundError` if there is no such resource.
+    fn delete(&self, resource_type: &str, id: &str) -> Result<(), SCIMError>;
+
+    /// Returns the page of resources selected by `query`.
+    ///
+    /// When the query has a `cursor`, the page starts where the cursor points and carries the cursors of
+    /// the pages around it (see [`CursorCodec`](crate::utils::cursor::CursorCodec)).
+    ///
+    /// # Errors
+    ///
+    /// * `SCIMError::ResourceTypeNotFound` - If the resource type is not registered.
+    /// * `SCIMError::InvalidFilter` - If the filter cannot be parsed or is not supported by the provider.
+    /// * `SCIMError::InvalidFieldValue` - If the cursor is invalid or has expired, or `sortBy` is not an
+    ///   attribute path.
+    fn list(&self, resource_type: &str, query: &ListQuery) -> Result<Page, SCIMError>;
+
+    /// Returns the resources modified since `since`, for periodic syncs that should not enumerate every
+    /// resource each time. `None` returns all resources.
+    ///
+    /// The default implementation calls [`ResourceProvider::changes_since_in`] with an empty context.
+    ///
+    /// # Errors
+    ///
+    /// * `SCIMError::InvalidFieldValue` - If the watermark is a token the provider did not issue.
+    /// * The errors of [`ResourceProvider::list`].
+    fn changes_since(
+        &self,
+        resource_type: &str,
+        since: Option<&Watermark>,
+    ) -> Result<Changes, SCIMError> {
+        self.changes_since_in(&RequestContext::default(), resource_type, since)
+    }
+
+    /// Returns the tombstones of the deleted resources of a type that the provider retains, oldest deletion
+    /// first.
+    ///
+    /// The default implementation calls [`ResourceProvider::deleted_in`] with an empty context.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::ResourceTypeNotFound` if the resource type is not registered.
+    fn deleted(&self, resource_type: &str) -> Result<Vec<Tombstone>, SCIMError> {
+        self.deleted_in(&RequestContext::default(), resource_type)
+    }
+
+    /// Restores a deleted resource under its old id and returns it as stored.
+    ///
+    /// The default implementation calls [`ResourceProvider::undelete_in`] with an empty context.
+    ///
+    /// # Errors
+    ///
+    /// * `SCIMError::NotFoundError` - If the provider retains no tombstone for the id.
+    /// * `SCIMError::ConflictError` - If a resource created since duplicates the value of a unique attribute.
+    fn undelete(&self, resource_type: &str, id: &str) -> Result<Value, SCIMError> {
+        self.undelete_in(&RequestContext::default(), resource_type, id)
+    }
+
+    /// [`ResourceProvider::create`] for a resource model, stored as its resource type.
+    ///
+    /// # Errors
+    ///
+    /// The errors of [`ResourceProvider::create`], and those of [`ScimResource::to_value`] and
+    /// [`ScimResource::from_value`].
+    fn create_resource<R: ScimResource>(&self, resource: &R) -> Result<R, SCIMError>
+    where
+        Self: Sized,
+    {
+        R::from_value(self.create(R::RESOURCE_TYPE, resource.to_value()?)?)
+    }
+
+    /// [`ResourceProvider::get`] for a resource model.
+    ///
+    /// # Errors
+    ///
+    /// The errors of [`ResourceProvider::get`], and `SCIMError::DeserializationError` if the stored resource
+    /// does not describe the model.
+    fn get_resource<R: ScimResource>(&self, id: &str) -> Result<R, SCIMError>
+    where
+        Self: Sized,
+    {
+        R::from_value(self.get(R::RESOURCE_TYPE, id)?)
+    }
+
+    /// [`ResourceProvider::replace`] for a resource model, under its own id.
+    ///
+    /// # Errors
+    ///
+    /// `SCIMError::MissingRequiredField` if the resource has no id, and the errors of
+    /// [`ResourceProvider::replace`], [`ScimResource::to_value`] and [`ScimResource::from_value`].
+    fn replace_resource<R: ScimResource>(&self, resource: &R) -> Result<R, SCIMError>
+    where
+        Self: Sized,
+    {
+        let id = resource
+            .id()
+            .ok_or_else(|| SCIMError::MissingRequiredField("id".into()))?;
+        R::from_value(self.replace(R::RESOURCE_TYPE, id, resource.to_value()?)?)
+    }
+
+    /// [`ResourceProvider::create`] on behalf of `context`.
+    ///
+    /// The `*_in` methods let a provider see who a request is from: which tenant, which actor, which
+    /// correlation id. Their default implementations ignore the context and call the plain methods, so a
+    /// provider that is shared between tenants must override all of them and keep the tenants' resources
+    /// apart.
+    ///
+    /// # Errors
+    ///
+    /// The errors of [`ResourceProvider::create`].
+    fn create_in(
+        &self,
+        _context: &RequestContext,
+        resource_type: &str,
+        resource: Value,
+    ) -> Result<Value, SCIMError> {
+        self.create(resource_type, resource)
+    }
+
+    /// [`ResourceProvider::get`] on behalf of `context`.
+    ///
+    /// # Errors
+    ///
+    /// The errors of [`ResourceProvider::get`].
+    fn get_in(
+        &self,
+        _context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+    ) -> Result<Value, SCIMError> {
+        self.get(resource_type, id)
+    }
+
+    /// [`ResourceProvider::replace`] on behalf of `context`.
+    ///
+    /// # Errors
+    ///
+    /// The errors of [`ResourceProvider::replace`].
+    fn replace_in(
+        &self,
+        _context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+        resource: Value,
+    ) -> Result<Value, SCIMError> {
+        self.replace(resource_type, id, resource)
+    }
+
+    /// [`ResourceProvider::delete`] on behalf of `context`.
+    ///
+    /// # Errors
+    ///
+    /// The errors of [`ResourceProvider::delete`].
+    fn delete_in(
+        &self,
+        _context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+    ) -> Result<(), SCIMError> {
+        self.delete(resource_type, id)
+    }
+
+    /// [`ResourceProvider::list`] on behalf of `context`.
+    ///
+    /// # Errors
+    ///
+    /// The errors of [`ResourceProvider::list`].
+    fn list_in(
+        &self,
+        _context: &RequestContext,
+        resource_type: &str,
+        query: &ListQuery,
+    ) -> Result<Page, SCIMError> {
+        self.list(resource_type, query)
+    }
+
+    /// [`ResourceProvider::changes_since`] on behalf of `context`.
+    ///
+    /// The default implementation lists the resources whose `meta.lastModified` is at or after a
+    /// [`Watermark::LastModified`], oldest first, and returns the latest `lastModified` among them as the next
+    /// watermark. Timestamps have a resolution of one second, so the resources of the watermark's second are
+    /// returned again by the next call: callers must tolerate seeing a resource twice. It does not report
+    /// deletions. Providers that track changes themselves can override this method and hand out
+    /// [`Watermark::Token`]s instead.
+    ///
+    /// # Errors
+    ///
+    /// The errors of [`ResourceProvider::changes_since`].
+    fn changes_since_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        since: Option<&Watermark>,
+    ) -> Result<Changes, SCIMError> {
+        let filter = match since {
+            None => None,
+            Some(Watermark::LastModified(time)) => Some(format!(
+                "meta.lastModified ge {}",
+                Value::from(time.as_str())
+            )),
+            Some(Watermark::Token(token)) => {
+                return Err(SCIMError::InvalidFieldValue(
+                    format!("unknown change token '{}'", token).into(),
+                ));
+            }
+        };
+        let query = ListQuery {
+            filter,
+            count: None,
+            sort_by: Some("meta.lastModified".to_string()),
+            sort_order: Some("ascending".to_string()),
+            ..ListQuery::default()
+        };
+        let resources = self.list_in(context, resource_type, &query)?.resources;
+        let watermark = resources
+            .iter()
+            .filter_map(|resource| resource["meta"]["lastModified"].as_str())
+            .max()
+            .map(|time| Watermark::LastModified(time.to_string()))
+            .or_else(|| since.cloned());
+        Ok(Changes {
+            resources,
+            deleted: Vec::new(),
+            watermark,
+        })
+    }
+
+    /// [`ResourceProvider::deleted`] on behalf of `context`.
+    ///
+    /// Providers that retain deleted resources answer `get`, `replace` and `delete` for their ids with
+    /// `SCIMError::Gone`, and `create` for their `externalId`s with `SCIMError::ConflictError`. The default
+    /// implementation retains nothing and returns no tombstones.
+    ///
+    /// # Errors
+    ///
+    /// The errors of [`ResourceProvider::deleted`].
+    fn deleted_in(
+        &self,
+        _context: &RequestContext,
+        _resource_type: &str,
+    ) -> Result<Vec<Tombstone>, SCIMError> {
+        Ok(Vec::new())
+    }
+
+    /// [`ResourceProvider::undelete`] on behalf of `context`.
+    ///
+    /// The default implementation retains nothing and fails with `SCIMError::NotFoundError`.
+    ///
+    /// # Errors
+    ///
+    /// The errors of [`ResourceProvider::undelete`].
+    fn undelete_in(
+        &self,
+        _context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+    ) -> Result<Value, SCIMError> {
+        Err(SCIMError::NotFoundError(
+            format!("deleted {} {}", resource_type, id).into(),
+        ))
+    }
+}
+
+/// Where a sync left off, as returned by [`ResourceProvider::changes_since`].
+///
+/// Serializes as `{"lastModified": "..."}` or `{"token": "..."}`, so servers can hand it to clients as is.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Watermark {
+    /// A `meta.lastModified` `dateTime`; resources modified at or after it are reported.
+    LastModified(String),
+    /// An opaque token issued by the provider.
+    Token(String),
+}
+
+/// The resources changed since a [`Watermark`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Changes {
+    /// The created or modified resources, oldest change first.
+    pub resources: Vec<Value>,
+    /// The ids of deleted resources, for providers that keep track of deletions.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deleted: Vec<String>,
+    /// The watermark to pass to the next call, or `None` if there are no resources yet.
+    pub watermark: Option<Watermark>,
+}
+
+/// One page of the resources matching a query.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Page {
+    /// The resources on this page.
+    pub resources: Vec<Value>,
+    /// The number of resources matching the query, across all pages.
+    pub total_results: i64,
+    /// The 1-based index of the first resource on this page.
+    pub start_index: i64,
+    /// The cursor of the next page, when the query paginates by cursor and there are more results.
+    pub next_cursor: Option<String>,
+    /// The cursor of the previous page, when the query paginates by cursor and this is not the first page.
+    pub previous_cursor: Option<String>,
+}
+
+impl Page {
+    /// Returns the page as the JSON body of a list response, which works for any resource type.
+    pub fn to_json(&self) -> Value {
+        let mut json = json!({
+            "schemas": [LIST_RESPONSE_URN],
+            "totalResults": self.total_results,
+            "itemsPerPage": self.resources.len(),
+            "startIndex": self.start_index,
+            "Resources": self.resources,
+        });
+        if let Some(next_cursor) = &self.next_cursor {
+            json["nextCursor"] = json!(next_cursor);
+        }
+        if let Some(previous_cursor) = &self.previous_cursor {
+            json["previousCursor"] = json!(previous_cursor);
+        }
+        json
+    }
+
+    /// Converts the page into the answer to `request`, which names the pages around it in the strategy of
+    /// `request`.
+    pub fn into_response(self, request: &PageRequest) -> PageResponse<Value> {
+        PageResponse::new(
+            request,
+            self.resources,
+            self.total_results,
+            self.start_index,
+            self.next_cursor.as_deref(),
+            self.previous_cursor.as_deref(),
+        )
+    }
+
+    /// Converts the page into a typed `ListResponse`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::DeserializationError` if a resource is not one of the types of
+    /// [`Resource`](crate::models::others::Resource).
+    pub fn into_list_response(self) -> Result<ListResponse, SCIMError> {
+        let resources = self
+            .resources
+            .into_iter()
+            .map(|resource| {
+                serde_json::from_value(resource).map_err(SCIMError::DeserializationError)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(ListResponse {
+            next_cursor: self.next_cursor,
+            previous_cursor: self.previous_cursor,
+            ..ListResponse::from_page(resources, self.total_results, self.start_index)?
+        })
+    }
+}
@@ -0,0 +1,253 @@
+//! A [`ResourceProvider`] decorator that enforces an [`AttributePolicy`].
+
+use serde_json::Value;
+
+use crate::models::attribute_policy::AttributePolicy;
+use crate::models::context::RequestContext;
+use crate::models::others::ListQuery;
+use crate::models::tombstone::Tombstone;
+use crate::provider::resource_provider::{Changes, Page, ResourceProvider, Watermark};
+use crate::utils::error::SCIMError;
+
+/// Wraps a [`ResourceProvider`] and applies an [`AttributePolicy`] with the scopes of each request's
+/// context.
+///
+/// Returned resources are redacted, creates and replacements are checked with
+/// [`AttributePolicy::check_write`] and [`AttributePolicy::guard_replace`], and queries with
+/// [`AttributePolicy::check_query`]. The methods without a context act for a caller without scopes.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::attribute_policy::AttributePolicy;
+/// use scim_v2::models::context::RequestContext;
+/// use scim_v2::provider::memory::MemoryStore;
+/// use scim_v2::provider::resource_provider::ResourceProvider;
+/// use scim_v2::provider::scoped::ScopedProvider;
+/// use serde_json::json;
+///
+/// let provider = ScopedProvider::new(
+///     MemoryStore::new("https://example.com/v2"),
+///     AttributePolicy::new().restrict_read("title", ["hr"]),
+/// );
+/// let user = json!({
+///     "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+///     "userName": "bjensen",
+///     "title": "Tour Guide"
+/// });
+/// let created = provider.create("User", user).unwrap();
+/// assert!(created.get("title").is_none());
+///
+/// let hr = RequestContext::default().with_scope("hr");
+/// let read = provider.get_in(&hr, "User", created["id"].as_str().unwrap()).unwrap();
+/// assert_eq!(read["title"], "Tour Guide");
+/// ```
+pub struct ScopedProvider<P> {
+    inner: P,
+    policy: AttributePolicy,
+}
+
+impl<P: ResourceProvider> ScopedProvider<P> {
+    /// Wraps `inner`, enforcing `policy`.
+    pub fn new(inner: P, policy: AttributePolicy) -> Self {
+        ScopedProvider { inner, policy }
+    }
+
+    /// Returns the wrapped provider.
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    fn redact(&self, context: &RequestContext, mut resource: Value) -> Value {
+        self.policy.redact(&context.scopes, &mut resource);
+        resource
+    }
+}
+
+impl<P: ResourceProvider> ResourceProvider for ScopedProvider<P> {
+    fn create(&self, resource_type: &str, resource: Value) -> Result<Value, SCIMError> {
+        self.create_in(&RequestContext::default(), resource_type, resource)
+    }
+
+    fn get(&self, resource_type: &str, id: &str) -> Result<Value, SCIMError> {
+        self.get_in(&RequestContext::default(), resource_type, id)
+    }
+
+    fn replace(&self, resource_type: &str, id: &str, resource: Value) -> Result<Value, SCIMError> {
+        self.replace_in(&RequestContext::default(), resource_type, id, resource)
+    }
+
+    fn delete(&self, resource_type: &str, id: &str) -> Result<(), SCIMError> {
+        self.delete_in(&RequestContext::default(), resource_type, id)
+    }
+
+    fn list(&self, resource_type: &str, query: &ListQuery) -> Result<Page, SCIMError> {
+        self.list_in(&RequestContext::default(), resource_type, query)
+    }
+
+    fn changes_since(
+        &self,
+        resource_type: &str,
+        since: Option<&Watermark>,
+    ) -> Result<Changes, SCIMError> {
+        self.changes_since_in(&RequestContext::default(), resource_type, since)
+    }
+
+    fn deleted(&self, resource_type: &str) -> Result<Vec<Tombstone>, SCIMError> {
+        self.deleted_in(&RequestContext::default(), resource_type)
+    }
+
+    fn undelete(&self, resource_type: &str, id: &str) -> Result<Value, SCIMError> {
+        self.undelete_in(&RequestContext::default(), resource_type, id)
+    }
+
+    fn create_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        resource: Value,
+    ) -> Result<Value, SCIMError> {
+        self.policy.check_write(&context.scopes, &resource)?;
+        let created = self.inner.create_in(context, resource_type, resource)?;
+        Ok(self.redact(context, created))
+    }
+
+    fn get_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+    ) -> Result<Value, SCIMError> {
+        let resource = self.inner.get_in(context, resource_type, id)?;
+        Ok(self.redact(context, resource))
+    }
+
+    fn replace_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+        mut resource: Value,
+    ) -> Result<Value, SCIMError> {
+        let stored = self.inner.get_in(context, resource_type, id)?;
+        self.policy
+            .guard_replace(&context.scopes, &stored, &mut resource)?;
+        let replaced = self
+            .inner
+            .replace_in(context, resource_type, id, resource)?;
+        Ok(self.redact(context, replaced))
+    }
+
+    fn delete_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+    ) -> Result<(), SCIMError> {
+        self.inner.delete_in(context, resource_type, id)
+    }
+
+    fn list_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        query: &ListQuery,
+    ) -> Result<Page, SCIMError> {
+        self.policy.check_query(&context.scopes, query)?;
+        let mut page = self.inner.list_in(context, resource_type, query)?;
+        for resource in &mut page.resources {
+            self.policy.redact(&context.scopes, resource);
+        }
+        Ok(page)
+    }
+
+    fn changes_since_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        since: Option<&Watermark>,
+    ) -> Result<Changes, SCIMError> {
+        let mut changes = self.inner.changes_since_in(context, resource_type, since)?;
+        for resource in &mut changes.resources {
+            self.policy.redact(&context.scopes, resource);
+        }
+        Ok(changes)
+    }
+
+    fn deleted_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+    ) -> Result<Vec<Tombstone>, SCIMError> {
+        self.inner.deleted_in(context, resource_type)
+    }
+
+    fn undelete_in(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+    ) -> Result<Value, SCIMError> {
+        let restored = self.inner.undelete_in(context, resource_type, id)?;
+        Ok(self.redact(context, restored))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::schema_uri::{ENTERPRISE_USER_URN, USER_URN};
+    use crate::provider::memory::MemoryStore;
+
+    #[test]
+    fn hides_restricted_attributes_without_losing_them() {
+        let provider = ScopedProvider::new(
+            MemoryStore::new("https://example.com/v2"),
+            AttributePolicy::new()
+                .restrict_read(ENTERPRISE_USER_URN, ["hr"])
+                .restrict_write("password", Vec::<String>::new()),
+        );
+        let hr = RequestContext::default().with_scope("hr");
+        let created = provider
+            .create_in(
+                &hr,
+                "User",
+                json!({
+                    "schemas": [USER_URN, ENTERPRISE_USER_URN],
+                    "userName": "bjensen",
+                    ENTERPRISE_USER_URN: {"employeeNumber": "701984"}
+                }),
+            )
+            .unwrap();
+        let id = created["id"].as_str().unwrap();
+
+        let mut shown = provider.get("User", id).unwrap();
+        assert!(shown.get(ENTERPRISE_USER_URN).is_none());
+        shown["displayName"] = json!("Babs");
+        provider.replace("User", id, shown.clone()).unwrap();
+        let stored = provider.get_in(&hr, "User", id).unwrap();
+        assert_eq!(stored["displayName"], "Babs");
+        assert_eq!(stored[ENTERPRISE_USER_URN]["employeeNumber"], "701984");
+
+        shown["password"] = json!("t1meMa$heen");
+        assert!(matches!(
+            provider.replace_in(&hr, "User", id, shown),
+            Err(SCIMError::InvalidFieldValue(_))
+        ));
+        let query = ListQuery {
+            sort_by: Some(format!("{}:employeeNumber", ENTERPRISE_USER_URN)),
+            ..ListQuery::default()
+        };
+        assert!(provider.list("User", &query).is_err());
+        assert_eq!(
+            provider
+                .list_in(&hr, "User", &query)
+                .unwrap()
+                .resources
+                .len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,472 @@
+//! A [`ResourceProvider`] that persists resources in a SQLite database.
+
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use rusqlite::{Connection, OptionalExtension, Transaction, params};
+use serde_json::Value;
+
+use crate::models::others::ListQuery;
+use crate::models::schema_registry::SchemaRegistry;
+use crate::provider::common::{self, Prepared, UniqueAttribute};
+use crate::provider::query;
+use crate::provider::resource_provider::{Page, ResourceProvider};
+use crate::utils::error::SCIMError;
+
+const CREATE_TABLES: &str = "
+    CREATE TABLE IF NOT EXISTS scim_resources (
+        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+        resource_type TEXT NOT NULL,
+        id TEXT NOT NULL COLLATE NOCASE,
+        user_name TEXT,
+        external_id TEXT,
+        data TEXT NOT NULL,
+        UNIQUE (resource_type, id)
+    );
+    CREATE INDEX IF NOT EXISTS scim_resources_user_name ON scim_resources (resource_type, user_name);
+    CREATE INDEX IF NOT EXISTS scim_resources_external_id ON scim_resources (resource_type, external_id);
+";
+
+/// Stores resources in a SQLite database, one JSON document per row.
+///
+/// Behaves like [`MemoryStore`](crate::provider::memory::MemoryStore): resources are validated against the
+/// schema registry, unique attributes are enforced, and `id` and `meta` are assigned by the store. The
+/// resources live in the `scim_resources` table, which is created if it does not exist. `userName`
+/// (lowercased) and `externalId` are kept in indexed columns, so uniqueness checks and `eq` filters on
+/// `id`, `userName` and `externalId` do not scan the table; the rest of a filter is evaluated on the
+/// candidates those lookups return.
+///
+/// Database errors are reported as `SCIMError::OtherError`. The store does not keep tenants apart; use one
+/// database per tenant.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::provider::resource_provider::ResourceProvider;
+/// use scim_v2::provider::sqlite::SqliteStore;
+/// use serde_json::json;
+///
+/// let store = SqliteStore::open_in_memory("https://example.com/v2").unwrap();
+/// let user = store
+///     .create(
+///         "User",
+///         json!({"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "userName": "bjensen"}),
+///     )
+///     .unwrap();
+/// let id = user["id"].as_str().unwrap();
+/// assert_eq!(store.get("User", id).unwrap(), user);
+/// ```
+#[derive(Debug)]
+pub struct SqliteStore {
+    base_url: String,
+    registry: Option<SchemaRegistry>,
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    /// Opens or creates the database at `path`, validating against the global schema registry.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::OtherError` if the database cannot be opened or its table cannot be created.
+    pub fn open(path: impl AsRef<Path>, base_url: impl Into<String>) -> Result<Self, SCIMError> {
+        let connection = Connection::open(path).map_err(storage_error)?;
+        SqliteStore::from_connection(connection, base_url)
+    }
+
+    /// Creates a store backed by a private in-memory database.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::OtherError` if the database cannot be created.
+    pub fn open_in_memory(base_url: impl Into<String>) -> Result<Self, SCIMError> {
+        let connection = Connection::open_in_memory().map_err(storage_error)?;
+        SqliteStore::from_connection(connection, base_url)
+    }
+
+    /// Creates a store on an open connection, validating against the global schema registry.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::OtherError` if the table cannot be created.
+    pub fn from_connection(
+        connection: Connection,
+        base_url: impl Into<String>,
+    ) -> Result<Self, SCIMError> {
+        connection
+            .execute_batch(CREATE_TABLES)
+            .map_err(storage_error)?;
+        Ok(SqliteStore {
+            base_url: base_url.into(),
+            registry: None,
+            connection: Mutex::new(connection),
+        })
+    }
+
+    /// Creates a store on an open connection, validating against `registry` instead of the global schema
+    /// registry.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::OtherError` if the table cannot be created.
+    pub fn with_registry(
+        connection: Connection,
+        base_url: impl Into<String>,
+        registry: SchemaRegistry,
+    ) -> Result<Self, SCIMError> {
+        Ok(SqliteStore {
+            registry: Some(registry),
+            ..SqliteStore::from_connection(connection, base_url)?
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Connection> {
+        self.connection
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl ResourceProvider for SqliteStore {
+    fn create(&self, resource_type: &str, resource: Value) -> Result<Value, SCIMError> {
+        let Prepared {
+            resource_type: registered,
+            members,
+            unique,
+        } = common::prepare(self.registry.as_ref(), resource_type, resource)?;
+        let name = &registered.name;
+        let key = name.to_ascii_lowercase();
+        let mut connection = self.lock();
+        let transaction = connection.transaction().map_err(storage_error)?;
+        check_unique(
+            &transaction,
+            &key,
+            &Value::Object(members.clone()),
+            &unique,
+            None,
+        )?;
+
+        let resource = common::stamp_created(members, &registered, &self.base_url)?;
+        let (user_name, external_id) = indexed(&resource);
+        transaction
+            .execute(
+                "INSERT INTO scim_resources (resource_type, id, user_name, external_id, data)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    key,
+                    resource["id"].as_str(),
+                    user_name,
+                    external_id,
+                    resource.to_string()
+                ],
+            )
+            .map_err(storage_error)?;
+        transaction.commit().map_err(storage_error)?;
+        Ok(resource)
+    }
+
+    fn get(&self, resource_type: &str, id: &str) -> Result<Value, SCIMError> {
+        let connection = self.lock();
+        find(&connection, &resource_type.to_ascii_lowercase(), id)?
+            .map(|(_, resource)| resource)
+            .ok_or_else(|| SCIMError::NotFoundError(format!("{} {}", resource_type, id).into()))
+    }
+
+    fn replace(&self, resource_type: &str, id: &str, resource: Value) -> Result<Value, SCIMError> {
+        let Prepared {
+            resource_type: registered,
+            members,
+            unique,
+        } = common::prepare(self.registry.as_ref(), resource_type, resource)?;
+        let name = &registered.name;
+        let key = name.to_ascii_lowercase();
+        let mut connection = self.lock();
+        let transaction = connection.transaction().map_err(storage_error)?;
+        let (seq, existing) = find(&transaction, &key, id)?
+            .ok_or_else(|| SCIMError::NotFoundError(format!("{} {}", resource_type, id).into()))?;
+        check_unique(
+            &transaction,
+            &key,
+            &Value::Object(members.clone()),
+            &unique,
+            Some(seq),
+        )?;
+
+        let resource = common::stamp_replaced(members, &existing)?;
+        let (user_name, external_id) = indexed(&resource);
+        transaction
+            .execute(
+                "UPDATE scim_resources SET user_name = ?1, external_id = ?2, data = ?3 WHERE seq = ?4",
+                params![user_name, external_id, resource.to_string(), seq],
+            )
+            .map_err(storage_error)?;
+        transaction.commit().map_err(storage_error)?;
+        Ok(resource)
+    }
+
+    fn delete(&self, resource_type: &str, id: &str) -> Result<(), SCIMError> {
+        let deleted = self
+            .lock()
+            .execute(
+                "DELETE FROM scim_resources WHERE resource_type = ?1 AND id = ?2",
+                params![resource_type.to_ascii_lowercase(), id],
+            )
+            .map_err(storage_error)?;
+        if deleted == 0 {
+            return Err(SCIMError::NotFoundError(
+                format!("{} {}", resource_type, id).into(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn list(&self, resource_type: &str, query: &ListQuery) -> Result<Page, SCIMError> {
+        let key =
+            common::resource_type_name(self.registry.as_ref(), resource_type)?.to_ascii_lowercase();
+        let filter = common::filter(query)?;
+        let connection = self.lock();
+
+        let sorted = query.sort_by.as_deref().is_some_and(|s| !s.is_empty());
+        let Some(filter) = filter else {
+            if sorted {
+                let all = select(&connection, "resource_type = ?1", params![key])?;
+                return common::page(all, query);
+            }
+            // Without a filter or a sort order, the page can be cut out by the database.
+            let total_results: i64 = connection
+                .query_row(
+                    "SELECT count(*) FROM scim_resources WHERE resource_type = ?1",
+                    params![key],
+                    |row| row.get(0),
+                )
+                .map_err(storage_error)?;
+            let (start_index, count) = common::window(query)?;
+            let limit = i64::try_from(count).unwrap_or(-1);
+            let resources = select(
+                &connection,
+                "seq IN (SELECT seq FROM scim_resources WHERE resource_type = ?1
+                         ORDER BY seq LIMIT ?2 OFFSET ?3)",
+                params![key, limit, start_index - 1],
+            )?;
+            return Ok(common::assemble(
+                resources,
+                total_results,
+                start_index,
+                query,
+            ));
+        };
+
+        let candidates = if let Some(id) = filter.equality("id") {
+            select(
+                &connection,
+                "resource_type = ?1 AND id = ?2",
+                params![key, id],
+            )?
+        } else if let Some(user_name) = filter.equality("userName") {
+            select(
+                &connection,
+                "resource_type = ?1 AND user_name = ?2",
+                params![key, user_name.to_lowercase()],
+            )?
+        } else if let Some(external_id) = filter.equality("externalId") {
+            select(
+                &connection,
+                "resource_type = ?1 AND external_id = ?2",
+                params![key, external_id],
+            )?
+        } else {
+            select(&connection, "resource_type = ?1", params![key])?
+        };
+        let matching = candidates
+            .into_iter()
+            .filter(|resource| filter.matches(resource))
+            .collect();
+        common::page(matching, query)
+    }
+}
+
+// Returns the values of the indexed columns: the lowercased userName and the externalId.
+fn indexed(resource: &Value) -> (Option<String>, Option<String>) {
+    let string_at = |path| {
+        query::values_at(resource, path)
+            .into_iter()
+            .find_map(Value::as_str)
+    };
+    (
+        string_at("userName").map(str::to_lowercase),
+        string_at("externalId").map(str::to_string),
+    )
+}
+
+fn find(connection: &Connection, key: &str, id: &str) -> Result<Option<(i64, Value)>, SCIMError> {
+    connection
+        .query_row(
+            "SELECT seq, data FROM scim_resources WHERE resource_type = ?1 AND id = ?2",
+            params![key, id],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
+        )
+        .optional()
+        .map_err(storage_error)?
+        .map(|(seq, data)| Ok((seq, parse(&data)?)))
+        .transpose()
+}
+
+// Returns the resources of the rows matching a condition, in creation order.
+fn select(
+    connection: &Connection,
+    condition: &str,
+    params: impl rusqlite::Params,
+) -> Result<Vec<Value>, SCIMError> {
+    let sql = format!(
+        "SELECT data FROM scim_resources WHERE {} ORDER BY seq",
+        condition
+    );
+    let mut statement = connection.prepare_cached(&sql).map_err(storage_error)?;
+    let rows = statement
+        .query_map(params, |row| row.get::<_, String>(0))
+        .map_err(storage_error)?;
+    rows.map(|data| parse(&data.map_err(storage_error)?))
+        .collect()
+}
+
+fn check_unique(
+    transaction: &Transaction<'_>,
+    key: &str,
+    resource: &Value,
+    unique: &[UniqueAttribute],
+    except: Option<i64>,
+) -> Result<(), SCIMError> {
+    let (indexed, scanned): (Vec<_>, Vec<_>) = unique
+        .iter()
+        .partition(|a| a.path.eq_ignore_ascii_case("userName") && !a.case_exact);
+    for attribute in indexed {
+        let Some(user_name) = self::indexed(resource).0 else {
+            continue;
+        };
+        let taken = transaction
+            .query_row(
+                "SELECT 1 FROM scim_resources
+                 WHERE resource_type = ?1 AND user_name = ?2 AND seq IS NOT ?3",
+                params![key, user_name, except],
+                |_| Ok(()),
+            )
+            .optional()
+            .map_err(storage_error)?
+            .is_some();
+        if taken {
+            return Err(SCIMError::ConflictError(
+                format!("{} '{}' is already in use", attribute.path, user_name).into(),
+            ));
+        }
+    }
+    if scanned.is_empty() {
+        return Ok(());
+    }
+    let scanned: Vec<UniqueAttribute> = scanned.into_iter().cloned().collect();
+    let others = match except {
+        Some(seq) => select(
+            transaction,
+            "resource_type = ?1 AND seq != ?2",
+            params![key, seq],
+        )?,
+        None => select(transaction, "resource_type = ?1", params![key])?,
+    };
+    common::check_unique(resource, &scanned, others.iter())
+}
+
+fn parse(data: &str) -> Result<Value, SCIMError> {
+    serde_json::from_str(data).map_err(SCIMError::DeserializationError)
+}
+
+fn storage_error(error: rusqlite::Error) -> SCIMError {
+    SCIMError::OtherError(format!("sqlite: {}", error).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::schema_uri::USER_URN;
+
+    fn store() -> SqliteStore {
+        let connection = Connection::open_in_memory().unwrap();
+        SqliteStore::with_registry(
+            connection,
+            "https://example.com/v2",
+            SchemaRegistry::with_builtins(),
+        )
+        .unwrap()
+    }
+
+    fn user(user_name: &str, external_id: &str) -> Value {
+        json!({"schemas": [USER_URN], "userName": user_name, "externalId": external_id})
+    }
+
+    #[test]
+    fn persists_and_enforces_unique_user_names() {
+        let store = store();
+        let created = store.create("User", user("bjensen", "701984")).unwrap();
+        let id = created["id"].as_str().unwrap();
+        assert_eq!(store.get("user", &id.to_uppercase()).unwrap(), created);
+        assert!(matches!(
+            store.create("User", user("BJENSEN", "701985")),
+            Err(SCIMError::ConflictError(_))
+        ));
+
+        let replaced = store
+            .replace("User", id, user("barbara", "701984"))
+            .unwrap();
+        assert_eq!(replaced["meta"]["created"], created["meta"]["created"]);
+        assert!(store.create("User", user("bjensen", "701985")).is_ok());
+
+        store.delete("User", id).unwrap();
+        assert!(matches!(
+            store.delete("User", id),
+            Err(SCIMError::NotFoundError(_))
+        ));
+    }
+
+    #[test]
+    fn lists_with_indexed_and_scanned_filters() {
+        let store = store();
+        for (name, external_id) in [("carol", "3"), ("alice", "1"), ("bob", "2")] {
+            store.create("User", user(name, external_id)).unwrap();
+        }
+        let list = |filter: Option<&str>, sort_by: Option<&str>, count| {
+            let query = ListQuery {
+                filter: filter.map(str::to_string),
+                sort_by: sort_by.map(str::to_string),
+                count,
+                ..ListQuery::default()
+            };
+            let page = store.list("User", &query).unwrap();
+            let names: Vec<String> = page
+                .resources
+                .iter()
+                .map(|r| r["userName"].as_str().unwrap().to_string())
+                .collect();
+            (page.total_results, names)
+        };
+        assert_eq!(
+            list(Some(r#"userName eq "ALICE""#), None, None),
+            (1, vec!["alice".into()])
+        );
+        assert_eq!(
+            list(Some(r#"externalId eq "2""#), None, None),
+            (1, vec!["bob".into()])
+        );
+        assert_eq!(
+            list(Some(r#"userName ew "b""#), None, None),
+            (1, vec!["bob".into()])
+        );
+        assert_eq!(
+            list(None, Some("userName"), Some(2)),
+            (3, vec!["alice".into(), "bob".into()])
+        );
+        assert_eq!(
+            list(None, None, Some(2)),
+            (3, vec!["carol".into(), "alice".into()])
+        );
+    }
+}
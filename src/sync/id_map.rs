@@ -0,0 +1,385 @@
+//! Remembers which service provider `id` each `externalId` was provisioned as.
+//!
+//! Identity providers know their users by their own identifiers, which they send as `externalId`, while
+//! service providers assign the `id`. Not every service provider stores `externalId` or lets clients filter
+//! on it, so a client that lost track of the `id` it got back creates the user again. [`IdMap`] keeps both
+//! directions per tenant and resource type, and hands every change to an [`IdMapStore`] so that the
+//! mappings survive restarts.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::{Debug, Formatter};
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::models::context::RequestContext;
+use crate::utils::error::SCIMError;
+
+/// One `externalId` and the `id` the resource has at the service provider.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct IdMapping {
+    /// The tenant, as in [`RequestContext::tenant_id`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
+    /// The name of the resource type, e.g. `User`.
+    pub resource_type: String,
+    pub external_id: String,
+    pub id: String,
+}
+
+/// Where an [`IdMap`] persists its mappings, e.g. a database table or a file.
+///
+/// The map calls the store on every change and reads all mappings back once, in [`IdMap::open`]. The unit
+/// type is a store that persists nothing.
+pub trait IdMapStore: Send + Sync {
+    /// Returns every stored mapping.
+    fn load(&self) -> Result<Vec<IdMapping>, SCIMError>;
+
+    /// Stores a new mapping.
+    fn save(&self, mapping: &IdMapping) -> Result<(), SCIMError>;
+
+    /// Removes a mapping that no longer holds.
+    fn remove(&self, mapping: &IdMapping) -> Result<(), SCIMError>;
+}
+
+impl IdMapStore for () {
+    fn load(&self) -> Result<Vec<IdMapping>, SCIMError> {
+        Ok(Vec::new())
+    }
+
+    fn save(&self, _mapping: &IdMapping) -> Result<(), SCIMError> {
+        Ok(())
+    }
+
+    fn remove(&self, _mapping: &IdMapping) -> Result<(), SCIMError> {
+        Ok(())
+    }
+}
+
+/// A thread-safe, bidirectional map between `externalId`s and `id`s.
+///
+/// Mappings are kept per tenant and resource type; each `externalId` maps to one `id` and back, so
+/// inserting a mapping replaces any earlier one for either identifier. `externalId`s are compared exactly,
+/// as the core schema declares them `caseExact`, and `id`s ASCII case-insensitively, as
+/// [`ResourceId`](crate::models::resource_id::ResourceId) does.
+///
+/// A [`Reconciler`](crate::sync::reconcile::Reconciler) given a map through
+/// [`Reconciler::with_id_map`](crate::sync::reconcile::Reconciler::with_id_map) matches source resources to
+/// target resources by the mapped `id` before trying its match keys.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::context::RequestContext;
+/// use scim_v2::sync::id_map::IdMap;
+///
+/// let ids = IdMap::new();
+/// let acme = RequestContext::default().with_tenant("acme");
+/// ids.insert(&acme, "User", "00u1abcd", "2819c223-7f76-453a-919d-413861904646").unwrap();
+///
+/// assert_eq!(
+///     ids.id_for(&acme, "User", "00u1abcd").as_deref(),
+///     Some("2819c223-7f76-453a-919d-413861904646")
+/// );
+/// assert_eq!(
+///     ids.external_id_for(&acme, "user", "2819C223-7F76-453A-919D-413861904646").as_deref(),
+///     Some("00u1abcd")
+/// );
+/// assert_eq!(ids.id_for(&RequestContext::default(), "User", "00u1abcd"), None);
+/// ```
+pub struct IdMap {
+    scopes: RwLock<HashMap<String, Scope>>,
+    store: Box<dyn IdMapStore>,
+}
+
+#[derive(Default)]
+struct Scope {
+    // externalId -> id.
+    ids: HashMap<String, String>,
+    // Lower-cased id -> externalId.
+    external_ids: HashMap<String, String>,
+}
+
+impl Debug for IdMap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IdMap")
+            .field("mappings", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for IdMap {
+    fn default() -> Self {
+        IdMap::new()
+    }
+}
+
+impl IdMap {
+    /// Creates an empty map that persists nothing.
+    pub fn new() -> Self {
+        IdMap {
+            scopes: RwLock::new(HashMap::new()),
+            store: Box::new(()),
+        }
+    }
+
+    /// Creates a map with the mappings of `store`, and persists every change to it.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of [`IdMapStore::load`].
+    pub fn open(store: impl IdMapStore + 'static) -> Result<Self, SCIMError> {
+        let mappings = store.load()?;
+        let map = IdMap {
+            scopes: RwLock::new(HashMap::new()),
+            store: Box::new(store),
+        };
+        {
+            let mut scopes = map.write();
+            for mapping in mappings {
+                let scope = scopes
+                    .entry(scope_key(
+                        mapping.tenant_id.as_deref(),
+                        &mapping.resource_type,
+                    ))
+                    .or_default();
+                scope
+                    .external_ids
+                    .insert(mapping.id.to_ascii_lowercase(), mapping.external_id.clone());
+                scope.ids.insert(mapping.external_id, mapping.id);
+            }
+        }
+        Ok(map)
+    }
+
+    /// Returns the number of mappings, across tenants and resource types.
+    pub fn len(&self) -> usize {
+        self.read().values().map(|scope| scope.ids.len()).sum()
+    }
+
+    /// Returns `true` if there are no mappings.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the `id` the resource with `external_id` was provisioned as.
+    pub fn id_for(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        external_id: &str,
+    ) -> Option<String> {
+        self.read()
+            .get(&scope_key(context.tenant_id.as_deref(), resource_type))?
+            .ids
+            .get(external_id)
+            .cloned()
+    }
+
+    /// Returns the `externalId` of the resource with `id`.
+    pub fn external_id_for(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+    ) -> Option<String> {
+        self.read()
+            .get(&scope_key(context.tenant_id.as_deref(), resource_type))?
+            .external_ids
+            .get(&id.to_ascii_lowercase())
+            .cloned()
+    }
+
+    /// Maps `external_id` to `id`, replacing the earlier mappings of both.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the store; the map itself is updated regardless.
+    pub fn insert(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        external_id: &str,
+        id: &str,
+    ) -> Result<(), SCIMError> {
+        let mapping = |external_id: &str, id: &str| IdMapping {
+            tenant_id: context.tenant_id.clone(),
+            resource_type: resource_type.to_string(),
+            external_id: external_id.to_string(),
+            id: id.to_string(),
+        };
+        let mut stale = Vec::new();
+        {
+            let mut scopes = self.write();
+            let scope = scopes
+                .entry(scope_key(context.tenant_id.as_deref(), resource_type))
+                .or_default();
+            if scope
+                .ids
+                .get(external_id)
+                .is_some_and(|mapped| mapped.eq_ignore_ascii_case(id))
+            {
+                return Ok(());
+            }
+            if let Some(old_id) = scope.ids.remove(external_id) {
+                scope.external_ids.remove(&old_id.to_ascii_lowercase());
+                stale.push(mapping(external_id, &old_id));
+            }
+            if let Some(old_external_id) = scope.external_ids.remove(&id.to_ascii_lowercase()) {
+                if let Some(old_id) = scope.ids.remove(&old_external_id) {
+                    stale.push(mapping(&old_external_id, &old_id));
+                }
+            }
+            scope
+                .external_ids
+                .insert(id.to_ascii_lowercase(), external_id.to_string());
+            scope.ids.insert(external_id.to_string(), id.to_string());
+        }
+        for old in &stale {
+            self.store.remove(old)?;
+        }
+        self.store.save(&mapping(external_id, id))
+    }
+
+    /// Removes the mapping of a resource that was deleted, returning its `externalId`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the store; the map itself is updated regardless.
+    pub fn remove(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        id: &str,
+    ) -> Result<Option<String>, SCIMError> {
+        let removed = {
+            let mut scopes = self.write();
+            scopes
+                .get_mut(&scope_key(context.tenant_id.as_deref(), resource_type))
+                .and_then(|scope| {
+                    let external_id = scope.external_ids.remove(&id.to_ascii_lowercase())?;
+                    let id = scope.ids.remove(&external_id)?;
+                    Some((external_id, id))
+                })
+        };
+        let Some((external_id, id)) = removed else {
+            return Ok(None);
+        };
+        self.store.remove(&IdMapping {
+            tenant_id: context.tenant_id.clone(),
+            resource_type: resource_type.to_string(),
+            external_id: external_id.clone(),
+            id,
+        })?;
+        Ok(Some(external_id))
+    }
+
+    /// Maps the `externalId` of every resource that has both an `id` and an `externalId`, e.g. the
+    /// resources a service provider returned, and returns how many there were.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error of the store.
+    pub fn learn(
+        &self,
+        context: &RequestContext,
+        resource_type: &str,
+        resources: &[Value],
+    ) -> Result<usize, SCIMError> {
+        let mut learned = 0;
+        for resource in resources {
+            if let (Some(id), Some(external_id)) =
+                (resource["id"].as_str(), resource["externalId"].as_str())
+            {
+                self.insert(context, resource_type, external_id, id)?;
+                learned += 1;
+            }
+        }
+        Ok(learned)
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, HashMap<String, Scope>> {
+        self.scopes.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, HashMap<String, Scope>> {
+        self.scopes.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+// Mappings are kept per tenant and resource type.
+fn scope_key(tenant_id: Option<&str>, resource_type: &str) -> String {
+    match tenant_id {
+        Some(tenant_id) => format!("{}/{}", tenant_id, resource_type.to_ascii_lowercase()),
+        None => resource_type.to_ascii_lowercase(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Table(Arc<Mutex<Vec<IdMapping>>>);
+
+    impl IdMapStore for Table {
+        fn load(&self) -> Result<Vec<IdMapping>, SCIMError> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+
+        fn save(&self, mapping: &IdMapping) -> Result<(), SCIMError> {
+            self.0.lock().unwrap().push(mapping.clone());
+            Ok(())
+        }
+
+        fn remove(&self, mapping: &IdMapping) -> Result<(), SCIMError> {
+            self.0.lock().unwrap().retain(|stored| stored != mapping);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn keeps_one_mapping_per_identifier_and_persists_changes() {
+        let table = Table::default();
+        let context = RequestContext::default().with_tenant("acme");
+        let ids = IdMap::open(table.clone()).unwrap();
+        ids.insert(&context, "User", "e1", "id-1").unwrap();
+        ids.insert(&context, "User", "e2", "id-2").unwrap();
+        // e1 was re-provisioned, and id-2 now belongs to e3.
+        ids.insert(&context, "User", "e1", "id-3").unwrap();
+        ids.insert(&context, "User", "e3", "ID-2").unwrap();
+        assert_eq!(ids.len(), 2);
+        assert_eq!(ids.id_for(&context, "User", "e2"), None);
+        assert_eq!(ids.external_id_for(&context, "User", "id-1"), None);
+        assert_eq!(
+            ids.remove(&context, "User", "id-3").unwrap().as_deref(),
+            Some("e1")
+        );
+
+        let reopened = IdMap::open(table.clone()).unwrap();
+        assert_eq!(table.0.lock().unwrap().len(), 1);
+        assert_eq!(
+            reopened.id_for(&context, "User", "e3").as_deref(),
+            Some("ID-2")
+        );
+        assert_eq!(
+            reopened
+                .learn(
+                    &context,
+                    "User",
+                    &[
+                        serde_json::json!({"id": "id-4", "externalId": "e4"}),
+                        serde_json::json!({"id": "id-5"}),
+                    ],
+                )
+                .unwrap(),
+            1
+        );
+        assert_eq!(reopened.len(), 2);
+    }
+}
@@ -0,0 +1,297 @@
+//! Links incoming resources to the existing resources they describe.
+//!
+//! When a source and a target directory do not share ids, the same person has to be recognised by the
+//! attributes both sides keep. A [`Matcher`] tries its rules in order, by default `externalId`, then
+//! `userName`, then e-mail addresses, and reports how confident the match is, so that a provisioning job
+//! only links resources it is sure about and leaves the rest for review.
+
+use std::collections::HashSet;
+
+use serde_json::Value;
+
+use crate::sync::reconcile::value_at;
+
+/// What a [`MatchRule`] compares.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MatchKey {
+    /// The string at an attribute path such as `userName` or
+    /// `urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:employeeNumber`.
+    Attribute {
+        path: String,
+        /// Whether values have to agree in case, as `externalId` values do.
+        case_exact: bool,
+    },
+    /// The `value`s of `emails`, compared case-insensitively.
+    Email,
+}
+
+/// One way of recognising a resource, and how sure a match by it is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchRule {
+    pub key: MatchKey,
+    /// The confidence of a match by this rule, from 0 to 1.
+    pub confidence: f64,
+}
+
+impl MatchRule {
+    /// Returns the name of the rule: the attribute path, or `emails`.
+    pub fn name(&self) -> &str {
+        match &self.key {
+            MatchKey::Attribute { path, .. } => path,
+            MatchKey::Email => "emails",
+        }
+    }
+}
+
+/// An existing resource an incoming resource may describe.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    /// The position of the resource among the existing resources.
+    pub index: usize,
+    /// The `id` of the resource, if it has one.
+    pub id: Option<String>,
+    /// The name of the rule that matched, see [`MatchRule::name`].
+    pub rule: String,
+    /// How sure the match is, from 0 to 1.
+    pub confidence: f64,
+}
+
+/// The result of [`Matcher::find`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum MatchResult {
+    /// Exactly one existing resource matches, with at least the matcher's threshold of confidence.
+    Linked(Candidate),
+    /// Several existing resources match by the same rule, or the only match is below the threshold; the
+    /// candidates need review.
+    Uncertain(Vec<Candidate>),
+    /// No existing resource matches.
+    Unmatched,
+}
+
+/// Finds the existing resource an incoming resource describes.
+///
+/// Rules are tried in order and the first rule that matches decides; later rules are not consulted. An
+/// existing resource whose `externalId` differs from the incoming one's is never matched, as it belongs to
+/// a different identity. A match by e-mail address has the rule's full confidence if the address is the
+/// primary address on both sides, and three quarters of it otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::sync::matcher::{MatchResult, Matcher};
+/// use serde_json::json;
+///
+/// let existing = [
+///     json!({"id": "1", "userName": "bjensen", "emails": [{"value": "bjensen@example.com", "primary": true}]}),
+///     json!({"id": "2", "externalId": "e7", "userName": "jsmith"}),
+/// ];
+/// let matcher = Matcher::new();
+///
+/// let incoming = json!({"userName": "BJensen"});
+/// let MatchResult::Linked(found) = matcher.find(&incoming, &existing) else { panic!() };
+/// assert_eq!((found.id.as_deref(), found.rule.as_str(), found.confidence), (Some("1"), "userName", 0.9));
+///
+/// let incoming = json!({"userName": "babs", "emails": [{"value": "BJensen@example.com", "primary": true}]});
+/// let MatchResult::Linked(found) = matcher.find(&incoming, &existing) else { panic!() };
+/// assert_eq!(found.rule, "emails");
+///
+/// let incoming = json!({"externalId": "e8", "userName": "jsmith"});
+/// assert_eq!(matcher.find(&incoming, &existing), MatchResult::Unmatched);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matcher {
+    rules: Vec<MatchRule>,
+    threshold: f64,
+}
+
+impl Default for Matcher {
+    fn default() -> Self {
+        Matcher::new()
+    }
+}
+
+impl Matcher {
+    /// Creates a matcher by `externalId` (confidence 1), `userName` (0.9) and e-mail address (0.7), which
+    /// links matches of at least 0.5.
+    pub fn new() -> Self {
+        Matcher::without_rules()
+            .on_attribute("externalId", true, 1.0)
+            .on_attribute("userName", false, 0.9)
+            .on_email(0.7)
+    }
+
+    /// Creates a matcher without rules, which links matches of at least 0.5.
+    pub fn without_rules() -> Self {
+        Matcher {
+            rules: Vec::new(),
+            threshold: 0.5,
+        }
+    }
+
+    /// Adds a rule matching the string at `path`.
+    pub fn on_attribute(
+        mut self,
+        path: impl Into<String>,
+        case_exact: bool,
+        confidence: f64,
+    ) -> Self {
+        self.rules.push(MatchRule {
+            key: MatchKey::Attribute {
+                path: path.into(),
+                case_exact,
+            },
+            confidence,
+        });
+        self
+    }
+
+    /// Adds a rule matching e-mail addresses.
+    pub fn on_email(mut self, confidence: f64) -> Self {
+        self.rules.push(MatchRule {
+            key: MatchKey::Email,
+            confidence,
+        });
+        self
+    }
+
+    /// Sets the confidence a match needs to be linked.
+    pub fn threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Returns the rules, in the order they are tried.
+    pub fn rules(&self) -> &[MatchRule] {
+        &self.rules
+    }
+
+    /// Finds the existing resource `incoming` describes.
+    pub fn find(&self, incoming: &Value, existing: &[Value]) -> MatchResult {
+        self.find_where(incoming, existing, |_| true)
+    }
+
+    // `find` among the existing resources whose index `eligible` accepts.
+    pub(crate) fn find_where(
+        &self,
+        incoming: &Value,
+        existing: &[Value],
+        eligible: impl Fn(usize) -> bool,
+    ) -> MatchResult {
+        let external_id = incoming["externalId"].as_str();
+        let eligible: Vec<(usize, &Value)> = existing
+            .iter()
+            .enumerate()
+            .filter(|(index, candidate)| {
+                eligible(*index)
+                    && match (external_id, candidate["externalId"].as_str()) {
+                        (Some(wanted), Some(other)) => wanted == other,
+                        _ => true,
+                    }
+            })
+            .collect();
+        for rule in &self.rules {
+            let mut candidates: Vec<Candidate> = eligible
+                .iter()
+                .filter_map(|(index, candidate)| {
+                    let factor = matches(&rule.key, incoming, candidate)?;
+                    Some(Candidate {
+                        index: *index,
+                        id: candidate["id"].as_str().map(str::to_string),
+                        rule: rule.name().to_string(),
+                        confidence: rule.confidence * factor,
+                    })
+                })
+                .collect();
+            match candidates.len() {
+                0 => continue,
+                1 if candidates[0].confidence >= self.threshold => {
+                    return MatchResult::Linked(candidates.remove(0));
+                }
+                _ => return MatchResult::Uncertain(candidates),
+            }
+        }
+        MatchResult::Unmatched
+    }
+}
+
+// Whether `incoming` and `candidate` agree on `key`, and the share of the rule's confidence the agreement
+// carries.
+fn matches(key: &MatchKey, incoming: &Value, candidate: &Value) -> Option<f64> {
+    match key {
+        MatchKey::Attribute { path, case_exact } => {
+            let wanted = value_at(incoming, path)?.as_str()?;
+            let other = value_at(candidate, path)?.as_str()?;
+            let equal = if *case_exact {
+                wanted == other
+            } else {
+                wanted.to_lowercase() == other.to_lowercase()
+            };
+            equal.then_some(1.0)
+        }
+        MatchKey::Email => {
+            let primary = |resource: &Value| {
+                emails(resource)
+                    .find(|(_, primary)| *primary)
+                    .map(|(address, _)| address)
+            };
+            if let (Some(wanted), Some(other)) = (primary(incoming), primary(candidate)) {
+                if wanted == other {
+                    return Some(1.0);
+                }
+            }
+            let addresses: HashSet<String> = emails(incoming).map(|(address, _)| address).collect();
+            emails(candidate)
+                .any(|(address, _)| addresses.contains(&address))
+                .then_some(0.75)
+        }
+    }
+}
+
+// The lower-cased addresses of a resource's `emails`, with whether each is primary.
+fn emails(resource: &Value) -> impl Iterator<Item = (String, bool)> + '_ {
+    resource["emails"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|email| {
+            let address = email["value"].as_str()?.to_lowercase();
+            Some((address, email["primary"] == Value::Bool(true)))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn leaves_doubtful_matches_for_review() {
+        let existing = [
+            json!({"id": "1", "userName": "bjensen", "emails": [{"value": "babs@example.com"}]}),
+            json!({"id": "2", "userName": "barbara", "emails": [{"value": "babs@example.com"}]}),
+            json!({"id": "3", "userName": "jsmith", "emails": [{"value": "js@example.com"}]}),
+        ];
+        let matcher = Matcher::new();
+
+        let shared = json!({"userName": "b.jensen", "emails": [{"value": "babs@example.com"}]});
+        let MatchResult::Uncertain(candidates) = matcher.find(&shared, &existing) else {
+            panic!();
+        };
+        let ids: Vec<_> = candidates.iter().map(|c| c.id.as_deref()).collect();
+        assert_eq!(ids, [Some("1"), Some("2")]);
+        assert_eq!(candidates[0].confidence, 0.7 * 0.75);
+
+        let unique = json!({"emails": [{"value": "JS@example.com", "primary": true}]});
+        assert!(matches!(matcher.find(&unique, &existing), MatchResult::Linked(c) if c.index == 2));
+        let strict = Matcher::new().threshold(0.8);
+        assert!(matches!(
+            strict.find(&unique, &existing),
+            MatchResult::Uncertain(c) if c.len() == 1
+        ));
+        assert_eq!(
+            Matcher::without_rules().find(&unique, &existing),
+            MatchResult::Unmatched
+        );
+    }
+}
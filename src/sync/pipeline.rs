@@ -0,0 +1,597 @@
+//! Provisioning jobs composed of a source, a mapping, validation, reconciliation and bulk submission.
+//!
+//! A job reads the identities of a source [`Connector`] (an HR feed, another directory), maps each record
+//! to a SCIM resource, validates it against the schema registry, plans the changes against what the target
+//! connector holds with a [`Reconciler`], and writes them to the target as SCIM bulk requests.
+
+use serde_json::{Value, json};
+
+use crate::models::capabilities::NegotiatedCapabilities;
+use crate::models::context::RequestContext;
+use crate::models::errors::ScimHttpError;
+use crate::models::others::ListQuery;
+use crate::models::schema_registry::SchemaRegistry;
+use crate::models::schema_uri::{BULK_REQUEST_URN, BULK_RESPONSE_URN};
+use crate::provider::common;
+use crate::provider::resource_provider::ResourceProvider;
+use crate::sync::reconcile::{Operation, Plan, Reconciler, Report};
+use crate::utils::error::SCIMError;
+use crate::utils::metrics::{BULK_OPERATIONS, Metrics};
+use crate::utils::routing::{RouteTarget, RoutingTable};
+
+/// A system identities are read from and written to.
+///
+/// Sources such as HR feeds only need to implement [`Connector::read`]. Targets also implement
+/// [`Connector::write`], which receives a SCIM `BulkRequest` and returns the `BulkResponse`, so a SCIM
+/// client only has to post the request to the service provider's `/Bulk` endpoint.
+pub trait Connector {
+    /// Reads all identities of a resource type, as JSON records.
+    fn read(&self, resource_type: &str) -> Result<Vec<Value>, SCIMError>;
+
+    /// Submits a `BulkRequest` and returns the `BulkResponse`.
+    ///
+    /// # Errors
+    ///
+    /// The default implementation returns `SCIMError::RequestError`, for read-only connectors.
+    fn write(&self, request: &Value) -> Result<Value, SCIMError> {
+        let _ = request;
+        Err(SCIMError::RequestError("connector is read-only".into()))
+    }
+}
+
+/// A [`Connector`] over a [`ResourceProvider`], such as a local store or a provider fronting a database.
+///
+/// Bulk operations are executed one by one with the provider's `*_in` methods, in the context given to
+/// [`ProviderConnector::with_context`]. A `PATCH` operation reads the resource, applies the `PatchOp` message
+/// to it with [`apply_patch_json`](crate::models::patch::apply_patch_json) and replaces it, so pipelines can
+/// write to a provider with either [`UpdateMethod`].
+pub struct ProviderConnector<P> {
+    provider: P,
+    routes: RoutingTable,
+    context: RequestContext,
+}
+
+impl<P: ResourceProvider> ProviderConnector<P> {
+    /// Wraps `provider`, resolving bulk operation paths against the resource types of `registry`.
+    pub fn new(provider: P, registry: &SchemaRegistry) -> Self {
+        ProviderConnector {
+            provider,
+            routes: RoutingTable::from_registry(registry),
+            context: RequestContext::default(),
+        }
+    }
+
+    /// Sets the context the provider is called with.
+    pub fn with_context(mut self, context: RequestContext) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Returns the wrapped provider.
+    pub fn provider(&self) -> &P {
+        &self.provider
+    }
+
+    // Executes one bulk operation, returning its status and the resulting resource, if any.
+    fn execute(&self, operation: &Value) -> Result<(u16, Option<Value>), SCIMError> {
+        let method = operation["method"].as_str().unwrap_or_default();
+        let path = operation["path"].as_str().unwrap_or_default();
+        let matched = self
+            .routes
+            .resolve(path)
+            .ok_or_else(|| SCIMError::RequestError(format!("unknown path '{}'", path).into()))?;
+        let resource_type = matched.route.handler.as_str();
+        let data = operation.get("data").cloned().unwrap_or(Value::Null);
+        match (method.to_ascii_uppercase().as_str(), matched.target) {
+            ("POST", RouteTarget::Collection) => {
+                let created = self
+                    .provider
+                    .create_in(&self.context, resource_type, data)?;
+                Ok((201, Some(created)))
+            }
+            ("PUT", RouteTarget::Resource(id)) => {
+                let replaced = self
+                    .provider
+                    .replace_in(&self.context, resource_type, id, data)?;
+                Ok((200, Some(replaced)))
+            }
+            ("DELETE", RouteTarget::Resource(id)) => {
+                self.provider.delete_in(&self.context, resource_type, id)?;
+                Ok((204, None))
+            }
+            ("PATCH", RouteTarget::Resource(id)) => {
+                let patched =
+                    common::patch(&self.provider, &self.context, resource_type, id, data)?;
+                Ok((200, Some(patched)))
+            }
+            _ => Err(SCIMError::RequestError(
+                format!("unsupported bulk operation {} {}", method, path).into(),
+            )),
+        }
+    }
+}
+
+impl<P: ResourceProvider> Connector for ProviderConnector<P> {
+    fn read(&self, resource_type: &str) -> Result<Vec<Value>, SCIMError> {
+        let query = ListQuery {
+            count: None,
+            ..ListQuery::default()
+        };
+        Ok(self
+            .provider
+            .list_in(&self.context, resource_type, &query)?
+            .resources)
+    }
+
+    /// Executes the operations in order, stopping once `failOnErrors` operations have failed.
+    fn write(&self, request: &Value) -> Result<Value, SCIMError> {
+        let operations = request["Operations"]
+            .as_array()
+            .ok_or_else(|| SCIMError::MissingRequiredField("Operations".into()))?;
+        let fail_on_errors = request["failOnErrors"].as_u64();
+        let mut errors = 0;
+        let mut results = Vec::new();
+        for operation in operations {
+            let mut result = json!({"method": operation["method"]});
+            if let Some(bulk_id) = operation.get("bulkId") {
+                result["bulkId"] = bulk_id.clone();
+            }
+            match self.execute(operation) {
+                Ok((status, resource)) => {
+                    result["status"] = json!(status.to_string());
+                    if let Some(location) =
+                        resource.as_ref().and_then(|r| r.pointer("/meta/location"))
+                    {
+                        result["location"] = location.clone();
+                    } else if let Some(path) = operation.get("path") {
+                        result["location"] = path.clone();
+                    }
+                    if status >= 400 {
+                        errors += 1;
+                    }
+                }
+                Err(e) => {
+                    let response = ScimHttpError::from(&e);
+                    result["status"] = json!(response.status);
+                    result["response"] = json!(response);
+                    errors += 1;
+                }
+            }
+            results.push(result);
+            if fail_on_errors.is_some_and(|limit| errors >= limit) {
+                break;
+            }
+        }
+        Ok(json!({"schemas": [BULK_RESPONSE_URN], "Operations": results}))
+    }
+}
+
+/// How a [`Pipeline`] submits updates of existing resources.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UpdateMethod {
+    /// `PATCH` with the operations the reconciler computed.
+    #[default]
+    Patch,
+    /// `PUT` with the whole resource, for targets that do not support PATCH.
+    Put,
+}
+
+/// A source record the pipeline could not turn into a valid resource.
+#[derive(Debug)]
+pub struct Rejected {
+    /// The position of the record in what the source returned.
+    pub index: usize,
+    pub error: SCIMError,
+}
+
+/// What a [`Pipeline`] found, or did, in one run.
+#[derive(Debug, Default)]
+pub struct RunReport {
+    /// The planned operations.
+    pub plan: Plan,
+    /// The source records that failed mapping or validation. They take no part in reconciliation.
+    pub rejected: Vec<Rejected>,
+    /// The `BulkResponse` operations whose status is not a 2xx. Empty for dry runs.
+    pub failed: Vec<Value>,
+}
+
+impl RunReport {
+    /// Summarizes the planned operations.
+    pub fn summary(&self) -> Report {
+        self.plan.report()
+    }
+}
+
+type MappingFn<'a> = Box<dyn Fn(&Value) -> Result<Value, SCIMError> + 'a>;
+
+/// A provisioning job: source records are mapped, validated, reconciled with the target, and submitted to
+/// it in bulk requests of at most `maxOperations` operations.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::mapping::hr::{HrMapping, HrRule};
+/// use scim_v2::models::schema_registry::SchemaRegistry;
+/// use scim_v2::provider::memory::MemoryStore;
+/// use scim_v2::sync::pipeline::{Connector, Pipeline, ProviderConnector, UpdateMethod};
+/// use scim_v2::utils::error::SCIMError;
+/// use serde_json::{Value, json};
+///
+/// struct Feed(Vec<Value>);
+///
+/// impl Connector for Feed {
+///     fn read(&self, _: &str) -> Result<Vec<Value>, SCIMError> {
+///         Ok(self.0.clone())
+///     }
+/// }
+///
+/// let registry = SchemaRegistry::with_builtins();
+/// let mapping = HrMapping::new("User")
+///     .with_rule(HrRule::new("userName", "Email"))
+///     .with_rule(HrRule::new(
+///         "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:employeeNumber",
+///         "Employee_ID",
+///     ));
+/// let feed = Feed(vec![
+///     json!({"Email": "bjensen@example.com", "Employee_ID": "701984"}),
+///     json!({"Employee_ID": "701985"}),
+/// ]);
+/// let target = ProviderConnector::new(
+///     MemoryStore::with_registry("https://example.com/v2", registry.clone()),
+///     &registry,
+/// );
+///
+/// let pipeline = Pipeline::new(&registry, "User")
+///     .unwrap()
+///     .with_mapping(move |record| mapping.apply(record))
+///     .with_update_method(UpdateMethod::Put);
+/// let report = pipeline.run(&feed, &target).unwrap();
+/// assert_eq!(report.summary().creates, 1);
+/// assert_eq!(report.rejected[0].index, 1);
+/// assert!(report.failed.is_empty());
+/// ```
+pub struct Pipeline<'a> {
+    registry: SchemaRegistry,
+    resource_type: String,
+    endpoint: String,
+    mapping: Option<MappingFn<'a>>,
+    metrics: Option<&'a dyn Metrics>,
+    reconciler: Reconciler,
+    update_method: UpdateMethod,
+    max_operations: usize,
+    max_payload_size: usize,
+}
+
+impl<'a> Pipeline<'a> {
+    /// Creates a pipeline for a resource type registered with `registry`. Records are used as they are until
+    /// a mapping is set, and the reconciler uses its default match keys.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::ResourceTypeNotFound` if the resource type is not registered.
+    pub fn new(registry: &SchemaRegistry, resource_type: &str) -> Result<Self, SCIMError> {
+        let registered = registry
+            .resource_type(resource_type)
+            .ok_or_else(|| SCIMError::ResourceTypeNotFound(resource_type.into()))?;
+        Ok(Pipeline {
+            resource_type: registered.name.clone(),
+            endpoint: format!("/{}", registered.endpoint.trim_matches('/')),
+            reconciler: Reconciler::new(registry, resource_type)?,
+            registry: registry.clone(),
+            mapping: None,
+            metrics: None,
+            update_method: UpdateMethod::default(),
+            max_operations: 1000,
+            max_payload_size: usize::MAX,
+        })
+    }
+
+    /// Sets the function turning a source record into a resource, such as
+    /// [`HrMapping::apply`](crate::mapping::hr::HrMapping::apply).
+    pub fn with_mapping(
+        mut self,
+        mapping: impl Fn(&Value) -> Result<Value, SCIMError> + 'a,
+    ) -> Self {
+        self.mapping = Some(Box::new(mapping));
+        self
+    }
+
+    /// Replaces the reconciler, e.g. to change its match keys or removal policy.
+    pub fn with_reconciler(mut self, reconciler: Reconciler) -> Self {
+        self.reconciler = reconciler;
+        self
+    }
+
+    /// Reports the bulk operations submitted by [`Pipeline::run`] to `metrics`, as
+    /// [`BULK_OPERATIONS`](crate::utils::metrics::BULK_OPERATIONS).
+    pub fn with_metrics(mut self, metrics: &'a dyn Metrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Sets how updates are submitted.
+    pub fn with_update_method(mut self, update_method: UpdateMethod) -> Self {
+        self.update_method = update_method;
+        self
+    }
+
+    /// Sets the number of operations per bulk request, which should not exceed the `bulk.maxOperations` the
+    /// target advertises. Defaults to 1000.
+    pub fn with_max_operations(mut self, max_operations: usize) -> Self {
+        self.max_operations = max_operations.max(1);
+        self
+    }
+
+    /// Sets the size of bulk request bodies, in bytes, which should not exceed the `bulk.maxPayloadSize` the
+    /// target advertises. A request holds at least one operation, however large. Unlimited by default.
+    pub fn with_max_payload_size(mut self, max_payload_size: usize) -> Self {
+        self.max_payload_size = max_payload_size;
+        self
+    }
+
+    /// Submits the plan the way the target accepts, as negotiated with
+    /// [`negotiate`](crate::models::capabilities::negotiate): updates as PUT when PATCH is not supported,
+    /// and bulk requests within the negotiated limits. Without bulk support every operation is submitted
+    /// in a request of its own, which a connector can send as a plain request.
+    pub fn with_capabilities(mut self, capabilities: &NegotiatedCapabilities) -> Self {
+        self.update_method = if capabilities.patch {
+            UpdateMethod::Patch
+        } else {
+            UpdateMethod::Put
+        };
+        let limit = |limit: i64| usize::try_from(limit).unwrap_or(usize::MAX).max(1);
+        match capabilities.bulk {
+            Some(bulk) => {
+                self.max_operations = limit(bulk.max_operations);
+                self.max_payload_size = limit(bulk.max_payload_size);
+            }
+            None => self.max_operations = 1,
+        }
+        self
+    }
+
+    /// Reads, maps, validates and reconciles, without writing anything.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of either connector's `read`. Records that fail mapping or validation are reported
+    /// in [`RunReport::rejected`] instead.
+    pub fn dry_run(
+        &self,
+        source: &impl Connector,
+        target: &impl Connector,
+    ) -> Result<RunReport, SCIMError> {
+        let mut resources = Vec::new();
+        let mut rejected = Vec::new();
+        for (index, record) in source.read(&self.resource_type)?.iter().enumerate() {
+            let mapped = match &self.mapping {
+                Some(mapping) => mapping(record),
+                None => Ok(record.clone()),
+            };
+            match mapped.and_then(|resource| {
+                self.registry
+                    .validate_resource(&self.resource_type, &resource)
+                    .map(|()| resource)
+            }) {
+                Ok(resource) => resources.push(resource),
+                Err(error) => rejected.push(Rejected { index, error }),
+            }
+        }
+        let existing = target.read(&self.resource_type)?;
+        Ok(RunReport {
+            plan: self.reconciler.plan(&resources, &existing),
+            rejected,
+            failed: Vec::new(),
+        })
+    }
+
+    /// Runs the job: plans as [`Pipeline::dry_run`] does and submits the plan to `target`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of a connector's `read` or `write`. Operations the target rejects are reported in
+    /// [`RunReport::failed`].
+    pub fn run(
+        &self,
+        source: &impl Connector,
+        target: &impl Connector,
+    ) -> Result<RunReport, SCIMError> {
+        let mut report = self.dry_run(source, target)?;
+        for request in self.bulk_requests(&report.plan) {
+            let response = target.write(&request)?;
+            for result in response["Operations"].as_array().into_iter().flatten() {
+                let status = result["status"].as_str().unwrap_or_default();
+                if let Some(metrics) = self.metrics {
+                    let method = result["method"].as_str().unwrap_or_default();
+                    metrics.increment(BULK_OPERATIONS, &[("method", method), ("status", status)]);
+                }
+                if !status.starts_with('2') {
+                    report.failed.push(result.clone());
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Splits a plan into `BulkRequest`s of at most `maxOperations` operations and `maxPayloadSize` bytes,
+    /// in the plan's order.
+    pub fn bulk_requests(&self, plan: &Plan) -> Vec<Value> {
+        let request = |operations: Vec<Value>| json!({"schemas": [BULK_REQUEST_URN], "Operations": operations});
+        let envelope = request(Vec::new()).to_string().len();
+        let mut requests = Vec::new();
+        let mut chunk = Vec::new();
+        let mut size = envelope;
+        for (n, operation) in plan.operations.iter().enumerate() {
+            let operation = self.bulk_operation(n, operation);
+            // The operation and the comma separating it from the previous one.
+            let length = operation.to_string().len() + 1;
+            if !chunk.is_empty()
+                && (chunk.len() == self.max_operations || size + length > self.max_payload_size)
+            {
+                requests.push(request(std::mem::take(&mut chunk)));
+                size = envelope;
+            }
+            size += length;
+            chunk.push(operation);
+        }
+        if !chunk.is_empty() {
+            requests.push(request(chunk));
+        }
+        requests
+    }
+
+    fn bulk_operation(&self, n: usize, operation: &Operation) -> Value {
+        match operation {
+            Operation::Create { resource } => json!({
+                "method": "POST",
+                "bulkId": format!("create-{}", n),
+                "path": self.endpoint,
+                "data": resource,
+            }),
+            Operation::Update {
+                id,
+                patch,
+                resource,
+            }
+            | Operation::Deactivate {
+                id,
+                patch,
+                resource,
+            } => match self.update_method {
+                UpdateMethod::Patch => json!({
+                    "method": "PATCH",
+                    "path": format!("{}/{}", self.endpoint, id),
+                    "data": patch,
+                }),
+                UpdateMethod::Put => json!({
+                    "method": "PUT",
+                    "path": format!("{}/{}", self.endpoint, id),
+                    "data": resource,
+                }),
+            },
+            Operation::Delete { id } => json!({
+                "method": "DELETE",
+                "path": format!("{}/{}", self.endpoint, id),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::schema_uri::USER_URN;
+    use crate::provider::memory::MemoryStore;
+
+    struct Feed(Vec<Value>);
+
+    #[derive(Default)]
+    struct Submitted(std::sync::Mutex<Vec<String>>);
+
+    impl Metrics for Submitted {
+        fn increment(&self, name: &str, labels: &[(&str, &str)]) {
+            assert_eq!(name, BULK_OPERATIONS);
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("{} {}", labels[0].1, labels[1].1));
+        }
+
+        fn observe(&self, _name: &str, _value: f64, _labels: &[(&str, &str)]) {}
+    }
+
+    impl Connector for Feed {
+        fn read(&self, _: &str) -> Result<Vec<Value>, SCIMError> {
+            Ok(self.0.clone())
+        }
+    }
+
+    fn target() -> ProviderConnector<MemoryStore> {
+        let registry = SchemaRegistry::with_builtins();
+        ProviderConnector::new(
+            MemoryStore::with_registry("https://example.com/v2", registry.clone()),
+            &registry,
+        )
+    }
+
+    #[test]
+    fn converges_the_target_in_bulk_requests() {
+        let registry = SchemaRegistry::with_builtins();
+        let target = target();
+        target
+            .provider()
+            .create("User", json!({"schemas": [USER_URN], "userName": "leaver"}))
+            .unwrap();
+        let feed = Feed(vec![
+            json!({"user": "bjensen"}),
+            json!({"user": "jsmith"}),
+            json!({"user": "leaver", "gone": true}),
+            json!({}),
+        ]);
+        let submitted = Submitted::default();
+        let pipeline = Pipeline::new(&registry, "user")
+            .unwrap()
+            .with_mapping(|record| {
+                let user_name = record["user"]
+                    .as_str()
+                    .ok_or_else(|| SCIMError::MissingRequiredField("user".into()))?;
+                Ok(json!({"schemas": [USER_URN], "userName": user_name, "active": record.get("gone").is_none()}))
+            })
+            .with_update_method(UpdateMethod::Put)
+            .with_max_operations(2)
+            .with_metrics(&submitted);
+
+        let dry = pipeline.dry_run(&feed, &target).unwrap();
+        assert_eq!(dry.summary().creates, 2);
+        assert_eq!(dry.summary().updates, 1);
+        assert_eq!(dry.rejected.len(), 1);
+        assert_eq!(pipeline.bulk_requests(&dry.plan).len(), 2);
+        let unbatched = Pipeline::new(&registry, "User")
+            .unwrap()
+            .with_max_payload_size(1);
+        assert_eq!(unbatched.bulk_requests(&dry.plan).len(), 3);
+        assert_eq!(target.read("User").unwrap().len(), 1);
+
+        let report = pipeline.run(&feed, &target).unwrap();
+        assert!(report.failed.is_empty(), "{:?}", report.failed);
+        assert_eq!(
+            *submitted.0.lock().unwrap(),
+            ["POST 201", "POST 201", "PUT 200"]
+        );
+        let users = target.read("User").unwrap();
+        assert_eq!(users.len(), 3);
+        assert!(
+            users
+                .iter()
+                .any(|u| u["userName"] == "leaver" && u["active"] == false)
+        );
+        let again = pipeline.dry_run(&feed, &target).unwrap();
+        assert!(again.plan.operations.is_empty());
+    }
+
+    #[test]
+    fn reports_failed_bulk_operations() {
+        let target = target();
+        let request = json!({
+            "schemas": [BULK_REQUEST_URN],
+            "failOnErrors": 2,
+            "Operations": [
+                {"method": "POST", "bulkId": "a", "path": "/Users", "data": {"schemas": [USER_URN], "userName": "bjensen"}},
+                {"method": "POST", "bulkId": "b", "path": "/Users", "data": {"schemas": [USER_URN], "userName": "bjensen"}},
+                {"method": "PATCH", "path": "/Users/missing", "data": {
+                    "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+                    "Operations": [{"op": "replace", "path": "active", "value": false}]
+                }},
+                {"method": "DELETE", "path": "/Users/missing"}
+            ]
+        });
+        let response = target.write(&request).unwrap();
+        let results = response["Operations"].as_array().unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0]["status"], "201");
+        assert_eq!(results[1]["status"], "409");
+        assert_eq!(results[1]["bulkId"], "b");
+        assert_eq!(results[2]["status"], "404");
+        assert!(Feed(vec![]).write(&request).is_err());
+    }
+}
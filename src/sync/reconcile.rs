@@ -0,0 +1,621 @@
+//! Computes the operations that make a target directory match a source directory.
+//!
+//! Provisioning jobs repeatedly compare what an authoritative source (an HR system, another directory) holds
+//! with what a SCIM service provider holds. [`Reconciler`] matches the resources of both sides on
+//! configurable keys and produces a [`Plan`]: resources to create, `PatchOp` updates for resources that
+//! differ, and deactivations or deletions for resources the source no longer has. The plan can be reviewed
+//! as a dry-run [`Report`] before it is applied.
+
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+use serde_json::{Value, json};
+
+use crate::models::context::RequestContext;
+use crate::models::others::ListQuery;
+use crate::models::schema_registry::SchemaRegistry;
+use crate::models::schema_uri::PATCH_OP_URN;
+use crate::models::scim_schema::MergedSchema;
+use crate::provider::resource_provider::ResourceProvider;
+use crate::sync::id_map::IdMap;
+use crate::sync::matcher::{MatchResult, Matcher};
+use crate::utils::diff::diff_resources;
+use crate::utils::error::SCIMError;
+
+/// What to do with target resources that have no counterpart in the source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Removal {
+    /// Set `active` to `false`. Resources whose schema has no `active` attribute are deleted instead.
+    #[default]
+    Deactivate,
+    /// Delete them.
+    Delete,
+    /// Leave them alone.
+    Keep,
+}
+
+/// One change to the target directory.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation {
+    /// Create `resource`.
+    Create {
+        /// The source resource, without `id` and `meta`.
+        resource: Value,
+    },
+    /// Apply `patch` to the resource with `id`. `resource` is the result, for targets without PATCH.
+    Update {
+        id: String,
+        patch: Value,
+        resource: Value,
+    },
+    /// Deactivate the resource with `id` by applying `patch`. `resource` is the result.
+    Deactivate {
+        id: String,
+        patch: Value,
+        resource: Value,
+    },
+    /// Delete the resource with `id`.
+    Delete { id: String },
+}
+
+/// The operations that converge a target with a source, in the order they should be applied.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Plan {
+    /// The resource type both sides hold.
+    pub resource_type: String,
+    pub operations: Vec<Operation>,
+    /// The number of matched resources that already agree.
+    pub unchanged: usize,
+    /// The source resources a [`Matcher`] could not link with certainty, which are neither created nor
+    /// updated until someone reviews them.
+    pub uncertain: Vec<Value>,
+}
+
+/// A summary of a [`Plan`], for dry runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Report {
+    pub creates: usize,
+    pub updates: usize,
+    pub deactivations: usize,
+    pub deletions: usize,
+    pub unchanged: usize,
+    pub uncertain: usize,
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} to create, {} to update, {} to deactivate, {} to delete, {} unchanged",
+            self.creates, self.updates, self.deactivations, self.deletions, self.unchanged
+        )?;
+        if self.uncertain > 0 {
+            write!(f, ", {} to review", self.uncertain)?;
+        }
+        Ok(())
+    }
+}
+
+impl Plan {
+    /// Counts the operations of the plan by kind.
+    pub fn report(&self) -> Report {
+        let mut report = Report {
+            unchanged: self.unchanged,
+            uncertain: self.uncertain.len(),
+            ..Report::default()
+        };
+        for operation in &self.operations {
+            match operation {
+                Operation::Create { .. } => report.creates += 1,
+                Operation::Update { .. } => report.updates += 1,
+                Operation::Deactivate { .. } => report.deactivations += 1,
+                Operation::Delete { .. } => report.deletions += 1,
+            }
+        }
+        report
+    }
+
+    /// Applies the plan to `target`, which does not need to support PATCH: updates and deactivations
+    /// replace the resource with its patched form.
+    ///
+    /// Operations are applied in order; the first failure stops the run.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the first operation that fails.
+    pub fn apply(
+        &self,
+        context: &RequestContext,
+        target: &impl ResourceProvider,
+    ) -> Result<(), SCIMError> {
+        for operation in &self.operations {
+            match operation {
+                Operation::Create { resource } => {
+                    target.create_in(context, &self.resource_type, resource.clone())?;
+                }
+                Operation::Update { id, resource, .. }
+                | Operation::Deactivate { id, resource, .. } => {
+                    target.replace_in(context, &self.resource_type, id, resource.clone())?;
+                }
+                Operation::Delete { id } => target.delete_in(context, &self.resource_type, id)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Plans the convergence of a target directory with a source directory.
+///
+/// Resources are exchanged as JSON, as in [`ResourceProvider`]. A source resource matches the target resource
+/// that has the same value at the first match key both of them have; by default the keys are `externalId`
+/// and then `userName` (or `displayName` for resources without `userName`). Values are compared
+/// case-insensitively unless the schema declares the attribute `caseExact`.
+///
+/// Matched resources are compared attribute by attribute, ignoring `id`, `meta`, and attributes that are
+/// read-only or never returned (such as a User's `groups` and `password`). Multi-valued attributes are
+/// compared without regard to order. Differences become `replace` operations, attributes only the target
+/// has become `remove` operations, and extension attributes are patched one by one.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::schema_registry::SchemaRegistry;
+/// use scim_v2::sync::reconcile::{Operation, Reconciler};
+/// use serde_json::json;
+///
+/// let reconciler = Reconciler::new(&SchemaRegistry::with_builtins(), "User").unwrap();
+/// let source = [
+///     json!({"externalId": "e1", "userName": "bjensen", "title": "Tour Guide"}),
+///     json!({"externalId": "e2", "userName": "jsmith"}),
+/// ];
+/// let target = [
+///     json!({"id": "1", "externalId": "e1", "userName": "bjensen", "title": "Guide"}),
+///     json!({"id": "2", "externalId": "e3", "userName": "olduser", "active": true}),
+/// ];
+/// let plan = reconciler.plan(&source, &target);
+/// assert_eq!(plan.report().to_string(), "1 to create, 1 to update, 1 to deactivate, 0 to delete, 0 unchanged");
+/// let Operation::Update { patch, .. } = &plan.operations[1] else { panic!() };
+/// assert_eq!(patch["Operations"][0], json!({"op": "replace", "path": "title", "value": "Tour Guide"}));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Reconciler {
+    resource_type: String,
+    schema: MergedSchema,
+    keys: Vec<String>,
+    removal: Removal,
+    ids: Option<Arc<IdMap>>,
+    matcher: Option<Matcher>,
+}
+
+impl Reconciler {
+    /// Creates a reconciler for a resource type registered with `registry`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::ResourceTypeNotFound` if the resource type is not registered.
+    pub fn new(registry: &SchemaRegistry, resource_type: &str) -> Result<Self, SCIMError> {
+        let schema = registry
+            .merged_schema(resource_type)
+            .ok_or_else(|| SCIMError::ResourceTypeNotFound(resource_type.into()))?;
+        let name_key = if schema.get("userName").is_some() {
+            "userName"
+        } else {
+            "displayName"
+        };
+        Ok(Reconciler {
+            resource_type: registry
+                .resource_type(resource_type)
+                .map_or_else(|| resource_type.to_string(), |rt| rt.name.clone()),
+            keys: vec!["externalId".to_string(), name_key.to_string()],
+            schema,
+            removal: Removal::default(),
+            ids: None,
+            matcher: None,
+        })
+    }
+
+    /// Replaces the match keys, which are attribute paths such as `externalId` or
+    /// `urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:employeeNumber`, in order of preference.
+    pub fn match_on<I, S>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.keys = keys.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets what happens to target resources the source does not have.
+    pub fn removal(mut self, removal: Removal) -> Self {
+        self.removal = removal;
+        self
+    }
+
+    /// Matches a source resource to the target resource its `externalId` is mapped to in `ids` before
+    /// trying the match keys, so that targets which do not keep `externalId` are not sent duplicates.
+    pub fn with_id_map(mut self, ids: Arc<IdMap>) -> Self {
+        self.ids = Some(ids);
+        self
+    }
+
+    /// Matches source resources with `matcher` instead of the match keys, for targets whose resources can
+    /// only be recognised heuristically. Source resources the matcher cannot link with certainty are set
+    /// aside in [`Plan::uncertain`] rather than created, so that a person is not provisioned twice.
+    pub fn with_matcher(mut self, matcher: Matcher) -> Self {
+        self.matcher = Some(matcher);
+        self
+    }
+
+    /// Plans the operations that make `target` match `source`.
+    ///
+    /// Creates come first, then updates, then deactivations and deletions. Every target resource is matched
+    /// at most once; when several source resources match the same target, the first one wins and the others
+    /// are created.
+    pub fn plan(&self, source: &[Value], target: &[Value]) -> Plan {
+        self.plan_in(&RequestContext::default(), source, target)
+    }
+
+    /// [`Reconciler::plan`] for the tenant of `context`, whose mappings of the id map are used.
+    pub fn plan_in(&self, context: &RequestContext, source: &[Value], target: &[Value]) -> Plan {
+        let mut claimed = vec![false; target.len()];
+        let mut creates = Vec::new();
+        let mut updates = Vec::new();
+        let mut unchanged = 0;
+        let mut uncertain = Vec::new();
+        let mut held = vec![false; target.len()];
+        for wanted in source {
+            let mapped = self.mapped_id(context, wanted).and_then(|id| {
+                target.iter().enumerate().find(|(i, existing)| {
+                    !claimed[*i]
+                        && existing["id"]
+                            .as_str()
+                            .is_some_and(|existing| existing.eq_ignore_ascii_case(&id))
+                })
+            });
+            let found = match (mapped, &self.matcher) {
+                (Some(found), _) => Some(found),
+                (None, Some(matcher)) => {
+                    match matcher.find_where(wanted, target, |i| !claimed[i]) {
+                        MatchResult::Linked(candidate) => {
+                            Some((candidate.index, &target[candidate.index]))
+                        }
+                        MatchResult::Uncertain(candidates) => {
+                            // The candidates may be this resource, so they are not removed either.
+                            for candidate in candidates {
+                                held[candidate.index] = true;
+                            }
+                            uncertain.push(wanted.clone());
+                            continue;
+                        }
+                        MatchResult::Unmatched => None,
+                    }
+                }
+                (None, None) => self.keys.iter().find_map(|key| {
+                    let value = self.key_value(wanted, key)?;
+                    target.iter().enumerate().find(|(i, existing)| {
+                        !claimed[*i] && self.key_value(existing, key).as_ref() == Some(&value)
+                    })
+                }),
+            };
+            let Some((index, existing)) = found else {
+                creates.push(Operation::Create {
+                    resource: without_server_attributes(wanted),
+                });
+                continue;
+            };
+            claimed[index] = true;
+            let operations = self.diff(existing, wanted);
+            if operations.is_empty() {
+                unchanged += 1;
+            } else {
+                let mut resource = without_server_attributes(wanted);
+                resource["id"] = existing["id"].clone();
+                updates.push(Operation::Update {
+                    id: id_of(existing),
+                    patch: patch_op(operations),
+                    resource,
+                });
+            }
+        }
+
+        let mut removals = Vec::new();
+        for (index, existing) in target.iter().enumerate() {
+            if claimed[index] || held[index] {
+                continue;
+            }
+            let deactivate =
+                self.removal == Removal::Deactivate && self.schema.get("active").is_some();
+            match self.removal {
+                Removal::Keep => {}
+                _ if deactivate => {
+                    if existing["active"] != Value::Bool(false) {
+                        let mut resource = without_server_attributes(existing);
+                        resource["id"] = existing["id"].clone();
+                        resource["active"] = Value::Bool(false);
+                        removals.push(Operation::Deactivate {
+                            id: id_of(existing),
+                            patch: patch_op(vec![
+                                json!({"op": "replace", "path": "active", "value": false}),
+                            ]),
+                            resource,
+                        });
+                    }
+                }
+                _ => removals.push(Operation::Delete {
+                    id: id_of(existing),
+                }),
+            }
+        }
+
+        creates.extend(updates);
+        creates.extend(removals);
+        Plan {
+            resource_type: self.resource_type.clone(),
+            operations: creates,
+            unchanged,
+            uncertain,
+        }
+    }
+
+    /// Plans against the resources `target` holds for `context`, listing all of them.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of [`ResourceProvider::list_in`].
+    pub fn plan_against(
+        &self,
+        source: &[Value],
+        context: &RequestContext,
+        target: &impl ResourceProvider,
+    ) -> Result<Plan, SCIMError> {
+        let query = ListQuery {
+            count: None,
+            ..ListQuery::default()
+        };
+        let existing = target
+            .list_in(context, &self.resource_type, &query)?
+            .resources;
+        Ok(self.plan_in(context, source, &existing))
+    }
+
+    // The target id the id map has for the `externalId` of a source resource.
+    fn mapped_id(&self, context: &RequestContext, wanted: &Value) -> Option<String> {
+        let external_id = wanted["externalId"].as_str()?;
+        self.ids
+            .as_ref()?
+            .id_for(context, &self.resource_type, external_id)
+    }
+
+    // The value at a match key, folded to lower case unless the attribute is case-exact.
+    fn key_value(&self, resource: &Value, key: &str) -> Option<String> {
+        let value = value_at(resource, key)?.as_str()?;
+        let case_exact = self
+            .schema
+            .get(key)
+            .and_then(|attribute| attribute.attribute.case_exact)
+            .unwrap_or(false);
+        Some(if case_exact {
+            value.to_string()
+        } else {
+            value.to_lowercase()
+        })
+    }
+
+    // The PATCH operations that turn `existing` into `wanted`.
+    fn diff(&self, existing: &Value, wanted: &Value) -> Vec<Value> {
+        diff_resources(
+            existing,
+            wanted,
+            |name| self.is_extension(name),
+            |path| self.managed(path),
+        )
+    }
+
+    // Whether the attribute is written by clients, and so is the source's to decide.
+    fn managed(&self, path: &str) -> bool {
+        if ["id", "meta", "schemas"]
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(path))
+        {
+            return false;
+        }
+        self.schema.get(path).is_none_or(|merged| {
+            let attribute = &merged.attribute;
+            !attribute
+                .mutability
+                .as_deref()
+                .is_some_and(|m| m.eq_ignore_ascii_case("readOnly"))
+                && !attribute
+                    .returned
+                    .as_deref()
+                    .is_some_and(|r| r.eq_ignore_ascii_case("never"))
+        })
+    }
+
+    fn is_extension(&self, name: &str) -> bool {
+        self.schema
+            .schemas
+            .iter()
+            .skip(1)
+            .any(|schema| schema.eq_ignore_ascii_case(name))
+    }
+}
+
+fn patch_op(operations: Vec<Value>) -> Value {
+    json!({"schemas": [PATCH_OP_URN], "Operations": operations})
+}
+
+fn id_of(resource: &Value) -> String {
+    resource["id"].as_str().unwrap_or_default().to_string()
+}
+
+fn without_server_attributes(resource: &Value) -> Value {
+    let mut resource = resource.clone();
+    if let Some(object) = resource.as_object_mut() {
+        object.remove("id");
+        object.remove("meta");
+    }
+    resource
+}
+
+// Looks up a dotted attribute path, optionally prefixed with an extension URN.
+pub(crate) fn value_at<'a>(resource: &'a Value, path: &str) -> Option<&'a Value> {
+    let (object, path) = match path.rsplit_once(':') {
+        Some((urn, attribute)) => (resource.get(urn)?, attribute),
+        None => (resource, path),
+    };
+    path.split('.')
+        .try_fold(object, |value, name| value.get(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::schema_uri::ENTERPRISE_USER_URN;
+    use crate::provider::memory::MemoryStore;
+
+    fn reconciler() -> Reconciler {
+        Reconciler::new(&SchemaRegistry::with_builtins(), "User").unwrap()
+    }
+
+    #[test]
+    fn diffs_matched_resources_attribute_by_attribute() {
+        let existing = json!({
+            "id": "1",
+            "userName": "BJensen",
+            "nickName": "Babs",
+            "emails": [{"value": "b@example.com"}, {"value": "bj@example.com"}],
+            "groups": [{"value": "g1"}],
+            ENTERPRISE_USER_URN: {"employeeNumber": "701984", "costCenter": "4130"},
+            "meta": {"resourceType": "User"}
+        });
+        let wanted = json!({
+            "userName": "bjensen",
+            "emails": [{"value": "bj@example.com"}, {"value": "b@example.com"}],
+            "password": "t1meMa$heen",
+            ENTERPRISE_USER_URN: {"employeeNumber": "701985"}
+        });
+        let plan = reconciler().plan(&[wanted], &[existing]);
+        let [
+            Operation::Update {
+                id,
+                patch,
+                resource,
+            },
+        ] = plan.operations.as_slice()
+        else {
+            panic!("{:?}", plan.operations);
+        };
+        assert_eq!(id, "1");
+        assert_eq!(resource["id"], "1");
+        assert_eq!(
+            patch["Operations"],
+            json!([
+                {"op": "replace", "path": format!("{}:employeeNumber", ENTERPRISE_USER_URN), "value": "701985"},
+                {"op": "remove", "path": format!("{}:costCenter", ENTERPRISE_USER_URN)},
+                {"op": "replace", "path": "userName", "value": "bjensen"},
+                {"op": "remove", "path": "nickName"}
+            ])
+        );
+    }
+
+    #[test]
+    fn matches_on_keys_and_applies_the_plan() {
+        let store =
+            MemoryStore::with_registry("https://example.com/v2", SchemaRegistry::with_builtins());
+        let context = RequestContext::default();
+        for (external_id, user_name) in [("e1", "bjensen"), ("e2", "jsmith"), ("e3", "gone")] {
+            store
+                .create(
+                    "User",
+                    json!({"schemas": [crate::models::schema_uri::USER_URN], "externalId": external_id, "userName": user_name}),
+                )
+                .unwrap();
+        }
+        let source = [
+            json!({"schemas": [crate::models::schema_uri::USER_URN], "externalId": "E1", "userName": "bjensen"}),
+            json!({"schemas": [crate::models::schema_uri::USER_URN], "userName": "JSMITH", "externalId": "e2", "title": "CTO"}),
+            json!({"schemas": [crate::models::schema_uri::USER_URN], "userName": "new"}),
+        ];
+
+        // externalId is case-exact, so E1 only matches by userName.
+        let plan = reconciler()
+            .plan_against(&source, &context, &store)
+            .unwrap();
+        assert_eq!(
+            plan.report(),
+            Report {
+                creates: 1,
+                updates: 2,
+                deactivations: 1,
+                deletions: 0,
+                unchanged: 0,
+                uncertain: 0
+            }
+        );
+        plan.apply(&context, &store).unwrap();
+        let plan = reconciler()
+            .plan_against(&source, &context, &store)
+            .unwrap();
+        assert_eq!(plan.report().unchanged, 3);
+        assert!(plan.operations.is_empty());
+
+        let deleting = reconciler()
+            .match_on(["userName"])
+            .removal(Removal::Delete)
+            .plan_against(&source[..1], &context, &store)
+            .unwrap();
+        assert_eq!(deleting.report().deletions, 3);
+    }
+
+    #[test]
+    fn matches_by_mapped_id_before_the_match_keys() {
+        let ids = Arc::new(IdMap::new());
+        let acme = RequestContext::default().with_tenant("acme");
+        ids.insert(&acme, "User", "00u1", "2819c223").unwrap();
+        // The target dropped externalId, and the user was renamed at the source.
+        let source = [json!({"externalId": "00u1", "userName": "barbara"})];
+        let target = [json!({"id": "2819C223", "userName": "bjensen"})];
+
+        let plan = reconciler()
+            .with_id_map(ids)
+            .plan_in(&acme, &source, &target);
+        let [Operation::Update { id, .. }] = plan.operations.as_slice() else {
+            panic!("{:?}", plan.operations);
+        };
+        assert_eq!(id, "2819C223");
+        assert_eq!(
+            reconciler()
+                .plan_in(&acme, &source, &target)
+                .report()
+                .creates,
+            1
+        );
+    }
+
+    #[test]
+    fn sets_aside_sources_the_matcher_cannot_link() {
+        let source = [
+            json!({"userName": "barbara", "emails": [{"value": "bjensen@example.com", "primary": true}]}),
+            json!({"userName": "jsmith", "emails": [{"value": "team@example.com"}]}),
+        ];
+        let target = [
+            json!({"id": "1", "userName": "bjensen", "active": true, "emails": [{"value": "BJensen@example.com", "primary": true}]}),
+            json!({"id": "2", "userName": "jdoe", "active": true, "emails": [{"value": "team@example.com"}]}),
+            json!({"id": "3", "userName": "asmith", "active": true, "emails": [{"value": "team@example.com"}]}),
+        ];
+        let plan = reconciler()
+            .with_matcher(Matcher::new())
+            .plan(&source, &target);
+        let [Operation::Update { id, .. }] = plan.operations.as_slice() else {
+            panic!("{:?}", plan.operations);
+        };
+        assert_eq!(id, "1");
+        assert_eq!(plan.uncertain, source[1..]);
+        assert_eq!(
+            plan.report().to_string(),
+            "0 to create, 1 to update, 0 to deactivate, 0 to delete, 0 unchanged, 1 to review"
+        );
+    }
+}
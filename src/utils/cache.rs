@@ -0,0 +1,245 @@
+//! A least-recently-used cache for parsed expressions.
+//!
+//! Identity providers send the same few filters and attribute paths (`userName eq "..."`, `members`) over
+//! and over. `ParseCache` keeps the parsed form of recently seen expressions, keyed by their text, so a
+//! server parses each distinct expression once instead of on every request. [`ParseCache::filter`] and
+//! [`ParseCache::path`] cache [`FilterExpr`]s and [`AttrPath`]s; the stores in `provider` share such caches
+//! for the filters and `sortBy` paths of their queries.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use crate::models::filter::{AttrPath, FilterExpr};
+use crate::utils::error::SCIMError;
+
+/// Hit and miss counters of a [`ParseCache`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Lookups answered from the cache.
+    pub hits: u64,
+    /// Lookups that had to parse the expression.
+    pub misses: u64,
+}
+
+// A least-recently-used map shared by `ParseCache` and the provider's `MemoryCache`; callers put it behind a
+// lock. Every lookup and insert advances the clock, and evicting scans for the entry used longest ago.
+pub(crate) struct Lru<V> {
+    capacity: usize,
+    entries: HashMap<String, (V, u64)>,
+    clock: u64,
+    stats: CacheStats,
+}
+
+impl<V> Lru<V> {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Lru {
+            capacity,
+            entries: HashMap::new(),
+            clock: 0,
+            stats: CacheStats::default(),
+        }
+    }
+
+    pub(crate) fn get(&mut self, key: &str) -> Option<&V> {
+        self.clock += 1;
+        match self.entries.get_mut(key) {
+            Some((value, last_used)) => {
+                *last_used = self.clock;
+                self.stats.hits += 1;
+                Some(value)
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub(crate) fn insert(&mut self, key: &str, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(key) {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.clock += 1;
+        self.entries.insert(key.to_string(), (value, self.clock));
+    }
+
+    pub(crate) fn remove(&mut self, key: &str) {
+        self.entries.remove(key);
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub(crate) fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// A thread-safe least-recently-used cache of parsed values keyed by their source text.
+///
+/// Values are shared as `Arc<T>`, so a cached value stays usable after it is evicted. Parsing happens outside
+/// the cache's lock; when two threads miss on the same text at once, both parse it and the later result is
+/// kept. Evicting the least recently used entry scans the cache, which is cheap at the sizes this cache is
+/// meant for (hundreds to a few thousand entries) because eviction only happens on a miss.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::utils::cache::ParseCache;
+///
+/// let cache: ParseCache<Vec<String>> = ParseCache::new(100);
+/// let parse = |path: &str| -> Result<Vec<String>, ()> { Ok(path.split('.').map(String::from).collect()) };
+/// let first = cache.get_or_parse("name.givenName", parse).unwrap();
+/// let second = cache.get_or_parse("name.givenName", parse).unwrap();
+/// assert_eq!(*first, ["name", "givenName"]);
+/// assert_eq!(cache.stats().hits, 1);
+/// assert!(std::sync::Arc::ptr_eq(&first, &second));
+/// ```
+pub struct ParseCache<T> {
+    inner: Mutex<Lru<Arc<T>>>,
+}
+
+impl<T> ParseCache<T> {
+    /// Creates a cache holding at most `capacity` entries; a capacity of zero disables caching.
+    pub fn new(capacity: usize) -> Self {
+        ParseCache {
+            inner: Mutex::new(Lru::new(capacity)),
+        }
+    }
+
+    /// Returns the cached value for `text`, or parses it with `parse` and caches the result.
+    ///
+    /// Parse errors are returned as they are and not cached.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `parse` if `text` is not cached and cannot be parsed.
+    pub fn get_or_parse<E>(
+        &self,
+        text: &str,
+        parse: impl FnOnce(&str) -> Result<T, E>,
+    ) -> Result<Arc<T>, E> {
+        if let Some(value) = self.lock().get(text) {
+            return Ok(Arc::clone(value));
+        }
+        let value = Arc::new(parse(text)?);
+        self.lock().insert(text, Arc::clone(&value));
+        Ok(value)
+    }
+
+    /// Returns the number of cached entries.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.lock().len() == 0
+    }
+
+    /// Returns the hit and miss counters accumulated since the cache was created.
+    pub fn stats(&self) -> CacheStats {
+        self.lock().stats()
+    }
+
+    /// Removes all entries, keeping the counters.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Lru<Arc<T>>> {
+        // The cache holds no invariants a panicking parser could break, so a poisoned lock is still usable.
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl ParseCache<FilterExpr> {
+    /// Returns the cached parse of `filter`, or parses it with [`FilterExpr::parse`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::InvalidFilter` if `filter` is not cached and is malformed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::filter::{AttrPath, FilterExpr};
+    /// use scim_v2::utils::cache::ParseCache;
+    ///
+    /// let filters: ParseCache<FilterExpr> = ParseCache::new(1000);
+    /// let filter = filters.filter(r#"userName eq "bjensen""#).unwrap();
+    /// assert!(matches!(*filter, FilterExpr::Compare(..)));
+    /// assert!(filters.filter("userName eq").is_err());
+    ///
+    /// let paths: ParseCache<AttrPath> = ParseCache::new(1000);
+    /// assert_eq!(paths.path("name.familyName").unwrap().name, "name");
+    /// ```
+    pub fn filter(&self, filter: &str) -> Result<Arc<FilterExpr>, SCIMError> {
+        self.get_or_parse(filter, FilterExpr::parse)
+    }
+}
+
+impl ParseCache<AttrPath> {
+    /// Returns the cached parse of `path`, or parses it with [`AttrPath::parse`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::InvalidFilter` if `path` is not cached and is not an attribute path.
+    pub fn path(&self, path: &str) -> Result<Arc<AttrPath>, SCIMError> {
+        self.get_or_parse(path, AttrPath::parse)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<usize, String> {
+        if text.is_empty() {
+            Err("empty".to_string())
+        } else {
+            Ok(text.len())
+        }
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used_entry() {
+        let cache = ParseCache::new(2);
+        cache.get_or_parse("a", parse).unwrap();
+        cache.get_or_parse("bb", parse).unwrap();
+        cache.get_or_parse("a", parse).unwrap();
+        cache.get_or_parse("ccc", parse).unwrap();
+        assert_eq!(cache.len(), 2);
+
+        cache.get_or_parse("a", parse).unwrap();
+        assert_eq!(cache.stats(), CacheStats { hits: 2, misses: 3 });
+        cache.get_or_parse("bb", parse).unwrap();
+        assert_eq!(cache.stats(), CacheStats { hits: 2, misses: 4 });
+    }
+
+    #[test]
+    fn does_not_cache_errors() {
+        let cache = ParseCache::new(10);
+        assert!(cache.get_or_parse("", parse).is_err());
+        assert!(cache.is_empty());
+
+        let disabled = ParseCache::new(0);
+        assert_eq!(*disabled.get_or_parse("abc", parse).unwrap(), 3);
+        assert!(disabled.is_empty());
+    }
+}
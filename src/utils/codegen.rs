@@ -0,0 +1,235 @@
+use std::fmt::Write;
+
+use crate::models::scim_schema::{Attributes, Schema};
+
+// Words that cannot be used as plain identifiers and need the raw `r#` prefix.
+const KEYWORDS: [&str; 47] = [
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false",
+    "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+    "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where", "while",
+    "abstract", "become", "box", "do", "final", "gen", "macro", "override", "priv", "try",
+    "typeof", "unsized", "virtual",
+];
+
+// Keywords that cannot be raw identifiers either; fields named after them get a trailing `_`. Field names
+// are lowercased, so `Self` ends up as `self`.
+const RESERVED: [&str; 3] = ["self", "super", "crate"];
+
+/// Generates Rust struct definitions for a `Schema` document.
+///
+/// The output is a self-contained Rust source snippet: one struct named after the schema, plus one struct for
+/// every complex attribute, named by appending the attribute name to the parent struct name (e.g. `UserName`
+/// for `name`). Attribute types map to `String`, `bool`, `i64` and `f64`; `dateTime`, `binary` and
+/// `reference` attributes are kept as `String`. Multi-valued attributes become `Vec<T>`, and attributes
+/// that are not required are wrapped in `Option`. Fields use snake_case names with a `#[serde(rename)]`
+/// wherever that differs from the SCIM attribute name, and descriptions become doc comments. Names that are
+/// Rust keywords become raw identifiers (`r#type`), except `self`, `super` and `crate`, which cannot be and
+/// get a trailing underscore instead (`self_`).
+///
+/// This is meant for onboarding partner schemas: run it once, check the result into the code base and
+/// adjust as needed. With the `codegen` feature enabled, the `scim-codegen` binary does the same from the
+/// command line.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::scim_schema::get_schema;
+/// use scim_v2::utils::codegen::generate_rust;
+///
+/// let schema = get_schema("urn:ietf:params:scim:schemas:extension:enterprise:2.0:User").unwrap();
+/// let code = generate_rust(&schema);
+/// assert!(code.contains("pub struct EnterpriseUser {"));
+/// assert!(code.contains("#[serde(rename = \"employeeNumber\", skip_serializing_if = \"Option::is_none\")]"));
+/// assert!(code.contains("pub employee_number: Option<String>,"));
+/// ```
+pub fn generate_rust(schema: &Schema) -> String {
+    let mut code = String::new();
+    code.push_str("use serde::{Deserialize, Serialize};\n");
+    let name = pascal_case(&schema.name);
+    write_struct(&mut code, &name, &schema.description, &schema.attributes);
+    code
+}
+
+fn write_struct(code: &mut String, name: &str, description: &str, attributes: &[Attributes]) {
+    let mut nested = Vec::new();
+
+    code.push('\n');
+    write_doc(code, "", description);
+    code.push_str("#[derive(Serialize, Deserialize, Debug, Clone, Default)]\n");
+    let _ = writeln!(code, "pub struct {} {{", name);
+    for attribute in attributes {
+        let mut ty = match attribute.r#type.as_str() {
+            "boolean" => "bool".to_string(),
+            "integer" => "i64".to_string(),
+            "decimal" => "f64".to_string(),
+            "complex" => {
+                let nested_name = format!("{}{}", name, pascal_case(&attribute.name));
+                let sub_attributes: Vec<Attributes> = attribute
+                    .sub_attributes
+                    .iter()
+                    .flatten()
+                    .cloned()
+                    .map(Attributes::from)
+                    .collect();
+                nested.push((
+                    nested_name.clone(),
+                    attribute.description.clone(),
+                    sub_attributes,
+                ));
+                nested_name
+            }
+            _ => "String".to_string(),
+        };
+        if attribute.multi_valued {
+            ty = format!("Vec<{}>", ty);
+        }
+        let required = attribute.required == Some(true);
+        if !required {
+            ty = format!("Option<{}>", ty);
+        }
+
+        let field = field_name(&attribute.name);
+        let mut serde = Vec::new();
+        if field.trim_start_matches("r#") != attribute.name {
+            serde.push(format!("rename = \"{}\"", attribute.name));
+        }
+        if !required {
+            serde.push("skip_serializing_if = \"Option::is_none\"".to_string());
+        }
+
+        if let Some(description) = &attribute.description {
+            write_doc(code, "    ", description);
+        }
+        if !serde.is_empty() {
+            let _ = writeln!(code, "    #[serde({})]", serde.join(", "));
+        }
+        let _ = writeln!(code, "    pub {}: {},", field, ty);
+    }
+    code.push_str("}\n");
+
+    for (nested_name, description, sub_attributes) in nested {
+        write_struct(
+            code,
+            &nested_name,
+            description.as_deref().unwrap_or_default(),
+            &sub_attributes,
+        );
+    }
+}
+
+fn write_doc(code: &mut String, indent: &str, description: &str) {
+    let description = description.split_whitespace().collect::<Vec<_>>().join(" ");
+    if !description.is_empty() {
+        let _ = writeln!(code, "{}/// {}", indent, description);
+    }
+}
+
+fn field_name(attribute: &str) -> String {
+    let mut field = String::new();
+    for c in attribute.trim_start_matches('$').chars() {
+        if c.is_ascii_uppercase() {
+            if !field.is_empty() && !field.ends_with('_') {
+                field.push('_');
+            }
+            field.push(c.to_ascii_lowercase());
+        } else if c == '-' {
+            field.push('_');
+        } else {
+            field.push(c);
+        }
+    }
+    if RESERVED.contains(&field.as_str()) {
+        format!("{}_", field)
+    } else if KEYWORDS.contains(&field.as_str()) {
+        format!("r#{}", field)
+    } else {
+        field
+    }
+}
+
+fn pascal_case(name: &str) -> String {
+    let mut result = String::new();
+    let mut upper = true;
+    for c in name.chars() {
+        if !c.is_ascii_alphanumeric() {
+            upper = true;
+        } else if upper {
+            result.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::schema_uri::{GROUP_URN, USER_URN};
+    use crate::models::scim_schema::get_schema;
+
+    #[test]
+    fn maps_names_and_keywords() {
+        assert_eq!(field_name("userName"), "user_name");
+        assert_eq!(field_name("$ref"), "r#ref");
+        assert_eq!(field_name("type"), "r#type");
+        assert_eq!(field_name("self"), "self_");
+        assert_eq!(field_name("Self"), "self_");
+        assert_eq!(field_name("x509Certificates"), "x509_certificates");
+        assert_eq!(pascal_case("EnterpriseUser"), "EnterpriseUser");
+        assert_eq!(pascal_case("phoneNumbers"), "PhoneNumbers");
+    }
+
+    #[test]
+    fn generates_nested_structs_for_complex_attributes() {
+        let code = generate_rust(&get_schema(GROUP_URN).unwrap());
+        assert!(code.contains("pub struct Group {"));
+        assert!(code.contains("pub members: Option<Vec<GroupMembers>>,"));
+        assert!(code.contains("pub struct GroupMembers {"));
+        assert!(code.contains("    #[serde(rename = \"$ref\", skip_serializing_if = \"Option::is_none\")]\n    pub r#ref: Option<String>,"));
+
+        let code = generate_rust(&get_schema(USER_URN).unwrap());
+        assert!(code.contains("    #[serde(rename = \"userName\")]\n    pub user_name: String,"));
+        assert!(code.contains("pub active: Option<bool>,"));
+    }
+
+    #[allow(dead_code)]
+    mod generated {
+        include!("testdata/codegen_keywords.rs");
+    }
+
+    #[test]
+    fn generated_code_compiles_and_keeps_attribute_names() {
+        let schema: Schema = serde_json::from_value(serde_json::json!({
+            "id": "urn:example:params:scim:schemas:core:1.0:Keywords",
+            "name": "Keywords",
+            "description": "Attributes named after Rust keywords",
+            "attributes": [
+                {"name": "self", "type": "reference", "multiValued": false},
+                {"name": "super", "type": "string", "multiValued": false, "required": true},
+                {"name": "crate", "type": "integer", "multiValued": false},
+                {"name": "type", "type": "string", "multiValued": false},
+                {"name": "try", "type": "boolean", "multiValued": true}
+            ],
+            "meta": {"resourceType": "Schema"}
+        }))
+        .unwrap();
+        assert_eq!(
+            generate_rust(&schema),
+            include_str!("testdata/codegen_keywords.rs")
+        );
+
+        let keywords = generated::Keywords {
+            self_: Some("https://example.com/v2/Keywords/1".to_string()),
+            super_: "root".to_string(),
+            crate_: Some(1),
+            r#type: None,
+            r#try: Some(vec![true]),
+        };
+        assert_eq!(
+            serde_json::to_value(keywords).unwrap(),
+            serde_json::json!({"self": "https://example.com/v2/Keywords/1", "super": "root", "crate": 1, "try": [true]})
+        );
+    }
+}
@@ -0,0 +1,241 @@
+//! Cursor-based pagination, as proposed by the SCIM cursor pagination draft.
+//!
+//! Instead of `startIndex`, a client sends the `cursor` it got as `nextCursor` or `previousCursor` of the
+//! previous page, or an empty `cursor` for the first page. [`CursorCodec`] mints and checks the opaque cursors
+//! on the server, and [`CursorPages`] follows them on the client.
+
+use crate::models::others::{ListQuery, ListResponse};
+use crate::utils::error::SCIMError;
+use crate::utils::etag::fnv1a;
+use crate::utils::pagination::{Follow, PageRequest, PageStrategy};
+use crate::utils::time::now_unix;
+
+/// Mints and decodes opaque pagination cursors.
+///
+/// A cursor encodes the 0-based offset of a page together with when it was minted, and a checksum over both
+/// and the query's `filter`, `sortBy` and `sortOrder`. A cursor therefore only decodes for the query it was
+/// minted for, and not after the codec's timeout. The checksum is not a signature: a client that forges a
+/// cursor gains nothing over sending a `startIndex`.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::others::ListQuery;
+/// use scim_v2::utils::cursor::CursorCodec;
+///
+/// let codec = CursorCodec::default();
+/// let query = ListQuery {
+///     filter: Some(r#"userName sw "b""#.to_string()),
+///     ..ListQuery::default()
+/// };
+/// let cursor = codec.mint(&query, 100);
+/// assert_eq!(codec.decode(&cursor, &query).unwrap(), 100);
+///
+/// let other = ListQuery::default();
+/// assert!(codec.decode(&cursor, &other).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CursorCodec {
+    timeout: Option<u64>,
+}
+
+impl CursorCodec {
+    /// Creates a codec whose cursors expire `seconds` after they were minted, matching the `cursorTimeout`
+    /// a service provider advertises in its `ServiceProviderConfig`.
+    pub fn with_timeout(seconds: u64) -> Self {
+        CursorCodec {
+            timeout: Some(seconds),
+        }
+    }
+
+    /// Returns a cursor for the page of `query`'s results starting at the 0-based `offset`.
+    pub fn mint(&self, query: &ListQuery, offset: usize) -> String {
+        let issued = now_unix();
+        format!(
+            "{:x}.{:x}.{:016x}",
+            offset,
+            issued,
+            checksum(query, offset, issued)
+        )
+    }
+
+    /// Returns the 0-based offset encoded in `cursor`. An empty cursor asks for the first page.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::InvalidFieldValue` if the cursor is malformed, was minted for a different query,
+    /// has expired, or encodes an offset too large to page to.
+    pub fn decode(&self, cursor: &str, query: &ListQuery) -> Result<usize, SCIMError> {
+        if cursor.is_empty() {
+            return Ok(0);
+        }
+        let invalid =
+            || SCIMError::InvalidFieldValue(format!("invalid cursor '{}'", cursor).into());
+        let mut parts = cursor.split('.');
+        let (Some(offset), Some(issued), Some(check), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+        let offset = usize::from_str_radix(offset, 16).map_err(|_| invalid())?;
+        // Checksums are unkeyed, so a forged offset must still make a valid 1-based `startIndex`.
+        if offset >= i64::MAX as usize {
+            return Err(invalid());
+        }
+        let issued = u64::from_str_radix(issued, 16).map_err(|_| invalid())?;
+        let check = u64::from_str_radix(check, 16).map_err(|_| invalid())?;
+        if check != checksum(query, offset, issued) {
+            return Err(invalid());
+        }
+        if let Some(timeout) = self.timeout {
+            if now_unix().saturating_sub(issued) > timeout {
+                return Err(SCIMError::InvalidFieldValue(
+                    format!("expired cursor '{}'", cursor).into(),
+                ));
+            }
+        }
+        Ok(offset)
+    }
+}
+
+fn checksum(query: &ListQuery, offset: usize, issued: u64) -> u64 {
+    let text = format!(
+        "{}.{}\n{}\n{}\n{}",
+        offset,
+        issued,
+        query.filter.as_deref().unwrap_or_default().trim(),
+        query.sort_by.as_deref().unwrap_or_default(),
+        query
+            .sort_order
+            .as_deref()
+            .unwrap_or_default()
+            .to_ascii_lowercase()
+    );
+    fnv1a(text.as_bytes())
+}
+
+/// Iterates over the pages of a cursor-paginated list, following `nextCursor` until there is none.
+///
+/// `fetch` is called with the cursor to request, starting with the empty cursor, and returns the page the
+/// service provider sent back; a transport is all a client needs to supply. The iteration ends after the
+/// first error, and fails if the service provider hands out a cursor a second time. To follow lists by
+/// index and by cursor alike, use [`Pages`](crate::utils::pagination::Pages).
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::others::ListResponse;
+/// use scim_v2::utils::cursor::CursorPages;
+///
+/// let pages = CursorPages::new(|cursor: &str| {
+///     Ok(ListResponse {
+///         total_results: 2,
+///         next_cursor: cursor.is_empty().then(|| "page-2".to_string()),
+///         ..ListResponse::default()
+///     })
+/// });
+/// assert_eq!(pages.count(), 2);
+/// ```
+pub struct CursorPages<F> {
+    fetch: F,
+    follow: Follow,
+}
+
+impl<F> CursorPages<F>
+where
+    F: FnMut(&str) -> Result<ListResponse, SCIMError>,
+{
+    /// Starts at the first page.
+    pub fn new(fetch: F) -> Self {
+        CursorPages {
+            fetch,
+            follow: Follow::new(PageRequest::first(PageStrategy::Cursor, None)),
+        }
+    }
+}
+
+impl<F> Iterator for CursorPages<F>
+where
+    F: FnMut(&str) -> Result<ListResponse, SCIMError>,
+{
+    type Item = Result<ListResponse, SCIMError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let PageRequest::Cursor { cursor, count } = self.follow.take()? else {
+            return None;
+        };
+        let page = match (self.fetch)(&cursor) {
+            Ok(page) => page,
+            Err(e) => return Some(Err(e)),
+        };
+        let next = page
+            .next_cursor
+            .clone()
+            .filter(|next| !next.is_empty())
+            .map(|cursor| PageRequest::Cursor { cursor, count });
+        if let Err(e) = self.follow.advance(next) {
+            return Some(Err(e));
+        }
+        Some(Ok(page))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_malformed_tampered_and_expired_cursors() {
+        let codec = CursorCodec::default();
+        let query = ListQuery::default();
+        assert_eq!(codec.decode("", &query).unwrap(), 0);
+        assert!(codec.decode("nonsense", &query).is_err());
+
+        let cursor = codec.mint(&query, 20);
+        let (offset, rest) = cursor.split_once('.').unwrap();
+        assert_eq!(offset, "14");
+        assert!(codec.decode(&format!("15.{}", rest), &query).is_err());
+
+        let sorted = ListQuery {
+            sort_by: Some("userName".to_string()),
+            ..ListQuery::default()
+        };
+        assert!(codec.decode(&cursor, &sorted).is_err());
+
+        let issued = now_unix() - 120;
+        let old = format!("14.{:x}.{:016x}", issued, checksum(&query, 20, issued));
+        assert_eq!(codec.decode(&old, &query).unwrap(), 20);
+        assert_eq!(
+            CursorCodec::with_timeout(300).decode(&old, &query).unwrap(),
+            20
+        );
+        assert!(CursorCodec::with_timeout(60).decode(&old, &query).is_err());
+
+        for offset in [usize::MAX, i64::MAX as usize] {
+            let forged = format!(
+                "{:x}.{:x}.{:016x}",
+                offset,
+                issued,
+                checksum(&query, offset, issued)
+            );
+            assert!(codec.decode(&forged, &query).is_err());
+        }
+    }
+
+    #[test]
+    fn stops_when_a_cursor_repeats() {
+        let mut calls = 0;
+        let results: Vec<_> = CursorPages::new(|_: &str| {
+            calls += 1;
+            Ok(ListResponse {
+                next_cursor: Some("again".to_string()),
+                ..ListResponse::default()
+            })
+        })
+        .take(5)
+        .collect();
+        assert_eq!(calls, 2);
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(SCIMError::RequestError(_))));
+    }
+}
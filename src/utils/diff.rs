@@ -0,0 +1,158 @@
+//! Attribute-level differences between two resources, expressed as PATCH operations.
+
+use serde_json::{Map, Value, json};
+
+/// Returns the PATCH operations that turn `before` into `after`.
+///
+/// Attributes only `after` has are added, those that differ are replaced, and those only `before` has are
+/// removed. Attributes for
+/// which `is_extension` holds are extension objects, whose attributes are compared one by one and addressed
+/// as `urn:...:attribute`. Attributes, and extension attributes, for which `included` does not hold are
+/// ignored. Multi-valued attributes are compared without regard to order.
+pub(crate) fn diff_resources(
+    before: &Value,
+    after: &Value,
+    is_extension: impl Fn(&str) -> bool,
+    included: impl Fn(&str) -> bool,
+) -> Vec<Value> {
+    let mut operations = Vec::new();
+    let diff = Diff {
+        is_extension,
+        included,
+        fine: false,
+    };
+    diff.members("", before, after, &mut operations);
+    operations
+}
+
+/// Returns the PATCH operations that turn `before` into `after`, as [`diff_resources`] does, but as small as
+/// the operations can be.
+///
+/// Single-valued complex attributes are compared sub-attribute by sub-attribute and addressed as
+/// `name.givenName`, since replacing a complex attribute leaves the sub-attributes the value does not
+/// mention unchanged. Values added to a multi-valued attribute are added rather than the attribute being
+/// replaced, and an extension `after` no longer has is removed as a whole by its schema URI.
+pub(crate) fn diff_attributes(
+    before: &Value,
+    after: &Value,
+    is_extension: impl Fn(&str) -> bool,
+    included: impl Fn(&str) -> bool,
+) -> Vec<Value> {
+    let mut operations = Vec::new();
+    let diff = Diff {
+        is_extension,
+        included,
+        fine: true,
+    };
+    diff.members("", before, after, &mut operations);
+    operations
+}
+
+struct Diff<E, I> {
+    is_extension: E,
+    included: I,
+    // Whether complex attributes are compared sub-attribute by sub-attribute, additions are added and
+    // dropped extensions are removed as a whole.
+    fine: bool,
+}
+
+impl<E: Fn(&str) -> bool, I: Fn(&str) -> bool> Diff<E, I> {
+    fn members(&self, prefix: &str, before: &Value, after: &Value, operations: &mut Vec<Value>) {
+        let Diff {
+            is_extension,
+            included,
+            ..
+        } = self;
+        let empty = Map::new();
+        let before = before.as_object().unwrap_or(&empty);
+        let after = after.as_object().unwrap_or(&empty);
+        // Extensions are only recognized at the top level.
+        let nested = |name: &str| prefix.is_empty() && is_extension(name);
+        // Sub-attributes have no sub-attributes of their own.
+        let descend = self.fine && !prefix.ends_with('.');
+        for (name, value) in after {
+            let path = format!("{}{}", prefix, name);
+            if !included(&path) {
+                continue;
+            }
+            let previous = before.get(name).unwrap_or(&Value::Null);
+            if nested(name) && self.fine && value.is_null() && !previous.is_null() {
+                operations.push(json!({"op": "remove", "path": path}));
+            } else if nested(name) {
+                self.members(&format!("{}:", name), previous, value, operations);
+            } else if !same(previous, value) {
+                self.changed(&path, previous, value, descend, operations);
+            }
+        }
+        for (name, value) in before {
+            let path = format!("{}{}", prefix, name);
+            if !included(&path) || after.contains_key(name) || value.is_null() {
+                continue;
+            }
+            if nested(name) && !self.fine {
+                self.members(&format!("{}:", name), value, &json!({}), operations);
+            } else {
+                operations.push(json!({"op": "remove", "path": path}));
+            }
+        }
+    }
+
+    // Emits the operations that change the attribute at `path` from `previous` to `value`.
+    fn changed(
+        &self,
+        path: &str,
+        previous: &Value,
+        value: &Value,
+        descend: bool,
+        operations: &mut Vec<Value>,
+    ) {
+        if descend && previous.is_object() && value.is_object() {
+            self.members(&format!("{}.", path), previous, value, operations);
+        } else if let Some(added) = self.added(previous, value) {
+            operations.push(json!({"op": "add", "path": path, "value": added}));
+        } else {
+            operations.push(match (previous.is_null(), value.is_null()) {
+                (_, true) => json!({"op": "remove", "path": path}),
+                (true, false) => json!({"op": "add", "path": path, "value": value}),
+                (false, false) => json!({"op": "replace", "path": path, "value": value}),
+            });
+        }
+    }
+
+    // The values `after` has in addition to all of those of `before`, if both are multi-valued attributes
+    // and only fine-grained operations are wanted.
+    fn added(&self, before: &Value, after: &Value) -> Option<Vec<Value>> {
+        let (Value::Array(before), Value::Array(after)) = (before, after) else {
+            return None;
+        };
+        if !self.fine || before.is_empty() {
+            return None;
+        }
+        let mut added: Vec<&Value> = after.iter().collect();
+        for value in before {
+            let found = added.iter().position(|x| same(x, value))?;
+            added.remove(found);
+        }
+        Some(added.into_iter().cloned().collect())
+    }
+}
+
+/// Compares JSON values, treating arrays as unordered.
+pub(crate) fn same(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Array(a), Value::Array(b)) => {
+            let mut unmatched: Vec<&Value> = b.iter().collect();
+            a.len() == b.len()
+                && a.iter().all(|x| {
+                    let found = unmatched.iter().position(|y| same(x, y));
+                    found.map(|i| unmatched.swap_remove(i)).is_some()
+                })
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(key, x)| b.get(key).is_some_and(|y| same(x, y)))
+        }
+        _ => a == b,
+    }
+}
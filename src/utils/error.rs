@@ -1,32 +1,284 @@
-use std::fmt;
-use std::fmt::{Display, Formatter};
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use core::fmt;
+use core::fmt::{Display, Formatter};
+use core::ops::Deref;
+
+use serde_json::Value;
 
 #[derive(Debug)]
 pub enum SCIMError {
     // Todo: Add 400 bad request SCIM Detail Error Keyword Values mentioned here: https://datatracker.ietf.org/doc/html/rfc7644#section-3.12
-    ConflictError(String),
+    ConflictError(ErrorDetail),
     DeserializationError(serde_json::Error),
-    InvalidFieldValue(String),
+    Gone(ErrorDetail),
+    InvalidFieldValue(ErrorDetail),
+    InvalidFilter(ErrorDetail),
     InvalidJsonFormat,
-    MissingRequiredField(String),
-    NotFoundError(String),
-    OtherError(String),
-    RequestError(String),
-    ResourceTypeNotFound(String),
-    SchemaNotFound(String),
+    InvalidPath(ErrorDetail),
+    MissingRequiredField(ErrorDetail),
+    NoTarget(ErrorDetail),
+    NotFoundError(ErrorDetail),
+    OtherError(ErrorDetail),
+    PayloadTooComplex(ErrorDetail),
+    PreconditionFailed(ErrorDetail),
+    RequestError(ErrorDetail),
+    ResourceTypeNotFound(ErrorDetail),
+    SchemaNotFound(ErrorDetail),
     SerializationError(serde_json::Error),
 }
 
+/// The message of an error, and what it is about.
+///
+/// Besides the human-readable message, the detail can name the attribute path, resource type and resource
+/// id an error concerns and the value that caused it, so that server frontends can build their own
+/// responses (or translate them) without parsing messages. It dereferences to the message, and converts
+/// from `String` and `&str`.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::utils::error::{ErrorDetail, SCIMError};
+/// use serde_json::json;
+///
+/// let error = SCIMError::ConflictError(
+///     ErrorDetail::new("userName 'bjensen' is already in use")
+///         .with_attribute("userName")
+///         .with_value(json!("bjensen")),
+/// )
+/// .with_resource_type("User");
+///
+/// assert_eq!(error.attribute(), Some("userName"));
+/// assert_eq!(error.resource_type(), Some("User"));
+/// assert_eq!(error.value(), Some(&json!("bjensen")));
+/// assert_eq!(error.to_string(), "Conflict error: userName 'bjensen' is already in use");
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ErrorDetail {
+    message: String,
+    context: Option<Box<ErrorContext>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+struct ErrorContext {
+    attribute: Option<String>,
+    resource_type: Option<String>,
+    resource_id: Option<String>,
+    value: Option<Value>,
+}
+
+impl ErrorDetail {
+    /// Creates a detail with a message and no context.
+    pub fn new(message: impl Into<String>) -> Self {
+        ErrorDetail {
+            message: message.into(),
+            context: None,
+        }
+    }
+
+    /// Returns the message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the path of the attribute the error concerns, e.g. `emails.value`.
+    pub fn attribute(&self) -> Option<&str> {
+        self.context.as_ref()?.attribute.as_deref()
+    }
+
+    /// Returns the resource type the error concerns.
+    pub fn resource_type(&self) -> Option<&str> {
+        self.context.as_ref()?.resource_type.as_deref()
+    }
+
+    /// Returns the id of the resource the error concerns.
+    pub fn resource_id(&self) -> Option<&str> {
+        self.context.as_ref()?.resource_id.as_deref()
+    }
+
+    /// Returns the value that caused the error.
+    pub fn value(&self) -> Option<&Value> {
+        self.context.as_ref()?.value.as_ref()
+    }
+
+    /// Sets the attribute path.
+    pub fn with_attribute(mut self, attribute: impl Into<String>) -> Self {
+        self.context_mut().attribute = Some(attribute.into());
+        self
+    }
+
+    /// Sets the resource type.
+    pub fn with_resource_type(mut self, resource_type: impl Into<String>) -> Self {
+        self.context_mut().resource_type = Some(resource_type.into());
+        self
+    }
+
+    /// Sets the resource id.
+    pub fn with_resource_id(mut self, resource_id: impl Into<String>) -> Self {
+        self.context_mut().resource_id = Some(resource_id.into());
+        self
+    }
+
+    /// Sets the offending value.
+    pub fn with_value(mut self, value: Value) -> Self {
+        self.context_mut().value = Some(value);
+        self
+    }
+
+    fn context_mut(&mut self) -> &mut ErrorContext {
+        self.context.get_or_insert_with(Box::default)
+    }
+}
+
+impl Display for ErrorDetail {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Deref for ErrorDetail {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.message
+    }
+}
+
+impl From<String> for ErrorDetail {
+    fn from(message: String) -> Self {
+        ErrorDetail::new(message)
+    }
+}
+
+impl From<&str> for ErrorDetail {
+    fn from(message: &str) -> Self {
+        ErrorDetail::new(message.to_string())
+    }
+}
+
+impl PartialEq<str> for ErrorDetail {
+    fn eq(&self, other: &str) -> bool {
+        self.message == other
+    }
+}
+
+impl PartialEq<&str> for ErrorDetail {
+    fn eq(&self, other: &&str) -> bool {
+        self.message == *other
+    }
+}
+
+impl SCIMError {
+    /// Returns the detail of the error; errors wrapping a serde error or without a message have none.
+    pub fn detail(&self) -> Option<&ErrorDetail> {
+        match self {
+            SCIMError::ConflictError(detail)
+            | SCIMError::Gone(detail)
+            | SCIMError::InvalidFieldValue(detail)
+            | SCIMError::InvalidFilter(detail)
+            | SCIMError::InvalidPath(detail)
+            | SCIMError::MissingRequiredField(detail)
+            | SCIMError::NoTarget(detail)
+            | SCIMError::NotFoundError(detail)
+            | SCIMError::OtherError(detail)
+            | SCIMError::PayloadTooComplex(detail)
+            | SCIMError::PreconditionFailed(detail)
+            | SCIMError::RequestError(detail)
+            | SCIMError::ResourceTypeNotFound(detail)
+            | SCIMError::SchemaNotFound(detail) => Some(detail),
+            SCIMError::DeserializationError(_)
+            | SCIMError::InvalidJsonFormat
+            | SCIMError::SerializationError(_) => None,
+        }
+    }
+
+    fn detail_mut(&mut self) -> Option<&mut ErrorDetail> {
+        match self {
+            SCIMError::ConflictError(detail)
+            | SCIMError::Gone(detail)
+            | SCIMError::InvalidFieldValue(detail)
+            | SCIMError::InvalidFilter(detail)
+            | SCIMError::InvalidPath(detail)
+            | SCIMError::MissingRequiredField(detail)
+            | SCIMError::NoTarget(detail)
+            | SCIMError::NotFoundError(detail)
+            | SCIMError::OtherError(detail)
+            | SCIMError::PayloadTooComplex(detail)
+            | SCIMError::PreconditionFailed(detail)
+            | SCIMError::RequestError(detail)
+            | SCIMError::ResourceTypeNotFound(detail)
+            | SCIMError::SchemaNotFound(detail) => Some(detail),
+            SCIMError::DeserializationError(_)
+            | SCIMError::InvalidJsonFormat
+            | SCIMError::SerializationError(_) => None,
+        }
+    }
+
+    /// Returns the path of the attribute the error concerns, if it is known.
+    pub fn attribute(&self) -> Option<&str> {
+        self.detail()?.attribute()
+    }
+
+    /// Returns the resource type the error concerns, if it is known.
+    pub fn resource_type(&self) -> Option<&str> {
+        self.detail()?.resource_type()
+    }
+
+    /// Returns the id of the resource the error concerns, if it is known.
+    pub fn resource_id(&self) -> Option<&str> {
+        self.detail()?.resource_id()
+    }
+
+    /// Returns the value that caused the error, if it is known.
+    pub fn value(&self) -> Option<&Value> {
+        self.detail()?.value()
+    }
+
+    /// Sets the attribute path of an error that has a detail, e.g. while passing it up from nested
+    /// validation.
+    pub fn with_attribute(self, attribute: impl Into<String>) -> Self {
+        self.map_detail(|detail| detail.with_attribute(attribute))
+    }
+
+    /// Sets the resource type of an error that has a detail.
+    pub fn with_resource_type(self, resource_type: impl Into<String>) -> Self {
+        self.map_detail(|detail| detail.with_resource_type(resource_type))
+    }
+
+    /// Sets the resource id of an error that has a detail.
+    pub fn with_resource_id(self, resource_id: impl Into<String>) -> Self {
+        self.map_detail(|detail| detail.with_resource_id(resource_id))
+    }
+
+    /// Sets the offending value of an error that has a detail.
+    pub fn with_value(self, value: Value) -> Self {
+        self.map_detail(|detail| detail.with_value(value))
+    }
+
+    fn map_detail(mut self, f: impl FnOnce(ErrorDetail) -> ErrorDetail) -> Self {
+        if let Some(detail) = self.detail_mut() {
+            *detail = f(core::mem::take(detail));
+        }
+        self
+    }
+}
+
 impl Display for SCIMError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             SCIMError::ConflictError(msg) => write!(f, "Conflict error: {}", msg),
             SCIMError::DeserializationError(e) => write!(f, "Deserialization error: {}", e),
+            SCIMError::Gone(msg) => write!(f, "Gone: {}", msg),
             SCIMError::InvalidFieldValue(msg) => write!(f, "Invalid field value: {}", msg),
+            SCIMError::InvalidFilter(msg) => write!(f, "Invalid filter: {}", msg),
             SCIMError::InvalidJsonFormat => write!(f, "Invalid JSON format"),
+            SCIMError::InvalidPath(msg) => write!(f, "Invalid path: {}", msg),
             SCIMError::MissingRequiredField(msg) => write!(f, "Missing required field: {}", msg),
+            SCIMError::NoTarget(msg) => write!(f, "No target: {}", msg),
             SCIMError::NotFoundError(msg) => write!(f, "Not found error: {}", msg),
             SCIMError::OtherError(msg) => write!(f, "Other Error: {}", msg),
+            SCIMError::PayloadTooComplex(msg) => write!(f, "Payload too complex: {}", msg),
+            SCIMError::PreconditionFailed(msg) => write!(f, "Precondition failed: {}", msg),
             SCIMError::RequestError(msg) => write!(f, "Request error: {}", msg),
             SCIMError::ResourceTypeNotFound(msg) => write!(f, "Resource type not found: {}", msg),
             SCIMError::SchemaNotFound(msg) => write!(f, "Schema not found: {}", msg),
@@ -40,3 +292,27 @@ impl From<serde_json::Error> for SCIMError {
         SCIMError::DeserializationError(err)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn carries_context_alongside_the_message() {
+        let error = SCIMError::MissingRequiredField("userName".into())
+            .with_attribute("userName")
+            .with_resource_type("User")
+            .with_resource_id("2819c223");
+        assert!(matches!(&error, SCIMError::MissingRequiredField(detail) if *detail == "userName"));
+        assert_eq!(error.attribute(), Some("userName"));
+        assert_eq!(error.resource_id(), Some("2819c223"));
+        assert_eq!(error.value(), None);
+        assert_eq!(error.to_string(), "Missing required field: userName");
+
+        let error = SCIMError::InvalidJsonFormat.with_value(json!(1));
+        assert!(error.detail().is_none());
+        assert_eq!(error.value(), None);
+    }
+}
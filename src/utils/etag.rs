@@ -0,0 +1,360 @@
+use std::io::{self, Write};
+use std::ops::Deref;
+use std::sync::OnceLock;
+
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
+use serde_json::{Map, Value};
+
+use crate::models::resource::ScimResource;
+use crate::utils::error::{ErrorDetail, SCIMError};
+use crate::utils::time::now_rfc3339;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Computes a weak entity tag (`W/"..."`) from the canonical JSON content of a resource.
+///
+/// The canonical content is the resource serialized with object keys in sorted order and without its
+/// `meta` attribute, so that updating timestamps or the version itself does not change the result.
+/// The hash is a 64-bit FNV-1a, which is stable across platforms and compiler versions.
+///
+/// # Errors
+///
+/// Returns `SCIMError::SerializationError` if the resource cannot be serialized.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::user::User;
+/// use scim_v2::utils::etag::content_version;
+///
+/// let user = User {
+///     user_name: "jdoe@example.com".to_string(),
+///     ..Default::default()
+/// };
+/// let version = content_version(&user).unwrap();
+/// assert!(version.starts_with("W/\""));
+/// ```
+pub fn content_version<T: Serialize>(resource: &T) -> Result<String, SCIMError> {
+    let value = serde_json::to_value(resource).map_err(SCIMError::SerializationError)?;
+    content_version_of_value(&value)
+}
+
+/// Computes the same entity tag as [`content_version`] for a resource that is already a JSON value.
+///
+/// Servers that hold resources as `serde_json::Value` can use this to skip converting each resource to a
+/// value again. The canonical form is hashed while it is being written, without building it in memory.
+///
+/// # Errors
+///
+/// Returns `SCIMError::SerializationError` if the value cannot be serialized.
+pub fn content_version_of_value(resource: &Value) -> Result<String, SCIMError> {
+    let mut hasher = Fnv1a(FNV_OFFSET_BASIS);
+    match resource {
+        Value::Object(map) => serde_json::to_writer(&mut hasher, &WithoutMeta(map)),
+        value => serde_json::to_writer(&mut hasher, &Canonical(value)),
+    }
+    .map_err(SCIMError::SerializationError)?;
+    Ok(format!("W/\"{:016x}\"", hasher.0))
+}
+
+/// A resource together with its entity tag, computed on first use and kept until the resource is mutated.
+///
+/// List endpoints that return the same resources over and over would otherwise compute each version anew
+/// on every request.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::user::User;
+/// use scim_v2::utils::etag::{Versioned, content_version};
+///
+/// let mut user = Versioned::new(User { user_name: "bjensen".to_string(), ..Default::default() });
+/// let version = user.version().unwrap().to_string();
+/// assert_eq!(version, content_version(&*user).unwrap());
+///
+/// user.get_mut().user_name = "jsmith".to_string();
+/// assert_ne!(user.version().unwrap(), version);
+/// ```
+#[derive(Debug, Default)]
+pub struct Versioned<T> {
+    resource: T,
+    version: OnceLock<String>,
+}
+
+impl<T: Serialize> Versioned<T> {
+    /// Wraps `resource`; its version is computed when first asked for.
+    pub fn new(resource: T) -> Self {
+        Versioned {
+            resource,
+            version: OnceLock::new(),
+        }
+    }
+
+    /// Returns the entity tag of the resource, computing it with [`content_version`] if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if the resource cannot be serialized.
+    pub fn version(&self) -> Result<&str, SCIMError> {
+        if let Some(version) = self.version.get() {
+            return Ok(version);
+        }
+        let version = content_version(&self.resource)?;
+        Ok(self.version.get_or_init(|| version))
+    }
+
+    /// Returns the resource for modification, discarding the cached version.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.version.take();
+        &mut self.resource
+    }
+
+    /// Returns the resource.
+    pub fn into_inner(self) -> T {
+        self.resource
+    }
+}
+
+/// Optimistic concurrency for resource models: versions are checked before a change and bumped after it.
+///
+/// The current version is the resource's `meta.version`, or its content version if it has none yet.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::group::Group;
+/// use scim_v2::utils::error::SCIMError;
+/// use scim_v2::utils::etag::Versioned;
+///
+/// let mut group = Versioned::new(Group { display_name: "Tour Guides".to_string(), ..Default::default() });
+/// let read = group.version().unwrap().to_string();
+///
+/// group.update(Some(&read), |group| group.display_name = "Guides".to_string()).unwrap();
+/// assert_eq!(group.meta.as_ref().unwrap().version.as_deref(), Some(group.version().unwrap()));
+///
+/// // A second writer still holding the old version is turned away.
+/// let stale = group.update(Some(&read), |group| group.display_name = "Tours".to_string());
+/// assert!(matches!(stale, Err(SCIMError::PreconditionFailed(_))));
+/// assert_eq!(group.display_name, "Guides");
+/// ```
+impl<T: ScimResource> Versioned<T> {
+    /// Returns the current version of the resource.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if the resource has no `meta.version` and cannot be
+    /// serialized.
+    pub fn current_version(&self) -> Result<&str, SCIMError> {
+        match self
+            .resource
+            .meta()
+            .and_then(|meta| meta.version.as_deref())
+        {
+            Some(version) => Ok(version),
+            None => self.version(),
+        }
+    }
+
+    /// Checks the version a client last read, e.g. from an `If-Match` header, against the current version.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::PreconditionFailed` if the versions differ, see [`check_version`].
+    pub fn check(&self, if_match: &str) -> Result<(), SCIMError> {
+        check_version(if_match, self.current_version()?)
+            .map_err(|error| error.with_resource_type(T::RESOURCE_TYPE))
+            .map_err(|error| match self.resource.id() {
+                Some(id) => error.with_resource_id(id),
+                None => error,
+            })
+    }
+
+    /// Applies `change` if `if_match` is `None` or the current version, then bumps the version.
+    ///
+    /// Returns the new version.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::PreconditionFailed` if `if_match` is not the current version, in which case the
+    /// resource is left unchanged, and `SCIMError::SerializationError` if it cannot be serialized.
+    pub fn update(
+        &mut self,
+        if_match: Option<&str>,
+        change: impl FnOnce(&mut T),
+    ) -> Result<&str, SCIMError> {
+        if let Some(if_match) = if_match {
+            self.check(if_match)?;
+        }
+        change(self.get_mut());
+        self.bump()
+    }
+
+    /// Sets `meta.version` to the content version of the resource and `meta.lastModified` to now, after
+    /// the resource was changed through [`Versioned::get_mut`].
+    ///
+    /// Returns the new version.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if the resource cannot be serialized.
+    pub fn bump(&mut self) -> Result<&str, SCIMError> {
+        let version = self.version()?.to_string();
+        let meta = self.resource.meta_mut();
+        meta.version = Some(version);
+        meta.last_modified = Some(now_rfc3339());
+        self.version()
+    }
+}
+
+impl<T> Deref for Versioned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.resource
+    }
+}
+
+/// Returns whether an `If-Match` header value matches the version of a resource.
+///
+/// The header may list several entity tags, separated by commas, or be `*`, which matches any version.
+/// Tags are compared weakly, ignoring the `W/` prefix, as SCIM service providers usually issue weak tags
+/// and clients send them back in `If-Match` unchanged.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::utils::etag::version_matches;
+///
+/// assert!(version_matches(r#""3694e05e9dff594", W/"3694e05e9dff591""#, r#"W/"3694e05e9dff591""#));
+/// assert!(version_matches("*", r#"W/"3694e05e9dff591""#));
+/// assert!(!version_matches(r#"W/"3694e05e9dff590""#, r#"W/"3694e05e9dff591""#));
+/// ```
+pub fn version_matches(if_match: &str, version: &str) -> bool {
+    let opaque = |tag: &str| {
+        let tag = tag.trim();
+        tag.strip_prefix("W/").unwrap_or(tag).to_string()
+    };
+    let version = opaque(version);
+    if_match
+        .split(',')
+        .any(|tag| tag.trim() == "*" || opaque(tag) == version)
+}
+
+/// Checks a version a client sent, e.g. in `If-Match`, against the current version of a resource.
+///
+/// Providers use this before conditional writes, so that every provider reports a lost update the same
+/// way: as `SCIMError::PreconditionFailed`, which servers answer with `412 Precondition Failed`.
+///
+/// # Errors
+///
+/// Returns `SCIMError::PreconditionFailed`, carrying the current version as its value, if
+/// [`version_matches`] is false.
+pub fn check_version(if_match: &str, version: &str) -> Result<(), SCIMError> {
+    if version_matches(if_match, version) {
+        return Ok(());
+    }
+    Err(SCIMError::PreconditionFailed(
+        ErrorDetail::new(format!(
+            "the resource has version {}, not {}",
+            version, if_match
+        ))
+        .with_value(Value::String(version.to_string())),
+    ))
+}
+
+// Serializes an object without its `meta` member. Keys are sorted at every level rather than relying on
+// the order of `Map`, which follows insertion order when serde_json's `preserve_order` feature is enabled
+// anywhere in the dependency graph.
+struct WithoutMeta<'a>(&'a Map<String, Value>);
+
+impl Serialize for WithoutMeta<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_sorted(self.0, |key| key != "meta", serializer)
+    }
+}
+
+// Serializes a value with the keys of its objects in sorted order.
+struct Canonical<'a>(&'a Value);
+
+impl Serialize for Canonical<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            Value::Object(map) => serialize_sorted(map, |_| true, serializer),
+            Value::Array(values) => serializer.collect_seq(values.iter().map(Canonical)),
+            value => value.serialize(serializer),
+        }
+    }
+}
+
+fn serialize_sorted<S: Serializer>(
+    map: &Map<String, Value>,
+    keep: impl Fn(&str) -> bool,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut entries: Vec<(&String, &Value)> = map.iter().filter(|(key, _)| keep(key)).collect();
+    entries.sort_unstable_by_key(|(key, _)| *key);
+    let mut state = serializer.serialize_map(Some(entries.len()))?;
+    for (key, value) in entries {
+        state.serialize_entry(key, &Canonical(value))?;
+    }
+    state.end()
+}
+
+/// Returns the 64-bit FNV-1a hash of `bytes`.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hasher = Fnv1a(FNV_OFFSET_BASIS);
+    let _ = hasher.write(bytes);
+    hasher.0
+}
+
+// A 64-bit FNV-1a hasher fed by writing bytes into it.
+struct Fnv1a(u64);
+
+impl Write for Fnv1a {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.0 = bytes.iter().fold(self.0, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+        });
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn content_version_ignores_meta_and_key_order() {
+        let a = json!({"userName": "bjensen", "active": true, "meta": {"version": "W/\"1\""}});
+        let b = json!({"active": true, "userName": "bjensen"});
+        assert_eq!(content_version(&a).unwrap(), content_version(&b).unwrap());
+    }
+
+    #[test]
+    fn content_version_changes_with_content() {
+        let a = json!({"userName": "bjensen"});
+        let b = json!({"userName": "jsmith"});
+        assert_ne!(content_version(&a).unwrap(), content_version(&b).unwrap());
+    }
+
+    #[test]
+    fn content_version_matches_the_hash_of_the_canonical_bytes() {
+        let user = json!({"userName": "bjensen", "name": {"givenName": "Barbara"}, "meta": {"version": "1"}});
+        let canonical = br#"{"name":{"givenName":"Barbara"},"userName":"bjensen"}"#;
+        let expected = canonical.iter().fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+        });
+        assert_eq!(
+            content_version_of_value(&user).unwrap(),
+            format!("W/\"{:016x}\"", expected)
+        );
+    }
+}
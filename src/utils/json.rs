@@ -0,0 +1,112 @@
+//! The JSON parser behind the `User`, `Group` and `ListResponse` deserialize paths.
+//!
+//! Parsing goes through serde_json unless the `simd-json` feature is enabled, in which case simd-json
+//! parses the input using SIMD instructions where the CPU supports them. Both backends drive the same
+//! serde implementations, so the parsed models are identical.
+
+use alloc::vec::Vec;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::utils::error::SCIMError;
+
+/// Parses a value from a JSON string with the configured backend.
+///
+/// simd-json parses in place, so with the `simd-json` feature the input is first copied into a scratch
+/// buffer; use [`from_slice`] to parse a buffer the caller owns without that copy.
+///
+/// # Errors
+///
+/// Returns `SCIMError::DeserializationError` if the JSON cannot be parsed into `T`.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::user::User;
+/// use scim_v2::utils::json;
+///
+/// let user: User = json::from_str(
+///     r#"{"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "userName": "bjensen"}"#,
+/// )
+/// .unwrap();
+/// assert_eq!(user.user_name, "bjensen");
+/// ```
+pub fn from_str<T: DeserializeOwned>(json: &str) -> Result<T, SCIMError> {
+    #[cfg(feature = "simd-json")]
+    {
+        from_slice(&mut json.as_bytes().to_vec())
+    }
+    #[cfg(not(feature = "simd-json"))]
+    {
+        serde_json::from_str(json).map_err(SCIMError::DeserializationError)
+    }
+}
+
+/// Parses a value from a mutable buffer of JSON with the configured backend.
+///
+/// With the `simd-json` feature the buffer is used as scratch space and its content is unspecified
+/// afterwards; serde_json leaves it untouched.
+///
+/// # Errors
+///
+/// Returns `SCIMError::DeserializationError` if the JSON cannot be parsed into `T`.
+pub fn from_slice<T: DeserializeOwned>(json: &mut [u8]) -> Result<T, SCIMError> {
+    #[cfg(feature = "simd-json")]
+    {
+        use serde::de::Error as _;
+
+        simd_json::serde::from_slice(json)
+            .map_err(|e| SCIMError::DeserializationError(serde_json::Error::custom(e)))
+    }
+    #[cfg(not(feature = "simd-json"))]
+    {
+        serde_json::from_slice(json).map_err(SCIMError::DeserializationError)
+    }
+}
+
+/// Serializes a value as JSON, appending it to `buffer`.
+///
+/// Without the `std` feature there is no `io::Write`, so the JSON is serialized to a new buffer first.
+///
+/// # Errors
+///
+/// Returns `SCIMError::SerializationError` if the value cannot be serialized.
+pub(crate) fn append_to<T: Serialize>(buffer: &mut Vec<u8>, value: &T) -> Result<(), SCIMError> {
+    #[cfg(feature = "std")]
+    {
+        serde_json::to_writer(buffer, value).map_err(SCIMError::SerializationError)
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        let json = serde_json::to_vec(value).map_err(SCIMError::SerializationError)?;
+        buffer.extend_from_slice(&json);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::others::{ListResponse, Resource};
+
+    use super::*;
+
+    #[test]
+    fn parses_list_responses() {
+        let mut body = r#"{
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:ListResponse"],
+            "totalResults": 1, "itemsPerPage": 1, "startIndex": 1,
+            "Resources": [{"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "userName": "béa"}]
+        }"#
+        .as_bytes()
+        .to_vec();
+        let list: ListResponse = from_slice(&mut body).unwrap();
+        assert!(matches!(&list.resources[0], Resource::User(user) if user.user_name == "béa"));
+    }
+
+    #[test]
+    fn reports_malformed_input() {
+        let result: Result<ListResponse, _> = from_str(r#"{"totalResults": "#);
+        assert!(matches!(result, Err(SCIMError::DeserializationError(_))));
+    }
+}
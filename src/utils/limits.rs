@@ -0,0 +1,233 @@
+//! Limits on the shape of untrusted JSON payloads.
+//!
+//! serde_json parses recursively and allocates as it goes, so a small request body can still ask for a
+//! deeply nested or very large structure. [`Limits::check`] scans the raw text without recursing or
+//! allocating per value and rejects payloads over the limits with `SCIMError::PayloadTooComplex` before
+//! they reach the parser.
+
+use alloc::format;
+use alloc::vec::Vec;
+
+use serde::de::DeserializeOwned;
+
+use crate::models::service_provider_config::Bulk;
+use crate::utils::error::SCIMError;
+use crate::utils::json;
+
+/// Limits on the size and nesting of a JSON payload.
+///
+/// The defaults are generous for SCIM resources and list responses: 1 MiB in total (the default bulk
+/// `maxPayloadSize`), 32 levels of nesting, 10,000 elements per array and 64 KiB per string.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::user::User;
+/// use scim_v2::utils::error::SCIMError;
+/// use scim_v2::utils::limits::Limits;
+///
+/// let limits = Limits::default().with_max_depth(3);
+/// let user: User = limits
+///     .parse(r#"{"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "userName": "bjensen"}"#)
+///     .unwrap();
+/// assert_eq!(user.user_name, "bjensen");
+///
+/// let nested = r#"{"userName": "bjensen", "name": {"givenName": [[["Barbara"]]]}}"#;
+/// assert!(matches!(limits.parse::<User>(nested), Err(SCIMError::PayloadTooComplex(_))));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// The maximum length of the payload in bytes.
+    pub max_bytes: usize,
+    /// The maximum nesting of objects and arrays; a flat object has depth 1.
+    pub max_depth: usize,
+    /// The maximum number of elements in one array.
+    pub max_array_len: usize,
+    /// The maximum length of one string, or object key, in bytes as written in the payload.
+    pub max_string_len: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_bytes: 1_048_576,
+            max_depth: 32,
+            max_array_len: 10_000,
+            max_string_len: 65_536,
+        }
+    }
+}
+
+impl Limits {
+    /// The default limits with the bulk `maxPayloadSize` a service provider advertises, for checking bulk
+    /// requests.
+    pub fn for_bulk(bulk: &Bulk) -> Self {
+        Limits::default().with_max_bytes(usize::try_from(bulk.max_payload_size).unwrap_or(0))
+    }
+
+    /// Sets the maximum length of the payload in bytes.
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Sets the maximum nesting depth.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Sets the maximum number of elements per array.
+    pub fn with_max_array_len(mut self, max_array_len: usize) -> Self {
+        self.max_array_len = max_array_len;
+        self
+    }
+
+    /// Sets the maximum length of a string in bytes.
+    pub fn with_max_string_len(mut self, max_string_len: usize) -> Self {
+        self.max_string_len = max_string_len;
+        self
+    }
+
+    /// Checks that a payload is within the limits. The payload does not have to be valid JSON; syntax
+    /// errors are left to the parser.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::PayloadTooComplex` naming the first limit the payload exceeds.
+    pub fn check(&self, json: &str) -> Result<(), SCIMError> {
+        if json.len() > self.max_bytes {
+            return Err(SCIMError::PayloadTooComplex(
+                format!(
+                    "payload of {} bytes exceeds the limit of {} bytes",
+                    json.len(),
+                    self.max_bytes
+                )
+                .into(),
+            ));
+        }
+        // One entry per open object or array: whether it is an array, and its element count.
+        let mut open: Vec<(bool, usize)> = Vec::new();
+        // Whether the next token starts an array element.
+        let mut expect_element = false;
+        let mut bytes = json.bytes();
+        while let Some(byte) = bytes.next() {
+            if byte.is_ascii_whitespace() {
+                continue;
+            }
+            if expect_element && byte != b']' {
+                if let Some((true, count)) = open.last_mut() {
+                    *count += 1;
+                    if *count > self.max_array_len {
+                        return Err(SCIMError::PayloadTooComplex(
+                            format!("array exceeds the limit of {} elements", self.max_array_len)
+                                .into(),
+                        ));
+                    }
+                }
+            }
+            expect_element = false;
+            match byte {
+                b'{' | b'[' => {
+                    open.push((byte == b'[', 0));
+                    expect_element = byte == b'[';
+                    if open.len() > self.max_depth {
+                        return Err(SCIMError::PayloadTooComplex(
+                            format!("nesting exceeds the limit of {} levels", self.max_depth)
+                                .into(),
+                        ));
+                    }
+                }
+                b'}' | b']' => {
+                    open.pop();
+                }
+                b',' => expect_element = matches!(open.last(), Some((true, _))),
+                b'"' => {
+                    let mut len = 0;
+                    while let Some(byte) = bytes.next() {
+                        match byte {
+                            b'"' => break,
+                            b'\\' => {
+                                bytes.next();
+                                len += 2;
+                            }
+                            _ => len += 1,
+                        }
+                    }
+                    if len > self.max_string_len {
+                        return Err(SCIMError::PayloadTooComplex(
+                            format!(
+                                "string of {} bytes exceeds the limit of {} bytes",
+                                len, self.max_string_len
+                            )
+                            .into(),
+                        ));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks a payload with [`Limits::check`] and parses it with the configured JSON backend.
+    ///
+    /// # Errors
+    ///
+    /// * `SCIMError::PayloadTooComplex` - If the payload exceeds a limit.
+    /// * `SCIMError::DeserializationError` - If the JSON cannot be parsed into `T`.
+    pub fn parse<T: DeserializeOwned>(&self, json: &str) -> Result<T, SCIMError> {
+        self.check(json)?;
+        json::from_str(json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use super::*;
+    use crate::models::errors::ScimHttpError;
+
+    #[test]
+    fn rejects_payloads_over_each_limit() {
+        let limits = Limits {
+            max_bytes: 200,
+            max_depth: 3,
+            max_array_len: 3,
+            max_string_len: 8,
+        };
+        assert!(limits.check(r#"{"a": [1, "b\"c", {"d": [1]}]}"#).is_err());
+        assert!(limits.check(r#"{"a": [1, "b\"c", {}]}"#).is_ok());
+        assert!(limits.check(r#"{"a": [1, 2, 3]}"#).is_ok());
+        assert!(limits.check(r#"{"a": [1, 2, 3, []]}"#).is_err());
+        assert!(limits.check(r#"{"a": [[], [], []], "b": []}"#).is_ok());
+        assert!(limits.check(r#"{"key": "12345678"}"#).is_ok());
+        assert!(limits.check(r#"{"key": "123456789"}"#).is_err());
+        assert!(limits.check(r#"{"key": "1234567\n"}"#).is_err());
+        assert!(limits.check(r#"{"[[[[": "]]]]"}"#).is_ok());
+        assert!(limits.check(&" ".repeat(201)).is_err());
+
+        let error = Limits::default()
+            .parse::<Value>(&"[".repeat(10_000))
+            .unwrap_err();
+        assert_eq!(ScimHttpError::from(&error).status, "413");
+    }
+
+    #[test]
+    fn limits_bulk_requests_to_the_advertised_payload_size() {
+        let bulk = Bulk {
+            supported: true,
+            max_operations: 10,
+            max_payload_size: 64,
+        };
+        let request = json!({"Operations": [{"method": "POST", "path": "/Users", "data": {}}]});
+        let limits = Limits::for_bulk(&bulk);
+        assert!(limits.parse::<Value>(&request.to_string()).is_ok());
+        let large = json!({"Operations": [{"method": "POST", "path": "/Users", "data": {"userName": "x".repeat(64)}}]});
+        assert!(matches!(
+            limits.parse::<Value>(&large.to_string()),
+            Err(SCIMError::PayloadTooComplex(_))
+        ));
+    }
+}
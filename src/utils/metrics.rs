@@ -0,0 +1,89 @@
+//! Hooks for reporting operation metrics to Prometheus, statsd and similar systems.
+//!
+//! The crate does not depend on a metrics library. Instead, the pieces that do I/O on behalf of an
+//! application (the [`MeteredProvider`](crate::provider::metered::MeteredProvider) decorator on the server
+//! side, and [`Pipeline`](crate::sync::pipeline::Pipeline) on the client side) report to a [`Metrics`]
+//! implementation, which forwards to whatever library the application uses.
+
+use std::time::Duration;
+
+use crate::models::errors::ScimHttpError;
+use crate::utils::error::SCIMError;
+
+/// Counts operations, labelled with `operation` (`create`, `get`, `replace`, `delete`, `list`, `changes`,
+/// `deleted` or `undelete`) and `resourceType`.
+pub const OPERATIONS: &str = "scim_operations_total";
+/// Observes the duration of operations in seconds, labelled like [`OPERATIONS`].
+pub const OPERATION_SECONDS: &str = "scim_operation_duration_seconds";
+/// Counts failed operations, labelled like [`OPERATIONS`] and with the `status` and `scimType` of the error
+/// response (`scimType` is empty for errors without one).
+pub const ERRORS: &str = "scim_errors_total";
+/// Counts submitted bulk operations, labelled with `method` and `status`.
+pub const BULK_OPERATIONS: &str = "scim_bulk_operations_total";
+
+/// Receives metrics. Implementations must be cheap, as they are called on every operation.
+///
+/// Labels are name/value pairs; the set of label names of a metric is fixed, as Prometheus requires.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::atomic::{AtomicU64, Ordering};
+///
+/// use scim_v2::utils::metrics::{Metrics, OPERATIONS};
+///
+/// #[derive(Default)]
+/// struct Counter(AtomicU64);
+///
+/// impl Metrics for Counter {
+///     fn increment(&self, name: &str, _labels: &[(&str, &str)]) {
+///         if name == OPERATIONS {
+///             self.0.fetch_add(1, Ordering::Relaxed);
+///         }
+///     }
+///
+///     fn observe(&self, _name: &str, _value: f64, _labels: &[(&str, &str)]) {}
+/// }
+/// ```
+pub trait Metrics: Send + Sync {
+    /// Adds one to a counter.
+    fn increment(&self, name: &str, labels: &[(&str, &str)]);
+
+    /// Records a value in a histogram.
+    fn observe(&self, name: &str, value: f64, labels: &[(&str, &str)]);
+}
+
+/// Discards all metrics.
+impl Metrics for () {
+    fn increment(&self, _name: &str, _labels: &[(&str, &str)]) {}
+
+    fn observe(&self, _name: &str, _value: f64, _labels: &[(&str, &str)]) {}
+}
+
+/// Reports one operation: its count, its duration and, if it failed, the error.
+pub fn record_operation<T>(
+    metrics: &dyn Metrics,
+    operation: &str,
+    resource_type: &str,
+    elapsed: Duration,
+    result: &Result<T, SCIMError>,
+) {
+    let labels = [("operation", operation), ("resourceType", resource_type)];
+    metrics.increment(OPERATIONS, &labels);
+    metrics.observe(OPERATION_SECONDS, elapsed.as_secs_f64(), &labels);
+    if let Err(e) = result {
+        let response = ScimHttpError::from(e);
+        metrics.increment(
+            ERRORS,
+            &[
+                ("operation", operation),
+                ("resourceType", resource_type),
+                ("status", &response.status),
+                (
+                    "scimType",
+                    response.scim_type.as_deref().unwrap_or_default(),
+                ),
+            ],
+        );
+    }
+}
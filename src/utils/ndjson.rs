@@ -0,0 +1,393 @@
+//! Streaming import and export of newline-delimited JSON (NDJSON) directory dumps.
+//!
+//! Directory dumps put one resource per line. `NdjsonReader` reads such a dump from a byte stream and
+//! yields its resources one at a time, validated, holding only the line being parsed in memory. A record
+//! that cannot be read or is invalid yields an error carrying its line number, and reading continues with
+//! the next line, so one bad record does not stop the import of millions of good ones. `NdjsonWriter`
+//! writes resources to the output as they are produced, for backups and for handing data to other systems.
+
+use std::io::{self, BufRead, Write};
+use std::marker::PhantomData;
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::models::anonymize::AnonymizationPolicy;
+use crate::models::attribute_policy::{covers, keys, remove};
+use crate::models::resource::ScimResource;
+use crate::utils::error::SCIMError;
+
+/// The default [`NdjsonReader::max_line_bytes`]: 1 MiB.
+pub const DEFAULT_MAX_LINE_BYTES: usize = 1 << 20;
+
+/// Reads resources from newline-delimited JSON, yielding `Result<T, (line_no, SCIMError)>`.
+///
+/// Each non-blank line is parsed as `T` (for example [`crate::models::user::User`] or
+/// [`crate::models::group::Group`]) and validated with [`ScimResource::validate`]. Line numbers start at 1.
+/// Lines longer than [`NdjsonReader::max_line_bytes`] are skipped without being buffered and yield
+/// `SCIMError::PayloadTooComplex`. A read error of the underlying stream yields `SCIMError::OtherError`
+/// and ends the iteration.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::user::User;
+/// use scim_v2::utils::error::SCIMError;
+/// use scim_v2::utils::ndjson::NdjsonReader;
+///
+/// let dump = concat!(
+///     r#"{"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "userName": "bjensen"}"#, "\n",
+///     r#"{"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "userName": ""}"#, "\n",
+///     "\n",
+///     r#"{"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "userName": "jsmith""#, "\n",
+///     r#"{"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "userName": "ajones"}"#, "\n",
+/// );
+/// let mut names = Vec::new();
+/// let mut failed = Vec::new();
+/// for record in NdjsonReader::<_, User>::new(dump.as_bytes()) {
+///     match record {
+///         Ok(user) => names.push(user.user_name),
+///         Err((line, _)) => failed.push(line),
+///     }
+/// }
+/// assert_eq!(names, ["bjensen", "ajones"]);
+/// assert_eq!(failed, [2, 4]);
+/// ```
+pub struct NdjsonReader<R, T> {
+    reader: R,
+    line: usize,
+    max_line_bytes: usize,
+    buffer: Vec<u8>,
+    done: bool,
+    resource: PhantomData<fn() -> T>,
+}
+
+impl<R: BufRead, T: ScimResource> NdjsonReader<R, T> {
+    /// Creates a reader over `reader`; wrap unbuffered sources in a `std::io::BufReader`.
+    pub fn new(reader: R) -> Self {
+        NdjsonReader {
+            reader,
+            line: 0,
+            max_line_bytes: DEFAULT_MAX_LINE_BYTES,
+            buffer: Vec::new(),
+            done: false,
+            resource: PhantomData,
+        }
+    }
+
+    /// Sets the length of the longest line that is parsed, which bounds the memory the reader uses.
+    pub fn max_line_bytes(mut self, max_line_bytes: usize) -> Self {
+        self.max_line_bytes = max_line_bytes;
+        self
+    }
+
+    /// Returns the number of the line read last.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    // Reads the next line into the buffer, without its line ending. Returns `None` at the end of the
+    // stream, and whether the line was too long to keep otherwise.
+    fn read_line(&mut self) -> io::Result<Option<bool>> {
+        self.buffer.clear();
+        let mut too_long = false;
+        let mut read_any = false;
+        loop {
+            let available = self.reader.fill_buf()?;
+            if available.is_empty() {
+                break;
+            }
+            read_any = true;
+            let (chunk, end_of_line) = match available.iter().position(|b| *b == b'\n') {
+                Some(end) => (&available[..end], true),
+                None => (available, false),
+            };
+            if too_long || self.buffer.len() + chunk.len() > self.max_line_bytes {
+                too_long = true;
+                self.buffer.clear();
+            } else {
+                self.buffer.extend_from_slice(chunk);
+            }
+            let consumed = chunk.len() + usize::from(end_of_line);
+            self.reader.consume(consumed);
+            if end_of_line {
+                break;
+            }
+        }
+        if !read_any {
+            return Ok(None);
+        }
+        if self.buffer.last() == Some(&b'\r') {
+            self.buffer.pop();
+        }
+        Ok(Some(too_long))
+    }
+
+    fn parse(&self) -> Result<T, SCIMError> {
+        let bytes = match self.line {
+            1 => self
+                .buffer
+                .strip_prefix(b"\xef\xbb\xbf")
+                .unwrap_or(&self.buffer),
+            _ => &self.buffer,
+        };
+        let value = serde_json::from_slice(bytes).map_err(SCIMError::DeserializationError)?;
+        let resource = T::from_value(value)?;
+        resource.validate()?;
+        Ok(resource)
+    }
+}
+
+impl<R: BufRead, T: ScimResource> Iterator for NdjsonReader<R, T> {
+    type Item = Result<T, (usize, SCIMError)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let too_long = match self.read_line() {
+                Ok(Some(too_long)) => too_long,
+                Ok(None) => {
+                    self.done = true;
+                    return None;
+                }
+                Err(error) => {
+                    self.done = true;
+                    let error = SCIMError::OtherError(format!("read error: {}", error).into());
+                    return Some(Err((self.line + 1, error)));
+                }
+            };
+            self.line += 1;
+            if too_long {
+                let error = SCIMError::PayloadTooComplex(
+                    format!("the line is longer than {} bytes", self.max_line_bytes).into(),
+                );
+                return Some(Err((self.line, error)));
+            }
+            if self.buffer.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            return Some(self.parse().map_err(|error| (self.line, error)));
+        }
+        None
+    }
+}
+
+/// Writes resources as newline-delimited JSON, one resource per line.
+///
+/// Before a resource is written, it is projected to [`NdjsonWriter::attributes`] if they are set, stripped
+/// of [`NdjsonWriter::excluded_attributes`], and anonymized with [`NdjsonWriter::anonymize`] if a policy is
+/// set. Paths are named as in an [`AttributePolicy`](crate::models::attribute_policy::AttributePolicy);
+/// `id` and `schemas` are always written.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::anonymize::AnonymizationPolicy;
+/// use scim_v2::models::user::User;
+/// use scim_v2::utils::ndjson::NdjsonWriter;
+///
+/// let user = User::try_from(
+///     r#"{
+///         "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+///         "id": "2819c223",
+///         "userName": "bjensen",
+///         "title": "Tour Guide",
+///         "emails": [{"value": "bjensen@example.com", "type": "work"}]
+///     }"#,
+/// )
+/// .unwrap();
+/// let mut writer = NdjsonWriter::new(Vec::new())
+///     .attributes(["userName", "emails.value"])
+///     .anonymize(AnonymizationPolicy::new(b"key").mask("emails"));
+/// writer.write(&user).unwrap();
+/// writer.write(&user).unwrap();
+/// let dump = String::from_utf8(writer.finish().unwrap()).unwrap();
+///
+/// let line: serde_json::Value = serde_json::from_str(dump.lines().next().unwrap()).unwrap();
+/// assert_eq!(dump.lines().count(), 2);
+/// assert_eq!(line["id"], "2819c223");
+/// assert!(line.get("title").is_none());
+/// assert_eq!(line["emails"], serde_json::json!([{"value": "b******@example.com"}]));
+/// ```
+pub struct NdjsonWriter<W: Write> {
+    writer: W,
+    attributes: Vec<String>,
+    excluded_attributes: Vec<String>,
+    anonymization: Option<AnonymizationPolicy>,
+    written: usize,
+}
+
+impl<W: Write> NdjsonWriter<W> {
+    /// Creates a writer that writes resources to `writer` as they are; wrap unbuffered destinations in a
+    /// `std::io::BufWriter`.
+    pub fn new(writer: W) -> Self {
+        NdjsonWriter {
+            writer,
+            attributes: Vec::new(),
+            excluded_attributes: Vec::new(),
+            anonymization: None,
+            written: 0,
+        }
+    }
+
+    /// Writes only the attributes at `paths`, and the attributes below them.
+    pub fn attributes<I, S>(mut self, paths: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.attributes = paths.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Leaves out the attributes at `paths`.
+    pub fn excluded_attributes<I, S>(mut self, paths: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.excluded_attributes = paths.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Anonymizes resources with `policy` before they are written.
+    pub fn anonymize(mut self, policy: AnonymizationPolicy) -> Self {
+        self.anonymization = Some(policy);
+        self
+    }
+
+    /// Returns the number of resources written so far.
+    pub fn written(&self) -> usize {
+        self.written
+    }
+
+    /// Writes one resource.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if the resource cannot be serialized or writing fails.
+    pub fn write<T: Serialize>(&mut self, resource: &T) -> Result<(), SCIMError> {
+        let mut value = serde_json::to_value(resource).map_err(SCIMError::SerializationError)?;
+        if !self.attributes.is_empty() {
+            if let Value::Object(object) = &mut value {
+                project(object, "", ':', &self.attributes);
+            }
+        }
+        for path in &self.excluded_attributes {
+            let keys = keys(&value, path);
+            remove(&mut value, &keys);
+        }
+        if let Some(policy) = &self.anonymization {
+            policy.apply(&mut value);
+        }
+        serde_json::to_writer(&mut self.writer, &value).map_err(SCIMError::SerializationError)?;
+        self.writer.write_all(b"\n").map_err(io_error)?;
+        self.written += 1;
+        Ok(())
+    }
+
+    /// Flushes the output and returns the underlying writer.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if flushing fails.
+    pub fn finish(mut self) -> Result<W, SCIMError> {
+        self.writer.flush().map_err(io_error)?;
+        Ok(self.writer)
+    }
+}
+
+// Keeps the members of `object`, found at `prefix`, that are at or above one of `paths`. Members of the
+// resource are joined to a prefix with `:` if the prefix is an extension URI, and with `.` otherwise.
+fn project(object: &mut Map<String, Value>, prefix: &str, separator: char, paths: &[String]) {
+    object.retain(|key, child| {
+        let path = match prefix {
+            "" if key == "id" || key == "schemas" => return true,
+            "" => key.clone(),
+            prefix => format!("{}{}{}", prefix, separator, key),
+        };
+        if paths.iter().any(|p| covers(p, &path)) {
+            return true;
+        }
+        if !paths.iter().any(|p| covers(&path, p)) {
+            return false;
+        }
+        let separator = if prefix.is_empty() && key.starts_with("urn:") {
+            ':'
+        } else {
+            '.'
+        };
+        match child {
+            Value::Object(child) => project(child, &path, separator, paths),
+            Value::Array(elements) => {
+                for element in elements.iter_mut() {
+                    if let Value::Object(element) = element {
+                        project(element, &path, separator, paths);
+                    }
+                }
+            }
+            _ => {}
+        }
+        true
+    });
+}
+
+fn io_error(error: io::Error) -> SCIMError {
+    SCIMError::SerializationError(serde_json::Error::io(error))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+
+    use super::*;
+    use crate::models::group::Group;
+
+    #[test]
+    fn skips_long_lines_and_numbers_records() {
+        let dump = format!(
+            "\u{feff}{}\r\n{{\"schemas\": [\"urn:ietf:params:scim:schemas:core:2.0:Group\"], \"displayName\": \"{}\"}}\n{}",
+            r#"{"schemas": ["urn:ietf:params:scim:schemas:core:2.0:Group"], "displayName": "Tour Guides"}"#,
+            "x".repeat(200),
+            r#"{"schemas": ["urn:ietf:params:scim:schemas:core:2.0:Group"], "displayName": "Admins"}"#,
+        );
+        // A small buffer makes lines span several reads.
+        let reader = BufReader::with_capacity(16, dump.as_bytes());
+        let records: Vec<_> = NdjsonReader::<_, Group>::new(reader)
+            .max_line_bytes(128)
+            .collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].as_ref().unwrap().display_name, "Tour Guides");
+        assert!(matches!(
+            records[1],
+            Err((2, SCIMError::PayloadTooComplex(_)))
+        ));
+        assert_eq!(records[2].as_ref().unwrap().display_name, "Admins");
+    }
+
+    #[test]
+    fn projects_into_extensions() {
+        let enterprise = "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User";
+        let user = serde_json::json!({
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User", enterprise],
+            "userName": "bjensen",
+            "name": {"givenName": "Barbara", "familyName": "Jensen"},
+            enterprise: {"employeeNumber": "701984", "manager": {"value": "26118915", "displayName": "John"}}
+        });
+        let mut writer = NdjsonWriter::new(Vec::new())
+            .attributes([
+                "name.familyName".to_string(),
+                format!("{}:manager", enterprise),
+            ])
+            .excluded_attributes([format!("{}:manager.displayName", enterprise)]);
+        writer.write(&user).unwrap();
+        let line: Value = serde_json::from_slice(&writer.finish().unwrap()).unwrap();
+        assert_eq!(
+            line,
+            serde_json::json!({
+                "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User", enterprise],
+                "name": {"familyName": "Jensen"},
+                enterprise: {"manager": {"value": "26118915"}}
+            })
+        );
+    }
+}
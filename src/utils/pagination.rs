@@ -0,0 +1,325 @@
+//! Pagination by `startIndex` and by cursor behind one type.
+//!
+//! A [`PageRequest`] names the page to fetch either by its 1-based index (RFC 7644, section 3.4.2.4) or by a
+//! cursor (see [`crate::utils::cursor`]), and a [`PageResponse`] carries a page together with the requests
+//! for the pages around it, in the same strategy. Code that only passes requests on, such as [`Pages`] on
+//! the client or [`Page::into_response`](crate::provider::resource_provider::Page::into_response) on the
+//! server, works the same whichever strategy the first request uses.
+
+use std::collections::HashSet;
+
+use crate::models::others::{ListQuery, ListResponse, Resource};
+use crate::utils::error::SCIMError;
+
+/// How a list is paginated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PageStrategy {
+    /// By `startIndex`.
+    Index,
+    /// By `cursor`.
+    Cursor,
+}
+
+/// The page of a list to fetch.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PageRequest {
+    /// The page starting at the 1-based `start_index`.
+    Index {
+        start_index: i64,
+        count: Option<i64>,
+    },
+    /// The page `cursor` points to; the empty cursor points to the first page.
+    Cursor { cursor: String, count: Option<i64> },
+}
+
+impl PageRequest {
+    /// Returns the request for the first page of at most `count` resources.
+    pub fn first(strategy: PageStrategy, count: Option<i64>) -> Self {
+        match strategy {
+            PageStrategy::Index => PageRequest::Index {
+                start_index: 1,
+                count,
+            },
+            PageStrategy::Cursor => PageRequest::Cursor {
+                cursor: String::new(),
+                count,
+            },
+        }
+    }
+
+    /// Returns the page a query asks for. A `cursor` takes precedence over `startIndex`, and a `startIndex`
+    /// below 1 is interpreted as 1.
+    pub fn of(query: &ListQuery) -> Self {
+        match &query.cursor {
+            Some(cursor) => PageRequest::Cursor {
+                cursor: cursor.clone(),
+                count: query.count,
+            },
+            None => PageRequest::Index {
+                start_index: query.start_index.unwrap_or(1).max(1),
+                count: query.count,
+            },
+        }
+    }
+
+    /// Returns the strategy of the request.
+    pub fn strategy(&self) -> PageStrategy {
+        match self {
+            PageRequest::Index { .. } => PageStrategy::Index,
+            PageRequest::Cursor { .. } => PageStrategy::Cursor,
+        }
+    }
+
+    /// Returns the maximum number of resources on the page; `None` leaves it to the service provider.
+    pub fn count(&self) -> Option<i64> {
+        match self {
+            PageRequest::Index { count, .. } | PageRequest::Cursor { count, .. } => *count,
+        }
+    }
+
+    /// Returns `query` asking for this page.
+    pub fn apply(&self, query: &ListQuery) -> ListQuery {
+        let (start_index, cursor) = match self {
+            PageRequest::Index { start_index, .. } => (Some(*start_index), None),
+            PageRequest::Cursor { cursor, .. } => (None, Some(cursor.clone())),
+        };
+        ListQuery {
+            start_index,
+            count: self.count(),
+            cursor,
+            ..query.clone()
+        }
+    }
+}
+
+impl Default for PageRequest {
+    /// The first page by index, with the page size left to the service provider.
+    fn default() -> Self {
+        PageRequest::first(PageStrategy::Index, None)
+    }
+}
+
+/// One page of a list, with the requests for the pages around it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageResponse<T> {
+    /// The resources on this page.
+    pub resources: Vec<T>,
+    /// The number of resources in the list, across all pages.
+    pub total_results: i64,
+    /// The request for the next page, if there are more resources.
+    pub next: Option<PageRequest>,
+    /// The request for the previous page, if this is not the first page.
+    pub previous: Option<PageRequest>,
+}
+
+impl<T> PageResponse<T> {
+    /// Assembles the answer to `request`: the page starting at the 1-based `start_index`, which is only
+    /// used for requests by index, and the cursors of the pages around it, which are only used for requests
+    /// by cursor.
+    pub fn new(
+        request: &PageRequest,
+        resources: Vec<T>,
+        total_results: i64,
+        start_index: i64,
+        next_cursor: Option<&str>,
+        previous_cursor: Option<&str>,
+    ) -> Self {
+        let count = request.count();
+        let (next, previous) = match request {
+            PageRequest::Index { .. } => {
+                let end = start_index - 1 + resources.len() as i64;
+                let next =
+                    (!resources.is_empty() && end < total_results).then_some(PageRequest::Index {
+                        start_index: end + 1,
+                        count,
+                    });
+                let size = count.unwrap_or(resources.len() as i64).max(1);
+                let previous = (start_index > 1).then_some(PageRequest::Index {
+                    start_index: (start_index - size).max(1),
+                    count,
+                });
+                (next, previous)
+            }
+            PageRequest::Cursor { .. } => {
+                let to_request = |cursor: Option<&str>| {
+                    cursor
+                        .filter(|cursor| !cursor.is_empty())
+                        .map(|cursor| PageRequest::Cursor {
+                            cursor: cursor.to_string(),
+                            count,
+                        })
+                };
+                (to_request(next_cursor), to_request(previous_cursor))
+            }
+        };
+        PageResponse {
+            resources,
+            total_results,
+            next,
+            previous,
+        }
+    }
+}
+
+impl PageResponse<Resource> {
+    /// Interprets the list response a service provider sent for `request`.
+    pub fn from_list_response(request: &PageRequest, response: ListResponse) -> Self {
+        PageResponse::new(
+            request,
+            response.resources,
+            response.total_results,
+            response.start_index,
+            response.next_cursor.as_deref(),
+            response.previous_cursor.as_deref(),
+        )
+    }
+}
+
+// Tracks the request for the next page while following a list, refusing cursors handed out twice.
+#[derive(Debug, Default)]
+pub(crate) struct Follow {
+    next: Option<PageRequest>,
+    seen: HashSet<String>,
+}
+
+impl Follow {
+    pub(crate) fn new(first: PageRequest) -> Self {
+        Follow {
+            next: Some(first),
+            seen: HashSet::new(),
+        }
+    }
+
+    // Takes the request for the next page, if there is one; the iteration ends unless `advance` follows.
+    pub(crate) fn take(&mut self) -> Option<PageRequest> {
+        self.next.take()
+    }
+
+    // Continues with `next`, the next page named by the page just fetched.
+    pub(crate) fn advance(&mut self, next: Option<PageRequest>) -> Result<(), SCIMError> {
+        if let Some(PageRequest::Cursor { cursor, .. }) = &next {
+            if !self.seen.insert(cursor.clone()) {
+                return Err(SCIMError::RequestError(
+                    format!("cursor '{}' was returned twice", cursor).into(),
+                ));
+            }
+        }
+        self.next = next;
+        Ok(())
+    }
+}
+
+/// Iterates over the pages of a list, by index or by cursor, until there is no next page.
+///
+/// `fetch` is called with the request for each page, starting with `first`, and returns the page; the
+/// iteration follows [`PageResponse::next`], so it does not depend on the strategy of `first`. It ends after
+/// the first error, and fails if the service provider hands out a cursor a second time.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::others::ListQuery;
+/// use scim_v2::provider::memory::MemoryStore;
+/// use scim_v2::provider::resource_provider::ResourceProvider;
+/// use scim_v2::utils::pagination::{PageRequest, PageStrategy, Pages};
+/// use serde_json::json;
+///
+/// let store = MemoryStore::new("https://example.com/v2");
+/// for user_name in ["bjensen", "jsmith", "mdoe"] {
+///     let user = json!({
+///         "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+///         "userName": user_name,
+///     });
+///     store.create("User", user).unwrap();
+/// }
+///
+/// let query = ListQuery::default();
+/// for strategy in [PageStrategy::Index, PageStrategy::Cursor] {
+///     let pages = Pages::new(PageRequest::first(strategy, Some(2)), |request: &PageRequest| {
+///         Ok(store.list("User", &request.apply(&query))?.into_response(request))
+///     });
+///     let sizes: Vec<usize> = pages.map(|page| page.unwrap().resources.len()).collect();
+///     assert_eq!(sizes, [2, 1]);
+/// }
+/// ```
+pub struct Pages<F> {
+    fetch: F,
+    follow: Follow,
+}
+
+impl<F, T> Pages<F>
+where
+    F: FnMut(&PageRequest) -> Result<PageResponse<T>, SCIMError>,
+{
+    /// Starts at `first`.
+    pub fn new(first: PageRequest, fetch: F) -> Self {
+        Pages {
+            fetch,
+            follow: Follow::new(first),
+        }
+    }
+}
+
+impl<F, T> Iterator for Pages<F>
+where
+    F: FnMut(&PageRequest) -> Result<PageResponse<T>, SCIMError>,
+{
+    type Item = Result<PageResponse<T>, SCIMError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let request = self.follow.take()?;
+        let page = match (self.fetch)(&request) {
+            Ok(page) => page,
+            Err(e) => return Some(Err(e)),
+        };
+        if let Err(e) = self.follow.advance(page.next.clone()) {
+            return Some(Err(e));
+        }
+        Some(Ok(page))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_the_pages_around_a_page_in_its_strategy() {
+        let by_index = PageRequest::Index {
+            start_index: 3,
+            count: Some(2),
+        };
+        let page = PageResponse::new(&by_index, vec![3, 4], 5, 3, Some("ignored"), None);
+        assert_eq!(
+            page.next,
+            Some(PageRequest::Index {
+                start_index: 5,
+                count: Some(2)
+            })
+        );
+        assert_eq!(
+            page.previous,
+            Some(PageRequest::Index {
+                start_index: 1,
+                count: Some(2)
+            })
+        );
+        let last = PageResponse::new(&by_index, vec![5], 5, 5, None, None);
+        assert_eq!(last.next, None);
+
+        let by_cursor = PageRequest::first(PageStrategy::Cursor, Some(2));
+        let page = PageResponse::new(&by_cursor, vec![1, 2], 5, 1, Some("c2"), Some(""));
+        assert_eq!(
+            page.next,
+            Some(PageRequest::Cursor {
+                cursor: "c2".to_string(),
+                count: Some(2)
+            })
+        );
+        assert_eq!(page.previous, None);
+
+        let query = by_cursor.apply(&ListQuery::default());
+        assert_eq!(query.start_index, None);
+        assert_eq!(PageRequest::of(&query), by_cursor);
+    }
+}
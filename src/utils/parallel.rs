@@ -0,0 +1,103 @@
+//! Parallel validation of large batches of resources.
+//!
+//! Validating the records of an import is independent per record, so these helpers spread the work over
+//! the rayon thread pool. Each returns the failures together with the index of the failing record, in the
+//! order of the input; an empty result means every record is valid.
+//!
+//! The crate has no bulk executor, so there is no parallel validation phase to switch on; a server that
+//! processes `BulkRequest`s can validate their `data` members with [`validate_resources_parallel`] before
+//! applying them.
+
+use rayon::prelude::*;
+use serde_json::Value;
+
+use crate::models::group::Group;
+use crate::models::schema_registry::SchemaRegistry;
+use crate::models::user::User;
+use crate::utils::error::SCIMError;
+
+/// Validates users in parallel with [`User::validate`].
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::user::User;
+/// use scim_v2::utils::parallel::validate_users_parallel;
+///
+/// let users: Vec<User> = (0..1000)
+///     .map(|i| User { user_name: if i == 500 { String::new() } else { format!("user{}", i) }, ..Default::default() })
+///     .collect();
+/// let failures = validate_users_parallel(&users);
+/// assert_eq!(failures.len(), 1);
+/// assert_eq!(failures[0].0, 500);
+/// ```
+pub fn validate_users_parallel(users: &[User]) -> Vec<(usize, SCIMError)> {
+    failures(users, User::validate)
+}
+
+/// Validates groups in parallel with [`Group::validate`].
+pub fn validate_groups_parallel(groups: &[Group]) -> Vec<(usize, SCIMError)> {
+    failures(groups, Group::validate)
+}
+
+/// Validates resource representations in parallel against a resource type registered in `registry`, with
+/// [`SchemaRegistry::validate_resource`].
+pub fn validate_resources_parallel(
+    registry: &SchemaRegistry,
+    resource_type: &str,
+    resources: &[Value],
+) -> Vec<(usize, SCIMError)> {
+    failures(resources, |resource| {
+        registry.validate_resource(resource_type, resource)
+    })
+}
+
+fn failures<T: Sync>(
+    records: &[T],
+    validate: impl Fn(&T) -> Result<(), SCIMError> + Sync,
+) -> Vec<(usize, SCIMError)> {
+    records
+        .par_iter()
+        .enumerate()
+        .filter_map(|(index, record)| validate(record).err().map(|e| (index, e)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn reports_failures_in_input_order() {
+        let resources: Vec<Value> = (0..500)
+            .map(|i| match i % 100 {
+                7 => json!({"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"]}),
+                _ => json!({
+                    "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+                    "userName": format!("user{}", i)
+                }),
+            })
+            .collect();
+        let registry = SchemaRegistry::with_builtins();
+        let failures = validate_resources_parallel(&registry, "User", &resources);
+        let indices: Vec<usize> = failures.iter().map(|(index, _)| *index).collect();
+        assert_eq!(indices, [7, 107, 207, 307, 407]);
+        assert!(matches!(failures[0].1, SCIMError::MissingRequiredField(_)));
+    }
+
+    #[test]
+    fn validates_groups() {
+        let groups = vec![
+            Group::default(),
+            Group {
+                display_name: String::new(),
+                ..Default::default()
+            },
+        ];
+        let failures = validate_groups_parallel(&groups);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0, 1);
+    }
+}
@@ -0,0 +1,221 @@
+//! Token-bucket rate limiting, for throttling outbound provisioning and for answering `429 Too Many
+//! Requests` on the server.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::models::context::RequestContext;
+use crate::models::errors::ScimHttpError;
+
+/// A sustained request rate with an allowance for bursts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    /// Requests allowed per second, on average.
+    pub per_second: f64,
+    /// Requests allowed at once after a quiet period. At least 1.
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// Allows `per_second` requests per second, in bursts of up to one second's worth.
+    pub fn per_second(per_second: f64) -> Self {
+        RateLimit {
+            per_second,
+            burst: (per_second.ceil() as u32).max(1),
+        }
+    }
+
+    /// Allows `per_minute` requests per minute, in bursts of up to one second's worth.
+    pub fn per_minute(per_minute: f64) -> Self {
+        RateLimit::per_second(per_minute / 60.0)
+    }
+
+    /// Sets the burst size.
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+}
+
+/// A request refused by a [`RateLimiter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Throttled {
+    /// How long until the request would be allowed.
+    pub retry_after: Duration,
+}
+
+impl Throttled {
+    /// Returns the value of the `Retry-After` header: whole seconds, rounded up.
+    pub fn retry_after_header(&self) -> String {
+        let seconds = self.retry_after.as_secs() + u64::from(self.retry_after.subsec_nanos() > 0);
+        seconds.max(1).to_string()
+    }
+
+    /// Returns the SCIM error response with status 429, to send along with the `Retry-After` header.
+    pub fn error_response(&self) -> ScimHttpError {
+        ScimHttpError {
+            detail: Some(self.to_string()),
+            status: "429".to_string(),
+            ..ScimHttpError::default()
+        }
+    }
+}
+
+impl Display for Throttled {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Too many requests; retry after {} seconds",
+            self.retry_after_header()
+        )
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token buckets, one per tenant.
+///
+/// Each tenant's bucket holds up to `burst` tokens and refills at `per_second` tokens per second; a request
+/// takes one token. The limit of a request is the [`RequestContext::rate_limit`] of its context, so a server
+/// can give each tenant its own limit when it builds the context, and the limiter's default otherwise.
+/// Requests without a tenant share one bucket.
+///
+/// Servers call [`RateLimiter::try_acquire`] and answer `429` when it fails; clients call
+/// [`RateLimiter::acquire`], which waits for a token, before each request they send.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::context::RequestContext;
+/// use scim_v2::utils::rate_limit::{RateLimit, RateLimiter};
+///
+/// let limiter = RateLimiter::new(RateLimit::per_minute(60.0));
+/// let acme = RequestContext::default().with_tenant("acme");
+/// assert!(limiter.try_acquire(&acme).is_ok());
+///
+/// let throttled = limiter.try_acquire(&acme).unwrap_err();
+/// assert_eq!(throttled.retry_after_header(), "1");
+/// assert_eq!(throttled.error_response().status, "429");
+///
+/// let premium = RequestContext::default()
+///     .with_tenant("globex")
+///     .with_rate_limit(RateLimit::per_second(100.0));
+/// assert!((0..100).all(|_| limiter.try_acquire(&premium).is_ok()));
+/// ```
+#[derive(Debug)]
+pub struct RateLimiter {
+    default: RateLimit,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    /// Creates a limiter applying `default` to contexts without a limit of their own.
+    pub fn new(default: RateLimit) -> Self {
+        RateLimiter {
+            default,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Takes a token for a request in `context`, or reports how long until one is available.
+    pub fn try_acquire(&self, context: &RequestContext) -> Result<(), Throttled> {
+        self.try_acquire_at(context, Instant::now())
+    }
+
+    /// Takes a token for a request in `context`, waiting for one if needed.
+    pub fn acquire(&self, context: &RequestContext) {
+        while let Err(throttled) = self.try_acquire(context) {
+            thread::sleep(throttled.retry_after);
+        }
+    }
+
+    fn try_acquire_at(&self, context: &RequestContext, now: Instant) -> Result<(), Throttled> {
+        let limit = context.rate_limit.unwrap_or(self.default);
+        let burst = f64::from(limit.burst.max(1));
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+        let bucket = buckets
+            .entry(context.tenant_id.clone().unwrap_or_default())
+            .or_insert(Bucket {
+                tokens: burst,
+                updated: now,
+            });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * limit.per_second).min(burst);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+        let missing = 1.0 - bucket.tokens;
+        let retry_after = if limit.per_second > 0.0 {
+            Duration::try_from_secs_f64(missing / limit.per_second).unwrap_or(Duration::MAX)
+        } else {
+            Duration::MAX
+        };
+        Err(Throttled { retry_after })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refills_over_time_per_tenant() {
+        let limiter = RateLimiter::new(RateLimit::per_second(2.0).with_burst(3));
+        let acme = RequestContext::default().with_tenant("acme");
+        let start = Instant::now();
+        for _ in 0..3 {
+            assert!(limiter.try_acquire_at(&acme, start).is_ok());
+        }
+        let throttled = limiter.try_acquire_at(&acme, start).unwrap_err();
+        assert_eq!(throttled.retry_after, Duration::from_millis(500));
+        assert!(
+            limiter
+                .try_acquire_at(&RequestContext::default(), start)
+                .is_ok()
+        );
+
+        let later = start + Duration::from_millis(500);
+        assert!(limiter.try_acquire_at(&acme, later).is_ok());
+        assert!(limiter.try_acquire_at(&acme, later).is_err());
+        let much_later = start + Duration::from_secs(60);
+        for _ in 0..3 {
+            assert!(limiter.try_acquire_at(&acme, much_later).is_ok());
+        }
+        assert!(limiter.try_acquire_at(&acme, much_later).is_err());
+    }
+
+    #[test]
+    fn reports_retry_after_in_whole_seconds() {
+        let throttled = Throttled {
+            retry_after: Duration::from_millis(1500),
+        };
+        assert_eq!(throttled.retry_after_header(), "2");
+        let response = throttled.error_response();
+        assert_eq!(response.status, "429");
+        assert_eq!(
+            response.detail.as_deref(),
+            Some("Too many requests; retry after 2 seconds")
+        );
+
+        let closed = RateLimiter::new(RateLimit {
+            per_second: 0.0,
+            burst: 1,
+        });
+        let context = RequestContext::default();
+        assert!(closed.try_acquire(&context).is_ok());
+        assert_eq!(
+            closed.try_acquire(&context).unwrap_err().retry_after,
+            Duration::MAX
+        );
+    }
+}
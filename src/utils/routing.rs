@@ -0,0 +1,159 @@
+use crate::models::schema_registry::SchemaRegistry;
+
+/// An endpoint exposed for a registered resource type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    /// The endpoint path relative to the base URL, e.g. `/Users`.
+    pub endpoint: String,
+    /// The base schema URI of the resource type.
+    pub schema: String,
+    /// The schema extension URIs of the resource type.
+    pub extensions: Vec<String>,
+    /// The resource type name, which web framework integrations use as the key to pick a handler.
+    pub handler: String,
+}
+
+/// What a request path addresses within a [`Route`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteTarget<'a> {
+    /// The endpoint itself, e.g. `GET /Users` or `POST /Users`.
+    Collection,
+    /// A single resource, e.g. `GET /Users/2819c223`.
+    Resource(&'a str),
+    /// The `.search` sub-path used for `POST` searches, e.g. `POST /Users/.search`.
+    Search,
+}
+
+/// A request path resolved by [`RoutingTable::resolve`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RouteMatch<'a> {
+    /// The route the path belongs to.
+    pub route: &'a Route,
+    /// The part of the route the path addresses.
+    pub target: RouteTarget<'a>,
+}
+
+/// The endpoints of every resource type in a [`SchemaRegistry`].
+///
+/// Web framework integrations build one of these at start-up and mount a handler per route, so registering a
+/// custom resource type is enough to expose its endpoint. The table can also resolve incoming request paths
+/// for frameworks that dispatch through a single catch-all handler.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::schema_registry::SchemaRegistry;
+/// use scim_v2::utils::routing::{RouteTarget, RoutingTable};
+///
+/// let table = RoutingTable::from_registry(&SchemaRegistry::with_builtins());
+/// assert_eq!(table.routes().len(), 2);
+///
+/// let matched = table.resolve("/Users/2819c223").unwrap();
+/// assert_eq!(matched.route.handler, "User");
+/// assert_eq!(matched.target, RouteTarget::Resource("2819c223"));
+/// assert!(table.resolve("/Devices").is_none());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RoutingTable {
+    routes: Vec<Route>,
+}
+
+impl RoutingTable {
+    /// Builds the routing table for all resource types registered with `registry`.
+    pub fn from_registry(registry: &SchemaRegistry) -> Self {
+        let routes = registry
+            .resource_types()
+            .iter()
+            .map(|resource_type| Route {
+                endpoint: format!("/{}", resource_type.endpoint.trim_matches('/')),
+                schema: resource_type.schema.clone(),
+                extensions: resource_type
+                    .schema_extensions
+                    .iter()
+                    .flatten()
+                    .map(|e| e.schema.clone())
+                    .collect(),
+                handler: resource_type.name.clone(),
+            })
+            .collect();
+        RoutingTable { routes }
+    }
+
+    /// Returns the routes, in the registry's registration order.
+    pub fn routes(&self) -> &[Route] {
+        &self.routes
+    }
+
+    /// Resolves a request path relative to the base URL, such as `/Users/2819c223`.
+    ///
+    /// Returns `None` if the path does not belong to any route. A trailing slash is ignored.
+    pub fn resolve<'a>(&'a self, path: &'a str) -> Option<RouteMatch<'a>> {
+        let path = path.trim_end_matches('/');
+        self.routes.iter().find_map(|route| {
+            let rest = path.strip_prefix(route.endpoint.as_str())?;
+            let target = match rest {
+                "" => RouteTarget::Collection,
+                "/.search" => RouteTarget::Search,
+                rest => {
+                    let id = rest.strip_prefix('/')?;
+                    if id.is_empty() || id.contains('/') {
+                        return None;
+                    }
+                    RouteTarget::Resource(id)
+                }
+            };
+            Some(RouteMatch { route, target })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::resource_types::ResourceType;
+    use crate::models::schema_uri::{ENTERPRISE_USER_URN, GROUP_URN};
+    use crate::models::scim_schema::{Meta, Schema};
+
+    #[test]
+    fn registered_resource_types_get_routes() {
+        let mut registry = SchemaRegistry::with_builtins();
+        registry
+            .register_schema(Schema {
+                id: "urn:example:params:scim:schemas:core:1.0:Device".to_string(),
+                name: "Device".to_string(),
+                description: "Device".to_string(),
+                attributes: vec![],
+                meta: Meta::default(),
+            })
+            .unwrap();
+        registry
+            .register_resource_type(ResourceType {
+                name: "Device".to_string(),
+                endpoint: "Devices/".to_string(),
+                schema: "urn:example:params:scim:schemas:core:1.0:Device".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let table = RoutingTable::from_registry(&registry);
+        assert_eq!(table.routes()[0].extensions, [ENTERPRISE_USER_URN]);
+        assert_eq!(table.routes()[2].endpoint, "/Devices");
+        assert_eq!(
+            table.resolve("/Devices/").unwrap().target,
+            RouteTarget::Collection
+        );
+        assert_eq!(
+            table.resolve("/Devices/.search").unwrap().target,
+            RouteTarget::Search
+        );
+    }
+
+    #[test]
+    fn resolve_rejects_unknown_and_nested_paths() {
+        let table = RoutingTable::from_registry(&SchemaRegistry::with_builtins());
+        assert_eq!(table.resolve("/Groups").unwrap().route.schema, GROUP_URN);
+        assert!(table.resolve("/Groupsx").is_none());
+        assert!(table.resolve("/Groups/abc/members").is_none());
+        assert!(table.resolve("/Schemas").is_none());
+    }
+}
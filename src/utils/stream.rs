@@ -0,0 +1,384 @@
+//! Streaming reading and writing of `ListResponse` messages.
+//!
+//! A page of a large query can hold tens of thousands of resources. `ListResponseReader` reads such a page
+//! from a byte stream and yields its resources one at a time, holding only the resource being parsed in
+//! memory; `ListResponseWriter` writes resources to the output as they are produced.
+
+use std::io::{self, BufRead, Write};
+use std::marker::PhantomData;
+
+use serde::Serialize;
+use serde::de::{DeserializeOwned, Error as _};
+use serde_json::Value;
+
+use crate::models::others::ListResponse;
+use crate::models::schema_uri::LIST_RESPONSE_URN;
+use crate::utils::error::SCIMError;
+
+enum State {
+    Start,
+    Members,
+    Resources { first: bool },
+    Done,
+}
+
+/// Reads a `ListResponse` from a byte stream, yielding its resources one at a time.
+///
+/// The reader is an iterator over the entries of `Resources`, parsed as `T` (for example
+/// [`crate::models::user::User`] or [`crate::models::others::Resource`]). The other members of the message
+/// are collected into [`ListResponseReader::envelope`] as they are read; since they may follow the
+/// `Resources` array, the envelope is only complete once the iterator is exhausted.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::user::User;
+/// use scim_v2::utils::stream::ListResponseReader;
+///
+/// let body = r#"{
+///     "schemas": ["urn:ietf:params:scim:api:messages:2.0:ListResponse"],
+///     "Resources": [
+///         {"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "userName": "bjensen"},
+///         {"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "userName": "jsmith"}
+///     ],
+///     "totalResults": 2,
+///     "itemsPerPage": 2,
+///     "startIndex": 1
+/// }"#;
+/// let mut reader = ListResponseReader::<_, User>::new(body.as_bytes());
+/// let names: Vec<String> = reader.by_ref().map(|user| user.unwrap().user_name).collect();
+/// assert_eq!(names, ["bjensen", "jsmith"]);
+/// assert_eq!(reader.envelope().total_results, 2);
+/// ```
+pub struct ListResponseReader<R, T> {
+    reader: R,
+    state: State,
+    envelope: ListResponse,
+    buffer: Vec<u8>,
+    resource: PhantomData<fn() -> T>,
+}
+
+impl<R: BufRead, T: DeserializeOwned> ListResponseReader<R, T> {
+    /// Creates a reader over `reader`; wrap unbuffered sources in a `std::io::BufReader`.
+    pub fn new(reader: R) -> Self {
+        ListResponseReader {
+            reader,
+            state: State::Start,
+            envelope: ListResponse::default(),
+            buffer: Vec::new(),
+            resource: PhantomData,
+        }
+    }
+
+    /// Returns the members of the message read so far, with an empty `resources` list.
+    pub fn envelope(&self) -> &ListResponse {
+        &self.envelope
+    }
+
+    /// Consumes the reader, returning the members of the message read so far.
+    pub fn into_envelope(self) -> ListResponse {
+        self.envelope
+    }
+
+    fn advance(&mut self) -> Result<Option<T>, SCIMError> {
+        loop {
+            match self.state {
+                State::Start => {
+                    self.expect(b'{')?;
+                    self.state = State::Members;
+                }
+                State::Members => match self.peek_token()? {
+                    b'}' => {
+                        self.reader.consume(1);
+                        self.state = State::Done;
+                    }
+                    b',' => self.reader.consume(1),
+                    _ => {
+                        let name: String = self.read_value()?;
+                        self.expect(b':')?;
+                        if name.eq_ignore_ascii_case("Resources") {
+                            self.expect(b'[')?;
+                            self.state = State::Resources { first: true };
+                        } else {
+                            let value: Value = self.read_value()?;
+                            self.set_member(&name, value)?;
+                        }
+                    }
+                },
+                State::Resources { first } => {
+                    if self.peek_token()? == b']' {
+                        self.reader.consume(1);
+                        self.state = State::Members;
+                        continue;
+                    }
+                    if !first {
+                        self.expect(b',')?;
+                    }
+                    self.state = State::Resources { first: false };
+                    return self.read_value().map(Some);
+                }
+                State::Done => return Ok(None),
+            }
+        }
+    }
+
+    fn set_member(&mut self, name: &str, value: Value) -> Result<(), SCIMError> {
+        let envelope = &mut self.envelope;
+        match name {
+            "totalResults" => envelope.total_results = from_value(value)?,
+            "itemsPerPage" => envelope.items_per_page = from_value(value)?,
+            "startIndex" => envelope.start_index = from_value(value)?,
+            "schemas" => envelope.schemas = from_value(value)?,
+            "nextCursor" => envelope.next_cursor = from_value(value)?,
+            "previousCursor" => envelope.previous_cursor = from_value(value)?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    // Copies the next JSON value into the buffer, then parses it.
+    fn read_value<V: DeserializeOwned>(&mut self) -> Result<V, SCIMError> {
+        self.buffer.clear();
+        match self.peek_token()? {
+            b'"' => self.copy_string()?,
+            b'{' | b'[' => {
+                let mut depth = 0usize;
+                loop {
+                    let byte = self.next_byte()?;
+                    self.buffer.push(byte);
+                    match byte {
+                        b'"' => self.copy_string_tail()?,
+                        b'{' | b'[' => depth += 1,
+                        b'}' | b']' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {
+                while let Some(byte) = self.peek()? {
+                    if matches!(byte, b',' | b'}' | b']') || byte.is_ascii_whitespace() {
+                        break;
+                    }
+                    self.buffer.push(byte);
+                    self.reader.consume(1);
+                }
+            }
+        }
+        serde_json::from_slice(&self.buffer).map_err(SCIMError::DeserializationError)
+    }
+
+    fn copy_string(&mut self) -> Result<(), SCIMError> {
+        let quote = self.next_byte()?;
+        self.buffer.push(quote);
+        self.copy_string_tail()
+    }
+
+    // Copies the rest of a string whose opening quote has already been copied.
+    fn copy_string_tail(&mut self) -> Result<(), SCIMError> {
+        loop {
+            let byte = self.next_byte()?;
+            self.buffer.push(byte);
+            match byte {
+                b'\\' => {
+                    let escaped = self.next_byte()?;
+                    self.buffer.push(escaped);
+                }
+                b'"' => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<(), SCIMError> {
+        let found = self.peek_token()?;
+        if found != expected {
+            return Err(syntax_error(format!(
+                "expected '{}', found '{}'",
+                expected as char, found as char
+            )));
+        }
+        self.reader.consume(1);
+        Ok(())
+    }
+
+    // Skips whitespace and returns the next byte without consuming it.
+    fn peek_token(&mut self) -> Result<u8, SCIMError> {
+        while let Some(byte) = self.peek()? {
+            if !byte.is_ascii_whitespace() {
+                return Ok(byte);
+            }
+            self.reader.consume(1);
+        }
+        Err(syntax_error("unexpected end of input".to_string()))
+    }
+
+    fn next_byte(&mut self) -> Result<u8, SCIMError> {
+        let byte = self
+            .peek()?
+            .ok_or_else(|| syntax_error("unexpected end of input".to_string()))?;
+        self.reader.consume(1);
+        Ok(byte)
+    }
+
+    fn peek(&mut self) -> Result<Option<u8>, SCIMError> {
+        let buffer = self
+            .reader
+            .fill_buf()
+            .map_err(|e| SCIMError::DeserializationError(serde_json::Error::io(e)))?;
+        Ok(buffer.first().copied())
+    }
+}
+
+impl<R: BufRead, T: DeserializeOwned> Iterator for ListResponseReader<R, T> {
+    type Item = Result<T, SCIMError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.advance() {
+            Ok(resource) => resource.map(Ok),
+            Err(e) => {
+                self.state = State::Done;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+fn from_value<V: DeserializeOwned>(value: Value) -> Result<V, SCIMError> {
+    serde_json::from_value(value).map_err(SCIMError::DeserializationError)
+}
+
+fn syntax_error(message: String) -> SCIMError {
+    SCIMError::DeserializationError(serde_json::Error::custom(message))
+}
+
+/// Writes a `ListResponse` to a byte stream one resource at a time.
+///
+/// The message members known up front are written by [`ListResponseWriter::new`]; `itemsPerPage` is
+/// written by [`ListResponseWriter::finish`] from the number of resources written.
+///
+/// # Examples
+///
+/// ```
+/// use scim_v2::models::user::User;
+/// use scim_v2::utils::stream::ListResponseWriter;
+///
+/// let mut writer = ListResponseWriter::new(Vec::new(), 1, 1).unwrap();
+/// writer.write(&User { user_name: "bjensen".to_string(), ..Default::default() }).unwrap();
+/// let body = String::from_utf8(writer.finish().unwrap()).unwrap();
+/// let value: serde_json::Value = serde_json::from_str(&body).unwrap();
+/// assert_eq!(value["itemsPerPage"], 1);
+/// assert_eq!(value["Resources"][0]["userName"], "bjensen");
+/// ```
+pub struct ListResponseWriter<W: Write> {
+    writer: W,
+    items: i64,
+}
+
+impl<W: Write> ListResponseWriter<W> {
+    /// Starts a message for the page beginning at the 1-based `start_index` of `total_results` results.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if writing to `writer` fails.
+    pub fn new(mut writer: W, total_results: i64, start_index: i64) -> Result<Self, SCIMError> {
+        write!(
+            writer,
+            r#"{{"schemas":["{}"],"totalResults":{},"startIndex":{},"Resources":["#,
+            LIST_RESPONSE_URN, total_results, start_index
+        )
+        .map_err(io_error)?;
+        Ok(ListResponseWriter { writer, items: 0 })
+    }
+
+    /// Writes one resource.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if the resource cannot be serialized or writing fails.
+    pub fn write<T: Serialize>(&mut self, resource: &T) -> Result<(), SCIMError> {
+        if self.items > 0 {
+            self.writer.write_all(b",").map_err(io_error)?;
+        }
+        serde_json::to_writer(&mut self.writer, resource).map_err(SCIMError::SerializationError)?;
+        self.items += 1;
+        Ok(())
+    }
+
+    /// Completes the message and returns the underlying writer.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if writing fails.
+    pub fn finish(mut self) -> Result<W, SCIMError> {
+        write!(self.writer, r#"],"itemsPerPage":{}}}"#, self.items).map_err(io_error)?;
+        self.writer.flush().map_err(io_error)?;
+        Ok(self.writer)
+    }
+}
+
+fn io_error(error: io::Error) -> SCIMError {
+    SCIMError::SerializationError(serde_json::Error::io(error))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::others::Resource;
+    use crate::models::user::User;
+
+    use super::*;
+
+    #[test]
+    fn reads_resources_and_envelope() {
+        let body = r#" {
+            "Resources" : [
+                {"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "userName": "a]{\"b"},
+                {"schemas": ["urn:ietf:params:scim:schemas:core:2.0:Group"], "displayName": "Tour Guides", "members": []}
+            ],
+            "totalResults": 12, "startIndex": 11, "itemsPerPage": 2, "previousCursor": "c9",
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:ListResponse"]
+        }"#;
+        let mut reader = ListResponseReader::<_, Resource>::new(body.as_bytes());
+        let resources: Vec<Resource> = reader.by_ref().collect::<Result<_, _>>().unwrap();
+        assert!(matches!(&resources[0], Resource::User(user) if user.user_name == "a]{\"b"));
+        assert!(matches!(&resources[1], Resource::Group(_)));
+        let envelope = reader.into_envelope();
+        assert_eq!(envelope.total_results, 12);
+        assert_eq!(envelope.start_index, 11);
+        assert_eq!(envelope.items_per_page, 2);
+        assert_eq!(envelope.previous_cursor.as_deref(), Some("c9"));
+    }
+
+    #[test]
+    fn round_trips_through_the_writer_and_stops_on_errors() {
+        let mut writer = ListResponseWriter::new(Vec::new(), 3, 1).unwrap();
+        for name in ["bjensen", "jsmith"] {
+            let user = User {
+                user_name: name.to_string(),
+                ..Default::default()
+            };
+            writer.write(&user).unwrap();
+        }
+        let body = writer.finish().unwrap();
+        let list: ListResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(list.items_per_page, 2);
+        assert_eq!(list.total_results, 3);
+
+        let users: Vec<_> = ListResponseReader::<_, User>::new(body.as_slice())
+            .map(|user| user.unwrap().user_name)
+            .collect();
+        assert_eq!(users, ["bjensen", "jsmith"]);
+
+        let mut reader =
+            ListResponseReader::<_, User>::new(&br#"{"Resources": [{"userName": "#[..]);
+        assert!(matches!(
+            reader.next(),
+            Some(Err(SCIMError::DeserializationError(_)))
+        ));
+        assert!(reader.next().is_none());
+    }
+}
@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+/// Attributes named after Rust keywords
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Keywords {
+    #[serde(rename = "self", skip_serializing_if = "Option::is_none")]
+    pub self_: Option<String>,
+    #[serde(rename = "super")]
+    pub super_: String,
+    #[serde(rename = "crate", skip_serializing_if = "Option::is_none")]
+    pub crate_: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub r#type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub r#try: Option<Vec<bool>>,
+}
@@ -0,0 +1,66 @@
+/// Returns the current UTC time formatted as an RFC 3339 `dateTime`, e.g. `2011-05-13T04:42:34Z`.
+///
+/// This is the format SCIM uses for `meta.created` and `meta.lastModified`. Values produced by this
+/// function sort lexicographically in chronological order.
+pub fn now_rfc3339() -> String {
+    format_rfc3339(now_unix())
+}
+
+/// Returns the current time in seconds since the Unix epoch, the form JWT claims such as `iat` use.
+#[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+pub fn now_unix() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Returns the current time in seconds since the Unix epoch, the form JWT claims such as `iat` use.
+///
+/// wasm32-unknown-unknown has no system clock, so the time comes from JavaScript's `Date.now()`.
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub fn now_unix() -> u64 {
+    (js_sys::Date::now() / 1000.0) as u64
+}
+
+/// Formats seconds since the Unix epoch as an RFC 3339 UTC `dateTime`.
+pub fn format_rfc3339(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60
+    )
+}
+
+// Converts days since 1970-01-01 into a (year, month, day) triple in the proleptic Gregorian calendar.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_rfc3339_formats_known_instants() {
+        assert_eq!(format_rfc3339(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_rfc3339(1_305_261_754), "2011-05-13T04:42:34Z");
+        assert_eq!(format_rfc3339(951_782_400), "2000-02-29T00:00:00Z");
+    }
+}